@@ -0,0 +1,135 @@
+//! Compute units of vault_manager instructions under Mollusk, checked against
+//! `budgets.toml` and written to `compute_units.md`.
+//!
+//! `cargo bench -- --record` instead records each instruction's highest case
+//! in `budgets.toml`.
+
+use std::collections::BTreeMap;
+use std::process::{Command, ExitCode};
+
+use mollusk_svm::program::{
+    create_program_account_loader_v3, keyed_account_for_system_program, loader_keys,
+};
+use mollusk_svm::result::ProgramResult;
+use mollusk_svm::Mollusk;
+use mollusk_svm_programs_token::{associated_token, token};
+use vault_manager_bench::budget::{self, Budgets};
+use vault_manager_bench::cases::CASES;
+use vault_manager_bench::report::{self, Measurement};
+use vault_manager_bench::world::World;
+
+const BUDGETS: &str = "budgets.toml";
+const REPORT: &str = "compute_units.md";
+
+fn main() -> ExitCode {
+    // Where `anchor build` leaves vault_manager, and the mocks are built to
+    if std::env::var_os("SBF_OUT_DIR").is_none() {
+        std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+    }
+    let record = std::env::args().any(|arg| arg == "--record");
+    let source = std::fs::read_to_string(BUDGETS).expect("budgets.toml is readable");
+    let budgets = Budgets::parse(&source).expect("budgets.toml is valid");
+    let unbudgeted: Vec<_> = CASES
+        .iter()
+        .filter(|case| budgets.limit(case.instruction).is_none())
+        .map(|case| case.instruction)
+        .collect();
+    if !unbudgeted.is_empty() {
+        eprintln!("no budget in budgets.toml for {}", unbudgeted.join(", "));
+        return ExitCode::FAILURE;
+    }
+
+    let mut mollusk = Mollusk::new(&vault_manager::ID, "vault_manager");
+    for (program, name) in [
+        (mock_dlmm::ID, "mock_dlmm"),
+        (mock_whirlpool::ID, "mock_whirlpool"),
+        (mock_swap::ID, "mock_swap"),
+    ] {
+        mollusk.add_program(&program, name, &loader_keys::LOADER_V3);
+    }
+    token::add_program(&mut mollusk);
+    associated_token::add_program(&mut mollusk);
+
+    let mut world = World::new();
+    for program in [mock_dlmm::ID, mock_whirlpool::ID, mock_swap::ID] {
+        world.set_account(program, create_program_account_loader_v3(&program));
+    }
+    for (key, account) in [
+        keyed_account_for_system_program(),
+        token::keyed_account(),
+        associated_token::keyed_account(),
+        mollusk.sysvars.keyed_account_for_rent_sysvar(),
+    ] {
+        world.set_account(key, account);
+    }
+
+    let mut measurements = Vec::new();
+    for case in CASES {
+        let instruction = (case.build)(&world);
+        let result = mollusk.process_instruction(&instruction, &world.accounts_for(&instruction));
+        if !matches!(result.program_result, ProgramResult::Success) {
+            eprintln!(
+                "{} ({}) failed: {:?}",
+                case.instruction, case.case, result.program_result
+            );
+            return ExitCode::FAILURE;
+        }
+        measurements.push(Measurement {
+            instruction: case.instruction.to_owned(),
+            case: case.case.to_owned(),
+            compute_units: result.compute_units_consumed,
+            budget: budgets.limit(case.instruction).unwrap(),
+        });
+        if case.keep {
+            world.apply(result.resulting_accounts);
+        }
+    }
+
+    let previous = std::fs::read_to_string(REPORT).unwrap_or_default();
+    let rendered = report::render(
+        &measurements,
+        &report::parse(&previous),
+        commit().as_deref(),
+    );
+    std::fs::write(REPORT, &rendered).expect("compute_units.md is writable");
+    print!("{rendered}");
+
+    if record {
+        let mut highest = BTreeMap::<String, u64>::new();
+        for measurement in &measurements {
+            let entry = highest.entry(measurement.instruction.clone()).or_default();
+            *entry = (*entry).max(measurement.compute_units);
+        }
+        std::fs::write(BUDGETS, budget::record(&source, &highest))
+            .expect("budgets.toml is writable");
+        return ExitCode::SUCCESS;
+    }
+
+    let over: Vec<_> = measurements.iter().filter(|m| m.over_budget()).collect();
+    for measurement in &over {
+        eprintln!(
+            "{} ({}) used {} compute units, over its budget of {}",
+            measurement.instruction,
+            measurement.case,
+            measurement.compute_units,
+            measurement.budget
+        );
+    }
+    if over.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Short hash of the checked out commit, if run inside the repository.
+fn commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
@@ -0,0 +1,67 @@
+//! Per-instruction compute unit ceilings, read from `budgets.toml`.
+//!
+//! The file records each instruction's highest measured compute units, and a
+//! case's ceiling is that plus the file's fixed `margin`.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Budgets {
+    /// Compute units any instruction may use over its recorded ones.
+    pub margin: u64,
+    pub instructions: BTreeMap<String, u64>,
+}
+
+impl Budgets {
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+
+    /// Ceiling of `instruction`, if it has one.
+    pub fn limit(&self, instruction: &str) -> Option<u64> {
+        self.instructions
+            .get(instruction)
+            .map(|recorded| recorded + self.margin)
+    }
+}
+
+/// `source` with the recorded compute units of each instruction in `measured`
+/// replaced, keeping everything else, comments included, as it was.
+pub fn record(source: &str, measured: &BTreeMap<String, u64>) -> String {
+    let mut in_instructions = false;
+    let mut recorded = String::with_capacity(source.len());
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_instructions = trimmed == "[instructions]";
+        }
+        let entry = trimmed
+            .split_once('=')
+            .map(|(key, _)| key.trim())
+            .filter(|_| in_instructions && !trimmed.starts_with('#'));
+        match entry.and_then(|key| Some((key, measured.get(key)?))) {
+            Some((key, compute_units)) => {
+                recorded.push_str(&format!("{key} = {}", separated(*compute_units)))
+            }
+            None => recorded.push_str(line),
+        }
+        recorded.push('\n');
+    }
+    recorded
+}
+
+/// `n` with `_` between groups of three digits, as TOML integers allow.
+fn separated(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push('_');
+        }
+        out.push(digit);
+    }
+    out
+}
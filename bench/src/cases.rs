@@ -0,0 +1,255 @@
+//! The benchmarked cases, in the order they run.
+//!
+//! Every instruction of the program has at least one case. Cases build their
+//! instruction from the accounts earlier kept cases left behind: the protocol
+//! config comes first, the Whirlpool vault is created before anything runs on
+//! it, and `close_vault` runs last, once `migrate_liquidity` has emptied it.
+
+use anchor_lang::solana_program::instruction::Instruction;
+
+use crate::world::{World, DEPOSIT};
+
+pub struct Case {
+    pub instruction: &'static str,
+    pub case: &'static str,
+    pub build: fn(&World) -> Instruction,
+    /// Whether later cases run on the accounts this one leaves behind.
+    pub keep: bool,
+}
+
+pub const CASES: &[Case] = &[
+    Case {
+        instruction: "initialize_protocol_config",
+        case: "by the upgrade authority",
+        build: World::initialize_protocol_config,
+        keep: true,
+    },
+    Case {
+        instruction: "initialize_vault",
+        case: "opens a DLMM position",
+        build: |world| world.dlmm.initialize_vault(world),
+        keep: false,
+    },
+    Case {
+        instruction: "initialize_vault",
+        case: "opens a Whirlpool position",
+        build: |world| world.whirlpool.initialize_vault(world),
+        keep: true,
+    },
+    Case {
+        instruction: "deposit_and_add_liquidity",
+        case: "DLMM, first deposit, zapped through the pool",
+        build: |world| world.deposit(&world.dlmm_vault, DEPOSIT),
+        keep: true,
+    },
+    Case {
+        instruction: "deposit_and_add_liquidity",
+        case: "DLMM, into an active position",
+        build: |world| world.deposit(&world.dlmm_vault, DEPOSIT),
+        keep: true,
+    },
+    Case {
+        instruction: "deposit_and_add_liquidity",
+        case: "Whirlpool, first deposit, zapped through the pool",
+        build: |world| world.deposit(&world.whirlpool_vault, DEPOSIT),
+        keep: true,
+    },
+    Case {
+        instruction: "deposit_and_add_liquidity",
+        case: "Whirlpool, into an active position",
+        build: |world| world.deposit(&world.whirlpool_vault, DEPOSIT),
+        keep: true,
+    },
+    Case {
+        instruction: "set_swap_programs",
+        case: "one swap program",
+        build: |world| world.set_swap_programs(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "deposit_via_route",
+        case: "DLMM, zapped through a swap program",
+        build: |world| world.deposit_via_route(&world.dlmm_vault, DEPOSIT),
+        keep: true,
+    },
+    Case {
+        instruction: "get_nav",
+        case: "DLMM, active position",
+        build: |world| world.get_nav(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "get_nav",
+        case: "Whirlpool, active position",
+        build: |world| world.get_nav(&world.whirlpool_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "collect",
+        case: "DLMM, with the protocol share",
+        build: |world| world.collect(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "collect",
+        case: "Whirlpool, with the protocol share",
+        build: |world| world.collect(&world.whirlpool_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "withdraw",
+        case: "DLMM, half of the shares",
+        build: |world| world.withdraw_half(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "withdraw",
+        case: "Whirlpool, half of the shares",
+        build: |world| world.withdraw_half(&world.whirlpool_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "rebalance",
+        case: "DLMM, into a new range",
+        build: |world| world.rebalance(&world.dlmm_vault, false),
+        keep: true,
+    },
+    Case {
+        instruction: "rebalance",
+        case: "Whirlpool, into a wider range",
+        build: |world| world.rebalance(&world.whirlpool_vault, true),
+        keep: true,
+    },
+    Case {
+        instruction: "migrate_liquidity",
+        case: "all shares, Whirlpool to DLMM",
+        build: |world| world.migrate_liquidity(&world.whirlpool_vault, &world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "request_withdrawal",
+        case: "all shares",
+        build: |world| world.request_withdrawal(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_authority",
+        case: "hand over",
+        build: |world| world.set_authority(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_bin_strategy",
+        case: "curve",
+        build: |world| world.set_bin_strategy(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_deposit_limits",
+        case: "minimum and cap",
+        build: |world| world.set_deposit_limits(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_lockup",
+        case: "lockup, exit fee and delay",
+        build: |world| world.set_lockup(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_nav_circuit_breaker",
+        case: "5% drop",
+        build: |world| world.set_nav_circuit_breaker(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_paused",
+        case: "pause",
+        build: |world| world.set_paused(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_price_oracle",
+        case: "Pyth feed",
+        build: |world| world.set_price_oracle(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_twap_guard",
+        case: "disabled",
+        build: |world| world.set_twap_guard(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_strategy_config",
+        case: "creates the config",
+        build: |world| world.set_strategy_config(&world.dlmm_vault),
+        keep: false,
+    },
+    Case {
+        instruction: "set_protocol_config",
+        case: "fee share and DEX programs",
+        build: World::set_protocol_config,
+        keep: false,
+    },
+    Case {
+        instruction: "set_protocol_admin",
+        case: "hand over",
+        build: World::set_protocol_admin,
+        keep: false,
+    },
+    Case {
+        instruction: "queue_param_change",
+        case: "deposit limits",
+        build: |world| world.queue_param_change(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "execute_param_change",
+        case: "deposit limits",
+        build: |world| world.execute_param_change(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "queue_param_change",
+        case: "deposit limits, again",
+        build: |world| world.queue_param_change(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "cancel_param_change",
+        case: "deposit limits",
+        build: |world| world.cancel_param_change(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "add_to_allowlist",
+        case: "one wallet",
+        build: |world| world.add_to_allowlist(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "remove_from_allowlist",
+        case: "one wallet",
+        build: |world| world.remove_from_allowlist(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "set_allowlist",
+        case: "Merkle root of two wallets",
+        build: |world| world.set_allowlist(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "join_allowlist",
+        case: "one-node proof",
+        build: |world| world.join_allowlist(&world.dlmm_vault),
+        keep: true,
+    },
+    Case {
+        instruction: "close_vault",
+        case: "Whirlpool, emptied by the migration",
+        build: |world| world.close_vault(&world.whirlpool_vault),
+        keep: true,
+    },
+];
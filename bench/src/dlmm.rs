@@ -0,0 +1,267 @@
+//! The mock DLMM pair and the vault written directly over it.
+//!
+//! The pair sits at bin 0 with LP liquidity over bins [-30, 30], and the
+//! vault's empty position covers bins [-5, 5]. A second pair without a vault
+//! is kept for `initialize_vault`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+use anchor_spl::token::spl_token;
+use mock_dlmm::state::{BinArray, Bins, LbPair, PositionV2};
+use mock_dlmm::{LiquidityParameterByStrategy, StrategyParameters, StrategyType};
+use vault_manager::dex::{BinStrategy, Dex};
+use vault_manager::{Vault, VaultConfig, VaultFees, VaultStrategy};
+
+use crate::world::{vault_address, VaultKeys, World};
+
+pub const BIN_STEP: u16 = 10;
+/// Bin step of the pair left for `initialize_vault`, so it gets its own address.
+const FRESH_BIN_STEP: u16 = 20;
+
+pub struct Dlmm {
+    pub pair: Pair,
+    pub fresh_pair: Pair,
+    pub event_authority: Pubkey,
+}
+
+#[derive(Clone, Copy)]
+pub struct Pair {
+    pub key: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub oracle: Pubkey,
+}
+
+impl Dlmm {
+    pub(crate) fn default_keys() -> Self {
+        Self {
+            pair: Pair::default_keys(),
+            fresh_pair: Pair::default_keys(),
+            event_authority: Pubkey::find_program_address(&[b"__event_authority"], &mock_dlmm::ID)
+                .0,
+        }
+    }
+
+    /// Writes both pairs, and the vault over the first into `world.dlmm_vault`.
+    pub(crate) fn seed(world: &mut World) -> Self {
+        let mut dlmm = Self::default_keys();
+        dlmm.pair = create_pair(world, BIN_STEP);
+        dlmm.fresh_pair = create_pair(world, FRESH_BIN_STEP);
+        let (fresh_vault, _) = vault_address(&dlmm.fresh_pair.key);
+        for (index, mint) in [world.mint_a, world.mint_b].into_iter().enumerate() {
+            let key = fresh_vault_token(&fresh_vault, index);
+            world.set_token_account(key, mint, fresh_vault, 0);
+        }
+        for bin_id in [-70, 0, 70] {
+            let array = BinArray::new(dlmm.pair.key, BinArray::index_of(bin_id), BIN_STEP).unwrap();
+            world.set_anchor_account(dlmm.bin_array(bin_id), &array);
+        }
+        dlmm.seed_lp_liquidity(world);
+        dlmm.seed_vault(world);
+        dlmm
+    }
+
+    pub fn bin_array(&self, bin_id: i32) -> Pubkey {
+        let index = BinArray::index_of(bin_id);
+        Pubkey::find_program_address(
+            &[b"bin_array", self.pair.key.as_ref(), &index.to_le_bytes()],
+            &mock_dlmm::ID,
+        )
+        .0
+    }
+
+    /// `initialize_vault` on the pair without a vault.
+    pub fn initialize_vault(&self, world: &World) -> Instruction {
+        let pool = self.fresh_pair.key;
+        let vault = VaultKeys {
+            vault_token_a: fresh_vault_token(&vault_address(&pool).0, 0),
+            vault_token_b: fresh_vault_token(&vault_address(&pool).0, 1),
+            ..VaultKeys::over(
+                Dex::MeteoraDlmm,
+                pool,
+                (self.fresh_pair.reserve_x, self.fresh_pair.reserve_y),
+            )
+        };
+        let position = Pubkey::new_unique();
+        let mut accounts = world.initialize_vault_accounts(
+            &vault,
+            position,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        // DLMM positions are keypair accounts, created with their signature
+        for meta in &mut accounts {
+            if meta.pubkey == position {
+                meta.is_signer = true;
+            }
+        }
+        accounts.push(AccountMeta::new_readonly(self.event_authority, false));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::InitializeVault {
+                dex: Dex::MeteoraDlmm,
+                tick_lower: -5,
+                tick_upper: 5,
+            }
+            .data(),
+        }
+    }
+
+    /// The DLMM backend's position account set.
+    pub(crate) fn position_accounts(&self, world: &World, position: Pubkey) -> Vec<AccountMeta> {
+        let state: PositionV2 = world.anchor_account(&position);
+        self.range_accounts(world, (state.lower_bin_id, state.upper_bin_id))
+    }
+
+    /// The account set of a position over bins `[lower_bin_id, upper_bin_id]`.
+    pub(crate) fn range_accounts(
+        &self,
+        world: &World,
+        (lower_bin_id, upper_bin_id): (i32, i32),
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(world.mint_a, false),
+            AccountMeta::new_readonly(world.mint_b, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.bin_array(lower_bin_id), false),
+            AccountMeta::new(self.bin_array(upper_bin_id), false),
+        ]
+    }
+
+    /// The DLMM backend's swap account set, with every bin array of the pair,
+    /// so it serves swaps in both directions.
+    pub(crate) fn swap_accounts(&self, world: &World) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(world.mint_a, false),
+            AccountMeta::new_readonly(world.mint_b, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.pair.oracle, false),
+            AccountMeta::new(self.bin_array(-70), false),
+            AccountMeta::new(self.bin_array(0), false),
+            AccountMeta::new(self.bin_array(70), false),
+        ]
+    }
+
+    /// LP liquidity over bins [-30, 30], with the reserves holding what it paid.
+    fn seed_lp_liquidity(&self, world: &mut World) {
+        let lb_pair: LbPair = world.anchor_account(&self.pair.key);
+        let mut position = PositionV2::new(self.pair.key, Pubkey::new_unique(), -30, 61).unwrap();
+        let keys = [self.bin_array(-70), self.bin_array(0)];
+        let mut bins = Bins::new(
+            keys.iter()
+                .map(|key| (*key, world.anchor_account(key)))
+                .collect(),
+        );
+        let (amount_x, amount_y) = mock_dlmm::pool::add_liquidity(
+            &lb_pair,
+            &mut position,
+            &mut bins,
+            &LiquidityParameterByStrategy {
+                amount_x: 1_000_000_000,
+                amount_y: 1_000_000_000,
+                active_id: lb_pair.active_id,
+                max_active_bin_slippage: 0,
+                strategy_parameters: StrategyParameters {
+                    min_bin_id: -30,
+                    max_bin_id: 30,
+                    strategy_type: StrategyType::SpotImBalanced,
+                    parameteres: [0; 64],
+                },
+            },
+        )
+        .unwrap();
+        for (key, array) in bins.into_arrays() {
+            world.set_anchor_account(key, &array);
+        }
+        let (mint_x, mint_y) = (world.mint_a, world.mint_b);
+        world.set_token_account(self.pair.reserve_x, mint_x, self.pair.key, amount_x);
+        world.set_token_account(self.pair.reserve_y, mint_y, self.pair.key, amount_y);
+    }
+
+    /// The vault over the pair, with an empty position over bins [-5, 5].
+    fn seed_vault(&self, world: &mut World) {
+        let keys = VaultKeys::over(
+            Dex::MeteoraDlmm,
+            self.pair.key,
+            (self.pair.reserve_x, self.pair.reserve_y),
+        );
+        let position = Pubkey::new_unique();
+        world.set_mint(keys.share_mint, keys.vault, 0);
+        let (mint_a, mint_b, user) = (world.mint_a, world.mint_b, world.user);
+        world.set_token_account(keys.vault_token_a, mint_a, keys.vault, 0);
+        world.set_token_account(keys.vault_token_b, mint_b, keys.vault, 0);
+        world.set_token_account(keys.user_share_account, keys.share_mint, user, 0);
+        let state = PositionV2::new(self.pair.key, keys.vault, -5, 11).unwrap();
+        world.set_anchor_account(position, &state);
+        let state = Vault {
+            authority: world.authority,
+            dex: Dex::MeteoraDlmm,
+            pool: self.pair.key,
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            vault_token_a: keys.vault_token_a,
+            vault_token_b: keys.vault_token_b,
+            position,
+            share_mint: keys.share_mint,
+            config: VaultConfig::default(),
+            strategy: VaultStrategy {
+                bin_strategy: BinStrategy::Spot,
+                swap_programs: Default::default(),
+            },
+            fees: VaultFees::default(),
+            last_nav_per_share_x64: 0,
+            last_nav_slot: 0,
+            paused: false,
+            token_a_decimals: 6,
+            token_b_decimals: 6,
+            bump: keys.bump,
+            is_active: false,
+        };
+        world.set_anchor_account(keys.vault, &state);
+        world.dlmm_vault = keys;
+    }
+}
+
+impl Pair {
+    fn default_keys() -> Self {
+        Self {
+            key: Pubkey::default(),
+            reserve_x: Pubkey::new_unique(),
+            reserve_y: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+        }
+    }
+}
+
+fn create_pair(world: &mut World, bin_step: u16) -> Pair {
+    let (mint_x, mint_y) = (world.mint_a, world.mint_b);
+    let key = Pubkey::find_program_address(
+        &[mint_x.as_ref(), mint_y.as_ref(), &bin_step.to_le_bytes()],
+        &mock_dlmm::ID,
+    )
+    .0;
+    let pair = Pair {
+        key,
+        ..Pair::default_keys()
+    };
+    world.set_token_account(pair.reserve_x, mint_x, key, 0);
+    world.set_token_account(pair.reserve_y, mint_y, key, 0);
+    let state = LbPair::new(
+        mint_x,
+        mint_y,
+        pair.reserve_x,
+        pair.reserve_y,
+        pair.oracle,
+        bin_step,
+        0,
+    );
+    world.set_anchor_account(key, &state);
+    pair
+}
+
+/// Fixed addresses for the fresh pair vault's token accounts.
+fn fresh_vault_token(vault: &Pubkey, index: usize) -> Pubkey {
+    Pubkey::create_with_seed(vault, &format!("token_{index}"), &spl_token::ID).unwrap()
+}
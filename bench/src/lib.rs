@@ -0,0 +1,20 @@
+//! Compute unit benchmarks of vault_manager instructions.
+//!
+//! `cargo bench` runs each case in Mollusk, an in-process SVM executing the
+//! SBF builds of vault_manager and the mock DLMM, Whirlpool and swap programs
+//! (`anchor build`, then `cargo build-sbf --sbf-out-dir ../../../target/deploy`
+//! in each of `tests/programs/mock_dlmm`, `mock_whirlpool` and `mock_swap`,
+//! which `anchor build` skips). It writes `compute_units.md` and fails when a case goes over the
+//! ceiling `budgets.toml` sets for its instruction, or an instruction has no
+//! ceiling.
+//!
+//! Cases run on a DLMM vault and a Whirlpool vault over the same tokens, the
+//! backends the mock programs cover. `cargo test` runs the same cases in the
+//! native test harness, so they are known to succeed without an SBF build.
+
+pub mod budget;
+pub mod cases;
+pub mod dlmm;
+pub mod report;
+pub mod whirlpool;
+pub mod world;
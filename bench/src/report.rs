@@ -0,0 +1,82 @@
+//! Markdown report of a benchmark run.
+//!
+//! Each run overwrites the report and shows the change from the one it
+//! replaces, so committing the report alongside a change records how the
+//! change moved each instruction's cost.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Compute units one benchmark case consumed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    pub instruction: String,
+    /// What sets this case apart from others of the same instruction.
+    pub case: String,
+    pub compute_units: u64,
+    pub budget: u64,
+}
+
+impl Measurement {
+    pub fn over_budget(&self) -> bool {
+        self.compute_units > self.budget
+    }
+
+    fn key(&self) -> (String, String) {
+        (self.instruction.clone(), self.case.clone())
+    }
+}
+
+/// Compute units per `(instruction, case)` in a report written by `render`.
+pub fn parse(report: &str) -> BTreeMap<(String, String), u64> {
+    report
+        .lines()
+        .filter_map(|line| {
+            let cells: Vec<&str> = line.split('|').map(str::trim).collect();
+            let [_, instruction, case, compute_units, ..] = cells[..] else {
+                return None;
+            };
+            let instruction = instruction.strip_prefix('`')?.strip_suffix('`')?;
+            let compute_units = compute_units.replace(',', "").parse().ok()?;
+            Some(((instruction.to_owned(), case.to_owned()), compute_units))
+        })
+        .collect()
+}
+
+/// Report of `measurements`, compared against the `previous` run's.
+pub fn render(
+    measurements: &[Measurement],
+    previous: &BTreeMap<(String, String), u64>,
+    commit: Option<&str>,
+) -> String {
+    let mut report = String::from("# Compute units\n\n");
+    if let Some(commit) = commit {
+        writeln!(report, "Measured at `{commit}`.\n").unwrap();
+    }
+    report.push_str("| Instruction | Case | Compute units | Budget | Change |\n");
+    report.push_str("| --- | --- | ---: | ---: | ---: |\n");
+    for measurement in measurements {
+        let change = match previous.get(&measurement.key()) {
+            None => "new".to_owned(),
+            Some(&before) if before == measurement.compute_units => String::new(),
+            Some(&before) => {
+                format!(
+                    "{:+}",
+                    i128::from(measurement.compute_units) - i128::from(before)
+                )
+            }
+        };
+        let budget = if measurement.over_budget() {
+            format!("**{}**", measurement.budget)
+        } else {
+            measurement.budget.to_string()
+        };
+        writeln!(
+            report,
+            "| `{}` | {} | {} | {budget} | {change} |",
+            measurement.instruction, measurement.case, measurement.compute_units,
+        )
+        .unwrap();
+    }
+    report
+}
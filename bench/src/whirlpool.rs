@@ -0,0 +1,219 @@
+//! The mock Whirlpool pool the `initialize_vault` case creates a vault over.
+//!
+//! The pool sits at tick 0 with LP liquidity over ticks [-1280, 1280). The
+//! vault's token accounts are written ahead of the vault.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+use anchor_spl::associated_token::get_associated_token_address;
+use mock_whirlpool::state::{Position, TickArray, TickArrays, Whirlpool, TICK_ARRAY_SIZE};
+use vault_manager::dex::Dex;
+
+use crate::world::{VaultKeys, World};
+
+pub const TICK_SPACING: u16 = 64;
+/// 0.3%, in hundredths of a basis point.
+const FEE_RATE: u16 = 3_000;
+const LP_LIQUIDITY: u128 = 10_000_000_000;
+const TICK_ARRAY_STARTS: [i32; 3] = [-5632, 0, 5632];
+
+pub struct WhirlpoolPool {
+    pub key: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub oracle: Pubkey,
+    /// NFT mint of the position `initialize_vault` opens.
+    pub position_mint: Pubkey,
+}
+
+impl WhirlpoolPool {
+    pub(crate) fn default_keys() -> Self {
+        Self {
+            key: Pubkey::default(),
+            token_vault_a: Pubkey::new_unique(),
+            token_vault_b: Pubkey::new_unique(),
+            oracle: Pubkey::default(),
+            position_mint: Pubkey::new_unique(),
+        }
+    }
+
+    /// Writes the pool, and the keys of the vault over it into
+    /// `world.whirlpool_vault`.
+    pub(crate) fn seed(world: &mut World) -> Self {
+        let config = Pubkey::new_unique();
+        let (mint_a, mint_b) = (world.mint_a, world.mint_b);
+        let key = Pubkey::find_program_address(
+            &[
+                b"whirlpool",
+                config.as_ref(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+                &TICK_SPACING.to_le_bytes(),
+            ],
+            &mock_whirlpool::ID,
+        )
+        .0;
+        let pool = Self {
+            key,
+            oracle: Pubkey::find_program_address(&[b"oracle", key.as_ref()], &mock_whirlpool::ID).0,
+            ..Self::default_keys()
+        };
+        let mut state = Whirlpool::new(
+            config,
+            mint_a,
+            mint_b,
+            pool.token_vault_a,
+            pool.token_vault_b,
+            TICK_SPACING,
+            FEE_RATE,
+            1 << 64,
+        )
+        .unwrap();
+
+        // LP liquidity, with the token vaults holding what it paid
+        let arrays = TICK_ARRAY_STARTS
+            .into_iter()
+            .map(|start| {
+                let array = TickArray::new(key, start, TICK_SPACING).unwrap();
+                (tick_array(&key, start), array)
+            })
+            .collect();
+        let mut ticks = TickArrays::new(TICK_SPACING, arrays);
+        let mut position = Position::new(key, &state, Pubkey::new_unique(), -1280, 1280).unwrap();
+        let (amount_a, amount_b) = mock_whirlpool::pool::modify_liquidity(
+            &mut state,
+            &mut position,
+            &mut ticks,
+            LP_LIQUIDITY as i128,
+            0,
+        )
+        .unwrap();
+        for (array_key, array) in ticks.into_arrays() {
+            world.set_anchor_account(array_key, &array);
+        }
+        world.set_token_account(pool.token_vault_a, mint_a, key, amount_a);
+        world.set_token_account(pool.token_vault_b, mint_b, key, amount_b);
+
+        let keys = VaultKeys::over(
+            Dex::Whirlpool,
+            key,
+            (pool.token_vault_a, pool.token_vault_b),
+        );
+        world.set_anchor_account(key, &state);
+        world.set_token_account(keys.vault_token_a, mint_a, keys.vault, 0);
+        world.set_token_account(keys.vault_token_b, mint_b, keys.vault, 0);
+        world.set_token_account(keys.user_share_account, keys.share_mint, world.user, 0);
+        world.whirlpool_vault = keys;
+        pool
+    }
+
+    /// `initialize_vault` over the pool, with a position over [-640, 640).
+    pub fn initialize_vault(&self, world: &World) -> Instruction {
+        let vault = &world.whirlpool_vault;
+        let mut accounts = world.initialize_vault_accounts(
+            vault,
+            position_address(&self.position_mint),
+            self.position_mint,
+            get_associated_token_address(&vault.vault, &self.position_mint),
+        );
+        // The new NFT mint signs for its creation
+        for meta in &mut accounts {
+            if meta.pubkey == self.position_mint {
+                meta.is_signer = true;
+            }
+        }
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::InitializeVault {
+                dex: Dex::Whirlpool,
+                tick_lower: -640,
+                tick_upper: 640,
+            }
+            .data(),
+        }
+    }
+
+    /// The Whirlpool backend's position account set: the vault's NFT account
+    /// and the position's tick arrays.
+    pub(crate) fn position_accounts(
+        &self,
+        world: &World,
+        vault: &VaultKeys,
+        position: Pubkey,
+    ) -> Vec<AccountMeta> {
+        let state: Position = world.anchor_account(&position);
+        self.range_accounts(
+            get_associated_token_address(&vault.vault, &state.position_mint),
+            (state.tick_lower_index, state.tick_upper_index),
+        )
+    }
+
+    /// The account set of a position over `[tick_lower, tick_upper)` whose NFT
+    /// is held in `nft_account`.
+    pub(crate) fn range_accounts(
+        &self,
+        nft_account: Pubkey,
+        (tick_lower, tick_upper): (i32, i32),
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(nft_account, false),
+            AccountMeta::new(self.tick_array(tick_lower), false),
+            AccountMeta::new(self.tick_array(tick_upper), false),
+        ]
+    }
+
+    /// The Whirlpool backend's swap account set, for a swap in direction
+    /// `a_to_b` from the current price.
+    pub(crate) fn swap_accounts(&self, world: &World, a_to_b: bool) -> Vec<AccountMeta> {
+        let current = world
+            .anchor_account::<Whirlpool>(&self.key)
+            .tick_current_index;
+        let step = i32::from(TICK_SPACING) * TICK_ARRAY_SIZE;
+        let step = if a_to_b { -step } else { step };
+        let mut accounts: Vec<_> = (0..3)
+            .map(|i| AccountMeta::new(self.tick_array(current + i * step), false))
+            .collect();
+        accounts.push(AccountMeta::new(self.oracle, false));
+        accounts
+    }
+
+    /// Accounts `close_vault` needs to close the vault's position: its NFT
+    /// account, which closing burns from, the tick arrays and the NFT mint.
+    pub(crate) fn close_position_accounts(
+        &self,
+        world: &World,
+        vault: &VaultKeys,
+    ) -> Vec<AccountMeta> {
+        let position = world.position(vault);
+        let state: Position = world.anchor_account(&position);
+        let mut accounts = self.position_accounts(world, vault, position);
+        accounts[0].is_writable = true;
+        accounts.push(AccountMeta::new(state.position_mint, false));
+        accounts
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        tick_array(
+            &self.key,
+            TickArray::start_index_of(tick_index, TICK_SPACING),
+        )
+    }
+}
+
+pub(crate) fn position_address(position_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position", position_mint.as_ref()], &mock_whirlpool::ID).0
+}
+
+fn tick_array(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            whirlpool.as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        &mock_whirlpool::ID,
+    )
+    .0
+}
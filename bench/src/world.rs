@@ -0,0 +1,1166 @@
+//! Accounts the benchmark cases run on, and the instructions they send.
+//!
+//! State is written directly, the way the native tests seed it: a DLMM pair
+//! with a vault over it (see `dlmm`), a Whirlpool pool whose vault the
+//! `initialize_vault` case creates (see `whirlpool`), and a mock swap pool for
+//! routed deposits. The protocol config does not exist until the
+//! `initialize_protocol_config` case creates it, so that case can run.
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use mock_swap::SwapPool;
+use solana_account::Account;
+use vault_manager::dex::{BinStrategy, Dex};
+use vault_manager::governance::ParamChange;
+use vault_manager::route::SwapRoute;
+use vault_manager::{allowlist, Vault};
+
+use crate::dlmm::Dlmm;
+use crate::whirlpool::{self, WhirlpoolPool};
+
+pub const DEPOSIT: u64 = 10_000_000;
+/// Share of collected fees the protocol takes in the benchmarks.
+const FEE_SHARE_BPS: u16 = 1_000;
+
+pub struct World {
+    pub accounts: HashMap<Pubkey, Account>,
+    pub authority: Pubkey,
+    pub user: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub protocol_config: Pubkey,
+    /// Program data account of vault_manager, naming `authority` as its
+    /// upgrade authority.
+    pub program_data: Pubkey,
+    pub user_token_a: Pubkey,
+    pub user_token_b: Pubkey,
+    /// The protocol treasury's token accounts; the treasury is `authority`, so
+    /// `close_vault` sweeps leftovers into them too.
+    pub treasury_token_a: Pubkey,
+    pub treasury_token_b: Pubkey,
+    /// Pyth price account `set_price_oracle` points vaults at.
+    pub price_feed: Pubkey,
+    pub swap_pool: SwapPoolKeys,
+    pub dlmm: Dlmm,
+    pub whirlpool: WhirlpoolPool,
+    /// Vault over the DLMM pair, written directly.
+    pub dlmm_vault: VaultKeys,
+    /// Vault over the Whirlpool pool, created by the `initialize_vault` case.
+    pub whirlpool_vault: VaultKeys,
+}
+
+/// Accounts of one vault and the user's share account in it. The current
+/// position is read from the vault, as `rebalance` moves it.
+#[derive(Clone, Copy)]
+pub struct VaultKeys {
+    pub dex: Dex,
+    pub pool: Pubkey,
+    pub pool_token_vault_a: Pubkey,
+    pub pool_token_vault_b: Pubkey,
+    pub vault: Pubkey,
+    pub bump: u8,
+    pub vault_token_a: Pubkey,
+    pub vault_token_b: Pubkey,
+    pub share_mint: Pubkey,
+    pub user_share_account: Pubkey,
+}
+
+/// A mock swap program pool, the route of `deposit_via_route`.
+#[derive(Clone, Copy)]
+pub struct SwapPoolKeys {
+    pub key: Pubkey,
+    pub reserve_a: Pubkey,
+    pub reserve_b: Pubkey,
+}
+
+impl World {
+    pub fn new() -> Self {
+        let authority = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+        let mut world = Self {
+            accounts: HashMap::new(),
+            authority,
+            user,
+            mint_a,
+            mint_b,
+            protocol_config: Pubkey::find_program_address(
+                &[vault_manager::PROTOCOL_CONFIG_SEED],
+                &vault_manager::ID,
+            )
+            .0,
+            program_data: Pubkey::find_program_address(
+                &[vault_manager::ID.as_ref()],
+                &bpf_loader_upgradeable::ID,
+            )
+            .0,
+            user_token_a: Pubkey::new_unique(),
+            user_token_b: Pubkey::new_unique(),
+            treasury_token_a: Pubkey::new_unique(),
+            treasury_token_b: Pubkey::new_unique(),
+            price_feed: Pubkey::new_unique(),
+            swap_pool: SwapPoolKeys {
+                key: Pubkey::find_program_address(
+                    &[b"pool", mint_a.as_ref(), mint_b.as_ref()],
+                    &mock_swap::ID,
+                )
+                .0,
+                reserve_a: Pubkey::new_unique(),
+                reserve_b: Pubkey::new_unique(),
+            },
+            dlmm: Dlmm::default_keys(),
+            whirlpool: WhirlpoolPool::default_keys(),
+            dlmm_vault: VaultKeys::default_keys(Dex::MeteoraDlmm),
+            whirlpool_vault: VaultKeys::default_keys(Dex::Whirlpool),
+        };
+        for key in [authority, user] {
+            world.set_account(key, Account::new(10_000_000_000, 0, &system_program::ID));
+        }
+        world.set_mint(mint_a, authority, 0);
+        world.set_mint(mint_b, authority, 0);
+        world.set_program_data();
+        world.set_token_account(world.user_token_a, mint_a, user, 0);
+        world.set_token_account(world.user_token_b, mint_b, user, 16 * DEPOSIT);
+        world.set_token_account(world.treasury_token_a, mint_a, authority, 0);
+        world.set_token_account(world.treasury_token_b, mint_b, authority, 0);
+        world.set_account(world.price_feed, pyth_price(1_000_000, -6));
+        world.seed_swap_pool();
+
+        world.dlmm = Dlmm::seed(&mut world);
+        world.whirlpool = WhirlpoolPool::seed(&mut world);
+        world
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.accounts.insert(key, account);
+    }
+
+    /// Keeps the accounts an instruction left behind, dropping closed ones.
+    pub fn apply(&mut self, accounts: Vec<(Pubkey, Account)>) {
+        for (key, account) in accounts {
+            if account.lamports == 0 {
+                self.accounts.remove(&key);
+            } else {
+                self.accounts.insert(key, account);
+            }
+        }
+    }
+
+    /// Accounts `instruction` references, in order and without duplicates.
+    /// Missing ones are lent out empty and system owned, as the runtime does.
+    pub fn accounts_for(&self, instruction: &Instruction) -> Vec<(Pubkey, Account)> {
+        let mut accounts: Vec<(Pubkey, Account)> = Vec::new();
+        for meta in &instruction.accounts {
+            if accounts.iter().all(|(key, _)| *key != meta.pubkey) {
+                let account = self.accounts.get(&meta.pubkey).cloned().unwrap_or_default();
+                accounts.push((meta.pubkey, account));
+            }
+        }
+        accounts
+    }
+
+    pub fn anchor_account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.accounts.get(key).expect("account does not exist");
+        T::try_deserialize(&mut &account.data[..]).expect("invalid account data")
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        let account = self
+            .accounts
+            .get(key)
+            .expect("token account does not exist");
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    pub fn initialize_protocol_config(&self) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::InitializeProtocolConfig {
+                protocol_config: self.protocol_config,
+                admin: self.authority,
+                payer: self.authority,
+                program: vault_manager::ID,
+                program_data: self.program_data,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::InitializeProtocolConfig {
+                fee_share_bps: FEE_SHARE_BPS,
+                treasury: self.authority,
+                dex_programs: vec![mock_dlmm::ID, mock_whirlpool::ID],
+            }
+            .data(),
+        }
+    }
+
+    /// `initialize_vault` over `pool`, before the DEX's own accounts.
+    pub fn initialize_vault_accounts(
+        &self,
+        vault: &VaultKeys,
+        position: Pubkey,
+        position_mint: Pubkey,
+        position_token_account: Pubkey,
+    ) -> Vec<AccountMeta> {
+        vault_manager::accounts::InitializeVault {
+            vault: vault.vault,
+            protocol_config: self.protocol_config,
+            vault_registry: self.vault_registry(),
+            pool: vault.pool,
+            token_a_mint: self.mint_a,
+            token_b_mint: self.mint_b,
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            share_mint: vault.share_mint,
+            position,
+            position_mint,
+            position_token_account,
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            authority: self.authority,
+            payer: self.authority,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            dex_program: vault.dex.program_id(),
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    /// Deposits `amount` of token B, half of it zapped through the pool.
+    pub fn deposit(&self, vault: &VaultKeys, amount: u64) -> Instruction {
+        let mut accounts = self.deposit_accounts(vault);
+        accounts.extend(self.swap_accounts(vault, false));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::DepositAndAddLiquidity {
+                amount,
+                min_swap_out: 0,
+                min_shares_out: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Deposits `amount` of token B, half of it zapped through the mock swap
+    /// program, which `set_swap_programs` must have whitelisted.
+    pub fn deposit_via_route(&self, vault: &VaultKeys, amount: u64) -> Instruction {
+        let mut accounts = self.deposit_accounts(vault);
+        accounts.push(AccountMeta::new_readonly(mock_swap::ID, false));
+        accounts.extend(
+            mock_swap::accounts::Swap {
+                pool: self.swap_pool.key,
+                reserve_a: self.swap_pool.reserve_a,
+                reserve_b: self.swap_pool.reserve_b,
+                user_token_in: vault.vault_token_b,
+                user_token_out: vault.vault_token_a,
+                user: vault.vault,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None)
+            .into_iter()
+            // The vault signs inside the deposit, not for the transaction
+            .map(|meta| AccountMeta {
+                is_signer: false,
+                ..meta
+            }),
+        );
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::DepositViaRoute {
+                amount,
+                min_shares_out: 0,
+                route: SwapRoute {
+                    data: mock_swap::instruction::Swap {
+                        amount_in: amount / 2,
+                        min_amount_out: 0,
+                    }
+                    .data(),
+                    min_amount_out: 0,
+                },
+            }
+            .data(),
+        }
+    }
+
+    pub fn get_nav(&self, vault: &VaultKeys) -> Instruction {
+        let mut accounts = vault_manager::accounts::GetNav {
+            vault: vault.vault,
+            pool: vault.pool,
+            position: self.position(vault),
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            share_mint: vault.share_mint,
+            token_program: spl_token::ID,
+            dex_program: vault.dex.program_id(),
+        }
+        .to_account_metas(None);
+        accounts.extend(self.position_accounts(vault, self.position(vault)));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::GetNav {}.data(),
+        }
+    }
+
+    pub fn collect(&self, vault: &VaultKeys) -> Instruction {
+        let mut accounts = vault_manager::accounts::Collect {
+            vault: vault.vault,
+            authority: self.authority,
+            pool: vault.pool,
+            position: self.position(vault),
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            token_program: spl_token::ID,
+            dex_program: vault.dex.program_id(),
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        accounts.extend(self.position_accounts(vault, self.position(vault)));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::Collect {}.data(),
+        }
+    }
+
+    pub fn withdraw(&self, vault: &VaultKeys, shares: u64) -> Instruction {
+        let mut accounts = vault_manager::accounts::Withdraw {
+            vault: vault.vault,
+            user: self.user,
+            user_share_account: vault.user_share_account,
+            user_token_a: self.user_token_a,
+            user_token_b: self.user_token_b,
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            share_mint: vault.share_mint,
+            pool: vault.pool,
+            position: self.position(vault),
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: vault.dex.program_id(),
+            user_position: self.user_position(vault),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        accounts.extend(self.position_accounts(vault, self.position(vault)));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::Withdraw {
+                shares,
+                min_amount_a: 0,
+                min_amount_b: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Withdraws half of the user's shares.
+    pub fn withdraw_half(&self, vault: &VaultKeys) -> Instruction {
+        self.withdraw(vault, self.token_balance(&vault.user_share_account) / 2)
+    }
+
+    /// Moves the vault's liquidity into a new position, opened by the
+    /// instruction over a range next to the current one, swapping in direction
+    /// `a_to_b` toward its ratio.
+    pub fn rebalance(&self, vault: &VaultKeys, a_to_b: bool) -> Instruction {
+        let position = self.position(vault);
+        // Whirlpool positions are PDAs of a new NFT mint, which signs; DLMM
+        // positions are keypair accounts, which sign themselves
+        let new_position_mint = Pubkey::new_unique();
+        let (new_position, new_position_signer, range) = match vault.dex {
+            Dex::Whirlpool => (
+                whirlpool::position_address(&new_position_mint),
+                new_position_mint,
+                (-1280, 1280),
+            ),
+            _ => {
+                let new_position = Pubkey::new_unique();
+                (new_position, new_position, (-10, 0))
+            }
+        };
+        let new_position_token_account =
+            get_associated_token_address(&vault.vault, &new_position_mint);
+        let mut accounts = vault_manager::accounts::Rebalance {
+            vault: vault.vault,
+            authority: self.authority,
+            payer: self.authority,
+            pool: vault.pool,
+            position,
+            share_mint: vault.share_mint,
+            new_position,
+            new_position_mint,
+            new_position_token_account,
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            dex_program: vault.dex.program_id(),
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        for meta in &mut accounts {
+            if meta.pubkey == new_position_signer {
+                meta.is_signer = true;
+            }
+        }
+        let mut position_accounts = self.position_accounts(vault, position);
+        match vault.dex {
+            Dex::Whirlpool => {
+                // Closing the emptied position burns its NFT
+                position_accounts[0].is_writable = true;
+                accounts.extend(position_accounts);
+                accounts.extend(self.swap_accounts(vault, a_to_b));
+                accounts.extend(
+                    self.whirlpool
+                        .range_accounts(new_position_token_account, range),
+                );
+                let state: mock_whirlpool::state::Position = self.anchor_account(&position);
+                accounts.push(AccountMeta::new(state.position_mint, false));
+            }
+            _ => {
+                accounts.extend(position_accounts);
+                accounts.extend(self.swap_accounts(vault, a_to_b));
+                accounts.push(AccountMeta::new_readonly(self.dlmm.event_authority, false));
+                accounts.extend(self.dlmm.range_accounts(self, range));
+            }
+        }
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::Rebalance {
+                tick_lower: range.0,
+                tick_upper: range.1,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                min_swap_out: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Moves all of the user's shares of `source` into `destination`.
+    pub fn migrate_liquidity(&self, source: &VaultKeys, destination: &VaultKeys) -> Instruction {
+        let mut accounts = vault_manager::accounts::MigrateLiquidity {
+            user: self.user,
+            source_vault: source.vault,
+            source_share_mint: source.share_mint,
+            user_source_share_account: source.user_share_account,
+            source_vault_token_a: source.vault_token_a,
+            source_vault_token_b: source.vault_token_b,
+            source_pool: source.pool,
+            source_position: self.position(source),
+            source_pool_token_vault_a: source.pool_token_vault_a,
+            source_pool_token_vault_b: source.pool_token_vault_b,
+            source_price_oracle: None,
+            source_dex_program: source.dex.program_id(),
+            source_user_position: self.user_position(source),
+            destination_vault: destination.vault,
+            destination_share_mint: destination.share_mint,
+            user_destination_share_account: destination.user_share_account,
+            destination_vault_token_a: destination.vault_token_a,
+            destination_vault_token_b: destination.vault_token_b,
+            destination_pool: destination.pool,
+            destination_position: self.position(destination),
+            destination_pool_token_vault_a: destination.pool_token_vault_a,
+            destination_pool_token_vault_b: destination.pool_token_vault_b,
+            destination_price_oracle: None,
+            destination_dex_program: destination.dex.program_id(),
+            destination_allowlist_entry: None,
+            token_program: spl_token::ID,
+            destination_user_position: self.user_position(destination),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        accounts.extend(self.position_accounts(source, self.position(source)));
+        accounts.extend(self.position_accounts(destination, self.position(destination)));
+        accounts.extend(self.swap_accounts(destination, false));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::MigrateLiquidity {
+                shares: self.token_balance(&source.user_share_account),
+                min_amount_a: 0,
+                min_amount_b: 0,
+                min_swap_out: 0,
+                min_shares_out: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Closes a vault without shares or liquidity.
+    pub fn close_vault(&self, vault: &VaultKeys) -> Instruction {
+        let mut accounts = vault_manager::accounts::CloseVault {
+            vault: vault.vault,
+            authority: self.authority,
+            vault_registry: self.vault_registry(),
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            authority_token_a: self.treasury_token_a,
+            authority_token_b: self.treasury_token_b,
+            share_mint: vault.share_mint,
+            pool: vault.pool,
+            position: self.position(vault),
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            token_program: spl_token::ID,
+            dex_program: vault.dex.program_id(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(self.close_position_accounts(vault));
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::CloseVault {}.data(),
+        }
+    }
+
+    pub fn request_withdrawal(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::RequestWithdrawal {
+                vault: vault.vault,
+                user: self.user,
+                user_position: self.user_position(vault),
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::RequestWithdrawal {
+                shares: self.token_balance(&vault.user_share_account),
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_paused(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetPaused {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetPaused { paused: true }.data(),
+        }
+    }
+
+    pub fn set_deposit_limits(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetDepositLimits {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetDepositLimits {
+                min_deposit: 1_000,
+                user_deposit_cap: 0,
+                deposit_cap: 1_000_000_000_000,
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_twap_guard(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetTwapGuard {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            // DLMM pools keep no tick observations, so only disabling is accepted
+            data: vault_manager::instruction::SetTwapGuard {
+                window_secs: 0,
+                max_deviation_ticks: 0,
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_lockup(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetLockup {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetLockup {
+                lockup_slots: 1_000,
+                early_exit_fee_bps: 50,
+                withdrawal_delay_slots: 100,
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_nav_circuit_breaker(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetNavCircuitBreaker {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetNavCircuitBreaker { max_drop_bps: 500 }.data(),
+        }
+    }
+
+    pub fn set_price_oracle(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetPriceOracle {
+                vault: vault.vault,
+                authority: self.authority,
+                price_oracle: Some(self.price_feed),
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetPriceOracle {
+                max_deviation_bps: 100,
+                max_staleness_secs: 60,
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_bin_strategy(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetBinStrategy {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetBinStrategy {
+                strategy: BinStrategy::Curve,
+            }
+            .data(),
+        }
+    }
+
+    /// Whitelists the mock swap program for routed deposits.
+    pub fn set_swap_programs(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetSwapPrograms {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetSwapPrograms {
+                programs: vec![mock_swap::ID],
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_authority(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetAuthority {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetAuthority {
+                new_authority: self.user,
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_strategy_config(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetStrategyConfig {
+                vault: vault.vault,
+                authority: self.authority,
+                payer: self.authority,
+                strategy_config: self.strategy_config(vault),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetStrategyConfig {
+                range_width: 20,
+                rebalance_threshold: 5,
+            }
+            .data(),
+        }
+    }
+
+    /// Queues a deposit limit change, which applies at once without a timelock.
+    pub fn queue_param_change(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::QueueParamChange {
+                vault: vault.vault,
+                authority: self.authority,
+                payer: self.authority,
+                pending_change: self.pending_change(vault),
+                system_program: system_program::ID,
+                price_oracle: None,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::QueueParamChange {
+                change: ParamChange::DepositLimits {
+                    min_deposit: 1_000,
+                    user_deposit_cap: 0,
+                    deposit_cap: 1_000_000_000_000,
+                },
+            }
+            .data(),
+        }
+    }
+
+    pub fn execute_param_change(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::ExecuteParamChange {
+                vault: vault.vault,
+                authority: self.authority,
+                payer: self.authority,
+                pending_change: self.pending_change(vault),
+                strategy_config: None,
+                system_program: None,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::ExecuteParamChange {}.data(),
+        }
+    }
+
+    pub fn cancel_param_change(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::CancelParamChange {
+                vault: vault.vault,
+                authority: self.authority,
+                pending_change: self.pending_change(vault),
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::CancelParamChange {}.data(),
+        }
+    }
+
+    /// Enables the allowlist with a Merkle root of the user and one other wallet.
+    pub fn set_allowlist(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetAllowlist {
+                vault: vault.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetAllowlist {
+                enabled: true,
+                merkle_root: allowlist::node(
+                    &allowlist::leaf(&self.user),
+                    &allowlist::leaf(&self.authority),
+                ),
+            }
+            .data(),
+        }
+    }
+
+    pub fn add_to_allowlist(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::AddToAllowlist {
+                vault: vault.vault,
+                allowlist_entry: self.allowlist_entry(vault),
+                authority: self.authority,
+                payer: self.authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::AddToAllowlist { wallet: self.user }.data(),
+        }
+    }
+
+    pub fn remove_from_allowlist(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::RemoveFromAllowlist {
+                vault: vault.vault,
+                allowlist_entry: self.allowlist_entry(vault),
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::RemoveFromAllowlist {}.data(),
+        }
+    }
+
+    /// The user joining with their proof against `set_allowlist`'s root.
+    pub fn join_allowlist(&self, vault: &VaultKeys) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::JoinAllowlist {
+                vault: vault.vault,
+                allowlist_entry: self.allowlist_entry(vault),
+                wallet: self.user,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::JoinAllowlist {
+                proof: vec![allowlist::leaf(&self.authority)],
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_protocol_config(&self) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetProtocolConfig {
+                protocol_config: self.protocol_config,
+                admin: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetProtocolConfig {
+                fee_share_bps: 2 * FEE_SHARE_BPS,
+                treasury: self.authority,
+                dex_programs: vec![mock_dlmm::ID, mock_whirlpool::ID],
+            }
+            .data(),
+        }
+    }
+
+    pub fn set_protocol_admin(&self) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetProtocolAdmin {
+                protocol_config: self.protocol_config,
+                admin: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetProtocolAdmin {
+                new_admin: self.user,
+            }
+            .data(),
+        }
+    }
+
+    pub fn vault_state(&self, vault: &VaultKeys) -> Vault {
+        self.anchor_account(&vault.vault)
+    }
+
+    /// The vault's current position.
+    pub fn position(&self, vault: &VaultKeys) -> Pubkey {
+        self.vault_state(vault).position
+    }
+
+    /// Registry page 0, which lists every vault the benchmarks create.
+    fn vault_registry(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn user_position(&self, vault: &VaultKeys) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::USER_POSITION_SEED,
+                vault.vault.as_ref(),
+                self.user.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn strategy_config(&self, vault: &VaultKeys) -> Pubkey {
+        Pubkey::find_program_address(
+            &[vault_manager::STRATEGY_CONFIG_SEED, vault.vault.as_ref()],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn pending_change(&self, vault: &VaultKeys) -> Pubkey {
+        Pubkey::find_program_address(
+            &[vault_manager::PARAM_CHANGE_SEED, vault.vault.as_ref()],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn allowlist_entry(&self, vault: &VaultKeys) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::ALLOWLIST_SEED,
+                vault.vault.as_ref(),
+                self.user.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    /// The user's deposit accounts, through the vault's position accounts.
+    fn deposit_accounts(&self, vault: &VaultKeys) -> Vec<AccountMeta> {
+        let mut accounts = vault_manager::accounts::DepositAndAddLiquidity {
+            vault: vault.vault,
+            user: self.user,
+            user_token_account: self.user_token_b,
+            user_share_account: vault.user_share_account,
+            vault_token_a: vault.vault_token_a,
+            vault_token_b: vault.vault_token_b,
+            share_mint: vault.share_mint,
+            pool: vault.pool,
+            position: self.position(vault),
+            pool_token_vault_a: vault.pool_token_vault_a,
+            pool_token_vault_b: vault.pool_token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: vault.dex.program_id(),
+            allowlist_entry: None,
+            user_position: self.user_position(vault),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(self.position_accounts(vault, self.position(vault)));
+        accounts
+    }
+
+    /// The backend's account set of `position`.
+    fn position_accounts(&self, vault: &VaultKeys, position: Pubkey) -> Vec<AccountMeta> {
+        match vault.dex {
+            Dex::MeteoraDlmm => self.dlmm.position_accounts(self, position),
+            Dex::Whirlpool => self.whirlpool.position_accounts(self, vault, position),
+            Dex::RaydiumClmm => unimplemented!("no Raydium pool in the benchmarks"),
+        }
+    }
+
+    /// The backend's swap account set, for a swap in direction `a_to_b`.
+    fn swap_accounts(&self, vault: &VaultKeys, a_to_b: bool) -> Vec<AccountMeta> {
+        match vault.dex {
+            Dex::MeteoraDlmm => self.dlmm.swap_accounts(self),
+            Dex::Whirlpool => self.whirlpool.swap_accounts(self, a_to_b),
+            Dex::RaydiumClmm => unimplemented!("no Raydium pool in the benchmarks"),
+        }
+    }
+
+    /// The backend's accounts for closing the vault's position.
+    fn close_position_accounts(&self, vault: &VaultKeys) -> Vec<AccountMeta> {
+        match vault.dex {
+            Dex::Whirlpool => self.whirlpool.close_position_accounts(self, vault),
+            _ => unimplemented!("only the Whirlpool vault is closed"),
+        }
+    }
+
+    /// Mock swap pool at a 1:1 price with deep reserves.
+    fn seed_swap_pool(&mut self) {
+        let pool = self.swap_pool;
+        self.set_token_account(pool.reserve_a, self.mint_a, pool.key, 1_000_000_000_000);
+        self.set_token_account(pool.reserve_b, self.mint_b, pool.key, 1_000_000_000_000);
+        let bump = Pubkey::find_program_address(
+            &[b"pool", self.mint_a.as_ref(), self.mint_b.as_ref()],
+            &mock_swap::ID,
+        )
+        .1;
+        self.set_anchor_account(
+            pool.key,
+            &SwapPool {
+                token_a_mint: self.mint_a,
+                token_b_mint: self.mint_b,
+                reserve_a: pool.reserve_a,
+                reserve_b: pool.reserve_b,
+                price_x64: 1 << 64,
+                fee_bps: 30,
+                bump,
+            },
+        );
+    }
+
+    /// vault_manager's program account and its program data, which
+    /// `initialize_protocol_config` checks the admin against.
+    fn set_program_data(&mut self) {
+        // Loader v3 state: the `Program` variant naming its data account, and
+        // the `ProgramData` header with the deploy slot and upgrade authority
+        let mut program = 2u32.to_le_bytes().to_vec();
+        program.extend(self.program_data.as_ref());
+        self.set_account(
+            vault_manager::ID,
+            Account {
+                executable: true,
+                ..rent_exempt(program, bpf_loader_upgradeable::ID)
+            },
+        );
+        let mut program_data = 3u32.to_le_bytes().to_vec();
+        program_data.extend(0u64.to_le_bytes());
+        program_data.push(1);
+        program_data.extend(self.authority.as_ref());
+        self.set_account(
+            self.program_data,
+            rent_exempt(program_data, bpf_loader_upgradeable::ID),
+        );
+    }
+
+    pub(crate) fn set_anchor_account<T: AccountSerialize + Owner>(
+        &mut self,
+        key: Pubkey,
+        value: &T,
+    ) {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        self.set_account(key, rent_exempt(data, T::owner()));
+    }
+
+    pub(crate) fn set_mint(&mut self, key: Pubkey, authority: Pubkey, supply: u64) {
+        self.set_mint_with_decimals(key, Some(authority), supply, 6);
+    }
+
+    pub(crate) fn set_mint_with_decimals(
+        &mut self,
+        key: Pubkey,
+        authority: Option<Pubkey>,
+        supply: u64,
+        decimals: u8,
+    ) {
+        let mint = spl_token::state::Mint {
+            mint_authority: authority.into(),
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        self.set_account(key, rent_exempt(data, spl_token::ID));
+    }
+
+    pub(crate) fn set_token_account(
+        &mut self,
+        key: Pubkey,
+        mint: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+    ) {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        self.set_account(key, rent_exempt(data, spl_token::ID));
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VaultKeys {
+    fn default_keys(dex: Dex) -> Self {
+        Self {
+            dex,
+            pool: Pubkey::default(),
+            pool_token_vault_a: Pubkey::default(),
+            pool_token_vault_b: Pubkey::default(),
+            vault: Pubkey::default(),
+            bump: 0,
+            vault_token_a: Pubkey::new_unique(),
+            vault_token_b: Pubkey::new_unique(),
+            share_mint: Pubkey::default(),
+            user_share_account: Pubkey::new_unique(),
+        }
+    }
+
+    /// Keys of the vault over `pool`, whose token vaults are given.
+    pub(crate) fn over(
+        dex: Dex,
+        pool: Pubkey,
+        (pool_token_vault_a, pool_token_vault_b): (Pubkey, Pubkey),
+    ) -> Self {
+        let (vault, bump) = vault_address(&pool);
+        Self {
+            pool,
+            pool_token_vault_a,
+            pool_token_vault_b,
+            vault,
+            bump,
+            share_mint: Pubkey::find_program_address(
+                &[vault_manager::SHARE_MINT_SEED, vault.as_ref()],
+                &vault_manager::ID,
+            )
+            .0,
+            ..Self::default_keys(dex)
+        }
+    }
+}
+
+/// The vault PDA of `pool` and its bump.
+pub(crate) fn vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[vault_manager::VAULT_SEED, pool.as_ref()],
+        &vault_manager::ID,
+    )
+}
+
+pub(crate) fn rent_exempt(data: Vec<u8>, owner: Pubkey) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Pyth v2 price account publishing `price` x 10^`expo` of token A in token B,
+/// trading, as `oracle` reads it.
+fn pyth_price(price: i64, expo: i32) -> Account {
+    let mut data = vec![0; 3312];
+    let mut put = |at: usize, bytes: &[u8]| data[at..at + bytes.len()].copy_from_slice(bytes);
+    put(0, &0xa1b2_c3d4u32.to_le_bytes());
+    put(4, &2u32.to_le_bytes());
+    put(8, &3u32.to_le_bytes());
+    put(20, &expo.to_le_bytes());
+    put(208, &price.to_le_bytes());
+    put(216, &1_000i64.to_le_bytes());
+    put(224, &1u32.to_le_bytes());
+    rent_exempt(data, Pubkey::new_unique())
+}
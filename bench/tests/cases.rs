@@ -0,0 +1,105 @@
+//! The benchmark cases, checked without an SBF build: every instruction of the
+//! program has a case and a budget, and every case succeeds when run in order
+//! in the native test harness.
+
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::Pubkey;
+use native_svm::{Harness, TestAccount};
+use solana_account::Account;
+use vault_manager_bench::budget::Budgets;
+use vault_manager_bench::cases::CASES;
+use vault_manager_bench::world::World;
+
+/// Instruction names in the checked-in IDL.
+fn idl_instructions() -> BTreeSet<String> {
+    let idl = include_str!("../../inspect/tests/idl/vault_manager.json");
+    let idl: serde_json::Value = serde_json::from_str(idl).unwrap();
+    idl["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|instruction| instruction["name"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn every_instruction_has_a_budget_and_a_case() {
+    let instructions = idl_instructions();
+    let budgets = Budgets::parse(include_str!("../budgets.toml")).unwrap();
+    assert_eq!(
+        budgets
+            .instructions
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>(),
+        instructions,
+        "budgets.toml does not list exactly the IDL's instructions"
+    );
+    let benchmarked: BTreeSet<_> = CASES
+        .iter()
+        .map(|case| case.instruction.to_owned())
+        .collect();
+    assert_eq!(
+        benchmarked, instructions,
+        "cases do not cover exactly the IDL's instructions"
+    );
+}
+
+#[test]
+fn cases_succeed_in_the_native_harness() {
+    let mut h = Harness::new();
+    h.add_program(vault_manager::ID, vault_manager::entry);
+    h.add_program(mock_dlmm::ID, mock_dlmm::entry);
+    h.add_program(mock_whirlpool::ID, mock_whirlpool::entry);
+    h.add_program(mock_swap::ID, mock_swap::entry);
+
+    let mut world = World::new();
+    for case in CASES {
+        let instruction = (case.build)(&world);
+        let keys: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        // The harness keeps its own program and sysvar accounts
+        for key in &keys {
+            if let Some(account) = world.accounts.get(key) {
+                h.set_account(
+                    *key,
+                    TestAccount {
+                        lamports: account.lamports,
+                        data: account.data.clone(),
+                        owner: account.owner,
+                        executable: account.executable,
+                    },
+                );
+            }
+        }
+        let snapshot = h.clone();
+        if let Err(err) = h.process(&instruction) {
+            panic!("{} ({}) failed: {err:?}", case.instruction, case.case);
+        }
+        if !case.keep {
+            h = snapshot;
+            continue;
+        }
+        for key in keys {
+            match h.account(&key) {
+                Some(account) => world.set_account(
+                    key,
+                    Account {
+                        lamports: account.lamports,
+                        data: account.data.clone(),
+                        owner: account.owner,
+                        executable: account.executable,
+                        rent_epoch: 0,
+                    },
+                ),
+                None => {
+                    world.accounts.remove(&key);
+                }
+            }
+        }
+    }
+}
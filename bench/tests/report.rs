@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use vault_manager_bench::budget::{self, Budgets};
+use vault_manager_bench::report::{self, Measurement};
+
+fn measurement(instruction: &str, case: &str, compute_units: u64, budget: u64) -> Measurement {
+    Measurement {
+        instruction: instruction.to_owned(),
+        case: case.to_owned(),
+        compute_units,
+        budget,
+    }
+}
+
+#[test]
+fn checked_in_budgets_parse() {
+    let budgets = Budgets::parse(include_str!("../budgets.toml")).unwrap();
+    assert_eq!(
+        budgets.limit("deposit_and_add_liquidity"),
+        Some(budgets.instructions["deposit_and_add_liquidity"] + budgets.margin)
+    );
+    assert!(budgets.limit("set_paused") < budgets.limit("withdraw"));
+    assert_eq!(budgets.limit("no_such_instruction"), None);
+}
+
+#[test]
+fn recording_replaces_only_the_measured_instructions() {
+    let source = "\
+# Ceilings
+margin = 1_000
+
+[instructions]
+# Through the DEX program
+withdraw = 95_000
+set_paused = 6_000
+";
+    let recorded = budget::record(
+        source,
+        &BTreeMap::from([("withdraw".to_owned(), 1_234_567)]),
+    );
+    assert_eq!(
+        recorded,
+        source.replace("withdraw = 95_000", "withdraw = 1_234_567")
+    );
+    let budgets = Budgets::parse(&recorded).unwrap();
+    assert_eq!(budgets.limit("withdraw"), Some(1_235_567));
+    assert_eq!(budgets.limit("set_paused"), Some(7_000));
+}
+
+#[test]
+fn report_shows_the_change_from_the_previous_run() {
+    let first = [
+        measurement("withdraw", "half of the shares", 80_000, 200_000),
+        measurement("set_paused", "pause", 4_000, 25_000),
+    ];
+    let previous = report::render(&first, &BTreeMap::new(), Some("abc1234"));
+    assert_eq!(
+        report::parse(&previous),
+        BTreeMap::from([
+            (("set_paused".into(), "pause".into()), 4_000),
+            (("withdraw".into(), "half of the shares".into()), 80_000),
+        ])
+    );
+
+    let second = [
+        measurement("withdraw", "half of the shares", 79_500, 200_000),
+        measurement("set_paused", "pause", 4_000, 25_000),
+        measurement("rebalance", "into a new range", 210_000, 200_000),
+    ];
+    let rendered = report::render(&second, &report::parse(&previous), None);
+    let rows: Vec<&str> = rendered.lines().skip(4).collect();
+    assert_eq!(
+        rows,
+        [
+            "| `withdraw` | half of the shares | 79500 | 200000 | -500 |",
+            "| `set_paused` | pause | 4000 | 25000 |  |",
+            "| `rebalance` | into a new range | 210000 | **200000** | new |",
+        ]
+    );
+    assert!(second[2].over_budget() && !second[0].over_budget());
+}
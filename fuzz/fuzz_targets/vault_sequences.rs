@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vault_manager_fuzz::Scenario;
+
+fuzz_target!(|scenario: Scenario| scenario.run());
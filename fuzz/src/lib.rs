@@ -0,0 +1,321 @@
+//! Random sequences of vault instructions against the mock Whirlpool.
+//!
+//! Every operation is a real transaction in the native test harness: the
+//! vault program runs its deposit, withdraw, rebalance and collect handlers,
+//! CPIs into the mock Whirlpool and SPL Token included, and price moves are
+//! trades through the pool, which also earn the vault fees. A failed
+//! operation leaves no trace, like a failed transaction.
+//!
+//! After every operation the fuzzer checks, through `get_nav` and the token
+//! accounts:
+//!
+//! - `get_nav` succeeds and its idle balances are the vault's token balances,
+//!   so the vault never owes more than its accounts hold,
+//! - user share balances add up to the share supply, and an empty supply
+//!   leaves nothing behind in the vault,
+//! - deposits, withdrawals and collections never lower the value of a
+//!   remaining share by more than a few atoms of rounding, counting what the
+//!   protocol took from the fees as still the vault's. A deposit's own zap
+//!   swap is compared against the same swap made by a trader, so its fee and
+//!   price move are the depositor's,
+//! - a withdrawal pays at most its shares' part of each token the vault
+//!   holds, uncollected fees included.
+//!
+//! The last two bound what any user can take out: shares are minted at most
+//! at the value deposited, and redeemed at most at their part of the vault,
+//! which only grows by the fees they earned while holding them.
+//!
+//! Run with `cargo +nightly fuzz run vault_sequences` from this directory;
+//! `cargo test` replays a fixed set of sequences.
+
+pub mod world;
+
+use anchor_lang::solana_program::program_error::ProgramError;
+use arbitrary::{Arbitrary, Unstructured};
+use vault_manager::math;
+use vault_manager::nav::VaultNav;
+
+pub use wide::U256;
+use world::{World, MAX_SPACINGS, TICK_SPACING};
+
+/// Kept apart from the Anchor prelude, whose `Result` alias the macro cannot use.
+#[allow(clippy::all)]
+mod wide {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+pub const USERS: usize = 4;
+/// Atoms of each token a share's value may lose to rounding in one operation.
+const ROUNDING_ATOMS: u64 = 3;
+
+/// Protocol fee share, starting range and the operations run against them.
+#[derive(Arbitrary, Clone, Debug)]
+pub struct Scenario {
+    pub fee_share_bps: u16,
+    pub range: Range,
+    pub ops: Vec<Op>,
+}
+
+#[derive(Arbitrary, Clone, Debug)]
+pub enum Op {
+    Deposit {
+        user: u8,
+        token_a: bool,
+        amount: Amount,
+    },
+    /// Redeems `fraction / u16::MAX` of the user's shares.
+    Withdraw {
+        user: u8,
+        fraction: u16,
+    },
+    /// Rebalances into `range`, swapping in direction `a_to_b`.
+    Rebalance {
+        range: Range,
+        a_to_b: bool,
+    },
+    Collect,
+    /// A trade through the pool, which moves the price and pays fees.
+    Swap {
+        a_to_b: bool,
+        amount: Amount,
+    },
+}
+
+/// Token amount spread over every magnitude rather than clustered near `u64::MAX`.
+#[derive(Clone, Copy, Debug)]
+pub struct Amount(pub u64);
+
+impl<'a> Arbitrary<'a> for Amount {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bits = u.int_in_range(0..=64u32)?;
+        let value = u64::arbitrary(u)?;
+        Ok(Self(value.checked_shr(64 - bits).unwrap_or(0)))
+    }
+}
+
+/// Tick range on the pool's spacing, as `lower` and a width in spacings.
+#[derive(Arbitrary, Clone, Copy, Debug, Default)]
+pub struct Range {
+    pub lower: i16,
+    pub width: u8,
+}
+
+impl Range {
+    /// `[tick_lower, tick_upper)` within the pool's initialized tick arrays, where
+    /// `lower` is in spacings from tick 0 when the range fits.
+    pub fn ticks(self) -> (i32, i32) {
+        let width = i32::from(self.width) + 1;
+        let lower = (i32::from(self.lower) + MAX_SPACINGS).rem_euclid(2 * MAX_SPACINGS - width + 1)
+            - MAX_SPACINGS;
+        let spacing = i32::from(TICK_SPACING);
+        (lower * spacing, (lower + width) * spacing)
+    }
+}
+
+impl Scenario {
+    /// Runs every operation, panicking on the first broken invariant.
+    pub fn run(&self) {
+        let mut world = self.world();
+        for op in &self.ops {
+            // A failed operation changes nothing, so only the invariants matter
+            let _ = apply(&mut world, op);
+            check(&mut world);
+        }
+    }
+
+    /// The vault the operations start from.
+    pub fn world(&self) -> World {
+        let (tick_lower, tick_upper) = self.range.ticks();
+        World::new(tick_lower, tick_upper, self.fee_share_bps % 10_000, USERS)
+    }
+}
+
+/// Sends `op` to the vault, checking what it changed if it succeeds.
+pub fn apply(world: &mut World, op: &Op) -> Result<(), ProgramError> {
+    match *op {
+        Op::Deposit {
+            user,
+            token_a,
+            amount,
+        } => deposit(world, usize::from(user) % USERS, token_a, amount.0),
+        Op::Withdraw { user, fraction } => {
+            let user = usize::from(user) % USERS;
+            let balance = world.h.token_balance(&world.users[user].shares);
+            let shares = math::mul_div_u64(balance, u64::from(fraction), u64::from(u16::MAX))
+                .expect("fraction of a balance");
+            withdraw(world, user, shares)
+        }
+        Op::Rebalance { range, a_to_b } => {
+            let (tick_lower, tick_upper) = range.ticks();
+            world.rebalance(tick_lower, tick_upper, a_to_b)
+        }
+        Op::Collect => collect(world),
+        Op::Swap { a_to_b, amount } => world.swap(a_to_b, amount.0),
+    }
+}
+
+/// Deposits `amount`, capped at the user's balance, and checks that the
+/// holders before it lost nothing to it but what the zap swap's price move
+/// costs them.
+pub fn deposit(
+    world: &mut World,
+    user: usize,
+    token_a: bool,
+    amount: u64,
+) -> Result<(), ProgramError> {
+    let account = if token_a {
+        world.users[user].token_a
+    } else {
+        world.users[user].token_b
+    };
+    let amount = amount.min(world.h.token_balance(&account));
+    let holders = world.h.mint_supply(&world.share_mint);
+    // Earlier holders' value as the deposit should leave it: as it is, or
+    // after the same swap made by a trader, its fees included
+    let expected = if holders == 0 {
+        None
+    } else if amount / 2 == 0 {
+        world
+            .nav()
+            .ok()
+            .map(|nav| nav.value_of_shares(holders).unwrap())
+    } else {
+        let mut baseline = world.clone();
+        baseline
+            .swap(token_a, amount / 2)
+            .ok()
+            .map(|()| baseline.nav().expect("get_nav fails after a swap"))
+            .map(|nav| nav.value_of_shares(holders).unwrap())
+    };
+
+    world.deposit(user, token_a, amount)?;
+    let nav = world.nav().expect("get_nav fails after a deposit");
+    if let Some(value) = expected {
+        let kept = nav.value_of_shares(holders).unwrap();
+        assert!(
+            kept + rounding(nav.sqrt_price) >= value,
+            "deposit left earlier holders {kept} of {value}"
+        );
+    }
+    Ok(())
+}
+
+/// Withdraws `shares` and checks the user got at most their part of each
+/// token, and the remaining shares kept their value.
+pub fn withdraw(world: &mut World, user: usize, shares: u64) -> Result<(), ProgramError> {
+    let before = world.nav().expect("get_nav fails before a withdrawal");
+    let balances = |world: &World| {
+        let user = &world.users[user];
+        [
+            world.h.token_balance(&user.token_a),
+            world.h.token_balance(&user.token_b),
+        ]
+    };
+    let (held, treasury) = (balances(world), treasury(world));
+
+    world.withdraw(user, shares)?;
+    let paid = balances(world);
+    for (i, total) in [before.total_a, before.total_b].into_iter().enumerate() {
+        let paid = paid[i] - held[i];
+        // Each part is rounded down, against a total that may be an atom short
+        assert!(
+            U256::from(paid) * U256::from(before.share_supply)
+                <= U256::from(shares) * (U256::from(total) + U256::from(ROUNDING_ATOMS)),
+            "withdrawal of {shares} / {} shares paid {paid} of {total} of token {}",
+            before.share_supply,
+            ["A", "B"][i],
+        );
+    }
+    check_share_value(world, &before, treasury);
+    Ok(())
+}
+
+/// Collects fees and checks shares kept their value, the protocol's part
+/// included.
+pub fn collect(world: &mut World) -> Result<(), ProgramError> {
+    let before = world.nav().expect("get_nav fails before a collection");
+    let treasury = treasury(world);
+    let collected = world.collect()?;
+    let took = treasury_delta(world, treasury);
+    assert_eq!(
+        took,
+        [collected.protocol_a, collected.protocol_b],
+        "treasury received other than the protocol share collect reported"
+    );
+    check_share_value(world, &before, treasury);
+    Ok(())
+}
+
+/// Invariants that hold between operations.
+pub fn check(world: &mut World) {
+    let nav = world.nav().expect("get_nav fails");
+    assert_eq!(
+        (nav.idle_a, nav.idle_b),
+        (
+            world.h.token_balance(&world.vault_token_a),
+            world.h.token_balance(&world.vault_token_b),
+        ),
+        "idle balances are not the vault's token balances"
+    );
+
+    let supply = world.h.mint_supply(&world.share_mint);
+    let held: u64 = world
+        .users
+        .iter()
+        .map(|user| world.h.token_balance(&user.shares))
+        .sum();
+    assert_eq!(held, supply, "user shares do not add up to the supply");
+    assert_eq!(nav.share_supply, supply);
+    if supply == 0 {
+        assert_eq!(
+            (nav.total_a, nav.total_b),
+            (0, 0),
+            "vault holds funds without shares"
+        );
+    }
+}
+
+fn treasury(world: &World) -> [u64; 2] {
+    [
+        world.h.token_balance(&world.treasury_token_a),
+        world.h.token_balance(&world.treasury_token_b),
+    ]
+}
+
+fn treasury_delta(world: &World, before: [u64; 2]) -> [u64; 2] {
+    let after = treasury(world);
+    [after[0] - before[0], after[1] - before[1]]
+}
+
+/// Token B value of `ROUNDING_ATOMS` of each token at `sqrt_price`.
+fn rounding(sqrt_price: u128) -> u128 {
+    math::value_in_b(ROUNDING_ATOMS, ROUNDING_ATOMS, sqrt_price).unwrap() + 1
+}
+
+/// A share is worth at least what it was `before`, less rounding, counting
+/// what the treasury gained since `treasury` as the vault's. The price has
+/// not moved.
+fn check_share_value(world: &mut World, before: &VaultNav, treasury: [u64; 2]) {
+    let after = world.nav().expect("get_nav fails");
+    if before.share_supply == 0 || after.share_supply == 0 {
+        return;
+    }
+    let took = treasury_delta(world, treasury);
+    let value_after =
+        after.total_value_b + math::value_in_b(took[0], took[1], after.sqrt_price).unwrap();
+    let (supply_before, supply_after) = (
+        U256::from(before.share_supply),
+        U256::from(after.share_supply),
+    );
+    let slack = U256::from(rounding(after.sqrt_price)) * supply_before.max(supply_after);
+    assert!(
+        U256::from(before.total_value_b) * supply_after
+            <= U256::from(value_after) * supply_before + slack,
+        "share value dropped from {} / {} to {value_after} / {}",
+        before.total_value_b,
+        before.share_supply,
+        after.share_supply,
+    );
+}
@@ -0,0 +1,658 @@
+//! A Whirlpool vault in the native test harness, and the instructions the
+//! fuzzer sends it.
+//!
+//! The pool is set up with the mock Whirlpool's own instructions and the vault
+//! with `initialize_vault`, as in the program's end-to-end tests.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use mock_whirlpool::state::{TickArray, Whirlpool, TICK_ARRAY_SIZE};
+use native_svm::Harness;
+use vault_manager::dex::Dex;
+use vault_manager::nav::VaultNav;
+use vault_manager::protocol_fee::CollectedFees;
+use vault_manager::{ProtocolConfig, Vault};
+
+pub const TICK_SPACING: u16 = 64;
+/// 0.3%, in hundredths of a basis point.
+const FEE_RATE: u16 = 3_000;
+/// Tick arrays are initialized from `-TICK_ARRAYS` to `TICK_ARRAYS` around tick 0.
+const TICK_ARRAYS: i32 = 4;
+/// Ticks positions may use, in spacings either side of tick 0.
+pub const MAX_SPACINGS: i32 = TICK_ARRAYS * TICK_ARRAY_SIZE;
+const LP_LIQUIDITY: u128 = 1 << 50;
+/// Token balance users start with, of each token.
+pub const USER_BALANCE: u64 = 1 << 40;
+
+/// A depositor's wallet and token accounts.
+#[derive(Clone)]
+pub struct User {
+    pub wallet: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub shares: Pubkey,
+}
+
+#[derive(Clone)]
+pub struct World {
+    pub h: Harness,
+    pub authority: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    pub whirlpool: Pubkey,
+    token_vault_a: Pubkey,
+    token_vault_b: Pubkey,
+    oracle: Pubkey,
+    pub vault: Pubkey,
+    pub vault_token_a: Pubkey,
+    pub vault_token_b: Pubkey,
+    pub share_mint: Pubkey,
+    pub position: Pubkey,
+    pub position_token_account: Pubkey,
+    pub treasury_token_a: Pubkey,
+    pub treasury_token_b: Pubkey,
+    pub users: Vec<User>,
+    /// Trades through the pool, with far more of each token than the users.
+    pub trader: User,
+}
+
+impl World {
+    /// Pool at tick 0 with LP liquidity over half the initialized ticks, a
+    /// vault over `[tick_lower, tick_upper)` whose protocol takes
+    /// `fee_share_bps` of fees, and `users` depositors.
+    pub fn new(tick_lower: i32, tick_upper: i32, fee_share_bps: u16, users: usize) -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_whirlpool::ID, mock_whirlpool::entry);
+        let authority = h.create_wallet();
+        let mint_a = h.create_mint(&authority, 6);
+        let mint_b = h.create_mint(&authority, 6);
+
+        let config = Pubkey::new_unique();
+        let (whirlpool, _) = Pubkey::find_program_address(
+            &[
+                b"whirlpool",
+                config.as_ref(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+                &TICK_SPACING.to_le_bytes(),
+            ],
+            &mock_whirlpool::ID,
+        );
+        let token_vault_a = h.create_token_account(&mint_a, &whirlpool, 0);
+        let token_vault_b = h.create_token_account(&mint_b, &whirlpool, 0);
+        let (oracle, _) =
+            Pubkey::find_program_address(&[b"oracle", whirlpool.as_ref()], &mock_whirlpool::ID);
+        let mut setup = vec![Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::InitializePool {
+                whirlpools_config: config,
+                token_mint_a: mint_a,
+                token_mint_b: mint_b,
+                funder: authority,
+                whirlpool,
+                token_vault_a,
+                token_vault_b,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_whirlpool::instruction::InitializePool {
+                tick_spacing: TICK_SPACING,
+                fee_rate: FEE_RATE,
+                initial_sqrt_price: 1 << 64,
+            }
+            .data(),
+        }];
+        let array_ticks = TICK_ARRAY_SIZE * i32::from(TICK_SPACING);
+        for start_tick_index in (-TICK_ARRAYS..=TICK_ARRAYS).map(|i| i * array_ticks) {
+            setup.push(Instruction {
+                program_id: mock_whirlpool::ID,
+                accounts: mock_whirlpool::accounts::InitializeTickArray {
+                    whirlpool,
+                    funder: authority,
+                    tick_array: tick_array(&whirlpool, start_tick_index),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: mock_whirlpool::instruction::InitializeTickArray { start_tick_index }.data(),
+            });
+        }
+        setup.push(Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::InitializeOracle {
+                whirlpool,
+                funder: authority,
+                oracle,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_whirlpool::instruction::InitializeOracle {}.data(),
+        });
+        h.process_transaction(&setup).unwrap();
+
+        let (vault, _) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, whirlpool.as_ref()],
+            &vault_manager::ID,
+        );
+        let (share_mint, _) = Pubkey::find_program_address(
+            &[vault_manager::SHARE_MINT_SEED, vault.as_ref()],
+            &vault_manager::ID,
+        );
+        let (protocol_config, protocol_bump) = Pubkey::find_program_address(
+            &[vault_manager::PROTOCOL_CONFIG_SEED],
+            &vault_manager::ID,
+        );
+        // `initialize_protocol_config` needs the program's upgrade authority
+        let treasury = h.create_wallet();
+        h.set_anchor_account(
+            protocol_config,
+            &ProtocolConfig {
+                admin: authority,
+                fee_share_bps,
+                treasury,
+                dex_programs: [
+                    mock_whirlpool::ID,
+                    Pubkey::default(),
+                    Pubkey::default(),
+                    Pubkey::default(),
+                ],
+                vault_count: 0,
+                bump: protocol_bump,
+            },
+        );
+        let position_mint = Pubkey::new_unique();
+        let mut world = Self {
+            authority,
+            mint_a,
+            mint_b,
+            whirlpool,
+            token_vault_a,
+            token_vault_b,
+            oracle,
+            vault,
+            vault_token_a: h.create_token_account(&mint_a, &vault, 0),
+            vault_token_b: h.create_token_account(&mint_b, &vault, 0),
+            share_mint,
+            position: Pubkey::find_program_address(
+                &[b"position", position_mint.as_ref()],
+                &mock_whirlpool::ID,
+            )
+            .0,
+            position_token_account: get_associated_token_address(&vault, &position_mint),
+            treasury_token_a: h.create_token_account(&mint_a, &treasury, 0),
+            treasury_token_b: h.create_token_account(&mint_b, &treasury, 0),
+            users: Vec::new(),
+            trader: User {
+                wallet: Pubkey::default(),
+                token_a: Pubkey::default(),
+                token_b: Pubkey::default(),
+                shares: Pubkey::default(),
+            },
+            h,
+        };
+
+        let lp = world.create_user(u64::MAX / 4);
+        let lp_ticks = MAX_SPACINGS / 2 * i32::from(TICK_SPACING);
+        let (lp_position, lp_nft) = world.open_position(lp.wallet, -lp_ticks, lp_ticks);
+        let increase = Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::ModifyLiquidity {
+                whirlpool,
+                token_program: spl_token::ID,
+                position_authority: lp.wallet,
+                position: lp_position,
+                position_token_account: lp_nft,
+                token_owner_account_a: lp.token_a,
+                token_owner_account_b: lp.token_b,
+                token_vault_a,
+                token_vault_b,
+                tick_array_lower: world.tick_array(-lp_ticks),
+                tick_array_upper: world.tick_array(lp_ticks),
+            }
+            .to_account_metas(None),
+            data: mock_whirlpool::instruction::IncreaseLiquidity {
+                liquidity_amount: LP_LIQUIDITY,
+                token_max_a: u64::MAX,
+                token_max_b: u64::MAX,
+            }
+            .data(),
+        };
+        world.h.process(&increase).unwrap();
+
+        let mut accounts = vault_manager::accounts::InitializeVault {
+            vault,
+            protocol_config,
+            vault_registry: Pubkey::find_program_address(
+                &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+                &vault_manager::ID,
+            )
+            .0,
+            pool: whirlpool,
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            vault_token_a: world.vault_token_a,
+            vault_token_b: world.vault_token_b,
+            share_mint,
+            position: world.position,
+            position_mint,
+            position_token_account: world.position_token_account,
+            pool_token_vault_a: token_vault_a,
+            pool_token_vault_b: token_vault_b,
+            authority,
+            payer: authority,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            dex_program: mock_whirlpool::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+        }
+        .to_account_metas(None);
+        // The new NFT mint signs for its creation
+        signer(&mut accounts, &position_mint);
+        let initialize = Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::InitializeVault {
+                dex: Dex::Whirlpool,
+                tick_lower,
+                tick_upper,
+            }
+            .data(),
+        };
+        world.h.process(&initialize).unwrap();
+
+        world.trader = world.create_user(u64::MAX / 4);
+        world.users = (0..users)
+            .map(|_| world.create_user(USER_BALANCE))
+            .collect();
+        world
+    }
+
+    /// Wallet with `balance` of each token and an empty share account.
+    fn create_user(&mut self, balance: u64) -> User {
+        let wallet = self.h.create_wallet();
+        User {
+            wallet,
+            token_a: self.h.create_token_account(&self.mint_a, &wallet, balance),
+            token_b: self.h.create_token_account(&self.mint_b, &wallet, balance),
+            shares: self.h.create_token_account(&self.share_mint, &wallet, 0),
+        }
+    }
+
+    pub fn pool(&self) -> Whirlpool {
+        self.h.anchor_account(&self.whirlpool)
+    }
+
+    pub fn vault_state(&self) -> Vault {
+        self.h.anchor_account(&self.vault)
+    }
+
+    /// Opens a position owned by `owner` over `[tick_lower, tick_upper)`.
+    /// Returns the position and its NFT account.
+    pub fn open_position(
+        &mut self,
+        owner: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> (Pubkey, Pubkey) {
+        let mint = Pubkey::new_unique();
+        let (position, position_bump) =
+            Pubkey::find_program_address(&[b"position", mint.as_ref()], &mock_whirlpool::ID);
+        let nft_account = get_associated_token_address(&owner, &mint);
+        let mut accounts = mock_whirlpool::accounts::OpenPosition {
+            funder: self.authority,
+            owner,
+            position,
+            position_mint: mint,
+            position_token_account: nft_account,
+            whirlpool: self.whirlpool,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &mint);
+        let open = Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts,
+            data: mock_whirlpool::instruction::OpenPosition {
+                _bumps: mock_whirlpool::PositionBumps { position_bump },
+                tick_lower_index: tick_lower,
+                tick_upper_index: tick_upper,
+            }
+            .data(),
+        };
+        self.h.process(&open).unwrap();
+        (position, nft_account)
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        tick_array(
+            &self.whirlpool,
+            TickArray::start_index_of(tick_index, TICK_SPACING),
+        )
+    }
+
+    fn protocol_config(&self) -> Pubkey {
+        Pubkey::find_program_address(&[vault_manager::PROTOCOL_CONFIG_SEED], &vault_manager::ID).0
+    }
+
+    fn user_position(&self, wallet: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::USER_POSITION_SEED,
+                self.vault.as_ref(),
+                wallet.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    /// Account set of `position`, whose NFT is held in `nft_account`.
+    fn position_metas(&self, position: Pubkey, nft_account: Pubkey) -> Vec<AccountMeta> {
+        let state: mock_whirlpool::state::Position = self.h.anchor_account(&position);
+        self.range_metas(nft_account, state.tick_lower_index, state.tick_upper_index)
+    }
+
+    /// Account set of a position over `[tick_lower, tick_upper)`.
+    fn range_metas(
+        &self,
+        nft_account: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(nft_account, false),
+            AccountMeta::new(self.tick_array(tick_lower), false),
+            AccountMeta::new(self.tick_array(tick_upper), false),
+        ]
+    }
+
+    /// The swap account set, for a swap in direction `a_to_b` from the current price.
+    fn swap_metas(&self, a_to_b: bool) -> Vec<AccountMeta> {
+        let step = i32::from(TICK_SPACING) * TICK_ARRAY_SIZE;
+        let step = if a_to_b { -step } else { step };
+        let current = self.pool().tick_current_index;
+        let mut metas: Vec<_> = (0..3)
+            .map(|i| AccountMeta::new(self.tick_array(current + i * step), false))
+            .collect();
+        metas.push(AccountMeta::new(self.oracle, false));
+        metas
+    }
+
+    /// NAV as `get_nav` returns it.
+    pub fn nav(&mut self) -> std::result::Result<VaultNav, ProgramError> {
+        let accounts = vault_manager::accounts::GetNav {
+            vault: self.vault,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+        };
+        let get_nav = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(self.position, self.position_token_account),
+            ]
+            .concat(),
+            data: vault_manager::instruction::GetNav {}.data(),
+        };
+        self.h.process(&get_nav)?;
+        let (_, data) = self.h.return_data().unwrap();
+        Ok(VaultNav::try_from_slice(data).unwrap())
+    }
+
+    pub fn deposit(
+        &mut self,
+        user: usize,
+        deposit_a: bool,
+        amount: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let user = &self.users[user];
+        let accounts = vault_manager::accounts::DepositAndAddLiquidity {
+            vault: self.vault,
+            user: user.wallet,
+            user_token_account: if deposit_a {
+                user.token_a
+            } else {
+                user.token_b
+            },
+            user_share_account: user.shares,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            allowlist_entry: None,
+            user_position: self.user_position(&user.wallet),
+            system_program: system_program::ID,
+        };
+        let deposit = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(self.position, self.position_token_account),
+                self.swap_metas(deposit_a),
+            ]
+            .concat(),
+            data: vault_manager::instruction::DepositAndAddLiquidity {
+                amount,
+                min_swap_out: 0,
+                min_shares_out: 0,
+            }
+            .data(),
+        };
+        self.h.process(&deposit)
+    }
+
+    pub fn withdraw(&mut self, user: usize, shares: u64) -> std::result::Result<(), ProgramError> {
+        let user = &self.users[user];
+        let accounts = vault_manager::accounts::Withdraw {
+            vault: self.vault,
+            user: user.wallet,
+            user_share_account: user.shares,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            user_position: self.user_position(&user.wallet),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        let withdraw = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(self.position, self.position_token_account),
+            ]
+            .concat(),
+            data: vault_manager::instruction::Withdraw {
+                shares,
+                min_amount_a: 0,
+                min_amount_b: 0,
+            }
+            .data(),
+        };
+        self.h.process(&withdraw)
+    }
+
+    /// Rebalances into a new vault position over `[tick_lower, tick_upper)`,
+    /// swapping toward it in direction `a_to_b`.
+    pub fn rebalance(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        a_to_b: bool,
+    ) -> std::result::Result<(), ProgramError> {
+        let new_position_mint = Pubkey::new_unique();
+        let (new_position, _) = Pubkey::find_program_address(
+            &[b"position", new_position_mint.as_ref()],
+            &mock_whirlpool::ID,
+        );
+        let nft_account = get_associated_token_address(&self.vault, &new_position_mint);
+        let mut accounts = vault_manager::accounts::Rebalance {
+            vault: self.vault,
+            authority: self.authority,
+            payer: self.authority,
+            pool: self.whirlpool,
+            position: self.position,
+            share_mint: self.share_mint,
+            new_position,
+            new_position_mint,
+            new_position_token_account: nft_account,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            dex_program: mock_whirlpool::ID,
+            protocol_config: self.protocol_config(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &new_position_mint);
+        let position: mock_whirlpool::state::Position = self.h.anchor_account(&self.position);
+        let mut position_metas = self.position_metas(self.position, self.position_token_account);
+        // Closing burns the NFT
+        position_metas[0].is_writable = true;
+        let rebalance = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts,
+                position_metas,
+                self.swap_metas(a_to_b),
+                self.range_metas(nft_account, tick_lower, tick_upper),
+                vec![AccountMeta::new(position.position_mint, false)],
+            ]
+            .concat(),
+            data: vault_manager::instruction::Rebalance {
+                tick_lower,
+                tick_upper,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                min_swap_out: 0,
+            }
+            .data(),
+        };
+        self.h.process(&rebalance)?;
+        self.position = new_position;
+        self.position_token_account = nft_account;
+        Ok(())
+    }
+
+    pub fn collect(&mut self) -> std::result::Result<CollectedFees, ProgramError> {
+        let accounts = vault_manager::accounts::Collect {
+            vault: self.vault,
+            authority: self.authority,
+            pool: self.whirlpool,
+            position: self.position,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            protocol_config: self.protocol_config(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        let collect = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(self.position, self.position_token_account),
+            ]
+            .concat(),
+            data: vault_manager::instruction::Collect {}.data(),
+        };
+        self.h.process(&collect)?;
+        let (_, data) = self.h.return_data().unwrap();
+        Ok(CollectedFees::try_from_slice(data).unwrap())
+    }
+
+    /// Exact-input swap of `amount` by the trader directly through the pool.
+    pub fn swap(&mut self, a_to_b: bool, amount: u64) -> std::result::Result<(), ProgramError> {
+        let tick_arrays = self.swap_metas(a_to_b);
+        let accounts = mock_whirlpool::accounts::Swap {
+            token_program: spl_token::ID,
+            token_authority: self.trader.wallet,
+            whirlpool: self.whirlpool,
+            token_owner_account_a: self.trader.token_a,
+            token_vault_a: self.token_vault_a,
+            token_owner_account_b: self.trader.token_b,
+            token_vault_b: self.token_vault_b,
+            tick_array_0: tick_arrays[0].pubkey,
+            tick_array_1: tick_arrays[1].pubkey,
+            tick_array_2: tick_arrays[2].pubkey,
+            oracle: self.oracle,
+        };
+        let sqrt_price_limit = if a_to_b {
+            mock_whirlpool::math::MIN_SQRT_PRICE_X64
+        } else {
+            mock_whirlpool::math::MAX_SQRT_PRICE_X64
+        };
+        let swap = Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: accounts.to_account_metas(None),
+            data: mock_whirlpool::instruction::Swap {
+                amount,
+                other_amount_threshold: 0,
+                sqrt_price_limit,
+                amount_specified_is_input: true,
+                a_to_b,
+            }
+            .data(),
+        };
+        self.h.process(&swap)
+    }
+}
+
+fn tick_array(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            whirlpool.as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        &mock_whirlpool::ID,
+    )
+    .0
+}
+
+fn signer(metas: &mut [AccountMeta], key: &Pubkey) {
+    for meta in metas.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = true;
+    }
+}
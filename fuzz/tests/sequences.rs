@@ -0,0 +1,163 @@
+//! Runs the fuzz target's scenarios without libFuzzer: a fixed stream of
+//! pseudo-random inputs, then hand-written sequences for known edge cases.
+
+use arbitrary::Unstructured;
+use vault_manager_fuzz::world::USER_BALANCE;
+use vault_manager_fuzz::{apply, check, Amount, Op, Range, Scenario};
+
+/// xorshift64*, so every run covers the same inputs.
+struct Bytes(u64);
+
+impl Bytes {
+    fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(8) {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            let word = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d).to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+}
+
+/// Scenario of `len` operations. Derived `Vec`s stop on a coin flip, which
+/// leaves uniform bytes with one operation on average.
+fn scenario(u: &mut Unstructured, len: usize) -> arbitrary::Result<Scenario> {
+    Ok(Scenario {
+        fee_share_bps: u.arbitrary()?,
+        range: u.arbitrary()?,
+        ops: (0..len)
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<_>>()?,
+    })
+}
+
+#[test]
+fn random_sequences_hold_the_invariants() {
+    let mut bytes = Bytes(0x9e37_79b9_7f4a_7c15);
+    let mut buffer = vec![0u8; 1_024];
+    let mut succeeded = [0; 5];
+    for _ in 0..100 {
+        bytes.fill(&mut buffer);
+        let Ok(scenario) = scenario(&mut Unstructured::new(&buffer), 32) else {
+            continue;
+        };
+        let mut world = scenario.world();
+        for op in &scenario.ops {
+            if apply(&mut world, op).is_ok() {
+                let kind = match op {
+                    Op::Deposit { .. } => 0,
+                    Op::Withdraw { .. } => 1,
+                    Op::Rebalance { .. } => 2,
+                    Op::Collect => 3,
+                    Op::Swap { .. } => 4,
+                };
+                succeeded[kind] += 1;
+            }
+            check(&mut world);
+        }
+    }
+    // The stream reaches every instruction, not just its failures
+    assert!(succeeded.iter().all(|&count| count > 0), "{succeeded:?}");
+}
+
+fn deposit(user: u8, token_a: bool, amount: u64) -> Op {
+    Op::Deposit {
+        user,
+        token_a,
+        amount: Amount(amount),
+    }
+}
+
+fn swap(a_to_b: bool, amount: u64) -> Op {
+    Op::Swap {
+        a_to_b,
+        amount: Amount(amount),
+    }
+}
+
+/// Runs `ops` on a vault over ticks [-640, 640), each of which must succeed.
+fn run_all(fee_share_bps: u16, ops: &[Op]) -> vault_manager_fuzz::world::World {
+    let scenario = Scenario {
+        fee_share_bps,
+        range: Range {
+            lower: -10,
+            width: 19,
+        },
+        ops: ops.to_vec(),
+    };
+    let mut world = scenario.world();
+    for op in ops {
+        apply(&mut world, op).unwrap_or_else(|err| panic!("{op:?} failed: {err}"));
+        check(&mut world);
+    }
+    world
+}
+
+#[test]
+fn fees_are_shared_by_the_holders_who_earned_them() {
+    let world = run_all(
+        1_000,
+        &[
+            deposit(0, true, 1_000_000_000),
+            swap(true, 50_000_000_000),
+            swap(false, 50_000_000_000),
+            deposit(1, false, 1_000_000_000),
+            swap(false, 10_000_000_000),
+            Op::Collect,
+            Op::Withdraw {
+                user: 1,
+                fraction: u16::MAX,
+            },
+            Op::Rebalance {
+                range: Range { lower: 0, width: 8 },
+                a_to_b: true,
+            },
+            Op::Withdraw {
+                user: 0,
+                fraction: u16::MAX / 3,
+            },
+            Op::Withdraw {
+                user: 0,
+                fraction: u16::MAX,
+            },
+        ],
+    );
+    // The protocol took its share of the fees on the way
+    assert!(world.h.token_balance(&world.treasury_token_a) > 0);
+}
+
+#[test]
+fn pool_zap_cost_is_the_depositors() {
+    // A deposit of every token the user has swaps half of it through the pool
+    run_all(
+        0,
+        &[
+            deposit(0, false, 1_000_000),
+            deposit(1, false, USER_BALANCE),
+            deposit(2, true, USER_BALANCE),
+        ],
+    );
+}
+
+#[test]
+fn full_withdrawal_empties_the_vault() {
+    let mut world = run_all(
+        0,
+        &[
+            deposit(2, true, 123_456_789),
+            deposit(3, false, 987_654_321),
+            Op::Withdraw {
+                user: 2,
+                fraction: u16::MAX,
+            },
+            Op::Withdraw {
+                user: 3,
+                fraction: u16::MAX,
+            },
+        ],
+    );
+    assert_eq!(world.h.mint_supply(&world.share_mint), 0);
+    let nav = world.nav().unwrap();
+    assert_eq!((nav.total_a, nav.total_b), (0, 0));
+}
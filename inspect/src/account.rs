@@ -0,0 +1,34 @@
+//! Account data decoded by the IDL of its owner.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{InspectError, Result};
+use crate::idl::Programs;
+use crate::value::read_defined;
+
+#[derive(Debug, Serialize)]
+pub struct DecodedAccount {
+    pub owner: String,
+    pub program_name: String,
+    pub name: String,
+    pub data: Value,
+}
+
+/// Decodes the data of an account owned by `owner`. Bytes after the account
+/// type are ignored, as accounts are often allocated with room to grow.
+pub fn decode_account(programs: &Programs, owner: &str, data: &[u8]) -> Result<DecodedAccount> {
+    let idl = programs
+        .get(owner)
+        .ok_or_else(|| InspectError::Unsupported(format!("accounts of program {owner}")))?;
+    let account = idl
+        .account(data)
+        .ok_or(InspectError::InvalidValue("account discriminator"))?;
+    let mut fields = &data[account.discriminator.len()..];
+    Ok(DecodedAccount {
+        owner: owner.to_string(),
+        program_name: idl.name().to_string(),
+        name: account.name.clone(),
+        data: read_defined(idl.types(), &account.name, &mut fields)?,
+    })
+}
@@ -0,0 +1,29 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InspectError {
+    #[error("data ends before {0} is complete")]
+    UnexpectedEnd(String),
+    #[error("{count} bytes left over after {name}")]
+    TrailingBytes { name: String, count: usize },
+    #[error("type `{0}` is not defined in the IDL")]
+    UnknownType(String),
+    #[error("variant {index} does not exist in enum `{name}`")]
+    InvalidVariant { name: String, index: u8 },
+    #[error("invalid {0} value")]
+    InvalidValue(&'static str),
+    #[error("{0} is not supported")]
+    Unsupported(String),
+    #[error("invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("building the IDL of {program} failed: {reason}")]
+    IdlBuild { program: String, reason: String },
+    #[error("the IDL build printed no {0} section")]
+    MissingIdlSection(&'static str),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, InspectError>;
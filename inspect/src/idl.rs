@@ -0,0 +1,239 @@
+//! Program IDLs: built from a program crate's `idl-build` feature, stored as
+//! JSON and looked up by program address and discriminator.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anchor_lang_idl::types::{Idl, IdlAccount, IdlErrorCode, IdlEvent, IdlInstruction, IdlTypeDef};
+use serde::Deserialize;
+
+use crate::error::{InspectError, Result};
+use crate::value::Types;
+
+/// An IDL with its type definitions indexed by name.
+pub struct ProgramIdl {
+    pub idl: Idl,
+    types: Types,
+}
+
+impl ProgramIdl {
+    pub fn new(idl: Idl) -> Self {
+        let types = idl
+            .types
+            .iter()
+            .map(|ty| (ty.name.clone(), ty.clone()))
+            .collect();
+        Self { idl, types }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let idl = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok(Self::new(idl))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.idl.metadata.name
+    }
+
+    pub fn types(&self) -> &Types {
+        &self.types
+    }
+
+    /// Instruction whose discriminator starts `data`.
+    pub fn instruction(&self, data: &[u8]) -> Option<&IdlInstruction> {
+        self.idl
+            .instructions
+            .iter()
+            .find(|ix| starts_with(data, &ix.discriminator))
+    }
+
+    /// Account type whose discriminator starts `data`.
+    pub fn account(&self, data: &[u8]) -> Option<&IdlAccount> {
+        self.idl
+            .accounts
+            .iter()
+            .find(|account| starts_with(data, &account.discriminator))
+    }
+
+    /// Event whose discriminator starts `data`.
+    pub fn event(&self, data: &[u8]) -> Option<&IdlEvent> {
+        self.idl
+            .events
+            .iter()
+            .find(|event| starts_with(data, &event.discriminator))
+    }
+
+    pub fn error(&self, code: u32) -> Option<&IdlErrorCode> {
+        self.idl.errors.iter().find(|error| error.code == code)
+    }
+}
+
+fn starts_with(data: &[u8], discriminator: &[u8]) -> bool {
+    !discriminator.is_empty() && data.starts_with(discriminator)
+}
+
+/// IDLs by program address.
+#[derive(Default)]
+pub struct Programs {
+    by_address: HashMap<String, ProgramIdl>,
+}
+
+impl Programs {
+    pub fn new(idls: impl IntoIterator<Item = ProgramIdl>) -> Self {
+        Self {
+            by_address: idls
+                .into_iter()
+                .map(|idl| (idl.idl.address.clone(), idl))
+                .collect(),
+        }
+    }
+
+    /// Every `*.json` IDL in `dir`, as `anchor build` leaves them in `target/idl`.
+    pub fn read_dir(dir: &Path) -> Result<Self> {
+        let mut idls = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                idls.push(ProgramIdl::read(&path)?);
+            }
+        }
+        Ok(Self::new(idls))
+    }
+
+    pub fn get(&self, address: &str) -> Option<&ProgramIdl> {
+        self.by_address.get(address)
+    }
+}
+
+/// Builds the IDL of the Anchor program crate at `program_path`.
+///
+/// Runs the crate's `idl-build` print tests, as `anchor idl build` does, but on
+/// the pinned stable toolchain rather than nightly. Docs are kept and module
+/// paths are dropped from type names that stay unique without them.
+pub fn build(program_path: &Path) -> Result<Idl> {
+    let output = Command::new("cargo")
+        .args([
+            "test",
+            "__anchor_private_print_idl",
+            "--features",
+            "idl-build",
+            "--lib",
+            "--",
+            "--show-output",
+            "--quiet",
+        ])
+        .env("ANCHOR_IDL_BUILD_PROGRAM_PATH", program_path)
+        .env("ANCHOR_IDL_BUILD_RESOLUTION", "TRUE")
+        .env("ANCHOR_IDL_BUILD_SKIP_LINT", "FALSE")
+        .env("ANCHOR_IDL_BUILD_NO_DOCS", "FALSE")
+        .current_dir(program_path)
+        .output()?;
+    let program = program_path.display().to_string();
+    if !output.status.success() {
+        return Err(InspectError::IdlBuild {
+            program,
+            reason: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    let idl = parse_build_output(&String::from_utf8_lossy(&output.stdout)).map_err(|err| {
+        InspectError::IdlBuild {
+            program,
+            reason: err.to_string(),
+        }
+    })?;
+    Ok(sort(shorten_type_paths(idl)?))
+}
+
+/// Assembles the IDL from the sections the print tests write to stdout.
+fn parse_build_output(stdout: &str) -> Result<Idl> {
+    #[derive(Deserialize)]
+    struct EventSection {
+        event: IdlEvent,
+        types: Vec<IdlTypeDef>,
+    }
+
+    let mut test = "";
+    let mut section: Option<(&str, Vec<&str>)> = None;
+    let mut address = None;
+    let mut program: Option<Idl> = None;
+    let mut types = BTreeMap::new();
+    let mut idl_events = Vec::new();
+    let mut idl_errors = Vec::new();
+    let mut constants = Vec::new();
+
+    for line in stdout.lines() {
+        if let Some((name, lines)) = &mut section {
+            if line != format!("--- IDL end {name} ---") {
+                lines.push(line);
+                continue;
+            }
+            let json = lines.join("\n");
+            match *name {
+                // Declared programs print addresses too; only the crate root's is ours
+                "address" if test == "__anchor_private_print_idl_address" => {
+                    let quoted: String = serde_json::from_str(&json)?;
+                    address = Some(quoted.trim_matches('"').to_string());
+                }
+                "const" => constants.push(serde_json::from_str(&json)?),
+                "event" => {
+                    let event: EventSection = serde_json::from_str(&json)?;
+                    idl_events.push(event.event);
+                    types.extend(event.types.into_iter().map(|ty| (ty.name.clone(), ty)));
+                }
+                "errors" => idl_errors.extend(serde_json::from_str::<Vec<IdlErrorCode>>(&json)?),
+                "program" => program = Some(serde_json::from_str(&json)?),
+                _ => {}
+            }
+            section = None;
+        } else if let Some(name) = line
+            .strip_prefix("--- IDL begin ")
+            .and_then(|rest| rest.strip_suffix(" ---"))
+        {
+            section = Some((name, Vec::new()));
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            test = name;
+        }
+    }
+
+    let mut idl = program.ok_or(InspectError::MissingIdlSection("program"))?;
+    idl.address = address.ok_or(InspectError::MissingIdlSection("address"))?;
+    idl.constants = constants;
+    idl.events = idl_events;
+    idl.errors = idl_errors;
+    types.extend(idl.types.drain(..).map(|ty| (ty.name.clone(), ty)));
+    idl.types = types.into_values().collect();
+    Ok(idl)
+}
+
+/// Replaces `module::path::Name` with `Name` wherever no other type shares the name.
+fn shorten_type_paths(idl: Idl) -> Result<Idl> {
+    let mut json = serde_json::to_string(&idl)?;
+    let short_name = |name: &str| name.rsplit("::").next().unwrap_or(name).to_string();
+    for ty in &idl.types {
+        let short = short_name(&ty.name);
+        let unique = idl
+            .types
+            .iter()
+            .filter(|other| short_name(&other.name) == short)
+            .count()
+            == 1;
+        if ty.name.contains("::") && unique {
+            json = json.replace(&format!("\"{}\"", ty.name), &format!("\"{short}\""));
+        }
+    }
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn sort(mut idl: Idl) -> Idl {
+    idl.accounts.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.constants.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.events.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.instructions.sort_by(|a, b| a.name.cmp(&b.name));
+    idl.types.sort_by(|a, b| a.name.cmp(&b.name));
+    idl
+}
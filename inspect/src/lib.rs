@@ -0,0 +1,19 @@
+//! Decodes vault_manager transactions and accounts into readable JSON using
+//! program IDLs.
+//!
+//! IDLs come from each program crate's `idl-build` feature, either as `anchor
+//! build` leaves them in `target/idl` or built with [`idl::build`]. The mock
+//! programs declare the IDs of the programs they stand in for, so their IDLs
+//! also decode the vault's Whirlpool and DLMM CPIs.
+
+pub mod account;
+pub mod error;
+pub mod idl;
+pub mod logs;
+pub mod transaction;
+pub mod value;
+
+pub use account::{decode_account, DecodedAccount};
+pub use error::{InspectError, Result};
+pub use idl::{ProgramIdl, Programs};
+pub use transaction::{decode_transaction, DecodedTransaction, RpcTransaction};
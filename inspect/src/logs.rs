@@ -0,0 +1,113 @@
+//! Transaction log messages: which program ran when, the events they emitted
+//! and where execution failed.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// `Program data:` payload, attributed to the program running at the time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogData {
+    pub program: String,
+    /// Top-level instruction the program ran under.
+    pub instruction: usize,
+    pub data: Vec<u8>,
+}
+
+/// Error an Anchor program logged before failing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnchorErrorLog {
+    pub program: String,
+    /// Account or source location the error names, if any.
+    pub origin: Option<String>,
+    pub name: String,
+    pub code: u32,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParsedLogs {
+    pub data: Vec<LogData>,
+    /// Innermost program that failed, where the error came from.
+    pub failed_program: Option<String>,
+    pub anchor_error: Option<AnchorErrorLog>,
+}
+
+pub fn parse(logs: &[String]) -> ParsedLogs {
+    let mut parsed = ParsedLogs::default();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut instruction = None;
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(payload) = rest.strip_prefix("data: ") {
+            let (Some(program), Some(instruction)) = (stack.last(), instruction) else {
+                continue;
+            };
+            // Several chunks are logged space separated, and concatenate
+            let data = payload
+                .split(' ')
+                .map(|chunk| STANDARD.decode(chunk))
+                .collect::<Result<Vec<_>, _>>();
+            if let Ok(data) = data {
+                parsed.data.push(LogData {
+                    program: program.to_string(),
+                    instruction,
+                    data: data.concat(),
+                });
+            }
+        } else if let Some(message) = rest.strip_prefix("log: ") {
+            if let (Some(program), Some(error)) = (stack.last(), parse_anchor_error(message)) {
+                parsed.anchor_error.get_or_insert(AnchorErrorLog {
+                    program: program.to_string(),
+                    ..error
+                });
+            }
+        } else if let Some((program, depth)) = parse_invoke(rest) {
+            if depth == 1 {
+                instruction = Some(instruction.map_or(0, |index| index + 1));
+            }
+            stack.truncate(depth - 1);
+            stack.push(program);
+        } else if let Some((program, _)) = rest.split_once(" success") {
+            if stack.last() == Some(&program) {
+                stack.pop();
+            }
+        } else if let Some((program, _)) = rest.split_once(" failed: ") {
+            parsed
+                .failed_program
+                .get_or_insert_with(|| program.to_string());
+            if stack.last() == Some(&program) {
+                stack.pop();
+            }
+        }
+    }
+    parsed
+}
+
+/// `<program> invoke [<depth>]`
+fn parse_invoke(line: &str) -> Option<(&str, usize)> {
+    let (program, depth) = line.split_once(" invoke [")?;
+    let depth = depth.strip_suffix(']')?.parse().ok()?;
+    (depth > 0).then_some((program, depth))
+}
+
+/// `AnchorError <origin>. Error Code: <name>. Error Number: <code>. Error Message: <message>.`
+fn parse_anchor_error(message: &str) -> Option<AnchorErrorLog> {
+    let rest = message.strip_prefix("AnchorError ")?;
+    let (origin, rest) = rest.split_once(". Error Code: ")?;
+    let (name, rest) = rest.split_once(". Error Number: ")?;
+    let (code, message) = rest.split_once(". Error Message: ")?;
+    let origin = origin
+        .strip_prefix("caused by account: ")
+        .or_else(|| origin.strip_prefix("thrown in "))
+        .map(str::to_string);
+    Some(AnchorErrorLog {
+        program: String::new(),
+        origin,
+        name: name.to_string(),
+        code: code.parse().ok()?,
+        message: message.strip_suffix('.').unwrap_or(message).to_string(),
+    })
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use vault_inspect::{
+    decode_account, decode_transaction, idl, InspectError, Programs, Result, RpcTransaction,
+};
+
+/// Decodes vault_manager transactions and accounts with program IDLs.
+#[derive(Parser)]
+#[command(name = "vault-inspect")]
+struct Cli {
+    /// Directory of IDL JSON files to decode with.
+    #[arg(long, global = true, default_value = "target/idl")]
+    idl_dir: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decode a `getTransaction` response, fetched with `"encoding": "json"`.
+    Decode {
+        /// File holding the transaction, `-` for stdin.
+        input: PathBuf,
+    },
+    /// Decode an account as `solana account --output json` prints it.
+    Account {
+        /// File holding the account, `-` for stdin.
+        input: PathBuf,
+    },
+    /// Build the IDL of an Anchor program crate from its `idl-build` feature.
+    Idl {
+        /// Directory of the program crate.
+        program: PathBuf,
+        /// Where to write the IDL. Defaults to `<idl dir>/<program name>.json`.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Name the IDL after the program a mock stands in for. The mock's own
+        /// error codes are left out, as they differ from the real program's.
+        #[arg(long = "as")]
+        stand_in_for: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct AccountJson {
+    account: AccountJsonData,
+}
+
+#[derive(Deserialize)]
+struct AccountJsonData {
+    /// Base64 data and its encoding.
+    data: (String, String),
+    owner: String,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Command::Decode { input } => {
+            let programs = Programs::read_dir(&cli.idl_dir)?;
+            let tx = RpcTransaction::from_json(&read_input(&input)?)?;
+            print(&decode_transaction(&programs, &tx)?)
+        }
+        Command::Account { input } => {
+            let programs = Programs::read_dir(&cli.idl_dir)?;
+            let account: AccountJson = serde_json::from_str(&read_input(&input)?)?;
+            let (data, encoding) = &account.account.data;
+            if encoding != "base64" {
+                return Err(InspectError::Unsupported(format!(
+                    "{encoding} account data"
+                )));
+            }
+            let data = STANDARD
+                .decode(data)
+                .map_err(|_| InspectError::InvalidValue("base64 account data"))?;
+            print(&decode_account(&programs, &account.account.owner, &data)?)
+        }
+        Command::Idl {
+            program,
+            out,
+            stand_in_for,
+        } => {
+            let mut idl = idl::build(&program)?;
+            if let Some(name) = stand_in_for {
+                idl.metadata.name = name;
+                idl.errors.clear();
+            }
+            let out =
+                out.unwrap_or_else(|| cli.idl_dir.join(format!("{}.json", idl.metadata.name)));
+            if let Some(dir) = out.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&out, serde_json::to_string_pretty(&idl)? + "\n")?;
+            eprintln!("Wrote {}", out.display());
+            Ok(())
+        }
+    }
+}
+
+fn read_input(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
+fn print(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
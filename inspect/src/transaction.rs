@@ -0,0 +1,446 @@
+//! Transactions as the `getTransaction` RPC method returns them with `"json"`
+//! encoding, decoded instruction by instruction.
+
+use anchor_lang_idl::types::{IdlInstruction, IdlInstructionAccountItem};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{InspectError, Result};
+use crate::idl::Programs;
+use crate::logs;
+use crate::value::{read_defined, read_fields};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransaction {
+    pub slot: Option<u64>,
+    pub transaction: RpcTransactionBody,
+    pub meta: Option<RpcMeta>,
+}
+
+#[derive(Deserialize)]
+pub struct RpcTransactionBody {
+    pub signatures: Vec<String>,
+    pub message: RpcMessage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcMessage {
+    pub header: RpcHeader,
+    pub account_keys: Vec<String>,
+    pub instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHeader {
+    pub num_required_signatures: usize,
+    pub num_readonly_signed_accounts: usize,
+    pub num_readonly_unsigned_accounts: usize,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcInstruction {
+    pub program_id_index: usize,
+    pub accounts: Vec<usize>,
+    /// Base58 instruction data.
+    pub data: String,
+    pub stack_height: Option<usize>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcMeta {
+    pub err: Option<Value>,
+    #[serde(default)]
+    pub log_messages: Option<Vec<String>>,
+    #[serde(default)]
+    pub inner_instructions: Option<Vec<RpcInnerInstructions>>,
+    #[serde(default)]
+    pub loaded_addresses: Option<RpcLoadedAddresses>,
+}
+
+#[derive(Deserialize)]
+pub struct RpcInnerInstructions {
+    pub index: usize,
+    pub instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct RpcLoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+impl RpcTransaction {
+    /// Parses a transaction, or a whole JSON-RPC response carrying one.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut value: Value = serde_json::from_str(json)?;
+        if let Some(result) = value.get_mut("result") {
+            value = result.take();
+        }
+        if value.is_null() {
+            return Err(InspectError::InvalidTransaction(
+                "the RPC node returned no transaction".into(),
+            ));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedTransaction {
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    pub instructions: Vec<DecodedInstruction>,
+    pub events: Vec<DecodedEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<DecodedError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedInstruction {
+    pub program: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_name: Option<String>,
+    /// Instruction name, when the program's IDL knows the discriminator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Value>,
+    pub accounts: Vec<DecodedAccountMeta>,
+    /// Base58 data of instructions that could not be decoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decode_error: Option<String>,
+    /// Instructions this one invoked.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inner: Vec<DecodedInstruction>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedAccountMeta {
+    /// IDL name, absent for remaining accounts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub pubkey: String,
+    pub writable: bool,
+    pub signer: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedEvent {
+    /// Top-level instruction that emitted the event.
+    pub instruction: usize,
+    pub program: String,
+    pub name: String,
+    pub data: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<usize>,
+    /// Program the error came from, which may be a CPI target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Account or source location an Anchor error names.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// The error as the RPC node reported it.
+    pub raw: Value,
+}
+
+/// Decodes every instruction, inner instruction, event and the error of `tx`
+/// with the IDLs in `programs`. Parts no IDL covers are left raw.
+pub fn decode_transaction(programs: &Programs, tx: &RpcTransaction) -> Result<DecodedTransaction> {
+    let meta = tx.meta.as_ref();
+    let keys = AccountKeys::new(tx, meta)?;
+    let logs = logs::parse(
+        meta.and_then(|meta| meta.log_messages.as_deref())
+            .unwrap_or_default(),
+    );
+
+    let mut instructions = tx
+        .transaction
+        .message
+        .instructions
+        .iter()
+        .map(|ix| decode_instruction(programs, &keys, ix))
+        .collect::<Result<Vec<_>>>()?;
+    for inner in meta
+        .and_then(|meta| meta.inner_instructions.as_ref())
+        .into_iter()
+        .flatten()
+    {
+        let parent = instructions.get_mut(inner.index).ok_or_else(|| {
+            InspectError::InvalidTransaction(format!("no instruction {}", inner.index))
+        })?;
+        for ix in &inner.instructions {
+            let decoded = decode_instruction(programs, &keys, ix)?;
+            // Stack height 2 is a CPI of the top-level instruction
+            let depth = ix.stack_height.map_or(0, |height| height.saturating_sub(2));
+            push_at_depth(&mut parent.inner, depth, decoded);
+        }
+    }
+
+    let events = logs
+        .data
+        .iter()
+        .filter_map(|log| {
+            let idl = programs.get(&log.program)?;
+            let event = idl.event(&log.data)?;
+            let mut data = &log.data[event.discriminator.len()..];
+            let data = read_defined(idl.types(), &event.name, &mut data).ok()?;
+            Some(DecodedEvent {
+                instruction: log.instruction,
+                program: log.program.clone(),
+                name: event.name.clone(),
+                data,
+            })
+        })
+        .collect();
+
+    let error = meta
+        .and_then(|meta| meta.err.clone())
+        .map(|raw| decode_error(programs, tx, &logs, raw));
+
+    Ok(DecodedTransaction {
+        signature: tx.transaction.signatures.first().cloned(),
+        slot: tx.slot,
+        instructions,
+        events,
+        error,
+    })
+}
+
+/// Adds `ix` under the latest instruction `depth` levels below `siblings`.
+fn push_at_depth(siblings: &mut Vec<DecodedInstruction>, depth: usize, ix: DecodedInstruction) {
+    match siblings.last_mut() {
+        Some(last) if depth > 0 => push_at_depth(&mut last.inner, depth - 1, ix),
+        _ => siblings.push(ix),
+    }
+}
+
+/// Static keys followed by the ones loaded from lookup tables, writable first.
+struct AccountKeys<'a> {
+    keys: Vec<&'a str>,
+    signers: usize,
+    writable_signers: usize,
+    writable_unsigned_end: usize,
+    static_len: usize,
+    loaded_writable: usize,
+}
+
+impl<'a> AccountKeys<'a> {
+    fn new(tx: &'a RpcTransaction, meta: Option<&'a RpcMeta>) -> Result<Self> {
+        let message = &tx.transaction.message;
+        let header = &message.header;
+        let static_len = message.account_keys.len();
+        let loaded = meta.and_then(|meta| meta.loaded_addresses.as_ref());
+        let (writable, readonly) = loaded
+            .map(|loaded| (&loaded.writable[..], &loaded.readonly[..]))
+            .unwrap_or_default();
+        if header.num_required_signatures > static_len
+            || header.num_readonly_signed_accounts > header.num_required_signatures
+            || header.num_readonly_unsigned_accounts > static_len - header.num_required_signatures
+        {
+            return Err(InspectError::InvalidTransaction(
+                "message header does not fit its accounts".into(),
+            ));
+        }
+        Ok(Self {
+            keys: message
+                .account_keys
+                .iter()
+                .chain(writable)
+                .chain(readonly)
+                .map(String::as_str)
+                .collect(),
+            signers: header.num_required_signatures,
+            writable_signers: header.num_required_signatures - header.num_readonly_signed_accounts,
+            writable_unsigned_end: static_len - header.num_readonly_unsigned_accounts,
+            static_len,
+            loaded_writable: writable.len(),
+        })
+    }
+
+    fn get(&self, index: usize) -> Result<&'a str> {
+        self.keys
+            .get(index)
+            .copied()
+            .ok_or_else(|| InspectError::InvalidTransaction(format!("no account at index {index}")))
+    }
+
+    fn meta(&self, index: usize) -> Result<DecodedAccountMeta> {
+        let writable = if index < self.signers {
+            index < self.writable_signers
+        } else if index < self.static_len {
+            index < self.writable_unsigned_end
+        } else {
+            index < self.static_len + self.loaded_writable
+        };
+        Ok(DecodedAccountMeta {
+            name: None,
+            pubkey: self.get(index)?.to_string(),
+            writable,
+            signer: index < self.signers,
+        })
+    }
+}
+
+fn decode_instruction(
+    programs: &Programs,
+    keys: &AccountKeys,
+    ix: &RpcInstruction,
+) -> Result<DecodedInstruction> {
+    let program = keys.get(ix.program_id_index)?;
+    let mut accounts = ix
+        .accounts
+        .iter()
+        .map(|index| keys.meta(*index))
+        .collect::<Result<Vec<_>>>()?;
+    let mut decoded = DecodedInstruction {
+        program: program.to_string(),
+        program_name: None,
+        name: None,
+        args: None,
+        accounts: Vec::new(),
+        data: Some(ix.data.clone()),
+        decode_error: None,
+        inner: Vec::new(),
+    };
+    let data = bs58::decode(&ix.data)
+        .into_vec()
+        .map_err(|_| InspectError::InvalidTransaction("instruction data is not base58".into()))?;
+
+    if let Some(idl) = programs.get(program) {
+        decoded.program_name = Some(idl.name().to_string());
+        if let Some(instruction) = idl.instruction(&data) {
+            decoded.name = Some(instruction.name.clone());
+            for (account, name) in accounts.iter_mut().zip(account_names(instruction)) {
+                account.name = Some(name);
+            }
+            let mut args = &data[instruction.discriminator.len()..];
+            match read_fields(idl.types(), &instruction.args, &mut args) {
+                Ok(_) if !args.is_empty() => {
+                    decoded.decode_error = Some(
+                        InspectError::TrailingBytes {
+                            name: instruction.name.clone(),
+                            count: args.len(),
+                        }
+                        .to_string(),
+                    )
+                }
+                Ok(value) => {
+                    decoded.args = Some(value);
+                    decoded.data = None;
+                }
+                Err(err) => decoded.decode_error = Some(err.to_string()),
+            }
+        }
+    }
+    decoded.accounts = accounts;
+    Ok(decoded)
+}
+
+/// Account names in the order the instruction takes them, with the accounts of
+/// nested account structs as `outer.inner`.
+fn account_names(instruction: &IdlInstruction) -> Vec<String> {
+    fn flatten(items: &[IdlInstructionAccountItem], prefix: &str, names: &mut Vec<String>) {
+        for item in items {
+            match item {
+                IdlInstructionAccountItem::Single(account) => {
+                    names.push(format!("{prefix}{}", account.name))
+                }
+                IdlInstructionAccountItem::Composite(accounts) => {
+                    let prefix = format!("{prefix}{}.", accounts.name);
+                    flatten(&accounts.accounts, &prefix, names);
+                }
+            }
+        }
+    }
+
+    let mut names = Vec::new();
+    flatten(&instruction.accounts, "", &mut names);
+    names
+}
+
+/// Names the error of a failed transaction, from the failing program's logs
+/// or its IDL.
+fn decode_error(
+    programs: &Programs,
+    tx: &RpcTransaction,
+    logs: &logs::ParsedLogs,
+    raw: Value,
+) -> DecodedError {
+    let mut error = DecodedError {
+        instruction: None,
+        program: None,
+        code: None,
+        name: None,
+        message: None,
+        origin: None,
+        raw: raw.clone(),
+    };
+    let Some([index, cause]) = raw
+        .get("InstructionError")
+        .and_then(Value::as_array)
+        .and_then(|parts| <&[Value; 2]>::try_from(parts.as_slice()).ok())
+    else {
+        // Transaction-level errors such as `BlockhashNotFound`
+        error.name = raw.as_str().map(str::to_string);
+        return error;
+    };
+    error.instruction = index.as_u64().map(|index| index as usize);
+    error.program = logs.failed_program.clone().or_else(|| {
+        let ix = tx
+            .transaction
+            .message
+            .instructions
+            .get(error.instruction?)?;
+        tx.transaction
+            .message
+            .account_keys
+            .get(ix.program_id_index)
+            .cloned()
+    });
+
+    match cause.get("Custom").and_then(Value::as_u64) {
+        Some(code) => error.code = u32::try_from(code).ok(),
+        // Runtime errors such as `InvalidAccountData` are their own name
+        None => error.name = cause.as_str().map(str::to_string),
+    }
+    let Some(code) = error.code else {
+        return error;
+    };
+    match &logs.anchor_error {
+        Some(logged) if logged.code == code => {
+            error.program = Some(logged.program.clone());
+            error.name = Some(logged.name.clone());
+            error.message = Some(logged.message.clone());
+            error.origin = logged.origin.clone();
+        }
+        _ => {
+            let known = error
+                .program
+                .as_deref()
+                .and_then(|program| programs.get(program))
+                .and_then(|idl| idl.error(code));
+            if let Some(known) = known {
+                error.name = Some(known.name.clone());
+                error.message = known.msg.clone();
+            }
+        }
+    }
+    error
+}
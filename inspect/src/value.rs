@@ -0,0 +1,163 @@
+//! Borsh data read back as JSON, following the types of an IDL.
+//!
+//! Integers of up to 32 bits become JSON numbers. Wider ones become decimal
+//! strings, as most JSON readers round anything past 53 bits. Public keys are
+//! base58, `bytes` base64, unit enum variants their name and other variants
+//! `{"Variant": fields}`.
+
+use std::collections::HashMap;
+
+use anchor_lang_idl::types::{
+    IdlArrayLen, IdlDefinedFields, IdlField, IdlSerialization, IdlType, IdlTypeDef, IdlTypeDefTy,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Map, Value};
+
+use crate::error::{InspectError, Result};
+
+/// Type definitions of one IDL, by name.
+pub type Types = HashMap<String, IdlTypeDef>;
+
+/// Reads `fields` in order into an object, consuming them from the front of `data`.
+pub fn read_fields(types: &Types, fields: &[IdlField], data: &mut &[u8]) -> Result<Value> {
+    let mut object = Map::new();
+    for field in fields {
+        object.insert(field.name.clone(), read_value(types, &field.ty, data)?);
+    }
+    Ok(Value::Object(object))
+}
+
+/// Reads one value of `ty` from the front of `data`.
+pub fn read_value(types: &Types, ty: &IdlType, data: &mut &[u8]) -> Result<Value> {
+    let value = match ty {
+        IdlType::Bool => match take::<1>(data, "bool")? {
+            [0] => Value::Bool(false),
+            [1] => Value::Bool(true),
+            _ => return Err(InspectError::InvalidValue("bool")),
+        },
+        IdlType::U8 => u8::from_le_bytes(take(data, "u8")?).into(),
+        IdlType::I8 => i8::from_le_bytes(take(data, "i8")?).into(),
+        IdlType::U16 => u16::from_le_bytes(take(data, "u16")?).into(),
+        IdlType::I16 => i16::from_le_bytes(take(data, "i16")?).into(),
+        IdlType::U32 => u32::from_le_bytes(take(data, "u32")?).into(),
+        IdlType::I32 => i32::from_le_bytes(take(data, "i32")?).into(),
+        IdlType::F32 => f32::from_le_bytes(take(data, "f32")?).into(),
+        IdlType::F64 => f64::from_le_bytes(take(data, "f64")?).into(),
+        IdlType::U64 => u64::from_le_bytes(take(data, "u64")?).to_string().into(),
+        IdlType::I64 => i64::from_le_bytes(take(data, "i64")?).to_string().into(),
+        IdlType::U128 => u128::from_le_bytes(take(data, "u128")?).to_string().into(),
+        IdlType::I128 => i128::from_le_bytes(take(data, "i128")?).to_string().into(),
+        IdlType::U256 | IdlType::I256 => {
+            return Err(InspectError::Unsupported("256-bit integers".into()))
+        }
+        IdlType::Bytes => STANDARD.encode(take_vec(data, "bytes")?).into(),
+        IdlType::String => String::from_utf8(take_vec(data, "string")?)
+            .map_err(|_| InspectError::InvalidValue("string"))?
+            .into(),
+        IdlType::Pubkey => bs58::encode(take::<32>(data, "pubkey")?)
+            .into_string()
+            .into(),
+        IdlType::Option(inner) => match take::<1>(data, "option")? {
+            [0] => Value::Null,
+            [1] => read_value(types, inner, data)?,
+            _ => return Err(InspectError::InvalidValue("option")),
+        },
+        IdlType::Vec(inner) => {
+            let len = u32::from_le_bytes(take(data, "vec length")?);
+            let items = (0..len)
+                .map(|_| read_value(types, inner, data))
+                .collect::<Result<_>>()?;
+            Value::Array(items)
+        }
+        IdlType::Array(inner, IdlArrayLen::Value(len)) => {
+            let items = (0..*len)
+                .map(|_| read_value(types, inner, data))
+                .collect::<Result<_>>()?;
+            Value::Array(items)
+        }
+        IdlType::Array(_, IdlArrayLen::Generic(_)) | IdlType::Generic(_) => {
+            return Err(InspectError::Unsupported("generic types".into()))
+        }
+        IdlType::Defined { name, generics } => {
+            if !generics.is_empty() {
+                return Err(InspectError::Unsupported(format!("generic type `{name}`")));
+            }
+            read_defined(types, name, data)?
+        }
+        _ => return Err(InspectError::Unsupported(format!("IDL type {ty:?}"))),
+    };
+    Ok(value)
+}
+
+/// Reads a value of the type named `name`.
+pub fn read_defined(types: &Types, name: &str, data: &mut &[u8]) -> Result<Value> {
+    let def = types
+        .get(name)
+        .ok_or_else(|| InspectError::UnknownType(name.to_string()))?;
+    if def.serialization != IdlSerialization::Borsh {
+        return Err(InspectError::Unsupported(format!(
+            "{:?} serialization of `{name}`",
+            def.serialization
+        )));
+    }
+    match &def.ty {
+        IdlTypeDefTy::Struct { fields } => read_defined_fields(types, fields.as_ref(), data),
+        IdlTypeDefTy::Enum { variants } => {
+            let [index] = take::<1>(data, name)?;
+            let variant =
+                variants
+                    .get(index as usize)
+                    .ok_or_else(|| InspectError::InvalidVariant {
+                        name: name.to_string(),
+                        index,
+                    })?;
+            Ok(match &variant.fields {
+                None => Value::String(variant.name.clone()),
+                Some(fields) => {
+                    let mut object = Map::new();
+                    let value = read_defined_fields(types, Some(fields), data)?;
+                    object.insert(variant.name.clone(), value);
+                    Value::Object(object)
+                }
+            })
+        }
+        IdlTypeDefTy::Type { alias } => read_value(types, alias, data),
+    }
+}
+
+fn read_defined_fields(
+    types: &Types,
+    fields: Option<&IdlDefinedFields>,
+    data: &mut &[u8],
+) -> Result<Value> {
+    match fields {
+        None => Ok(Value::Object(Map::new())),
+        Some(IdlDefinedFields::Named(fields)) => read_fields(types, fields, data),
+        Some(IdlDefinedFields::Tuple(items)) => {
+            let items = items
+                .iter()
+                .map(|ty| read_value(types, ty, data))
+                .collect::<Result<_>>()?;
+            Ok(Value::Array(items))
+        }
+    }
+}
+
+fn take<const N: usize>(data: &mut &[u8], what: &str) -> Result<[u8; N]> {
+    let Some((head, rest)) = data.split_first_chunk::<N>() else {
+        return Err(InspectError::UnexpectedEnd(what.to_string()));
+    };
+    *data = rest;
+    Ok(*head)
+}
+
+fn take_vec(data: &mut &[u8], what: &str) -> Result<Vec<u8>> {
+    let len = u32::from_le_bytes(take(data, what)?) as usize;
+    if data.len() < len {
+        return Err(InspectError::UnexpectedEnd(what.to_string()));
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Ok(head.to_vec())
+}
@@ -0,0 +1,451 @@
+//! Transactions built from the programs' own instruction builders, decoded
+//! with the IDLs checked in under `tests/idl`.
+
+use std::path::Path;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{Discriminator, Event, InstructionData, ToAccountMetas};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use vault_inspect::{decode_account, decode_transaction, Programs, RpcTransaction};
+use vault_manager::dex::Dex;
+use vault_manager::governance::{ParamChange, ParamChangeQueued};
+use vault_manager::{Vault, VaultConfig, VaultError, VaultFees, VaultStrategy};
+
+fn programs() -> Programs {
+    Programs::read_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/idl")).unwrap()
+}
+
+/// A `getTransaction` response for `ix`, with `inner` as its CPIs at the
+/// given stack heights.
+struct TxBuilder {
+    ix: Instruction,
+    inner: Vec<(usize, Instruction)>,
+    logs: Vec<String>,
+    err: Value,
+}
+
+impl TxBuilder {
+    fn new(ix: Instruction) -> Self {
+        Self {
+            ix,
+            inner: Vec::new(),
+            logs: Vec::new(),
+            err: Value::Null,
+        }
+    }
+
+    fn inner(mut self, stack_height: usize, ix: Instruction) -> Self {
+        self.inner.push((stack_height, ix));
+        self
+    }
+
+    fn logs(mut self, logs: &[&str]) -> Self {
+        self.logs = logs.iter().map(|line| line.to_string()).collect();
+        self
+    }
+
+    fn err(mut self, err: Value) -> Self {
+        self.err = err;
+        self
+    }
+
+    /// Orders the keys as a legacy message does: writable signers, readonly
+    /// signers, writable and readonly unsigned accounts.
+    fn build(self) -> RpcTransaction {
+        let mut keys: Vec<(Pubkey, bool, bool)> = Vec::new();
+        let mut add = |pubkey: Pubkey, signer: bool, writable: bool| match keys
+            .iter_mut()
+            .find(|(key, _, _)| *key == pubkey)
+        {
+            Some((_, s, w)) => {
+                *s |= signer;
+                *w |= writable;
+            }
+            None => keys.push((pubkey, signer, writable)),
+        };
+        for ix in std::iter::once(&self.ix).chain(self.inner.iter().map(|(_, ix)| ix)) {
+            for meta in &ix.accounts {
+                add(meta.pubkey, meta.is_signer, meta.is_writable);
+            }
+            add(ix.program_id, false, false);
+        }
+        keys.sort_by_key(|(_, signer, writable)| (!signer, !writable));
+
+        let index = |pubkey: &Pubkey| keys.iter().position(|(key, _, _)| key == pubkey).unwrap();
+        let compile = |ix: &Instruction, stack_height: usize| {
+            json!({
+                "programIdIndex": index(&ix.program_id),
+                "accounts": ix.accounts.iter().map(|meta| index(&meta.pubkey)).collect::<Vec<_>>(),
+                "data": bs58::encode(&ix.data).into_string(),
+                "stackHeight": stack_height,
+            })
+        };
+        let inner: Vec<Value> = self
+            .inner
+            .iter()
+            .map(|(height, ix)| compile(ix, *height))
+            .collect();
+        let count = |signer: bool, writable: bool| {
+            keys.iter()
+                .filter(|(_, s, w)| *s == signer && *w == writable)
+                .count()
+        };
+        let tx = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "slot": 42,
+                "transaction": {
+                    "signatures": ["sig"],
+                    "message": {
+                        "header": {
+                            "numRequiredSignatures": count(true, true) + count(true, false),
+                            "numReadonlySignedAccounts": count(true, false),
+                            "numReadonlyUnsignedAccounts": count(false, false),
+                        },
+                        "accountKeys": keys.iter().map(|(key, _, _)| key.to_string()).collect::<Vec<_>>(),
+                        "instructions": [compile(&self.ix, 1)],
+                    },
+                },
+                "meta": {
+                    "err": self.err,
+                    "logMessages": self.logs,
+                    "innerInstructions": [{ "index": 0, "instructions": inner }],
+                },
+            },
+        });
+        RpcTransaction::from_json(&tx.to_string()).unwrap()
+    }
+}
+
+fn set_paused(vault: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::SetPaused { vault, authority }.to_account_metas(None),
+        data: vault_manager::instruction::SetPaused { paused: true }.data(),
+    }
+}
+
+fn modify_liquidity(authority: Pubkey) -> mock_whirlpool::accounts::ModifyLiquidity {
+    mock_whirlpool::accounts::ModifyLiquidity {
+        whirlpool: Pubkey::new_unique(),
+        token_program: token_program_id(),
+        position_authority: authority,
+        position: Pubkey::new_unique(),
+        position_token_account: Pubkey::new_unique(),
+        token_owner_account_a: Pubkey::new_unique(),
+        token_owner_account_b: Pubkey::new_unique(),
+        token_vault_a: Pubkey::new_unique(),
+        token_vault_b: Pubkey::new_unique(),
+        tick_array_lower: Pubkey::new_unique(),
+        tick_array_upper: Pubkey::new_unique(),
+    }
+}
+
+fn token_program_id() -> Pubkey {
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn instruction_args_and_accounts_are_named() {
+    let (vault, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let tx = TxBuilder::new(set_paused(vault, authority)).build();
+
+    let decoded = decode_transaction(&programs(), &tx).unwrap();
+    assert_eq!(decoded.signature.as_deref(), Some("sig"));
+    assert_eq!(decoded.slot, Some(42));
+    assert!(decoded.error.is_none());
+    let [ix] = decoded.instructions.as_slice() else {
+        panic!("expected one instruction");
+    };
+    assert_eq!(ix.program_name.as_deref(), Some("vault_manager"));
+    assert_eq!(ix.name.as_deref(), Some("set_paused"));
+    assert_eq!(ix.args, Some(json!({ "paused": true })));
+    assert_eq!(ix.data, None);
+    let accounts: Vec<_> = ix
+        .accounts
+        .iter()
+        .map(|meta| {
+            (
+                meta.name.as_deref(),
+                meta.pubkey.clone(),
+                meta.writable,
+                meta.signer,
+            )
+        })
+        .collect();
+    assert_eq!(
+        accounts,
+        [
+            (Some("vault"), vault.to_string(), true, false),
+            (Some("authority"), authority.to_string(), false, true),
+        ]
+    );
+}
+
+#[test]
+fn whirlpool_cpis_nest_under_the_vault_instruction() {
+    let user = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    let withdraw = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::Withdraw {
+            vault,
+            user,
+            user_share_account: Pubkey::new_unique(),
+            user_token_a: Pubkey::new_unique(),
+            user_token_b: Pubkey::new_unique(),
+            vault_token_a: Pubkey::new_unique(),
+            vault_token_b: Pubkey::new_unique(),
+            share_mint: Pubkey::new_unique(),
+            pool: Pubkey::new_unique(),
+            position: Pubkey::new_unique(),
+            pool_token_vault_a: Pubkey::new_unique(),
+            pool_token_vault_b: Pubkey::new_unique(),
+            price_oracle: None,
+            token_program: token_program_id(),
+            dex_program: mock_whirlpool::ID,
+            user_position: Pubkey::new_unique(),
+            system_program: Pubkey::default(),
+            protocol_config: Pubkey::new_unique(),
+            treasury_token_a: Pubkey::new_unique(),
+            treasury_token_b: Pubkey::new_unique(),
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::Withdraw {
+            shares: u64::MAX,
+            min_amount_a: 1,
+            min_amount_b: 2,
+        }
+        .data(),
+    };
+    let decrease = Instruction {
+        program_id: mock_whirlpool::ID,
+        accounts: modify_liquidity(vault).to_account_metas(None),
+        data: mock_whirlpool::instruction::DecreaseLiquidity {
+            liquidity_amount: u128::MAX,
+            token_min_a: 3,
+            token_min_b: 4,
+        }
+        .data(),
+    };
+    let transfer = Instruction {
+        program_id: token_program_id(),
+        accounts: vec![AccountMeta::new(Pubkey::new_unique(), false)],
+        data: vec![3, 1, 0, 0, 0, 0, 0, 0, 0],
+    };
+    let tx = TxBuilder::new(withdraw)
+        .inner(2, decrease)
+        .inner(3, transfer)
+        .build();
+
+    let decoded = decode_transaction(&programs(), &tx).unwrap();
+    let withdraw = &decoded.instructions[0];
+    assert_eq!(withdraw.name.as_deref(), Some("withdraw"));
+    assert_eq!(
+        withdraw.args,
+        Some(json!({
+            "shares": u64::MAX.to_string(),
+            "min_amount_a": "1",
+            "min_amount_b": "2",
+        }))
+    );
+    // Absent optional accounts are passed as the program ID
+    let names: Vec<_> = withdraw
+        .accounts
+        .iter()
+        .map(|meta| meta.name.as_deref().unwrap())
+        .collect();
+    assert_eq!(names[12], "price_oracle");
+    assert_eq!(withdraw.accounts[12].pubkey, vault_manager::ID.to_string());
+    assert_eq!(names.last(), Some(&"treasury_token_b"));
+
+    let [decrease] = withdraw.inner.as_slice() else {
+        panic!("expected one CPI of the vault");
+    };
+    assert_eq!(decrease.program_name.as_deref(), Some("whirlpool"));
+    assert_eq!(decrease.name.as_deref(), Some("decrease_liquidity"));
+    assert_eq!(
+        decrease.args,
+        Some(json!({
+            "liquidity_amount": u128::MAX.to_string(),
+            "token_min_a": "3",
+            "token_min_b": "4",
+        }))
+    );
+    assert_eq!(
+        decrease.accounts[2].name.as_deref(),
+        Some("position_authority")
+    );
+    assert_eq!(decrease.accounts[2].pubkey, vault.to_string());
+
+    // No IDL for the token program: the transfer stays raw, under the Whirlpool
+    let [transfer] = decrease.inner.as_slice() else {
+        panic!("expected one CPI of the Whirlpool");
+    };
+    assert_eq!(transfer.name, None);
+    assert_eq!(transfer.program_name, None);
+    assert_eq!(
+        transfer.data.as_deref(),
+        Some(
+            bs58::encode([3, 1, 0, 0, 0, 0, 0, 0, 0])
+                .into_string()
+                .as_str()
+        )
+    );
+}
+
+#[test]
+fn events_are_read_from_program_data_logs() {
+    let (vault, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let queue = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::QueueParamChange {
+            vault,
+            authority,
+            payer: authority,
+            pending_change: Pubkey::new_unique(),
+            system_program: System::id(),
+            price_oracle: None,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::QueueParamChange {
+            change: ParamChange::NavCircuitBreaker { max_drop_bps: 500 },
+        }
+        .data(),
+    };
+    let event = ParamChangeQueued {
+        vault,
+        change: ParamChange::NavCircuitBreaker { max_drop_bps: 500 },
+        execute_after_slot: 1_000,
+    };
+    let program = vault_manager::ID.to_string();
+    let system = System::id().to_string();
+    let logs = [
+        format!("Program {program} invoke [1]"),
+        "Program log: Instruction: QueueParamChange".into(),
+        format!("Program {system} invoke [2]"),
+        format!("Program {system} success"),
+        format!("Program data: {}", STANDARD.encode(event.data())),
+        format!("Program {program} success"),
+    ];
+    let logs: Vec<_> = logs.iter().map(String::as_str).collect();
+    let tx = TxBuilder::new(queue).logs(&logs).build();
+
+    let decoded = decode_transaction(&programs(), &tx).unwrap();
+    assert_eq!(
+        decoded.instructions[0].args,
+        Some(json!({ "change": { "NavCircuitBreaker": { "max_drop_bps": 500 } } }))
+    );
+    let [event] = decoded.events.as_slice() else {
+        panic!("expected one event");
+    };
+    assert_eq!(event.instruction, 0);
+    assert_eq!(event.program, program);
+    assert_eq!(event.name, "ParamChangeQueued");
+    assert_eq!(
+        event.data,
+        json!({
+            "vault": vault.to_string(),
+            "change": { "NavCircuitBreaker": { "max_drop_bps": 500 } },
+            "execute_after_slot": "1000",
+        })
+    );
+}
+
+#[test]
+fn errors_are_named_by_the_failing_program() {
+    let (vault, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let program = vault_manager::ID.to_string();
+    let whirlpool = mock_whirlpool::ID.to_string();
+
+    // A Whirlpool error inside the vault's CPI, named by its Anchor log
+    let logs = [
+        format!("Program {program} invoke [1]"),
+        format!("Program {whirlpool} invoke [2]"),
+        "Program log: AnchorError occurred. Error Code: TokenMinSubceeded. Error Number: 6018. \
+         Error Message: Token min subceeded."
+            .to_string(),
+        format!("Program {whirlpool} failed: custom program error: 0x1782"),
+        format!("Program {program} failed: custom program error: 0x1782"),
+    ];
+    let logs: Vec<_> = logs.iter().map(String::as_str).collect();
+    let tx = TxBuilder::new(set_paused(vault, authority))
+        .logs(&logs)
+        .err(json!({ "InstructionError": [0, { "Custom": 6018 }] }))
+        .build();
+    let error = decode_transaction(&programs(), &tx).unwrap().error.unwrap();
+    assert_eq!(error.instruction, Some(0));
+    assert_eq!(error.program.as_deref(), Some(whirlpool.as_str()));
+    assert_eq!(error.code, Some(6018));
+    assert_eq!(error.name.as_deref(), Some("TokenMinSubceeded"));
+    assert_eq!(error.message.as_deref(), Some("Token min subceeded"));
+
+    // Without logs, the vault's own code is looked up in its IDL
+    let code = u32::from(VaultError::VaultPaused);
+    let tx = TxBuilder::new(set_paused(vault, authority))
+        .err(json!({ "InstructionError": [0, { "Custom": code }] }))
+        .build();
+    let error = decode_transaction(&programs(), &tx).unwrap().error.unwrap();
+    assert_eq!(error.program.as_deref(), Some(program.as_str()));
+    assert_eq!(error.code, Some(code));
+    assert_eq!(error.name.as_deref(), Some("VaultPaused"));
+    assert!(error.message.is_some());
+
+    let tx = TxBuilder::new(set_paused(vault, authority))
+        .err(json!({ "InstructionError": [0, "InvalidAccountData"] }))
+        .build();
+    let error = decode_transaction(&programs(), &tx).unwrap().error.unwrap();
+    assert_eq!(error.code, None);
+    assert_eq!(error.name.as_deref(), Some("InvalidAccountData"));
+}
+
+#[test]
+fn accounts_decode_by_their_discriminator() {
+    let authority = Pubkey::new_unique();
+    let vault = Vault {
+        authority,
+        dex: Dex::MeteoraDlmm,
+        pool: Pubkey::new_unique(),
+        token_a_mint: Pubkey::new_unique(),
+        token_b_mint: Pubkey::new_unique(),
+        vault_token_a: Pubkey::new_unique(),
+        vault_token_b: Pubkey::new_unique(),
+        position: Pubkey::new_unique(),
+        share_mint: Pubkey::new_unique(),
+        config: VaultConfig::default(),
+        strategy: VaultStrategy::default(),
+        fees: VaultFees::default(),
+        last_nav_per_share_x64: 1 << 64,
+        last_nav_slot: 7,
+        paused: true,
+        token_a_decimals: 9,
+        token_b_decimals: 6,
+        bump: 254,
+        is_active: true,
+    };
+    let mut data = Vault::DISCRIMINATOR.to_vec();
+    vault.serialize(&mut data).unwrap();
+    // Room left to grow is ignored
+    data.extend([0; 16]);
+
+    let decoded = decode_account(&programs(), &vault_manager::ID.to_string(), &data).unwrap();
+    assert_eq!(decoded.program_name, "vault_manager");
+    assert_eq!(decoded.name, "Vault");
+    assert_eq!(decoded.data["authority"], json!(authority.to_string()));
+    assert_eq!(decoded.data["dex"], json!("MeteoraDlmm"));
+    assert_eq!(
+        decoded.data["last_nav_per_share_x64"],
+        json!((1u128 << 64).to_string())
+    );
+    assert_eq!(decoded.data["paused"], json!(true));
+    assert_eq!(decoded.data["token_a_decimals"], json!(9));
+    assert_eq!(decoded.data["is_active"], json!(true));
+
+    assert!(decode_account(&programs(), &mock_whirlpool::ID.to_string(), &[0; 8]).is_err());
+}
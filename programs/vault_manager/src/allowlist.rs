@@ -0,0 +1,35 @@
+//! Depositor allowlist.
+//!
+//! An allowed wallet has an `AllowlistEntry` PDA for the vault, which deposit
+//! checks. The authority creates entries directly, or publishes a Merkle root of
+//! allowed wallets so each wallet can create its own entry with a proof.
+//!
+//! Leaves are `sha256(0x00 || wallet)` and inner nodes
+//! `sha256(0x01 || min(l, r) || max(l, r))`, so proofs need no left/right flags.
+
+use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, wallet.as_ref()]).to_bytes()
+}
+
+pub fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    hashv(&[NODE_PREFIX, lo, hi]).to_bytes()
+}
+
+/// Whether `proof` links `wallet`'s leaf to `root`.
+pub fn verify(root: &[u8; 32], proof: &[[u8; 32]], wallet: &Pubkey) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf(wallet), |hash, sibling| node(&hash, sibling));
+    computed == *root
+}
@@ -0,0 +1,86 @@
+//! Helpers shared by the hand-written DEX program bindings.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Implements the Anchor account traits for an account type owned by the
+/// program whose `ID` is declared in the parent module.
+macro_rules! external_account {
+    ($name:ident, $discriminator:expr) => {
+        impl Discriminator for $name {
+            const DISCRIMINATOR: &'static [u8] = &$discriminator;
+        }
+
+        impl Owner for $name {
+            fn owner() -> Pubkey {
+                super::ID
+            }
+        }
+
+        impl AccountSerialize for $name {}
+
+        impl AccountDeserialize for $name {
+            fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+                if buf.len() < Self::DISCRIMINATOR.len()
+                    || &buf[..Self::DISCRIMINATOR.len()] != Self::DISCRIMINATOR
+                {
+                    return err!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+                }
+                Self::try_deserialize_unchecked(buf)
+            }
+
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+                let mut data = &buf[Self::DISCRIMINATOR.len()..];
+                AnchorDeserialize::deserialize(&mut data)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+    };
+}
+
+/// Declares a CPI accounts struct in the callee's instruction order.
+macro_rules! cpi_accounts {
+    ($name:ident { $($field:ident: $kind:ident),* $(,)? }) => {
+        pub struct $name<'info> {
+            $(pub $field: AccountInfo<'info>,)*
+        }
+
+        impl<'info> ToAccountMetas for $name<'info> {
+            fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+                vec![$(cpi_accounts!(@meta $kind, self.$field.key())),*]
+            }
+        }
+
+        impl<'info> ToAccountInfos<'info> for $name<'info> {
+            fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+                vec![$(self.$field.clone()),*]
+            }
+        }
+    };
+    (@meta readonly, $key:expr) => { AccountMeta::new_readonly($key, false) };
+    (@meta writable, $key:expr) => { AccountMeta::new($key, false) };
+    (@meta signer, $key:expr) => { AccountMeta::new_readonly($key, true) };
+    (@meta writable_signer, $key:expr) => { AccountMeta::new($key, true) };
+}
+
+/// Invokes an Anchor instruction of `ctx.program` with borsh-encoded `args`.
+pub(crate) fn invoke<'info, T, A>(
+    ctx: CpiContext<'_, '_, '_, 'info, T>,
+    discriminator: [u8; 8],
+    args: A,
+) -> Result<()>
+where
+    T: ToAccountMetas + ToAccountInfos<'info>,
+    A: AnchorSerialize,
+{
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data)?;
+
+    let ix = Instruction {
+        program_id: ctx.program.key(),
+        accounts: ctx.to_account_metas(None),
+        data,
+    };
+    invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds).map_err(Into::into)
+}
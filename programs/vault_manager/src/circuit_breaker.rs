@@ -0,0 +1,82 @@
+//! Circuit breaker on NAV per share drops between operations.
+//!
+//! Deposits, withdrawals, rebalances and migrations record the NAV per share
+//! they observe with its slot. When one observes a drop of more than
+//! `max_nav_drop_bps` from the last recorded value, the vault pauses and
+//! `NavCircuitBreakerTripped` is emitted. The instruction then succeeds so the
+//! pause sticks: deposits, rebalances and migrations into the vault stop short
+//! of moving funds, and close any `UserPosition` they just created, while
+//! withdrawals, which are paid pro rata, go on.
+//!
+//! The authority unpauses with `set_paused`, which also drops the recorded NAV
+//! so the next operation starts a new baseline.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::nav::VaultNav;
+use crate::{Vault, VaultError};
+
+#[event]
+pub struct NavCircuitBreakerTripped {
+    pub vault: Pubkey,
+    pub last_nav_per_share_x64: u128,
+    pub last_nav_slot: u64,
+    pub nav_per_share_x64: u128,
+    pub slot: u64,
+    pub drop_bps: u64,
+}
+
+/// Drop from `last` to `current` in bps, zero when the NAV did not fall.
+pub fn drop_bps(last: u128, current: u128) -> Result<u64> {
+    if last == 0 || current >= last {
+        return Ok(0);
+    }
+    // At most 10_000, as `current` is below `last`
+    Ok(math::mul_div(last - current, 10_000, last)? as u64)
+}
+
+/// Checks `nav`, observed at `slot`, against the vault's last recorded NAV per
+/// share and records it. Returns whether the breaker tripped, in which case the
+/// vault is now paused and the caller should return without acting.
+///
+/// Vaults already paused, or without shares, record nothing.
+pub fn observe_nav(
+    vault: &mut Vault,
+    vault_key: Pubkey,
+    nav: &VaultNav,
+    slot: u64,
+) -> Result<bool> {
+    if vault.paused {
+        return Ok(false);
+    }
+    if nav.share_supply == 0 {
+        vault.last_nav_per_share_x64 = 0;
+        vault.last_nav_slot = 0;
+        return Ok(false);
+    }
+
+    let current = nav.price_per_share_x64;
+    let drop = drop_bps(vault.last_nav_per_share_x64, current)?;
+    if vault.config.max_nav_drop_bps > 0 && drop > u64::from(vault.config.max_nav_drop_bps) {
+        vault.paused = true;
+        emit!(NavCircuitBreakerTripped {
+            vault: vault_key,
+            last_nav_per_share_x64: vault.last_nav_per_share_x64,
+            last_nav_slot: vault.last_nav_slot,
+            nav_per_share_x64: current,
+            slot,
+            drop_bps: drop,
+        });
+        return Ok(true);
+    }
+    vault.last_nav_per_share_x64 = current;
+    vault.last_nav_slot = slot;
+    Ok(false)
+}
+
+/// Refuses operations that move funds into a paused vault.
+pub fn require_not_paused(vault: &Vault) -> Result<()> {
+    require!(!vault.paused, VaultError::VaultPaused);
+    Ok(())
+}
@@ -1,4 +1,35 @@
+//! PDA seeds, for clients to derive the program's addresses with.
+
 use anchor_lang::prelude::*;
 
+/// `[VAULT_SEED, pool]`: a pool's vault, which also signs for its token accounts.
+#[constant]
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// `[SHARE_MINT_SEED, vault]`: the vault's share mint.
+#[constant]
+pub const SHARE_MINT_SEED: &[u8] = b"shares";
+
+/// `[USER_POSITION_SEED, vault, user]`: a depositor's receipt.
+#[constant]
+pub const USER_POSITION_SEED: &[u8] = b"user";
+
+/// `[ALLOWLIST_SEED, vault, wallet]`: a wallet's allowlist entry.
+#[constant]
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// `[PARAM_CHANGE_SEED, vault]`: the vault's queued parameter change.
+#[constant]
+pub const PARAM_CHANGE_SEED: &[u8] = b"param_change";
+
+/// `[STRATEGY_CONFIG_SEED, vault]`: the vault's rebalancing policy.
+#[constant]
+pub const STRATEGY_CONFIG_SEED: &[u8] = b"strategy";
+
+/// `[PROTOCOL_CONFIG_SEED]`: the protocol config.
+#[constant]
+pub const PROTOCOL_CONFIG_SEED: &[u8] = b"protocol_config";
+
+/// `[VAULT_REGISTRY_SEED, page as u32 little-endian]`: a page of the vault registry.
 #[constant]
-pub const SEED: &str = "anchor";
+pub const VAULT_REGISTRY_SEED: &[u8] = b"vault_registry";
@@ -0,0 +1,535 @@
+//! Meteora DLMM backend.
+//!
+//! Position accounts: `token_x_mint`, `token_y_mint`, `event_authority`,
+//! `bin_array_lower`, `bin_array_upper`.
+//! Swap accounts: `token_x_mint`, `token_y_mint`, `event_authority`, `oracle`,
+//! `bin_array_0`, `bin_array_1`, `bin_array_2`, in the order the swap crosses them.
+//! Opening a position additionally takes `event_authority`.
+//!
+//! DLMM prices are per bin, so bin ids stand in for ticks: the current tick is
+//! the active bin and a position covers `[lower_bin_id, upper_bin_id + 1)`.
+//! Position liquidity is the sum of its bin shares. DLMM removes liquidity in
+//! whole basis points of every bin, so a partial decrease is rounded down to
+//! the nearest basis point of the position, in favour of the vault.
+//! The bin array bitmap extension is never passed, so positions must stay in
+//! the pool's default bitmap range.
+
+use anchor_lang::prelude::*;
+
+use super::{
+    next_account, token_account, BinStrategy, ClosePositionAccounts, DexAccounts, DexAdapter,
+    OpenPositionAccounts, PoolPrice, PositionRange, RemainingAccounts,
+};
+use crate::math::{self, checked_add};
+use crate::meteora::cpi::{self, LiquidityParameterByStrategy, StrategyParameters, StrategyType};
+use crate::meteora::state::{BinArray, LbPair, PositionV2, MAX_BIN_PER_POSITION, NUM_REWARDS};
+use crate::nav::PositionValue;
+use crate::VaultError;
+
+pub struct MeteoraDlmmAdapter<'info> {
+    accounts: DexAccounts<'info>,
+}
+
+/// Pool accounts every DLMM instruction takes besides the vault's named accounts.
+pub struct DlmmPoolAccounts<'info> {
+    pub token_x_mint: AccountInfo<'info>,
+    pub token_y_mint: AccountInfo<'info>,
+    pub event_authority: AccountInfo<'info>,
+}
+
+pub struct MeteoraPosition<'info> {
+    pub position: AccountInfo<'info>,
+    pub pool: DlmmPoolAccounts<'info>,
+    pub bin_array_lower: AccountInfo<'info>,
+    pub bin_array_upper: AccountInfo<'info>,
+}
+
+pub struct MeteoraSwapAccounts<'info> {
+    pub pool: DlmmPoolAccounts<'info>,
+    pub oracle: AccountInfo<'info>,
+    pub bin_array_0: AccountInfo<'info>,
+    pub bin_array_1: AccountInfo<'info>,
+    pub bin_array_2: AccountInfo<'info>,
+}
+
+impl<'info> MeteoraDlmmAdapter<'info> {
+    fn lb_pair(&self) -> Result<LbPair> {
+        LbPair::try_deserialize(&mut &self.accounts.pool.try_borrow_data()?[..])
+    }
+
+    fn load_pool_accounts(
+        &self,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<DlmmPoolAccounts<'info>> {
+        let token_x_mint = next_account(remaining)?;
+        let token_y_mint = next_account(remaining)?;
+        require!(
+            token_x_mint.key() == self.accounts.token_a_mint
+                && token_y_mint.key() == self.accounts.token_b_mint,
+            VaultError::MintMismatch
+        );
+        Ok(DlmmPoolAccounts {
+            token_x_mint,
+            token_y_mint,
+            event_authority: next_account(remaining)?,
+        })
+    }
+
+    fn total_shares(&self, position: &MeteoraPosition) -> Result<u128> {
+        let state = PositionV2::load(&position.position)?;
+        (0..bin_count(&state)).try_fold(0u128, |total, i| {
+            let share = PositionV2::read_bin(&position.position, i)?.liquidity_share;
+            total
+                .checked_add(share)
+                .ok_or_else(|| error!(VaultError::MathOverflow))
+        })
+    }
+
+    fn modify_liquidity_accounts(
+        &self,
+        position: &MeteoraPosition<'info>,
+    ) -> cpi::accounts::ModifyLiquidity<'info> {
+        let accounts = &self.accounts;
+        cpi::accounts::ModifyLiquidity {
+            position: position.position.clone(),
+            lb_pair: accounts.pool.clone(),
+            bin_array_bitmap_extension: accounts.dex_program.clone(),
+            user_token_x: accounts.vault_token_a.clone(),
+            user_token_y: accounts.vault_token_b.clone(),
+            reserve_x: accounts.pool_token_vault_a.clone(),
+            reserve_y: accounts.pool_token_vault_b.clone(),
+            token_x_mint: position.pool.token_x_mint.clone(),
+            token_y_mint: position.pool.token_y_mint.clone(),
+            bin_array_lower: position.bin_array_lower.clone(),
+            bin_array_upper: position.bin_array_upper.clone(),
+            sender: accounts.vault.clone(),
+            token_x_program: accounts.token_program.clone(),
+            token_y_program: accounts.token_program.clone(),
+            event_authority: position.pool.event_authority.clone(),
+            program: accounts.dex_program.clone(),
+        }
+    }
+
+    /// Bin array of `position` holding `bin_id`.
+    fn bin_array<'p>(
+        position: &'p MeteoraPosition<'info>,
+        state: &PositionV2,
+        bin_id: i32,
+    ) -> &'p AccountInfo<'info> {
+        if BinArray::index(bin_id) == BinArray::index(state.lower_bin_id) {
+            &position.bin_array_lower
+        } else {
+            &position.bin_array_upper
+        }
+    }
+}
+
+fn bin_count(state: &PositionV2) -> usize {
+    let width = i64::from(state.upper_bin_id) - i64::from(state.lower_bin_id) + 1;
+    width.clamp(0, MAX_BIN_PER_POSITION as i64) as usize
+}
+
+/// First bin past the position, where its price range ends.
+fn upper_bin_end(state: &PositionV2) -> Result<i32> {
+    state
+        .upper_bin_id
+        .checked_add(1)
+        .ok_or_else(|| error!(VaultError::MathOverflow))
+}
+
+/// The vault deposits whatever token ratio it holds, hence the imbalanced variants.
+impl From<BinStrategy> for StrategyType {
+    fn from(strategy: BinStrategy) -> Self {
+        match strategy {
+            BinStrategy::Spot => StrategyType::SpotImBalanced,
+            BinStrategy::Curve => StrategyType::CurveImBalanced,
+            BinStrategy::BidAsk => StrategyType::BidAskImBalanced,
+        }
+    }
+}
+
+impl<'info> DexAdapter<'info> for MeteoraDlmmAdapter<'info> {
+    type Position = MeteoraPosition<'info>;
+    type SwapAccounts = MeteoraSwapAccounts<'info>;
+
+    fn load(accounts: DexAccounts<'info>) -> Result<Self> {
+        require_keys_eq!(
+            *accounts.pool.owner,
+            crate::meteora::ID,
+            ErrorCode::AccountOwnedByWrongProgram
+        );
+        let adapter = Self { accounts };
+        let lb_pair = adapter.lb_pair()?;
+        require!(
+            lb_pair.token_x_mint == adapter.accounts.token_a_mint
+                && lb_pair.token_y_mint == adapter.accounts.token_b_mint,
+            VaultError::MintMismatch
+        );
+        require_keys_eq!(
+            lb_pair.reserve_x,
+            adapter.accounts.pool_token_vault_a.key(),
+            ErrorCode::ConstraintAddress
+        );
+        require_keys_eq!(
+            lb_pair.reserve_y,
+            adapter.accounts.pool_token_vault_b.key(),
+            ErrorCode::ConstraintAddress
+        );
+        Ok(adapter)
+    }
+
+    fn load_position(
+        &self,
+        position: AccountInfo<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::Position> {
+        let pool = self.load_pool_accounts(remaining)?;
+        let bin_array_lower = next_account(remaining)?;
+        let bin_array_upper = next_account(remaining)?;
+
+        // DLMM positions are plain accounts with an owner field rather than NFTs
+        let state = PositionV2::load(&position)?;
+        require!(
+            state.lb_pair == self.accounts.pool.key() && state.owner == self.accounts.vault.key(),
+            VaultError::InvalidPosition
+        );
+
+        Ok(MeteoraPosition {
+            position,
+            pool,
+            bin_array_lower,
+            bin_array_upper,
+        })
+    }
+
+    fn load_swap(
+        &self,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::SwapAccounts> {
+        Ok(MeteoraSwapAccounts {
+            pool: self.load_pool_accounts(remaining)?,
+            oracle: next_account(remaining)?,
+            bin_array_0: next_account(remaining)?,
+            bin_array_1: next_account(remaining)?,
+            bin_array_2: next_account(remaining)?,
+        })
+    }
+
+    fn price(&self) -> Result<PoolPrice> {
+        let lb_pair = self.lb_pair()?;
+        Ok(PoolPrice {
+            sqrt_price: math::sqrt_price_from_bin_id(lb_pair.active_id, lb_pair.bin_step)?,
+            tick_current: lb_pair.active_id,
+        })
+    }
+
+    fn twap_tick(&self, _swap: &Self::SwapAccounts, _window_secs: u32) -> Result<Option<i32>> {
+        Ok(None)
+    }
+
+    fn position_range(&self, position: &Self::Position) -> Result<PositionRange> {
+        let bin_step = self.lb_pair()?.bin_step;
+        let state = PositionV2::load(&position.position)?;
+        Ok(PositionRange {
+            sqrt_lower: math::sqrt_price_from_bin_id(state.lower_bin_id, bin_step)?,
+            sqrt_upper: math::sqrt_price_from_bin_id(upper_bin_end(&state)?, bin_step)?,
+            liquidity: self.total_shares(position)?,
+        })
+    }
+
+    /// Bin reserves owned through the position's shares plus fees and rewards
+    /// already credited to its bins. Rewards emitted since a bin was last
+    /// updated are not included.
+    fn position_value(&self, position: &Self::Position) -> Result<PositionValue> {
+        let lb_pair = self.lb_pair()?;
+        let pool_key = self.accounts.pool.key();
+        let state = PositionV2::load(&position.position)?;
+
+        let mut value = PositionValue::default();
+        let mut rewards = [0u64; NUM_REWARDS];
+        for i in 0..bin_count(&state) {
+            let owned = PositionV2::read_bin(&position.position, i)?;
+            let bin_id = state
+                .lower_bin_id
+                .checked_add(i as i32)
+                .ok_or(VaultError::MathOverflow)?;
+            let bin =
+                BinArray::read_bin(Self::bin_array(position, &state, bin_id), &pool_key, bin_id)?;
+
+            if owned.liquidity_share > 0 && bin.liquidity_supply > 0 {
+                let share_of = |amount: u64| -> Result<u64> {
+                    math::narrow(math::mul_div(
+                        owned.liquidity_share,
+                        u128::from(amount),
+                        bin.liquidity_supply,
+                    )?)
+                };
+                value.amount_a = checked_add(value.amount_a, share_of(bin.amount_x)?)?;
+                value.amount_b = checked_add(value.amount_b, share_of(bin.amount_y)?)?;
+            }
+
+            // Per-token growth is Q64.64 per 2^64 units of liquidity share
+            let scaled_share = owned.liquidity_share >> 64;
+            let accrued = |stored: u128, complete: u128| {
+                math::mul_shift_right_64(scaled_share, stored.wrapping_sub(complete))
+            };
+            let fee_a = checked_add(
+                owned.fee_x_pending,
+                accrued(
+                    bin.fee_amount_x_per_token_stored,
+                    owned.fee_x_per_token_complete,
+                )?,
+            )?;
+            let fee_b = checked_add(
+                owned.fee_y_pending,
+                accrued(
+                    bin.fee_amount_y_per_token_stored,
+                    owned.fee_y_per_token_complete,
+                )?,
+            )?;
+            value.fees_owed_a = checked_add(value.fees_owed_a, fee_a)?;
+            value.fees_owed_b = checked_add(value.fees_owed_b, fee_b)?;
+
+            for (j, reward) in rewards.iter_mut().enumerate() {
+                let amount = checked_add(
+                    owned.reward_pendings[j],
+                    accrued(
+                        bin.reward_per_token_stored[j],
+                        owned.reward_per_token_completes[j],
+                    )?,
+                )?;
+                *reward = checked_add(*reward, amount)?;
+            }
+        }
+
+        for (j, reward) in lb_pair.reward_infos.iter().enumerate() {
+            if reward.initialized() {
+                value.rewards_owed[j] = (reward.mint, rewards[j]);
+            }
+        }
+        Ok(value)
+    }
+
+    fn open_position(
+        &self,
+        accounts: OpenPositionAccounts<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        let event_authority = next_account(remaining)?;
+        let width = tick_upper
+            .checked_sub(tick_lower)
+            .map(|span| span + 1)
+            .filter(|width| (1..=MAX_BIN_PER_POSITION as i32).contains(width))
+            .ok_or(VaultError::InvalidTickRange)?;
+
+        self.accounts.with_vault_signer(|signer| {
+            cpi::initialize_position(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    cpi::accounts::InitializePosition {
+                        payer: accounts.funder,
+                        position: accounts.position,
+                        lb_pair: self.accounts.pool.clone(),
+                        owner: self.accounts.vault.clone(),
+                        system_program: accounts.system_program,
+                        rent: accounts.rent,
+                        event_authority,
+                        program: self.accounts.dex_program.clone(),
+                    },
+                    signer,
+                ),
+                tick_lower,
+                width,
+            )
+        })
+    }
+
+    fn close_position(
+        &self,
+        position: Self::Position,
+        accounts: ClosePositionAccounts<'info>,
+        _remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<()> {
+        self.accounts.with_vault_signer(|signer| {
+            cpi::close_position(CpiContext::new_with_signer(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::ClosePosition {
+                    position: position.position,
+                    lb_pair: self.accounts.pool.clone(),
+                    bin_array_lower: position.bin_array_lower,
+                    bin_array_upper: position.bin_array_upper,
+                    sender: self.accounts.vault.clone(),
+                    rent_receiver: accounts.receiver,
+                    event_authority: position.pool.event_authority,
+                    program: self.accounts.dex_program.clone(),
+                },
+                signer,
+            ))
+        })
+    }
+
+    /// Deposits `max_a` and `max_b` over the position's bins with the vault's
+    /// bin strategy. Returns the liquidity shares minted.
+    fn increase_liquidity(
+        &self,
+        position: &Self::Position,
+        max_a: u64,
+        max_b: u64,
+    ) -> Result<u128> {
+        if max_a == 0 && max_b == 0 {
+            return Ok(0);
+        }
+        let state = PositionV2::load(&position.position)?;
+        let shares_before = self.total_shares(position)?;
+        let parameters = LiquidityParameterByStrategy {
+            amount_x: max_a,
+            amount_y: max_b,
+            active_id: self.lb_pair()?.active_id,
+            max_active_bin_slippage: 0,
+            strategy_parameters: StrategyParameters {
+                min_bin_id: state.lower_bin_id,
+                max_bin_id: state.upper_bin_id,
+                strategy_type: self.accounts.bin_strategy.into(),
+                parameteres: [0; 64],
+            },
+        };
+        self.accounts.with_vault_signer(|signer| {
+            cpi::add_liquidity_by_strategy(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    self.modify_liquidity_accounts(position),
+                    signer,
+                ),
+                parameters,
+            )
+        })?;
+
+        let shares_after = self.total_shares(position)?;
+        Ok(shares_after.saturating_sub(shares_before))
+    }
+
+    fn decrease_liquidity(
+        &self,
+        position: &Self::Position,
+        liquidity: u128,
+        min_a: u64,
+        min_b: u64,
+    ) -> Result<()> {
+        let total = self.total_shares(position)?;
+        let bps = if liquidity == 0 || total == 0 {
+            0
+        } else if liquidity >= total {
+            10_000
+        } else {
+            math::mul_div(liquidity, 10_000, total)? as u16
+        };
+        if bps == 0 {
+            require!(min_a == 0 && min_b == 0, VaultError::SlippageExceeded);
+            return Ok(());
+        }
+
+        // DLMM takes no minimums, so they are checked on the vault's balances
+        let balances = || -> Result<(u64, u64)> {
+            Ok((
+                token_account(&self.accounts.vault_token_a)?.amount,
+                token_account(&self.accounts.vault_token_b)?.amount,
+            ))
+        };
+        let (before_a, before_b) = balances()?;
+        let state = PositionV2::load(&position.position)?;
+        self.accounts.with_vault_signer(|signer| {
+            cpi::remove_liquidity_by_range(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    self.modify_liquidity_accounts(position),
+                    signer,
+                ),
+                state.lower_bin_id,
+                state.upper_bin_id,
+                bps,
+            )
+        })?;
+        let (after_a, after_b) = balances()?;
+        require!(
+            math::checked_sub(after_a, before_a)? >= min_a
+                && math::checked_sub(after_b, before_b)? >= min_b,
+            VaultError::SlippageExceeded
+        );
+        Ok(())
+    }
+
+    fn collect_fees(&self, position: &Self::Position) -> Result<()> {
+        let accounts = &self.accounts;
+        accounts.with_vault_signer(|signer| {
+            cpi::claim_fee(CpiContext::new_with_signer(
+                accounts.dex_program.clone(),
+                cpi::accounts::ClaimFee {
+                    lb_pair: accounts.pool.clone(),
+                    position: position.position.clone(),
+                    bin_array_lower: position.bin_array_lower.clone(),
+                    bin_array_upper: position.bin_array_upper.clone(),
+                    sender: accounts.vault.clone(),
+                    reserve_x: accounts.pool_token_vault_a.clone(),
+                    reserve_y: accounts.pool_token_vault_b.clone(),
+                    user_token_x: accounts.vault_token_a.clone(),
+                    user_token_y: accounts.vault_token_b.clone(),
+                    token_x_mint: position.pool.token_x_mint.clone(),
+                    token_y_mint: position.pool.token_y_mint.clone(),
+                    token_program: accounts.token_program.clone(),
+                    event_authority: position.pool.event_authority.clone(),
+                    program: accounts.dex_program.clone(),
+                },
+                signer,
+            ))
+        })
+    }
+
+    fn swap(
+        &self,
+        swap: &Self::SwapAccounts,
+        amount_in: u64,
+        a_to_b: bool,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let accounts = &self.accounts;
+        let (user_token_in, user_token_out) = if a_to_b {
+            (&accounts.vault_token_a, &accounts.vault_token_b)
+        } else {
+            (&accounts.vault_token_b, &accounts.vault_token_a)
+        };
+        accounts.with_vault_signer(|signer| {
+            cpi::swap(
+                CpiContext::new_with_signer(
+                    accounts.dex_program.clone(),
+                    cpi::accounts::Swap {
+                        lb_pair: accounts.pool.clone(),
+                        bin_array_bitmap_extension: accounts.dex_program.clone(),
+                        reserve_x: accounts.pool_token_vault_a.clone(),
+                        reserve_y: accounts.pool_token_vault_b.clone(),
+                        user_token_in: user_token_in.clone(),
+                        user_token_out: user_token_out.clone(),
+                        token_x_mint: swap.pool.token_x_mint.clone(),
+                        token_y_mint: swap.pool.token_y_mint.clone(),
+                        oracle: swap.oracle.clone(),
+                        host_fee_in: accounts.dex_program.clone(),
+                        user: accounts.vault.clone(),
+                        token_x_program: accounts.token_program.clone(),
+                        token_y_program: accounts.token_program.clone(),
+                        event_authority: swap.pool.event_authority.clone(),
+                        program: accounts.dex_program.clone(),
+                    },
+                    signer,
+                )
+                .with_remaining_accounts(vec![
+                    swap.bin_array_0.clone(),
+                    swap.bin_array_1.clone(),
+                    swap.bin_array_2.clone(),
+                ]),
+                amount_in,
+                min_amount_out,
+            )
+        })
+    }
+}
@@ -0,0 +1,251 @@
+//! DEX adapters.
+//!
+//! Vault instructions are written once against [`DexAdapter`], and `Vault.dex`
+//! selects the backend. Accounts every backend needs are named in the
+//! instruction's `Accounts` struct. Backend-specific accounts are passed as
+//! remaining accounts, in the order documented on each adapter's position and
+//! swap account sets.
+
+// Enum derives refer to `borsh` by name, which the direct borsh dependency makes ambiguous.
+use anchor_lang::prelude::borsh;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::constants::VAULT_SEED;
+use crate::math;
+use crate::nav::PositionValue;
+
+pub mod meteora;
+pub mod raydium;
+pub mod whirlpool;
+
+pub use meteora::MeteoraDlmmAdapter;
+pub use raydium::RaydiumClmmAdapter;
+pub use whirlpool::WhirlpoolAdapter;
+
+/// Most reward slots any backend has.
+pub const MAX_REWARDS: usize = 3;
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum Dex {
+    #[default]
+    Whirlpool,
+    RaydiumClmm,
+    MeteoraDlmm,
+}
+
+impl Dex {
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Dex::Whirlpool => crate::whirlpool::ID,
+            Dex::RaydiumClmm => crate::raydium::ID,
+            Dex::MeteoraDlmm => crate::meteora::ID,
+        }
+    }
+
+    /// Whether the pool keeps the tick observations the TWAP guard reads.
+    pub fn keeps_tick_observations(&self) -> bool {
+        matches!(self, Dex::RaydiumClmm)
+    }
+}
+
+/// How bin-based backends spread liquidity across a position's bins.
+/// Tick-based backends ignore it.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum BinStrategy {
+    /// Even distribution over the range.
+    #[default]
+    Spot,
+    /// Concentrated around the active bin.
+    Curve,
+    /// Weighted toward the edges of the range.
+    BidAsk,
+}
+
+/// Accounts every backend needs, taken from the instruction's named accounts.
+#[derive(Clone)]
+pub struct DexAccounts<'info> {
+    /// Vault PDA, which owns the position and the vault token accounts.
+    pub vault: AccountInfo<'info>,
+    pub vault_bump: u8,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub vault_token_a: AccountInfo<'info>,
+    pub vault_token_b: AccountInfo<'info>,
+    pub pool: AccountInfo<'info>,
+    pub pool_token_vault_a: AccountInfo<'info>,
+    pub pool_token_vault_b: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub dex_program: AccountInfo<'info>,
+    pub bin_strategy: BinStrategy,
+}
+
+impl DexAccounts<'_> {
+    /// Runs `f` with the vault PDA signer seeds.
+    pub fn with_vault_signer<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        let pool = self.pool.key();
+        let bump = [self.vault_bump];
+        let seeds: &[&[u8]] = &[VAULT_SEED, pool.as_ref(), &bump];
+        f(&[seeds])
+    }
+}
+
+/// Accounts needed to open a new position owned by the vault.
+pub struct OpenPositionAccounts<'info> {
+    pub funder: AccountInfo<'info>,
+    pub position: AccountInfo<'info>,
+    pub position_mint: AccountInfo<'info>,
+    pub position_token_account: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+}
+
+/// Accounts needed to close a position owned by the vault.
+pub struct ClosePositionAccounts<'info> {
+    /// Receives the position's rent, where the backend lets it be chosen.
+    pub receiver: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Pool price in the vault's token A/B orientation.
+#[derive(Clone, Copy, Debug)]
+pub struct PoolPrice {
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+}
+
+/// Price range and liquidity of a position.
+#[derive(Clone, Copy, Debug)]
+pub struct PositionRange {
+    pub sqrt_lower: u128,
+    pub sqrt_upper: u128,
+    pub liquidity: u128,
+}
+
+pub type RemainingAccounts<'c, 'info> = std::slice::Iter<'c, AccountInfo<'info>>;
+
+/// Concentrated liquidity operations the vault performs on its pool.
+pub trait DexAdapter<'info>: Sized {
+    type Position;
+    type SwapAccounts;
+
+    /// Loads the pool and checks it against the vault's mints and pool token vaults.
+    fn load(accounts: DexAccounts<'info>) -> Result<Self>;
+
+    /// Takes the position's account set from `remaining` and checks the vault owns it.
+    fn load_position(
+        &self,
+        position: AccountInfo<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::Position>;
+
+    /// Takes the swap account set from `remaining`.
+    fn load_swap(&self, remaining: &mut RemainingAccounts<'_, 'info>)
+        -> Result<Self::SwapAccounts>;
+
+    fn price(&self) -> Result<PoolPrice>;
+
+    /// Average tick over at least the last `window_secs`, from the pool's tick
+    /// observations in `swap`. `None` when the backend keeps none.
+    fn twap_tick(&self, swap: &Self::SwapAccounts, window_secs: u32) -> Result<Option<i32>>;
+
+    fn position_range(&self, position: &Self::Position) -> Result<PositionRange>;
+
+    /// Position liquidity, uncollected fees and rewards at the current price.
+    fn position_value(&self, position: &Self::Position) -> Result<PositionValue>;
+
+    /// Opens an empty position over `[tick_lower, tick_upper]` owned by the vault.
+    fn open_position(
+        &self,
+        accounts: OpenPositionAccounts<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()>;
+
+    /// Closes the empty `position`, burning its NFT where the backend has one.
+    fn close_position(
+        &self,
+        position: Self::Position,
+        accounts: ClosePositionAccounts<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<()>;
+
+    /// Adds as much liquidity as `max_a` and `max_b` fund and returns the amount added.
+    fn increase_liquidity(&self, position: &Self::Position, max_a: u64, max_b: u64)
+        -> Result<u128>;
+
+    /// Removes `liquidity`, failing if it pays out less than `min_a` or `min_b`.
+    fn decrease_liquidity(
+        &self,
+        position: &Self::Position,
+        liquidity: u128,
+        min_a: u64,
+        min_b: u64,
+    ) -> Result<()>;
+
+    /// Moves uncollected fees into the vault token accounts, and rewards into
+    /// the vault's reward accounts where the backend's position account set
+    /// names them.
+    fn collect_fees(&self, position: &Self::Position) -> Result<()>;
+
+    /// Exact-input swap of `amount_in` with no price limit, failing if it pays
+    /// out less than `min_amount_out`.
+    fn swap(
+        &self,
+        swap: &Self::SwapAccounts,
+        amount_in: u64,
+        a_to_b: bool,
+        min_amount_out: u64,
+    ) -> Result<()>;
+}
+
+pub(crate) fn next_account<'info>(
+    remaining: &mut RemainingAccounts<'_, 'info>,
+) -> Result<AccountInfo<'info>> {
+    remaining
+        .next()
+        .cloned()
+        .ok_or_else(|| error!(ErrorCode::AccountNotEnoughKeys))
+}
+
+/// Checks `info` is the vault's token account holding the position NFT `mint`.
+pub(crate) fn check_position_nft(info: &AccountInfo, mint: &Pubkey, vault: &Pubkey) -> Result<()> {
+    let account = token_account(info)?;
+    require!(
+        account.mint == *mint && account.owner == *vault && account.amount == 1,
+        crate::VaultError::InvalidPosition
+    );
+    Ok(())
+}
+
+/// Checks a tick-based position range: `tick_lower` below `tick_upper`, both
+/// within the tick math bounds and on the pool's `tick_spacing`.
+pub(crate) fn check_tick_range(tick_lower: i32, tick_upper: i32, tick_spacing: u16) -> Result<()> {
+    require!(tick_lower < tick_upper, crate::VaultError::InvalidTickRange);
+    require!(
+        tick_lower >= math::MIN_TICK_INDEX && tick_upper <= math::MAX_TICK_INDEX,
+        crate::VaultError::InvalidTickIndex
+    );
+    let spacing = i32::from(tick_spacing);
+    require!(
+        spacing > 0 && tick_lower % spacing == 0 && tick_upper % spacing == 0,
+        crate::VaultError::TickSpacingMisaligned
+    );
+    Ok(())
+}
+
+/// Deserializes an SPL token account, checking its owner program.
+pub(crate) fn token_account(info: &AccountInfo) -> Result<TokenAccount> {
+    require_keys_eq!(
+        *info.owner,
+        anchor_spl::token::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
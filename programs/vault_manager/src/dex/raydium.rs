@@ -0,0 +1,463 @@
+//! Raydium CLMM backend.
+//!
+//! Position accounts: `nft_account`, `protocol_position`, `tick_array_lower`,
+//! `tick_array_upper`, then a `(reward_vault, vault_reward_account)` pair for
+//! every initialized pool reward, in reward slot order.
+//! Swap accounts: `amm_config`, `observation_state`, `tick_array_0`,
+//! `tick_array_1`, `tick_array_2`.
+//! Opening a position additionally takes `metadata_account`, `metadata_program`,
+//! `protocol_position`, `tick_array_lower` and `tick_array_upper`.
+//! Closing a position additionally takes `position_nft_mint`; Raydium pays the
+//! rent to the vault, which passes it on when the vault itself is closed.
+//!
+//! Raydium has no separate fee collection: fees and rewards are paid out by a
+//! zero-liquidity `decrease_liquidity`, so rewards land in the vault's reward
+//! accounts whenever liquidity is decreased.
+
+use anchor_lang::prelude::*;
+
+use super::{
+    check_position_nft, check_tick_range, next_account, token_account, ClosePositionAccounts,
+    DexAccounts, DexAdapter, OpenPositionAccounts, PoolPrice, PositionRange, RemainingAccounts,
+    MAX_REWARDS,
+};
+use crate::math;
+use crate::nav::{ClmmPosition, ClmmReward, PositionValue, TickGrowth};
+use crate::raydium::cpi;
+use crate::raydium::state::{
+    ObservationState, PersonalPositionState, PoolState, TickArrayState, TickState,
+};
+use crate::VaultError;
+
+pub struct RaydiumClmmAdapter<'info> {
+    accounts: DexAccounts<'info>,
+}
+
+pub struct RaydiumPosition<'info> {
+    pub personal_position: AccountInfo<'info>,
+    pub nft_account: AccountInfo<'info>,
+    pub protocol_position: AccountInfo<'info>,
+    pub tick_array_lower: AccountInfo<'info>,
+    pub tick_array_upper: AccountInfo<'info>,
+    pub reward_accounts: Vec<AccountInfo<'info>>,
+}
+
+pub struct RaydiumSwapAccounts<'info> {
+    pub amm_config: AccountInfo<'info>,
+    pub observation_state: AccountInfo<'info>,
+    pub tick_array_0: AccountInfo<'info>,
+    pub tick_array_1: AccountInfo<'info>,
+    pub tick_array_2: AccountInfo<'info>,
+}
+
+impl RaydiumClmmAdapter<'_> {
+    fn pool_state(&self) -> Result<PoolState> {
+        PoolState::try_deserialize(&mut &self.accounts.pool.try_borrow_data()?[..])
+    }
+}
+
+fn read_position(info: &AccountInfo) -> Result<PersonalPositionState> {
+    require_keys_eq!(*info.owner, crate::raydium::ID, VaultError::InvalidPosition);
+    PersonalPositionState::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+impl<'info> DexAdapter<'info> for RaydiumClmmAdapter<'info> {
+    type Position = RaydiumPosition<'info>;
+    type SwapAccounts = RaydiumSwapAccounts<'info>;
+
+    fn load(accounts: DexAccounts<'info>) -> Result<Self> {
+        require_keys_eq!(
+            *accounts.pool.owner,
+            crate::raydium::ID,
+            ErrorCode::AccountOwnedByWrongProgram
+        );
+        let adapter = Self { accounts };
+        let pool = adapter.pool_state()?;
+        require!(
+            pool.token_mint_0 == adapter.accounts.token_a_mint
+                && pool.token_mint_1 == adapter.accounts.token_b_mint,
+            VaultError::MintMismatch
+        );
+        require_keys_eq!(
+            pool.token_vault_0,
+            adapter.accounts.pool_token_vault_a.key(),
+            ErrorCode::ConstraintAddress
+        );
+        require_keys_eq!(
+            pool.token_vault_1,
+            adapter.accounts.pool_token_vault_b.key(),
+            ErrorCode::ConstraintAddress
+        );
+        Ok(adapter)
+    }
+
+    fn load_position(
+        &self,
+        position: AccountInfo<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::Position> {
+        let nft_account = next_account(remaining)?;
+        let protocol_position = next_account(remaining)?;
+        let tick_array_lower = next_account(remaining)?;
+        let tick_array_upper = next_account(remaining)?;
+
+        let state = read_position(&position)?;
+        require_keys_eq!(
+            state.pool_id,
+            self.accounts.pool.key(),
+            VaultError::InvalidPosition
+        );
+        let vault = self.accounts.vault.key();
+        check_position_nft(&nft_account, &state.nft_mint, &vault)?;
+
+        let mut reward_accounts = Vec::new();
+        for reward in self.pool_state()?.reward_infos.iter() {
+            if !reward.initialized() {
+                continue;
+            }
+            let reward_vault = next_account(remaining)?;
+            let recipient = next_account(remaining)?;
+            require_keys_eq!(
+                reward_vault.key(),
+                reward.token_vault,
+                ErrorCode::ConstraintAddress
+            );
+            let recipient_account = token_account(&recipient)?;
+            require!(
+                recipient_account.owner == vault && recipient_account.mint == reward.token_mint,
+                VaultError::InvalidRewardAccount
+            );
+            reward_accounts.push(reward_vault);
+            reward_accounts.push(recipient);
+        }
+
+        Ok(RaydiumPosition {
+            personal_position: position,
+            nft_account,
+            protocol_position,
+            tick_array_lower,
+            tick_array_upper,
+            reward_accounts,
+        })
+    }
+
+    fn load_swap(
+        &self,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::SwapAccounts> {
+        Ok(RaydiumSwapAccounts {
+            amm_config: next_account(remaining)?,
+            observation_state: next_account(remaining)?,
+            tick_array_0: next_account(remaining)?,
+            tick_array_1: next_account(remaining)?,
+            tick_array_2: next_account(remaining)?,
+        })
+    }
+
+    fn price(&self) -> Result<PoolPrice> {
+        let pool = self.pool_state()?;
+        Ok(PoolPrice {
+            sqrt_price: pool.sqrt_price_x64,
+            tick_current: pool.tick_current,
+        })
+    }
+
+    fn twap_tick(&self, swap: &Self::SwapAccounts, window_secs: u32) -> Result<Option<i32>> {
+        let pool = self.pool_state()?;
+        require_keys_eq!(
+            swap.observation_state.key(),
+            pool.observation_key,
+            VaultError::InvalidObservationState
+        );
+        let observations =
+            ObservationState::load(&swap.observation_state, &self.accounts.pool.key())?;
+        let now = Clock::get()?.unix_timestamp;
+        observations
+            .twap_tick(pool.tick_current, now, window_secs)
+            .map(Some)
+    }
+
+    fn position_range(&self, position: &Self::Position) -> Result<PositionRange> {
+        let state = read_position(&position.personal_position)?;
+        Ok(PositionRange {
+            sqrt_lower: math::sqrt_price_from_tick_index(state.tick_lower_index)?,
+            sqrt_upper: math::sqrt_price_from_tick_index(state.tick_upper_index)?,
+            liquidity: state.liquidity,
+        })
+    }
+
+    fn position_value(&self, position: &Self::Position) -> Result<PositionValue> {
+        let pool = self.pool_state()?;
+        let state = read_position(&position.personal_position)?;
+        let pool_key = self.accounts.pool.key();
+        let tick_lower = TickArrayState::read_tick(
+            &position.tick_array_lower,
+            &pool_key,
+            state.tick_lower_index,
+            pool.tick_spacing,
+        )?;
+        let tick_upper = TickArrayState::read_tick(
+            &position.tick_array_upper,
+            &pool_key,
+            state.tick_upper_index,
+            pool.tick_spacing,
+        )?;
+
+        let mut rewards = [ClmmReward::default(); MAX_REWARDS];
+        for (i, reward) in pool.reward_infos.iter().enumerate() {
+            if reward.initialized() {
+                rewards[i] = ClmmReward {
+                    mint: reward.token_mint,
+                    growth_global: reward.reward_growth_global_x64,
+                    growth_checkpoint: state.reward_infos[i].growth_inside_last_x64,
+                    amount_owed: state.reward_infos[i].reward_amount_owed,
+                };
+            }
+        }
+
+        ClmmPosition {
+            sqrt_price: pool.sqrt_price_x64,
+            tick_current: pool.tick_current,
+            tick_lower_index: state.tick_lower_index,
+            tick_upper_index: state.tick_upper_index,
+            liquidity: state.liquidity,
+            tick_lower: tick_growth(&tick_lower),
+            tick_upper: tick_growth(&tick_upper),
+            fee_growth_global_a: pool.fee_growth_global_0_x64,
+            fee_growth_global_b: pool.fee_growth_global_1_x64,
+            fee_growth_checkpoint_a: state.fee_growth_inside_0_last_x64,
+            fee_growth_checkpoint_b: state.fee_growth_inside_1_last_x64,
+            fee_owed_a: state.token_fees_owed_0,
+            fee_owed_b: state.token_fees_owed_1,
+            rewards,
+        }
+        .value()
+    }
+
+    fn open_position(
+        &self,
+        accounts: OpenPositionAccounts<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        let metadata_account = next_account(remaining)?;
+        let metadata_program = next_account(remaining)?;
+        let protocol_position = next_account(remaining)?;
+        let tick_array_lower = next_account(remaining)?;
+        let tick_array_upper = next_account(remaining)?;
+
+        let tick_spacing = self.pool_state()?.tick_spacing;
+        check_tick_range(tick_lower, tick_upper, tick_spacing)?;
+        cpi::open_position(
+            CpiContext::new(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::OpenPosition {
+                    payer: accounts.funder,
+                    position_nft_owner: self.accounts.vault.clone(),
+                    position_nft_mint: accounts.position_mint,
+                    position_nft_account: accounts.position_token_account,
+                    metadata_account,
+                    pool_state: self.accounts.pool.clone(),
+                    protocol_position,
+                    tick_array_lower,
+                    tick_array_upper,
+                    personal_position: accounts.position,
+                    token_account_0: self.accounts.vault_token_a.clone(),
+                    token_account_1: self.accounts.vault_token_b.clone(),
+                    token_vault_0: self.accounts.pool_token_vault_a.clone(),
+                    token_vault_1: self.accounts.pool_token_vault_b.clone(),
+                    rent: accounts.rent,
+                    system_program: accounts.system_program,
+                    token_program: self.accounts.token_program.clone(),
+                    associated_token_program: accounts.associated_token_program,
+                    metadata_program,
+                },
+            ),
+            tick_lower,
+            tick_upper,
+            TickArrayState::start_index(tick_lower, tick_spacing),
+            TickArrayState::start_index(tick_upper, tick_spacing),
+            0,
+            0,
+            0,
+        )
+    }
+
+    fn close_position(
+        &self,
+        position: Self::Position,
+        accounts: ClosePositionAccounts<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<()> {
+        let position_nft_mint = next_account(remaining)?;
+        require_keys_eq!(
+            read_position(&position.personal_position)?.nft_mint,
+            position_nft_mint.key(),
+            VaultError::InvalidPosition
+        );
+        self.accounts.with_vault_signer(|signer| {
+            cpi::close_position(CpiContext::new_with_signer(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::ClosePosition {
+                    nft_owner: self.accounts.vault.clone(),
+                    position_nft_mint,
+                    position_nft_account: position.nft_account,
+                    personal_position: position.personal_position,
+                    system_program: accounts.system_program,
+                    token_program: self.accounts.token_program.clone(),
+                },
+                signer,
+            ))
+        })
+    }
+
+    fn increase_liquidity(
+        &self,
+        position: &Self::Position,
+        max_a: u64,
+        max_b: u64,
+    ) -> Result<u128> {
+        let range = self.position_range(position)?;
+        let liquidity = math::liquidity_for_amounts(
+            self.price()?.sqrt_price,
+            range.sqrt_lower,
+            range.sqrt_upper,
+            max_a,
+            max_b,
+        )?;
+        if liquidity == 0 {
+            return Ok(0);
+        }
+        self.accounts.with_vault_signer(|signer| {
+            cpi::increase_liquidity(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    cpi::accounts::IncreaseLiquidity {
+                        nft_owner: self.accounts.vault.clone(),
+                        nft_account: position.nft_account.clone(),
+                        pool_state: self.accounts.pool.clone(),
+                        protocol_position: position.protocol_position.clone(),
+                        personal_position: position.personal_position.clone(),
+                        tick_array_lower: position.tick_array_lower.clone(),
+                        tick_array_upper: position.tick_array_upper.clone(),
+                        token_account_0: self.accounts.vault_token_a.clone(),
+                        token_account_1: self.accounts.vault_token_b.clone(),
+                        token_vault_0: self.accounts.pool_token_vault_a.clone(),
+                        token_vault_1: self.accounts.pool_token_vault_b.clone(),
+                        token_program: self.accounts.token_program.clone(),
+                    },
+                    signer,
+                ),
+                liquidity,
+                max_a,
+                max_b,
+            )
+        })?;
+        Ok(liquidity)
+    }
+
+    fn decrease_liquidity(
+        &self,
+        position: &Self::Position,
+        liquidity: u128,
+        min_a: u64,
+        min_b: u64,
+    ) -> Result<()> {
+        self.accounts.with_vault_signer(|signer| {
+            cpi::decrease_liquidity(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    cpi::accounts::DecreaseLiquidity {
+                        nft_owner: self.accounts.vault.clone(),
+                        nft_account: position.nft_account.clone(),
+                        personal_position: position.personal_position.clone(),
+                        pool_state: self.accounts.pool.clone(),
+                        protocol_position: position.protocol_position.clone(),
+                        token_vault_0: self.accounts.pool_token_vault_a.clone(),
+                        token_vault_1: self.accounts.pool_token_vault_b.clone(),
+                        tick_array_lower: position.tick_array_lower.clone(),
+                        tick_array_upper: position.tick_array_upper.clone(),
+                        recipient_token_account_0: self.accounts.vault_token_a.clone(),
+                        recipient_token_account_1: self.accounts.vault_token_b.clone(),
+                        token_program: self.accounts.token_program.clone(),
+                    },
+                    signer,
+                )
+                .with_remaining_accounts(position.reward_accounts.clone()),
+                liquidity,
+                min_a,
+                min_b,
+            )
+        })
+    }
+
+    fn collect_fees(&self, position: &Self::Position) -> Result<()> {
+        self.decrease_liquidity(position, 0, 0, 0)
+    }
+
+    fn swap(
+        &self,
+        swap: &Self::SwapAccounts,
+        amount_in: u64,
+        a_to_b: bool,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let accounts = &self.accounts;
+        let (input_token_account, output_token_account, input_vault, output_vault) = if a_to_b {
+            (
+                &accounts.vault_token_a,
+                &accounts.vault_token_b,
+                &accounts.pool_token_vault_a,
+                &accounts.pool_token_vault_b,
+            )
+        } else {
+            (
+                &accounts.vault_token_b,
+                &accounts.vault_token_a,
+                &accounts.pool_token_vault_b,
+                &accounts.pool_token_vault_a,
+            )
+        };
+        accounts.with_vault_signer(|signer| {
+            cpi::swap(
+                CpiContext::new_with_signer(
+                    accounts.dex_program.clone(),
+                    cpi::accounts::Swap {
+                        payer: accounts.vault.clone(),
+                        amm_config: swap.amm_config.clone(),
+                        pool_state: accounts.pool.clone(),
+                        input_token_account: input_token_account.clone(),
+                        output_token_account: output_token_account.clone(),
+                        input_vault: input_vault.clone(),
+                        output_vault: output_vault.clone(),
+                        observation_state: swap.observation_state.clone(),
+                        token_program: accounts.token_program.clone(),
+                        tick_array: swap.tick_array_0.clone(),
+                    },
+                    signer,
+                )
+                .with_remaining_accounts(vec![
+                    swap.tick_array_1.clone(),
+                    swap.tick_array_2.clone(),
+                ]),
+                amount_in,
+                min_amount_out,
+                // Zero lets Raydium use its own min/max sqrt price bound
+                0,
+                true,
+            )
+        })
+    }
+}
+
+/// Raydium follows Uniswap: the outside growth of an uninitialized tick is zero
+/// rather than the global growth, so every tick is treated as initialized.
+fn tick_growth(tick: &TickState) -> TickGrowth {
+    TickGrowth {
+        initialized: true,
+        fee_growth_outside_a: tick.fee_growth_outside_0_x64,
+        fee_growth_outside_b: tick.fee_growth_outside_1_x64,
+        reward_growths_outside: tick.reward_growths_outside_x64,
+    }
+}
@@ -0,0 +1,469 @@
+//! Orca Whirlpool backend.
+//!
+//! Position accounts: `position_token_account`, `tick_array_lower`,
+//! `tick_array_upper`, then a `(reward_vault, vault_reward_account)` pair for
+//! every initialized pool reward, in reward slot order.
+//! Swap accounts: `tick_array_0`, `tick_array_1`, `tick_array_2`, `oracle`.
+//! Closing a position additionally takes `position_mint`.
+//!
+//! Rewards are collected into the vault's reward accounts along with the fees,
+//! and once more before a position is closed, which Whirlpool refuses while
+//! any are owed.
+
+use anchor_lang::prelude::*;
+
+use super::{
+    check_position_nft, check_tick_range, next_account, token_account, ClosePositionAccounts,
+    DexAccounts, DexAdapter, OpenPositionAccounts, PoolPrice, PositionRange, RemainingAccounts,
+    MAX_REWARDS,
+};
+use crate::math;
+use crate::nav::{ClmmPosition, ClmmReward, PositionValue, TickGrowth};
+use crate::whirlpool::cpi;
+use crate::whirlpool::state::{Position, Tick, TickArray, Whirlpool};
+use crate::VaultError;
+
+pub struct WhirlpoolAdapter<'info> {
+    accounts: DexAccounts<'info>,
+}
+
+pub struct WhirlpoolPosition<'info> {
+    pub position: AccountInfo<'info>,
+    pub position_token_account: AccountInfo<'info>,
+    pub tick_array_lower: AccountInfo<'info>,
+    pub tick_array_upper: AccountInfo<'info>,
+    pub rewards: Vec<WhirlpoolReward<'info>>,
+}
+
+/// Accounts collecting one initialized pool reward.
+pub struct WhirlpoolReward<'info> {
+    pub index: u8,
+    pub reward_vault: AccountInfo<'info>,
+    pub vault_reward_account: AccountInfo<'info>,
+}
+
+pub struct WhirlpoolSwapAccounts<'info> {
+    pub tick_array_0: AccountInfo<'info>,
+    pub tick_array_1: AccountInfo<'info>,
+    pub tick_array_2: AccountInfo<'info>,
+    pub oracle: AccountInfo<'info>,
+}
+
+impl<'info> WhirlpoolAdapter<'info> {
+    fn whirlpool(&self) -> Result<Whirlpool> {
+        Whirlpool::try_deserialize(&mut &self.accounts.pool.try_borrow_data()?[..])
+    }
+
+    fn modify_liquidity_accounts(
+        &self,
+        position: &WhirlpoolPosition<'info>,
+    ) -> cpi::accounts::ModifyLiquidity<'info> {
+        cpi::accounts::ModifyLiquidity {
+            whirlpool: self.accounts.pool.clone(),
+            token_program: self.accounts.token_program.clone(),
+            position_authority: self.accounts.vault.clone(),
+            position: position.position.clone(),
+            position_token_account: position.position_token_account.clone(),
+            token_owner_account_a: self.accounts.vault_token_a.clone(),
+            token_owner_account_b: self.accounts.vault_token_b.clone(),
+            token_vault_a: self.accounts.pool_token_vault_a.clone(),
+            token_vault_b: self.accounts.pool_token_vault_b.clone(),
+            tick_array_lower: position.tick_array_lower.clone(),
+            tick_array_upper: position.tick_array_upper.clone(),
+        }
+    }
+
+    /// Moves every reward owed to `position` into the vault's reward accounts.
+    fn collect_rewards(&self, position: &WhirlpoolPosition<'info>) -> Result<()> {
+        for reward in &position.rewards {
+            self.accounts.with_vault_signer(|signer| {
+                cpi::collect_reward(
+                    CpiContext::new_with_signer(
+                        self.accounts.dex_program.clone(),
+                        cpi::accounts::CollectReward {
+                            whirlpool: self.accounts.pool.clone(),
+                            position_authority: self.accounts.vault.clone(),
+                            position: position.position.clone(),
+                            position_token_account: position.position_token_account.clone(),
+                            reward_owner_account: reward.vault_reward_account.clone(),
+                            reward_vault: reward.reward_vault.clone(),
+                            token_program: self.accounts.token_program.clone(),
+                        },
+                        signer,
+                    ),
+                    reward.index,
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn read_position(info: &AccountInfo) -> Result<Position> {
+    require_keys_eq!(
+        *info.owner,
+        crate::whirlpool::ID,
+        VaultError::InvalidPosition
+    );
+    Position::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+impl<'info> DexAdapter<'info> for WhirlpoolAdapter<'info> {
+    type Position = WhirlpoolPosition<'info>;
+    type SwapAccounts = WhirlpoolSwapAccounts<'info>;
+
+    fn load(accounts: DexAccounts<'info>) -> Result<Self> {
+        require_keys_eq!(
+            *accounts.pool.owner,
+            crate::whirlpool::ID,
+            ErrorCode::AccountOwnedByWrongProgram
+        );
+        let adapter = Self { accounts };
+        let whirlpool = adapter.whirlpool()?;
+        require!(
+            whirlpool.token_mint_a == adapter.accounts.token_a_mint
+                && whirlpool.token_mint_b == adapter.accounts.token_b_mint,
+            VaultError::MintMismatch
+        );
+        require_keys_eq!(
+            whirlpool.token_vault_a,
+            adapter.accounts.pool_token_vault_a.key(),
+            ErrorCode::ConstraintAddress
+        );
+        require_keys_eq!(
+            whirlpool.token_vault_b,
+            adapter.accounts.pool_token_vault_b.key(),
+            ErrorCode::ConstraintAddress
+        );
+        Ok(adapter)
+    }
+
+    fn load_position(
+        &self,
+        position: AccountInfo<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::Position> {
+        let position_token_account = next_account(remaining)?;
+        let tick_array_lower = next_account(remaining)?;
+        let tick_array_upper = next_account(remaining)?;
+
+        let state = read_position(&position)?;
+        require_keys_eq!(
+            state.whirlpool,
+            self.accounts.pool.key(),
+            VaultError::InvalidPosition
+        );
+        let vault = self.accounts.vault.key();
+        check_position_nft(&position_token_account, &state.position_mint, &vault)?;
+
+        let mut rewards = Vec::new();
+        for (index, reward) in self.whirlpool()?.reward_infos.iter().enumerate() {
+            if !reward.initialized() {
+                continue;
+            }
+            let reward_vault = next_account(remaining)?;
+            let vault_reward_account = next_account(remaining)?;
+            require_keys_eq!(
+                reward_vault.key(),
+                reward.vault,
+                ErrorCode::ConstraintAddress
+            );
+            let recipient = token_account(&vault_reward_account)?;
+            require!(
+                recipient.owner == vault && recipient.mint == reward.mint,
+                VaultError::InvalidRewardAccount
+            );
+            rewards.push(WhirlpoolReward {
+                index: index as u8,
+                reward_vault,
+                vault_reward_account,
+            });
+        }
+
+        Ok(WhirlpoolPosition {
+            position,
+            position_token_account,
+            tick_array_lower,
+            tick_array_upper,
+            rewards,
+        })
+    }
+
+    fn load_swap(
+        &self,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self::SwapAccounts> {
+        Ok(WhirlpoolSwapAccounts {
+            tick_array_0: next_account(remaining)?,
+            tick_array_1: next_account(remaining)?,
+            tick_array_2: next_account(remaining)?,
+            oracle: next_account(remaining)?,
+        })
+    }
+
+    fn price(&self) -> Result<PoolPrice> {
+        let whirlpool = self.whirlpool()?;
+        Ok(PoolPrice {
+            sqrt_price: whirlpool.sqrt_price,
+            tick_current: whirlpool.tick_current_index,
+        })
+    }
+
+    fn twap_tick(&self, _swap: &Self::SwapAccounts, _window_secs: u32) -> Result<Option<i32>> {
+        Ok(None)
+    }
+
+    fn position_range(&self, position: &Self::Position) -> Result<PositionRange> {
+        let state = read_position(&position.position)?;
+        Ok(PositionRange {
+            sqrt_lower: math::sqrt_price_from_tick_index(state.tick_lower_index)?,
+            sqrt_upper: math::sqrt_price_from_tick_index(state.tick_upper_index)?,
+            liquidity: state.liquidity,
+        })
+    }
+
+    fn position_value(&self, position: &Self::Position) -> Result<PositionValue> {
+        let whirlpool = self.whirlpool()?;
+        let state = read_position(&position.position)?;
+        let pool = self.accounts.pool.key();
+        let tick_lower = TickArray::read_tick(
+            &position.tick_array_lower,
+            &pool,
+            state.tick_lower_index,
+            whirlpool.tick_spacing,
+        )?;
+        let tick_upper = TickArray::read_tick(
+            &position.tick_array_upper,
+            &pool,
+            state.tick_upper_index,
+            whirlpool.tick_spacing,
+        )?;
+
+        let mut rewards = [ClmmReward::default(); MAX_REWARDS];
+        for (i, reward) in whirlpool.reward_infos.iter().enumerate() {
+            rewards[i] = ClmmReward {
+                mint: reward.mint,
+                growth_global: reward.growth_global_x64,
+                growth_checkpoint: state.reward_infos[i].growth_inside_checkpoint,
+                amount_owed: state.reward_infos[i].amount_owed,
+            };
+        }
+
+        ClmmPosition {
+            sqrt_price: whirlpool.sqrt_price,
+            tick_current: whirlpool.tick_current_index,
+            tick_lower_index: state.tick_lower_index,
+            tick_upper_index: state.tick_upper_index,
+            liquidity: state.liquidity,
+            tick_lower: tick_growth(&tick_lower),
+            tick_upper: tick_growth(&tick_upper),
+            fee_growth_global_a: whirlpool.fee_growth_global_a,
+            fee_growth_global_b: whirlpool.fee_growth_global_b,
+            fee_growth_checkpoint_a: state.fee_growth_checkpoint_a,
+            fee_growth_checkpoint_b: state.fee_growth_checkpoint_b,
+            fee_owed_a: state.fee_owed_a,
+            fee_owed_b: state.fee_owed_b,
+            rewards,
+        }
+        .value()
+    }
+
+    fn open_position(
+        &self,
+        accounts: OpenPositionAccounts<'info>,
+        _remaining: &mut RemainingAccounts<'_, 'info>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        check_tick_range(tick_lower, tick_upper, self.whirlpool()?.tick_spacing)?;
+        let (_, position_bump) = Pubkey::find_program_address(
+            &[b"position", accounts.position_mint.key.as_ref()],
+            &crate::whirlpool::ID,
+        );
+        cpi::open_position(
+            CpiContext::new(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::OpenPosition {
+                    funder: accounts.funder,
+                    owner: self.accounts.vault.clone(),
+                    position: accounts.position,
+                    position_mint: accounts.position_mint,
+                    position_token_account: accounts.position_token_account,
+                    whirlpool: self.accounts.pool.clone(),
+                    token_program: self.accounts.token_program.clone(),
+                    system_program: accounts.system_program,
+                    rent: accounts.rent,
+                    associated_token_program: accounts.associated_token_program,
+                },
+            ),
+            cpi::OpenPositionBumps { position_bump },
+            tick_lower,
+            tick_upper,
+        )
+    }
+
+    fn close_position(
+        &self,
+        position: Self::Position,
+        accounts: ClosePositionAccounts<'info>,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<()> {
+        let position_mint = next_account(remaining)?;
+        require_keys_eq!(
+            read_position(&position.position)?.position_mint,
+            position_mint.key(),
+            VaultError::InvalidPosition
+        );
+        self.collect_rewards(&position)?;
+        self.accounts.with_vault_signer(|signer| {
+            cpi::close_position(CpiContext::new_with_signer(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::ClosePosition {
+                    position_authority: self.accounts.vault.clone(),
+                    receiver: accounts.receiver,
+                    position: position.position,
+                    position_mint,
+                    position_token_account: position.position_token_account,
+                    token_program: self.accounts.token_program.clone(),
+                },
+                signer,
+            ))
+        })
+    }
+
+    fn increase_liquidity(
+        &self,
+        position: &Self::Position,
+        max_a: u64,
+        max_b: u64,
+    ) -> Result<u128> {
+        let range = self.position_range(position)?;
+        let liquidity = math::liquidity_for_amounts(
+            self.price()?.sqrt_price,
+            range.sqrt_lower,
+            range.sqrt_upper,
+            max_a,
+            max_b,
+        )?;
+        if liquidity == 0 {
+            return Ok(0);
+        }
+        self.accounts.with_vault_signer(|signer| {
+            cpi::increase_liquidity(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    self.modify_liquidity_accounts(position),
+                    signer,
+                ),
+                liquidity,
+                max_a,
+                max_b,
+            )
+        })?;
+        Ok(liquidity)
+    }
+
+    fn decrease_liquidity(
+        &self,
+        position: &Self::Position,
+        liquidity: u128,
+        min_a: u64,
+        min_b: u64,
+    ) -> Result<()> {
+        self.accounts.with_vault_signer(|signer| {
+            cpi::decrease_liquidity(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    self.modify_liquidity_accounts(position),
+                    signer,
+                ),
+                liquidity,
+                min_a,
+                min_b,
+            )
+        })
+    }
+
+    fn collect_fees(&self, position: &Self::Position) -> Result<()> {
+        if read_position(&position.position)?.liquidity > 0 {
+            cpi::update_fees_and_rewards(CpiContext::new(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::UpdateFeesAndRewards {
+                    whirlpool: self.accounts.pool.clone(),
+                    position: position.position.clone(),
+                    tick_array_lower: position.tick_array_lower.clone(),
+                    tick_array_upper: position.tick_array_upper.clone(),
+                },
+            ))?;
+        }
+        self.accounts.with_vault_signer(|signer| {
+            cpi::collect_fees(CpiContext::new_with_signer(
+                self.accounts.dex_program.clone(),
+                cpi::accounts::CollectFees {
+                    whirlpool: self.accounts.pool.clone(),
+                    position_authority: self.accounts.vault.clone(),
+                    position: position.position.clone(),
+                    position_token_account: position.position_token_account.clone(),
+                    token_owner_account_a: self.accounts.vault_token_a.clone(),
+                    token_vault_a: self.accounts.pool_token_vault_a.clone(),
+                    token_owner_account_b: self.accounts.vault_token_b.clone(),
+                    token_vault_b: self.accounts.pool_token_vault_b.clone(),
+                    token_program: self.accounts.token_program.clone(),
+                },
+                signer,
+            ))
+        })?;
+        self.collect_rewards(position)
+    }
+
+    fn swap(
+        &self,
+        swap: &Self::SwapAccounts,
+        amount_in: u64,
+        a_to_b: bool,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let sqrt_price_limit = if a_to_b {
+            math::MIN_SQRT_PRICE_X64
+        } else {
+            math::MAX_SQRT_PRICE_X64
+        };
+        self.accounts.with_vault_signer(|signer| {
+            cpi::swap(
+                CpiContext::new_with_signer(
+                    self.accounts.dex_program.clone(),
+                    cpi::accounts::Swap {
+                        token_program: self.accounts.token_program.clone(),
+                        token_authority: self.accounts.vault.clone(),
+                        whirlpool: self.accounts.pool.clone(),
+                        token_owner_account_a: self.accounts.vault_token_a.clone(),
+                        token_vault_a: self.accounts.pool_token_vault_a.clone(),
+                        token_owner_account_b: self.accounts.vault_token_b.clone(),
+                        token_vault_b: self.accounts.pool_token_vault_b.clone(),
+                        tick_array_0: swap.tick_array_0.clone(),
+                        tick_array_1: swap.tick_array_1.clone(),
+                        tick_array_2: swap.tick_array_2.clone(),
+                        oracle: swap.oracle.clone(),
+                    },
+                    signer,
+                ),
+                amount_in,
+                min_amount_out,
+                sqrt_price_limit,
+                true,
+                a_to_b,
+            )
+        })
+    }
+}
+
+/// Whirlpool counts growth of an uninitialized tick as below it, which is what
+/// `ClmmPosition` does for ticks marked uninitialized.
+fn tick_growth(tick: &Tick) -> TickGrowth {
+    TickGrowth {
+        initialized: tick.initialized,
+        fee_growth_outside_a: tick.fee_growth_outside_a,
+        fee_growth_outside_b: tick.fee_growth_outside_b,
+        reward_growths_outside: tick.reward_growths_outside,
+    }
+}
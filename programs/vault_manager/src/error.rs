@@ -1,7 +1,127 @@
+//! Errors returned by the program.
+//!
+//! Anchor numbers the variants from 6000 in declaration order, and clients map
+//! those codes to their own messages, so new variants go at the end and none
+//! are removed or reordered. Related failures:
+//! - tick ranges: `InvalidTickRange`, `TickSpacingMisaligned`, `InvalidTickIndex`
+//! - mints: `MintMismatch`
+//! - roles: `Unauthorized`, `NotUpgradeAuthority`
+//! - pauses: `VaultPaused`
+//! - slippage: `SlippageExceeded`, `SharesBelowMinimum`, `SwapOutputBelowMinimum`,
+//!   `OraclePriceDeviation`, `TwapDeviation`
+//! - caps: `DepositCapExceeded`, `UserDepositCapExceeded`
+//! - oracles: `StaleOracle`
+//! - arithmetic: `MathOverflow`
+//! - cooldowns: `SharesLocked`, `WithdrawalNotReady`, `ParamChangeNotReady`
+
 use anchor_lang::prelude::*;
 
 #[error_code]
-pub enum ErrorCode {
-    #[msg("Custom error message")]
-    CustomError,
+pub enum VaultError {
+    /// No longer raised, as deposits join the active position; kept so later
+    /// codes stay put.
+    #[msg("Position is already active")]
+    PositionActive,
+    #[msg("No active position to rebalance")]
+    NoActivePosition,
+    #[msg("Token mint does not match the vault")]
+    MintMismatch,
+    #[msg("Tick index is out of bounds")]
+    InvalidTickIndex,
+    #[msg("Tick array does not cover the requested tick")]
+    InvalidTickArray,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Deposit is too small to mint any shares")]
+    ZeroShares,
+    #[msg("Not enough shares outstanding")]
+    InsufficientShares,
+    #[msg("Withdrawn amounts are below the requested minimum")]
+    SlippageExceeded,
+    #[msg("Position does not belong to the vault's pool")]
+    InvalidPosition,
+    #[msg("Price oracle account is required for this vault")]
+    OracleRequired,
+    #[msg("Price oracle account is invalid")]
+    InvalidOracle,
+    #[msg("Price oracle is stale")]
+    StaleOracle,
+    #[msg("Pool price deviates too far from the oracle price")]
+    OraclePriceDeviation,
+    #[msg("Observation account does not belong to the vault's pool")]
+    InvalidObservationState,
+    #[msg("Pool observations do not cover the TWAP window")]
+    InsufficientOracleHistory,
+    #[msg("Spot tick deviates too far from the TWAP")]
+    TwapDeviation,
+    #[msg("Reward token account is not the vault's account for the reward mint")]
+    InvalidRewardAccount,
+    #[msg("Bin array does not cover the requested bin")]
+    InvalidBinArray,
+    #[msg("Source and destination vaults must differ")]
+    SameVault,
+    #[msg("Swap program is not whitelisted by the vault")]
+    SwapProgramNotAllowed,
+    #[msg("Too many swap programs")]
+    TooManySwapPrograms,
+    #[msg("Swap route spent more of the vault's tokens than allowed")]
+    RouteOverspent,
+    #[msg("Deposit is below the vault's minimum")]
+    DepositTooSmall,
+    #[msg("Deposit would exceed the vault's TVL cap")]
+    DepositCapExceeded,
+    #[msg("Deposit would exceed the per-user cap")]
+    UserDepositCapExceeded,
+    #[msg("Depositor is not on the vault's allowlist")]
+    NotAllowlisted,
+    #[msg("Allowlist proof does not match the vault's Merkle root")]
+    InvalidAllowlistProof,
+    /// No longer raised, as withdrawals always carry the receipt; kept so later
+    /// codes stay put.
+    #[msg("User position is required by the vault's lockup or withdrawal queue")]
+    UserPositionRequired,
+    #[msg("Shares are still locked after the latest deposit")]
+    SharesLocked,
+    #[msg("Withdrawal exceeds the shares requested")]
+    WithdrawalNotRequested,
+    #[msg("Withdrawal request is still in its delay")]
+    WithdrawalNotReady,
+    #[msg("Fee must be below 10000 bps")]
+    InvalidFee,
+    #[msg("Vault still has shares or position liquidity")]
+    VaultNotEmpty,
+    #[msg("Parameter changes must be queued while the vault has a timelock")]
+    ParamChangeTimelocked,
+    #[msg("Parameter change timelock has not passed")]
+    ParamChangeNotReady,
+    #[msg("Vault is paused")]
+    VaultPaused,
+    #[msg("NAV drop threshold must be below 10000 bps")]
+    InvalidNavDropThreshold,
+    #[msg("Rebalance threshold must be below the range width")]
+    InvalidStrategyConfig,
+    #[msg("DEX program is not allowed by the protocol config")]
+    DexNotAllowed,
+    #[msg("Too many DEX programs")]
+    TooManyDexPrograms,
+    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+    #[msg("Lower tick must be below the upper tick")]
+    InvalidTickRange,
+    #[msg("Tick is not a multiple of the pool's tick spacing")]
+    TickSpacingMisaligned,
+    #[msg("Signer does not hold the role this instruction requires")]
+    Unauthorized,
+    #[msg("The vault's DEX keeps no tick observations for the TWAP guard")]
+    TwapUnavailable,
+    #[msg("Swap route passes the vault's position or a vault token account other than its token A and B accounts")]
+    RouteAccountNotAllowed,
+    #[msg("Vault is not listed on this registry page")]
+    VaultNotInRegistry,
+    #[msg("Strategy config changes need the vault's strategy config account")]
+    StrategyConfigRequired,
+    #[msg("Shares minted are below the requested minimum")]
+    SharesBelowMinimum,
+    #[msg("Swap output is below the requested minimum")]
+    SwapOutputBelowMinimum,
 }
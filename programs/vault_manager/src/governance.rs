@@ -0,0 +1,182 @@
+//! Timelocked strategy parameter changes.
+//!
+//! While a vault's `param_timelock_slots` is non-zero, its strategy parameters
+//! only change through `queue_param_change` and, at least that many slots later,
+//! `execute_param_change`. Both emit events, so depositors who disagree with a
+//! queued change can withdraw before it applies. Without a timelock the setters
+//! apply changes directly.
+
+// Enum derives refer to `borsh` by name, which the direct borsh dependency makes ambiguous.
+use anchor_lang::prelude::borsh;
+use anchor_lang::prelude::*;
+
+use crate::dex::BinStrategy;
+use crate::route::MAX_SWAP_PROGRAMS;
+use crate::{StrategyConfig, Vault, VaultError};
+
+/// A strategy parameter update, applied as the matching setter would.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub enum ParamChange {
+    BinStrategy(BinStrategy),
+    TwapGuard {
+        window_secs: u32,
+        max_deviation_ticks: u32,
+    },
+    SwapPrograms(#[max_len(MAX_SWAP_PROGRAMS)] Vec<Pubkey>),
+    DepositLimits {
+        min_deposit: u64,
+        user_deposit_cap: u64,
+        deposit_cap: u64,
+    },
+    Lockup {
+        lockup_slots: u64,
+        early_exit_fee_bps: u16,
+        withdrawal_delay_slots: u64,
+    },
+    NavCircuitBreaker {
+        max_drop_bps: u16,
+    },
+    /// Applied to the vault's `StrategyConfig` account rather than the vault.
+    StrategyConfig {
+        range_width: u32,
+        rebalance_threshold: u32,
+    },
+    /// `Pubkey::default()` disables the oracle guard.
+    PriceOracle {
+        price_oracle: Pubkey,
+        max_deviation_bps: u16,
+        max_staleness_secs: u32,
+    },
+    /// Delay for later changes; this change itself waits out the current delay.
+    Timelock {
+        slots: u64,
+    },
+}
+
+impl ParamChange {
+    pub fn validate(&self, vault: &Vault) -> Result<()> {
+        match self {
+            ParamChange::SwapPrograms(programs) => require!(
+                programs.len() <= MAX_SWAP_PROGRAMS,
+                VaultError::TooManySwapPrograms
+            ),
+            // A window on a pool without tick observations would fail every rebalance
+            ParamChange::TwapGuard { window_secs, .. } => require!(
+                *window_secs == 0 || vault.dex.keeps_tick_observations(),
+                VaultError::TwapUnavailable
+            ),
+            ParamChange::Lockup {
+                early_exit_fee_bps, ..
+            } => require!(*early_exit_fee_bps < 10_000, VaultError::InvalidFee),
+            ParamChange::NavCircuitBreaker { max_drop_bps } => {
+                require!(*max_drop_bps < 10_000, VaultError::InvalidNavDropThreshold)
+            }
+            ParamChange::StrategyConfig {
+                range_width,
+                rebalance_threshold,
+            } => require!(
+                rebalance_threshold < range_width,
+                VaultError::InvalidStrategyConfig
+            ),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// `strategy_config` is only needed, and only touched, by `StrategyConfig` changes.
+    pub fn apply(
+        &self,
+        vault: &mut Vault,
+        strategy_config: Option<&mut StrategyConfig>,
+    ) -> Result<()> {
+        self.validate(vault)?;
+        match self {
+            ParamChange::BinStrategy(strategy) => vault.strategy.bin_strategy = *strategy,
+            ParamChange::TwapGuard {
+                window_secs,
+                max_deviation_ticks,
+            } => {
+                vault.config.twap_window_secs = *window_secs;
+                vault.config.max_twap_deviation_ticks = *max_deviation_ticks;
+            }
+            ParamChange::SwapPrograms(programs) => {
+                vault.strategy.swap_programs = [Pubkey::default(); MAX_SWAP_PROGRAMS];
+                vault.strategy.swap_programs[..programs.len()].copy_from_slice(programs);
+            }
+            ParamChange::DepositLimits {
+                min_deposit,
+                user_deposit_cap,
+                deposit_cap,
+            } => {
+                vault.config.min_deposit = *min_deposit;
+                vault.config.user_deposit_cap = *user_deposit_cap;
+                vault.config.deposit_cap = *deposit_cap;
+            }
+            ParamChange::Lockup {
+                lockup_slots,
+                early_exit_fee_bps,
+                withdrawal_delay_slots,
+            } => {
+                vault.config.lockup_slots = *lockup_slots;
+                vault.fees.early_exit_fee_bps = *early_exit_fee_bps;
+                vault.config.withdrawal_delay_slots = *withdrawal_delay_slots;
+            }
+            ParamChange::NavCircuitBreaker { max_drop_bps } => {
+                vault.config.max_nav_drop_bps = *max_drop_bps
+            }
+            ParamChange::StrategyConfig {
+                range_width,
+                rebalance_threshold,
+            } => {
+                let config = strategy_config.ok_or(VaultError::StrategyConfigRequired)?;
+                config.range_width = *range_width;
+                config.rebalance_threshold = *rebalance_threshold;
+            }
+            ParamChange::PriceOracle {
+                price_oracle,
+                max_deviation_bps,
+                max_staleness_secs,
+            } => {
+                vault.config.price_oracle = *price_oracle;
+                vault.config.max_oracle_deviation_bps = *max_deviation_bps;
+                vault.config.max_oracle_staleness_secs = *max_staleness_secs;
+            }
+            ParamChange::Timelock { slots } => vault.config.param_timelock_slots = *slots,
+        }
+        Ok(())
+    }
+}
+
+/// Applies `change` right away, for setters of vaults without a timelock.
+pub fn apply_now(vault: &mut Vault, change: ParamChange) -> Result<()> {
+    require_no_timelock(vault)?;
+    change.apply(vault, None)
+}
+
+/// Refuses direct parameter changes while the vault has a timelock.
+pub fn require_no_timelock(vault: &Vault) -> Result<()> {
+    require!(
+        vault.config.param_timelock_slots == 0,
+        VaultError::ParamChangeTimelocked
+    );
+    Ok(())
+}
+
+#[event]
+pub struct ParamChangeQueued {
+    pub vault: Pubkey,
+    pub change: ParamChange,
+    pub execute_after_slot: u64,
+}
+
+#[event]
+pub struct ParamChangeExecuted {
+    pub vault: Pubkey,
+    pub change: ParamChange,
+}
+
+#[event]
+pub struct ParamChangeCancelled {
+    pub vault: Pubkey,
+    pub change: ParamChange,
+}
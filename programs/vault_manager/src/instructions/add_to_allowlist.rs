@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init, payer = payer, space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<AddToAllowlist>, wallet: Pubkey) -> Result<()> {
+    *ctx.accounts.allowlist_entry = AllowlistEntry {
+        vault: ctx.accounts.vault.key(),
+        wallet,
+        bump: ctx.bumps.allowlist_entry,
+    };
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::governance::ParamChangeCancelled;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct CancelParamChange<'info> {
+    #[account(has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        close = authority,
+        has_one = vault,
+        seeds = [PARAM_CHANGE_SEED, vault.key().as_ref()],
+        bump = pending_change.bump,
+    )]
+    pub pending_change: Account<'info, PendingParamChange>,
+}
+
+pub fn handler(ctx: Context<CancelParamChange>) -> Result<()> {
+    emit!(ParamChangeCancelled {
+        vault: ctx.accounts.vault.key(),
+        change: ctx.accounts.pending_change.change.clone(),
+    });
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::dex::{
+    ClosePositionAccounts, Dex, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter,
+    WhirlpoolAdapter,
+};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [VAULT_SEED, vault.pool.as_ref()], bump = vault.bump,
+        has_one = authority @ VaultError::Unauthorized,
+        has_one = pool, has_one = position, has_one = share_mint,
+        has_one = vault_token_a, has_one = vault_token_b,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// Registry page listing the vault
+    #[account(
+        mut,
+        seeds = [VAULT_REGISTRY_SEED, &vault_registry.page.to_le_bytes()],
+        bump = vault_registry.bump,
+    )]
+    pub vault_registry: Box<Account<'info, VaultRegistry>>,
+    #[account(mut)]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+    /// Receives the vault's leftover token A
+    #[account(mut, token::mint = vault.token_a_mint, token::authority = authority)]
+    pub authority_token_a: Box<Account<'info, TokenAccount>>,
+    /// Receives the vault's leftover token B
+    #[account(mut, token::mint = vault.token_b_mint, token::authority = authority)]
+    pub authority_token_b: Box<Account<'info, TokenAccount>>,
+    pub share_mint: Account<'info, Mint>,
+    /// CHECK: Pool of `vault.dex` - checked by the DEX adapter
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: Vault position - checked by the DEX adapter
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Program of `vault.dex`
+    #[account(address = vault.dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl_dex_accounts!(CloseVault);
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, CloseVault<'info>>) -> Result<()> {
+    match ctx.accounts.vault.dex {
+        Dex::Whirlpool => process_close_vault::<WhirlpoolAdapter>(ctx),
+        Dex::RaydiumClmm => process_close_vault::<RaydiumClmmAdapter>(ctx),
+        Dex::MeteoraDlmm => process_close_vault::<MeteoraDlmmAdapter>(ctx),
+    }
+}
+
+fn process_close_vault<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, CloseVault<'info>>,
+) -> Result<()> {
+    require!(
+        ctx.accounts.share_mint.supply == 0,
+        VaultError::VaultNotEmpty
+    );
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let mut remaining = ctx.remaining_accounts.iter();
+    let position = dex.load_position(ctx.accounts.position.to_account_info(), &mut remaining)?;
+    require!(
+        dex.position_range(&position)?.liquidity == 0,
+        VaultError::VaultNotEmpty
+    );
+    dex.close_position(
+        position,
+        ClosePositionAccounts {
+            receiver: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        },
+        &mut remaining,
+    )?;
+    // Closing collects the position's last rewards, possibly into these
+    ctx.accounts.vault_token_a.reload()?;
+    ctx.accounts.vault_token_b.reload()?;
+
+    let vault_key = ctx.accounts.vault.key();
+    let registry = &mut ctx.accounts.vault_registry;
+    let index = registry
+        .vaults
+        .iter()
+        .position(|key| *key == vault_key)
+        .ok_or(VaultError::VaultNotInRegistry)?;
+    registry.vaults.remove(index);
+
+    // With no shares left, rounding dust and tokens sent to the vault belong to
+    // no one; the vault account itself is closed by the `close` constraint
+    let pool_key = ctx.accounts.vault.pool;
+    let bump = [ctx.accounts.vault.bump];
+    let seeds: &[&[u8]] = &[VAULT_SEED, pool_key.as_ref(), &bump];
+    let signer = &[seeds];
+    for (account, destination) in [
+        (&ctx.accounts.vault_token_a, &ctx.accounts.authority_token_a),
+        (&ctx.accounts.vault_token_b, &ctx.accounts.authority_token_b),
+    ] {
+        if account.amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: account.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    signer,
+                ),
+                account.amount,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: account.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            signer,
+        ))?;
+    }
+    Ok(())
+}
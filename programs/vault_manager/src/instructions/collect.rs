@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::constants::*;
+use crate::dex::{Dex, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter, WhirlpoolAdapter};
+use crate::protocol_fee::{CollectedFees, ProtocolFee};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct Collect<'info> {
+    #[account(
+        seeds = [VAULT_SEED, vault.pool.as_ref()], bump = vault.bump,
+        has_one = authority @ VaultError::Unauthorized, has_one = pool, has_one = position,
+        has_one = vault_token_a, has_one = vault_token_b,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    /// CHECK: Pool of `vault.dex` - checked by the DEX adapter
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: Vault position - checked by the DEX adapter
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Program of `vault.dex`
+    #[account(address = vault.dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+    #[account(seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    /// Receives the protocol's share of token A fees
+    #[account(mut, token::mint = vault.token_a_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_a: Box<Account<'info, TokenAccount>>,
+    /// Receives the protocol's share of token B fees
+    #[account(mut, token::mint = vault.token_b_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_b: Box<Account<'info, TokenAccount>>,
+}
+
+impl_dex_accounts!(Collect);
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, Collect<'info>>) -> Result<CollectedFees> {
+    match ctx.accounts.vault.dex {
+        Dex::Whirlpool => process_collect::<WhirlpoolAdapter>(ctx),
+        Dex::RaydiumClmm => process_collect::<RaydiumClmmAdapter>(ctx),
+        Dex::MeteoraDlmm => process_collect::<MeteoraDlmmAdapter>(ctx),
+    }
+}
+
+fn process_collect<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, Collect<'info>>,
+) -> Result<CollectedFees> {
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let position = dex.load_position(
+        ctx.accounts.position.to_account_info(),
+        &mut ctx.remaining_accounts.iter(),
+    )?;
+    ProtocolFee::new(
+        &ctx.accounts.protocol_config,
+        &ctx.accounts.treasury_token_a,
+        &ctx.accounts.treasury_token_b,
+    )
+    .collect_fees(
+        &dex,
+        &position,
+        &ctx.accounts.dex_accounts(),
+        &mut ctx.accounts.vault_token_a,
+        &mut ctx.accounts.vault_token_b,
+    )
+}
@@ -0,0 +1,361 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::dex::{Dex, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter, WhirlpoolAdapter};
+use crate::nav::{vault_nav, VaultNav};
+use crate::route::{self, RouteAccounts, SwapBalances, SwapRoute};
+use crate::state::*;
+use crate::{circuit_breaker, math, oracle, VaultError};
+
+#[derive(Accounts)]
+pub struct DepositAndAddLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.pool.as_ref()], bump = vault.bump,
+        has_one = pool, has_one = position, has_one = share_mint,
+        has_one = vault_token_a, has_one = vault_token_b,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::authority = user,
+        constraint = user_token_account.mint == vault.token_a_mint
+            || user_token_account.mint == vault.token_b_mint @ VaultError::MintMismatch,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = share_mint, token::authority = user)]
+    pub user_share_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+    /// CHECK: Pool of `vault.dex` - checked by the DEX adapter
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: Vault position - checked by the DEX adapter
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    /// CHECK: External price feed - must match `vault.config.price_oracle`, parsed by the guard
+    pub price_oracle: Option<UncheckedAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Program of `vault.dex`
+    #[account(address = vault.dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+    /// Required while the vault's allowlist is enabled
+    #[account(
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+    #[account(
+        init_if_needed, payer = user, space = 8 + UserPosition::INIT_SPACE,
+        seeds = [USER_POSITION_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl_dex_accounts!(DepositAndAddLiquidity);
+
+/// Where a deposit's zap swap runs: through the vault's pool with a minimum
+/// output, or through a route, which carries its own.
+pub enum ZapSwap {
+    Pool { min_swap_out: u64 },
+    Route(SwapRoute),
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositAndAddLiquidity<'info>>,
+    amount: u64,
+    min_shares_out: u64,
+    swap: ZapSwap,
+) -> Result<()> {
+    match ctx.accounts.vault.dex {
+        Dex::Whirlpool => process_deposit::<WhirlpoolAdapter>(ctx, amount, min_shares_out, swap),
+        Dex::RaydiumClmm => {
+            process_deposit::<RaydiumClmmAdapter>(ctx, amount, min_shares_out, swap)
+        }
+        Dex::MeteoraDlmm => {
+            process_deposit::<MeteoraDlmmAdapter>(ctx, amount, min_shares_out, swap)
+        }
+    }
+}
+
+/// A deposit's zap swap with its accounts loaded.
+enum Zap<'info, S> {
+    Pool(S, u64),
+    Route(RouteAccounts<'info>, SwapRoute),
+}
+
+fn process_deposit<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, DepositAndAddLiquidity<'info>>,
+    amount: u64,
+    min_shares_out: u64,
+    swap: ZapSwap,
+) -> Result<()> {
+    circuit_breaker::require_not_paused(&ctx.accounts.vault)?;
+    if ctx.accounts.vault.config.allowlist_enabled {
+        require!(
+            ctx.accounts.allowlist_entry.is_some(),
+            VaultError::NotAllowlisted
+        );
+    }
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let mut remaining = ctx.remaining_accounts.iter();
+    let position = dex.load_position(ctx.accounts.position.to_account_info(), &mut remaining)?;
+    let zap = match swap {
+        ZapSwap::Route(route) => Zap::Route(
+            RouteAccounts::load(
+                &ctx.accounts.vault.key(),
+                &ctx.accounts.vault,
+                &mut remaining,
+            )?,
+            route,
+        ),
+        ZapSwap::Pool { min_swap_out } => Zap::Pool(dex.load_swap(&mut remaining)?, min_swap_out),
+    };
+
+    let sqrt_price = dex.price()?.sqrt_price;
+    oracle::guard_pool_price(
+        &ctx.accounts.vault,
+        ctx.accounts.price_oracle.as_deref(),
+        sqrt_price,
+    )?;
+    let reference_price = oracle::reference_price_x64(
+        &ctx.accounts.vault,
+        ctx.accounts.price_oracle.as_deref(),
+        sqrt_price,
+    )?;
+
+    let input_mint = ctx.accounts.user_token_account.mint;
+    let is_deposit_a = input_mint == ctx.accounts.vault.token_a_mint;
+    let (deposit_a, deposit_b) = if is_deposit_a {
+        (amount, 0)
+    } else {
+        (0, amount)
+    };
+
+    // Price the deposit against the vault before any funds move
+    let nav = vault_nav(
+        &dex,
+        &position,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+        ctx.accounts.share_mint.supply,
+    )?;
+    let clock = Clock::get()?;
+    let vault_key = ctx.accounts.vault.key();
+    if circuit_breaker::observe_nav(&mut ctx.accounts.vault, vault_key, &nav, clock.slot)? {
+        // Nothing is deposited, so a receipt created by this call has nothing to record
+        if ctx.accounts.user_position.vault == Pubkey::default() {
+            ctx.accounts
+                .user_position
+                .close(ctx.accounts.user.to_account_info())?;
+        }
+        return Ok(());
+    }
+    let deposit_value = if is_deposit_a {
+        math::a_to_b_value(amount, sqrt_price)?
+    } else {
+        u128::from(amount)
+    };
+    check_deposit_limits(
+        &ctx.accounts.vault,
+        &nav,
+        deposit_value,
+        ctx.accounts.user_position.shares,
+    )?;
+    let mut shares = nav.shares_for_value(deposit_value)?;
+    require!(shares > 0, VaultError::ZeroShares);
+
+    // Transfer deposit token to vault
+    let vault_token_input = if is_deposit_a {
+        ctx.accounts.vault_token_a.to_account_info()
+    } else {
+        ctx.accounts.vault_token_b.to_account_info()
+    };
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.user_token_account.to_account_info(),
+        to: vault_token_input,
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, amount)?;
+
+    // Swap 50% of the input to the paired token, rounded down
+    let swap_amount = amount / 2;
+    if swap_amount > 0 {
+        // Shares are priced on what the swap left, at the price it left, so its
+        // fee and price impact are the depositor's, whichever way it went
+        let (held_a, held_b) = match &zap {
+            Zap::Pool(swap_accounts, min_swap_out) => {
+                dex.swap(swap_accounts, swap_amount, is_deposit_a, *min_swap_out)?;
+                ctx.accounts.vault_token_a.reload()?;
+                ctx.accounts.vault_token_b.reload()?;
+                (
+                    math::checked_sub(ctx.accounts.vault_token_a.amount, nav.idle_a)?,
+                    math::checked_sub(ctx.accounts.vault_token_b.amount, nav.idle_b)?,
+                )
+            }
+            Zap::Route(route_accounts, route) => {
+                let (spent, received) = swap_via_route(
+                    ctx.accounts,
+                    route_accounts,
+                    route,
+                    swap_amount,
+                    is_deposit_a,
+                )?;
+                let kept = math::checked_sub(amount, spent)?;
+                if is_deposit_a {
+                    (kept, received)
+                } else {
+                    (received, kept)
+                }
+            }
+        };
+        ctx.accounts.vault_token_a.reload()?;
+        ctx.accounts.vault_token_b.reload()?;
+        // Everything the vault holds now, the deposit included
+        let after = vault_nav(
+            &dex,
+            &position,
+            ctx.accounts.vault_token_a.amount,
+            ctx.accounts.vault_token_b.amount,
+            ctx.accounts.share_mint.supply,
+        )?;
+        let value = math::value_in_b(held_a, held_b, after.sqrt_price)?;
+        shares = after.shares_for_held_value(value)?;
+        require!(shares > 0, VaultError::ZeroShares);
+    }
+
+    require!(shares >= min_shares_out, VaultError::SharesBelowMinimum);
+
+    // Add all idle balances as liquidity at the post-swap price
+    ctx.accounts.vault_token_a.reload()?;
+    ctx.accounts.vault_token_b.reload()?;
+    let liquidity = dex.increase_liquidity(
+        &position,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+    )?;
+    if liquidity > 0 {
+        ctx.accounts.vault.is_active = true;
+    }
+
+    // Mint vault shares to the depositor
+    let pool_key = ctx.accounts.vault.pool;
+    let bump = [ctx.accounts.vault.bump];
+    let seeds: &[&[u8]] = &[VAULT_SEED, pool_key.as_ref(), &bump];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.user_share_account.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            &[seeds],
+        ),
+        shares,
+    )?;
+
+    let user_key = ctx.accounts.user.key();
+    let user_position = &mut ctx.accounts.user_position;
+    user_position.init_if_new(vault_key, user_key, ctx.bumps.user_position);
+    user_position.record_deposit(
+        deposit_a,
+        deposit_b,
+        math::value_at_price(deposit_a, deposit_b, reference_price)?,
+        shares,
+        clock.unix_timestamp,
+        clock.slot,
+    )?;
+
+    Ok(())
+}
+
+/// Applies the vault's minimum deposit, TVL cap and per-user cap to a deposit
+/// worth `deposit_value` into a vault valued at `nav`, by a user whose receipt
+/// tracks `user_shares`.
+pub(crate) fn check_deposit_limits(
+    vault: &Vault,
+    nav: &VaultNav,
+    deposit_value: u128,
+    user_shares: u64,
+) -> Result<()> {
+    require!(
+        deposit_value >= u128::from(vault.config.min_deposit),
+        VaultError::DepositTooSmall
+    );
+    if vault.config.deposit_cap > 0 {
+        let total_value = nav
+            .total_value_b
+            .checked_add(deposit_value)
+            .ok_or(VaultError::MathOverflow)?;
+        require!(
+            total_value <= u128::from(vault.config.deposit_cap),
+            VaultError::DepositCapExceeded
+        );
+    }
+    if vault.config.user_deposit_cap > 0 {
+        let user_value = nav
+            .value_of_shares(user_shares)?
+            .checked_add(deposit_value)
+            .ok_or(VaultError::MathOverflow)?;
+        require!(
+            user_value <= u128::from(vault.config.user_deposit_cap),
+            VaultError::UserDepositCapExceeded
+        );
+    }
+    Ok(())
+}
+
+/// Runs a deposit's zap swap through `route` and returns the amounts spent and
+/// received, checked from the vault token balances.
+fn swap_via_route<'info>(
+    accounts: &mut DepositAndAddLiquidity<'info>,
+    route_accounts: &RouteAccounts<'info>,
+    route: &SwapRoute,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Result<(u64, u64)> {
+    let balances = |accounts: &DepositAndAddLiquidity| {
+        let (a, b) = (accounts.vault_token_a.amount, accounts.vault_token_b.amount);
+        if a_to_b {
+            SwapBalances {
+                input: a,
+                output: b,
+            }
+        } else {
+            SwapBalances {
+                input: b,
+                output: a,
+            }
+        }
+    };
+    accounts.vault_token_a.reload()?;
+    accounts.vault_token_b.reload()?;
+    let before = balances(accounts);
+
+    let instruction = route_accounts.instruction(&accounts.vault.key(), route.data.clone());
+    let pool_key = accounts.vault.pool;
+    let bump = [accounts.vault.bump];
+    let seeds: &[&[u8]] = &[VAULT_SEED, pool_key.as_ref(), &bump];
+    route_accounts.invoke(&instruction, &[seeds])?;
+
+    accounts.vault_token_a.reload()?;
+    accounts.vault_token_b.reload()?;
+    route::check_swap(before, balances(accounts), amount_in, route.min_amount_out)
+}
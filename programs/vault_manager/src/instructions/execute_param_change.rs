@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::governance::ParamChangeExecuted;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct ExecuteParamChange<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        close = payer,
+        has_one = vault,
+        seeds = [PARAM_CHANGE_SEED, vault.key().as_ref()],
+        bump = pending_change.bump,
+    )]
+    pub pending_change: Account<'info, PendingParamChange>,
+    /// Written by `StrategyConfig` changes, created on the first one
+    #[account(
+        init_if_needed, payer = payer, space = 8 + StrategyConfig::INIT_SPACE,
+        seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub strategy_config: Option<Account<'info, StrategyConfig>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+pub fn handler(ctx: Context<ExecuteParamChange>) -> Result<()> {
+    let pending = &ctx.accounts.pending_change;
+    require!(
+        Clock::get()?.slot >= pending.execute_after_slot,
+        VaultError::ParamChangeNotReady
+    );
+    let vault_key = ctx.accounts.vault.key();
+    let bump = ctx.bumps.strategy_config;
+    let strategy_config = ctx.accounts.strategy_config.as_mut().map(|config| {
+        config.vault = vault_key;
+        config.bump = bump.unwrap_or(config.bump);
+        &mut **config
+    });
+    pending
+        .change
+        .apply(&mut ctx.accounts.vault, strategy_config)?;
+    emit!(ParamChangeExecuted {
+        vault: ctx.accounts.vault.key(),
+        change: pending.change.clone(),
+    });
+    Ok(())
+}
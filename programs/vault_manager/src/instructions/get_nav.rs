@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::dex::{Dex, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter, WhirlpoolAdapter};
+use crate::nav::{vault_nav, VaultNav};
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct GetNav<'info> {
+    #[account(
+        seeds = [VAULT_SEED, vault.pool.as_ref()], bump = vault.bump,
+        has_one = pool, has_one = position, has_one = share_mint,
+        has_one = vault_token_a, has_one = vault_token_b,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Pool of `vault.dex` - checked by the DEX adapter
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: Vault position - checked by the DEX adapter
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    pub vault_token_a: Account<'info, TokenAccount>,
+    pub vault_token_b: Account<'info, TokenAccount>,
+    pub share_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Program of `vault.dex`
+    #[account(address = vault.dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+}
+
+impl_dex_accounts!(GetNav);
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, GetNav<'info>>) -> Result<VaultNav> {
+    match ctx.accounts.vault.dex {
+        Dex::Whirlpool => process_get_nav::<WhirlpoolAdapter>(ctx),
+        Dex::RaydiumClmm => process_get_nav::<RaydiumClmmAdapter>(ctx),
+        Dex::MeteoraDlmm => process_get_nav::<MeteoraDlmmAdapter>(ctx),
+    }
+}
+
+fn process_get_nav<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, GetNav<'info>>,
+) -> Result<VaultNav> {
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let position = dex.load_position(
+        ctx.accounts.position.to_account_info(),
+        &mut ctx.remaining_accounts.iter(),
+    )?;
+    vault_nav(
+        &dex,
+        &position,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+        ctx.accounts.share_mint.supply,
+    )
+}
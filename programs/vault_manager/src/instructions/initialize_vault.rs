@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::dex::{
+    Dex, DexAdapter, MeteoraDlmmAdapter, OpenPositionAccounts, RaydiumClmmAdapter, WhirlpoolAdapter,
+};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+#[instruction(dex: Dex)]
+pub struct InitializeVault<'info> {
+    #[account(
+        init, payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [VAULT_SEED, pool.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump,
+        constraint = protocol_config.allows_dex_program(&dex.program_id())
+            @ VaultError::DexNotAllowed,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    #[account(
+        init_if_needed, payer = payer, space = 8 + VaultRegistry::INIT_SPACE,
+        seeds = [VAULT_REGISTRY_SEED, &protocol_config.registry_page().to_le_bytes()],
+        bump
+    )]
+    pub vault_registry: Box<Account<'info, VaultRegistry>>,
+    /// CHECK: Pool of the selected DEX - checked by the DEX adapter
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    pub token_a_mint: Account<'info, Mint>,
+    pub token_b_mint: Account<'info, Mint>,
+    #[account(mut, token::mint = token_a_mint, token::authority = vault)]
+    pub vault_token_a: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = token_b_mint, token::authority = vault)]
+    pub vault_token_b: Account<'info, TokenAccount>,
+    #[account(
+        init, payer = payer,
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref()],
+        bump,
+        mint::decimals = token_b_mint.decimals,
+        mint::authority = vault,
+    )]
+    pub share_mint: Account<'info, Mint>,
+    /// CHECK: Position created by the DEX program - its address is checked by the DEX
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: New position NFT mint, signing to be created - unused on DLMM, which has no NFT
+    #[account(mut)]
+    pub position_mint: UncheckedAccount<'info>,
+    /// CHECK: Vault's associated token account for the NFT, created by the DEX program
+    #[account(mut)]
+    pub position_token_account: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    /// Any signer, including a multisig program's PDA; the payer funds the accounts
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: Program of `dex`
+    #[account(address = dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl_dex_accounts!(InitializeVault);
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, InitializeVault<'info>>,
+    dex: Dex,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<()> {
+    let protocol_config = &mut ctx.accounts.protocol_config;
+    let registry = &mut ctx.accounts.vault_registry;
+    registry.page = protocol_config.registry_page();
+    registry.bump = ctx.bumps.vault_registry;
+    registry.vaults.push(ctx.accounts.vault.key());
+    protocol_config.vault_count = protocol_config
+        .vault_count
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let vault = &mut ctx.accounts.vault;
+    vault.authority = ctx.accounts.authority.key();
+    vault.dex = dex;
+    vault.pool = ctx.accounts.pool.key();
+    vault.token_a_mint = ctx.accounts.token_a_mint.key();
+    vault.token_b_mint = ctx.accounts.token_b_mint.key();
+    vault.vault_token_a = ctx.accounts.vault_token_a.key();
+    vault.vault_token_b = ctx.accounts.vault_token_b.key();
+    vault.position = ctx.accounts.position.key();
+    vault.share_mint = ctx.accounts.share_mint.key();
+    vault.token_a_decimals = ctx.accounts.token_a_mint.decimals;
+    vault.token_b_decimals = ctx.accounts.token_b_mint.decimals;
+    vault.bump = ctx.bumps.vault;
+    vault.is_active = false;
+    match dex {
+        Dex::Whirlpool => open_vault_position::<WhirlpoolAdapter>(ctx, tick_lower, tick_upper),
+        Dex::RaydiumClmm => open_vault_position::<RaydiumClmmAdapter>(ctx, tick_lower, tick_upper),
+        Dex::MeteoraDlmm => open_vault_position::<MeteoraDlmmAdapter>(ctx, tick_lower, tick_upper),
+    }
+}
+
+/// Opens the position `initialize_vault` recorded in `vault.position`.
+fn open_vault_position<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, InitializeVault<'info>>,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<()> {
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let accounts = &ctx.accounts;
+    dex.open_position(
+        OpenPositionAccounts {
+            funder: accounts.payer.to_account_info(),
+            position: accounts.position.to_account_info(),
+            position_mint: accounts.position_mint.to_account_info(),
+            position_token_account: accounts.position_token_account.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            rent: accounts.rent.to_account_info(),
+            associated_token_program: accounts.associated_token_program.to_account_info(),
+        },
+        &mut ctx.remaining_accounts.iter(),
+        tick_lower,
+        tick_upper,
+    )
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::allowlist;
+use crate::constants::*;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct JoinAllowlist<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init, payer = wallet, space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [ALLOWLIST_SEED, vault.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<JoinAllowlist>, proof: Vec<[u8; 32]>) -> Result<()> {
+    let root = ctx.accounts.vault.config.allowlist_merkle_root;
+    let wallet = ctx.accounts.wallet.key();
+    require!(
+        root != [0; 32] && allowlist::verify(&root, &proof, &wallet),
+        VaultError::InvalidAllowlistProof
+    );
+    *ctx.accounts.allowlist_entry = AllowlistEntry {
+        vault: ctx.accounts.vault.key(),
+        wallet,
+        bump: ctx.bumps.allowlist_entry,
+    };
+    Ok(())
+}
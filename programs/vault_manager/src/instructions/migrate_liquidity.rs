@@ -0,0 +1,450 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::dex::{
+    Dex, DexAccounts, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter, WhirlpoolAdapter,
+};
+use crate::instructions::deposit::check_deposit_limits;
+use crate::instructions::withdraw::redeem_shares;
+use crate::nav::vault_nav;
+use crate::protocol_fee::ProtocolFee;
+use crate::state::*;
+use crate::{circuit_breaker, lockup, math, oracle, twap, VaultError};
+
+#[derive(Accounts)]
+pub struct MigrateLiquidity<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, source_vault.pool.as_ref()], bump = source_vault.bump,
+    )]
+    pub source_vault: Box<Account<'info, Vault>>,
+    #[account(mut, address = source_vault.share_mint)]
+    pub source_share_mint: Box<Account<'info, Mint>>,
+    #[account(mut, token::mint = source_share_mint, token::authority = user)]
+    pub user_source_share_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = source_vault.vault_token_a)]
+    pub source_vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = source_vault.vault_token_b)]
+    pub source_vault_token_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Pool of `source_vault.dex` - checked by the DEX adapter
+    #[account(mut, address = source_vault.pool)]
+    pub source_pool: UncheckedAccount<'info>,
+    /// CHECK: Source vault position - checked by the DEX adapter
+    #[account(mut, address = source_vault.position)]
+    pub source_position: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub source_pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub source_pool_token_vault_b: UncheckedAccount<'info>,
+    /// CHECK: External price feed - must match `source_vault.config.price_oracle`, parsed by the
+    /// guard
+    pub source_price_oracle: Option<UncheckedAccount<'info>>,
+    /// CHECK: Program of `source_vault.dex`
+    #[account(address = source_vault.dex.program_id())]
+    pub source_dex_program: UncheckedAccount<'info>,
+    /// Receipt of the user's source vault deposits, created for shares received
+    /// by transfer
+    #[account(
+        init_if_needed, payer = user, space = 8 + UserPosition::INIT_SPACE,
+        seeds = [USER_POSITION_SEED, source_vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub source_user_position: Box<Account<'info, UserPosition>>,
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, destination_vault.pool.as_ref()], bump = destination_vault.bump,
+        constraint = destination_vault.key() != source_vault.key() @ VaultError::SameVault,
+        constraint = destination_vault.token_a_mint == source_vault.token_a_mint
+            && destination_vault.token_b_mint == source_vault.token_b_mint
+            @ VaultError::MintMismatch,
+    )]
+    pub destination_vault: Box<Account<'info, Vault>>,
+    #[account(mut, address = destination_vault.share_mint)]
+    pub destination_share_mint: Box<Account<'info, Mint>>,
+    #[account(mut, token::mint = destination_share_mint, token::authority = user)]
+    pub user_destination_share_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = destination_vault.vault_token_a)]
+    pub destination_vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = destination_vault.vault_token_b)]
+    pub destination_vault_token_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Pool of `destination_vault.dex` - checked by the DEX adapter
+    #[account(mut, address = destination_vault.pool)]
+    pub destination_pool: UncheckedAccount<'info>,
+    /// CHECK: Destination vault position - checked by the DEX adapter
+    #[account(mut, address = destination_vault.position)]
+    pub destination_position: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub destination_pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub destination_pool_token_vault_b: UncheckedAccount<'info>,
+    /// CHECK: External price feed - must match `destination_vault.config.price_oracle`, parsed by
+    /// the guard
+    pub destination_price_oracle: Option<UncheckedAccount<'info>>,
+    /// CHECK: Program of `destination_vault.dex`
+    #[account(address = destination_vault.dex.program_id())]
+    pub destination_dex_program: UncheckedAccount<'info>,
+    /// Required while the destination vault's allowlist is enabled
+    #[account(
+        seeds = [ALLOWLIST_SEED, destination_vault.key().as_ref(), user.key().as_ref()],
+        bump = destination_allowlist_entry.bump,
+    )]
+    pub destination_allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+    pub token_program: Program<'info, Token>,
+    #[account(
+        init_if_needed, payer = user, space = 8 + UserPosition::INIT_SPACE,
+        seeds = [USER_POSITION_SEED, destination_vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub destination_user_position: Box<Account<'info, UserPosition>>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    /// Receives the protocol's share of the source vault's token A fees
+    #[account(mut, token::mint = source_vault.token_a_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_a: Box<Account<'info, TokenAccount>>,
+    /// Receives the protocol's share of the source vault's token B fees
+    #[account(mut, token::mint = source_vault.token_b_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_b: Box<Account<'info, TokenAccount>>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigrateLiquidity<'info>>,
+    shares: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    min_swap_out: u64,
+    min_shares_out: u64,
+) -> Result<()> {
+    let bounds = MigrationBounds {
+        min_amount_a,
+        min_amount_b,
+        min_swap_out,
+        min_shares_out,
+    };
+    match ctx.accounts.source_vault.dex {
+        Dex::Whirlpool => migrate_from::<WhirlpoolAdapter>(ctx, shares, bounds),
+        Dex::RaydiumClmm => migrate_from::<RaydiumClmmAdapter>(ctx, shares, bounds),
+        Dex::MeteoraDlmm => migrate_from::<MeteoraDlmmAdapter>(ctx, shares, bounds),
+    }
+}
+
+/// Slippage bounds of a migration.
+struct MigrationBounds {
+    /// Minimum token A and B redeemed from the source vault.
+    min_amount_a: u64,
+    min_amount_b: u64,
+    /// Minimum output of the destination swap, if one is needed.
+    min_swap_out: u64,
+    /// Minimum destination shares minted.
+    min_shares_out: u64,
+}
+
+/// Dispatches on the destination backend once the source backend is known.
+fn migrate_from<'info, S: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, MigrateLiquidity<'info>>,
+    shares: u64,
+    bounds: MigrationBounds,
+) -> Result<()> {
+    match ctx.accounts.destination_vault.dex {
+        Dex::Whirlpool => process_migrate::<S, WhirlpoolAdapter>(ctx, shares, bounds),
+        Dex::RaydiumClmm => process_migrate::<S, RaydiumClmmAdapter>(ctx, shares, bounds),
+        Dex::MeteoraDlmm => process_migrate::<S, MeteoraDlmmAdapter>(ctx, shares, bounds),
+    }
+}
+
+fn process_migrate<'info, S: DexAdapter<'info>, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, MigrateLiquidity<'info>>,
+    shares: u64,
+    bounds: MigrationBounds,
+) -> Result<()> {
+    require!(shares > 0, VaultError::ZeroShares);
+    circuit_breaker::require_not_paused(&ctx.accounts.destination_vault)?;
+    if ctx.accounts.destination_vault.config.allowlist_enabled {
+        require!(
+            ctx.accounts.destination_allowlist_entry.is_some(),
+            VaultError::NotAllowlisted
+        );
+    }
+    let source = S::load(ctx.accounts.source_dex_accounts())?;
+    let destination = D::load(ctx.accounts.destination_dex_accounts())?;
+    let mut remaining = ctx.remaining_accounts.iter();
+    let source_position = source.load_position(
+        ctx.accounts.source_position.to_account_info(),
+        &mut remaining,
+    )?;
+    let destination_position = destination.load_position(
+        ctx.accounts.destination_position.to_account_info(),
+        &mut remaining,
+    )?;
+    let swap_accounts = destination.load_swap(&mut remaining)?;
+
+    oracle::guard_pool_price(
+        &ctx.accounts.source_vault,
+        ctx.accounts.source_price_oracle.as_deref(),
+        source.price()?.sqrt_price,
+    )?;
+    let price = destination.price()?;
+    oracle::guard_pool_price(
+        &ctx.accounts.destination_vault,
+        ctx.accounts.destination_price_oracle.as_deref(),
+        price.sqrt_price,
+    )?;
+    let reference_price = oracle::reference_price_x64(
+        &ctx.accounts.destination_vault,
+        ctx.accounts.destination_price_oracle.as_deref(),
+        price.sqrt_price,
+    )?;
+    twap::guard_twap(
+        &ctx.accounts.destination_vault,
+        &destination,
+        &swap_accounts,
+        price.tick_current,
+    )?;
+
+    // Both vaults watch their NAV; only a paused destination stops the migration.
+    // The destination NAV prices the migrated tokens once they are swapped.
+    let clock = Clock::get()?;
+    let source_nav = vault_nav(
+        &source,
+        &source_position,
+        ctx.accounts.source_vault_token_a.amount,
+        ctx.accounts.source_vault_token_b.amount,
+        ctx.accounts.source_share_mint.supply,
+    )?;
+    let source_key = ctx.accounts.source_vault.key();
+    let source_vault = &mut ctx.accounts.source_vault;
+    circuit_breaker::observe_nav(source_vault, source_key, &source_nav, clock.slot)?;
+    let nav = vault_nav(
+        &destination,
+        &destination_position,
+        ctx.accounts.destination_vault_token_a.amount,
+        ctx.accounts.destination_vault_token_b.amount,
+        ctx.accounts.destination_share_mint.supply,
+    )?;
+    let destination_key = ctx.accounts.destination_vault.key();
+    let destination_vault = &mut ctx.accounts.destination_vault;
+    if circuit_breaker::observe_nav(destination_vault, destination_key, &nav, clock.slot)? {
+        // Nothing moves, so receipts created by this call have nothing to record
+        for user_position in [
+            &mut ctx.accounts.source_user_position,
+            &mut ctx.accounts.destination_user_position,
+        ] {
+            if user_position.vault == Pubkey::default() {
+                user_position.close(ctx.accounts.user.to_account_info())?;
+            }
+        }
+        return Ok(());
+    }
+
+    let user_key = ctx.accounts.user.key();
+    let source_user_position = &mut ctx.accounts.source_user_position;
+    source_user_position.init_if_new(source_key, user_key, ctx.bumps.source_user_position);
+    let paid_shares = lockup::guard_withdrawal(
+        &ctx.accounts.source_vault,
+        source_user_position,
+        shares,
+        clock.slot,
+    )?;
+    let protocol_fee = ProtocolFee::new(
+        &ctx.accounts.protocol_config,
+        &ctx.accounts.treasury_token_a,
+        &ctx.accounts.treasury_token_b,
+    );
+    let (amount_a, amount_b) = redeem_shares(
+        &source,
+        &source_position,
+        (&ctx.accounts.source_dex_accounts(), &protocol_fee),
+        &mut ctx.accounts.source_vault,
+        &mut ctx.accounts.source_vault_token_a,
+        &mut ctx.accounts.source_vault_token_b,
+        ctx.accounts.source_share_mint.supply,
+        paid_shares,
+        (bounds.min_amount_a, bounds.min_amount_b),
+    )?;
+    require!(
+        amount_a >= bounds.min_amount_a && amount_b >= bounds.min_amount_b,
+        VaultError::SlippageExceeded
+    );
+
+    check_deposit_limits(
+        &ctx.accounts.destination_vault,
+        &nav,
+        math::value_in_b(amount_a, amount_b, price.sqrt_price)?,
+        ctx.accounts.destination_user_position.shares,
+    )?;
+    ctx.accounts
+        .source_user_position
+        .record_withdraw(shares, amount_a, amount_b)?;
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.source_share_mint.to_account_info(),
+                from: ctx.accounts.user_source_share_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    // Hand the redeemed tokens straight to the destination vault
+    let source_pool = ctx.accounts.source_vault.pool;
+    let source_bump = [ctx.accounts.source_vault.bump];
+    let source_seeds: &[&[u8]] = &[VAULT_SEED, source_pool.as_ref(), &source_bump];
+    let signer = &[source_seeds];
+    if amount_a > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source_vault_token_a.to_account_info(),
+                    to: ctx.accounts.destination_vault_token_a.to_account_info(),
+                    authority: ctx.accounts.source_vault.to_account_info(),
+                },
+                signer,
+            ),
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source_vault_token_b.to_account_info(),
+                    to: ctx.accounts.destination_vault_token_b.to_account_info(),
+                    authority: ctx.accounts.source_vault.to_account_info(),
+                },
+                signer,
+            ),
+            amount_b,
+        )?;
+    }
+
+    // Swap the migrated tokens toward the destination range's ratio; the
+    // destination's own idle balances are its holders' and stay out of it
+    let range = destination.position_range(&destination_position)?;
+    let swap = math::rebalance_swap(
+        price.sqrt_price,
+        range.sqrt_lower,
+        range.sqrt_upper,
+        amount_a,
+        amount_b,
+    )?;
+    if let Some((amount, a_to_b)) = swap {
+        destination.swap(&swap_accounts, amount, a_to_b, bounds.min_swap_out)?;
+    }
+
+    // The swap's fee and price impact are the migrating user's, as in a deposit,
+    // so what it left is priced against the destination as it left it. It
+    // spends at most the migrated tokens, so the balances still cover the idle
+    // ones the NAV counted
+    ctx.accounts.destination_vault_token_a.reload()?;
+    ctx.accounts.destination_vault_token_b.reload()?;
+    let held_a = math::checked_sub(ctx.accounts.destination_vault_token_a.amount, nav.idle_a)?;
+    let held_b = math::checked_sub(ctx.accounts.destination_vault_token_b.amount, nav.idle_b)?;
+    let after = vault_nav(
+        &destination,
+        &destination_position,
+        ctx.accounts.destination_vault_token_a.amount,
+        ctx.accounts.destination_vault_token_b.amount,
+        ctx.accounts.destination_share_mint.supply,
+    )?;
+    let shares_out =
+        after.shares_for_held_value(math::value_in_b(held_a, held_b, after.sqrt_price)?)?;
+    require!(shares_out > 0, VaultError::ZeroShares);
+    require!(
+        shares_out >= bounds.min_shares_out,
+        VaultError::SharesBelowMinimum
+    );
+
+    // Add all idle balances as liquidity at the post-swap price
+    let liquidity = destination.increase_liquidity(
+        &destination_position,
+        ctx.accounts.destination_vault_token_a.amount,
+        ctx.accounts.destination_vault_token_b.amount,
+    )?;
+    if liquidity > 0 {
+        ctx.accounts.destination_vault.is_active = true;
+    }
+
+    let destination_pool = ctx.accounts.destination_vault.pool;
+    let destination_bump = [ctx.accounts.destination_vault.bump];
+    let destination_seeds: &[&[u8]] = &[VAULT_SEED, destination_pool.as_ref(), &destination_bump];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.destination_share_mint.to_account_info(),
+                to: ctx
+                    .accounts
+                    .user_destination_share_account
+                    .to_account_info(),
+                authority: ctx.accounts.destination_vault.to_account_info(),
+            },
+            &[destination_seeds],
+        ),
+        shares_out,
+    )?;
+
+    let user_position = &mut ctx.accounts.destination_user_position;
+    user_position.init_if_new(
+        destination_key,
+        user_key,
+        ctx.bumps.destination_user_position,
+    );
+    user_position.record_deposit(
+        amount_a,
+        amount_b,
+        math::value_at_price(amount_a, amount_b, reference_price)?,
+        shares_out,
+        clock.unix_timestamp,
+        clock.slot,
+    )?;
+
+    Ok(())
+}
+
+impl<'info> MigrateLiquidity<'info> {
+    fn source_dex_accounts(&self) -> DexAccounts<'info> {
+        DexAccounts {
+            vault: self.source_vault.to_account_info(),
+            vault_bump: self.source_vault.bump,
+            token_a_mint: self.source_vault.token_a_mint,
+            token_b_mint: self.source_vault.token_b_mint,
+            vault_token_a: self.source_vault_token_a.to_account_info(),
+            vault_token_b: self.source_vault_token_b.to_account_info(),
+            pool: self.source_pool.to_account_info(),
+            pool_token_vault_a: self.source_pool_token_vault_a.to_account_info(),
+            pool_token_vault_b: self.source_pool_token_vault_b.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            dex_program: self.source_dex_program.to_account_info(),
+            bin_strategy: self.source_vault.strategy.bin_strategy,
+        }
+    }
+
+    fn destination_dex_accounts(&self) -> DexAccounts<'info> {
+        DexAccounts {
+            vault: self.destination_vault.to_account_info(),
+            vault_bump: self.destination_vault.bump,
+            token_a_mint: self.destination_vault.token_a_mint,
+            token_b_mint: self.destination_vault.token_b_mint,
+            vault_token_a: self.destination_vault_token_a.to_account_info(),
+            vault_token_b: self.destination_vault_token_b.to_account_info(),
+            pool: self.destination_pool.to_account_info(),
+            pool_token_vault_a: self.destination_pool_token_vault_a.to_account_info(),
+            pool_token_vault_b: self.destination_pool_token_vault_b.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            dex_program: self.destination_dex_program.to_account_info(),
+            bin_strategy: self.destination_vault.strategy.bin_strategy,
+        }
+    }
+}
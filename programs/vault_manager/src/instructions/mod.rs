@@ -1,3 +1,90 @@
-pub mod initialize;
+//! One module per instruction, each with its Accounts struct and `handler`.
+//!
+//! The glob re-exports make the Accounts structs available at the crate root,
+//! as `#[program]` needs. Every module exports a `handler`, so callers name it
+//! through its module.
+#![allow(ambiguous_glob_reexports)]
 
-pub use initialize::*;
+/// Builds the `DexAccounts` of an instruction from its named accounts.
+macro_rules! impl_dex_accounts {
+    ($($name:ident),*) => {
+        $(impl<'info> $name<'info> {
+            fn dex_accounts(&self) -> $crate::dex::DexAccounts<'info> {
+                $crate::dex::DexAccounts {
+                    vault: self.vault.to_account_info(),
+                    vault_bump: self.vault.bump,
+                    token_a_mint: self.vault.token_a_mint,
+                    token_b_mint: self.vault.token_b_mint,
+                    vault_token_a: self.vault_token_a.to_account_info(),
+                    vault_token_b: self.vault_token_b.to_account_info(),
+                    pool: self.pool.to_account_info(),
+                    pool_token_vault_a: self.pool_token_vault_a.to_account_info(),
+                    pool_token_vault_b: self.pool_token_vault_b.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                    dex_program: self.dex_program.to_account_info(),
+                    bin_strategy: self.vault.strategy.bin_strategy,
+                }
+            }
+        })*
+    };
+}
+
+pub mod add_to_allowlist;
+pub mod cancel_param_change;
+pub mod close_vault;
+pub mod collect;
+pub mod deposit;
+pub mod execute_param_change;
+pub mod get_nav;
+pub mod initialize_protocol_config;
+pub mod initialize_vault;
+pub mod join_allowlist;
+pub mod migrate_liquidity;
+pub mod queue_param_change;
+pub mod rebalance;
+pub mod remove_from_allowlist;
+pub mod request_withdrawal;
+pub mod set_allowlist;
+pub mod set_authority;
+pub mod set_bin_strategy;
+pub mod set_deposit_limits;
+pub mod set_lockup;
+pub mod set_nav_circuit_breaker;
+pub mod set_paused;
+pub mod set_price_oracle;
+pub mod set_protocol_admin;
+pub mod set_protocol_config;
+pub mod set_strategy_config;
+pub mod set_swap_programs;
+pub mod set_twap_guard;
+pub mod withdraw;
+
+pub use add_to_allowlist::*;
+pub use cancel_param_change::*;
+pub use close_vault::*;
+pub use collect::*;
+pub use deposit::*;
+pub use execute_param_change::*;
+pub use get_nav::*;
+pub use initialize_protocol_config::*;
+pub use initialize_vault::*;
+pub use join_allowlist::*;
+pub use migrate_liquidity::*;
+pub use queue_param_change::*;
+pub use rebalance::*;
+pub use remove_from_allowlist::*;
+pub use request_withdrawal::*;
+pub use set_allowlist::*;
+pub use set_authority::*;
+pub use set_bin_strategy::*;
+pub use set_deposit_limits::*;
+pub use set_lockup::*;
+pub use set_nav_circuit_breaker::*;
+pub use set_paused::*;
+pub use set_price_oracle::*;
+pub use set_protocol_admin::*;
+pub use set_protocol_config::*;
+pub use set_strategy_config::*;
+pub use set_swap_programs::*;
+pub use set_twap_guard::*;
+pub use withdraw::*;
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::governance::{ParamChange, ParamChangeQueued};
+use crate::oracle;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct QueueParamChange<'info> {
+    #[account(has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init, payer = payer, space = 8 + PendingParamChange::INIT_SPACE,
+        seeds = [PARAM_CHANGE_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub pending_change: Account<'info, PendingParamChange>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Feed of a `PriceOracle` change - layout is checked by the handler
+    pub price_oracle: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler(ctx: Context<QueueParamChange>, change: ParamChange) -> Result<()> {
+    change.validate(&ctx.accounts.vault)?;
+    if let ParamChange::PriceOracle { price_oracle, .. } = &change {
+        if *price_oracle != Pubkey::default() {
+            let info = ctx
+                .accounts
+                .price_oracle
+                .as_ref()
+                .ok_or(VaultError::OracleRequired)?;
+            require_keys_eq!(info.key(), *price_oracle, VaultError::InvalidOracle);
+            oracle::PriceFeed::load(info)?;
+        }
+    }
+    let slot = Clock::get()?.slot;
+    let timelock_slots = ctx.accounts.vault.config.param_timelock_slots;
+    let execute_after_slot = slot.saturating_add(timelock_slots);
+    emit!(ParamChangeQueued {
+        vault: ctx.accounts.vault.key(),
+        change: change.clone(),
+        execute_after_slot,
+    });
+    *ctx.accounts.pending_change = PendingParamChange {
+        vault: ctx.accounts.vault.key(),
+        change,
+        queued_slot: slot,
+        execute_after_slot,
+        bump: ctx.bumps.pending_change,
+    };
+    Ok(())
+}
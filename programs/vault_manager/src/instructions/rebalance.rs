@@ -0,0 +1,226 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::dex::{
+    ClosePositionAccounts, Dex, DexAdapter, MeteoraDlmmAdapter, OpenPositionAccounts,
+    RaydiumClmmAdapter, WhirlpoolAdapter,
+};
+use crate::nav::vault_nav;
+use crate::protocol_fee::ProtocolFee;
+use crate::state::*;
+use crate::{circuit_breaker, math, oracle, twap, VaultError};
+
+#[derive(Accounts)]
+pub struct Rebalance<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.pool.as_ref()], bump = vault.bump,
+        has_one = authority @ VaultError::Unauthorized, has_one = pool, has_one = position,
+        has_one = vault_token_a, has_one = vault_token_b, has_one = share_mint,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    /// Funds the new position and receives the current position's rent where
+    /// the DEX lets it be chosen
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Pool of `vault.dex` - checked by the DEX adapter
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: Vault position, closed once emptied - checked by the DEX adapter
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    pub share_mint: Account<'info, Mint>,
+    /// CHECK: Position created by the DEX program - its address is checked by the DEX
+    #[account(mut)]
+    pub new_position: UncheckedAccount<'info>,
+    /// CHECK: New position NFT mint, signing to be created - unused on DLMM, which has no NFT
+    #[account(mut)]
+    pub new_position_mint: UncheckedAccount<'info>,
+    /// CHECK: Vault's associated token account for the NFT, created by the DEX program
+    #[account(mut)]
+    pub new_position_token_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    /// CHECK: External price feed - must match `vault.config.price_oracle`, parsed by the guard
+    pub price_oracle: Option<UncheckedAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: Program of `vault.dex`
+    #[account(address = vault.dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+    #[account(seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    /// Receives the protocol's share of token A fees
+    #[account(mut, token::mint = vault.token_a_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_a: Box<Account<'info, TokenAccount>>,
+    /// Receives the protocol's share of token B fees
+    #[account(mut, token::mint = vault.token_b_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_b: Box<Account<'info, TokenAccount>>,
+}
+
+impl_dex_accounts!(Rebalance);
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Rebalance<'info>>,
+    tick_lower: i32,
+    tick_upper: i32,
+    min_amount_a: u64,
+    min_amount_b: u64,
+    min_swap_out: u64,
+) -> Result<()> {
+    let bounds = RebalanceBounds {
+        tick_lower,
+        tick_upper,
+        min_amount_a,
+        min_amount_b,
+        min_swap_out,
+    };
+    match ctx.accounts.vault.dex {
+        Dex::Whirlpool => process_rebalance::<WhirlpoolAdapter>(ctx, bounds),
+        Dex::RaydiumClmm => process_rebalance::<RaydiumClmmAdapter>(ctx, bounds),
+        Dex::MeteoraDlmm => process_rebalance::<MeteoraDlmmAdapter>(ctx, bounds),
+    }
+}
+
+/// New range and slippage bounds of a rebalance.
+struct RebalanceBounds {
+    /// Range of the position the liquidity moves into.
+    tick_lower: i32,
+    tick_upper: i32,
+    /// Minimum token A and B the removal from the current position pays out.
+    min_amount_a: u64,
+    min_amount_b: u64,
+    /// Minimum output of the swap toward the new range, if one is needed.
+    min_swap_out: u64,
+}
+
+fn process_rebalance<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, Rebalance<'info>>,
+    bounds: RebalanceBounds,
+) -> Result<()> {
+    require!(ctx.accounts.vault.is_active, VaultError::NoActivePosition);
+    circuit_breaker::require_not_paused(&ctx.accounts.vault)?;
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let mut remaining = ctx.remaining_accounts.iter();
+    let position = dex.load_position(ctx.accounts.position.to_account_info(), &mut remaining)?;
+    let swap_accounts = dex.load_swap(&mut remaining)?;
+
+    let price = dex.price()?;
+    oracle::guard_pool_price(
+        &ctx.accounts.vault,
+        ctx.accounts.price_oracle.as_deref(),
+        price.sqrt_price,
+    )?;
+    twap::guard_twap(
+        &ctx.accounts.vault,
+        &dex,
+        &swap_accounts,
+        price.tick_current,
+    )?;
+    let nav = vault_nav(
+        &dex,
+        &position,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+        ctx.accounts.share_mint.supply,
+    )?;
+    let vault_key = ctx.accounts.vault.key();
+    let slot = Clock::get()?.slot;
+    if circuit_breaker::observe_nav(&mut ctx.accounts.vault, vault_key, &nav, slot)? {
+        return Ok(());
+    }
+
+    // Collect fees and pull all liquidity out of the current position
+    let liquidity = dex.position_range(&position)?.liquidity;
+    ProtocolFee::new(
+        &ctx.accounts.protocol_config,
+        &ctx.accounts.treasury_token_a,
+        &ctx.accounts.treasury_token_b,
+    )
+    .collect_fees(
+        &dex,
+        &position,
+        &ctx.accounts.dex_accounts(),
+        &mut ctx.accounts.vault_token_a,
+        &mut ctx.accounts.vault_token_b,
+    )?;
+    if liquidity > 0 {
+        dex.decrease_liquidity(
+            &position,
+            liquidity,
+            bounds.min_amount_a,
+            bounds.min_amount_b,
+        )?;
+    }
+
+    // Open the new position, owned by the vault
+    let accounts = &ctx.accounts;
+    dex.open_position(
+        OpenPositionAccounts {
+            funder: accounts.payer.to_account_info(),
+            position: accounts.new_position.to_account_info(),
+            position_mint: accounts.new_position_mint.to_account_info(),
+            position_token_account: accounts.new_position_token_account.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            rent: accounts.rent.to_account_info(),
+            associated_token_program: accounts.associated_token_program.to_account_info(),
+        },
+        &mut remaining,
+        bounds.tick_lower,
+        bounds.tick_upper,
+    )?;
+    let new_position =
+        dex.load_position(ctx.accounts.new_position.to_account_info(), &mut remaining)?;
+
+    // Swap toward the token ratio of the new range
+    ctx.accounts.vault_token_a.reload()?;
+    ctx.accounts.vault_token_b.reload()?;
+    let range = dex.position_range(&new_position)?;
+    let swap = math::rebalance_swap(
+        dex.price()?.sqrt_price,
+        range.sqrt_lower,
+        range.sqrt_upper,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+    )?;
+    if let Some((amount, a_to_b)) = swap {
+        dex.swap(&swap_accounts, amount, a_to_b, bounds.min_swap_out)?;
+    }
+
+    // Re-add liquidity in the new range
+    ctx.accounts.vault_token_a.reload()?;
+    ctx.accounts.vault_token_b.reload()?;
+    let new_liquidity = dex.increase_liquidity(
+        &new_position,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+    )?;
+
+    // The emptied position would otherwise stay open with its rent
+    dex.close_position(
+        position,
+        ClosePositionAccounts {
+            receiver: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        },
+        &mut remaining,
+    )?;
+
+    let vault = &mut ctx.accounts.vault;
+    vault.position = ctx.accounts.new_position.key();
+    vault.is_active = new_liquidity > 0;
+    Ok(())
+}
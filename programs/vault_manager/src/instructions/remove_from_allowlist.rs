@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, has_one = vault, close = authority)]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(_ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+    Ok(())
+}
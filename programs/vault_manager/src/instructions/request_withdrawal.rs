@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    pub vault: Account<'info, Vault>,
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [USER_POSITION_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+}
+
+pub fn handler(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+    let user_position = &mut ctx.accounts.user_position;
+    require!(
+        shares <= user_position.shares,
+        VaultError::InsufficientShares
+    );
+    user_position.pending_withdrawal_shares = shares;
+    user_position.withdrawal_requested_slot = Clock::get()?.slot;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetAllowlist<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetAllowlist>, enabled: bool, merkle_root: [u8; 32]) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    vault.config.allowlist_enabled = enabled;
+    vault.config.allowlist_merkle_root = merkle_root;
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetAuthority<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+    ctx.accounts.vault.authority = new_authority;
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::dex::BinStrategy;
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetBinStrategy<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetBinStrategy>, strategy: BinStrategy) -> Result<()> {
+    governance::apply_now(&mut ctx.accounts.vault, ParamChange::BinStrategy(strategy))
+}
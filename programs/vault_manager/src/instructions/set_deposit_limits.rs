@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetDepositLimits<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<SetDepositLimits>,
+    min_deposit: u64,
+    user_deposit_cap: u64,
+    deposit_cap: u64,
+) -> Result<()> {
+    governance::apply_now(
+        &mut ctx.accounts.vault,
+        ParamChange::DepositLimits {
+            min_deposit,
+            user_deposit_cap,
+            deposit_cap,
+        },
+    )
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetLockup<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<SetLockup>,
+    lockup_slots: u64,
+    early_exit_fee_bps: u16,
+    withdrawal_delay_slots: u64,
+) -> Result<()> {
+    governance::apply_now(
+        &mut ctx.accounts.vault,
+        ParamChange::Lockup {
+            lockup_slots,
+            early_exit_fee_bps,
+            withdrawal_delay_slots,
+        },
+    )
+}
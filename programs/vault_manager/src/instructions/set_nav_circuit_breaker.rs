@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetNavCircuitBreaker<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetNavCircuitBreaker>, max_drop_bps: u16) -> Result<()> {
+    governance::apply_now(
+        &mut ctx.accounts.vault,
+        ParamChange::NavCircuitBreaker { max_drop_bps },
+    )
+}
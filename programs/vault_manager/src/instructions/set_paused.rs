@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    vault.paused = paused;
+    if !paused {
+        vault.last_nav_per_share_x64 = 0;
+        vault.last_nav_slot = 0;
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::{self, ParamChange};
+use crate::oracle;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetPriceOracle<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    /// CHECK: External price feed - layout is checked by the handler
+    pub price_oracle: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler(
+    ctx: Context<SetPriceOracle>,
+    max_deviation_bps: u16,
+    max_staleness_secs: u32,
+) -> Result<()> {
+    let price_oracle = match &ctx.accounts.price_oracle {
+        Some(info) => {
+            oracle::PriceFeed::load(info)?;
+            info.key()
+        }
+        None => Pubkey::default(),
+    };
+    governance::apply_now(
+        &mut ctx.accounts.vault,
+        ParamChange::PriceOracle {
+            price_oracle,
+            max_deviation_bps,
+            max_staleness_secs,
+        },
+    )
+}
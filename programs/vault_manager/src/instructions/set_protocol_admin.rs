@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetProtocolAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump,
+        has_one = admin @ VaultError::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub admin: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetProtocolAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.protocol_config.admin = new_admin;
+    Ok(())
+}
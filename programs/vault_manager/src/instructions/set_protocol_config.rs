@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump,
+        has_one = admin @ VaultError::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub admin: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<SetProtocolConfig>,
+    fee_share_bps: u16,
+    treasury: Pubkey,
+    dex_programs: Vec<Pubkey>,
+) -> Result<()> {
+    ctx.accounts
+        .protocol_config
+        .configure(fee_share_bps, treasury, &dex_programs)
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetStrategyConfig<'info> {
+    #[account(has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed, payer = payer, space = 8 + StrategyConfig::INIT_SPACE,
+        seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<SetStrategyConfig>,
+    range_width: u32,
+    rebalance_threshold: u32,
+) -> Result<()> {
+    governance::require_no_timelock(&ctx.accounts.vault)?;
+    let config = &mut ctx.accounts.strategy_config;
+    config.vault = ctx.accounts.vault.key();
+    config.bump = ctx.bumps.strategy_config;
+    ParamChange::StrategyConfig {
+        range_width,
+        rebalance_threshold,
+    }
+    .apply(&mut ctx.accounts.vault, Some(config))
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetSwapPrograms<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetSwapPrograms>, programs: Vec<Pubkey>) -> Result<()> {
+    governance::apply_now(&mut ctx.accounts.vault, ParamChange::SwapPrograms(programs))
+}
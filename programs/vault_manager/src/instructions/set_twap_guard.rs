@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::{self, ParamChange};
+use crate::state::*;
+use crate::VaultError;
+
+#[derive(Accounts)]
+pub struct SetTwapGuard<'info> {
+    #[account(mut, has_one = authority @ VaultError::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<SetTwapGuard>,
+    window_secs: u32,
+    max_deviation_ticks: u32,
+) -> Result<()> {
+    governance::apply_now(
+        &mut ctx.accounts.vault,
+        ParamChange::TwapGuard {
+            window_secs,
+            max_deviation_ticks,
+        },
+    )
+}
@@ -0,0 +1,252 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::dex::{
+    Dex, DexAccounts, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter, WhirlpoolAdapter,
+};
+use crate::nav::vault_nav;
+use crate::protocol_fee::ProtocolFee;
+use crate::state::*;
+use crate::{circuit_breaker, lockup, math, oracle, VaultError};
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.pool.as_ref()], bump = vault.bump,
+        has_one = pool, has_one = position, has_one = share_mint,
+        has_one = vault_token_a, has_one = vault_token_b,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, token::mint = share_mint, token::authority = user)]
+    pub user_share_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault.token_a_mint)]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = vault.token_b_mint)]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+    /// CHECK: Pool of `vault.dex` - checked by the DEX adapter
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: Vault position - checked by the DEX adapter
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_a: UncheckedAccount<'info>,
+    /// CHECK: Pool token vault - checked against the pool by the DEX adapter
+    #[account(mut)]
+    pub pool_token_vault_b: UncheckedAccount<'info>,
+    /// CHECK: External price feed - must match `vault.config.price_oracle`, parsed by the guard
+    pub price_oracle: Option<UncheckedAccount<'info>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Program of `vault.dex`
+    #[account(address = vault.dex.program_id())]
+    pub dex_program: UncheckedAccount<'info>,
+    /// Receipt of the user's deposits, created for shares received by transfer
+    #[account(
+        init_if_needed, payer = user, space = 8 + UserPosition::INIT_SPACE,
+        seeds = [USER_POSITION_SEED, vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [PROTOCOL_CONFIG_SEED], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    /// Receives the protocol's share of token A fees
+    #[account(mut, token::mint = vault.token_a_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_a: Box<Account<'info, TokenAccount>>,
+    /// Receives the protocol's share of token B fees
+    #[account(mut, token::mint = vault.token_b_mint, token::authority = protocol_config.treasury)]
+    pub treasury_token_b: Box<Account<'info, TokenAccount>>,
+}
+
+impl_dex_accounts!(Withdraw);
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+    shares: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<()> {
+    match ctx.accounts.vault.dex {
+        Dex::Whirlpool => {
+            process_withdraw::<WhirlpoolAdapter>(ctx, shares, min_amount_a, min_amount_b)
+        }
+        Dex::RaydiumClmm => {
+            process_withdraw::<RaydiumClmmAdapter>(ctx, shares, min_amount_a, min_amount_b)
+        }
+        Dex::MeteoraDlmm => {
+            process_withdraw::<MeteoraDlmmAdapter>(ctx, shares, min_amount_a, min_amount_b)
+        }
+    }
+}
+
+fn process_withdraw<'info, D: DexAdapter<'info>>(
+    ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+    shares: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<()> {
+    require!(shares > 0, VaultError::ZeroShares);
+    let dex = D::load(ctx.accounts.dex_accounts())?;
+    let position = dex.load_position(
+        ctx.accounts.position.to_account_info(),
+        &mut ctx.remaining_accounts.iter(),
+    )?;
+    oracle::guard_pool_price(
+        &ctx.accounts.vault,
+        ctx.accounts.price_oracle.as_deref(),
+        dex.price()?.sqrt_price,
+    )?;
+
+    // Withdrawals stay open once the breaker trips, so the result only matters to others
+    let slot = Clock::get()?.slot;
+    let nav = vault_nav(
+        &dex,
+        &position,
+        ctx.accounts.vault_token_a.amount,
+        ctx.accounts.vault_token_b.amount,
+        ctx.accounts.share_mint.supply,
+    )?;
+    let vault_key = ctx.accounts.vault.key();
+    circuit_breaker::observe_nav(&mut ctx.accounts.vault, vault_key, &nav, slot)?;
+
+    let user_key = ctx.accounts.user.key();
+    let user_position = &mut ctx.accounts.user_position;
+    user_position.init_if_new(vault_key, user_key, ctx.bumps.user_position);
+    let paid_shares = lockup::guard_withdrawal(&ctx.accounts.vault, user_position, shares, slot)?;
+    let protocol_fee = ProtocolFee::new(
+        &ctx.accounts.protocol_config,
+        &ctx.accounts.treasury_token_a,
+        &ctx.accounts.treasury_token_b,
+    );
+    let (amount_a, amount_b) = redeem_shares(
+        &dex,
+        &position,
+        (&ctx.accounts.dex_accounts(), &protocol_fee),
+        &mut ctx.accounts.vault,
+        &mut ctx.accounts.vault_token_a,
+        &mut ctx.accounts.vault_token_b,
+        ctx.accounts.share_mint.supply,
+        paid_shares,
+        (min_amount_a, min_amount_b),
+    )?;
+    require!(
+        amount_a >= min_amount_a && amount_b >= min_amount_b,
+        VaultError::SlippageExceeded
+    );
+    ctx.accounts
+        .user_position
+        .record_withdraw(shares, amount_a, amount_b)?;
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.user_share_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    // Transfer the withdrawn tokens back
+    let pool_key = ctx.accounts.vault.pool;
+    let bump = [ctx.accounts.vault.bump];
+    let seeds: &[&[u8]] = &[VAULT_SEED, pool_key.as_ref(), &bump];
+    let signer = &[seeds];
+    if amount_a > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_a.to_account_info(),
+                    to: ctx.accounts.user_token_a.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer,
+            ),
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_b.to_account_info(),
+                    to: ctx.accounts.user_token_b.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer,
+            ),
+            amount_b,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Collects fees so they are shared pro rata, net of the protocol's share, then frees `shares` out of
+/// `share_supply` of the idle balances and position liquidity. The tokens stay
+/// in the vault token accounts; returns the amounts freed.
+///
+/// The liquidity removal is bounded by what `min_amounts` leaves after the
+/// idle part; callers check the totals.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn redeem_shares<'info, D: DexAdapter<'info>>(
+    dex: &D,
+    position: &D::Position,
+    (accounts, protocol_fee): (&DexAccounts<'info>, &ProtocolFee<'info>),
+    vault: &mut Vault,
+    vault_token_a: &mut Account<'info, TokenAccount>,
+    vault_token_b: &mut Account<'info, TokenAccount>,
+    share_supply: u64,
+    shares: u64,
+    (min_amount_a, min_amount_b): (u64, u64),
+) -> Result<(u64, u64)> {
+    let position_liquidity = dex.position_range(position)?.liquidity;
+    protocol_fee.collect_fees(dex, position, accounts, vault_token_a, vault_token_b)?;
+
+    require!(shares <= share_supply, VaultError::InsufficientShares);
+    let idle_a = vault_token_a.amount;
+    let idle_b = vault_token_b.amount;
+    // Rounded down, so redeeming never takes more than the shares' part
+    let mut amount_a = math::mul_div_u64(idle_a, shares, share_supply)?;
+    let mut amount_b = math::mul_div_u64(idle_b, shares, share_supply)?;
+
+    // Remove the same share of position liquidity
+    let liquidity = math::mul_div(
+        position_liquidity,
+        u128::from(shares),
+        u128::from(share_supply),
+    )?;
+    if liquidity > 0 {
+        dex.decrease_liquidity(
+            position,
+            liquidity,
+            min_amount_a.saturating_sub(amount_a),
+            min_amount_b.saturating_sub(amount_b),
+        )?;
+        vault_token_a.reload()?;
+        vault_token_b.reload()?;
+        let freed_a = math::checked_sub(vault_token_a.amount, idle_a)?;
+        let freed_b = math::checked_sub(vault_token_b.amount, idle_b)?;
+        amount_a = math::checked_add(amount_a, freed_a)?;
+        amount_b = math::checked_add(amount_b, freed_b)?;
+        if liquidity == position_liquidity {
+            vault.is_active = false;
+        }
+    }
+    Ok((amount_a, amount_b))
+}
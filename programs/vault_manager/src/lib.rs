@@ -1,6 +1,37 @@
 #![allow(clippy::result_large_err)]
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+#[macro_use]
+mod bindings;
+
+pub mod allowlist;
+pub mod circuit_breaker;
+pub mod constants;
+pub mod dex;
+pub mod error;
+pub mod governance;
+pub mod instructions;
+pub mod lockup;
+pub mod math;
+pub mod meteora;
+pub mod nav;
+pub mod oracle;
+pub mod protocol_fee;
+pub mod raydium;
+pub mod route;
+pub mod state;
+pub mod twap;
+pub mod whirlpool;
+
+pub use constants::*;
+use dex::{BinStrategy, Dex};
+pub use error::*;
+use governance::ParamChange;
+pub use instructions::*;
+use nav::VaultNav;
+use protocol_fee::CollectedFees;
+use route::SwapRoute;
+pub use state::*;
 
 declare_id!("7Y8CNTcNiU4s6Aih26rFcFWLrGTka4Kqzd5MajUKhVFx");
 
@@ -8,126 +39,321 @@ declare_id!("7Y8CNTcNiU4s6Aih26rFcFWLrGTka4Kqzd5MajUKhVFx");
 pub mod vault_manager {
     use super::*;
 
-    pub fn initialize_vault(
-        ctx: Context<InitializeVault>,
-        bump: u8,
+    /// Create the protocol config. Only the program's upgrade authority can, and it
+    /// becomes the protocol admin.
+    pub fn initialize_protocol_config(
+        ctx: Context<InitializeProtocolConfig>,
+        fee_share_bps: u16,
+        treasury: Pubkey,
+        dex_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        initialize_protocol_config::handler(ctx, fee_share_bps, treasury, dex_programs)
+    }
+
+    /// Replace the protocol fee share, treasury and the DEX programs new vaults may use.
+    pub fn set_protocol_config(
+        ctx: Context<SetProtocolConfig>,
+        fee_share_bps: u16,
+        treasury: Pubkey,
+        dex_programs: Vec<Pubkey>,
     ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        vault.authority = ctx.accounts.authority.key();
-        vault.whirlpool = ctx.accounts.whirlpool.key();
-        vault.token_a_mint = ctx.accounts.token_a_mint.key();
-        vault.token_b_mint = ctx.accounts.token_b_mint.key();
-        vault.vault_token_a = ctx.accounts.vault_token_a.key();
-        vault.vault_token_b = ctx.accounts.vault_token_b.key();
-        vault.position = ctx.accounts.position.key();
-        vault.bump = bump;
-        vault.is_active = false;
-        Ok(())
-    }
-
-    pub fn deposit_and_add_liquidity(
-        ctx: Context<DepositAndAddLiquidity>,
+        set_protocol_config::handler(ctx, fee_share_bps, treasury, dex_programs)
+    }
+
+    pub fn set_protocol_admin(ctx: Context<SetProtocolAdmin>, new_admin: Pubkey) -> Result<()> {
+        set_protocol_admin::handler(ctx, new_admin)
+    }
+
+    /// Create a vault on `pool` and open its position over `[tick_lower, tick_upper]`
+    /// (bin ids on DLMM), owned by the vault PDA. The vault is appended to the
+    /// registry; `dex` must be allowed by the protocol config.
+    ///
+    /// Remaining accounts: the backend's accounts for opening a position.
+    pub fn initialize_vault<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeVault<'info>>,
+        dex: Dex,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        initialize_vault::handler(ctx, dex, tick_lower, tick_upper)
+    }
+
+    /// Deposit one of the vault's tokens, swapping half of it for the other through
+    /// the vault's pool. Shares are priced on what the vault holds after the swap,
+    /// so its fee and price impact only cost the depositor.
+    ///
+    /// `min_swap_out` bounds the swap output, `min_shares_out` the shares minted.
+    ///
+    /// If the NAV circuit breaker trips, the vault pauses and the instruction
+    /// succeeds without depositing; clients watch for `NavCircuitBreakerTripped`.
+    ///
+    /// Remaining accounts: the position's account set, then the swap account set
+    /// of the vault's DEX backend.
+    pub fn deposit_and_add_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositAndAddLiquidity<'info>>,
         amount: u64,
-        _tick_lower: i32,
-        _tick_upper: i32,
+        min_swap_out: u64,
+        min_shares_out: u64,
     ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        require!(!vault.is_active, VaultError::PositionActive);
-
-        // Transfer deposit token to vault
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_input.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
-
-        // Determine if deposit is token A
-        let input_mint = ctx.accounts.user_token_account.mint;
-        let _is_deposit_a = input_mint == vault.token_a_mint;
-
-        // TODO: Implement swap using CPI calls to Whirlpool program
-        // For now, this is a placeholder - you'll need to construct the instruction
-        // data manually or use a working whirlpools SDK version
-        // Swap 50% logic would go here
-        let _swap_amount = amount / 2;
-        
-        // Placeholder - implement actual Whirlpool swap CPI here
-        // This requires the whirlpools crate or manual instruction construction
-
-        vault.is_active = true;
-        Ok(())
-    }
-
-    // other methods: collect_fees, rebalance, withdraw remain similar but update to use `whirlpools` crate types
-}
+        deposit::handler(ctx, amount, min_shares_out, ZapSwap::Pool { min_swap_out })
+    }
 
-#[derive(Accounts)]
-#[instruction(bump: u8)]
-pub struct InitializeVault<'info> {
-    #[account(
-        init, payer = authority,
-        space = 8 + 32*7 + 1 + 1, // 8 (discriminator) + 7 Pubkeys (32 each) + bump (1) + is_active (1)
-        seeds = [b"vault", whirlpool.key().as_ref()],
-        bump
-    )]
-    pub vault: Account<'info, Vault>,
-    /// CHECK: Whirlpool account - validated by Whirlpool program
-    pub whirlpool: UncheckedAccount<'info>,
-    pub token_a_mint: Account<'info, Mint>,
-    pub token_b_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub vault_token_a: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub vault_token_b: Account<'info, TokenAccount>,
-    /// CHECK: Position account - validated by Whirlpool program  
-    #[account(mut)]
-    pub position: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub position_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub position_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// `deposit_and_add_liquidity` with the zap swap sent through `route` on one of
+    /// the vault's whitelisted swap programs instead of the vault's pool. `route`
+    /// must swap at most half of `amount`. Shares are priced on what the vault
+    /// holds after the swap, so a poor route only costs the depositor.
+    ///
+    /// Remaining accounts: the position's account set, the swap program, then the
+    /// route's accounts.
+    pub fn deposit_via_route<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositAndAddLiquidity<'info>>,
+        amount: u64,
+        min_shares_out: u64,
+        route: SwapRoute,
+    ) -> Result<()> {
+        deposit::handler(ctx, amount, min_shares_out, ZapSwap::Route(route))
+    }
 
-#[derive(Accounts)]
-pub struct DepositAndAddLiquidity<'info> {
-    #[account(mut, seeds = [b"vault", vault.whirlpool.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    /// CHECK: Token account for vault input - validated by token program
-    #[account(mut)]
-    pub vault_token_input: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
-    /// CHECK: Whirlpool program - validated by instruction
-    pub whirlpool_program: UncheckedAccount<'info>,
-}
+    /// Read-only NAV of the vault, meant to be called through simulateTransaction.
+    ///
+    /// Remaining accounts: the position's account set.
+    pub fn get_nav<'info>(ctx: Context<'_, '_, '_, 'info, GetNav<'info>>) -> Result<VaultNav> {
+        get_nav::handler(ctx)
+    }
 
-#[account]
-pub struct Vault {
-    pub authority: Pubkey,
-    pub whirlpool: Pubkey,
-    pub token_a_mint: Pubkey,
-    pub token_b_mint: Pubkey,
-    pub vault_token_a: Pubkey,
-    pub vault_token_b: Pubkey,
-    pub position: Pubkey,
-    pub bump: u8,
-    pub is_active: bool,
-}
+    /// Collect the position's fees into the vault, paying the protocol its share,
+    /// and return the amounts collected.
+    ///
+    /// Remaining accounts: the position's account set.
+    pub fn collect<'info>(
+        ctx: Context<'_, '_, '_, 'info, Collect<'info>>,
+    ) -> Result<CollectedFees> {
+        collect::handler(ctx)
+    }
+
+    /// Burn `shares` and return the pro-rata part of idle balances, fees and position
+    /// liquidity, at least `min_amount_a` and `min_amount_b`. The protocol's fee
+    /// share of the collected fees goes to the treasury first.
+    ///
+    /// Remaining accounts: the position's account set.
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        shares: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Result<()> {
+        withdraw::handler(ctx, shares, min_amount_a, min_amount_b)
+    }
+
+    /// Rebalance: collect fees, paying the protocol its share, remove all liquidity,
+    /// open `new_position` over `[tick_lower, tick_upper]`, swap to its ratio and
+    /// re-add into it, then close the emptied current position.
+    ///
+    /// `min_amount_a` and `min_amount_b` bound the removal, `min_swap_out` the swap.
+    ///
+    /// If the NAV circuit breaker trips, the vault pauses and the instruction
+    /// succeeds without rebalancing; clients watch for `NavCircuitBreakerTripped`.
+    ///
+    /// Remaining accounts: the current position's account set, the swap account
+    /// set, the accounts opening a position takes, the new position's account set,
+    /// then the accounts closing the current position takes.
+    pub fn rebalance<'info>(
+        ctx: Context<'_, '_, '_, 'info, Rebalance<'info>>,
+        tick_lower: i32,
+        tick_upper: i32,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        min_swap_out: u64,
+    ) -> Result<()> {
+        rebalance::handler(
+            ctx,
+            tick_lower,
+            tick_upper,
+            min_amount_a,
+            min_amount_b,
+            min_swap_out,
+        )
+    }
+
+    /// Move `shares` of the source vault into the destination vault in one step:
+    /// redeem them like `withdraw`, protocol fee share included, hand the tokens
+    /// to the destination vault, swap them toward its range and add liquidity, then
+    /// mint destination shares for the value moved, priced after the swap like a
+    /// deposit. The destination's
+    /// allowlist and deposit limits apply. Both vaults must hold the same token pair.
+    ///
+    /// `min_amount_a` and `min_amount_b` bound the redemption, `min_swap_out` the
+    /// destination swap and `min_shares_out` the destination shares minted.
+    ///
+    /// If the destination's NAV circuit breaker trips, it pauses and the
+    /// instruction succeeds without moving anything.
+    ///
+    /// Remaining accounts: the source position's account set, the destination
+    /// position's account set, then the destination's swap account set.
+    pub fn migrate_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateLiquidity<'info>>,
+        shares: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+        min_swap_out: u64,
+        min_shares_out: u64,
+    ) -> Result<()> {
+        migrate_liquidity::handler(
+            ctx,
+            shares,
+            min_amount_a,
+            min_amount_b,
+            min_swap_out,
+            min_shares_out,
+        )
+    }
+
+    /// Tear down a vault with no shares or position liquidity: sweep its idle
+    /// balances to the authority's token accounts, drop it from its registry page,
+    /// and close its position, its token accounts and the vault itself, returning
+    /// the rent to the authority. Closing the position collects any rewards it is
+    /// still owed. The share mint stays, as SPL Token mints cannot be closed.
+    ///
+    /// Remaining accounts: the position's account set, then the backend's accounts
+    /// for closing a position.
+    pub fn close_vault<'info>(ctx: Context<'_, '_, '_, 'info, CloseVault<'info>>) -> Result<()> {
+        close_vault::handler(ctx)
+    }
+
+    /// Hand the vault to `new_authority`, such as a multisig program's PDA.
+    pub fn set_authority(ctx: Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+        set_authority::handler(ctx, new_authority)
+    }
+
+    /// Configure the external price oracle. Omitting `price_oracle` disables the guard.
+    pub fn set_price_oracle(
+        ctx: Context<SetPriceOracle>,
+        max_deviation_bps: u16,
+        max_staleness_secs: u32,
+    ) -> Result<()> {
+        set_price_oracle::handler(ctx, max_deviation_bps, max_staleness_secs)
+    }
+
+    /// Configure the TWAP check on rebalance, from the pool's tick observations, which
+    /// only Raydium CLMM pools keep. A zero window disables it, and is the only
+    /// window other backends accept.
+    pub fn set_twap_guard(
+        ctx: Context<SetTwapGuard>,
+        window_secs: u32,
+        max_deviation_ticks: u32,
+    ) -> Result<()> {
+        set_twap_guard::handler(ctx, window_secs, max_deviation_ticks)
+    }
+
+    /// Replace the swap programs deposits may route their zap swap through. The
+    /// vault signs for its token accounts in a route, so only whitelist programs
+    /// that move tokens solely as the route instruction asks, such as aggregators.
+    pub fn set_swap_programs(ctx: Context<SetSwapPrograms>, programs: Vec<Pubkey>) -> Result<()> {
+        set_swap_programs::handler(ctx, programs)
+    }
+
+    /// Configure deposit limits, all in token B value. Zero disables a limit.
+    ///
+    /// `user_deposit_cap` bounds the value of the shares the depositor's
+    /// `UserPosition` tracks after the deposit, whichever share account got them.
+    pub fn set_deposit_limits(
+        ctx: Context<SetDepositLimits>,
+        min_deposit: u64,
+        user_deposit_cap: u64,
+        deposit_cap: u64,
+    ) -> Result<()> {
+        set_deposit_limits::handler(ctx, min_deposit, user_deposit_cap, deposit_cap)
+    }
+
+    /// Turn the depositor allowlist on or off. A non-zero `merkle_root` lets
+    /// wallets in the tree add themselves with `join_allowlist`.
+    pub fn set_allowlist(
+        ctx: Context<SetAllowlist>,
+        enabled: bool,
+        merkle_root: [u8; 32],
+    ) -> Result<()> {
+        set_allowlist::handler(ctx, enabled, merkle_root)
+    }
+
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, wallet: Pubkey) -> Result<()> {
+        add_to_allowlist::handler(ctx, wallet)
+    }
+
+    /// Remove a wallet's entry, returning its rent to the authority.
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        remove_from_allowlist::handler(ctx)
+    }
+
+    /// Add the signing wallet to the allowlist with a proof against the vault's Merkle root.
+    pub fn join_allowlist(ctx: Context<JoinAllowlist>, proof: Vec<[u8; 32]>) -> Result<()> {
+        join_allowlist::handler(ctx, proof)
+    }
+
+    /// Configure the withdrawal lockup and queue, see `lockup`. Zero slots disable
+    /// either; a zero fee refuses withdrawals during the lockup instead of charging.
+    pub fn set_lockup(
+        ctx: Context<SetLockup>,
+        lockup_slots: u64,
+        early_exit_fee_bps: u16,
+        withdrawal_delay_slots: u64,
+    ) -> Result<()> {
+        set_lockup::handler(ctx, lockup_slots, early_exit_fee_bps, withdrawal_delay_slots)
+    }
+
+    /// Queue `shares` for withdrawal, replacing any earlier request and restarting
+    /// the delay. Zero cancels the request.
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+        request_withdrawal::handler(ctx, shares)
+    }
+
+    /// Pause the vault when any operation sees its NAV per share fall more than
+    /// `max_drop_bps` since the previous one, see `circuit_breaker`. Zero disables it.
+    pub fn set_nav_circuit_breaker(
+        ctx: Context<SetNavCircuitBreaker>,
+        max_drop_bps: u16,
+    ) -> Result<()> {
+        set_nav_circuit_breaker::handler(ctx, max_drop_bps)
+    }
 
-#[error_code]
-pub enum VaultError {
-    #[msg("Position is already active")]
-    PositionActive,
-    #[msg("No active position to rebalance")]
-    NoActivePosition,
+    /// Pause or resume the vault. Resuming drops the recorded NAV, so the circuit
+    /// breaker compares against the next operation's NAV.
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        set_paused::handler(ctx, paused)
+    }
+
+    /// Choose how bin-based backends distribute liquidity. Applies from the next
+    /// deposit or rebalance.
+    pub fn set_bin_strategy(ctx: Context<SetBinStrategy>, strategy: BinStrategy) -> Result<()> {
+        set_bin_strategy::handler(ctx, strategy)
+    }
+
+    /// Publish the rebalancing policy keepers follow for the vault, see
+    /// `StrategyConfig`. `rebalance_threshold` must be below `range_width`.
+    pub fn set_strategy_config(
+        ctx: Context<SetStrategyConfig>,
+        range_width: u32,
+        rebalance_threshold: u32,
+    ) -> Result<()> {
+        set_strategy_config::handler(ctx, range_width, rebalance_threshold)
+    }
+
+    /// Queue a strategy parameter change, executable once the vault's
+    /// `param_timelock_slots` have passed. Only one change can be pending.
+    /// `PriceOracle` changes pass the new feed as `price_oracle`.
+    pub fn queue_param_change(ctx: Context<QueueParamChange>, change: ParamChange) -> Result<()> {
+        queue_param_change::handler(ctx, change)
+    }
+
+    /// Apply the pending change once its timelock has passed, returning the
+    /// pending account's rent to the payer. `StrategyConfig` changes pass the
+    /// vault's `strategy_config`, which the first one creates at the payer's
+    /// expense.
+    pub fn execute_param_change(ctx: Context<ExecuteParamChange>) -> Result<()> {
+        execute_param_change::handler(ctx)
+    }
+
+    /// Drop the pending change without applying it.
+    pub fn cancel_param_change(ctx: Context<CancelParamChange>) -> Result<()> {
+        cancel_param_change::handler(ctx)
+    }
 }
@@ -0,0 +1,56 @@
+//! Withdrawal lockup and queue, against just-in-time liquidity around rebalances.
+//!
+//! Both are enforced through the withdrawing wallet's `UserPosition`:
+//! - a lockup keeps shares for `lockup_slots` after the wallet's latest deposit,
+//!   either refusing withdrawals or charging an early-exit fee that stays in the
+//!   vault for the remaining holders;
+//! - a withdrawal queue only pays out shares requested at least
+//!   `withdrawal_delay_slots` earlier with `request_withdrawal`.
+//!
+//! While either is configured, only shares tracked by the wallet's receipt can be
+//! redeemed, so moving shares to another wallet does not skip the lockup.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::state::UserPosition;
+use crate::{Vault, VaultError};
+
+/// Applies the vault's lockup and withdrawal queue to a redemption of `shares`
+/// at `slot`. Returns the shares whose value is paid out, net of the early-exit
+/// fee; all `shares` are burned.
+pub fn guard_withdrawal(
+    vault: &Vault,
+    position: &mut UserPosition,
+    shares: u64,
+    slot: u64,
+) -> Result<u64> {
+    if vault.config.lockup_slots == 0 && vault.config.withdrawal_delay_slots == 0 {
+        return Ok(shares);
+    }
+    require!(shares <= position.shares, VaultError::InsufficientShares);
+
+    if vault.config.withdrawal_delay_slots > 0 {
+        require!(
+            shares <= position.pending_withdrawal_shares,
+            VaultError::WithdrawalNotRequested
+        );
+        let ready_at = position
+            .withdrawal_requested_slot
+            .saturating_add(vault.config.withdrawal_delay_slots);
+        require!(slot >= ready_at, VaultError::WithdrawalNotReady);
+        position.pending_withdrawal_shares =
+            math::checked_sub(position.pending_withdrawal_shares, shares)?;
+    }
+
+    let unlocked_at = position
+        .last_deposit_slot
+        .saturating_add(vault.config.lockup_slots);
+    if slot >= unlocked_at {
+        return Ok(shares);
+    }
+    require!(vault.fees.early_exit_fee_bps > 0, VaultError::SharesLocked);
+    // Rounded up, as the fee stays with the vault
+    let fee = math::bps_fee(shares, vault.fees.early_exit_fee_bps)?;
+    math::checked_sub(shares, fee)
+}
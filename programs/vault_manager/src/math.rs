@@ -0,0 +1,364 @@
+//! Fixed-point tick, price, liquidity and share math.
+//!
+//! Products are taken in 256 or 512 bits and every narrowing or overflowing
+//! operation returns `MathOverflow` instead of panicking or wrapping. Divisions
+//! round in the vault's favour: amounts paid or credited to users (shares
+//! minted, tokens withdrawn, liquidity removed) round down, and amounts users
+//! owe the vault (fees) round up.
+
+use anchor_lang::prelude::*;
+
+use crate::VaultError;
+
+pub use wide::U256;
+use wide::U512;
+
+/// Kept apart from the Anchor prelude, whose `Result` alias the macro cannot use.
+#[allow(clippy::all)]
+mod wide {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+
+    uint::construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+pub const MIN_TICK_INDEX: i32 = -443636;
+pub const MAX_TICK_INDEX: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
+
+/// Q128 ratios for sqrt(1.0001)^-(2^i), i = 1..19. Bit 0 is handled separately.
+const TICK_RATIOS_X128: [u128; 19] = [
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// Q64.64 sqrt price for a tick index, matching the Whirlpool tick math.
+pub fn sqrt_price_from_tick_index(tick: i32) -> Result<u128> {
+    require!(
+        (MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick),
+        VaultError::InvalidTickIndex
+    );
+    let abs_tick = tick.unsigned_abs();
+
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001u128)
+    } else {
+        U256::one() << 128
+    };
+    for (i, factor) in TICK_RATIOS_X128.iter().enumerate() {
+        if abs_tick & (2 << i) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128 -> Q64, rounding up so the result never undershoots the tick.
+    let rounding = if ratio.low_u64() == 0 { 0 } else { 1 };
+    Ok(((ratio >> 64) + rounding).as_u128())
+}
+
+/// Q64.64 price `(1 + bin_step / 10_000) ^ bin_id` of a DLMM bin (token B atoms per token A atom).
+pub fn price_x64_from_bin_id(bin_id: i32, bin_step: u16) -> Result<u128> {
+    require!(
+        (MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&bin_id),
+        VaultError::InvalidTickIndex
+    );
+    let one = U256::one() << 64;
+    let mut base = one + (U256::from(bin_step) << 64) / U256::from(10_000u32);
+    let mut exponent = bin_id.unsigned_abs();
+    let mut price = one;
+    while exponent > 0 {
+        if exponent & 1 != 0 {
+            price = price.checked_mul(base).ok_or(VaultError::MathOverflow)? >> 64;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base.checked_mul(base).ok_or(VaultError::MathOverflow)? >> 64;
+        }
+    }
+    if bin_id < 0 {
+        require!(!price.is_zero(), VaultError::MathOverflow);
+        price = (U256::one() << 128) / price;
+    }
+    to_u128(price)
+}
+
+/// Q64.64 sqrt price of a DLMM bin.
+pub fn sqrt_price_from_bin_id(bin_id: i32, bin_step: u16) -> Result<u128> {
+    let price = price_x64_from_bin_id(bin_id, bin_step)?;
+    to_u128((U256::from(price) << 64).integer_sqrt())
+}
+
+/// Amount of token A between two sqrt prices for `liquidity`, rounded down.
+pub fn amount_a_for_liquidity(sqrt_lower: u128, sqrt_upper: u128, liquidity: u128) -> Result<u64> {
+    let (lo, hi) = ordered(sqrt_lower, sqrt_upper);
+    if liquidity == 0 || lo == hi {
+        return Ok(0);
+    }
+    let quotient = mul_div_u256(
+        U256::from(liquidity) << 64,
+        U256::from(hi - lo),
+        U256::from(hi) * U256::from(lo),
+    )?;
+    to_u64(quotient)
+}
+
+/// Amount of token B between two sqrt prices for `liquidity`, rounded down.
+pub fn amount_b_for_liquidity(sqrt_lower: u128, sqrt_upper: u128, liquidity: u128) -> Result<u64> {
+    let (lo, hi) = ordered(sqrt_lower, sqrt_upper);
+    to_u64((U256::from(liquidity) * U256::from(hi - lo)) >> 64)
+}
+
+/// Token amounts represented by `liquidity` in `[sqrt_lower, sqrt_upper]` at the current price.
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    liquidity: u128,
+) -> Result<(u64, u64)> {
+    if sqrt_price <= sqrt_lower {
+        Ok((
+            amount_a_for_liquidity(sqrt_lower, sqrt_upper, liquidity)?,
+            0,
+        ))
+    } else if sqrt_price >= sqrt_upper {
+        Ok((
+            0,
+            amount_b_for_liquidity(sqrt_lower, sqrt_upper, liquidity)?,
+        ))
+    } else {
+        Ok((
+            amount_a_for_liquidity(sqrt_price, sqrt_upper, liquidity)?,
+            amount_b_for_liquidity(sqrt_lower, sqrt_price, liquidity)?,
+        ))
+    }
+}
+
+/// Largest liquidity that can be funded by `amount_a` and `amount_b` at the current price.
+pub fn liquidity_for_amounts(
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<u128> {
+    let (sqrt_lower, sqrt_upper) = ordered(sqrt_lower, sqrt_upper);
+    let liquidity_a = |lo: u128, hi: u128| -> U256 {
+        if hi == lo {
+            return U256::zero();
+        }
+        mul_div_u256(
+            U256::from(amount_a) * U256::from(lo),
+            U256::from(hi),
+            U256::from(hi - lo) << 64,
+        )
+        .unwrap_or(U256::MAX)
+    };
+    let liquidity_b = |lo: u128, hi: u128| -> U256 {
+        if hi == lo {
+            return U256::zero();
+        }
+        (U256::from(amount_b) << 64) / U256::from(hi - lo)
+    };
+
+    let liquidity = if sqrt_price <= sqrt_lower {
+        liquidity_a(sqrt_lower, sqrt_upper)
+    } else if sqrt_price >= sqrt_upper {
+        liquidity_b(sqrt_lower, sqrt_upper)
+    } else {
+        liquidity_a(sqrt_price, sqrt_upper).min(liquidity_b(sqrt_lower, sqrt_price))
+    };
+    to_u128(liquidity)
+}
+
+/// Value of `amount_a` expressed in token B at the given sqrt price, rounded down.
+pub fn a_to_b_value(amount_a: u64, sqrt_price: u128) -> Result<u128> {
+    let price_x128 = U256::from(sqrt_price) * U256::from(sqrt_price);
+    to_u128(mul_div_u256(
+        U256::from(amount_a),
+        price_x128,
+        U256::one() << 128,
+    )?)
+}
+
+/// Combined value of `amount_a` and `amount_b` expressed in token B, rounded down.
+pub fn value_in_b(amount_a: u64, amount_b: u64, sqrt_price: u128) -> Result<u128> {
+    a_to_b_value(amount_a, sqrt_price)?
+        .checked_add(u128::from(amount_b))
+        .ok_or_else(|| error!(VaultError::MathOverflow))
+}
+
+/// Combined value of `amount_a` and `amount_b` in token B at a Q64.64 price, rounded down.
+pub fn value_at_price(amount_a: u64, amount_b: u64, price_x64: u128) -> Result<u128> {
+    mul_div(u128::from(amount_a), price_x64, 1 << 64)?
+        .checked_add(u128::from(amount_b))
+        .ok_or_else(|| error!(VaultError::MathOverflow))
+}
+
+/// Amount of token A worth `value_b` of token B at the given sqrt price, rounded down.
+pub fn b_to_a_amount(value_b: u128, sqrt_price: u128) -> Result<u64> {
+    let price_x128 = U256::from(sqrt_price) * U256::from(sqrt_price);
+    to_u64(mul_div_u256(
+        U256::from(value_b),
+        U256::one() << 128,
+        price_x128,
+    )?)
+}
+
+/// Q64.64 pool price (token B atoms per token A atom) for a Q64.64 sqrt price.
+pub fn price_x64_from_sqrt_price(sqrt_price: u128) -> Result<u128> {
+    to_u128((U256::from(sqrt_price) * U256::from(sqrt_price)) >> 64)
+}
+
+/// Swap that brings idle balances to the token ratio a `[sqrt_lower, sqrt_upper]`
+/// position needs at `sqrt_price`, as `(amount_in, a_to_b)`.
+///
+/// Fees and price impact are ignored, so a small remainder may stay idle.
+pub fn rebalance_swap(
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<Option<(u64, bool)>> {
+    let total_value_b = value_in_b(amount_a, amount_b, sqrt_price)?;
+
+    // Per unit of liquidity, the A leg is worth (upper - p) * p / upper and the
+    // B leg is worth (p - lower), both in Q64 token B units.
+    let target_b = if sqrt_price <= sqrt_lower {
+        0
+    } else if sqrt_price >= sqrt_upper {
+        total_value_b
+    } else {
+        let value_a = mul_div(sqrt_upper - sqrt_price, sqrt_price, sqrt_upper)?;
+        let value_b = sqrt_price - sqrt_lower;
+        let total = value_a
+            .checked_add(value_b)
+            .ok_or(VaultError::MathOverflow)?;
+        mul_div(total_value_b, value_b, total)?
+    };
+
+    let current_b = u128::from(amount_b);
+    if current_b > target_b {
+        let excess_b = to_u64(U256::from(current_b - target_b))?;
+        Ok(Some((excess_b, false)).filter(|(amount, _)| *amount > 0))
+    } else {
+        let excess_a = b_to_a_amount(target_b - current_b, sqrt_price)?.min(amount_a);
+        Ok(Some((excess_a, true)).filter(|(amount, _)| *amount > 0))
+    }
+}
+
+/// `(a * b) >> 64`, as used for Q64.64 growth counters.
+pub fn mul_shift_right_64(a: u128, b: u128) -> Result<u64> {
+    to_u64((U256::from(a) * U256::from(b)) >> 64)
+}
+
+/// `a * b / denominator` computed in 256 bits, rounded down.
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator != 0, VaultError::MathOverflow);
+    to_u128(U256::from(a) * U256::from(b) / U256::from(denominator))
+}
+
+/// `a * b / denominator` computed in 256 bits, rounded up.
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator != 0, VaultError::MathOverflow);
+    let product = U256::from(a) * U256::from(b);
+    let denominator = U256::from(denominator);
+    let mut quotient = product / denominator;
+    if !(product % denominator).is_zero() {
+        quotient += U256::one();
+    }
+    to_u128(quotient)
+}
+
+/// `amount * numerator / denominator` for token amounts, rounded down.
+pub fn mul_div_u64(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    narrow(mul_div(
+        u128::from(amount),
+        u128::from(numerator),
+        u128::from(denominator),
+    )?)
+}
+
+/// `bps` of `amount`, rounded up, for fees charged by the vault.
+pub fn bps_fee(amount: u64, bps: u16) -> Result<u64> {
+    narrow(mul_div_ceil(u128::from(amount), u128::from(bps), 10_000)?)
+}
+
+pub fn checked_add(a: u64, b: u64) -> Result<u64> {
+    a.checked_add(b)
+        .ok_or_else(|| error!(VaultError::MathOverflow))
+}
+
+pub fn checked_sub(a: u64, b: u64) -> Result<u64> {
+    a.checked_sub(b)
+        .ok_or_else(|| error!(VaultError::MathOverflow))
+}
+
+/// A `u128` result that must fit a token amount.
+pub fn narrow(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+/// `a * b / denominator` with a 512-bit intermediate product, rounded down.
+fn mul_div_u256(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    require!(!denominator.is_zero(), VaultError::MathOverflow);
+    let quotient = widen(a) * widen(b) / widen(denominator);
+    require!(
+        quotient.0[4..].iter().all(|limb| *limb == 0),
+        VaultError::MathOverflow
+    );
+    Ok(U256([
+        quotient.0[0],
+        quotient.0[1],
+        quotient.0[2],
+        quotient.0[3],
+    ]))
+}
+
+fn widen(value: U256) -> U512 {
+    let mut limbs = [0u64; 8];
+    limbs[..4].copy_from_slice(&value.0);
+    U512(limbs)
+}
+
+fn ordered(a: u128, b: u128) -> (u128, u128) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn to_u64(value: U256) -> Result<u64> {
+    require!(value <= U256::from(u64::MAX), VaultError::MathOverflow);
+    Ok(value.as_u64())
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    require!(value <= U256::from(u128::MAX), VaultError::MathOverflow);
+    Ok(value.as_u128())
+}
@@ -0,0 +1,172 @@
+// Enum derives refer to `borsh` by name, which the direct borsh dependency makes ambiguous.
+use anchor_lang::prelude::borsh;
+use anchor_lang::prelude::*;
+
+use crate::bindings::invoke;
+
+/// Shape of the liquidity distribution across the strategy's bin range.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrategyType {
+    SpotOneSide,
+    CurveOneSide,
+    BidAskOneSide,
+    SpotBalanced,
+    CurveBalanced,
+    BidAskBalanced,
+    SpotImBalanced,
+    CurveImBalanced,
+    BidAskImBalanced,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct StrategyParameters {
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub strategy_type: StrategyType,
+    pub parameteres: [u8; 64],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LiquidityParameterByStrategy {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub active_id: i32,
+    pub max_active_bin_slippage: i32,
+    pub strategy_parameters: StrategyParameters,
+}
+
+pub mod accounts {
+    use super::*;
+
+    cpi_accounts!(InitializePosition {
+        payer: writable_signer,
+        position: writable_signer,
+        lb_pair: readonly,
+        owner: signer,
+        system_program: readonly,
+        rent: readonly,
+        event_authority: readonly,
+        program: readonly,
+    });
+
+    cpi_accounts!(ClosePosition {
+        position: writable,
+        lb_pair: writable,
+        bin_array_lower: writable,
+        bin_array_upper: writable,
+        sender: signer,
+        rent_receiver: writable,
+        event_authority: readonly,
+        program: readonly,
+    });
+
+    cpi_accounts!(ModifyLiquidity {
+        position: writable,
+        lb_pair: writable,
+        bin_array_bitmap_extension: readonly,
+        user_token_x: writable,
+        user_token_y: writable,
+        reserve_x: writable,
+        reserve_y: writable,
+        token_x_mint: readonly,
+        token_y_mint: readonly,
+        bin_array_lower: writable,
+        bin_array_upper: writable,
+        sender: signer,
+        token_x_program: readonly,
+        token_y_program: readonly,
+        event_authority: readonly,
+        program: readonly,
+    });
+
+    cpi_accounts!(ClaimFee {
+        lb_pair: writable,
+        position: writable,
+        bin_array_lower: writable,
+        bin_array_upper: writable,
+        sender: signer,
+        reserve_x: writable,
+        reserve_y: writable,
+        user_token_x: writable,
+        user_token_y: writable,
+        token_x_mint: readonly,
+        token_y_mint: readonly,
+        token_program: readonly,
+        event_authority: readonly,
+        program: readonly,
+    });
+
+    cpi_accounts!(Swap {
+        lb_pair: writable,
+        bin_array_bitmap_extension: readonly,
+        reserve_x: writable,
+        reserve_y: writable,
+        user_token_in: writable,
+        user_token_out: writable,
+        token_x_mint: readonly,
+        token_y_mint: readonly,
+        oracle: writable,
+        host_fee_in: readonly,
+        user: signer,
+        token_x_program: readonly,
+        token_y_program: readonly,
+        event_authority: readonly,
+        program: readonly,
+    });
+}
+
+/// Creates a position of `width` bins starting at `lower_bin_id`.
+pub fn initialize_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::InitializePosition<'info>>,
+    lower_bin_id: i32,
+    width: i32,
+) -> Result<()> {
+    invoke(
+        ctx,
+        [219, 192, 234, 71, 190, 191, 102, 80],
+        (lower_bin_id, width),
+    )
+}
+
+pub fn close_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ClosePosition<'info>>,
+) -> Result<()> {
+    invoke(ctx, [123, 134, 81, 0, 49, 68, 98, 98], ())
+}
+
+pub fn add_liquidity_by_strategy<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ModifyLiquidity<'info>>,
+    liquidity_parameter: LiquidityParameterByStrategy,
+) -> Result<()> {
+    invoke(ctx, [7, 3, 150, 127, 148, 40, 61, 200], liquidity_parameter)
+}
+
+/// Removes `bps_to_remove` of the position's share in every bin of `[from_bin_id, to_bin_id]`.
+pub fn remove_liquidity_by_range<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ModifyLiquidity<'info>>,
+    from_bin_id: i32,
+    to_bin_id: i32,
+    bps_to_remove: u16,
+) -> Result<()> {
+    let args = (from_bin_id, to_bin_id, bps_to_remove);
+    invoke(ctx, [26, 82, 102, 152, 240, 74, 105, 26], args)
+}
+
+pub fn claim_fee<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ClaimFee<'info>>,
+) -> Result<()> {
+    invoke(ctx, [169, 32, 79, 137, 136, 232, 70, 137], ())
+}
+
+/// Swaps through the bin arrays in `remaining_accounts`, in the order the swap crosses them.
+pub fn swap<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::Swap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<()> {
+    invoke(
+        ctx,
+        [248, 198, 158, 145, 225, 117, 135, 200],
+        (amount_in, min_amount_out),
+    )
+}
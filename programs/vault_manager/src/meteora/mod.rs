@@ -0,0 +1,11 @@
+//! Minimal bindings for the Meteora DLMM (`lb_clmm`) program.
+//!
+//! Like the other DEX bindings, only the layouts and instructions used by the
+//! vault are mirrored here.
+
+use anchor_lang::prelude::*;
+
+pub mod cpi;
+pub mod state;
+
+declare_id!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
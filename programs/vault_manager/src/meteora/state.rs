@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+
+use crate::VaultError;
+
+pub const NUM_REWARDS: usize = 2;
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+pub const MAX_BIN_PER_POSITION: usize = 70;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub funder: Pubkey,
+    pub reward_duration: u64,
+    pub reward_duration_end: u64,
+    pub reward_rate: u128,
+    pub last_update_time: u64,
+    pub cumulative_seconds_with_empty_liquidity_reward: u64,
+}
+
+impl RewardInfo {
+    pub fn initialized(&self) -> bool {
+        self.mint != Pubkey::default()
+    }
+}
+
+/// Leading fields of the zero-copy `LbPair` account.
+///
+/// The fee parameters are kept as raw bytes since the vault never reads them.
+/// The bin array bitmap and the fields after the oracle are not read.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LbPair {
+    pub parameters: [u8; 32],
+    pub v_parameters: [u8; 32],
+    pub bump_seed: [u8; 1],
+    pub bin_step_seed: [u8; 2],
+    pub pair_type: u8,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub status: u8,
+    pub require_base_factor_seed: u8,
+    pub base_factor_seed: [u8; 2],
+    pub activation_type: u8,
+    pub creator_pool_on_off_control: u8,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub protocol_fee: [u8; 16],
+    pub padding_1: [u8; 32],
+    pub reward_infos: [RewardInfo; NUM_REWARDS],
+    pub oracle: Pubkey,
+}
+
+external_account!(LbPair, [33, 11, 49, 98, 181, 101, 177, 13]);
+
+/// Liquidity share and checkpoints of a position in one of its bins.
+#[derive(Clone, Copy, Default)]
+pub struct PositionBin {
+    pub liquidity_share: u128,
+    pub reward_per_token_completes: [u128; NUM_REWARDS],
+    pub reward_pendings: [u64; NUM_REWARDS],
+    pub fee_x_per_token_complete: u128,
+    pub fee_y_per_token_complete: u128,
+    pub fee_x_pending: u64,
+    pub fee_y_pending: u64,
+}
+
+/// Header of a zero-copy `PositionV2` account. Per-bin state is read with
+/// [`PositionV2::read_bin`], as the whole account is too large for the stack.
+#[derive(Clone, Copy)]
+pub struct PositionV2 {
+    pub lb_pair: Pubkey,
+    pub owner: Pubkey,
+    pub lower_bin_id: i32,
+    pub upper_bin_id: i32,
+}
+
+impl PositionV2 {
+    pub const DISCRIMINATOR: [u8; 8] = [117, 176, 212, 199, 245, 180, 133, 182];
+    const LB_PAIR_OFFSET: usize = 8;
+    const OWNER_OFFSET: usize = 40;
+    const LIQUIDITY_SHARES_OFFSET: usize = 72;
+    const REWARD_INFOS_OFFSET: usize = Self::LIQUIDITY_SHARES_OFFSET + 16 * MAX_BIN_PER_POSITION;
+    const FEE_INFOS_OFFSET: usize = Self::REWARD_INFOS_OFFSET + 48 * MAX_BIN_PER_POSITION;
+    const LOWER_BIN_ID_OFFSET: usize = Self::FEE_INFOS_OFFSET + 48 * MAX_BIN_PER_POSITION;
+    pub const LEN: usize = 8120;
+
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        let data = Self::data(info)?;
+        let i32_at = |at: usize| i32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        Ok(Self {
+            lb_pair: Pubkey::try_from(&data[Self::LB_PAIR_OFFSET..Self::OWNER_OFFSET]).unwrap(),
+            owner: Pubkey::try_from(&data[Self::OWNER_OFFSET..Self::LIQUIDITY_SHARES_OFFSET])
+                .unwrap(),
+            lower_bin_id: i32_at(Self::LOWER_BIN_ID_OFFSET),
+            upper_bin_id: i32_at(Self::LOWER_BIN_ID_OFFSET + 4),
+        })
+    }
+
+    /// State of the position in the bin at `index` from its lower bin.
+    pub fn read_bin(info: &AccountInfo, index: usize) -> Result<PositionBin> {
+        require!(index < MAX_BIN_PER_POSITION, VaultError::InvalidPosition);
+        let data = Self::data(info)?;
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let u128_at = |at: usize| u128::from_le_bytes(data[at..at + 16].try_into().unwrap());
+
+        let reward = Self::REWARD_INFOS_OFFSET + 48 * index;
+        let fee = Self::FEE_INFOS_OFFSET + 48 * index;
+        Ok(PositionBin {
+            liquidity_share: u128_at(Self::LIQUIDITY_SHARES_OFFSET + 16 * index),
+            reward_per_token_completes: [u128_at(reward), u128_at(reward + 16)],
+            reward_pendings: [u64_at(reward + 32), u64_at(reward + 40)],
+            fee_x_per_token_complete: u128_at(fee),
+            fee_y_per_token_complete: u128_at(fee + 16),
+            fee_x_pending: u64_at(fee + 32),
+            fee_y_pending: u64_at(fee + 40),
+        })
+    }
+
+    fn data<'a>(info: &'a AccountInfo) -> Result<std::cell::Ref<'a, &'a mut [u8]>> {
+        require_keys_eq!(*info.owner, super::ID, VaultError::InvalidPosition);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= Self::LEN && data[..8] == Self::DISCRIMINATOR,
+            VaultError::InvalidPosition
+        );
+        Ok(data)
+    }
+}
+
+/// Reserves and growth counters of a single bin, as stored in a `BinArray`.
+#[derive(Clone, Copy, Default)]
+pub struct Bin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub price: u128,
+    pub liquidity_supply: u128,
+    pub reward_per_token_stored: [u128; NUM_REWARDS],
+    pub fee_amount_x_per_token_stored: u128,
+    pub fee_amount_y_per_token_stored: u128,
+}
+
+impl Bin {
+    pub const LEN: usize = 8 + 8 + 16 + 16 + 16 * NUM_REWARDS + 16 + 16 + 16 + 16;
+}
+
+/// Read-only view over a zero-copy `BinArray` account.
+pub struct BinArray;
+
+impl BinArray {
+    pub const DISCRIMINATOR: [u8; 8] = [92, 142, 92, 220, 5, 148, 70, 181];
+    const INDEX_OFFSET: usize = 8;
+    const LB_PAIR_OFFSET: usize = 24;
+    const BINS_OFFSET: usize = 56;
+    pub const LEN: usize = Self::BINS_OFFSET + MAX_BIN_PER_ARRAY as usize * Bin::LEN;
+
+    /// Index of the bin array holding `bin_id`.
+    pub fn index(bin_id: i32) -> i64 {
+        i64::from(bin_id.div_euclid(MAX_BIN_PER_ARRAY))
+    }
+
+    /// Loads the bin `bin_id` from a bin array of `lb_pair`.
+    pub fn read_bin(info: &AccountInfo, lb_pair: &Pubkey, bin_id: i32) -> Result<Bin> {
+        require_keys_eq!(*info.owner, super::ID, VaultError::InvalidBinArray);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= Self::LEN
+                && data[..8] == Self::DISCRIMINATOR
+                && data[Self::LB_PAIR_OFFSET..Self::BINS_OFFSET] == lb_pair.to_bytes(),
+            VaultError::InvalidBinArray
+        );
+
+        let index_bytes: [u8; 8] = data[Self::INDEX_OFFSET..Self::INDEX_OFFSET + 8]
+            .try_into()
+            .unwrap();
+        require!(
+            i64::from_le_bytes(index_bytes) == Self::index(bin_id),
+            VaultError::InvalidBinArray
+        );
+
+        let offset = bin_id.rem_euclid(MAX_BIN_PER_ARRAY) as usize;
+        let start = Self::BINS_OFFSET + offset * Bin::LEN;
+        let raw = &data[start..start + Bin::LEN];
+        let u64_at = |at: usize| u64::from_le_bytes(raw[at..at + 8].try_into().unwrap());
+        let u128_at = |at: usize| u128::from_le_bytes(raw[at..at + 16].try_into().unwrap());
+        Ok(Bin {
+            amount_x: u64_at(0),
+            amount_y: u64_at(8),
+            price: u128_at(16),
+            liquidity_supply: u128_at(32),
+            reward_per_token_stored: [u128_at(48), u128_at(64)],
+            fee_amount_x_per_token_stored: u128_at(80),
+            fee_amount_y_per_token_stored: u128_at(96),
+        })
+    }
+}
@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+
+use crate::dex::{DexAdapter, MAX_REWARDS};
+use crate::math::{self, checked_add};
+
+/// Net asset value of a vault, returned by `get_nav` through return data.
+///
+/// Token totals include idle balances, position liquidity at the current sqrt
+/// price and uncollected fees. Uncollected rewards are reported per reward slot
+/// but left out of the totals, even in one of the vault's tokens: not every
+/// backend pays them out, and those that do pay them into reward accounts. A
+/// reward counts once it lands in the vault's token A or B account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VaultNav {
+    pub total_a: u64,
+    pub total_b: u64,
+    pub idle_a: u64,
+    pub idle_b: u64,
+    pub position_a: u64,
+    pub position_b: u64,
+    pub fees_owed_a: u64,
+    pub fees_owed_b: u64,
+    pub rewards_owed: [u64; MAX_REWARDS],
+    pub sqrt_price: u128,
+    /// Total value of the vault denominated in token B.
+    pub total_value_b: u128,
+    pub share_supply: u64,
+    /// Token B value of one share, Q64.64.
+    pub price_per_share_x64: u128,
+}
+
+impl VaultNav {
+    /// Shares owed for a deposit worth `value_b`, rounded down in favour of the vault.
+    pub fn shares_for_value(&self, value_b: u128) -> Result<u64> {
+        let shares = if self.share_supply == 0 || self.total_value_b == 0 {
+            value_b
+        } else {
+            math::mul_div(value_b, u128::from(self.share_supply), self.total_value_b)?
+        };
+        u64::try_from(shares).map_err(|_| error!(crate::VaultError::MathOverflow))
+    }
+
+    /// Token B value of `shares`, rounded down.
+    pub fn value_of_shares(&self, shares: u64) -> Result<u128> {
+        if self.share_supply == 0 {
+            return Ok(0);
+        }
+        math::mul_div(
+            u128::from(shares),
+            self.total_value_b,
+            u128::from(self.share_supply),
+        )
+    }
+
+    /// Shares owed for `value_b` the vault already holds and counts in this NAV,
+    /// priced against the rest of the vault and rounded down.
+    pub fn shares_for_held_value(&self, value_b: u128) -> Result<u64> {
+        let rest = self
+            .total_value_b
+            .checked_sub(value_b)
+            .ok_or(crate::VaultError::MathOverflow)?;
+        let shares = if self.share_supply == 0 || rest == 0 {
+            value_b
+        } else {
+            math::mul_div(value_b, u128::from(self.share_supply), rest)?
+        };
+        u64::try_from(shares).map_err(|_| error!(crate::VaultError::MathOverflow))
+    }
+}
+
+/// Token amounts held by a position, as reported by a DEX adapter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionValue {
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub fees_owed_a: u64,
+    pub fees_owed_b: u64,
+    /// Uncollected rewards per reward slot with their mint, default for unused slots.
+    pub rewards_owed: [(Pubkey, u64); MAX_REWARDS],
+}
+
+/// Balances needed to value a vault.
+pub struct NavInputs<'a> {
+    pub sqrt_price: u128,
+    pub position: &'a PositionValue,
+    pub idle_a: u64,
+    pub idle_b: u64,
+    pub share_supply: u64,
+}
+
+pub fn compute_nav(inputs: &NavInputs) -> Result<VaultNav> {
+    let position = inputs.position;
+
+    let mut rewards_owed = [0u64; MAX_REWARDS];
+    for (owed, (_, amount)) in rewards_owed.iter_mut().zip(&position.rewards_owed) {
+        *owed = *amount;
+    }
+
+    let total_a = [inputs.idle_a, position.amount_a, position.fees_owed_a]
+        .into_iter()
+        .try_fold(0u64, checked_add)?;
+    let total_b = [inputs.idle_b, position.amount_b, position.fees_owed_b]
+        .into_iter()
+        .try_fold(0u64, checked_add)?;
+
+    let total_value_b = math::a_to_b_value(total_a, inputs.sqrt_price)?
+        .checked_add(u128::from(total_b))
+        .ok_or(crate::VaultError::MathOverflow)?;
+    let price_per_share_x64 = if inputs.share_supply == 0 {
+        1u128 << 64
+    } else {
+        math::mul_div(total_value_b, 1u128 << 64, u128::from(inputs.share_supply))?
+    };
+
+    Ok(VaultNav {
+        total_a,
+        total_b,
+        idle_a: inputs.idle_a,
+        idle_b: inputs.idle_b,
+        position_a: position.amount_a,
+        position_b: position.amount_b,
+        fees_owed_a: position.fees_owed_a,
+        fees_owed_b: position.fees_owed_b,
+        rewards_owed,
+        sqrt_price: inputs.sqrt_price,
+        total_value_b,
+        share_supply: inputs.share_supply,
+        price_per_share_x64,
+    })
+}
+
+/// NAV of a vault with its position on `dex` and the given idle balances.
+pub fn vault_nav<'info, D: DexAdapter<'info>>(
+    dex: &D,
+    position: &D::Position,
+    idle_a: u64,
+    idle_b: u64,
+    share_supply: u64,
+) -> Result<VaultNav> {
+    compute_nav(&NavInputs {
+        sqrt_price: dex.price()?.sqrt_price,
+        position: &dex.position_value(position)?,
+        idle_a,
+        idle_b,
+        share_supply,
+    })
+}
+
+/// Growth counters of one boundary tick of a position.
+#[derive(Clone, Copy, Default)]
+pub struct TickGrowth {
+    pub initialized: bool,
+    pub fee_growth_outside_a: u128,
+    pub fee_growth_outside_b: u128,
+    pub reward_growths_outside: [u128; MAX_REWARDS],
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ClmmReward {
+    pub mint: Pubkey,
+    pub growth_global: u128,
+    pub growth_checkpoint: u128,
+    pub amount_owed: u64,
+}
+
+/// Tick-based position together with the pool state needed to value it.
+pub struct ClmmPosition {
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+    pub tick_lower: TickGrowth,
+    pub tick_upper: TickGrowth,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+    pub fee_growth_checkpoint_a: u128,
+    pub fee_growth_checkpoint_b: u128,
+    pub fee_owed_a: u64,
+    pub fee_owed_b: u64,
+    pub rewards: [ClmmReward; MAX_REWARDS],
+}
+
+impl ClmmPosition {
+    /// Liquidity at the current sqrt price plus fees and rewards accrued since the
+    /// position's last checkpoint.
+    pub fn value(&self) -> Result<PositionValue> {
+        let sqrt_lower = math::sqrt_price_from_tick_index(self.tick_lower_index)?;
+        let sqrt_upper = math::sqrt_price_from_tick_index(self.tick_upper_index)?;
+        let (amount_a, amount_b) =
+            math::amounts_for_liquidity(self.sqrt_price, sqrt_lower, sqrt_upper, self.liquidity)?;
+
+        let fee_inside_a = self.growth_inside(self.fee_growth_global_a, |t| t.fee_growth_outside_a);
+        let fee_inside_b = self.growth_inside(self.fee_growth_global_b, |t| t.fee_growth_outside_b);
+        let fees_owed_a = owed(
+            self.fee_owed_a,
+            fee_inside_a.wrapping_sub(self.fee_growth_checkpoint_a),
+            self.liquidity,
+        )?;
+        let fees_owed_b = owed(
+            self.fee_owed_b,
+            fee_inside_b.wrapping_sub(self.fee_growth_checkpoint_b),
+            self.liquidity,
+        )?;
+
+        let mut rewards_owed = [(Pubkey::default(), 0u64); MAX_REWARDS];
+        for (i, reward) in self.rewards.iter().enumerate() {
+            if reward.mint == Pubkey::default() {
+                continue;
+            }
+            let growth = self.growth_inside(reward.growth_global, |t| t.reward_growths_outside[i]);
+            let amount = owed(
+                reward.amount_owed,
+                growth.wrapping_sub(reward.growth_checkpoint),
+                self.liquidity,
+            )?;
+            rewards_owed[i] = (reward.mint, amount);
+        }
+
+        Ok(PositionValue {
+            amount_a,
+            amount_b,
+            fees_owed_a,
+            fees_owed_b,
+            rewards_owed,
+        })
+    }
+
+    /// Growth accrued inside the position range. Growth of an uninitialized
+    /// tick counts as having happened below it.
+    fn growth_inside(&self, global: u128, outside: impl Fn(&TickGrowth) -> u128) -> u128 {
+        let below = if !self.tick_lower.initialized {
+            global
+        } else if self.tick_current < self.tick_lower_index {
+            global.wrapping_sub(outside(&self.tick_lower))
+        } else {
+            outside(&self.tick_lower)
+        };
+        let above = if !self.tick_upper.initialized {
+            0
+        } else if self.tick_current < self.tick_upper_index {
+            outside(&self.tick_upper)
+        } else {
+            global.wrapping_sub(outside(&self.tick_upper))
+        };
+        global.wrapping_sub(below).wrapping_sub(above)
+    }
+}
+
+fn owed(already_owed: u64, growth_delta: u128, liquidity: u128) -> Result<u64> {
+    checked_add(
+        already_owed,
+        math::mul_shift_right_64(growth_delta, liquidity)?,
+    )
+}
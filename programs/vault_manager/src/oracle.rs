@@ -0,0 +1,128 @@
+//! External price oracle guard.
+//!
+//! Price accounts use the Pyth v2 price account layout. Only the header and the
+//! aggregate price are read, so any account with that layout (for example a
+//! local mock) can stand in for a Pyth feed. The feed address is chosen by the
+//! vault authority, which is what establishes trust in it.
+
+use anchor_lang::prelude::*;
+
+use crate::math::{self, U256};
+use crate::{Vault, VaultError};
+
+const MAGIC: u32 = 0xa1b2_c3d4;
+const VERSION: u32 = 2;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const STATUS_TRADING: u32 = 1;
+
+const EXPO_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+const HEADER_LEN: usize = 240;
+
+/// Aggregate price of token A denominated in token B.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub status: u32,
+}
+
+impl PriceFeed {
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        let data = info.try_borrow_data()?;
+        require!(data.len() >= HEADER_LEN, VaultError::InvalidOracle);
+
+        let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        require!(
+            u32_at(0) == MAGIC && u32_at(4) == VERSION && u32_at(8) == ACCOUNT_TYPE_PRICE,
+            VaultError::InvalidOracle
+        );
+
+        Ok(Self {
+            price: u64_at(AGG_PRICE_OFFSET) as i64,
+            conf: u64_at(AGG_CONF_OFFSET),
+            expo: u32_at(EXPO_OFFSET) as i32,
+            publish_time: u64_at(TIMESTAMP_OFFSET) as i64,
+            status: u32_at(AGG_STATUS_OFFSET),
+        })
+    }
+
+    /// Oracle price converted to the pool's raw Q64.64 price (token B atoms per token A atom).
+    pub fn price_x64(&self, decimals_a: u8, decimals_b: u8) -> Result<u128> {
+        require!(self.price > 0, VaultError::InvalidOracle);
+        let exponent = self
+            .expo
+            .checked_add(i32::from(decimals_b) - i32::from(decimals_a))
+            .ok_or(VaultError::InvalidOracle)?;
+        require!(exponent.abs() <= 38, VaultError::InvalidOracle);
+
+        let scale = 10u128.pow(exponent.unsigned_abs());
+        let price = self.price as u128;
+        if exponent >= 0 {
+            let scaled = U256::from(price) * U256::from(scale);
+            require!(scaled < U256::one() << 64, VaultError::MathOverflow);
+            Ok(scaled.as_u128() << 64)
+        } else {
+            math::mul_div(price, 1u128 << 64, scale)
+        }
+    }
+}
+
+/// Price of token A in token B, Q64.64, that user cost basis is recorded at: the
+/// oracle's when the vault has one, otherwise the pool's. The feed is expected to
+/// have passed `guard_pool_price`.
+pub fn reference_price_x64(
+    vault: &Vault,
+    oracle: Option<&AccountInfo>,
+    sqrt_price: u128,
+) -> Result<u128> {
+    match oracle.filter(|_| vault.config.price_oracle != Pubkey::default()) {
+        Some(info) => {
+            PriceFeed::load(info)?.price_x64(vault.token_a_decimals, vault.token_b_decimals)
+        }
+        None => math::price_x64_from_sqrt_price(sqrt_price),
+    }
+}
+
+/// Rejects the operation when the vault has an oracle configured and the pool
+/// price is stale relative to it or deviates by more than the configured bps.
+pub fn guard_pool_price(
+    vault: &Vault,
+    oracle: Option<&AccountInfo>,
+    sqrt_price: u128,
+) -> Result<()> {
+    if vault.config.price_oracle == Pubkey::default() {
+        return Ok(());
+    }
+    let info = oracle.ok_or(VaultError::OracleRequired)?;
+    require_keys_eq!(
+        info.key(),
+        vault.config.price_oracle,
+        VaultError::InvalidOracle
+    );
+
+    let feed = PriceFeed::load(info)?;
+    require!(feed.status == STATUS_TRADING, VaultError::StaleOracle);
+    let now = Clock::get()?.unix_timestamp;
+    let age = now.saturating_sub(feed.publish_time);
+    require!(
+        age <= i64::from(vault.config.max_oracle_staleness_secs),
+        VaultError::StaleOracle
+    );
+
+    let oracle_price = feed.price_x64(vault.token_a_decimals, vault.token_b_decimals)?;
+    let pool_price = math::price_x64_from_sqrt_price(sqrt_price)?;
+    let deviation = pool_price.abs_diff(oracle_price);
+    let deviation_bps = math::mul_div(deviation, 10_000, oracle_price)?;
+    require!(
+        deviation_bps <= u128::from(vault.config.max_oracle_deviation_bps),
+        VaultError::OraclePriceDeviation
+    );
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Protocol share of the fees vault positions earn.
+//!
+//! Instructions that collect a position's fees (`collect`, `withdraw`,
+//! `rebalance` and `migrate_liquidity` on the source vault) take the
+//! `ProtocolConfig` and the treasury's token accounts. `fee_share_bps` of the
+//! fees collected goes to the treasury, rounded down, before the rest is
+//! shared by the vault's holders. Every collection emits `FeesCollected`.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, TokenAccount, Transfer};
+
+use crate::dex::{DexAccounts, DexAdapter};
+use crate::math;
+use crate::state::ProtocolConfig;
+
+/// Where and how much of the collected fees go to the protocol.
+pub struct ProtocolFee<'info> {
+    pub fee_share_bps: u16,
+    pub treasury_token_a: AccountInfo<'info>,
+    pub treasury_token_b: AccountInfo<'info>,
+}
+
+impl<'info> ProtocolFee<'info> {
+    pub fn new(
+        config: &ProtocolConfig,
+        treasury_token_a: &Account<'info, TokenAccount>,
+        treasury_token_b: &Account<'info, TokenAccount>,
+    ) -> Self {
+        Self {
+            fee_share_bps: config.fee_share_bps,
+            treasury_token_a: treasury_token_a.to_account_info(),
+            treasury_token_b: treasury_token_b.to_account_info(),
+        }
+    }
+
+    /// Collects `position`'s fees into the vault token accounts, which are
+    /// reloaded, and pays the protocol its share. Returns the fees collected
+    /// and the protocol's share of them, per token.
+    pub fn collect_fees<D: DexAdapter<'info>>(
+        &self,
+        dex: &D,
+        position: &D::Position,
+        accounts: &DexAccounts<'info>,
+        vault_token_a: &mut Account<'info, TokenAccount>,
+        vault_token_b: &mut Account<'info, TokenAccount>,
+    ) -> Result<CollectedFees> {
+        vault_token_a.reload()?;
+        vault_token_b.reload()?;
+        let (before_a, before_b) = (vault_token_a.amount, vault_token_b.amount);
+        dex.collect_fees(position)?;
+        vault_token_a.reload()?;
+        vault_token_b.reload()?;
+
+        let fees_a = math::checked_sub(vault_token_a.amount, before_a)?;
+        let fees_b = math::checked_sub(vault_token_b.amount, before_b)?;
+        let protocol_a = math::mul_div_u64(fees_a, u64::from(self.fee_share_bps), 10_000)?;
+        let protocol_b = math::mul_div_u64(fees_b, u64::from(self.fee_share_bps), 10_000)?;
+        for (from, to, amount) in [
+            (&mut *vault_token_a, &self.treasury_token_a, protocol_a),
+            (&mut *vault_token_b, &self.treasury_token_b, protocol_b),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            accounts.with_vault_signer(|signer| {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        accounts.token_program.clone(),
+                        Transfer {
+                            from: from.to_account_info(),
+                            to: to.clone(),
+                            authority: accounts.vault.clone(),
+                        },
+                        signer,
+                    ),
+                    amount,
+                )
+            })?;
+            from.reload()?;
+        }
+        let collected = CollectedFees {
+            fees_a,
+            fees_b,
+            protocol_a,
+            protocol_b,
+        };
+        emit!(FeesCollected {
+            vault: accounts.vault.key(),
+            fees: collected,
+        });
+        Ok(collected)
+    }
+}
+
+/// Fees one collection took from a position, and the protocol's share of them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CollectedFees {
+    pub fees_a: u64,
+    pub fees_b: u64,
+    pub protocol_a: u64,
+    pub protocol_b: u64,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub vault: Pubkey,
+    pub fees: CollectedFees,
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+
+use crate::bindings::invoke;
+
+pub mod accounts {
+    use super::*;
+
+    cpi_accounts!(OpenPosition {
+        payer: writable_signer,
+        position_nft_owner: readonly,
+        position_nft_mint: writable_signer,
+        position_nft_account: writable,
+        metadata_account: writable,
+        pool_state: writable,
+        protocol_position: writable,
+        tick_array_lower: writable,
+        tick_array_upper: writable,
+        personal_position: writable,
+        token_account_0: writable,
+        token_account_1: writable,
+        token_vault_0: writable,
+        token_vault_1: writable,
+        rent: readonly,
+        system_program: readonly,
+        token_program: readonly,
+        associated_token_program: readonly,
+        metadata_program: readonly,
+    });
+
+    cpi_accounts!(ClosePosition {
+        nft_owner: writable_signer,
+        position_nft_mint: writable,
+        position_nft_account: writable,
+        personal_position: writable,
+        system_program: readonly,
+        token_program: readonly,
+    });
+
+    cpi_accounts!(IncreaseLiquidity {
+        nft_owner: signer,
+        nft_account: readonly,
+        pool_state: writable,
+        protocol_position: writable,
+        personal_position: writable,
+        tick_array_lower: writable,
+        tick_array_upper: writable,
+        token_account_0: writable,
+        token_account_1: writable,
+        token_vault_0: writable,
+        token_vault_1: writable,
+        token_program: readonly,
+    });
+
+    cpi_accounts!(DecreaseLiquidity {
+        nft_owner: signer,
+        nft_account: readonly,
+        personal_position: writable,
+        pool_state: writable,
+        protocol_position: writable,
+        token_vault_0: writable,
+        token_vault_1: writable,
+        tick_array_lower: writable,
+        tick_array_upper: writable,
+        recipient_token_account_0: writable,
+        recipient_token_account_1: writable,
+        token_program: readonly,
+    });
+
+    cpi_accounts!(Swap {
+        payer: signer,
+        amm_config: readonly,
+        pool_state: writable,
+        input_token_account: writable,
+        output_token_account: writable,
+        input_vault: writable,
+        output_vault: writable,
+        observation_state: writable,
+        token_program: readonly,
+        tick_array: writable,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn open_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::OpenPosition<'info>>,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Result<()> {
+    let args = (
+        tick_lower_index,
+        tick_upper_index,
+        tick_array_lower_start_index,
+        tick_array_upper_start_index,
+        liquidity,
+        amount_0_max,
+        amount_1_max,
+    );
+    invoke(ctx, [135, 128, 47, 77, 15, 152, 240, 49], args)
+}
+
+/// Burns the position NFT and closes the empty position, paying the rent to `nft_owner`.
+pub fn close_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ClosePosition<'info>>,
+) -> Result<()> {
+    invoke(ctx, [123, 134, 81, 0, 49, 68, 98, 98], ())
+}
+
+pub fn increase_liquidity<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::IncreaseLiquidity<'info>>,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Result<()> {
+    let args = (liquidity, amount_0_max, amount_1_max);
+    invoke(ctx, [46, 156, 243, 118, 13, 205, 251, 178], args)
+}
+
+/// Removes liquidity and collects fees; reward accounts go in `remaining_accounts`
+/// as (reward vault, recipient) pairs for every initialized pool reward.
+pub fn decrease_liquidity<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::DecreaseLiquidity<'info>>,
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+) -> Result<()> {
+    let args = (liquidity, amount_0_min, amount_1_min);
+    invoke(ctx, [160, 38, 208, 111, 104, 91, 44, 1], args)
+}
+
+/// Swaps through `tick_array` and any further tick arrays in `remaining_accounts`.
+pub fn swap<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::Swap<'info>>,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: u128,
+    is_base_input: bool,
+) -> Result<()> {
+    let args = (
+        amount,
+        other_amount_threshold,
+        sqrt_price_limit_x64,
+        is_base_input,
+    );
+    invoke(ctx, [248, 198, 158, 145, 225, 117, 135, 200], args)
+}
@@ -0,0 +1,11 @@
+//! Minimal bindings for the Raydium concentrated liquidity (CLMM) program.
+//!
+//! Like the Whirlpool bindings, only the layouts and instructions used by the
+//! vault are mirrored here.
+
+use anchor_lang::prelude::*;
+
+pub mod cpi;
+pub mod state;
+
+declare_id!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+
+use crate::VaultError;
+
+pub const NUM_REWARDS: usize = 3;
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
+impl RewardInfo {
+    pub fn initialized(&self) -> bool {
+        self.token_mint != Pubkey::default()
+    }
+}
+
+/// Leading fields of the zero-copy `PoolState` account.
+///
+/// The account is `repr(C, packed)`, so its prefix decodes like borsh. The tick
+/// array bitmap and the fields after it are not read.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PoolState {
+    pub bump: [u8; 1],
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub padding3: u16,
+    pub padding4: u16,
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    pub status: u8,
+    pub padding: [u8; 7],
+    pub reward_infos: [RewardInfo; NUM_REWARDS],
+}
+
+external_account!(PoolState, [247, 237, 227, 245, 215, 195, 222, 70]);
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PositionRewardInfo {
+    pub growth_inside_last_x64: u128,
+    pub reward_amount_owed: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PersonalPositionState {
+    pub bump: [u8; 1],
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x64: u128,
+    pub fee_growth_inside_1_last_x64: u128,
+    pub token_fees_owed_0: u64,
+    pub token_fees_owed_1: u64,
+    pub reward_infos: [PositionRewardInfo; NUM_REWARDS],
+}
+
+external_account!(PersonalPositionState, [70, 111, 150, 126, 230, 15, 25, 117]);
+
+pub const OBSERVATION_NUM: usize = 100;
+
+/// One slot of the pool's observation ring buffer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Observation {
+    /// Unix timestamp of the observation, zero for an unwritten slot.
+    pub block_timestamp: u32,
+    /// Sum of `tick_current * seconds elapsed` since the pool was created.
+    pub tick_cumulative: i64,
+}
+
+/// Read-only view over a zero-copy `ObservationState` account, the tick
+/// observations swaps record at most once per update interval.
+pub struct ObservationState {
+    pub pool_id: Pubkey,
+    /// Slot of the most recent observation.
+    pub observation_index: u16,
+    pub observations: [Observation; OBSERVATION_NUM],
+}
+
+impl ObservationState {
+    pub const DISCRIMINATOR: [u8; 8] = [122, 174, 197, 53, 129, 9, 165, 132];
+    const INDEX_OFFSET: usize = 17;
+    const POOL_OFFSET: usize = 19;
+    const OBSERVATIONS_OFFSET: usize = 51;
+    const OBSERVATION_LEN: usize = 4 + 8 + 8 * 4;
+    pub const LEN: usize =
+        Self::OBSERVATIONS_OFFSET + OBSERVATION_NUM * Self::OBSERVATION_LEN + 8 * 4;
+
+    /// Loads the observation account of `pool`.
+    pub fn load(info: &AccountInfo, pool: &Pubkey) -> Result<Self> {
+        require_keys_eq!(*info.owner, super::ID, VaultError::InvalidObservationState);
+        let state = Self::parse(&info.try_borrow_data()?)?;
+        require_keys_eq!(state.pool_id, *pool, VaultError::InvalidObservationState);
+        Ok(state)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= Self::LEN && data[..8] == Self::DISCRIMINATOR,
+            VaultError::InvalidObservationState
+        );
+        let mut observations = [Observation::default(); OBSERVATION_NUM];
+        for (i, observation) in observations.iter_mut().enumerate() {
+            let at = Self::OBSERVATIONS_OFFSET + i * Self::OBSERVATION_LEN;
+            *observation = Observation {
+                block_timestamp: u32::from_le_bytes(data[at..at + 4].try_into().unwrap()),
+                tick_cumulative: i64::from_le_bytes(data[at + 4..at + 12].try_into().unwrap()),
+            };
+        }
+        Ok(Self {
+            pool_id: Pubkey::try_from(&data[Self::POOL_OFFSET..Self::OBSERVATIONS_OFFSET]).unwrap(),
+            observation_index: u16::from_le_bytes(
+                data[Self::INDEX_OFFSET..Self::POOL_OFFSET]
+                    .try_into()
+                    .unwrap(),
+            ),
+            observations,
+        })
+    }
+
+    /// Average tick over at least the last `window_secs`, extending the newest
+    /// observation to `now` with the pool's current tick.
+    pub fn twap_tick(&self, tick_current: i32, now: i64, window_secs: u32) -> Result<i32> {
+        let newest_index = usize::from(self.observation_index) % OBSERVATION_NUM;
+        let newest = self.observations[newest_index];
+        require!(
+            newest.block_timestamp > 0,
+            VaultError::InsufficientOracleHistory
+        );
+        let cumulative_now = i64::from(tick_current)
+            .checked_mul(now.saturating_sub(i64::from(newest.block_timestamp)))
+            .and_then(|extension| newest.tick_cumulative.checked_add(extension))
+            .ok_or(VaultError::MathOverflow)?;
+
+        let target = now.saturating_sub(i64::from(window_secs));
+        for age in 0..OBSERVATION_NUM {
+            let observation =
+                self.observations[(newest_index + OBSERVATION_NUM - age) % OBSERVATION_NUM];
+            let timestamp = i64::from(observation.block_timestamp);
+            if timestamp == 0 {
+                break;
+            }
+            if timestamp <= target {
+                let elapsed = now.checked_sub(timestamp).ok_or(VaultError::MathOverflow)?;
+                require!(elapsed > 0, VaultError::InsufficientOracleHistory);
+                let delta = cumulative_now
+                    .checked_sub(observation.tick_cumulative)
+                    .ok_or(VaultError::MathOverflow)?;
+                return i32::try_from(delta.div_euclid(elapsed))
+                    .map_err(|_| error!(VaultError::MathOverflow));
+            }
+        }
+        err!(VaultError::InsufficientOracleHistory)
+    }
+}
+
+/// Growth counters of a single tick, as stored in a `TickArrayState`.
+#[derive(Clone, Copy, Default)]
+pub struct TickState {
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_0_x64: u128,
+    pub fee_growth_outside_1_x64: u128,
+    pub reward_growths_outside_x64: [u128; NUM_REWARDS],
+}
+
+impl TickState {
+    pub const LEN: usize = 4 + 16 + 16 + 16 + 16 + 16 * NUM_REWARDS + 4 * 13;
+}
+
+/// Read-only view over a zero-copy `TickArrayState` account.
+pub struct TickArrayState;
+
+impl TickArrayState {
+    pub const DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+    const POOL_OFFSET: usize = 8;
+    const START_TICK_OFFSET: usize = 40;
+    const TICKS_OFFSET: usize = 44;
+    const TICKS_END: usize = Self::TICKS_OFFSET + TICK_ARRAY_SIZE as usize * TickState::LEN;
+
+    /// Start index of the tick array holding `tick_index`.
+    pub fn start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = TICK_ARRAY_SIZE * i32::from(tick_spacing);
+        tick_index.div_euclid(ticks_in_array) * ticks_in_array
+    }
+
+    /// Loads the tick at `tick_index` from a tick array of `pool`.
+    pub fn read_tick(
+        info: &AccountInfo,
+        pool: &Pubkey,
+        tick_index: i32,
+        tick_spacing: u16,
+    ) -> Result<TickState> {
+        require_keys_eq!(*info.owner, super::ID, VaultError::InvalidTickArray);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= Self::TICKS_END
+                && data[..8] == Self::DISCRIMINATOR
+                && data[Self::POOL_OFFSET..Self::START_TICK_OFFSET] == pool.to_bytes(),
+            VaultError::InvalidTickArray
+        );
+
+        let start_bytes: [u8; 4] = data[Self::START_TICK_OFFSET..Self::TICKS_OFFSET]
+            .try_into()
+            .unwrap();
+        let start_tick_index = i32::from_le_bytes(start_bytes);
+        let spacing = i32::from(tick_spacing);
+        let offset = tick_index
+            .checked_sub(start_tick_index)
+            .ok_or(VaultError::InvalidTickArray)?;
+        require!(
+            spacing > 0
+                && offset >= 0
+                && offset % spacing == 0
+                && offset / spacing < TICK_ARRAY_SIZE,
+            VaultError::InvalidTickArray
+        );
+
+        let start = Self::TICKS_OFFSET + (offset / spacing) as usize * TickState::LEN;
+        let raw = &data[start..start + TickState::LEN];
+        let u128_at = |at: usize| u128::from_le_bytes(raw[at..at + 16].try_into().unwrap());
+
+        let mut reward_growths_outside_x64 = [0u128; NUM_REWARDS];
+        for (i, growth) in reward_growths_outside_x64.iter_mut().enumerate() {
+            *growth = u128_at(68 + 16 * i);
+        }
+        Ok(TickState {
+            liquidity_gross: u128_at(20),
+            fee_growth_outside_0_x64: u128_at(36),
+            fee_growth_outside_1_x64: u128_at(52),
+            reward_growths_outside_x64,
+        })
+    }
+}
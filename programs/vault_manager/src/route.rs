@@ -0,0 +1,129 @@
+//! Zap swaps routed through an external swap program.
+//!
+//! Swapping in the pool the vault provides liquidity to moves that pool's price
+//! against the deposit. A route hands the swap to a program on the vault's
+//! whitelist, such as an aggregator, with caller-supplied accounts and
+//! instruction data. The vault signs for its token accounts, so the outcome is
+//! checked from their balances rather than trusted, and no other token account
+//! the vault could sign for, such as its position NFT account, may be passed,
+//! nor the vault's position itself.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, TokenAccount};
+
+use crate::dex::{next_account, RemainingAccounts};
+use crate::{Vault, VaultError};
+
+/// Number of swap programs a vault can whitelist.
+pub const MAX_SWAP_PROGRAMS: usize = 4;
+
+/// Caller-built swap instruction for a whitelisted program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwapRoute {
+    /// Instruction data passed to the swap program as is.
+    pub data: Vec<u8>,
+    /// Least amount of the output token the vault must receive.
+    pub min_amount_out: u64,
+}
+
+pub struct RouteAccounts<'info> {
+    pub program: AccountInfo<'info>,
+    pub accounts: Vec<AccountInfo<'info>>,
+}
+
+impl<'info> RouteAccounts<'info> {
+    /// Takes the swap program and every account after it from `remaining`.
+    /// Of the token accounts `vault_key` owns or is the delegate of, only the
+    /// vault's token A and B accounts are allowed, and the vault's position is
+    /// refused.
+    pub fn load(
+        vault_key: &Pubkey,
+        vault: &Vault,
+        remaining: &mut RemainingAccounts<'_, 'info>,
+    ) -> Result<Self> {
+        let program = next_account(remaining)?;
+        require!(
+            program.executable && vault.allows_swap_program(program.key),
+            VaultError::SwapProgramNotAllowed
+        );
+        let accounts: Vec<_> = remaining.cloned().collect();
+        for info in &accounts {
+            require!(
+                *info.key != vault.position
+                    && (*info.key == vault.vault_token_a
+                        || *info.key == vault.vault_token_b
+                        || !spendable_by(vault_key, info)),
+                VaultError::RouteAccountNotAllowed
+            );
+        }
+        Ok(Self { program, accounts })
+    }
+
+    /// Swap instruction over the route accounts in order, with `vault` marked as
+    /// a signer wherever it appears.
+    pub fn instruction(&self, vault: &Pubkey, data: Vec<u8>) -> Instruction {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|info| AccountMeta {
+                pubkey: *info.key,
+                is_signer: info.is_signer || info.key == vault,
+                is_writable: info.is_writable,
+            })
+            .collect();
+        Instruction {
+            program_id: *self.program.key,
+            accounts,
+            data,
+        }
+    }
+
+    pub fn invoke(&self, instruction: &Instruction, signer: &[&[&[u8]]]) -> Result<()> {
+        let mut infos = self.accounts.clone();
+        infos.push(self.program.clone());
+        invoke_signed(instruction, &infos, signer).map_err(Into::into)
+    }
+}
+
+/// Whether `info` is a token account `authority`'s signature lets a program spend.
+fn spendable_by(authority: &Pubkey, info: &AccountInfo) -> bool {
+    if *info.owner != token::ID {
+        return false;
+    }
+    // An account that cannot be read is refused
+    let Ok(data) = info.try_borrow_data() else {
+        return true;
+    };
+    TokenAccount::try_deserialize(&mut &data[..]).is_ok_and(|account| {
+        account.owner == *authority || account.delegate == COption::Some(*authority)
+    })
+}
+
+/// Balances of the vault's input and output token accounts around a swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapBalances {
+    pub input: u64,
+    pub output: u64,
+}
+
+/// Checks a routed swap spent at most `max_amount_in` of the input token, took
+/// none of the output token and brought in at least `min_amount_out` of it.
+/// Returns the amounts spent and received.
+pub fn check_swap(
+    before: SwapBalances,
+    after: SwapBalances,
+    max_amount_in: u64,
+    min_amount_out: u64,
+) -> Result<(u64, u64)> {
+    let spent = before.input.saturating_sub(after.input);
+    let received = after
+        .output
+        .checked_sub(before.output)
+        .ok_or(VaultError::RouteOverspent)?;
+    require!(spent <= max_amount_in, VaultError::RouteOverspent);
+    require!(received >= min_amount_out, VaultError::SwapOutputBelowMinimum);
+    Ok((spent, received))
+}
@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+/// Marks `wallet` as allowed to deposit into `vault`, see `crate::allowlist`.
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub bump: u8,
+}
@@ -0,0 +1,17 @@
+//! Accounts owned by the program.
+
+pub mod allowlist_entry;
+pub mod pending_param_change;
+pub mod protocol_config;
+pub mod strategy_config;
+pub mod user_position;
+pub mod vault;
+pub mod vault_registry;
+
+pub use allowlist_entry::*;
+pub use pending_param_change::*;
+pub use protocol_config::*;
+pub use strategy_config::*;
+pub use user_position::*;
+pub use vault::*;
+pub use vault_registry::*;
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+use crate::governance::ParamChange;
+
+/// The vault's queued parameter change; one at a time.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingParamChange {
+    pub vault: Pubkey,
+    pub change: ParamChange,
+    pub queued_slot: u64,
+    /// First slot `execute_param_change` accepts.
+    pub execute_after_slot: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::state::VAULTS_PER_REGISTRY_PAGE;
+use crate::VaultError;
+
+pub const MAX_DEX_PROGRAMS: usize = 4;
+
+/// Program-wide settings, a single PDA at `[PROTOCOL_CONFIG_SEED]`.
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    /// Signs protocol configuration instructions.
+    pub admin: Pubkey,
+    /// Share of vault fees owed to the protocol, in bps.
+    pub fee_share_bps: u16,
+    /// Wallet receiving the protocol's fee share.
+    pub treasury: Pubkey,
+    /// DEX programs new vaults may be created on, `Pubkey::default()` for unused slots.
+    pub dex_programs: [Pubkey; MAX_DEX_PROGRAMS],
+    /// Vaults created so far, which is also the index of the next vault in the registry.
+    pub vault_count: u64,
+    pub bump: u8,
+}
+
+impl ProtocolConfig {
+    /// Sets the fee share, treasury and DEX programs, as given to the protocol
+    /// config instructions.
+    pub fn configure(
+        &mut self,
+        fee_share_bps: u16,
+        treasury: Pubkey,
+        dex_programs: &[Pubkey],
+    ) -> Result<()> {
+        require!(fee_share_bps < 10_000, VaultError::InvalidFee);
+        require!(
+            dex_programs.len() <= MAX_DEX_PROGRAMS,
+            VaultError::TooManyDexPrograms
+        );
+        self.fee_share_bps = fee_share_bps;
+        self.treasury = treasury;
+        self.dex_programs = [Pubkey::default(); MAX_DEX_PROGRAMS];
+        self.dex_programs[..dex_programs.len()].copy_from_slice(dex_programs);
+        Ok(())
+    }
+
+    pub fn allows_dex_program(&self, program: &Pubkey) -> bool {
+        *program != Pubkey::default() && self.dex_programs.contains(program)
+    }
+
+    /// Registry page the next vault is appended to.
+    pub fn registry_page(&self) -> u32 {
+        (self.vault_count / VAULTS_PER_REGISTRY_PAGE as u64) as u32
+    }
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Rebalancing policy of a vault, for the keepers that rebalance it: centre new
+/// positions `range_width` ticks (bins on DLMM) either side of the price, and
+/// rebalance once the price comes within `rebalance_threshold` of a range edge.
+#[account]
+#[derive(InitSpace)]
+pub struct StrategyConfig {
+    pub vault: Pubkey,
+    pub range_width: u32,
+    pub rebalance_threshold: u32,
+    pub bump: u8,
+}
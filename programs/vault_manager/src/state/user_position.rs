@@ -0,0 +1,91 @@
+//! Per-depositor receipt used to show each user their PnL.
+//!
+//! Shares stay plain SPL tokens; the receipt only tracks what its owner minted
+//! and redeemed. Shares received by transfer are not covered, so redemptions
+//! beyond the tracked shares leave the receipt at zero.
+
+use anchor_lang::prelude::*;
+
+use crate::math::{self, checked_add};
+use crate::VaultError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserPosition {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    /// Shares minted to the user and not yet redeemed.
+    pub shares: u64,
+    pub deposited_a: u64,
+    pub deposited_b: u64,
+    pub withdrawn_a: u64,
+    pub withdrawn_b: u64,
+    /// Token B value of the tracked shares when deposited, in token B atoms, at
+    /// the oracle price (the pool price without an oracle). Reduced pro rata on
+    /// withdraw.
+    pub cost_basis_b: u128,
+    pub first_deposit_at: i64,
+    pub last_deposit_at: i64,
+    pub bump: u8,
+    /// Slot of the latest deposit, which starts the vault's lockup over.
+    pub last_deposit_slot: u64,
+    /// Shares the user asked to withdraw in a vault with a withdrawal queue.
+    pub pending_withdrawal_shares: u64,
+    pub withdrawal_requested_slot: u64,
+}
+
+impl UserPosition {
+    /// Fills in a receipt `init_if_needed` just created; existing receipts are left as is.
+    pub fn init_if_new(&mut self, vault: Pubkey, user: Pubkey, bump: u8) {
+        if self.vault == Pubkey::default() {
+            self.vault = vault;
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    /// Records a deposit of `amount_a` and `amount_b` worth `cost_basis` that minted `shares`.
+    pub fn record_deposit(
+        &mut self,
+        amount_a: u64,
+        amount_b: u64,
+        cost_basis: u128,
+        shares: u64,
+        now: i64,
+        slot: u64,
+    ) -> Result<()> {
+        self.shares = checked_add(self.shares, shares)?;
+        self.deposited_a = checked_add(self.deposited_a, amount_a)?;
+        self.deposited_b = checked_add(self.deposited_b, amount_b)?;
+        self.cost_basis_b = self
+            .cost_basis_b
+            .checked_add(cost_basis)
+            .ok_or(VaultError::MathOverflow)?;
+        if self.first_deposit_at == 0 {
+            self.first_deposit_at = now;
+        }
+        self.last_deposit_at = now;
+        self.last_deposit_slot = slot;
+        Ok(())
+    }
+
+    /// Records a redemption of `shares` paying out `amount_a` and `amount_b`.
+    pub fn record_withdraw(&mut self, shares: u64, amount_a: u64, amount_b: u64) -> Result<()> {
+        let redeemed = shares.min(self.shares);
+        if redeemed > 0 {
+            let released = math::mul_div(
+                self.cost_basis_b,
+                u128::from(redeemed),
+                u128::from(self.shares),
+            )?;
+            self.cost_basis_b = self
+                .cost_basis_b
+                .checked_sub(released)
+                .ok_or(VaultError::MathOverflow)?;
+            self.shares = math::checked_sub(self.shares, redeemed)?;
+        }
+        self.withdrawn_a = checked_add(self.withdrawn_a, amount_a)?;
+        self.withdrawn_b = checked_add(self.withdrawn_b, amount_b)?;
+        Ok(())
+    }
+}
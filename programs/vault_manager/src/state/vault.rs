@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::dex::{BinStrategy, Dex};
+use crate::route::MAX_SWAP_PROGRAMS;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    /// Signs configuration instructions. Any signer works, including a multisig
+    /// program's PDA: rent for new accounts comes from a separate payer.
+    pub authority: Pubkey,
+    /// Backend the pool and position belong to.
+    pub dex: Dex,
+    pub pool: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub vault_token_a: Pubkey,
+    pub vault_token_b: Pubkey,
+    pub position: Pubkey,
+    pub share_mint: Pubkey,
+    pub config: VaultConfig,
+    pub strategy: VaultStrategy,
+    pub fees: VaultFees,
+    /// NAV per share recorded by the latest operation, Q64.64, zero when none.
+    pub last_nav_per_share_x64: u128,
+    pub last_nav_slot: u64,
+    /// Refuses deposits, rebalances and migrations into the vault, see `circuit_breaker`.
+    pub paused: bool,
+    pub token_a_decimals: u8,
+    pub token_b_decimals: u8,
+    pub bump: u8,
+    pub is_active: bool,
+}
+
+impl Vault {
+    pub fn allows_swap_program(&self, program: &Pubkey) -> bool {
+        *program != Pubkey::default() && self.strategy.swap_programs.contains(program)
+    }
+}
+
+/// Guards and limits on what depositors and the authority can do.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct VaultConfig {
+    /// External price feed, `Pubkey::default()` when the oracle guard is disabled.
+    pub price_oracle: Pubkey,
+    pub max_oracle_deviation_bps: u16,
+    pub max_oracle_staleness_secs: u32,
+    /// TWAP window checked on rebalance, zero when the TWAP guard is disabled.
+    pub twap_window_secs: u32,
+    pub max_twap_deviation_ticks: u32,
+    /// Deposit limits in token B value, zero when disabled.
+    pub min_deposit: u64,
+    pub user_deposit_cap: u64,
+    pub deposit_cap: u64,
+    /// Deposits need an `AllowlistEntry` for the depositor while enabled.
+    pub allowlist_enabled: bool,
+    /// Root of the wallets that may join the allowlist themselves, zero when unused.
+    pub allowlist_merkle_root: [u8; 32],
+    /// Slots a wallet's shares stay locked after its latest deposit, zero when disabled.
+    pub lockup_slots: u64,
+    /// Slots between `request_withdrawal` and the withdrawal, zero when the queue is disabled.
+    pub withdrawal_delay_slots: u64,
+    /// Slots a queued parameter change waits; when non-zero, setters are refused.
+    pub param_timelock_slots: u64,
+    /// NAV per share drop in bps that trips the circuit breaker, zero when disabled.
+    pub max_nav_drop_bps: u16,
+}
+
+/// How the vault provides liquidity and swaps.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct VaultStrategy {
+    /// Liquidity distribution across bins, only used by bin-based backends.
+    pub bin_strategy: BinStrategy,
+    /// Programs deposits may route their zap swap through, `Pubkey::default()` for unused slots.
+    pub swap_programs: [Pubkey; MAX_SWAP_PROGRAMS],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct VaultFees {
+    /// Fee charged on withdrawals during the lockup, zero to refuse them instead.
+    pub early_exit_fee_bps: u16,
+}
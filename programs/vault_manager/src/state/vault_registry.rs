@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+pub const VAULTS_PER_REGISTRY_PAGE: usize = 100;
+
+/// One page of the list of open vaults, in creation order. Page `n` is the PDA
+/// at `[VAULT_REGISTRY_SEED, n as u32 little-endian]` and holds vaults
+/// `n * VAULTS_PER_REGISTRY_PAGE` onwards, less those closed since;
+/// `ProtocolConfig::vault_count` tells how many pages exist.
+#[account]
+#[derive(InitSpace)]
+pub struct VaultRegistry {
+    pub page: u32,
+    #[max_len(VAULTS_PER_REGISTRY_PAGE)]
+    pub vaults: Vec<Pubkey>,
+    pub bump: u8,
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::dex::DexAdapter;
+use crate::{Vault, VaultError};
+
+/// Rejects the operation when the vault has a TWAP window configured and the
+/// spot tick is further than `max_twap_deviation_ticks` from the pool's TWAP.
+///
+/// The TWAP comes from the pool's own tick observations. Only Raydium CLMM
+/// pools keep them: Whirlpool oracle accounts hold adaptive fee state, and the
+/// DLMM oracle tracks bins rather than ticks. Vaults on those backends cannot
+/// configure a window.
+pub fn guard_twap<'info, D: DexAdapter<'info>>(
+    vault: &Vault,
+    dex: &D,
+    swap: &D::SwapAccounts,
+    tick_current: i32,
+) -> Result<()> {
+    if vault.config.twap_window_secs == 0 {
+        return Ok(());
+    }
+    let twap_tick = dex
+        .twap_tick(swap, vault.config.twap_window_secs)?
+        .ok_or(VaultError::TwapUnavailable)?;
+    require!(
+        tick_current.abs_diff(twap_tick) <= vault.config.max_twap_deviation_ticks,
+        VaultError::TwapDeviation
+    );
+    Ok(())
+}
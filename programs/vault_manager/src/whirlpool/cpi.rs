@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+
+use crate::bindings::invoke;
+
+pub mod accounts {
+    use super::*;
+
+    cpi_accounts!(Swap {
+        token_program: readonly,
+        token_authority: signer,
+        whirlpool: writable,
+        token_owner_account_a: writable,
+        token_vault_a: writable,
+        token_owner_account_b: writable,
+        token_vault_b: writable,
+        tick_array_0: writable,
+        tick_array_1: writable,
+        tick_array_2: writable,
+        oracle: writable,
+    });
+
+    cpi_accounts!(OpenPosition {
+        funder: writable_signer,
+        owner: readonly,
+        position: writable,
+        position_mint: writable_signer,
+        position_token_account: writable,
+        whirlpool: readonly,
+        token_program: readonly,
+        system_program: readonly,
+        rent: readonly,
+        associated_token_program: readonly,
+    });
+
+    cpi_accounts!(ClosePosition {
+        position_authority: signer,
+        receiver: writable,
+        position: writable,
+        position_mint: writable,
+        position_token_account: writable,
+        token_program: readonly,
+    });
+
+    cpi_accounts!(ModifyLiquidity {
+        whirlpool: writable,
+        token_program: readonly,
+        position_authority: signer,
+        position: writable,
+        position_token_account: readonly,
+        token_owner_account_a: writable,
+        token_owner_account_b: writable,
+        token_vault_a: writable,
+        token_vault_b: writable,
+        tick_array_lower: writable,
+        tick_array_upper: writable,
+    });
+
+    cpi_accounts!(UpdateFeesAndRewards {
+        whirlpool: writable,
+        position: writable,
+        tick_array_lower: readonly,
+        tick_array_upper: readonly,
+    });
+
+    cpi_accounts!(CollectFees {
+        whirlpool: readonly,
+        position_authority: signer,
+        position: writable,
+        position_token_account: readonly,
+        token_owner_account_a: writable,
+        token_vault_a: writable,
+        token_owner_account_b: writable,
+        token_vault_b: writable,
+        token_program: readonly,
+    });
+
+    cpi_accounts!(CollectReward {
+        whirlpool: readonly,
+        position_authority: signer,
+        position: writable,
+        position_token_account: readonly,
+        reward_owner_account: writable,
+        reward_vault: writable,
+        token_program: readonly,
+    });
+}
+
+pub fn swap<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::Swap<'info>>,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit: u128,
+    amount_specified_is_input: bool,
+    a_to_b: bool,
+) -> Result<()> {
+    let args = (
+        amount,
+        other_amount_threshold,
+        sqrt_price_limit,
+        amount_specified_is_input,
+        a_to_b,
+    );
+    invoke(ctx, [248, 198, 158, 145, 225, 117, 135, 200], args)
+}
+
+#[derive(AnchorSerialize)]
+pub struct OpenPositionBumps {
+    pub position_bump: u8,
+}
+
+pub fn open_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::OpenPosition<'info>>,
+    bumps: OpenPositionBumps,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> Result<()> {
+    let args = (bumps, tick_lower_index, tick_upper_index);
+    invoke(ctx, [135, 128, 47, 77, 15, 152, 240, 49], args)
+}
+
+/// Burns the position NFT, closing its token account, and closes the empty position.
+pub fn close_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ClosePosition<'info>>,
+) -> Result<()> {
+    invoke(ctx, [123, 134, 81, 0, 49, 68, 98, 98], ())
+}
+
+pub fn increase_liquidity<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ModifyLiquidity<'info>>,
+    liquidity_amount: u128,
+    token_max_a: u64,
+    token_max_b: u64,
+) -> Result<()> {
+    let args = (liquidity_amount, token_max_a, token_max_b);
+    invoke(ctx, [46, 156, 243, 118, 13, 205, 251, 178], args)
+}
+
+pub fn decrease_liquidity<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::ModifyLiquidity<'info>>,
+    liquidity_amount: u128,
+    token_min_a: u64,
+    token_min_b: u64,
+) -> Result<()> {
+    let args = (liquidity_amount, token_min_a, token_min_b);
+    invoke(ctx, [160, 38, 208, 111, 104, 91, 44, 1], args)
+}
+
+pub fn update_fees_and_rewards<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::UpdateFeesAndRewards<'info>>,
+) -> Result<()> {
+    invoke(ctx, [154, 230, 250, 13, 236, 209, 75, 223], ())
+}
+
+pub fn collect_fees<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::CollectFees<'info>>,
+) -> Result<()> {
+    invoke(ctx, [164, 152, 207, 99, 30, 186, 19, 182], ())
+}
+
+pub fn collect_reward<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, accounts::CollectReward<'info>>,
+    reward_index: u8,
+) -> Result<()> {
+    invoke(ctx, [70, 5, 132, 87, 86, 235, 177, 34], reward_index)
+}
@@ -0,0 +1,21 @@
+//! Minimal bindings for the Orca Whirlpool program.
+//!
+//! Only the account layouts and instructions used by the vault are mirrored
+//! here, so the program does not depend on a specific whirlpools crate version.
+
+use anchor_lang::prelude::*;
+
+pub mod cpi;
+pub mod state;
+
+declare_id!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+/// Marker type so the Whirlpool program can be used as `Program<'info, Whirlpool>`.
+#[derive(Clone)]
+pub struct Whirlpool;
+
+impl anchor_lang::Id for Whirlpool {
+    fn id() -> Pubkey {
+        ID
+    }
+}
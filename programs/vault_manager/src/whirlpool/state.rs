@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+
+use crate::VaultError;
+
+pub const NUM_REWARDS: usize = 3;
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct WhirlpoolRewardInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub emissions_per_second_x64: u128,
+    pub growth_global_x64: u128,
+}
+
+impl WhirlpoolRewardInfo {
+    pub fn initialized(&self) -> bool {
+        self.mint != Pubkey::default()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Whirlpool {
+    pub whirlpools_config: Pubkey,
+    pub whirlpool_bump: [u8; 1],
+    pub tick_spacing: u16,
+    pub fee_tier_index_seed: [u8; 2],
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub protocol_fee_owed_a: u64,
+    pub protocol_fee_owed_b: u64,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub fee_growth_global_a: u128,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub fee_growth_global_b: u128,
+    pub reward_last_updated_timestamp: u64,
+    pub reward_infos: [WhirlpoolRewardInfo; NUM_REWARDS],
+}
+
+external_account!(Whirlpool, [63, 149, 209, 12, 225, 128, 99, 9]);
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PositionRewardInfo {
+    pub growth_inside_checkpoint: u128,
+    pub amount_owed: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Position {
+    pub whirlpool: Pubkey,
+    pub position_mint: Pubkey,
+    pub liquidity: u128,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub fee_growth_checkpoint_a: u128,
+    pub fee_owed_a: u64,
+    pub fee_growth_checkpoint_b: u128,
+    pub fee_owed_b: u64,
+    pub reward_infos: [PositionRewardInfo; NUM_REWARDS],
+}
+
+external_account!(Position, [170, 188, 143, 228, 122, 64, 247, 208]);
+
+/// Growth counters of a single tick, as stored in a fixed-size tick array.
+#[derive(Clone, Copy, Default)]
+pub struct Tick {
+    pub initialized: bool,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_a: u128,
+    pub fee_growth_outside_b: u128,
+    pub reward_growths_outside: [u128; NUM_REWARDS],
+}
+
+impl Tick {
+    pub const LEN: usize = 1 + 16 + 16 + 16 + 16 + 16 * NUM_REWARDS;
+}
+
+/// Read-only view over a zero-copy `TickArray` account.
+pub struct TickArray;
+
+impl TickArray {
+    pub const DISCRIMINATOR: [u8; 8] = [69, 97, 189, 190, 110, 7, 66, 187];
+    const START_TICK_OFFSET: usize = 8;
+    const TICKS_OFFSET: usize = 12;
+    const WHIRLPOOL_OFFSET: usize = Self::TICKS_OFFSET + TICK_ARRAY_SIZE as usize * Tick::LEN;
+    pub const LEN: usize = Self::WHIRLPOOL_OFFSET + 32;
+
+    /// Loads the tick at `tick_index` from a tick array of `whirlpool`.
+    pub fn read_tick(
+        info: &AccountInfo,
+        whirlpool: &Pubkey,
+        tick_index: i32,
+        tick_spacing: u16,
+    ) -> Result<Tick> {
+        require_keys_eq!(*info.owner, super::ID, VaultError::InvalidTickArray);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= Self::LEN
+                && data[..8] == Self::DISCRIMINATOR
+                && data[Self::WHIRLPOOL_OFFSET..Self::LEN] == whirlpool.to_bytes(),
+            VaultError::InvalidTickArray
+        );
+
+        let start_bytes: [u8; 4] = data[Self::START_TICK_OFFSET..Self::TICKS_OFFSET]
+            .try_into()
+            .unwrap();
+        let start_tick_index = i32::from_le_bytes(start_bytes);
+        let spacing = i32::from(tick_spacing);
+        let offset = tick_index
+            .checked_sub(start_tick_index)
+            .ok_or(VaultError::InvalidTickArray)?;
+        require!(
+            spacing > 0
+                && offset >= 0
+                && offset % spacing == 0
+                && offset / spacing < TICK_ARRAY_SIZE,
+            VaultError::InvalidTickArray
+        );
+
+        let start = Self::TICKS_OFFSET + (offset / spacing) as usize * Tick::LEN;
+        let raw = &data[start..start + Tick::LEN];
+        let u128_at = |at: usize| u128::from_le_bytes(raw[at..at + 16].try_into().unwrap());
+
+        let mut reward_growths_outside = [0u128; NUM_REWARDS];
+        for (i, growth) in reward_growths_outside.iter_mut().enumerate() {
+            *growth = u128_at(65 + 16 * i);
+        }
+        Ok(Tick {
+            initialized: raw[0] != 0,
+            liquidity_net: u128_at(1) as i128,
+            liquidity_gross: u128_at(17),
+            fee_growth_outside_a: u128_at(33),
+            fee_growth_outside_b: u128_at(49),
+            reward_growths_outside,
+        })
+    }
+}
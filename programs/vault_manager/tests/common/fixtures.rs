@@ -0,0 +1,114 @@
+//! Account fixtures, dumped from mainnet by `scripts/dump-fixtures.ts` or
+//! generated from the mock programs by `tests/fixtures.rs`.
+//!
+//! Each pool has a directory under `tests/fixtures/` holding one `<pubkey>.json`
+//! per account, in the format `solana account --output json` prints.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anchor_lang::prelude::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::{Harness, TestAccount};
+
+#[derive(Serialize, Deserialize)]
+pub struct AccountFixture {
+    pub pubkey: String,
+    pub account: FixtureAccount,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixtureAccount {
+    pub lamports: u64,
+    /// Base64 data and the encoding name, always `"base64"`.
+    pub data: (String, String),
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub space: u64,
+}
+
+impl AccountFixture {
+    pub fn new(key: &Pubkey, account: &TestAccount) -> Self {
+        Self {
+            pubkey: key.to_string(),
+            account: FixtureAccount {
+                lamports: account.lamports,
+                data: (STANDARD.encode(&account.data), "base64".to_string()),
+                owner: account.owner.to_string(),
+                executable: account.executable,
+                rent_epoch: 0,
+                space: account.data.len() as u64,
+            },
+        }
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let json = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()));
+        serde_json::from_str(&json)
+            .unwrap_or_else(|err| panic!("invalid fixture {}: {err}", path.display()))
+    }
+
+    pub fn write(&self, dir: &Path) {
+        fs::create_dir_all(dir).unwrap();
+        let json = serde_json::to_string_pretty(self).unwrap();
+        fs::write(dir.join(format!("{}.json", self.pubkey)), json + "\n").unwrap();
+    }
+
+    pub fn key(&self) -> Pubkey {
+        Pubkey::from_str(&self.pubkey).expect("invalid fixture pubkey")
+    }
+
+    pub fn to_account(&self) -> TestAccount {
+        let (data, encoding) = &self.account.data;
+        assert_eq!(encoding, "base64", "unsupported fixture encoding");
+        let data = STANDARD.decode(data).expect("invalid fixture data");
+        assert_eq!(data.len() as u64, self.account.space, "fixture data length");
+        TestAccount {
+            lamports: self.account.lamports,
+            data,
+            owner: Pubkey::from_str(&self.account.owner).expect("invalid fixture owner"),
+            executable: self.account.executable,
+        }
+    }
+}
+
+/// Directories of the checked-in pool fixtures, by name.
+pub fn pool_fixtures() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Stores every account fixture in `dir` in `h`. Returns their keys, in file name order.
+pub fn load_fixtures(h: &mut Harness, dir: &Path) -> Vec<Pubkey> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("cannot read {}: {err}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let fixture = AccountFixture::read(path);
+            let key = fixture.key();
+            h.set_account(key, fixture.to_account());
+            key
+        })
+        .collect()
+}
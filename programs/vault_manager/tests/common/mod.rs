@@ -0,0 +1,7 @@
+//! Native test harness, see `native_svm`: instructions run on the programs'
+//! entry points, CPIs included, against an in-memory account store.
+#![allow(dead_code)]
+
+pub mod fixtures;
+
+pub use native_svm::*;
@@ -0,0 +1,963 @@
+//! DLMM backend against the mock DLMM program.
+//!
+//! The vault is created by `initialize_vault`. Adapter tests seed liquidity,
+//! trades and fee accrual through the mock's pool state transitions, which the
+//! mock's instructions run on chain, and read the vault side back through
+//! `MeteoraDlmmAdapter`, the code behind deposit, withdraw, rebalance and
+//! `get_nav`. Instruction tests run the vault's instructions end to end, CPIs
+//! into the mock included.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::Harness;
+use mock_dlmm::pool;
+use mock_dlmm::state::{BinArray, Bins, LbPair, PositionV2};
+use vault_manager::dex::meteora::MeteoraPosition;
+use vault_manager::dex::{BinStrategy, Dex, DexAccounts, DexAdapter, MeteoraDlmmAdapter};
+use vault_manager::meteora::cpi::{LiquidityParameterByStrategy, StrategyParameters};
+use vault_manager::nav::{self, NavInputs, PositionValue, VaultNav};
+use vault_manager::protocol_fee::CollectedFees;
+use vault_manager::{math, ProtocolConfig, Vault, VaultError};
+
+const BIN_STEP: u16 = 10;
+/// Token balance depositors start with, of each token.
+const USER_BALANCE: u64 = 1_000_000_000;
+/// Protocol share of collected fees.
+const FEE_SHARE_BPS: u16 = 1_000;
+
+/// A depositor's wallet and token accounts.
+struct User {
+    wallet: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    shares: Pubkey,
+}
+
+struct Env {
+    h: Harness,
+    authority: Pubkey,
+    mint_x: Pubkey,
+    mint_y: Pubkey,
+    lb_pair: Pubkey,
+    reserve_x: Pubkey,
+    reserve_y: Pubkey,
+    event_authority: Pubkey,
+    vault: Pubkey,
+    vault_token_a: Pubkey,
+    vault_token_b: Pubkey,
+    share_mint: Pubkey,
+    lp_position: Pubkey,
+    protocol_config: Pubkey,
+    /// The protocol treasury's token accounts.
+    treasury_token_a: Pubkey,
+    treasury_token_b: Pubkey,
+}
+
+impl Env {
+    /// Pool at bin 0 with LP liquidity over bins [-30, 30], and a vault
+    /// created by `initialize_vault` with an empty position over bins [-5, 5].
+    fn new() -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_dlmm::ID, mock_dlmm::entry);
+
+        let authority = h.create_wallet();
+        let mint_x = h.create_mint(&authority, 6);
+        let mint_y = h.create_mint(&authority, 6);
+        let (lb_pair, _) = Pubkey::find_program_address(
+            &[mint_x.as_ref(), mint_y.as_ref(), &BIN_STEP.to_le_bytes()],
+            &mock_dlmm::ID,
+        );
+        let reserve_x = h.create_token_account(&mint_x, &lb_pair, 0);
+        let reserve_y = h.create_token_account(&mint_y, &lb_pair, 0);
+        let state = LbPair::new(
+            mint_x,
+            mint_y,
+            reserve_x,
+            reserve_y,
+            Pubkey::new_unique(),
+            BIN_STEP,
+            0,
+        );
+        h.set_anchor_account(lb_pair, &state);
+
+        let (event_authority, _) =
+            Pubkey::find_program_address(&[b"__event_authority"], &mock_dlmm::ID);
+        let (vault, _) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, lb_pair.as_ref()],
+            &vault_manager::ID,
+        );
+        let (share_mint, _) = Pubkey::find_program_address(
+            &[vault_manager::SHARE_MINT_SEED, vault.as_ref()],
+            &vault_manager::ID,
+        );
+        let vault_token_a = h.create_token_account(&mint_x, &vault, 0);
+        let vault_token_b = h.create_token_account(&mint_y, &vault, 0);
+
+        // `initialize_protocol_config` needs the program's upgrade authority
+        let (protocol_config, protocol_bump) = Pubkey::find_program_address(
+            &[vault_manager::PROTOCOL_CONFIG_SEED],
+            &vault_manager::ID,
+        );
+        let treasury = h.create_wallet();
+        h.set_anchor_account(
+            protocol_config,
+            &ProtocolConfig {
+                admin: authority,
+                fee_share_bps: FEE_SHARE_BPS,
+                treasury,
+                dex_programs: [
+                    mock_dlmm::ID,
+                    Pubkey::default(),
+                    Pubkey::default(),
+                    Pubkey::default(),
+                ],
+                vault_count: 0,
+                bump: protocol_bump,
+            },
+        );
+        let treasury_token_a = h.create_token_account(&mint_x, &treasury, 0);
+        let treasury_token_b = h.create_token_account(&mint_y, &treasury, 0);
+
+        let mut env = Self {
+            h,
+            authority,
+            mint_x,
+            mint_y,
+            lb_pair,
+            reserve_x,
+            reserve_y,
+            event_authority,
+            vault,
+            vault_token_a,
+            vault_token_b,
+            share_mint,
+            lp_position: Pubkey::default(),
+            protocol_config,
+            treasury_token_a,
+            treasury_token_b,
+        };
+        for bin_id in [-70, 0, 70] {
+            let array = BinArray::new(lb_pair, BinArray::index_of(bin_id), BIN_STEP).unwrap();
+            env.h.set_anchor_account(env.bin_array(bin_id), &array);
+        }
+
+        let lp = Pubkey::new_unique();
+        env.lp_position = env.open_position(lp, -30, 61);
+        env.add_liquidity(
+            env.lp_position,
+            1_000_000_000,
+            1_000_000_000,
+            BinStrategy::Spot,
+        );
+        // The reserves hold what the LP paid in
+        let (lp_x, lp_y) = env.removal_amounts(&env.lp_position);
+        env.h.set_token_account(reserve_x, &mint_x, &lb_pair, lp_x);
+        env.h.set_token_account(reserve_y, &mint_y, &lb_pair, lp_y);
+
+        let payer = env.h.create_wallet();
+        let position = Pubkey::new_unique();
+        let mut accounts = vault_manager::accounts::InitializeVault {
+            vault,
+            protocol_config,
+            vault_registry: Pubkey::find_program_address(
+                &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+                &vault_manager::ID,
+            )
+            .0,
+            pool: lb_pair,
+            token_a_mint: mint_x,
+            token_b_mint: mint_y,
+            vault_token_a,
+            vault_token_b,
+            share_mint,
+            position,
+            position_mint: Pubkey::new_unique(),
+            position_token_account: Pubkey::new_unique(),
+            pool_token_vault_a: reserve_x,
+            pool_token_vault_b: reserve_y,
+            authority,
+            payer,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            dex_program: mock_dlmm::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None);
+        // DLMM positions are keypair accounts, created with their signature
+        signer(&mut accounts, &position);
+        accounts.push(AccountMeta::new_readonly(event_authority, false));
+        let initialize = Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::InitializeVault {
+                dex: Dex::MeteoraDlmm,
+                tick_lower: -5,
+                tick_upper: 5,
+            }
+            .data(),
+        };
+        env.h.process(&initialize).unwrap();
+        env
+    }
+
+    fn bin_array(&self, bin_id: i32) -> Pubkey {
+        let index = BinArray::index_of(bin_id);
+        Pubkey::find_program_address(
+            &[b"bin_array", self.lb_pair.as_ref(), &index.to_le_bytes()],
+            &mock_dlmm::ID,
+        )
+        .0
+    }
+
+    fn pair(&self) -> LbPair {
+        self.h.anchor_account(&self.lb_pair)
+    }
+
+    fn vault_state(&self) -> Vault {
+        self.h.anchor_account(&self.vault)
+    }
+
+    fn position(&self, key: &Pubkey) -> PositionV2 {
+        self.h.anchor_account(key)
+    }
+
+    fn bins(&self) -> Bins {
+        let keys = [self.bin_array(-1), self.bin_array(0)];
+        Bins::new(
+            keys.iter()
+                .map(|key| (*key, self.h.anchor_account(key)))
+                .collect(),
+        )
+    }
+
+    fn save_bins(&mut self, bins: Bins) {
+        for (key, array) in bins.into_arrays() {
+            self.h.set_anchor_account(key, &array);
+        }
+    }
+
+    fn open_position(&mut self, owner: Pubkey, lower_bin_id: i32, width: i32) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let position = PositionV2::new(self.lb_pair, owner, lower_bin_id, width).unwrap();
+        self.h.set_anchor_account(key, &position);
+        key
+    }
+
+    /// Adds liquidity over the whole position with the parameters the vault
+    /// backend sends for `strategy`.
+    fn add_liquidity(&mut self, key: Pubkey, amount_x: u64, amount_y: u64, strategy: BinStrategy) {
+        let pair = self.pair();
+        let mut position = self.position(&key);
+        let parameter = LiquidityParameterByStrategy {
+            amount_x,
+            amount_y,
+            active_id: pair.active_id,
+            max_active_bin_slippage: 0,
+            strategy_parameters: StrategyParameters {
+                min_bin_id: position.lower_bin_id,
+                max_bin_id: position.upper_bin_id,
+                strategy_type: strategy.into(),
+                parameteres: [0; 64],
+            },
+        };
+        let mut bins = self.bins();
+        pool::add_liquidity(&pair, &mut position, &mut bins, &reencode(&parameter)).unwrap();
+        self.h.set_anchor_account(key, &position);
+        self.save_bins(bins);
+    }
+
+    /// Trades `amount_in` through the pool, moving the active bin.
+    fn trade(&mut self, amount_in: u64, swap_for_y: bool) -> u64 {
+        let mut pair = self.pair();
+        let mut bins = self.bins();
+        let amount_out = pool::swap(&mut pair, &mut bins, amount_in, swap_for_y).unwrap();
+        self.h.set_anchor_account(self.lb_pair, &pair);
+        self.save_bins(bins);
+        amount_out
+    }
+
+    /// Token amounts removing the whole position would pay out, with the pool left untouched.
+    fn removal_amounts(&self, key: &Pubkey) -> (u64, u64) {
+        let mut position = self.position(key);
+        let (lower, upper) = (position.lower_bin_id, position.upper_bin_id);
+        pool::remove_liquidity(&mut position, &mut self.bins(), lower, upper, 10_000).unwrap()
+    }
+
+    /// Runs `f` on the vault's DLMM backend with `position` loaded from the
+    /// given bin arrays.
+    fn with_adapter<R>(
+        &self,
+        position: Pubkey,
+        bin_arrays: [Pubkey; 2],
+        f: impl for<'a> FnOnce(&MeteoraDlmmAdapter<'a>, &MeteoraPosition<'a>) -> Result<R>,
+    ) -> Result<R> {
+        let vault = self.vault_state();
+        let metas = [
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new(self.vault_token_a, false),
+            AccountMeta::new(self.vault_token_b, false),
+            AccountMeta::new(self.lb_pair, false),
+            AccountMeta::new(self.reserve_x, false),
+            AccountMeta::new(self.reserve_y, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(mock_dlmm::ID, false),
+            AccountMeta::new(position, false),
+            AccountMeta::new_readonly(self.mint_x, false),
+            AccountMeta::new_readonly(self.mint_y, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(bin_arrays[0], false),
+            AccountMeta::new(bin_arrays[1], false),
+        ];
+        self.h.with_accounts(&metas, |infos| {
+            let dex = MeteoraDlmmAdapter::load(DexAccounts {
+                vault: infos[0].clone(),
+                vault_bump: vault.bump,
+                token_a_mint: vault.token_a_mint,
+                token_b_mint: vault.token_b_mint,
+                vault_token_a: infos[1].clone(),
+                vault_token_b: infos[2].clone(),
+                pool: infos[3].clone(),
+                pool_token_vault_a: infos[4].clone(),
+                pool_token_vault_b: infos[5].clone(),
+                token_program: infos[6].clone(),
+                dex_program: infos[7].clone(),
+                bin_strategy: vault.strategy.bin_strategy,
+            })?;
+            let position = dex.load_position(infos[8].clone(), &mut infos[9..].iter())?;
+            f(&dex, &position)
+        })
+    }
+
+    fn position_bin_arrays(&self, key: &Pubkey) -> [Pubkey; 2] {
+        let position = self.position(key);
+        [
+            self.bin_array(position.lower_bin_id),
+            self.bin_array(position.upper_bin_id),
+        ]
+    }
+
+    fn position_value(&self, key: Pubkey) -> PositionValue {
+        self.with_adapter(key, self.position_bin_arrays(&key), |dex, position| {
+            dex.position_value(position)
+        })
+        .unwrap()
+    }
+
+    /// NAV as `get_nav` computes it.
+    fn nav(&self, share_supply: u64) -> VaultNav {
+        let vault = self.vault_state();
+        let idle_a = self.h.token_balance(&self.vault_token_a);
+        let idle_b = self.h.token_balance(&self.vault_token_b);
+        let arrays = self.position_bin_arrays(&vault.position);
+        self.with_adapter(vault.position, arrays, |dex, position| {
+            nav::compute_nav(&NavInputs {
+                sqrt_price: dex.price()?.sqrt_price,
+                position: &dex.position_value(position)?,
+                idle_a,
+                idle_b,
+                share_supply,
+            })
+        })
+        .unwrap()
+    }
+
+    fn user_position_key(&self, user: &User) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::USER_POSITION_SEED,
+                self.vault.as_ref(),
+                user.wallet.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    /// Wallet with `USER_BALANCE` of each token and an empty share account.
+    fn create_user(&mut self) -> User {
+        let wallet = self.h.create_wallet();
+        User {
+            wallet,
+            token_a: self
+                .h
+                .create_token_account(&self.mint_x, &wallet, USER_BALANCE),
+            token_b: self
+                .h
+                .create_token_account(&self.mint_y, &wallet, USER_BALANCE),
+            shares: self.h.create_token_account(&self.share_mint, &wallet, 0),
+        }
+    }
+
+    /// Account set of a position over bins `[lower_bin_id, upper_bin_id]`.
+    fn range_metas(&self, lower_bin_id: i32, upper_bin_id: i32) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.mint_x, false),
+            AccountMeta::new_readonly(self.mint_y, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.bin_array(lower_bin_id), false),
+            AccountMeta::new(self.bin_array(upper_bin_id), false),
+        ]
+    }
+
+    /// The vault position's account set.
+    fn position_metas(&self) -> Vec<AccountMeta> {
+        let position = self.position(&self.vault_state().position);
+        self.range_metas(position.lower_bin_id, position.upper_bin_id)
+    }
+
+    /// The swap account set, with every bin array of the pair, so it serves
+    /// swaps in both directions.
+    fn swap_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.mint_x, false),
+            AccountMeta::new_readonly(self.mint_y, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new(self.pair().oracle, false),
+        ];
+        metas.extend([-70, 0, 70].map(|bin_id| AccountMeta::new(self.bin_array(bin_id), false)));
+        metas
+    }
+
+    /// Deposit of `amount` of token B, zapped into the position's ratio.
+    fn deposit_ix(&self, user: &User, amount: u64) -> Instruction {
+        let accounts = vault_manager::accounts::DepositAndAddLiquidity {
+            vault: self.vault,
+            user: user.wallet,
+            user_token_account: user.token_b,
+            user_share_account: user.shares,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.lb_pair,
+            position: self.vault_state().position,
+            pool_token_vault_a: self.reserve_x,
+            pool_token_vault_b: self.reserve_y,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: mock_dlmm::ID,
+            allowlist_entry: None,
+            user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(),
+                self.swap_metas(),
+            ]
+            .concat(),
+            data: vault_manager::instruction::DepositAndAddLiquidity {
+                amount,
+                min_swap_out: 0,
+                min_shares_out: 0,
+            }
+            .data(),
+        }
+    }
+
+    fn withdraw_ix(&self, user: &User, shares: u64) -> Instruction {
+        let accounts = vault_manager::accounts::Withdraw {
+            vault: self.vault,
+            user: user.wallet,
+            user_share_account: user.shares,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.lb_pair,
+            position: self.vault_state().position,
+            pool_token_vault_a: self.reserve_x,
+            pool_token_vault_b: self.reserve_y,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: mock_dlmm::ID,
+            user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::Withdraw {
+                shares,
+                min_amount_a: 0,
+                min_amount_b: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// `collect` by the authority, with the fees it reports.
+    fn collect(&mut self) -> std::result::Result<CollectedFees, ProgramError> {
+        let accounts = vault_manager::accounts::Collect {
+            vault: self.vault,
+            authority: self.authority,
+            pool: self.lb_pair,
+            position: self.vault_state().position,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.reserve_x,
+            pool_token_vault_b: self.reserve_y,
+            token_program: spl_token::ID,
+            dex_program: mock_dlmm::ID,
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        let collect = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::Collect {}.data(),
+        };
+        self.h.process(&collect)?;
+        let (program_id, data) = self.h.return_data().unwrap();
+        assert_eq!(*program_id, vault_manager::ID);
+        Ok(CollectedFees::try_from_slice(data).unwrap())
+    }
+
+    /// Rebalance into a new position over bins `[lower_bin_id, upper_bin_id]`,
+    /// paid for by `payer`. Returns the instruction and the new position.
+    fn rebalance_ix(
+        &self,
+        payer: Pubkey,
+        (lower_bin_id, upper_bin_id): (i32, i32),
+    ) -> (Instruction, Pubkey) {
+        let new_position = Pubkey::new_unique();
+        let mut accounts = vault_manager::accounts::Rebalance {
+            vault: self.vault,
+            authority: self.authority,
+            payer,
+            pool: self.lb_pair,
+            position: self.vault_state().position,
+            share_mint: self.share_mint,
+            new_position,
+            // DLMM positions have no NFT
+            new_position_mint: Pubkey::new_unique(),
+            new_position_token_account: Pubkey::new_unique(),
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.reserve_x,
+            pool_token_vault_b: self.reserve_y,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::ID,
+            dex_program: mock_dlmm::ID,
+            protocol_config: self.protocol_config,
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &new_position);
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts,
+                self.position_metas(),
+                self.swap_metas(),
+                vec![AccountMeta::new_readonly(self.event_authority, false)],
+                self.range_metas(lower_bin_id, upper_bin_id),
+            ]
+            .concat(),
+            data: vault_manager::instruction::Rebalance {
+                tick_lower: lower_bin_id,
+                tick_upper: upper_bin_id,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                min_swap_out: 0,
+            }
+            .data(),
+        };
+        (instruction, new_position)
+    }
+
+    /// `close_vault`, sweeping leftovers into the authority's `tokens`.
+    fn close_vault_ix(
+        &self,
+        (authority_token_a, authority_token_b): (Pubkey, Pubkey),
+    ) -> Instruction {
+        let accounts = vault_manager::accounts::CloseVault {
+            vault: self.vault,
+            authority: self.authority,
+            vault_registry: Pubkey::find_program_address(
+                &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+                &vault_manager::ID,
+            )
+            .0,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            authority_token_a,
+            authority_token_b,
+            share_mint: self.share_mint,
+            pool: self.lb_pair,
+            position: self.vault_state().position,
+            pool_token_vault_a: self.reserve_x,
+            pool_token_vault_b: self.reserve_y,
+            token_program: spl_token::ID,
+            dex_program: mock_dlmm::ID,
+            system_program: system_program::ID,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::CloseVault {}.data(),
+        }
+    }
+}
+
+/// Decodes a value of the vault's DLMM bindings as the mock's matching type.
+fn reencode<T: AnchorSerialize, U: AnchorDeserialize>(value: &T) -> U {
+    U::try_from_slice(&borsh::to_vec(value).unwrap()).unwrap()
+}
+
+fn signer(metas: &mut [AccountMeta], key: &Pubkey) {
+    for meta in metas.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = true;
+    }
+}
+
+#[test]
+fn bin_prices_match_the_vault_math() {
+    let env = Env::new();
+    for index in [-1, 0] {
+        let array: BinArray = env.h.anchor_account(&env.bin_array(index * 70));
+        for (i, bin) in array.bins.iter().enumerate() {
+            let bin_id = index * 70 + i as i32;
+            assert_eq!(
+                bin.price,
+                math::price_x64_from_bin_id(bin_id, BIN_STEP).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn position_value_matches_what_removal_pays_out() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    env.add_liquidity(position, 500_000, 500_000, BinStrategy::Spot);
+    assert_eq!(
+        env.position_value(position).amount_a,
+        env.removal_amounts(&position).0
+    );
+
+    // Push the price up through a few of the vault's bins
+    env.trade(100_000_000, false);
+    let active_id = env.pair().active_id;
+    assert!((2..5).contains(&active_id), "active bin is {active_id}");
+
+    let value = env.position_value(position);
+    assert_eq!(
+        (value.amount_a, value.amount_b),
+        env.removal_amounts(&position)
+    );
+    // Token X in the crossed bins was sold for token Y
+    assert!(value.amount_b > 500_000);
+
+    let arrays = env.position_bin_arrays(&position);
+    let (price, range) = env
+        .with_adapter(position, arrays, |dex, position| {
+            Ok((dex.price()?, dex.position_range(position)?))
+        })
+        .unwrap();
+    assert_eq!(price.tick_current, active_id);
+    assert_eq!(
+        price.sqrt_price,
+        math::sqrt_price_from_bin_id(active_id, BIN_STEP).unwrap()
+    );
+    let state = env.position(&position);
+    assert_eq!(range.liquidity, state.liquidity_shares.iter().sum::<u128>());
+    assert_eq!(
+        range.sqrt_lower,
+        math::sqrt_price_from_bin_id(-5, BIN_STEP).unwrap()
+    );
+    assert_eq!(
+        range.sqrt_upper,
+        math::sqrt_price_from_bin_id(6, BIN_STEP).unwrap()
+    );
+}
+
+#[test]
+fn nav_counts_position_and_idle_balances() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    // A first deposit of 1_000_000 token B: half swapped, then both sides added
+    env.add_liquidity(position, 500_000, 500_000, BinStrategy::Spot);
+    let (leftover_a, leftover_b) = (2, 2);
+    env.h
+        .set_token_account(env.vault_token_a, &env.mint_x, &env.vault, leftover_a);
+    env.h
+        .set_token_account(env.vault_token_b, &env.mint_y, &env.vault, leftover_b);
+
+    let nav = env.nav(1_000_000);
+    assert_eq!((nav.idle_a, nav.idle_b), (leftover_a, leftover_b));
+    assert_eq!(nav.total_a, nav.position_a + leftover_a);
+    assert_eq!(nav.total_b, nav.position_b + leftover_b);
+    // Price is 1 at bin 0, so value only loses the per-bin rounding
+    assert!(nav.total_value_b <= 1_000_000);
+    assert!(nav.total_value_b >= 999_980, "{}", nav.total_value_b);
+
+    let shares = nav.shares_for_value(100_000).unwrap();
+    assert!((100_000..=100_003).contains(&shares), "{shares}");
+}
+
+#[test]
+fn bin_strategies_shape_the_distribution() {
+    let shares_with = |strategy: BinStrategy| {
+        let mut env = Env::new();
+        let position = env.vault_state().position;
+        env.add_liquidity(position, 600_000, 600_000, strategy);
+        let state = env.position(&position);
+        move |bin_id: i32| state.liquidity_shares[(bin_id - state.lower_bin_id) as usize]
+    };
+
+    let spot = shares_with(BinStrategy::Spot);
+    for bin_id in [-5, -1, 2, 5] {
+        // Equal token amounts per bin, so shares only differ by the bin price
+        let (share, reference) = (spot(bin_id), spot(1));
+        assert!(share.abs_diff(reference) <= reference / 100, "bin {bin_id}");
+    }
+
+    let curve = shares_with(BinStrategy::Curve);
+    assert!(curve(1) > curve(3) && curve(3) > curve(5));
+    assert!(curve(-1) > curve(-3) && curve(-3) > curve(-5));
+
+    let bid_ask = shares_with(BinStrategy::BidAsk);
+    assert!(bid_ask(5) > bid_ask(3) && bid_ask(3) > bid_ask(1));
+    assert!(bid_ask(-5) > bid_ask(-3) && bid_ask(-3) > bid_ask(-1));
+}
+
+#[test]
+fn fees_and_rewards_are_valued_as_claimed() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    env.add_liquidity(position, 500_000, 500_000, BinStrategy::Spot);
+
+    let reward_mint = Pubkey::new_unique();
+    let mut pair = env.pair();
+    pair.reward_infos[1].mint = reward_mint;
+    env.h.set_anchor_account(env.lb_pair, &pair);
+    let mut bins = env.bins();
+    for bin_id in -5..=5 {
+        let bin = bins.bin(bin_id).unwrap();
+        bin.fee_amount_x_per_token_stored = 1 << 60;
+        bin.fee_amount_y_per_token_stored = 1 << 59;
+        bin.reward_per_token_stored[1] = 1 << 58;
+    }
+    env.save_bins(bins);
+
+    let value = env.position_value(position);
+    let mut state = env.position(&position);
+    let claimed = pool::claim_fee(&mut state, &mut env.bins()).unwrap();
+    assert!(claimed.0 > 0 && claimed.1 > 0);
+    assert_eq!((value.fees_owed_a, value.fees_owed_b), claimed);
+
+    let reward: u64 = state
+        .reward_infos
+        .iter()
+        .map(|info| info.reward_pendings[1])
+        .sum();
+    assert!(reward > 0);
+    assert_eq!(value.rewards_owed[1], (reward_mint, reward));
+    assert_eq!(value.rewards_owed[0], (Pubkey::default(), 0));
+}
+
+#[test]
+fn rejects_positions_and_bin_arrays_it_does_not_own() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    let arrays = env.position_bin_arrays(&position);
+
+    let lp_position = env.lp_position;
+    let err = env
+        .with_adapter(lp_position, arrays, |_, _| Ok(()))
+        .unwrap_err();
+    assert_eq!(err, VaultError::InvalidPosition.into());
+
+    // A vault-owned position in another pair
+    let other_pair = Pubkey::new_unique();
+    let foreign = Pubkey::new_unique();
+    let state = PositionV2::new(other_pair, env.vault, -5, 11).unwrap();
+    env.h.set_anchor_account(foreign, &state);
+    let err = env
+        .with_adapter(foreign, arrays, |_, _| Ok(()))
+        .unwrap_err();
+    assert_eq!(err, VaultError::InvalidPosition.into());
+
+    // Bin arrays passed in the wrong order do not cover the bins read from them
+    let err = env
+        .with_adapter(position, [arrays[1], arrays[0]], |dex, position| {
+            dex.position_value(position)
+        })
+        .unwrap_err();
+    assert_eq!(err, VaultError::InvalidBinArray.into());
+}
+
+#[test]
+fn set_bin_strategy_is_authority_only() {
+    let mut env = Env::new();
+    let set_strategy = |authority: Pubkey| Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::SetBinStrategy {
+            vault: env.vault,
+            authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetBinStrategy {
+            strategy: BinStrategy::Curve,
+        }
+        .data(),
+    };
+
+    let intruder = set_strategy(Pubkey::new_unique());
+    let owner = set_strategy(env.authority);
+    assert_eq!(
+        env.h.process(&intruder),
+        Err(ProgramError::Custom(VaultError::Unauthorized.into()))
+    );
+    assert_eq!(env.vault_state().strategy.bin_strategy, BinStrategy::Spot);
+
+    env.h.process(&owner).unwrap();
+    assert_eq!(env.vault_state().strategy.bin_strategy, BinStrategy::Curve);
+}
+
+#[test]
+fn deposit_and_withdraw_round_trip_through_the_dlmm_pair() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    let amount = 1_000_000;
+    env.h.process(&env.deposit_ix(&user, amount)).unwrap();
+
+    let vault = env.vault_state();
+    assert!(vault.is_active);
+    let position = env.position(&vault.position);
+    assert!(position.liquidity_shares.iter().any(|share| *share > 0));
+    let shares = env.h.token_balance(&user.shares);
+    let nav = env.nav(shares);
+    assert!(nav.position_a > 0 && nav.position_b > 0);
+    // The zap swap has no fee, so only the per-bin rounding is lost
+    assert!(nav.total_value_b * 1_000 >= u128::from(amount) * 999);
+
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+    assert_eq!(env.h.mint_supply(&env.share_mint), 0);
+    let position = env.position(&vault.position);
+    assert!(position.liquidity_shares.iter().all(|share| *share == 0));
+    let returned = env.h.token_balance(&user.token_a) + env.h.token_balance(&user.token_b);
+    assert!(returned >= 2 * USER_BALANCE - amount / 100);
+}
+
+#[test]
+fn collect_claims_bin_fees_less_the_protocol_share() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h.process(&env.deposit_ix(&user, 1_000_000)).unwrap();
+    let position = env.vault_state().position;
+    let mut bins = env.bins();
+    for bin_id in -5..=5 {
+        let bin = bins.bin(bin_id).unwrap();
+        bin.fee_amount_x_per_token_stored = 1 << 60;
+        bin.fee_amount_y_per_token_stored = 1 << 59;
+    }
+    env.save_bins(bins);
+    let owed = env.position_value(position);
+    assert!(owed.fees_owed_a > 0 && owed.fees_owed_b > 0);
+
+    let idle = (
+        env.h.token_balance(&env.vault_token_a),
+        env.h.token_balance(&env.vault_token_b),
+    );
+    let collected = env.collect().unwrap();
+    assert_eq!(
+        (collected.fees_a, collected.fees_b),
+        (owed.fees_owed_a, owed.fees_owed_b)
+    );
+    let protocol_share = |fees: u64| fees * u64::from(FEE_SHARE_BPS) / 10_000;
+    assert_eq!(
+        env.h.token_balance(&env.treasury_token_a),
+        protocol_share(owed.fees_owed_a)
+    );
+    assert_eq!(
+        env.h.token_balance(&env.vault_token_b),
+        idle.1 + owed.fees_owed_b - protocol_share(owed.fees_owed_b)
+    );
+    let after = env.position_value(position);
+    assert_eq!((after.fees_owed_a, after.fees_owed_b), (0, 0));
+}
+
+#[test]
+fn rebalance_opens_the_new_position_and_closes_the_old_one() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h.process(&env.deposit_ix(&user, 1_000_000)).unwrap();
+    let old_position = env.vault_state().position;
+    let value_before = env.nav(env.h.mint_supply(&env.share_mint)).total_value_b;
+
+    let payer = env.h.create_wallet();
+    let payer_lamports = env.h.account(&payer).unwrap().lamports;
+    let (rebalance, new_position) = env.rebalance_ix(payer, (-10, 0));
+    env.h.process(&rebalance).unwrap();
+
+    assert!(env.h.account(&old_position).is_none());
+    let vault = env.vault_state();
+    assert_eq!(vault.position, new_position);
+    assert!(vault.is_active);
+    let state = env.position(&new_position);
+    assert_eq!(state.owner, env.vault);
+    assert_eq!((state.lower_bin_id, state.upper_bin_id), (-10, 0));
+    assert!(state.liquidity_shares.iter().any(|share| *share > 0));
+    // The payer funds the new position and gets the old one's rent back
+    let payer_lamports_after = env.h.account(&payer).unwrap().lamports;
+    assert_eq!(payer_lamports_after, payer_lamports);
+    let nav = env.nav(env.h.mint_supply(&env.share_mint));
+    assert!(nav.total_value_b * 1_000 >= value_before * 995);
+
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+    assert_eq!(env.h.mint_supply(&env.share_mint), 0);
+}
+
+#[test]
+fn close_vault_closes_the_position_and_sweeps_leftovers() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h.process(&env.deposit_ix(&user, 1_000_000)).unwrap();
+    let position = env.vault_state().position;
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+
+    let authority_tokens = (
+        env.h.create_token_account(&env.mint_x, &env.authority, 0),
+        env.h.create_token_account(&env.mint_y, &env.authority, 0),
+    );
+    let leftovers = (
+        env.h.token_balance(&env.vault_token_a),
+        env.h.token_balance(&env.vault_token_b),
+    );
+    let position_rent = env.h.account(&position).unwrap().lamports;
+    let authority_lamports = env.h.account(&env.authority).unwrap().lamports;
+    env.h
+        .process(&env.close_vault_ix(authority_tokens))
+        .unwrap();
+    assert!(env.h.account(&position).is_none());
+    assert!(env.h.account(&env.vault).is_none());
+    assert_eq!(
+        (
+            env.h.token_balance(&authority_tokens.0),
+            env.h.token_balance(&authority_tokens.1)
+        ),
+        leftovers
+    );
+    assert!(env.h.account(&env.authority).unwrap().lamports > authority_lamports + position_rent);
+}
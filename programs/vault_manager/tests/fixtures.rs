@@ -0,0 +1,1316 @@
+//! Vault backends against account fixtures.
+//!
+//! Every pool directory in `tests/fixtures/` goes through the checks of its
+//! DEX, picked by the program owning its pool account. The `generated-*`
+//! directories are WSOL/USDC pools built here with the mock programs, one per
+//! backend, and rewritten by `write_generated_fixtures`; pools dumped from
+//! mainnet by `scripts/dump-fixtures.ts` take the same checks.
+
+mod common;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::spl_token;
+use common::fixtures::{load_fixtures, pool_fixtures, AccountFixture};
+use common::{Harness, TestAccount};
+use mock_dlmm::state::{BinArray as MockBinArray, Bins, LbPair, PositionV2};
+use mock_raydium::state::{
+    AmmConfig, Observation, ObservationState as MockObservationState, PersonalPositionState,
+    PoolState, TickArrayState, TickArrays as RaydiumTickArrays,
+};
+use mock_whirlpool::pool;
+use mock_whirlpool::state::{
+    Oracle, Position, TickArray as MockTickArray, TickArrays, Whirlpool, WhirlpoolRewardInfo,
+};
+use vault_manager::dex::{
+    DexAccounts, DexAdapter, MeteoraDlmmAdapter, RaydiumClmmAdapter, WhirlpoolAdapter,
+};
+use vault_manager::math;
+use vault_manager::meteora::state::BinArray;
+use vault_manager::raydium::state::{self as raydium_binding, ObservationState};
+use vault_manager::whirlpool::state::{self as binding, TickArray, TICK_ARRAY_SIZE};
+
+/// Mainnet mints the generated pools trade.
+const WSOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+const USDC: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+const ORCA: Pubkey = pubkey!("orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE");
+
+/// Unix time the generated pools start at; each step of their history is a
+/// minute later.
+const GENERATED_AT: i64 = 1_760_000_000;
+const STEP_SECS: i64 = 60;
+
+/// Program owning a fixture's pool account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Backend {
+    Whirlpool,
+    Raydium,
+    Dlmm,
+}
+
+/// Runs the checks of the pool in `dir`. Returns its backend.
+fn check_pool_fixture(dir: &Path) -> Backend {
+    let mut h = Harness::new();
+    let keys = load_fixtures(&mut h, dir);
+    let accounts = FixtureAccounts {
+        dir: dir.to_path_buf(),
+        keys,
+    };
+    if !accounts
+        .of_type(
+            &h,
+            &vault_manager::whirlpool::ID,
+            binding::Whirlpool::DISCRIMINATOR,
+        )
+        .is_empty()
+    {
+        WhirlpoolFixture::load(h, &accounts).check();
+        Backend::Whirlpool
+    } else if !accounts
+        .of_type(&h, &vault_manager::raydium::ID, PoolState::DISCRIMINATOR)
+        .is_empty()
+    {
+        RaydiumFixture::load(h, &accounts).check();
+        Backend::Raydium
+    } else if !accounts
+        .of_type(&h, &vault_manager::meteora::ID, LbPair::DISCRIMINATOR)
+        .is_empty()
+    {
+        DlmmFixture::load(h, &accounts).check();
+        Backend::Dlmm
+    } else {
+        panic!("{}: no Whirlpool, Raydium or DLMM pool", dir.display())
+    }
+}
+
+/// Keys of the accounts loaded from one fixture directory.
+struct FixtureAccounts {
+    dir: PathBuf,
+    keys: Vec<Pubkey>,
+}
+
+impl FixtureAccounts {
+    fn of_type(&self, h: &Harness, owner: &Pubkey, discriminator: &[u8]) -> Vec<Pubkey> {
+        self.keys
+            .iter()
+            .copied()
+            .filter(|key| {
+                let account = h.account(key).unwrap();
+                account.owner == *owner && account.data.starts_with(discriminator)
+            })
+            .collect()
+    }
+
+    /// The single account of a type, which the directory must hold.
+    fn one_of_type(&self, h: &Harness, owner: &Pubkey, discriminator: &[u8], name: &str) -> Pubkey {
+        let keys = self.of_type(h, owner, discriminator);
+        assert_eq!(keys.len(), 1, "{}: one {name}", self.dir.display());
+        keys[0]
+    }
+
+    /// Checks the directory holds nothing besides `typed` accounts and those
+    /// of `others` it has.
+    fn check_no_others(&self, typed: usize, others: &[Pubkey]) {
+        let expected = typed + others.iter().filter(|key| self.keys.contains(key)).count();
+        assert_eq!(
+            self.keys.len(),
+            expected,
+            "{}: unexpected accounts",
+            self.dir.display()
+        );
+    }
+}
+
+/// Pool accounts every backend's `DexAccounts` take.
+struct PoolKeys {
+    pool: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    vault_a: Pubkey,
+    vault_b: Pubkey,
+    program: Pubkey,
+}
+
+/// Runs `f` with the `DexAccounts` of `pool` for `vault`, followed by the
+/// accounts of `extra`.
+fn with_dex_accounts<R>(
+    h: &Harness,
+    pool: &PoolKeys,
+    vault: Pubkey,
+    extra: &[AccountMeta],
+    f: impl for<'a> FnOnce(DexAccounts<'a>, &'a [AccountInfo<'a>]) -> Result<R>,
+) -> Result<R> {
+    let mut metas = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new(Pubkey::new_unique(), false),
+        AccountMeta::new(Pubkey::new_unique(), false),
+        AccountMeta::new(pool.pool, false),
+        AccountMeta::new(pool.vault_a, false),
+        AccountMeta::new(pool.vault_b, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(pool.program, false),
+    ];
+    metas.extend_from_slice(extra);
+    h.with_accounts(&metas, |infos| {
+        let accounts = DexAccounts {
+            vault: infos[0].clone(),
+            vault_bump: 0,
+            token_a_mint: pool.mint_a,
+            token_b_mint: pool.mint_b,
+            vault_token_a: infos[1].clone(),
+            vault_token_b: infos[2].clone(),
+            pool: infos[3].clone(),
+            pool_token_vault_a: infos[4].clone(),
+            pool_token_vault_b: infos[5].clone(),
+            token_program: infos[6].clone(),
+            dex_program: infos[7].clone(),
+            bin_strategy: Default::default(),
+        };
+        f(accounts, &infos[8..])
+    })
+}
+
+fn check_mints(h: &Harness, mints: [Pubkey; 2]) {
+    for mint in mints {
+        let account = h.account(&mint).expect("mint fixture");
+        spl_token::state::Mint::unpack(&account.data).unwrap();
+    }
+}
+
+/// Accounts of one Whirlpool, loaded from its fixture directory.
+struct WhirlpoolFixture {
+    h: Harness,
+    whirlpool: Pubkey,
+    tick_arrays: Vec<Pubkey>,
+    /// The pool's adaptive fee oracle, which older pools do not have.
+    oracle: Option<Pubkey>,
+    /// `(reward_vault, vault_reward_account)` of each initialized pool reward.
+    reward_accounts: Vec<(Pubkey, Pubkey)>,
+}
+
+impl WhirlpoolFixture {
+    fn load(h: Harness, accounts: &FixtureAccounts) -> Self {
+        let whirlpool = accounts.one_of_type(
+            &h,
+            &vault_manager::whirlpool::ID,
+            binding::Whirlpool::DISCRIMINATOR,
+            "Whirlpool",
+        );
+        let tick_arrays =
+            accounts.of_type(&h, &vault_manager::whirlpool::ID, &TickArray::DISCRIMINATOR);
+        let oracles = accounts.of_type(&h, &vault_manager::whirlpool::ID, Oracle::DISCRIMINATOR);
+        assert!(
+            oracles.len() <= 1,
+            "{}: one oracle at most",
+            accounts.dir.display()
+        );
+
+        // Besides those, the dump holds the pool's mints and token vaults
+        let pool: Whirlpool = h.anchor_account(&whirlpool);
+        accounts.check_no_others(
+            1 + tick_arrays.len() + oracles.len(),
+            &[
+                pool.token_mint_a,
+                pool.token_mint_b,
+                pool.token_vault_a,
+                pool.token_vault_b,
+            ],
+        );
+        Self {
+            whirlpool,
+            tick_arrays,
+            oracle: oracles.first().copied(),
+            reward_accounts: Vec::new(),
+            h,
+        }
+    }
+
+    fn pool(&self) -> Whirlpool {
+        self.h.anchor_account(&self.whirlpool)
+    }
+
+    /// Runs `f` on the vault's Whirlpool backend for this pool, as `vault`.
+    fn with_adapter<R>(
+        &self,
+        vault: Pubkey,
+        position: Option<(Pubkey, Pubkey)>,
+        f: impl for<'a> FnOnce(&WhirlpoolAdapter<'a>, &[AccountInfo<'a>]) -> Result<R>,
+    ) -> Result<R> {
+        let pool = self.pool();
+        let keys = PoolKeys {
+            pool: self.whirlpool,
+            mint_a: pool.token_mint_a,
+            mint_b: pool.token_mint_b,
+            vault_a: pool.token_vault_a,
+            vault_b: pool.token_vault_b,
+            program: mock_whirlpool::ID,
+        };
+        let mut metas = Vec::new();
+        if let Some((position, nft_account)) = position {
+            let state: Position = self.h.anchor_account(&position);
+            metas.extend([
+                AccountMeta::new(position, false),
+                AccountMeta::new_readonly(nft_account, false),
+                AccountMeta::new(self.tick_array(state.tick_lower_index), false),
+                AccountMeta::new(self.tick_array(state.tick_upper_index), false),
+            ]);
+            for (reward_vault, vault_reward_account) in &self.reward_accounts {
+                metas.push(AccountMeta::new(*reward_vault, false));
+                metas.push(AccountMeta::new(*vault_reward_account, false));
+            }
+        }
+        with_dex_accounts(&self.h, &keys, vault, &metas, |accounts, infos| {
+            f(&WhirlpoolAdapter::load(accounts)?, infos)
+        })
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        let start = MockTickArray::start_index_of(tick_index, self.pool().tick_spacing);
+        whirlpool_tick_array(&self.whirlpool, start)
+    }
+
+    fn ticks(&self) -> TickArrays {
+        TickArrays::new(
+            self.pool().tick_spacing,
+            self.tick_arrays
+                .iter()
+                .map(|key| (*key, self.h.anchor_account(key)))
+                .collect(),
+        )
+    }
+
+    fn check(mut self) {
+        let pool = self.pool();
+        let tick = pool.tick_current_index;
+        let price = self
+            .with_adapter(Pubkey::new_unique(), None, |dex, _| dex.price())
+            .unwrap();
+        assert_eq!(
+            (price.sqrt_price, price.tick_current),
+            (pool.sqrt_price, tick)
+        );
+        assert!(math::sqrt_price_from_tick_index(tick).unwrap() <= pool.sqrt_price);
+        assert!(pool.sqrt_price < math::sqrt_price_from_tick_index(tick + 1).unwrap());
+        check_mints(&self.h, [pool.token_mint_a, pool.token_mint_b]);
+
+        // The oracle swaps pass is the pool's, in the layout the program reads
+        if let Some(key) = self.oracle {
+            let (address, _) = Pubkey::find_program_address(
+                &[b"oracle", self.whirlpool.as_ref()],
+                &vault_manager::whirlpool::ID,
+            );
+            assert_eq!(key, address, "oracle is not the pool's");
+            let account = self.h.account(&key).unwrap();
+            assert_eq!(account.data.len(), 8 + Oracle::INIT_SPACE);
+            let oracle: Oracle = self.h.anchor_account(&key);
+            assert_eq!(oracle.whirlpool, self.whirlpool);
+        }
+
+        // The vault reads every tick as the layout stores it
+        let spacing = pool.tick_spacing;
+        for key in &self.tick_arrays {
+            let array: MockTickArray = self.h.anchor_account(key);
+            assert_eq!(array.whirlpool, self.whirlpool);
+            for (i, expected) in array.ticks.iter().enumerate() {
+                let tick_index = array.start_tick_index + i as i32 * i32::from(spacing);
+                let tick = self
+                    .h
+                    .with_accounts(&[AccountMeta::new_readonly(*key, false)], |infos| {
+                        TickArray::read_tick(&infos[0], &self.whirlpool, tick_index, spacing)
+                    })
+                    .unwrap();
+                assert_eq!(tick.initialized, expected.initialized);
+                assert_eq!(tick.liquidity_net, expected.liquidity_net);
+                assert_eq!(tick.liquidity_gross, expected.liquidity_gross);
+                assert_eq!(tick.fee_growth_outside_a, expected.fee_growth_outside_a);
+                assert_eq!(tick.fee_growth_outside_b, expected.fee_growth_outside_b);
+                assert_eq!(tick.reward_growths_outside, expected.reward_growths_outside);
+            }
+        }
+
+        // A new vault position over the tick array holding the current tick is
+        // valued at what removing it pays out
+        let vault = Pubkey::new_unique();
+        let lower = MockTickArray::start_index_of(tick, spacing);
+        let upper = lower + (TICK_ARRAY_SIZE - 1) * i32::from(spacing);
+        let now = pool.reward_last_updated_timestamp as i64;
+        let mint = Pubkey::new_unique();
+        let (key, _) =
+            Pubkey::find_program_address(&[b"position", mint.as_ref()], &mock_whirlpool::ID);
+        let mut whirlpool = self.pool();
+        let mut position = Position::new(self.whirlpool, &whirlpool, mint, lower, upper).unwrap();
+        let mut ticks = self.ticks();
+        pool::modify_liquidity(&mut whirlpool, &mut position, &mut ticks, 1 << 40, now).unwrap();
+        self.h.set_anchor_account(self.whirlpool, &whirlpool);
+        self.h.set_anchor_account(key, &position);
+        for (key, array) in ticks.into_arrays() {
+            self.h.set_anchor_account(key, &array);
+        }
+        let nft_account = self.h.create_token_account(&mint, &vault, 1);
+        for reward in whirlpool.reward_infos.iter().filter(|r| r.initialized()) {
+            let recipient = self.h.create_token_account(&reward.mint, &vault, 0);
+            self.reward_accounts.push((reward.vault, recipient));
+        }
+
+        let value = self
+            .with_adapter(vault, Some((key, nft_account)), |dex, infos| {
+                let position = dex.load_position(infos[0].clone(), &mut infos[1..].iter())?;
+                dex.position_value(&position)
+            })
+            .unwrap();
+        let removal = pool::modify_liquidity(
+            &mut whirlpool,
+            &mut position,
+            &mut self.ticks(),
+            -(1 << 40),
+            now,
+        )
+        .unwrap();
+        assert_eq!((value.amount_a, value.amount_b), removal);
+        assert_eq!((value.fees_owed_a, value.fees_owed_b), (0, 0));
+        assert!(value.rewards_owed.iter().all(|(_, amount)| *amount == 0));
+    }
+}
+
+/// Accounts of one Raydium CLMM pool, loaded from its fixture directory.
+struct RaydiumFixture {
+    h: Harness,
+    pool: Pubkey,
+    tick_arrays: Vec<Pubkey>,
+}
+
+impl RaydiumFixture {
+    fn load(h: Harness, accounts: &FixtureAccounts) -> Self {
+        let program = vault_manager::raydium::ID;
+        let pool = accounts.one_of_type(&h, &program, PoolState::DISCRIMINATOR, "pool state");
+        let tick_arrays = accounts.of_type(
+            &h,
+            &program,
+            &raydium_binding::TickArrayState::DISCRIMINATOR,
+        );
+
+        // Besides those, the dump holds the pool's config, observations, mints
+        // and token vaults
+        let state: PoolState = h.anchor_account(&pool);
+        accounts.check_no_others(
+            1 + tick_arrays.len(),
+            &[
+                state.amm_config,
+                state.observation_key,
+                state.token_mint_0,
+                state.token_mint_1,
+                state.token_vault_0,
+                state.token_vault_1,
+            ],
+        );
+        Self {
+            h,
+            pool,
+            tick_arrays,
+        }
+    }
+
+    fn pool(&self) -> PoolState {
+        self.h.anchor_account(&self.pool)
+    }
+
+    /// Runs `f` on the vault's Raydium backend for this pool, as `vault`.
+    fn with_adapter<R>(
+        &self,
+        vault: Pubkey,
+        position: Option<(Pubkey, Pubkey)>,
+        f: impl for<'a> FnOnce(&RaydiumClmmAdapter<'a>, &[AccountInfo<'a>]) -> Result<R>,
+    ) -> Result<R> {
+        let pool = self.pool();
+        let keys = PoolKeys {
+            pool: self.pool,
+            mint_a: pool.token_mint_0,
+            mint_b: pool.token_mint_1,
+            vault_a: pool.token_vault_0,
+            vault_b: pool.token_vault_1,
+            program: mock_raydium::ID,
+        };
+        let mut metas = Vec::new();
+        if let Some((position, nft_account)) = position {
+            let state: PersonalPositionState = self.h.anchor_account(&position);
+            metas.extend([
+                AccountMeta::new(position, false),
+                AccountMeta::new_readonly(nft_account, false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new(self.tick_array(state.tick_lower_index), false),
+                AccountMeta::new(self.tick_array(state.tick_upper_index), false),
+            ]);
+        }
+        with_dex_accounts(&self.h, &keys, vault, &metas, |accounts, infos| {
+            f(&RaydiumClmmAdapter::load(accounts)?, infos)
+        })
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        let start = TickArrayState::start_index_of(tick_index, self.pool().tick_spacing);
+        raydium_tick_array(&self.pool, start)
+    }
+
+    fn ticks(&self) -> RaydiumTickArrays {
+        RaydiumTickArrays::new(
+            self.pool().tick_spacing,
+            self.tick_arrays
+                .iter()
+                .map(|key| (*key, self.h.anchor_account(key)))
+                .collect(),
+        )
+    }
+
+    fn check(mut self) {
+        let pool = self.pool();
+        let tick = pool.tick_current;
+        let price = self
+            .with_adapter(Pubkey::new_unique(), None, |dex, _| dex.price())
+            .unwrap();
+        assert_eq!(
+            (price.sqrt_price, price.tick_current),
+            (pool.sqrt_price_x64, tick)
+        );
+        assert!(math::sqrt_price_from_tick_index(tick).unwrap() <= pool.sqrt_price_x64);
+        assert!(pool.sqrt_price_x64 < math::sqrt_price_from_tick_index(tick + 1).unwrap());
+        check_mints(&self.h, [pool.token_mint_0, pool.token_mint_1]);
+
+        // The config and observations swaps pass are the pool's
+        let config: AmmConfig = self.h.anchor_account(&pool.amm_config);
+        assert_eq!(config.tick_spacing, pool.tick_spacing);
+        let (observation, _) = Pubkey::find_program_address(
+            &[b"observation", self.pool.as_ref()],
+            &vault_manager::raydium::ID,
+        );
+        assert_eq!(
+            pool.observation_key, observation,
+            "observations are not the pool's"
+        );
+        self.h
+            .with_accounts(&[AccountMeta::new_readonly(observation, false)], |infos| {
+                ObservationState::load(&infos[0], &self.pool)
+            })
+            .unwrap();
+
+        // The vault reads every tick as the layout stores it
+        let spacing = pool.tick_spacing;
+        for key in &self.tick_arrays {
+            let array: TickArrayState = self.h.anchor_account(key);
+            assert_eq!(array.pool_id, self.pool);
+            for expected in &array.ticks {
+                let tick = self
+                    .h
+                    .with_accounts(&[AccountMeta::new_readonly(*key, false)], |infos| {
+                        raydium_binding::TickArrayState::read_tick(
+                            &infos[0],
+                            &self.pool,
+                            expected.tick,
+                            spacing,
+                        )
+                    })
+                    .unwrap();
+                assert_eq!(tick.liquidity_gross, expected.liquidity_gross);
+                assert_eq!(
+                    tick.fee_growth_outside_0_x64,
+                    expected.fee_growth_outside_0_x64
+                );
+                assert_eq!(
+                    tick.fee_growth_outside_1_x64,
+                    expected.fee_growth_outside_1_x64
+                );
+                assert_eq!(
+                    tick.reward_growths_outside_x64,
+                    expected.reward_growths_outside_x64
+                );
+            }
+        }
+
+        // A new vault position over the tick array holding the current tick is
+        // valued at what removing it pays out
+        let vault = Pubkey::new_unique();
+        let lower = TickArrayState::start_index_of(tick, spacing);
+        let upper = lower + (mock_raydium::state::TICK_ARRAY_SIZE - 1) * i32::from(spacing);
+        let mint = Pubkey::new_unique();
+        let (key, _) =
+            Pubkey::find_program_address(&[b"position", mint.as_ref()], &mock_raydium::ID);
+        let mut state = self.pool();
+        let mut position = raydium_position(mint, self.pool, lower, upper);
+        let mut ticks = self.ticks();
+        mock_raydium::pool::modify_liquidity(&mut state, &mut position, &mut ticks, 1 << 40)
+            .unwrap();
+        self.h.set_anchor_account(self.pool, &state);
+        self.h.set_anchor_account(key, &position);
+        for (key, array) in ticks.into_arrays() {
+            self.h.set_anchor_account(key, &array);
+        }
+        let nft_account = self.h.create_token_account(&mint, &vault, 1);
+
+        let value = self
+            .with_adapter(vault, Some((key, nft_account)), |dex, infos| {
+                let position = dex.load_position(infos[0].clone(), &mut infos[1..].iter())?;
+                dex.position_value(&position)
+            })
+            .unwrap();
+        let removal = mock_raydium::pool::modify_liquidity(
+            &mut state,
+            &mut position,
+            &mut self.ticks(),
+            -(1 << 40),
+        )
+        .unwrap();
+        assert_eq!((value.amount_a, value.amount_b), removal);
+        assert_eq!((value.fees_owed_a, value.fees_owed_b), (0, 0));
+        assert!(value.rewards_owed.iter().all(|(_, amount)| *amount == 0));
+    }
+}
+
+/// Accounts of one DLMM pair, loaded from its fixture directory.
+struct DlmmFixture {
+    h: Harness,
+    lb_pair: Pubkey,
+    bin_arrays: Vec<Pubkey>,
+}
+
+impl DlmmFixture {
+    fn load(h: Harness, accounts: &FixtureAccounts) -> Self {
+        let program = vault_manager::meteora::ID;
+        let lb_pair = accounts.one_of_type(&h, &program, LbPair::DISCRIMINATOR, "LbPair");
+        let bin_arrays = accounts.of_type(&h, &program, &BinArray::DISCRIMINATOR);
+
+        // Besides those, the dump holds the pair's oracle, mints and reserves
+        let pair: LbPair = h.anchor_account(&lb_pair);
+        accounts.check_no_others(
+            1 + bin_arrays.len(),
+            &[
+                pair.oracle,
+                pair.token_x_mint,
+                pair.token_y_mint,
+                pair.reserve_x,
+                pair.reserve_y,
+            ],
+        );
+        Self {
+            h,
+            lb_pair,
+            bin_arrays,
+        }
+    }
+
+    fn pair(&self) -> LbPair {
+        self.h.anchor_account(&self.lb_pair)
+    }
+
+    /// Runs `f` on the vault's DLMM backend for this pair, as `vault`.
+    fn with_adapter<R>(
+        &self,
+        vault: Pubkey,
+        position: Option<Pubkey>,
+        f: impl for<'a> FnOnce(&MeteoraDlmmAdapter<'a>, &[AccountInfo<'a>]) -> Result<R>,
+    ) -> Result<R> {
+        let pair = self.pair();
+        let keys = PoolKeys {
+            pool: self.lb_pair,
+            mint_a: pair.token_x_mint,
+            mint_b: pair.token_y_mint,
+            vault_a: pair.reserve_x,
+            vault_b: pair.reserve_y,
+            program: mock_dlmm::ID,
+        };
+        let mut metas = Vec::new();
+        if let Some(position) = position {
+            let state: PositionV2 = self.h.anchor_account(&position);
+            let (event_authority, _) =
+                Pubkey::find_program_address(&[b"__event_authority"], &mock_dlmm::ID);
+            metas.extend([
+                AccountMeta::new(position, false),
+                AccountMeta::new_readonly(pair.token_x_mint, false),
+                AccountMeta::new_readonly(pair.token_y_mint, false),
+                AccountMeta::new_readonly(event_authority, false),
+                AccountMeta::new(self.bin_array(state.lower_bin_id), false),
+                AccountMeta::new(self.bin_array(state.upper_bin_id), false),
+            ]);
+        }
+        with_dex_accounts(&self.h, &keys, vault, &metas, |accounts, infos| {
+            f(&MeteoraDlmmAdapter::load(accounts)?, infos)
+        })
+    }
+
+    fn bin_array(&self, bin_id: i32) -> Pubkey {
+        dlmm_bin_array(&self.lb_pair, MockBinArray::index_of(bin_id))
+    }
+
+    fn bins(&self) -> Bins {
+        Bins::new(
+            self.bin_arrays
+                .iter()
+                .map(|key| (*key, self.h.anchor_account(key)))
+                .collect(),
+        )
+    }
+
+    fn check(mut self) {
+        let pair = self.pair();
+        let price = self
+            .with_adapter(Pubkey::new_unique(), None, |dex, _| dex.price())
+            .unwrap();
+        assert_eq!(
+            (price.sqrt_price, price.tick_current),
+            (
+                math::sqrt_price_from_bin_id(pair.active_id, pair.bin_step).unwrap(),
+                pair.active_id
+            )
+        );
+        check_mints(&self.h, [pair.token_x_mint, pair.token_y_mint]);
+
+        // The vault reads every bin as the layout stores it, at the price its
+        // math gives the bin
+        for key in &self.bin_arrays {
+            let array: MockBinArray = self.h.anchor_account(key);
+            assert_eq!(array.lb_pair, self.lb_pair);
+            for (i, expected) in array.bins.iter().enumerate() {
+                let bin_id = (array.index * i64::from(mock_dlmm::state::MAX_BIN_PER_ARRAY)) as i32
+                    + i as i32;
+                let bin = self
+                    .h
+                    .with_accounts(&[AccountMeta::new_readonly(*key, false)], |infos| {
+                        BinArray::read_bin(&infos[0], &self.lb_pair, bin_id)
+                    })
+                    .unwrap();
+                assert_eq!(
+                    (bin.amount_x, bin.amount_y),
+                    (expected.amount_x, expected.amount_y)
+                );
+                assert_eq!(bin.price, expected.price);
+                assert_eq!(
+                    bin.price,
+                    math::price_x64_from_bin_id(bin_id, pair.bin_step).unwrap()
+                );
+                assert_eq!(bin.liquidity_supply, expected.liquidity_supply);
+                assert_eq!(
+                    bin.reward_per_token_stored,
+                    expected.reward_per_token_stored
+                );
+                assert_eq!(
+                    bin.fee_amount_x_per_token_stored,
+                    expected.fee_amount_x_per_token_stored
+                );
+                assert_eq!(
+                    bin.fee_amount_y_per_token_stored,
+                    expected.fee_amount_y_per_token_stored
+                );
+            }
+        }
+
+        // A new vault position over the bin array holding the active bin is
+        // valued at what removing it pays out
+        let vault = Pubkey::new_unique();
+        let max_bins = mock_dlmm::state::MAX_BIN_PER_ARRAY;
+        let lower = MockBinArray::index_of(pair.active_id) as i32 * max_bins;
+        let upper = lower + max_bins - 1;
+        let key = Pubkey::new_unique();
+        let mut position = PositionV2::new(self.lb_pair, vault, lower, max_bins).unwrap();
+        let mut bins = self.bins();
+        let parameter = strategy(&pair, lower, upper, mock_dlmm::StrategyType::SpotBalanced);
+        mock_dlmm::pool::add_liquidity(
+            &pair,
+            &mut position,
+            &mut bins,
+            &mock_dlmm::LiquidityParameterByStrategy {
+                amount_x: 1_000_000_000,
+                amount_y: 150_000_000,
+                ..parameter
+            },
+        )
+        .unwrap();
+        self.h.set_anchor_account(key, &position);
+        for (key, array) in bins.into_arrays() {
+            self.h.set_anchor_account(key, &array);
+        }
+
+        let value = self
+            .with_adapter(vault, Some(key), |dex, infos| {
+                let position = dex.load_position(infos[0].clone(), &mut infos[1..].iter())?;
+                dex.position_value(&position)
+            })
+            .unwrap();
+        let removal = mock_dlmm::pool::remove_liquidity(
+            &mut position,
+            &mut self.bins(),
+            lower,
+            upper,
+            10_000,
+        )
+        .unwrap();
+        assert_eq!((value.amount_a, value.amount_b), removal);
+        assert_eq!((value.fees_owed_a, value.fees_owed_b), (0, 0));
+        assert!(value.rewards_owed.iter().all(|(_, amount)| *amount == 0));
+    }
+}
+
+fn whirlpool_tick_array(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            whirlpool.as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        &mock_whirlpool::ID,
+    )
+    .0
+}
+
+fn raydium_tick_array(pool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            pool.as_ref(),
+            &start_tick_index.to_be_bytes(),
+        ],
+        &mock_raydium::ID,
+    )
+    .0
+}
+
+fn dlmm_bin_array(lb_pair: &Pubkey, index: i64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"bin_array", lb_pair.as_ref(), &index.to_le_bytes()],
+        &mock_dlmm::ID,
+    )
+    .0
+}
+
+/// Empty Raydium position over `[lower, upper)`, whose NFT is `nft_mint`.
+fn raydium_position(
+    nft_mint: Pubkey,
+    pool: Pubkey,
+    lower: i32,
+    upper: i32,
+) -> PersonalPositionState {
+    PersonalPositionState {
+        bump: [0],
+        nft_mint,
+        pool_id: pool,
+        tick_lower_index: lower,
+        tick_upper_index: upper,
+        liquidity: 0,
+        fee_growth_inside_0_last_x64: 0,
+        fee_growth_inside_1_last_x64: 0,
+        token_fees_owed_0: 0,
+        token_fees_owed_1: 0,
+        reward_infos: Default::default(),
+        recent_epoch: 0,
+        padding: [0; 7],
+    }
+}
+
+/// Deposit over `[min_bin_id, max_bin_id]` of `pair` with no tokens yet.
+fn strategy(
+    pair: &LbPair,
+    min_bin_id: i32,
+    max_bin_id: i32,
+    strategy_type: mock_dlmm::StrategyType,
+) -> mock_dlmm::LiquidityParameterByStrategy {
+    mock_dlmm::LiquidityParameterByStrategy {
+        amount_x: 0,
+        amount_y: 0,
+        active_id: pair.active_id,
+        max_active_bin_slippage: 0,
+        strategy_parameters: mock_dlmm::StrategyParameters {
+            min_bin_id,
+            max_bin_id,
+            strategy_type,
+            parameteres: [0; 64],
+        },
+    }
+}
+
+/// Address standing in for a keypair account of a generated pool, derived
+/// from `label` so regenerating the fixtures reproduces it.
+fn generated_key(label: &str) -> Pubkey {
+    Pubkey::create_with_seed(&Pubkey::default(), label, &system_program::ID).unwrap()
+}
+
+fn mint_account(decimals: u8) -> TestAccount {
+    let mint = spl_token::state::Mint {
+        mint_authority: COption::None,
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    mint.pack_into_slice(&mut data);
+    TestAccount::new(data, spl_token::ID)
+}
+
+/// Pool reserves as liquidity and swaps move them.
+#[derive(Default)]
+struct Reserves {
+    a: u64,
+    b: u64,
+}
+
+impl Reserves {
+    fn deposit(&mut self, (a, b): (u64, u64)) {
+        self.a += a;
+        self.b += b;
+    }
+
+    fn swap(&mut self, a_to_b: bool, (amount_in, amount_out): (u64, u64)) {
+        if a_to_b {
+            self.a += amount_in;
+            self.b -= amount_out;
+        } else {
+            self.b += amount_in;
+            self.a -= amount_out;
+        }
+    }
+}
+
+/// Stores the WSOL and USDC mints and the pool's token vaults holding
+/// `reserves`. Returns their keys.
+fn set_tokens(
+    h: &mut Harness,
+    pool: &Pubkey,
+    vaults: [Pubkey; 2],
+    reserves: &Reserves,
+) -> Vec<Pubkey> {
+    h.set_account(WSOL, mint_account(9));
+    h.set_account(USDC, mint_account(6));
+    h.set_token_account(vaults[0], &WSOL, pool, reserves.a);
+    h.set_token_account(vaults[1], &USDC, pool, reserves.b);
+    vec![WSOL, USDC, vaults[0], vaults[1]]
+}
+
+/// LP ranges of the generated CLMM pools and their liquidity, around a
+/// current tick of -18972, a WSOL price of 150 USDC. The bounds are
+/// multiples of both pools' tick spacings.
+const CLMM_POSITIONS: [(i32, i32, i128); 4] = [
+    (-19_840, -17_920, 1_000_000_000_000),
+    (-25_600, -12_800, 3_000_000_000_000),
+    (-19_200, -18_560, 20_000_000_000_000),
+    (-30_720, -20_480, 1_000_000_000_000),
+];
+/// Swaps of the generated CLMM pools, as the amount in and `a_to_b`, taking
+/// the price over the upper bound of the narrowest range and back.
+const CLMM_SWAPS: [(u64, bool); 4] = [
+    (250_000_000_000, false),
+    (300_000_000_000, true),
+    (80_000_000_000, false),
+    (200_000_000_000, true),
+];
+
+fn clmm_sqrt_price() -> u128 {
+    math::sqrt_price_from_tick_index(-18_972).unwrap() + 987_654_321
+}
+
+/// An Orca WSOL/USDC Whirlpool with an ORCA reward and its adaptive fee oracle.
+fn generate_whirlpool(h: &mut Harness) -> Vec<Pubkey> {
+    const SPACING: u16 = 64;
+    let config = generated_key("orca-wsol-usdc whirlpools config");
+    let (whirlpool, _) = Pubkey::find_program_address(
+        &[
+            b"whirlpool",
+            config.as_ref(),
+            WSOL.as_ref(),
+            USDC.as_ref(),
+            &SPACING.to_le_bytes(),
+        ],
+        &mock_whirlpool::ID,
+    );
+    let vaults = [
+        generated_key("orca-wsol-usdc token vault a"),
+        generated_key("orca-wsol-usdc token vault b"),
+    ];
+    let mut state = Whirlpool::new(
+        config,
+        WSOL,
+        USDC,
+        vaults[0],
+        vaults[1],
+        SPACING,
+        3_000,
+        clmm_sqrt_price(),
+    )
+    .unwrap();
+    let mut now = GENERATED_AT;
+    state.reward_last_updated_timestamp = now as u64;
+    state.reward_infos[0] = WhirlpoolRewardInfo {
+        mint: ORCA,
+        vault: generated_key("orca-wsol-usdc reward vault 0"),
+        authority: generated_key("orca-wsol-usdc reward authority"),
+        emissions_per_second_x64: 50_000 << 64,
+        growth_global_x64: 0,
+    };
+
+    let starts = [-33_792, -28_160, -22_528, -16_896, -11_264];
+    let mut ticks = TickArrays::new(
+        SPACING,
+        starts
+            .iter()
+            .map(|start| {
+                let array = MockTickArray::new(whirlpool, *start, SPACING).unwrap();
+                (whirlpool_tick_array(&whirlpool, *start), array)
+            })
+            .collect(),
+    );
+    let mut reserves = Reserves::default();
+    for (i, (lower, upper, liquidity)) in CLMM_POSITIONS.into_iter().enumerate() {
+        let mint = generated_key(&format!("orca-wsol-usdc position {i}"));
+        let mut position = Position::new(whirlpool, &state, mint, lower, upper).unwrap();
+        reserves.deposit(
+            pool::modify_liquidity(&mut state, &mut position, &mut ticks, liquidity, now).unwrap(),
+        );
+        now += STEP_SECS;
+    }
+    for (amount, a_to_b) in CLMM_SWAPS {
+        reserves.swap(
+            a_to_b,
+            pool::swap(&mut state, &mut ticks, amount, 0, a_to_b, now).unwrap(),
+        );
+        now += STEP_SECS;
+    }
+    pool::update_rewards(&mut state, now).unwrap();
+
+    let (oracle, _) =
+        Pubkey::find_program_address(&[b"oracle", whirlpool.as_ref()], &mock_whirlpool::ID);
+    h.set_anchor_account(
+        oracle,
+        &Oracle {
+            whirlpool,
+            trade_enable_timestamp: 0,
+            adaptive_fee_constants: Default::default(),
+            adaptive_fee_variables: Default::default(),
+            reserved: [0; 128],
+        },
+    );
+    h.set_anchor_account(whirlpool, &state);
+    let mut keys = vec![whirlpool, oracle];
+    for (key, array) in ticks.into_arrays() {
+        h.set_anchor_account(key, &array);
+        keys.push(key);
+    }
+    keys.extend(set_tokens(h, &whirlpool, vaults, &reserves));
+    keys
+}
+
+/// A Raydium CLMM WSOL/USDC pool with its config and observations.
+fn generate_raydium(h: &mut Harness) -> Vec<Pubkey> {
+    const SPACING: u16 = 10;
+    const TRADE_FEE_RATE: u32 = 500;
+    let index = 1u16;
+    let (amm_config, config_bump) =
+        Pubkey::find_program_address(&[b"amm_config", &index.to_be_bytes()], &mock_raydium::ID);
+    let (pool_key, pool_bump) = Pubkey::find_program_address(
+        &[b"pool", amm_config.as_ref(), WSOL.as_ref(), USDC.as_ref()],
+        &mock_raydium::ID,
+    );
+    let (observation, _) =
+        Pubkey::find_program_address(&[b"observation", pool_key.as_ref()], &mock_raydium::ID);
+    let vaults = [
+        generated_key("raydium-wsol-usdc token vault 0"),
+        generated_key("raydium-wsol-usdc token vault 1"),
+    ];
+    let owner = generated_key("raydium-wsol-usdc owner");
+    h.set_anchor_account(
+        amm_config,
+        &AmmConfig {
+            bump: config_bump,
+            index,
+            owner,
+            protocol_fee_rate: 0,
+            trade_fee_rate: TRADE_FEE_RATE,
+            tick_spacing: SPACING,
+            fund_fee_rate: 0,
+            padding_u32: 0,
+            fund_owner: owner,
+            padding: [0; 3],
+        },
+    );
+
+    let sqrt_price_x64 = clmm_sqrt_price();
+    let mut state = PoolState {
+        bump: [pool_bump],
+        amm_config,
+        owner,
+        token_mint_0: WSOL,
+        token_mint_1: USDC,
+        token_vault_0: vaults[0],
+        token_vault_1: vaults[1],
+        observation_key: observation,
+        mint_decimals_0: 9,
+        mint_decimals_1: 6,
+        tick_spacing: SPACING,
+        liquidity: 0,
+        sqrt_price_x64,
+        tick_current: mock_whirlpool::math::tick_index_from_sqrt_price(sqrt_price_x64).unwrap(),
+        padding3: 0,
+        padding4: 0,
+        fee_growth_global_0_x64: 0,
+        fee_growth_global_1_x64: 0,
+        protocol_fees_token_0: 0,
+        protocol_fees_token_1: 0,
+        swap_in_amount_token_0: 0,
+        swap_out_amount_token_1: 0,
+        swap_in_amount_token_1: 0,
+        swap_out_amount_token_0: 0,
+        status: 0,
+        padding: [0; 7],
+        reward_infos: Default::default(),
+        tick_array_bitmap: [0; 16],
+        total_fees_token_0: 0,
+        total_fees_claimed_token_0: 0,
+        total_fees_token_1: 0,
+        total_fees_claimed_token_1: 0,
+        fund_fees_token_0: 0,
+        fund_fees_token_1: 0,
+        open_time: GENERATED_AT as u64,
+        recent_epoch: 0,
+        padding1: [0; 24],
+        padding2: [0; 32],
+    };
+    let mut observations = MockObservationState {
+        initialized: false,
+        recent_epoch: 0,
+        observation_index: 0,
+        pool_id: pool_key,
+        observations: [Observation::default(); mock_raydium::state::OBSERVATION_NUM],
+        padding: [0; 4],
+    };
+
+    let starts = [
+        -31_200, -25_800, -21_000, -20_400, -19_800, -19_200, -18_600, -18_000, -13_200,
+    ];
+    let mut ticks = RaydiumTickArrays::new(
+        SPACING,
+        starts
+            .iter()
+            .map(|start| {
+                let array = TickArrayState::new(pool_key, *start, SPACING).unwrap();
+                (raydium_tick_array(&pool_key, *start), array)
+            })
+            .collect(),
+    );
+    let mut reserves = Reserves::default();
+    let mut now = GENERATED_AT;
+    observations.update(now as u32, state.tick_current);
+    for (i, (lower, upper, liquidity)) in CLMM_POSITIONS.into_iter().enumerate() {
+        let mint = generated_key(&format!("raydium-wsol-usdc position {i}"));
+        let mut position = raydium_position(mint, pool_key, lower, upper);
+        reserves.deposit(
+            mock_raydium::pool::modify_liquidity(&mut state, &mut position, &mut ticks, liquidity)
+                .unwrap(),
+        );
+        now += STEP_SECS;
+    }
+    for (amount, a_to_b) in CLMM_SWAPS {
+        let swap =
+            mock_raydium::pool::swap(&mut state, &mut ticks, amount, 0, a_to_b, TRADE_FEE_RATE)
+                .unwrap();
+        reserves.swap(a_to_b, swap);
+        observations.update(now as u32, state.tick_current);
+        now += STEP_SECS;
+    }
+
+    h.set_anchor_account(pool_key, &state);
+    h.set_anchor_account(observation, &observations);
+    let mut keys = vec![amm_config, pool_key, observation];
+    for (key, array) in ticks.into_arrays() {
+        h.set_anchor_account(key, &array);
+        keys.push(key);
+    }
+    keys.extend(set_tokens(h, &pool_key, vaults, &reserves));
+    keys
+}
+
+/// A Meteora DLMM WSOL/USDC pair with 10 basis point bins. The mock has no
+/// oracle account, so the dump holds none.
+fn generate_dlmm(h: &mut Harness) -> Vec<Pubkey> {
+    const BIN_STEP: u16 = 10;
+    const ACTIVE_ID: i32 = -1_898;
+    let (lb_pair, _) = Pubkey::find_program_address(
+        &[WSOL.as_ref(), USDC.as_ref(), &BIN_STEP.to_le_bytes()],
+        &mock_dlmm::ID,
+    );
+    let (oracle, _) = Pubkey::find_program_address(&[b"oracle", lb_pair.as_ref()], &mock_dlmm::ID);
+    let reserves_keys = [
+        generated_key("meteora-wsol-usdc reserve x"),
+        generated_key("meteora-wsol-usdc reserve y"),
+    ];
+    let mut pair = LbPair::new(
+        WSOL,
+        USDC,
+        reserves_keys[0],
+        reserves_keys[1],
+        oracle,
+        BIN_STEP,
+        ACTIVE_ID,
+    );
+
+    let mut bins = Bins::new(
+        (-30..=-26)
+            .map(|index| {
+                let array = MockBinArray::new(lb_pair, index, BIN_STEP).unwrap();
+                (dlmm_bin_array(&lb_pair, index), array)
+            })
+            .collect(),
+    );
+    let mut reserves = Reserves::default();
+    let deposits = [
+        (
+            ACTIVE_ID - 35,
+            70,
+            mock_dlmm::StrategyType::SpotBalanced,
+            200_000_000_000,
+            30_000_000_000,
+        ),
+        (
+            ACTIVE_ID - 10,
+            21,
+            mock_dlmm::StrategyType::CurveBalanced,
+            500_000_000_000,
+            75_000_000_000,
+        ),
+        (
+            ACTIVE_ID - 69,
+            70,
+            mock_dlmm::StrategyType::BidAskImBalanced,
+            0,
+            50_000_000_000,
+        ),
+        (
+            ACTIVE_ID + 1,
+            60,
+            mock_dlmm::StrategyType::SpotImBalanced,
+            300_000_000_000,
+            0,
+        ),
+    ];
+    for (i, (lower, width, strategy_type, amount_x, amount_y)) in deposits.into_iter().enumerate() {
+        let owner = generated_key(&format!("meteora-wsol-usdc position {i}"));
+        let mut position = PositionV2::new(lb_pair, owner, lower, width).unwrap();
+        let parameter = strategy(&pair, lower, lower + width - 1, strategy_type);
+        reserves.deposit(
+            mock_dlmm::pool::add_liquidity(
+                &pair,
+                &mut position,
+                &mut bins,
+                &mock_dlmm::LiquidityParameterByStrategy {
+                    amount_x,
+                    amount_y,
+                    ..parameter
+                },
+            )
+            .unwrap(),
+        );
+    }
+    for (amount, swap_for_y) in [
+        (200_000_000_000, true),
+        (50_000_000_000, false),
+        (100_000_000_000, true),
+    ] {
+        let amount_out = mock_dlmm::pool::swap(&mut pair, &mut bins, amount, swap_for_y).unwrap();
+        reserves.swap(swap_for_y, (amount, amount_out));
+    }
+
+    h.set_anchor_account(lb_pair, &pair);
+    let mut keys = vec![lb_pair];
+    for (key, array) in bins.into_arrays() {
+        h.set_anchor_account(key, &array);
+        keys.push(key);
+    }
+    keys.extend(set_tokens(h, &lb_pair, reserves_keys, &reserves));
+    keys
+}
+
+/// Builds a pool in a harness. Returns the keys of the accounts to dump.
+type Generator = fn(&mut Harness) -> Vec<Pubkey>;
+
+/// The generated pools, by fixture directory name.
+fn generated_fixtures() -> [(&'static str, Generator); 3] {
+    [
+        ("generated-orca-wsol-usdc", generate_whirlpool),
+        ("generated-raydium-wsol-usdc", generate_raydium),
+        ("generated-meteora-wsol-usdc", generate_dlmm),
+    ]
+}
+
+fn write_fixtures(h: &Harness, keys: &[Pubkey], dir: &Path) {
+    for key in keys {
+        AccountFixture::new(key, h.account(key).unwrap()).write(dir);
+    }
+}
+
+#[test]
+fn fixtures_round_trip_through_the_loader() {
+    for (name, generate) in generated_fixtures() {
+        let mut h = Harness::new();
+        let mut keys = generate(&mut h);
+        let dir =
+            std::env::temp_dir().join(format!("vault-fixtures-{}-{name}", std::process::id()));
+        write_fixtures(&h, &keys, &dir);
+
+        let mut loaded = Harness::new();
+        let mut loaded_keys = load_fixtures(&mut loaded, &dir);
+        loaded_keys.sort();
+        keys.sort();
+        assert_eq!(loaded_keys, keys);
+        for key in &keys {
+            assert_eq!(loaded.account(key), h.account(key));
+        }
+        check_pool_fixture(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn whirlpool_fixtures_find_the_pool_oracle_and_tick_arrays() {
+    let mut h = Harness::new();
+    let keys = generate_whirlpool(&mut h);
+    let accounts = FixtureAccounts {
+        dir: PathBuf::from("generated-orca-wsol-usdc"),
+        keys,
+    };
+    let fixture = WhirlpoolFixture::load(h, &accounts);
+    let (oracle, _) = Pubkey::find_program_address(
+        &[b"oracle", fixture.whirlpool.as_ref()],
+        &mock_whirlpool::ID,
+    );
+    assert_eq!(fixture.tick_arrays.len(), 5);
+    assert_eq!(fixture.oracle, Some(oracle));
+    assert!(fixture.pool().reward_infos[0].initialized());
+}
+
+#[test]
+fn pool_fixtures_value_with_the_vault_backend() {
+    let backends: BTreeSet<Backend> = pool_fixtures()
+        .iter()
+        .map(|dir| check_pool_fixture(dir))
+        .collect();
+    assert_eq!(
+        backends,
+        BTreeSet::from([Backend::Whirlpool, Backend::Raydium, Backend::Dlmm]),
+        "a pool fixture of every backend; see tests/fixtures/README.md"
+    );
+}
+
+/// Rewrites the `generated-*` fixture directories from the mock programs.
+#[test]
+#[ignore = "rewrites tests/fixtures; run with --ignored after changing a generator"]
+fn write_generated_fixtures() {
+    for (name, generate) in generated_fixtures() {
+        let mut h = Harness::new();
+        let keys = generate(&mut h);
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        write_fixtures(&h, &keys, &dir);
+    }
+}
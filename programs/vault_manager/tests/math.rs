@@ -0,0 +1,191 @@
+//! Properties of the share, fee and liquidity math.
+//!
+//! Every function is driven over its whole input domain: results either hold
+//! their rounding guarantee or come back as `MathOverflow`, never as a panic.
+
+use anchor_lang::prelude::*;
+use proptest::prelude::*;
+use vault_manager::circuit_breaker::drop_bps;
+use vault_manager::math::{self, U256};
+use vault_manager::nav::VaultNav;
+use vault_manager::VaultError;
+
+fn overflow<T>() -> Result<T> {
+    err!(VaultError::MathOverflow)
+}
+
+fn nav(total_value_b: u128, share_supply: u64) -> VaultNav {
+    VaultNav {
+        total_value_b,
+        share_supply,
+        ..VaultNav::default()
+    }
+}
+
+/// Tick bounds of a position, lower below upper.
+fn tick_range() -> impl Strategy<Value = (i32, i32)> {
+    (math::MIN_TICK_INDEX..math::MAX_TICK_INDEX)
+        .prop_flat_map(|lower| (Just(lower), lower + 1..=math::MAX_TICK_INDEX))
+}
+
+fn sqrt_price(tick: i32) -> u128 {
+    math::sqrt_price_from_tick_index(tick).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn mul_div_rounds_down_and_ceil_rounds_up(a: u128, b: u128, denominator: u128) {
+        let product = U256::from(a) * U256::from(b);
+        let floor = math::mul_div(a, b, denominator);
+        let ceil = math::mul_div_ceil(a, b, denominator);
+        if denominator == 0 {
+            prop_assert_eq!(floor, overflow());
+            prop_assert_eq!(ceil, overflow());
+            return Ok(());
+        }
+
+        let exact = product / U256::from(denominator);
+        let exact_up = exact + u8::from(!(product % U256::from(denominator)).is_zero());
+        match floor {
+            Ok(floor) => prop_assert_eq!(U256::from(floor), exact),
+            Err(_) => prop_assert!(exact > U256::from(u128::MAX)),
+        }
+        match ceil {
+            Ok(ceil) => prop_assert_eq!(U256::from(ceil), exact_up),
+            Err(_) => prop_assert!(exact_up > U256::from(u128::MAX)),
+        }
+    }
+
+    #[test]
+    fn mul_div_u64_fits_shares_of_an_amount(amount: u64, shares: u64, supply: u64) {
+        let paid = math::mul_div_u64(amount, shares.min(supply), supply);
+        if supply == 0 {
+            prop_assert_eq!(paid, overflow());
+        } else {
+            prop_assert!(paid.unwrap() <= amount);
+        }
+    }
+
+    #[test]
+    fn split_redemptions_never_pay_more(amount: u64, supply in 1u64.., split: (u64, u64)) {
+        let first = split.0 % (supply + 1);
+        let second = split.1 % (supply - first + 1);
+        let paid = |shares| math::mul_div_u64(amount, shares, supply).unwrap();
+        prop_assert!(paid(first) + paid(second) <= paid(first + second));
+    }
+
+    #[test]
+    fn fees_round_up_for_the_vault(amount: u64, bps in 0u16..10_000) {
+        let fee = math::bps_fee(amount, bps).unwrap();
+        let exact = u128::from(amount) * u128::from(bps);
+        prop_assert!(u128::from(fee) * 10_000 >= exact);
+        prop_assert!(u128::from(fee) * 10_000 < exact + 10_000);
+        prop_assert!(fee <= amount);
+    }
+
+    #[test]
+    fn deposits_never_dilute_holders(total_value_b: u64, supply: u64, deposit: u64) {
+        let before = nav(u128::from(total_value_b), supply);
+        let Ok(shares) = before.shares_for_value(u128::from(deposit)) else {
+            return Ok(());
+        };
+        let Some(new_supply) = supply.checked_add(shares) else {
+            return Ok(());
+        };
+        let after = nav(u128::from(total_value_b) + u128::from(deposit), new_supply);
+
+        // Redeeming right away returns at most the deposit
+        prop_assert!(after.value_of_shares(shares).unwrap() <= u128::from(deposit));
+        // and existing shares are worth at least what they were
+        prop_assert!(
+            after.value_of_shares(supply).unwrap() >= before.value_of_shares(supply).unwrap()
+        );
+    }
+
+    #[test]
+    fn liquidity_never_needs_more_than_its_amounts(
+        (lower, upper) in tick_range(),
+        current in math::MIN_TICK_INDEX..=math::MAX_TICK_INDEX,
+        amount_a: u64,
+        amount_b: u64,
+    ) {
+        let (sqrt_lower, sqrt_upper, sqrt_current) =
+            (sqrt_price(lower), sqrt_price(upper), sqrt_price(current));
+        let liquidity =
+            math::liquidity_for_amounts(sqrt_current, sqrt_lower, sqrt_upper, amount_a, amount_b)
+                .unwrap();
+        let (needed_a, needed_b) =
+            math::amounts_for_liquidity(sqrt_current, sqrt_lower, sqrt_upper, liquidity).unwrap();
+        prop_assert!(needed_a <= amount_a);
+        prop_assert!(needed_b <= amount_b);
+    }
+
+    #[test]
+    fn sqrt_prices_increase_with_ticks(tick in math::MIN_TICK_INDEX..math::MAX_TICK_INDEX) {
+        let (lower, upper) = (sqrt_price(tick), sqrt_price(tick + 1));
+        prop_assert!(lower < upper);
+        prop_assert!((math::MIN_SQRT_PRICE_X64..=math::MAX_SQRT_PRICE_X64).contains(&lower));
+    }
+
+    #[test]
+    fn rebalance_swaps_at_most_the_balance(
+        sqrt_price: u128,
+        sqrt_lower: u128,
+        sqrt_upper: u128,
+        amount_a: u64,
+        amount_b: u64,
+    ) {
+        if let Ok(Some((amount_in, a_to_b))) =
+            math::rebalance_swap(sqrt_price, sqrt_lower, sqrt_upper, amount_a, amount_b)
+        {
+            let balance = if a_to_b { amount_a } else { amount_b };
+            prop_assert!(amount_in <= balance);
+        }
+    }
+
+    #[test]
+    fn value_conversions_never_panic(
+        amount_a: u64,
+        amount_b: u64,
+        value: u128,
+        sqrt_price: u128,
+        price: u128,
+        liquidity: u128,
+        sqrt_lower: u128,
+        sqrt_upper: u128,
+    ) {
+        let _ = math::value_in_b(amount_a, amount_b, sqrt_price);
+        let _ = math::value_at_price(amount_a, amount_b, price);
+        let _ = math::b_to_a_amount(value, sqrt_price);
+        let _ = math::price_x64_from_sqrt_price(sqrt_price);
+        let _ = math::mul_shift_right_64(value, liquidity);
+        let _ = math::amounts_for_liquidity(sqrt_price, sqrt_lower, sqrt_upper, liquidity);
+        let _ = math::liquidity_for_amounts(sqrt_price, sqrt_lower, sqrt_upper, amount_a, amount_b);
+    }
+
+    #[test]
+    fn bin_prices_never_panic(bin_id: i32, bin_step: u16) {
+        let _ = math::sqrt_price_from_bin_id(bin_id, bin_step);
+    }
+
+    #[test]
+    fn nav_drops_are_at_most_everything(last: u128, current: u128) {
+        prop_assert!(drop_bps(last, current).unwrap() <= 10_000);
+    }
+}
+
+#[test]
+fn fee_on_a_single_share_is_charged() {
+    assert_eq!(math::bps_fee(1, 1), Ok(1));
+    assert_eq!(math::bps_fee(10_000, 1), Ok(1));
+    assert_eq!(math::bps_fee(10_001, 1), Ok(2));
+    assert_eq!(math::bps_fee(0, 9_999), Ok(0));
+}
+
+#[test]
+fn narrowing_overflows_instead_of_truncating() {
+    assert_eq!(math::narrow(u128::from(u64::MAX)), Ok(u64::MAX));
+    assert_eq!(math::narrow(u128::from(u64::MAX) + 1), overflow());
+    assert_eq!(math::checked_sub(1, 2), overflow());
+    assert_eq!(math::checked_add(u64::MAX, 1), overflow());
+}
@@ -0,0 +1,258 @@
+//! Vault authority held by a multisig, through the mock multisig program.
+//!
+//! Approvals run through the mock's instructions. The harness cannot run
+//! `execute`'s CPI, so the test applies its threshold check and processes
+//! `mock_multisig::authority_instruction`, the instruction it invokes, with the
+//! multisig's authority PDA signing.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::{Harness, TestAccount};
+use mock_multisig::{MockMultisigError, Multisig, Proposal};
+use vault_manager::dex::{BinStrategy, Dex};
+use vault_manager::route::MAX_SWAP_PROGRAMS;
+use vault_manager::{Vault, VaultConfig, VaultError, VaultFees, VaultStrategy};
+
+struct Env {
+    h: Harness,
+    vault: Pubkey,
+    multisig: Pubkey,
+    /// The multisig's authority PDA, which holds the vault's authority.
+    authority: Pubkey,
+    members: [Pubkey; 3],
+}
+
+impl Env {
+    /// Vault whose authority is a 2-of-3 multisig.
+    fn new() -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_multisig::ID, mock_multisig::entry);
+
+        let members = [(); 3].map(|_| Pubkey::new_unique());
+        let multisig = Pubkey::new_unique();
+        let (authority, authority_bump) = mock_multisig::authority_address(&multisig);
+        h.set_anchor_account(
+            multisig,
+            &Multisig {
+                members: members.to_vec(),
+                threshold: 2,
+                authority_bump,
+            },
+        );
+
+        let pool = Pubkey::new_unique();
+        let (vault, bump) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, pool.as_ref()],
+            &vault_manager::ID,
+        );
+        h.set_anchor_account(
+            vault,
+            &Vault {
+                authority,
+                // The one backend the TWAP guard can be configured on
+                dex: Dex::RaydiumClmm,
+                pool,
+                token_a_mint: Pubkey::new_unique(),
+                token_b_mint: Pubkey::new_unique(),
+                vault_token_a: Pubkey::new_unique(),
+                vault_token_b: Pubkey::new_unique(),
+                position: Pubkey::new_unique(),
+                share_mint: Pubkey::new_unique(),
+                config: VaultConfig::default(),
+                strategy: VaultStrategy {
+                    bin_strategy: BinStrategy::Spot,
+                    swap_programs: [Pubkey::default(); MAX_SWAP_PROGRAMS],
+                },
+                fees: VaultFees::default(),
+                last_nav_per_share_x64: 0,
+                last_nav_slot: 0,
+                paused: false,
+                token_a_decimals: 6,
+                token_b_decimals: 6,
+                bump,
+                is_active: false,
+            },
+        );
+
+        Self {
+            h,
+            vault,
+            multisig,
+            authority,
+            members,
+        }
+    }
+
+    fn vault_state(&self) -> Vault {
+        self.h.anchor_account(&self.vault)
+    }
+
+    fn set_twap_guard(&self, authority: Pubkey, window_secs: u32) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetTwapGuard {
+                vault: self.vault,
+                authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetTwapGuard {
+                window_secs,
+                max_deviation_ticks: 100,
+            }
+            .data(),
+        }
+    }
+
+    /// Proposal of `instruction` by the first member, stored directly since
+    /// `propose` creates the account through a CPI.
+    fn propose(&mut self, instruction: Instruction) -> Pubkey {
+        let proposal = Pubkey::new_unique();
+        self.store_proposal(
+            proposal,
+            &Proposal {
+                multisig: self.multisig,
+                instruction: instruction.into(),
+                approvals: vec![self.members[0]],
+                executed: false,
+            },
+        );
+        proposal
+    }
+
+    /// Stores `state` with `propose`'s space, so approvals fit.
+    fn store_proposal(&mut self, key: Pubkey, state: &Proposal) {
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.resize(8 + Proposal::INIT_SPACE, 0);
+        self.h
+            .set_account(key, TestAccount::new(data, mock_multisig::ID));
+    }
+
+    fn approve(
+        &mut self,
+        proposal: Pubkey,
+        member: Pubkey,
+    ) -> std::result::Result<(), ProgramError> {
+        self.h.process(&Instruction {
+            program_id: mock_multisig::ID,
+            accounts: mock_multisig::accounts::Approve {
+                multisig: self.multisig,
+                proposal,
+                member,
+            }
+            .to_account_metas(None),
+            data: mock_multisig::instruction::Approve {}.data(),
+        })
+    }
+
+    /// What `execute` does: checks the approvals, marks the proposal executed
+    /// and invokes its instruction as the multisig authority.
+    fn execute(&mut self, proposal: Pubkey) -> std::result::Result<(), ProgramError> {
+        let multisig: Multisig = self.h.anchor_account(&self.multisig);
+        let mut state: Proposal = self.h.anchor_account(&proposal);
+        state.check_approved(&multisig)?;
+        state.executed = true;
+        self.store_proposal(proposal, &state);
+
+        let invoked = mock_multisig::authority_instruction(&self.multisig, &state.instruction);
+        self.h.process(&invoked)
+    }
+}
+
+#[test]
+fn multisig_approves_a_parameter_change() {
+    let mut env = Env::new();
+    let change = env.set_twap_guard(env.authority, 1_800);
+    // The signer flag is left to the multisig
+    assert!(change.accounts.iter().any(|meta| meta.is_signer));
+    let proposal = env.propose(change);
+
+    let not_enough = Err(ProgramError::Custom(
+        MockMultisigError::NotEnoughApprovals.into(),
+    ));
+    assert_eq!(env.execute(proposal), not_enough);
+    assert_eq!(
+        env.approve(proposal, Pubkey::new_unique()),
+        Err(ProgramError::Custom(MockMultisigError::NotAMember.into()))
+    );
+    assert_eq!(
+        env.approve(proposal, env.members[0]),
+        Err(ProgramError::Custom(
+            MockMultisigError::AlreadyApproved.into()
+        ))
+    );
+    assert_eq!(env.vault_state().config.twap_window_secs, 0);
+
+    env.approve(proposal, env.members[2]).unwrap();
+    env.execute(proposal).unwrap();
+    let vault = env.vault_state();
+    assert_eq!(vault.config.twap_window_secs, 1_800);
+    assert_eq!(vault.config.max_twap_deviation_ticks, 100);
+    assert_eq!(
+        env.execute(proposal),
+        Err(ProgramError::Custom(
+            MockMultisigError::AlreadyExecuted.into()
+        ))
+    );
+}
+
+#[test]
+fn vault_needs_the_multisig_signature() {
+    let mut env = Env::new();
+
+    // The authority PDA's key without its signature
+    let mut unsigned = env.set_twap_guard(env.authority, 1_800);
+    for meta in &mut unsigned.accounts {
+        meta.is_signer = false;
+    }
+    assert_eq!(
+        env.h.process(&unsigned),
+        Err(ProgramError::Custom(ErrorCode::AccountNotSigner.into()))
+    );
+
+    // A member signing alone
+    let member = env.set_twap_guard(env.members[0], 1_800);
+    assert_eq!(
+        env.h.process(&member),
+        Err(ProgramError::Custom(VaultError::Unauthorized.into()))
+    );
+    assert_eq!(env.vault_state().config.twap_window_secs, 0);
+}
+
+#[test]
+fn hot_key_hands_the_vault_to_the_multisig() {
+    let mut env = Env::new();
+    let hot_key = Pubkey::new_unique();
+    let mut vault = env.vault_state();
+    vault.authority = hot_key;
+    env.h.set_anchor_account(env.vault, &vault);
+
+    env.h
+        .process(&Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetAuthority {
+                vault: env.vault,
+                authority: hot_key,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetAuthority {
+                new_authority: env.authority,
+            }
+            .data(),
+        })
+        .unwrap();
+    assert_eq!(env.vault_state().authority, env.authority);
+    assert_eq!(
+        env.h.process(&env.set_twap_guard(hot_key, 1_800)),
+        Err(ProgramError::Custom(VaultError::Unauthorized.into()))
+    );
+
+    let proposal = env.propose(env.set_twap_guard(env.authority, 1_800));
+    env.approve(proposal, env.members[1]).unwrap();
+    env.execute(proposal).unwrap();
+    assert_eq!(env.vault_state().config.twap_window_secs, 1_800);
+}
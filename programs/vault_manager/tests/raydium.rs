@@ -0,0 +1,849 @@
+//! Raydium CLMM backend: observation parsing, and the vault end to end
+//! against the mock Raydium program.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use common::Harness;
+use mock_raydium::state::{PersonalPositionState, PoolState, TickArrayState};
+use mock_raydium::MockRaydiumError;
+use vault_manager::dex::Dex;
+use vault_manager::nav::VaultNav;
+use vault_manager::raydium::state::{ObservationState, OBSERVATION_NUM};
+use vault_manager::{ProtocolConfig, VaultError};
+
+const NOW: i64 = 1_700_000_000;
+
+/// `ObservationState` account data of `pool` holding `observations` of
+/// `(block_timestamp, tick_cumulative)` from slot 0, the last one newest.
+fn observation_state(pool: &Pubkey, observations: &[(u32, i64)]) -> Vec<u8> {
+    let mut data = ObservationState::DISCRIMINATOR.to_vec();
+    data.push(1);
+    data.extend(0u64.to_le_bytes());
+    data.extend((observations.len() as u16 - 1).to_le_bytes());
+    data.extend(pool.as_ref());
+    for i in 0..OBSERVATION_NUM {
+        let (block_timestamp, tick_cumulative) = observations.get(i).copied().unwrap_or_default();
+        data.extend(block_timestamp.to_le_bytes());
+        data.extend(tick_cumulative.to_le_bytes());
+        data.extend([0; 32]);
+    }
+    data.extend([0; 32]);
+    data
+}
+
+#[test]
+fn observations_give_the_time_weighted_tick() {
+    let pool = Pubkey::new_unique();
+    let start = NOW as u32;
+    // At tick 0 for 600 seconds, then at tick 600 since
+    let data = observation_state(&pool, &[(start, 0), (start + 600, 0)]);
+    assert_eq!(data.len(), ObservationState::LEN);
+    let state = ObservationState::parse(&data).unwrap();
+    assert_eq!(state.pool_id, pool);
+    assert_eq!(state.observation_index, 1);
+
+    assert_eq!(state.twap_tick(600, NOW + 1_200, 1_200).unwrap(), 300);
+    // The newest observation alone covers the last 600 seconds
+    assert_eq!(state.twap_tick(600, NOW + 1_200, 600).unwrap(), 600);
+    assert_eq!(
+        state.twap_tick(600, NOW + 1_200, 1_201).unwrap_err(),
+        error!(VaultError::InsufficientOracleHistory)
+    );
+}
+
+#[test]
+fn observation_state_must_have_the_raydium_layout() {
+    let pool = Pubkey::new_unique();
+    let data = observation_state(&pool, &[(NOW as u32, 0)]);
+
+    let mut other_account = data.clone();
+    other_account[0] ^= 1;
+    let truncated = &data[..ObservationState::LEN - 1];
+    for data in [&other_account[..], truncated] {
+        assert_eq!(
+            ObservationState::parse(data).err().unwrap(),
+            error!(VaultError::InvalidObservationState)
+        );
+    }
+    // An account that has never been observed has no TWAP
+    let empty = observation_state(&pool, &[(0, 0)]);
+    assert_eq!(
+        ObservationState::parse(&empty)
+            .unwrap()
+            .twap_tick(0, NOW, 60)
+            .unwrap_err(),
+        error!(VaultError::InsufficientOracleHistory)
+    );
+}
+
+const TICK_SPACING: u16 = 10;
+/// 0.3%, in hundredths of a basis point.
+const TRADE_FEE_RATE: u32 = 3_000;
+const LP_LIQUIDITY: u128 = 10_000_000_000;
+/// Arrays of 60 ticks, 600 apart at this spacing.
+const TICK_ARRAY_STARTS: [i32; 6] = [-1800, -1200, -600, 0, 600, 1200];
+/// Token balance depositors and traders start with, of each token.
+const USER_BALANCE: u64 = 1_000_000_000;
+
+/// A wallet and its token accounts.
+struct User {
+    wallet: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    shares: Pubkey,
+}
+
+struct Env {
+    h: Harness,
+    authority: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    amm_config: Pubkey,
+    pool: Pubkey,
+    token_vault_a: Pubkey,
+    token_vault_b: Pubkey,
+    observation_state: Pubkey,
+    vault: Pubkey,
+    vault_token_a: Pubkey,
+    vault_token_b: Pubkey,
+    share_mint: Pubkey,
+    position: Pubkey,
+    position_nft: Pubkey,
+    /// The protocol treasury's token accounts.
+    treasury_token_a: Pubkey,
+    treasury_token_b: Pubkey,
+}
+
+impl Env {
+    /// Pool at tick 0 with LP liquidity over ticks [-1200, 1200), and a vault
+    /// created by `initialize_vault` over ticks [-600, 600).
+    fn new() -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_raydium::ID, mock_raydium::entry);
+        let payer = h.create_wallet();
+
+        let authority = h.create_wallet();
+        // Raydium orders a pool's mints by address
+        let first = h.create_mint(&authority, 6);
+        let second = h.create_mint(&authority, 6);
+        let (mint_a, mint_b) = (first.min(second), first.max(second));
+        let amm_config =
+            Pubkey::find_program_address(&[b"amm_config", &0u16.to_be_bytes()], &mock_raydium::ID)
+                .0;
+        let pool = Pubkey::find_program_address(
+            &[
+                b"pool",
+                amm_config.as_ref(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+            ],
+            &mock_raydium::ID,
+        )
+        .0;
+        let observation_state =
+            Pubkey::find_program_address(&[b"observation", pool.as_ref()], &mock_raydium::ID).0;
+        let token_vault_a = h.create_token_account(&mint_a, &pool, 0);
+        let token_vault_b = h.create_token_account(&mint_b, &pool, 0);
+
+        let mut setup = vec![
+            Instruction {
+                program_id: mock_raydium::ID,
+                accounts: mock_raydium::accounts::CreateAmmConfig {
+                    owner: payer,
+                    amm_config,
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: mock_raydium::instruction::CreateAmmConfig {
+                    index: 0,
+                    tick_spacing: TICK_SPACING,
+                    trade_fee_rate: TRADE_FEE_RATE,
+                    protocol_fee_rate: 0,
+                    fund_fee_rate: 0,
+                }
+                .data(),
+            },
+            Instruction {
+                program_id: mock_raydium::ID,
+                accounts: mock_raydium::accounts::CreatePool {
+                    pool_creator: payer,
+                    amm_config,
+                    pool_state: pool,
+                    token_mint_0: mint_a,
+                    token_mint_1: mint_b,
+                    token_vault_0: token_vault_a,
+                    token_vault_1: token_vault_b,
+                    observation_state,
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: mock_raydium::instruction::CreatePool {
+                    sqrt_price_x64: 1 << 64,
+                    open_time: 0,
+                }
+                .data(),
+            },
+        ];
+        for start_tick_index in TICK_ARRAY_STARTS {
+            setup.push(Instruction {
+                program_id: mock_raydium::ID,
+                accounts: mock_raydium::accounts::InitializeTickArray {
+                    pool_state: pool,
+                    payer,
+                    tick_array: tick_array(&pool, start_tick_index),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: mock_raydium::instruction::InitializeTickArray { start_tick_index }.data(),
+            });
+        }
+        h.process_transaction(&setup).unwrap();
+
+        let (vault, _) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, pool.as_ref()],
+            &vault_manager::ID,
+        );
+        let (share_mint, _) = Pubkey::find_program_address(
+            &[vault_manager::SHARE_MINT_SEED, vault.as_ref()],
+            &vault_manager::ID,
+        );
+        let mut env = Self {
+            h,
+            authority,
+            mint_a,
+            mint_b,
+            amm_config,
+            pool,
+            token_vault_a,
+            token_vault_b,
+            observation_state,
+            vault,
+            vault_token_a: Pubkey::default(),
+            vault_token_b: Pubkey::default(),
+            share_mint,
+            position: Pubkey::default(),
+            position_nft: Pubkey::default(),
+            treasury_token_a: Pubkey::default(),
+            treasury_token_b: Pubkey::default(),
+        };
+
+        let lp = env.h.create_wallet();
+        let lp = User {
+            wallet: lp,
+            token_a: env.h.create_token_account(&mint_a, &lp, u64::MAX / 2),
+            token_b: env.h.create_token_account(&mint_b, &lp, u64::MAX / 2),
+            shares: Pubkey::default(),
+        };
+        env.open_position(&lp, lp.wallet, (-1200, 1200), LP_LIQUIDITY);
+
+        let (protocol_config, protocol_bump) = Pubkey::find_program_address(
+            &[vault_manager::PROTOCOL_CONFIG_SEED],
+            &vault_manager::ID,
+        );
+        // `initialize_protocol_config` needs the program's upgrade authority
+        let treasury = env.h.create_wallet();
+        env.h.set_anchor_account(
+            protocol_config,
+            &ProtocolConfig {
+                admin: authority,
+                fee_share_bps: 0,
+                treasury,
+                dex_programs: [
+                    mock_raydium::ID,
+                    Pubkey::default(),
+                    Pubkey::default(),
+                    Pubkey::default(),
+                ],
+                vault_count: 0,
+                bump: protocol_bump,
+            },
+        );
+        env.treasury_token_a = env.h.create_token_account(&mint_a, &treasury, 0);
+        env.treasury_token_b = env.h.create_token_account(&mint_b, &treasury, 0);
+        env.vault_token_a = env.h.create_token_account(&mint_a, &vault, 0);
+        env.vault_token_b = env.h.create_token_account(&mint_b, &vault, 0);
+        let position_mint = Pubkey::new_unique();
+        env.position = personal_position(&position_mint);
+        env.position_nft = get_associated_token_address(&vault, &position_mint);
+        let mut accounts = vault_manager::accounts::InitializeVault {
+            vault,
+            protocol_config,
+            vault_registry: Pubkey::find_program_address(
+                &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+                &vault_manager::ID,
+            )
+            .0,
+            pool,
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            vault_token_a: env.vault_token_a,
+            vault_token_b: env.vault_token_b,
+            share_mint,
+            position: env.position,
+            position_mint,
+            position_token_account: env.position_nft,
+            pool_token_vault_a: token_vault_a,
+            pool_token_vault_b: token_vault_b,
+            authority,
+            payer,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            dex_program: mock_raydium::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None);
+        // The new NFT mint signs for its creation
+        signer(&mut accounts, &position_mint);
+        // The mock creates no NFT metadata, any accounts will do
+        accounts.extend([
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(env.protocol_position(-600, 600), false),
+            AccountMeta::new(env.tick_array(-600), false),
+            AccountMeta::new(env.tick_array(600), false),
+        ]);
+        let initialize = Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::InitializeVault {
+                dex: Dex::RaydiumClmm,
+                tick_lower: -600,
+                tick_upper: 600,
+            }
+            .data(),
+        };
+        env.h.process(&initialize).unwrap();
+        env
+    }
+
+    /// Opens a position over `[tick_lower, tick_upper)` for `owner` through the
+    /// mock, adding `liquidity` from `payer`'s tokens. Returns the position and
+    /// its NFT account.
+    fn open_position(
+        &mut self,
+        payer: &User,
+        owner: Pubkey,
+        (tick_lower, tick_upper): (i32, i32),
+        liquidity: u128,
+    ) -> (Pubkey, Pubkey) {
+        let mint = Pubkey::new_unique();
+        let position = personal_position(&mint);
+        let nft_account = get_associated_token_address(&owner, &mint);
+        let mut accounts = mock_raydium::accounts::OpenPosition {
+            payer: payer.wallet,
+            position_nft_owner: owner,
+            position_nft_mint: mint,
+            position_nft_account: nft_account,
+            metadata_account: Pubkey::new_unique(),
+            pool_state: self.pool,
+            protocol_position: self.protocol_position(tick_lower, tick_upper),
+            tick_array_lower: self.tick_array(tick_lower),
+            tick_array_upper: self.tick_array(tick_upper),
+            personal_position: position,
+            token_account_0: payer.token_a,
+            token_account_1: payer.token_b,
+            token_vault_0: self.token_vault_a,
+            token_vault_1: self.token_vault_b,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            metadata_program: Pubkey::new_unique(),
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &mint);
+        let open = Instruction {
+            program_id: mock_raydium::ID,
+            accounts,
+            data: mock_raydium::instruction::OpenPosition {
+                tick_lower_index: tick_lower,
+                tick_upper_index: tick_upper,
+                tick_array_lower_start_index: start_index(tick_lower),
+                tick_array_upper_start_index: start_index(tick_upper),
+                liquidity,
+                amount_0_max: u64::MAX,
+                amount_1_max: u64::MAX,
+            }
+            .data(),
+        };
+        self.h.process(&open).unwrap();
+        (position, nft_account)
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        tick_array(&self.pool, start_index(tick_index))
+    }
+
+    /// Address the real program gives the range's protocol position, which the
+    /// mock does not keep.
+    fn protocol_position(&self, tick_lower: i32, tick_upper: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"position",
+                self.pool.as_ref(),
+                &tick_lower.to_be_bytes(),
+                &tick_upper.to_be_bytes(),
+            ],
+            &mock_raydium::ID,
+        )
+        .0
+    }
+
+    fn pool_state(&self) -> PoolState {
+        self.h.anchor_account(&self.pool)
+    }
+
+    fn create_user(&mut self) -> User {
+        let wallet = self.h.create_wallet();
+        User {
+            wallet,
+            token_a: self
+                .h
+                .create_token_account(&self.mint_a, &wallet, USER_BALANCE),
+            token_b: self
+                .h
+                .create_token_account(&self.mint_b, &wallet, USER_BALANCE),
+            shares: self.h.create_token_account(&self.share_mint, &wallet, 0),
+        }
+    }
+
+    /// Account set of `position`, whose NFT is held in `nft_account`.
+    fn position_metas_of(&self, position: Pubkey, nft_account: Pubkey) -> Vec<AccountMeta> {
+        let state: PersonalPositionState = self.h.anchor_account(&position);
+        self.range_metas(nft_account, state.tick_lower_index, state.tick_upper_index)
+    }
+
+    /// Account set of a position over `[tick_lower, tick_upper)`.
+    fn range_metas(
+        &self,
+        nft_account: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(nft_account, false),
+            AccountMeta::new(self.protocol_position(tick_lower, tick_upper), false),
+            AccountMeta::new(self.tick_array(tick_lower), false),
+            AccountMeta::new(self.tick_array(tick_upper), false),
+        ]
+    }
+
+    fn position_metas(&self) -> Vec<AccountMeta> {
+        self.position_metas_of(self.position, self.position_nft)
+    }
+
+    /// The swap account set, for a swap in direction `a_to_b` from the current price.
+    fn swap_metas(&self, a_to_b: bool) -> Vec<AccountMeta> {
+        let current = self.pool_state().tick_current;
+        let step = i32::from(TICK_SPACING) * mock_raydium::state::TICK_ARRAY_SIZE;
+        let step = if a_to_b { -step } else { step };
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.amm_config, false),
+            AccountMeta::new(self.observation_state, false),
+        ];
+        metas.extend((0..3).map(|i| AccountMeta::new(self.tick_array(current + i * step), false)));
+        metas
+    }
+
+    /// Exact-input swap of `amount` by `user` directly through the pool.
+    fn swap_ix(&self, user: &User, a_to_b: bool, amount: u64) -> Instruction {
+        let (input_token_account, output_token_account, input_vault, output_vault) = if a_to_b {
+            (
+                user.token_a,
+                user.token_b,
+                self.token_vault_a,
+                self.token_vault_b,
+            )
+        } else {
+            (
+                user.token_b,
+                user.token_a,
+                self.token_vault_b,
+                self.token_vault_a,
+            )
+        };
+        let tick_arrays = self.swap_metas(a_to_b);
+        let accounts = mock_raydium::accounts::Swap {
+            payer: user.wallet,
+            amm_config: self.amm_config,
+            pool_state: self.pool,
+            input_token_account,
+            output_token_account,
+            input_vault,
+            output_vault,
+            observation_state: self.observation_state,
+            token_program: spl_token::ID,
+            tick_array: tick_arrays[2].pubkey,
+        };
+        Instruction {
+            program_id: mock_raydium::ID,
+            accounts: [accounts.to_account_metas(None), tick_arrays[3..].to_vec()].concat(),
+            data: mock_raydium::instruction::Swap {
+                amount,
+                other_amount_threshold: 0,
+                sqrt_price_limit_x64: 0,
+                is_base_input: true,
+            }
+            .data(),
+        }
+    }
+
+    fn protocol_config_key(&self) -> Pubkey {
+        Pubkey::find_program_address(&[vault_manager::PROTOCOL_CONFIG_SEED], &vault_manager::ID).0
+    }
+
+    fn user_position_key(&self, user: &User) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::USER_POSITION_SEED,
+                self.vault.as_ref(),
+                user.wallet.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    /// NAV as `get_nav` returns it.
+    fn nav(&mut self) -> VaultNav {
+        let accounts = vault_manager::accounts::GetNav {
+            vault: self.vault,
+            pool: self.pool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            token_program: spl_token::ID,
+            dex_program: mock_raydium::ID,
+        };
+        let get_nav = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::GetNav {}.data(),
+        };
+        self.h.process(&get_nav).unwrap();
+        let (program_id, data) = self.h.return_data().unwrap();
+        assert_eq!(*program_id, vault_manager::ID);
+        VaultNav::try_from_slice(data).unwrap()
+    }
+
+    /// Deposit of `amount` token B, half of it swapped to A through the pool.
+    fn deposit_ix(&self, user: &User, amount: u64) -> Instruction {
+        let accounts = vault_manager::accounts::DepositAndAddLiquidity {
+            vault: self.vault,
+            user: user.wallet,
+            user_token_account: user.token_b,
+            user_share_account: user.shares,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.pool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: mock_raydium::ID,
+            allowlist_entry: None,
+            user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(),
+                self.swap_metas(false),
+            ]
+            .concat(),
+            data: vault_manager::instruction::DepositAndAddLiquidity {
+                amount,
+                min_swap_out: 0,
+                min_shares_out: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Withdrawal of `shares`, paying out at least `min_amount_a` of token A.
+    fn withdraw_ix(&self, user: &User, shares: u64, min_amount_a: u64) -> Instruction {
+        let accounts = vault_manager::accounts::Withdraw {
+            vault: self.vault,
+            user: user.wallet,
+            user_share_account: user.shares,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.pool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            dex_program: mock_raydium::ID,
+            user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config_key(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::Withdraw {
+                shares,
+                min_amount_a,
+                min_amount_b: 0,
+            }
+            .data(),
+        }
+    }
+
+    /// Rebalance into a new position over `[tick_lower, tick_upper)`, paid for
+    /// by `payer`. Returns the instruction, the new position and its NFT account.
+    fn rebalance_ix(
+        &self,
+        payer: &User,
+        (tick_lower, tick_upper): (i32, i32),
+    ) -> (Instruction, (Pubkey, Pubkey)) {
+        let new_position_mint = Pubkey::new_unique();
+        let new_position = personal_position(&new_position_mint);
+        let nft_account = get_associated_token_address(&self.vault, &new_position_mint);
+        let mut accounts = vault_manager::accounts::Rebalance {
+            vault: self.vault,
+            authority: self.authority,
+            payer: payer.wallet,
+            pool: self.pool,
+            position: self.position,
+            share_mint: self.share_mint,
+            new_position,
+            new_position_mint,
+            new_position_token_account: nft_account,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: None,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::ID,
+            dex_program: mock_raydium::ID,
+            protocol_config: self.protocol_config_key(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &new_position_mint);
+        let position: PersonalPositionState = self.h.anchor_account(&self.position);
+        let mut position_metas = self.position_metas();
+        // Closing burns the NFT
+        position_metas[0].is_writable = true;
+        // The mock creates no NFT metadata, any accounts will do
+        let open_metas = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(self.protocol_position(tick_lower, tick_upper), false),
+            AccountMeta::new(self.tick_array(tick_lower), false),
+            AccountMeta::new(self.tick_array(tick_upper), false),
+        ];
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts,
+                position_metas,
+                self.swap_metas(self.pool_state().tick_current >= 0),
+                open_metas,
+                self.range_metas(nft_account, tick_lower, tick_upper),
+                vec![AccountMeta::new(position.nft_mint, false)],
+            ]
+            .concat(),
+            data: vault_manager::instruction::Rebalance {
+                tick_lower,
+                tick_upper,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                min_swap_out: 0,
+            }
+            .data(),
+        };
+        (instruction, (new_position, nft_account))
+    }
+}
+
+fn start_index(tick_index: i32) -> i32 {
+    TickArrayState::start_index_of(tick_index, TICK_SPACING)
+}
+
+fn tick_array(pool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            pool.as_ref(),
+            &start_tick_index.to_be_bytes(),
+        ],
+        &mock_raydium::ID,
+    )
+    .0
+}
+
+fn personal_position(nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"position", nft_mint.as_ref()], &mock_raydium::ID).0
+}
+
+fn signer(metas: &mut [AccountMeta], key: &Pubkey) {
+    for meta in metas.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = true;
+    }
+}
+
+/// The error an Anchor program fails with for `error`.
+fn program_error(error: impl Into<anchor_lang::error::Error>) -> ProgramError {
+    error.into().into()
+}
+
+#[test]
+fn deposit_and_withdraw_round_trip_through_the_raydium_pool() {
+    let mut env = Env::new();
+    assert_eq!(env.h.token_balance(&env.position_nft), 1);
+    let user = env.create_user();
+    let amount = 10_000_000;
+    env.h.process(&env.deposit_ix(&user, amount)).unwrap();
+
+    let position: PersonalPositionState = env.h.anchor_account(&env.position);
+    assert!(position.liquidity > 0);
+    assert!(
+        env.h
+            .anchor_account::<vault_manager::Vault>(&env.vault)
+            .is_active
+    );
+    let nav = env.nav();
+    assert!(nav.position_a > 0 && nav.position_b > 0);
+    // Only the zap swap's fee and price impact are lost
+    assert!(nav.total_value_b * 100 >= u128::from(amount) * 99);
+
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares, 0)).unwrap();
+    assert_eq!(env.h.mint_supply(&env.share_mint), 0);
+    let returned = env.h.token_balance(&user.token_a) + env.h.token_balance(&user.token_b);
+    assert!(returned >= 2 * USER_BALANCE - amount / 100);
+    let position: PersonalPositionState = env.h.anchor_account(&env.position);
+    assert_eq!(position.liquidity, 0);
+}
+
+#[test]
+fn nav_reads_fees_from_the_tick_arrays_and_rebalance_collects_them() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h.process(&env.deposit_ix(&user, 10_000_000)).unwrap();
+    let trader = env.create_user();
+    for a_to_b in [true, false, true, false] {
+        env.h
+            .process(&env.swap_ix(&trader, a_to_b, 50_000_000))
+            .unwrap();
+    }
+
+    // The mock only credits fees to the position when it is touched, so the
+    // vault's figures come from the growth in the pool and tick arrays
+    let position: PersonalPositionState = env.h.anchor_account(&env.position);
+    assert_eq!(
+        (position.token_fees_owed_0, position.token_fees_owed_1),
+        (0, 0)
+    );
+    let nav = env.nav();
+    assert!(nav.fees_owed_a > 0 && nav.fees_owed_b > 0);
+
+    let payer = env.create_user();
+    let vault_lamports = env.h.account(&env.vault).unwrap().lamports;
+    let (rebalance, new_position) = env.rebalance_ix(&payer, (-1200, 1200));
+    env.h.process(&rebalance).unwrap();
+    // Fees are collected before the emptied position is closed, and Raydium
+    // pays its rent to the vault
+    assert!(env.h.account(&env.position).is_none());
+    assert!(env.h.account(&env.position_nft).is_none());
+    assert!(env.h.account(&env.vault).unwrap().lamports > vault_lamports);
+    env.position = new_position.0;
+    env.position_nft = new_position.1;
+    let after = env.nav();
+    assert!(after.total_value_b * 1_000 >= nav.total_value_b * 995);
+    assert!(after.position_a > 0 && after.position_b > 0);
+}
+
+#[test]
+fn twap_guard_reads_the_pool_observations() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    // The deposit's swap starts the observation series
+    env.h.process(&env.deposit_ix(&user, 10_000_000)).unwrap();
+    let set_twap_guard = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::SetTwapGuard {
+            vault: env.vault,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetTwapGuard {
+            window_secs: 600,
+            max_deviation_ticks: 100,
+        }
+        .data(),
+    };
+    env.h.process(&set_twap_guard).unwrap();
+    let payer = env.create_user();
+    let (rebalance, _) = env.rebalance_ix(&payer, (-1200, 1200));
+    assert_eq!(
+        env.h.process(&rebalance),
+        Err(program_error(VaultError::InsufficientOracleHistory))
+    );
+
+    // A large trade after ten quiet minutes moves the spot tick away from the TWAP
+    env.h.advance_clock(1_500, 600);
+    let tick_before = env.pool_state().tick_current;
+    let trader = env.create_user();
+    env.h
+        .process(&env.swap_ix(&trader, false, 150_000_000))
+        .unwrap();
+    assert!(env.pool_state().tick_current - tick_before > 100);
+    let (rebalance, _) = env.rebalance_ix(&payer, (-1200, 1200));
+    assert_eq!(
+        env.h.process(&rebalance),
+        Err(program_error(VaultError::TwapDeviation))
+    );
+
+    // Once the new price has held for the window, the TWAP has caught up
+    env.h.advance_clock(1_500, 600);
+    let (rebalance, new_position) = env.rebalance_ix(&payer, (-1200, 1200));
+    env.h.process(&rebalance).unwrap();
+    assert_eq!(
+        env.h
+            .anchor_account::<vault_manager::Vault>(&env.vault)
+            .position,
+        new_position.0
+    );
+}
+
+#[test]
+fn withdrawal_minimums_reach_the_raydium_program() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h.process(&env.deposit_ix(&user, 10_000_000)).unwrap();
+    let shares = env.h.token_balance(&user.shares);
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&user, shares, 10_000_000)),
+        Err(program_error(MockRaydiumError::PriceSlippageCheck))
+    );
+    env.h
+        .process(&env.withdraw_ix(&user, shares, 4_000_000))
+        .unwrap();
+}
@@ -0,0 +1,371 @@
+//! Zap swap routing through the mock swap program.
+//!
+//! The route instruction is built and checked by the vault's route code; its
+//! effect on the vault token accounts is applied with the mock's `quote`, the
+//! pricing rule its `swap` instruction runs on chain.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::{Harness, TestAccount};
+use mock_swap::SwapPool;
+use vault_manager::dex::{BinStrategy, Dex};
+use vault_manager::route::{self, RouteAccounts, SwapBalances, MAX_SWAP_PROGRAMS};
+use vault_manager::{Vault, VaultConfig, VaultError, VaultFees, VaultStrategy};
+
+/// 1.5 token B per token A, Q64.64.
+const PRICE_X64: u128 = 3 << 63;
+const FEE_BPS: u16 = 30;
+
+struct Env {
+    h: Harness,
+    authority: Pubkey,
+    vault: Pubkey,
+    vault_token_a: Pubkey,
+    vault_token_b: Pubkey,
+    pool: Pubkey,
+    reserve_a: Pubkey,
+    reserve_b: Pubkey,
+}
+
+impl Env {
+    /// Vault holding 1_000_000 of each token with the mock swap program
+    /// whitelisted, and a mock swap pool with deep reserves.
+    fn new() -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_swap::ID, mock_swap::entry);
+
+        let authority = Pubkey::new_unique();
+        let mint_a = h.create_mint(&authority, 6);
+        let mint_b = h.create_mint(&authority, 6);
+
+        let (pool, bump) = Pubkey::find_program_address(
+            &[b"pool", mint_a.as_ref(), mint_b.as_ref()],
+            &mock_swap::ID,
+        );
+        let reserve_a = h.create_token_account(&mint_a, &pool, 1_000_000_000);
+        let reserve_b = h.create_token_account(&mint_b, &pool, 1_000_000_000);
+        h.set_anchor_account(
+            pool,
+            &SwapPool {
+                token_a_mint: mint_a,
+                token_b_mint: mint_b,
+                reserve_a,
+                reserve_b,
+                price_x64: PRICE_X64,
+                fee_bps: FEE_BPS,
+                bump,
+            },
+        );
+
+        let vault_pool = Pubkey::new_unique();
+        let (vault, bump) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, vault_pool.as_ref()],
+            &vault_manager::ID,
+        );
+        let vault_token_a = h.create_token_account(&mint_a, &vault, 1_000_000);
+        let vault_token_b = h.create_token_account(&mint_b, &vault, 1_000_000);
+        let mut swap_programs = [Pubkey::default(); MAX_SWAP_PROGRAMS];
+        swap_programs[0] = mock_swap::ID;
+        h.set_anchor_account(
+            vault,
+            &Vault {
+                authority,
+                dex: Dex::Whirlpool,
+                pool: vault_pool,
+                token_a_mint: mint_a,
+                token_b_mint: mint_b,
+                vault_token_a,
+                vault_token_b,
+                position: Pubkey::new_unique(),
+                share_mint: Pubkey::new_unique(),
+                config: VaultConfig::default(),
+                strategy: VaultStrategy {
+                    bin_strategy: BinStrategy::Spot,
+                    swap_programs,
+                },
+                fees: VaultFees::default(),
+                last_nav_per_share_x64: 0,
+                last_nav_slot: 0,
+                paused: false,
+                token_a_decimals: 6,
+                token_b_decimals: 6,
+                bump,
+                is_active: false,
+            },
+        );
+
+        Self {
+            h,
+            authority,
+            vault,
+            vault_token_a,
+            vault_token_b,
+            pool,
+            reserve_a,
+            reserve_b,
+        }
+    }
+
+    fn vault_state(&self) -> Vault {
+        self.h.anchor_account(&self.vault)
+    }
+
+    /// Route accounts of a mock swap selling the vault's token A.
+    fn route_metas(&self) -> Vec<AccountMeta> {
+        mock_swap::accounts::Swap {
+            pool: self.pool,
+            reserve_a: self.reserve_a,
+            reserve_b: self.reserve_b,
+            user_token_in: self.vault_token_a,
+            user_token_out: self.vault_token_b,
+            user: self.vault,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    /// The swap instruction the vault would invoke for a route on `program`.
+    fn route_instruction(&self, program: Pubkey, data: Vec<u8>) -> Result<Instruction> {
+        self.route_instruction_over(program, self.route_metas(), data)
+    }
+
+    /// The swap instruction the vault would invoke for a route on `program`
+    /// over `route_metas`.
+    fn route_instruction_over(
+        &self,
+        program: Pubkey,
+        route_metas: Vec<AccountMeta>,
+        data: Vec<u8>,
+    ) -> Result<Instruction> {
+        let vault = self.vault_state();
+        // The vault PDA cannot sign the outer transaction
+        let mut metas = vec![AccountMeta::new_readonly(program, false)];
+        metas.extend(route_metas.into_iter().map(|meta| AccountMeta {
+            is_signer: false,
+            ..meta
+        }));
+        self.h.with_accounts(&metas, |infos| {
+            let route = RouteAccounts::load(&self.vault, &vault, &mut infos.iter())?;
+            Ok(route.instruction(&self.vault, data))
+        })
+    }
+
+    fn balances(&self) -> SwapBalances {
+        SwapBalances {
+            input: self.h.token_balance(&self.vault_token_a),
+            output: self.h.token_balance(&self.vault_token_b),
+        }
+    }
+
+    /// Applies a mock swap instruction's token movements.
+    fn execute(&mut self, instruction: &Instruction) -> u64 {
+        let args = mock_swap::instruction::Swap::try_from_slice(&instruction.data[8..]).unwrap();
+        let pool: SwapPool = self.h.anchor_account(&self.pool);
+        let amount_out = mock_swap::quote(&pool, args.amount_in, true).unwrap();
+        assert!(amount_out >= args.min_amount_out);
+
+        let moves = [
+            (self.vault_token_a, -i128::from(args.amount_in)),
+            (self.reserve_a, i128::from(args.amount_in)),
+            (self.reserve_b, -i128::from(amount_out)),
+            (self.vault_token_b, i128::from(amount_out)),
+        ];
+        for (key, delta) in moves {
+            self.add_balance(key, delta);
+        }
+        amount_out
+    }
+
+    fn add_balance(&mut self, key: Pubkey, delta: i128) {
+        let account = self.h.account(&key).unwrap();
+        let state = spl_token::state::Account::unpack(&account.data).unwrap();
+        let amount = u64::try_from(i128::from(state.amount) + delta).unwrap();
+        self.h
+            .set_token_account(key, &state.mint, &state.owner, amount);
+    }
+}
+
+fn swap_data(amount_in: u64, min_amount_out: u64) -> Vec<u8> {
+    mock_swap::instruction::Swap {
+        amount_in,
+        min_amount_out,
+    }
+    .data()
+}
+
+#[test]
+fn route_instruction_signs_for_the_vault_only() {
+    let env = Env::new();
+    let data = swap_data(500_000, 0);
+    let instruction = env.route_instruction(mock_swap::ID, data.clone()).unwrap();
+
+    assert_eq!(instruction.program_id, mock_swap::ID);
+    assert_eq!(instruction.data, data);
+    let expected: Vec<AccountMeta> = env
+        .route_metas()
+        .into_iter()
+        .map(|meta| AccountMeta {
+            is_signer: meta.pubkey == env.vault,
+            ..meta
+        })
+        .collect();
+    assert_eq!(instruction.accounts, expected);
+}
+
+#[test]
+fn routes_need_a_whitelisted_program() {
+    let mut env = Env::new();
+    let unlisted = Pubkey::new_unique();
+    env.h.set_account(unlisted, TestAccount::executable());
+    let err = env.route_instruction(unlisted, Vec::new()).unwrap_err();
+    assert_eq!(err, VaultError::SwapProgramNotAllowed.into());
+
+    // Unused whitelist slots do not admit the system program
+    let system_program = anchor_lang::system_program::ID;
+    let err = env
+        .route_instruction(system_program, Vec::new())
+        .unwrap_err();
+    assert_eq!(err, VaultError::SwapProgramNotAllowed.into());
+
+    // The whitelisted key must be a deployed program
+    env.h
+        .set_account(mock_swap::ID, TestAccount::new(Vec::new(), system_program));
+    let err = env
+        .route_instruction(mock_swap::ID, Vec::new())
+        .unwrap_err();
+    assert_eq!(err, VaultError::SwapProgramNotAllowed.into());
+}
+
+#[test]
+fn routed_swap_is_checked_from_vault_balances() {
+    let mut env = Env::new();
+    let deposit = 1_000_000;
+    let swap_amount = deposit / 2;
+    let quoted = mock_swap::quote(&env.h.anchor_account(&env.pool), swap_amount, true).unwrap();
+    // 500_000 A at 1.5 B per A, less the 0.3% fee
+    assert_eq!(quoted, 747_750);
+
+    let before = env.balances();
+    let instruction = env
+        .route_instruction(mock_swap::ID, swap_data(swap_amount, quoted))
+        .unwrap();
+    let amount_out = env.execute(&instruction);
+    assert_eq!(amount_out, quoted);
+    assert_eq!(
+        route::check_swap(before, env.balances(), swap_amount, quoted),
+        Ok((swap_amount, quoted))
+    );
+
+    // Asking for more than the route delivered
+    assert_eq!(
+        route::check_swap(before, env.balances(), swap_amount, quoted + 1),
+        Err(VaultError::SwapOutputBelowMinimum.into())
+    );
+}
+
+#[test]
+fn routes_cannot_spend_more_than_the_zap_amount() {
+    let mut env = Env::new();
+    let swap_amount = 500_000;
+
+    // A route built for the whole deposit rather than the half being zapped
+    let before = env.balances();
+    let instruction = env
+        .route_instruction(mock_swap::ID, swap_data(2 * swap_amount, 0))
+        .unwrap();
+    env.execute(&instruction);
+    assert_eq!(
+        route::check_swap(before, env.balances(), swap_amount, 0),
+        Err(VaultError::RouteOverspent.into())
+    );
+
+    // Nor take any of the output token
+    let before = env.balances();
+    env.add_balance(env.vault_token_b, -1);
+    assert_eq!(
+        route::check_swap(before, env.balances(), swap_amount, 0),
+        Err(VaultError::RouteOverspent.into())
+    );
+}
+
+#[test]
+fn routes_cannot_pass_other_vault_token_accounts_or_the_position() {
+    let mut env = Env::new();
+    let mint_a = env.vault_state().token_a_mint;
+    // Rewards the vault collected in token A, and an account it is a delegate of
+    let rewards = env.h.create_token_account(&mint_a, &env.vault, 1_000_000);
+    let delegated = env
+        .h
+        .create_token_account(&mint_a, &Pubkey::new_unique(), 1_000_000);
+    let mut account = env.h.account(&delegated).unwrap().clone();
+    let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+    state.delegate = Some(env.vault).into();
+    state.delegated_amount = 1_000_000;
+    state.pack_into_slice(&mut account.data);
+    env.h.set_account(delegated, account);
+
+    // The position is not a token account, but a DEX would let the vault's
+    // signature move its liquidity
+    let position = env.vault_state().position;
+    for spent in [rewards, delegated, position] {
+        let metas = env
+            .route_metas()
+            .into_iter()
+            .map(|meta| AccountMeta {
+                pubkey: if meta.pubkey == env.vault_token_a {
+                    spent
+                } else {
+                    meta.pubkey
+                },
+                ..meta
+            })
+            .collect();
+        let err = env
+            .route_instruction_over(mock_swap::ID, metas, swap_data(500_000, 0))
+            .unwrap_err();
+        assert_eq!(err, VaultError::RouteAccountNotAllowed.into());
+    }
+
+    // Token accounts of others, like the pool reserves, are fine
+    env.route_instruction(mock_swap::ID, swap_data(500_000, 0))
+        .unwrap();
+}
+
+#[test]
+fn set_swap_programs_is_authority_only_and_bounded() {
+    let mut env = Env::new();
+    let aggregator = Pubkey::new_unique();
+    let set_programs = |authority: Pubkey, programs: Vec<Pubkey>| Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::SetSwapPrograms {
+            vault: env.vault,
+            authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetSwapPrograms { programs }.data(),
+    };
+
+    let intruder = set_programs(Pubkey::new_unique(), vec![aggregator]);
+    let too_many = set_programs(env.authority, vec![aggregator; MAX_SWAP_PROGRAMS + 1]);
+    let replace = set_programs(env.authority, vec![aggregator]);
+    assert_eq!(
+        env.h.process(&intruder),
+        Err(ProgramError::Custom(VaultError::Unauthorized.into()))
+    );
+    assert_eq!(
+        env.h.process(&too_many),
+        Err(ProgramError::Custom(VaultError::TooManySwapPrograms.into()))
+    );
+
+    env.h.process(&replace).unwrap();
+    let vault = env.vault_state();
+    assert!(vault.allows_swap_program(&aggregator));
+    assert!(!vault.allows_swap_program(&mock_swap::ID));
+    assert!(!vault.allows_swap_program(&Pubkey::default()));
+}
@@ -0,0 +1,2177 @@
+//! Vault instructions end to end against the mock Whirlpool program.
+//!
+//! Every step is a real instruction: the pool is set up with the mock's
+//! instructions, and the vault's CPIs into the mock, SPL Token and the system
+//! program run in the harness.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use common::{Harness, TestAccount};
+use mock_whirlpool::state::{TickArray, Whirlpool};
+use mock_whirlpool::MockWhirlpoolError;
+use vault_manager::dex::Dex;
+use vault_manager::governance::ParamChange;
+use vault_manager::nav::VaultNav;
+use vault_manager::protocol_fee::CollectedFees;
+use vault_manager::{
+    allowlist, math, ProtocolConfig, StrategyConfig, UserPosition, Vault, VaultError, VaultRegistry,
+};
+
+const TICK_SPACING: u16 = 64;
+/// 0.3%, in hundredths of a basis point.
+const FEE_RATE: u16 = 3_000;
+const LP_LIQUIDITY: u128 = 10_000_000_000;
+const TICK_ARRAY_STARTS: [i32; 3] = [-5632, 0, 5632];
+/// Token balance depositors start with, of each token.
+const USER_BALANCE: u64 = 1_000_000_000;
+
+/// A depositor's wallet and token accounts.
+struct User {
+    wallet: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    shares: Pubkey,
+}
+
+struct Env {
+    h: Harness,
+    authority: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    whirlpool: Pubkey,
+    token_vault_a: Pubkey,
+    token_vault_b: Pubkey,
+    oracle: Pubkey,
+    vault: Pubkey,
+    vault_token_a: Pubkey,
+    vault_token_b: Pubkey,
+    share_mint: Pubkey,
+    position: Pubkey,
+    position_token_account: Pubkey,
+    /// Price account deposits and withdrawals pass as the vault's oracle.
+    price_oracle: Option<Pubkey>,
+    /// The protocol treasury's token accounts.
+    treasury_token_a: Pubkey,
+    treasury_token_b: Pubkey,
+    /// `(reward_vault, vault_reward_account)` of each initialized pool reward,
+    /// which every position account set ends with.
+    reward_metas: Vec<AccountMeta>,
+}
+
+impl Env {
+    /// Pool at tick 0 with LP liquidity over ticks [-1280, 1280), and a vault
+    /// created by `initialize_vault` over ticks [-640, 640).
+    fn new() -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_whirlpool::ID, mock_whirlpool::entry);
+        let authority = h.create_wallet();
+        let mint_a = h.create_mint(&authority, 6);
+        let mint_b = h.create_mint(&authority, 6);
+        Self::with_pool(h, authority, mint_a, mint_b)
+    }
+
+    /// A second vault like `new`'s, on a pool of the same tokens under another
+    /// config. Its accounts live in `self`'s harness, which `hand_over` passes
+    /// between the two.
+    fn second_vault(&mut self) -> Self {
+        let h = std::mem::take(&mut self.h);
+        let mut other = Self::with_pool(h, self.authority, self.mint_a, self.mint_b);
+        other.hand_over(self);
+        other
+    }
+
+    fn hand_over(&mut self, other: &mut Env) {
+        std::mem::swap(&mut self.h, &mut other.h);
+    }
+
+    fn with_pool(mut h: Harness, authority: Pubkey, mint_a: Pubkey, mint_b: Pubkey) -> Self {
+        let payer = h.create_wallet();
+        let config = Pubkey::new_unique();
+        let (whirlpool, _) = Pubkey::find_program_address(
+            &[
+                b"whirlpool",
+                config.as_ref(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+                &TICK_SPACING.to_le_bytes(),
+            ],
+            &mock_whirlpool::ID,
+        );
+        let token_vault_a = h.create_token_account(&mint_a, &whirlpool, 0);
+        let token_vault_b = h.create_token_account(&mint_b, &whirlpool, 0);
+        let (oracle, _) =
+            Pubkey::find_program_address(&[b"oracle", whirlpool.as_ref()], &mock_whirlpool::ID);
+
+        let mut setup = vec![Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::InitializePool {
+                whirlpools_config: config,
+                token_mint_a: mint_a,
+                token_mint_b: mint_b,
+                funder: payer,
+                whirlpool,
+                token_vault_a,
+                token_vault_b,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_whirlpool::instruction::InitializePool {
+                tick_spacing: TICK_SPACING,
+                fee_rate: FEE_RATE,
+                initial_sqrt_price: 1 << 64,
+            }
+            .data(),
+        }];
+        for start_tick_index in TICK_ARRAY_STARTS {
+            setup.push(Instruction {
+                program_id: mock_whirlpool::ID,
+                accounts: mock_whirlpool::accounts::InitializeTickArray {
+                    whirlpool,
+                    funder: payer,
+                    tick_array: tick_array(&whirlpool, start_tick_index),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: mock_whirlpool::instruction::InitializeTickArray { start_tick_index }.data(),
+            });
+        }
+        setup.push(Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::InitializeOracle {
+                whirlpool,
+                funder: payer,
+                oracle,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_whirlpool::instruction::InitializeOracle {}.data(),
+        });
+        h.process_transaction(&setup).unwrap();
+
+        let (vault, _) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, whirlpool.as_ref()],
+            &vault_manager::ID,
+        );
+        let (share_mint, _) = Pubkey::find_program_address(
+            &[vault_manager::SHARE_MINT_SEED, vault.as_ref()],
+            &vault_manager::ID,
+        );
+        let mut env = Self {
+            h,
+            authority,
+            mint_a,
+            mint_b,
+            whirlpool,
+            token_vault_a,
+            token_vault_b,
+            oracle,
+            vault,
+            vault_token_a: Pubkey::default(),
+            vault_token_b: Pubkey::default(),
+            share_mint,
+            position: Pubkey::default(),
+            position_token_account: Pubkey::default(),
+            price_oracle: None,
+            treasury_token_a: Pubkey::default(),
+            treasury_token_b: Pubkey::default(),
+            reward_metas: Vec::new(),
+        };
+
+        let lp = env.h.create_wallet();
+        let lp_token_a = env.h.create_token_account(&mint_a, &lp, u64::MAX / 2);
+        let lp_token_b = env.h.create_token_account(&mint_b, &lp, u64::MAX / 2);
+        let (lp_position, lp_nft) = env.open_position(lp, lp, -1280, 1280);
+        let increase = env.modify_liquidity_ix(
+            lp,
+            lp_position,
+            lp_nft,
+            (lp_token_a, lp_token_b),
+            mock_whirlpool::instruction::IncreaseLiquidity {
+                liquidity_amount: LP_LIQUIDITY,
+                token_max_a: u64::MAX,
+                token_max_b: u64::MAX,
+            }
+            .data(),
+        );
+        env.h.process(&increase).unwrap();
+
+        let (protocol_config, protocol_bump) = Pubkey::find_program_address(
+            &[vault_manager::PROTOCOL_CONFIG_SEED],
+            &vault_manager::ID,
+        );
+        // `initialize_protocol_config` needs the program's upgrade authority
+        if env.h.account(&protocol_config).is_none() {
+            let treasury = env.h.create_wallet();
+            env.h.set_anchor_account(
+                protocol_config,
+                &ProtocolConfig {
+                    admin: authority,
+                    fee_share_bps: 0,
+                    treasury,
+                    dex_programs: [
+                        mock_whirlpool::ID,
+                        Pubkey::default(),
+                        Pubkey::default(),
+                        Pubkey::default(),
+                    ],
+                    vault_count: 0,
+                    bump: protocol_bump,
+                },
+            );
+        }
+        let treasury = env.protocol_config().treasury;
+        env.treasury_token_a = env.h.create_token_account(&mint_a, &treasury, 0);
+        env.treasury_token_b = env.h.create_token_account(&mint_b, &treasury, 0);
+        env.vault_token_a = env.h.create_token_account(&mint_a, &vault, 0);
+        env.vault_token_b = env.h.create_token_account(&mint_b, &vault, 0);
+        let position_mint = Pubkey::new_unique();
+        env.position = Pubkey::find_program_address(
+            &[b"position", position_mint.as_ref()],
+            &mock_whirlpool::ID,
+        )
+        .0;
+        env.position_token_account = get_associated_token_address(&vault, &position_mint);
+        let mut accounts = vault_manager::accounts::InitializeVault {
+            vault,
+            protocol_config,
+            vault_registry: Pubkey::find_program_address(
+                &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+                &vault_manager::ID,
+            )
+            .0,
+            pool: whirlpool,
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            vault_token_a: env.vault_token_a,
+            vault_token_b: env.vault_token_b,
+            share_mint,
+            position: env.position,
+            position_mint,
+            position_token_account: env.position_token_account,
+            pool_token_vault_a: token_vault_a,
+            pool_token_vault_b: token_vault_b,
+            authority,
+            payer,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            dex_program: mock_whirlpool::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None);
+        // The new NFT mint signs for its creation
+        signer(&mut accounts, &position_mint);
+        let initialize = Instruction {
+            program_id: vault_manager::ID,
+            accounts,
+            data: vault_manager::instruction::InitializeVault {
+                dex: Dex::Whirlpool,
+                tick_lower: -640,
+                tick_upper: 640,
+            }
+            .data(),
+        };
+        env.h.process(&initialize).unwrap();
+        env
+    }
+
+    /// Opens a position of `owner`, paid for by `funder`, through the mock.
+    /// Returns the position and its NFT account.
+    fn open_position(
+        &mut self,
+        funder: Pubkey,
+        owner: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> (Pubkey, Pubkey) {
+        let mint = Pubkey::new_unique();
+        let (position, position_bump) =
+            Pubkey::find_program_address(&[b"position", mint.as_ref()], &mock_whirlpool::ID);
+        let nft_account = get_associated_token_address(&owner, &mint);
+        let mut accounts = mock_whirlpool::accounts::OpenPosition {
+            funder,
+            owner,
+            position,
+            position_mint: mint,
+            position_token_account: nft_account,
+            whirlpool: self.whirlpool,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &mint);
+        let open = Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts,
+            data: mock_whirlpool::instruction::OpenPosition {
+                _bumps: mock_whirlpool::PositionBumps { position_bump },
+                tick_lower_index: tick_lower,
+                tick_upper_index: tick_upper,
+            }
+            .data(),
+        };
+        self.h.process(&open).unwrap();
+        (position, nft_account)
+    }
+
+    fn modify_liquidity_ix(
+        &self,
+        owner: Pubkey,
+        position: Pubkey,
+        nft_account: Pubkey,
+        (token_a, token_b): (Pubkey, Pubkey),
+        data: Vec<u8>,
+    ) -> Instruction {
+        let state: mock_whirlpool::state::Position = self.h.anchor_account(&position);
+        Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::ModifyLiquidity {
+                whirlpool: self.whirlpool,
+                token_program: spl_token::ID,
+                position_authority: owner,
+                position,
+                position_token_account: nft_account,
+                token_owner_account_a: token_a,
+                token_owner_account_b: token_b,
+                token_vault_a: self.token_vault_a,
+                token_vault_b: self.token_vault_b,
+                tick_array_lower: self.tick_array(state.tick_lower_index),
+                tick_array_upper: self.tick_array(state.tick_upper_index),
+            }
+            .to_account_metas(None),
+            data,
+        }
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        tick_array(
+            &self.whirlpool,
+            TickArray::start_index_of(tick_index, TICK_SPACING),
+        )
+    }
+
+    fn pool(&self) -> Whirlpool {
+        self.h.anchor_account(&self.whirlpool)
+    }
+
+    fn protocol_config_key(&self) -> Pubkey {
+        Pubkey::find_program_address(&[vault_manager::PROTOCOL_CONFIG_SEED], &vault_manager::ID).0
+    }
+
+    fn protocol_config(&self) -> ProtocolConfig {
+        self.h.anchor_account(&self.protocol_config_key())
+    }
+
+    fn vault_state(&self) -> Vault {
+        self.h.anchor_account(&self.vault)
+    }
+
+    fn user_position_key(&self, user: &User) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::USER_POSITION_SEED,
+                self.vault.as_ref(),
+                user.wallet.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn user_position(&self, user: &User) -> UserPosition {
+        self.h.anchor_account(&self.user_position_key(user))
+    }
+
+    /// Wallet with `USER_BALANCE` of each token and an empty share account.
+    fn create_user(&mut self) -> User {
+        let wallet = self.h.create_wallet();
+        User {
+            wallet,
+            token_a: self
+                .h
+                .create_token_account(&self.mint_a, &wallet, USER_BALANCE),
+            token_b: self
+                .h
+                .create_token_account(&self.mint_b, &wallet, USER_BALANCE),
+            shares: self.h.create_token_account(&self.share_mint, &wallet, 0),
+        }
+    }
+
+    /// The vault position's account set.
+    fn position_metas(&self) -> Vec<AccountMeta> {
+        self.position_metas_of(self.position, self.position_token_account)
+    }
+
+    /// Account set of `position`, whose NFT is held in `nft_account`.
+    fn position_metas_of(&self, position: Pubkey, nft_account: Pubkey) -> Vec<AccountMeta> {
+        let state: mock_whirlpool::state::Position = self.h.anchor_account(&position);
+        self.range_metas(nft_account, state.tick_lower_index, state.tick_upper_index)
+    }
+
+    /// Account set of a position over `[tick_lower, tick_upper)`.
+    fn range_metas(
+        &self,
+        nft_account: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Vec<AccountMeta> {
+        [
+            vec![
+                AccountMeta::new_readonly(nft_account, false),
+                AccountMeta::new(self.tick_array(tick_lower), false),
+                AccountMeta::new(self.tick_array(tick_upper), false),
+            ],
+            self.reward_metas.clone(),
+        ]
+        .concat()
+    }
+
+    /// Starts the pool emitting `emissions_per_second` of `mint` through the
+    /// mock, from a deep reward vault. The vault collects it into
+    /// `vault_reward_account`.
+    fn initialize_reward(
+        &mut self,
+        mint: Pubkey,
+        emissions_per_second: u64,
+        vault_reward_account: Pubkey,
+    ) {
+        let reward_vault = self
+            .h
+            .create_token_account(&mint, &self.whirlpool, u64::MAX / 2);
+        let initialize = Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: mock_whirlpool::accounts::InitializeReward {
+                whirlpool: self.whirlpool,
+                reward_authority: self.authority,
+                reward_mint: mint,
+                reward_vault,
+            }
+            .to_account_metas(None),
+            data: mock_whirlpool::instruction::InitializeReward {
+                reward_index: (self.reward_metas.len() / 2) as u8,
+                emissions_per_second_x64: u128::from(emissions_per_second) << 64,
+            }
+            .data(),
+        };
+        self.h.process(&initialize).unwrap();
+        self.reward_metas.extend([
+            AccountMeta::new(reward_vault, false),
+            AccountMeta::new(vault_reward_account, false),
+        ]);
+    }
+
+    /// The swap account set, for a swap in direction `a_to_b` from the current price.
+    fn swap_metas(&self, a_to_b: bool) -> Vec<AccountMeta> {
+        self.swap_metas_from(self.pool().tick_current_index, a_to_b)
+    }
+
+    /// The swap account set, for a swap in direction `a_to_b` from tick `current`.
+    fn swap_metas_from(&self, current: i32, a_to_b: bool) -> Vec<AccountMeta> {
+        let step = i32::from(TICK_SPACING) * mock_whirlpool::state::TICK_ARRAY_SIZE;
+        let step = if a_to_b { -step } else { step };
+        let mut metas: Vec<_> = (0..3)
+            .map(|i| AccountMeta::new(self.tick_array(current + i * step), false))
+            .collect();
+        metas.push(AccountMeta::new(self.oracle, false));
+        metas
+    }
+
+    /// Creates `feed` and makes it the vault's oracle through `set_price_oracle`.
+    fn set_price_oracle(
+        &mut self,
+        feed: TestAccount,
+        max_deviation_bps: u16,
+        max_staleness_secs: u32,
+    ) -> std::result::Result<Pubkey, ProgramError> {
+        let key = Pubkey::new_unique();
+        self.h.set_account(key, feed);
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetPriceOracle {
+                vault: self.vault,
+                authority: self.authority,
+                price_oracle: Some(key),
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetPriceOracle {
+                max_deviation_bps,
+                max_staleness_secs,
+            }
+            .data(),
+        };
+        self.h.process(&instruction)?;
+        self.price_oracle = Some(key);
+        Ok(key)
+    }
+
+    fn strategy_config_key(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[vault_manager::STRATEGY_CONFIG_SEED, self.vault.as_ref()],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn set_strategy_config_ix(&self, range_width: u32, rebalance_threshold: u32) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetStrategyConfig {
+                vault: self.vault,
+                authority: self.authority,
+                payer: self.authority,
+                strategy_config: self.strategy_config_key(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetStrategyConfig {
+                range_width,
+                rebalance_threshold,
+            }
+            .data(),
+        }
+    }
+
+    fn pending_change_key(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[vault_manager::PARAM_CHANGE_SEED, self.vault.as_ref()],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    fn queue_param_change_ix(
+        &self,
+        change: ParamChange,
+        price_oracle: Option<Pubkey>,
+    ) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::QueueParamChange {
+                vault: self.vault,
+                authority: self.authority,
+                payer: self.authority,
+                pending_change: self.pending_change_key(),
+                system_program: system_program::ID,
+                price_oracle,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::QueueParamChange { change }.data(),
+        }
+    }
+
+    /// `execute_param_change` paid for by `payer`, passing the strategy config
+    /// account when `with_strategy_config`.
+    fn execute_param_change_ix(&self, payer: Pubkey, with_strategy_config: bool) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::ExecuteParamChange {
+                vault: self.vault,
+                authority: self.authority,
+                payer,
+                pending_change: self.pending_change_key(),
+                strategy_config: with_strategy_config.then(|| self.strategy_config_key()),
+                system_program: with_strategy_config.then_some(system_program::ID),
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::ExecuteParamChange {}.data(),
+        }
+    }
+
+    /// Rebalance into a new position over `[tick_lower, tick_upper)`, paid for
+    /// by `payer`. Returns the instruction, the new position and its NFT account.
+    fn rebalance_ix(
+        &self,
+        payer: Pubkey,
+        (tick_lower, tick_upper): (i32, i32),
+        min_amount_a: u64,
+        min_amount_b: u64,
+        min_swap_out: u64,
+    ) -> (Instruction, (Pubkey, Pubkey)) {
+        let new_position_mint = Pubkey::new_unique();
+        let new_position = Pubkey::find_program_address(
+            &[b"position", new_position_mint.as_ref()],
+            &mock_whirlpool::ID,
+        )
+        .0;
+        let nft_account = get_associated_token_address(&self.vault, &new_position_mint);
+        let mut accounts = vault_manager::accounts::Rebalance {
+            vault: self.vault,
+            authority: self.authority,
+            payer,
+            pool: self.whirlpool,
+            position: self.position,
+            share_mint: self.share_mint,
+            new_position,
+            new_position_mint,
+            new_position_token_account: nft_account,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: self.price_oracle,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::ID,
+            dex_program: mock_whirlpool::ID,
+            protocol_config: self.protocol_config_key(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        }
+        .to_account_metas(None);
+        signer(&mut accounts, &new_position_mint);
+        let position: mock_whirlpool::state::Position = self.h.anchor_account(&self.position);
+        let mut position_metas = self.position_metas();
+        // Closing burns the NFT
+        position_metas[0].is_writable = true;
+        // The swap runs after the removal, from the same price
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts,
+                position_metas,
+                self.swap_metas(self.pool().tick_current_index >= 0),
+                self.range_metas(nft_account, tick_lower, tick_upper),
+                vec![AccountMeta::new(position.position_mint, false)],
+            ]
+            .concat(),
+            data: vault_manager::instruction::Rebalance {
+                tick_lower,
+                tick_upper,
+                min_amount_a,
+                min_amount_b,
+                min_swap_out,
+            }
+            .data(),
+        };
+        (instruction, (new_position, nft_account))
+    }
+
+    /// Exact-input swap of `amount` by `user` directly through the pool.
+    fn swap_ix(&self, user: &User, a_to_b: bool, amount: u64) -> Instruction {
+        let tick_arrays = self.swap_metas(a_to_b);
+        let accounts = mock_whirlpool::accounts::Swap {
+            token_program: spl_token::ID,
+            token_authority: user.wallet,
+            whirlpool: self.whirlpool,
+            token_owner_account_a: user.token_a,
+            token_vault_a: self.token_vault_a,
+            token_owner_account_b: user.token_b,
+            token_vault_b: self.token_vault_b,
+            tick_array_0: tick_arrays[0].pubkey,
+            tick_array_1: tick_arrays[1].pubkey,
+            tick_array_2: tick_arrays[2].pubkey,
+            oracle: self.oracle,
+        };
+        let sqrt_price_limit = if a_to_b {
+            mock_whirlpool::math::MIN_SQRT_PRICE_X64
+        } else {
+            mock_whirlpool::math::MAX_SQRT_PRICE_X64
+        };
+        Instruction {
+            program_id: mock_whirlpool::ID,
+            accounts: accounts.to_account_metas(None),
+            data: mock_whirlpool::instruction::Swap {
+                amount,
+                other_amount_threshold: 0,
+                sqrt_price_limit,
+                amount_specified_is_input: true,
+                a_to_b,
+            }
+            .data(),
+        }
+    }
+
+    /// NAV as `get_nav` returns it.
+    fn nav(&mut self) -> VaultNav {
+        let accounts = vault_manager::accounts::GetNav {
+            vault: self.vault,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+        };
+        let get_nav = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::GetNav {}.data(),
+        };
+        self.h.process(&get_nav).unwrap();
+        let (program_id, data) = self.h.return_data().unwrap();
+        assert_eq!(*program_id, vault_manager::ID);
+        VaultNav::try_from_slice(data).unwrap()
+    }
+
+    fn deposit_ix(&self, user: &User, deposit_a: bool, amount: u64) -> Instruction {
+        self.bounded_deposit_ix(user, deposit_a, amount, 0, 0)
+    }
+
+    fn bounded_deposit_ix(
+        &self,
+        user: &User,
+        deposit_a: bool,
+        amount: u64,
+        min_swap_out: u64,
+        min_shares_out: u64,
+    ) -> Instruction {
+        let accounts = vault_manager::accounts::DepositAndAddLiquidity {
+            vault: self.vault,
+            user: user.wallet,
+            user_token_account: if deposit_a {
+                user.token_a
+            } else {
+                user.token_b
+            },
+            user_share_account: user.shares,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: self.price_oracle,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            allowlist_entry: self.allowlist_entry(&user.wallet),
+            user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                self.position_metas(),
+                self.swap_metas(deposit_a),
+            ]
+            .concat(),
+            data: vault_manager::instruction::DepositAndAddLiquidity {
+                amount,
+                min_swap_out,
+                min_shares_out,
+            }
+            .data(),
+        }
+    }
+
+    fn withdraw_ix(&self, user: &User, shares: u64) -> Instruction {
+        self.bounded_withdraw_ix(user, shares, 0, 0)
+    }
+
+    fn bounded_withdraw_ix(
+        &self,
+        user: &User,
+        shares: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Instruction {
+        let accounts = vault_manager::accounts::Withdraw {
+            vault: self.vault,
+            user: user.wallet,
+            user_share_account: user.shares,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            share_mint: self.share_mint,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            price_oracle: self.price_oracle,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config_key(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::Withdraw {
+                shares,
+                min_amount_a,
+                min_amount_b,
+            }
+            .data(),
+        }
+    }
+
+    /// `collect` signed by `signer`, with the fees it reports.
+    fn collect(&mut self, signer: &Pubkey) -> std::result::Result<CollectedFees, ProgramError> {
+        let accounts = vault_manager::accounts::Collect {
+            vault: self.vault,
+            authority: *signer,
+            pool: self.whirlpool,
+            position: self.position,
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            protocol_config: self.protocol_config_key(),
+            treasury_token_a: self.treasury_token_a,
+            treasury_token_b: self.treasury_token_b,
+        };
+        let collect = Instruction {
+            program_id: vault_manager::ID,
+            accounts: [accounts.to_account_metas(None), self.position_metas()].concat(),
+            data: vault_manager::instruction::Collect {}.data(),
+        };
+        self.h.process(&collect)?;
+        let (program_id, data) = self.h.return_data().unwrap();
+        assert_eq!(*program_id, vault_manager::ID);
+        Ok(CollectedFees::try_from_slice(data).unwrap())
+    }
+
+    /// Migration of `shares` of `user`'s from `source` into this vault, minting
+    /// into `shares_account`. The destination swap's direction is `a_to_b`.
+    fn migrate_ix(
+        &self,
+        source: &Env,
+        user: &User,
+        shares_account: Pubkey,
+        shares: u64,
+        a_to_b: bool,
+    ) -> Instruction {
+        let accounts = vault_manager::accounts::MigrateLiquidity {
+            user: user.wallet,
+            source_vault: source.vault,
+            source_share_mint: source.share_mint,
+            user_source_share_account: user.shares,
+            source_vault_token_a: source.vault_token_a,
+            source_vault_token_b: source.vault_token_b,
+            source_pool: source.whirlpool,
+            source_position: source.position,
+            source_pool_token_vault_a: source.token_vault_a,
+            source_pool_token_vault_b: source.token_vault_b,
+            source_price_oracle: None,
+            source_dex_program: mock_whirlpool::ID,
+            source_user_position: source.user_position_key(user),
+            destination_vault: self.vault,
+            destination_share_mint: self.share_mint,
+            user_destination_share_account: shares_account,
+            destination_vault_token_a: self.vault_token_a,
+            destination_vault_token_b: self.vault_token_b,
+            destination_pool: self.whirlpool,
+            destination_position: self.position,
+            destination_pool_token_vault_a: self.token_vault_a,
+            destination_pool_token_vault_b: self.token_vault_b,
+            destination_price_oracle: None,
+            destination_dex_program: mock_whirlpool::ID,
+            destination_allowlist_entry: self.allowlist_entry(&user.wallet),
+            token_program: spl_token::ID,
+            destination_user_position: self.user_position_key(user),
+            system_program: system_program::ID,
+            protocol_config: self.protocol_config_key(),
+            treasury_token_a: source.treasury_token_a,
+            treasury_token_b: source.treasury_token_b,
+        };
+        // Both vaults' accounts are in this env's harness
+        let source_position: mock_whirlpool::state::Position =
+            self.h.anchor_account(&source.position);
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                source.range_metas(
+                    source.position_token_account,
+                    source_position.tick_lower_index,
+                    source_position.tick_upper_index,
+                ),
+                self.position_metas(),
+                self.swap_metas(a_to_b),
+            ]
+            .concat(),
+            data: vault_manager::instruction::MigrateLiquidity {
+                shares,
+                min_amount_a: 0,
+                min_amount_b: 0,
+                min_swap_out: 0,
+                min_shares_out: 0,
+            }
+            .data(),
+        }
+    }
+
+    fn allowlist_entry_key(&self, wallet: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                vault_manager::ALLOWLIST_SEED,
+                self.vault.as_ref(),
+                wallet.as_ref(),
+            ],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    /// `wallet`'s allowlist entry, if it has one.
+    fn allowlist_entry(&self, wallet: &Pubkey) -> Option<Pubkey> {
+        let key = self.allowlist_entry_key(wallet);
+        self.h.account(&key).map(|_| key)
+    }
+
+    fn set_allowlist(&mut self, enabled: bool, merkle_root: [u8; 32]) {
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetAllowlist {
+                vault: self.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetAllowlist {
+                enabled,
+                merkle_root,
+            }
+            .data(),
+        };
+        self.h.process(&instruction).unwrap();
+    }
+
+    /// `user` adding themselves to the allowlist with a Merkle `proof`.
+    fn join_allowlist_ix(&self, user: &User, proof: Vec<[u8; 32]>) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::JoinAllowlist {
+                vault: self.vault,
+                allowlist_entry: self.allowlist_entry_key(&user.wallet),
+                wallet: user.wallet,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::JoinAllowlist { proof }.data(),
+        }
+    }
+
+    fn add_to_allowlist(&mut self, wallet: Pubkey) {
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::AddToAllowlist {
+                vault: self.vault,
+                allowlist_entry: self.allowlist_entry_key(&wallet),
+                authority: self.authority,
+                payer: self.authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::AddToAllowlist { wallet }.data(),
+        };
+        self.h.process(&instruction).unwrap();
+    }
+
+    fn set_lockup(
+        &mut self,
+        lockup_slots: u64,
+        early_exit_fee_bps: u16,
+        withdrawal_delay_slots: u64,
+    ) {
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetLockup {
+                vault: self.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetLockup {
+                lockup_slots,
+                early_exit_fee_bps,
+                withdrawal_delay_slots,
+            }
+            .data(),
+        };
+        self.h.process(&instruction).unwrap();
+    }
+
+    fn request_withdrawal_ix(&self, user: &User, shares: u64) -> Instruction {
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::RequestWithdrawal {
+                vault: self.vault,
+                user: user.wallet,
+                user_position: self.user_position_key(user),
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::RequestWithdrawal { shares }.data(),
+        }
+    }
+
+    /// Moves `shares` from `from`'s share account to `to`'s.
+    fn transfer_shares(&mut self, from: &User, to: &User, shares: u64) {
+        let transfer = spl_token::instruction::transfer(
+            &spl_token::ID,
+            &from.shares,
+            &to.shares,
+            &from.wallet,
+            &[],
+            shares,
+        )
+        .unwrap();
+        self.h.process(&transfer).unwrap();
+    }
+
+    fn registry_key(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[vault_manager::VAULT_REGISTRY_SEED, &0u32.to_le_bytes()],
+            &vault_manager::ID,
+        )
+        .0
+    }
+
+    /// `close_vault`, sweeping leftovers into `authority_tokens`.
+    fn close_vault_ix(
+        &self,
+        (authority_token_a, authority_token_b): (Pubkey, Pubkey),
+    ) -> Instruction {
+        let accounts = vault_manager::accounts::CloseVault {
+            vault: self.vault,
+            authority: self.authority,
+            vault_registry: self.registry_key(),
+            vault_token_a: self.vault_token_a,
+            vault_token_b: self.vault_token_b,
+            authority_token_a,
+            authority_token_b,
+            share_mint: self.share_mint,
+            pool: self.whirlpool,
+            position: self.position,
+            pool_token_vault_a: self.token_vault_a,
+            pool_token_vault_b: self.token_vault_b,
+            token_program: spl_token::ID,
+            dex_program: mock_whirlpool::ID,
+            system_program: system_program::ID,
+        };
+        let position: mock_whirlpool::state::Position = self.h.anchor_account(&self.position);
+        Instruction {
+            program_id: vault_manager::ID,
+            accounts: [
+                accounts.to_account_metas(None),
+                // Closing burns the NFT and closes its token account
+                vec![AccountMeta::new(self.position_token_account, false)],
+                self.position_metas()[1..].to_vec(),
+                vec![AccountMeta::new(position.position_mint, false)],
+            ]
+            .concat(),
+            data: vault_manager::instruction::CloseVault {}.data(),
+        }
+    }
+
+    fn set_nav_circuit_breaker(&mut self, max_drop_bps: u16) {
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetNavCircuitBreaker {
+                vault: self.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetNavCircuitBreaker { max_drop_bps }.data(),
+        };
+        self.h.process(&instruction).unwrap();
+    }
+
+    /// Records a NAV per share `factor` times the current one, so the next
+    /// operation observes a drop.
+    fn inflate_recorded_nav(&mut self, factor: u128) {
+        let mut vault = self.vault_state();
+        vault.last_nav_per_share_x64 = self.nav().price_per_share_x64 * factor;
+        self.h.set_anchor_account(self.vault, &vault);
+    }
+
+    fn set_deposit_limits(&mut self, min_deposit: u64, user_deposit_cap: u64, deposit_cap: u64) {
+        let instruction = Instruction {
+            program_id: vault_manager::ID,
+            accounts: vault_manager::accounts::SetDepositLimits {
+                vault: self.vault,
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: vault_manager::instruction::SetDepositLimits {
+                min_deposit,
+                user_deposit_cap,
+                deposit_cap,
+            }
+            .data(),
+        };
+        self.h.process(&instruction).unwrap();
+    }
+}
+
+fn tick_array(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            whirlpool.as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        &mock_whirlpool::ID,
+    )
+    .0
+}
+
+/// Pyth v2 price account publishing `price` x 10^`expo` of token A in token B
+/// at `publish_time`, trading unless `status` says otherwise.
+fn pyth_price(price: i64, expo: i32, publish_time: i64, status: u32) -> TestAccount {
+    let mut data = vec![0; 3312];
+    let mut put = |at: usize, bytes: &[u8]| data[at..at + bytes.len()].copy_from_slice(bytes);
+    put(0, &0xa1b2_c3d4u32.to_le_bytes());
+    put(4, &2u32.to_le_bytes());
+    put(8, &3u32.to_le_bytes());
+    put(20, &expo.to_le_bytes());
+    put(96, &publish_time.to_le_bytes());
+    put(208, &price.to_le_bytes());
+    put(216, &1_000i64.to_le_bytes());
+    put(224, &status.to_le_bytes());
+    TestAccount::new(data, Pubkey::new_unique())
+}
+
+/// Pyth price status of a feed that is trading.
+const PYTH_TRADING: u32 = 1;
+
+/// Marks `key` as signing `metas`, for accounts the harness lets sign without a keypair.
+fn signer(metas: &mut [AccountMeta], key: &Pubkey) {
+    for meta in metas.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = true;
+    }
+}
+
+/// The error an Anchor program fails with for `error`.
+fn program_error(error: impl Into<anchor_lang::error::Error>) -> ProgramError {
+    error.into().into()
+}
+
+#[test]
+fn deposit_and_withdraw_round_trip_through_the_pool() {
+    let mut env = Env::new();
+    let vault = env.vault_state();
+    assert_eq!(
+        (vault.authority, vault.position),
+        (env.authority, env.position)
+    );
+    assert_eq!(env.h.token_balance(&env.position_token_account), 1);
+
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let shares = env.h.token_balance(&user.shares);
+    assert!(shares > 0);
+    assert!(env.vault_state().is_active);
+    assert_eq!(env.user_position(&user).shares, shares);
+    assert_eq!(env.h.token_balance(&user.token_b), USER_BALANCE - 1_000_000);
+
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+    assert_eq!(env.h.token_balance(&user.shares), 0);
+    assert_eq!(env.h.mint_supply(&env.share_mint), 0);
+    // The zap swap's fee and rounding are all that is lost
+    let (balance_a, balance_b) = (
+        env.h.token_balance(&user.token_a),
+        env.h.token_balance(&user.token_b),
+    );
+    let returned = (balance_a - USER_BALANCE) + (balance_b - (USER_BALANCE - 1_000_000));
+    assert!((995_000..1_000_000).contains(&returned), "{returned}");
+
+    let err = env.h.process(&env.withdraw_ix(&user, 1)).unwrap_err();
+    assert_eq!(err, program_error(VaultError::InsufficientShares));
+}
+
+#[test]
+fn deposits_are_priced_after_the_zap_swap() {
+    let first_deposit = |env: &mut Env| {
+        let first = env.create_user();
+        env.h
+            .process(&env.deposit_ix(&first, false, 1_000_000))
+            .unwrap();
+        env.h.token_balance(&first.shares)
+    };
+
+    // Zaps that move the price within the vault's range and past it
+    for amount in [10_000_000, 400_000_000] {
+        // The price move of the deposit's zap swap, made by a trader instead
+        let mut baseline = Env::new();
+        let first_shares = first_deposit(&mut baseline);
+        let trader = baseline.create_user();
+        baseline
+            .h
+            .process(&baseline.swap_ix(&trader, true, amount / 2))
+            .unwrap();
+        let baseline_value = baseline.nav().value_of_shares(first_shares).unwrap();
+
+        // A deposit large enough to move the pool pays its own fee and price
+        // impact, so the first depositor holds at least what the move leaves them
+        let mut env = Env::new();
+        let first_shares = first_deposit(&mut env);
+        let second = env.create_user();
+        env.h
+            .process(&env.deposit_ix(&second, true, amount))
+            .unwrap();
+        assert_eq!(
+            env.pool().tick_current_index,
+            baseline.pool().tick_current_index
+        );
+        let nav = env.nav();
+        assert!(nav.value_of_shares(first_shares).unwrap() >= baseline_value);
+        // Worth less than the deposit at the 1:1 price it was made at
+        let shares = env.h.token_balance(&second.shares);
+        assert!(nav.value_of_shares(shares).unwrap() < u128::from(amount));
+    }
+}
+
+#[test]
+fn deposit_and_withdraw_bounds_are_enforced() {
+    let mut env = Env::new();
+    let user = env.create_user();
+
+    // The pool's own check stops a swap paying out less than asked
+    let err = env
+        .h
+        .process(&env.bounded_deposit_ix(&user, false, 1_000_000, 500_000, 0))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        program_error(MockWhirlpoolError::AmountOutBelowMinimum)
+    );
+
+    let probe = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&probe, false, 1_000_000))
+        .unwrap();
+    let shares = env.h.token_balance(&probe.shares);
+    let err = env
+        .h
+        .process(&env.bounded_deposit_ix(&user, false, 1_000_000, 0, shares + 1))
+        .unwrap_err();
+    assert_eq!(err, program_error(VaultError::SharesBelowMinimum));
+    // The probe's swap moved the price, so the same deposit now gets a little less
+    env.h
+        .process(&env.bounded_deposit_ix(&user, false, 1_000_000, 490_000, shares - shares / 100))
+        .unwrap();
+
+    // Minimums beyond what the position pays out fail in the pool's removal
+    let shares = env.h.token_balance(&user.shares);
+    let err = env
+        .h
+        .process(&env.bounded_withdraw_ix(&user, shares, 0, 1_000_000))
+        .unwrap_err();
+    assert_eq!(err, program_error(MockWhirlpoolError::TokenMinSubceeded));
+    env.h
+        .process(&env.bounded_withdraw_ix(&user, shares, 400_000, 400_000))
+        .unwrap();
+}
+
+#[test]
+fn deposits_record_cost_basis_at_a_fresh_oracle_price() {
+    let mut env = Env::new();
+    let now = env.h.clock().unix_timestamp;
+    // 1.005 B per A, with both mints at 6 decimals
+    env.set_price_oracle(pyth_price(100_500_000, -8, now - 30, PYTH_TRADING), 100, 60)
+        .unwrap();
+
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, true, 1_000_000))
+        .unwrap();
+    // 1.005 has no exact Q64.64 form, and the value rounds down
+    assert_eq!(env.user_position(&user).cost_basis_b, 1_004_999);
+}
+
+#[test]
+fn withdrawals_update_the_receipt_pro_rata() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let deposited = env.user_position(&user);
+    assert_eq!(deposited.cost_basis_b, 1_000_000);
+    let shares = env.h.token_balance(&user.shares);
+    assert_eq!(deposited.shares, shares);
+
+    env.h.process(&env.withdraw_ix(&user, shares / 4)).unwrap();
+    let position = env.user_position(&user);
+    assert_eq!(position.shares, shares - shares / 4);
+    assert_eq!(
+        position.cost_basis_b,
+        1_000_000 - 1_000_000 * u128::from(shares / 4) / u128::from(shares)
+    );
+    assert_eq!(
+        (position.withdrawn_a, position.withdrawn_b),
+        (
+            env.h.token_balance(&user.token_a) - USER_BALANCE,
+            env.h.token_balance(&user.token_b) - (USER_BALANCE - 1_000_000)
+        )
+    );
+}
+
+#[test]
+fn shares_received_by_transfer_get_a_receipt_on_withdraw() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let shares = env.h.token_balance(&user.shares);
+    let recipient = env.create_user();
+    env.transfer_shares(&user, &recipient, shares / 2);
+
+    env.h
+        .process(&env.withdraw_ix(&recipient, shares / 2))
+        .unwrap();
+    // Shares the receipt does not track leave it at zero
+    let position = env.user_position(&recipient);
+    assert_eq!(
+        (position.vault, position.user),
+        (env.vault, recipient.wallet)
+    );
+    assert_eq!((position.shares, position.cost_basis_b), (0, 0));
+    assert!(position.withdrawn_a > 0 && position.withdrawn_b > 0);
+    assert_eq!(env.user_position(&user).shares, shares);
+}
+
+#[test]
+fn withdrawals_pay_the_protocol_its_share_of_collected_fees() {
+    let withdraw_after_trading = |fee_share_bps: u16| {
+        let mut env = Env::new();
+        let mut config = env.protocol_config();
+        config.fee_share_bps = fee_share_bps;
+        env.h.set_anchor_account(env.protocol_config_key(), &config);
+        let user = env.create_user();
+        env.h
+            .process(&env.deposit_ix(&user, false, 1_000_000))
+            .unwrap();
+        let trader = env.create_user();
+        env.h
+            .process(&env.swap_ix(&trader, true, 100_000_000))
+            .unwrap();
+        env.h
+            .process(&env.swap_ix(&trader, false, 100_000_000))
+            .unwrap();
+        let shares = env.h.token_balance(&user.shares);
+        env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+        (
+            [
+                env.h.token_balance(&user.token_a),
+                env.h.token_balance(&user.token_b),
+            ],
+            [
+                env.h.token_balance(&env.treasury_token_a),
+                env.h.token_balance(&env.treasury_token_b),
+            ],
+        )
+    };
+
+    let (untaxed, [0, 0]) = withdraw_after_trading(0) else {
+        panic!("a zero fee share pays the treasury nothing");
+    };
+    let (taxed, treasury) = withdraw_after_trading(1_000);
+    // The only holder gives up exactly what the treasury receives
+    for i in 0..2 {
+        assert!(treasury[i] > 0);
+        assert_eq!(untaxed[i] - taxed[i], treasury[i]);
+    }
+}
+
+#[test]
+fn collect_moves_position_fees_into_the_vault_less_the_protocol_share() {
+    let mut env = Env::new();
+    let mut config = env.protocol_config();
+    config.fee_share_bps = 2_500;
+    env.h.set_anchor_account(env.protocol_config_key(), &config);
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let trader = env.create_user();
+    env.h
+        .process(&env.swap_ix(&trader, true, 100_000_000))
+        .unwrap();
+    env.h
+        .process(&env.swap_ix(&trader, false, 100_000_000))
+        .unwrap();
+
+    let err = env.collect(&user.wallet).unwrap_err();
+    assert_eq!(err, program_error(VaultError::Unauthorized));
+
+    let before = [
+        env.h.token_balance(&env.vault_token_a),
+        env.h.token_balance(&env.vault_token_b),
+    ];
+    let authority = env.authority;
+    let collected = env.collect(&authority).unwrap();
+    assert!(collected.fees_a > 0 && collected.fees_b > 0);
+    assert_eq!(collected.protocol_a, collected.fees_a / 4);
+    assert_eq!(collected.protocol_b, collected.fees_b / 4);
+    assert_eq!(
+        env.h.token_balance(&env.vault_token_a),
+        before[0] + collected.fees_a - collected.protocol_a
+    );
+    assert_eq!(
+        env.h.token_balance(&env.vault_token_b),
+        before[1] + collected.fees_b - collected.protocol_b
+    );
+    assert_eq!(
+        env.h.token_balance(&env.treasury_token_a),
+        collected.protocol_a
+    );
+    assert_eq!(
+        env.h.token_balance(&env.treasury_token_b),
+        collected.protocol_b
+    );
+
+    // Nothing is left to collect until the pool trades again
+    assert_eq!(env.collect(&authority).unwrap(), CollectedFees::default());
+}
+
+#[test]
+fn collect_pays_out_rewards_which_count_toward_nav_once_collected() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let other_mint = env.h.create_mint(&env.authority, 6);
+    let other_rewards = env.h.create_token_account(&other_mint, &env.vault, 0);
+    env.initialize_reward(other_mint, 1_000, other_rewards);
+    let (mint_b, vault_token_b) = (env.mint_b, env.vault_token_b);
+    env.initialize_reward(mint_b, 1_000, vault_token_b);
+    env.h.advance_clock(250, 100);
+    // A trade brings the pool's reward growth up to date
+    let trader = env.create_user();
+    env.h.process(&env.swap_ix(&trader, true, 1_000)).unwrap();
+
+    // Owed rewards are reported, but not counted, even in token B
+    let nav = env.nav();
+    assert!(nav.rewards_owed[0] > 0 && nav.rewards_owed[1] > 0);
+    assert_eq!(nav.total_b, nav.idle_b + nav.position_b + nav.fees_owed_b);
+
+    let authority = env.authority;
+    let collected = env.collect(&authority).unwrap();
+    assert_eq!(env.h.token_balance(&other_rewards), nav.rewards_owed[0]);
+    assert_eq!(collected.fees_b, nav.fees_owed_b + nav.rewards_owed[1]);
+
+    // Collected into the vault's token B account, the token B reward counts
+    let after = env.nav();
+    assert_eq!(after.rewards_owed, [0; 3]);
+    assert_eq!(
+        after.idle_b,
+        nav.idle_b + collected.fees_b - collected.protocol_b
+    );
+}
+
+#[test]
+fn oracle_guard_rejects_missing_stale_halted_and_deviating_prices() {
+    let mut env = Env::new();
+    let now = env.h.clock().unix_timestamp;
+    let feed = env
+        .set_price_oracle(pyth_price(1_000_000, -6, now, PYTH_TRADING), 100, 60)
+        .unwrap();
+    let user = env.create_user();
+    let deposit_with = |env: &mut Env, oracle: Option<Pubkey>, price: Option<TestAccount>| {
+        if let (Some(key), Some(price)) = (oracle, price) {
+            env.h.set_account(key, price);
+        }
+        env.price_oracle = oracle;
+        env.h.process(&env.deposit_ix(&user, true, 1_000_000))
+    };
+
+    assert_eq!(
+        deposit_with(&mut env, None, None),
+        Err(program_error(VaultError::OracleRequired))
+    );
+    let other = Pubkey::new_unique();
+    let fresh = pyth_price(1_000_000, -6, now, PYTH_TRADING);
+    assert_eq!(
+        deposit_with(&mut env, Some(other), Some(fresh)),
+        Err(program_error(VaultError::InvalidOracle))
+    );
+    let stale = pyth_price(1_000_000, -6, now - 61, PYTH_TRADING);
+    assert_eq!(
+        deposit_with(&mut env, Some(feed), Some(stale)),
+        Err(program_error(VaultError::StaleOracle))
+    );
+    let halted = pyth_price(1_000_000, -6, now, 0);
+    assert_eq!(
+        deposit_with(&mut env, Some(feed), Some(halted)),
+        Err(program_error(VaultError::StaleOracle))
+    );
+    // 1.02 against a pool at 1.0
+    let deviating = pyth_price(1_020_000, -6, now, PYTH_TRADING);
+    assert_eq!(
+        deposit_with(&mut env, Some(feed), Some(deviating)),
+        Err(program_error(VaultError::OraclePriceDeviation))
+    );
+    let negative = pyth_price(-1_000_000, -6, now, PYTH_TRADING);
+    assert_eq!(
+        deposit_with(&mut env, Some(feed), Some(negative)),
+        Err(program_error(VaultError::InvalidOracle))
+    );
+    // Within 1% and 60 seconds
+    let nearby = pyth_price(1_009_000, -6, now - 60, PYTH_TRADING);
+    deposit_with(&mut env, Some(feed), Some(nearby)).unwrap();
+}
+
+#[test]
+fn set_price_oracle_rejects_accounts_without_the_pyth_price_layout() {
+    let mut env = Env::new();
+    let now = env.h.clock().unix_timestamp;
+    let fresh = pyth_price(1_000_000, -6, now, PYTH_TRADING);
+
+    let mut wrong_magic = fresh.clone();
+    wrong_magic.data[0] ^= 1;
+    let mut product_account = fresh.clone();
+    product_account.data[8] = 2;
+    let mut truncated = fresh.clone();
+    truncated.data.truncate(239);
+    for feed in [wrong_magic, product_account, truncated] {
+        assert_eq!(
+            env.set_price_oracle(feed, 100, 60),
+            Err(program_error(VaultError::InvalidOracle))
+        );
+    }
+    env.set_price_oracle(fresh, 100, 60).unwrap();
+}
+
+#[test]
+fn rebalance_moves_all_liquidity_into_the_new_range() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 10_000_000))
+        .unwrap();
+    let value_before = env.nav().total_value_b;
+
+    let payer = env.h.create_wallet();
+    // Removal minimums above what the position holds stop the rebalance
+    let (rebalance, _) = env.rebalance_ix(payer, (-1280, 1280), 10_000_000, 0, 0);
+    let err = env.h.process(&rebalance).unwrap_err();
+    assert_eq!(err, program_error(MockWhirlpoolError::TokenMinSubceeded));
+
+    let old_position = (env.position, env.position_token_account);
+    let (rebalance, new_position) = env.rebalance_ix(payer, (-1280, 1280), 4_000_000, 4_000_000, 0);
+    env.h.process(&rebalance).unwrap();
+    // The emptied position is closed and its NFT burned
+    assert!(env.h.account(&old_position.0).is_none());
+    assert!(env.h.account(&old_position.1).is_none());
+    let vault = env.vault_state();
+    assert_eq!(vault.position, new_position.0);
+    assert!(vault.is_active);
+    env.position = new_position.0;
+    env.position_token_account = new_position.1;
+    let new: mock_whirlpool::state::Position = env.h.anchor_account(&env.position);
+    assert_eq!((new.tick_lower_index, new.tick_upper_index), (-1280, 1280));
+    assert!(new.liquidity > 0);
+    // Only the swap toward the new ratio costs value, and little is left idle
+    let nav = env.nav();
+    assert!(nav.total_value_b * 1_000 >= value_before * 995);
+    assert!(u128::from(nav.idle_a + nav.idle_b) * 100 < nav.total_value_b);
+
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+    assert_eq!(env.h.mint_supply(&env.share_mint), 0);
+}
+
+#[test]
+fn twap_guard_refuses_pools_without_tick_observations() {
+    let env = Env::new();
+    let set_twap_guard = |window_secs| Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::SetTwapGuard {
+            vault: env.vault,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetTwapGuard {
+            window_secs,
+            max_deviation_ticks: 100,
+        }
+        .data(),
+    };
+    // Whirlpool oracles hold adaptive fee state, not tick history
+    assert_eq!(
+        env.h.clone().process(&set_twap_guard(600)),
+        Err(program_error(VaultError::TwapUnavailable))
+    );
+    let mut h = env.h.clone();
+    h.process(&set_twap_guard(0)).unwrap();
+}
+
+#[test]
+fn migrations_are_priced_after_the_destination_swap() {
+    let mut source = Env::new();
+    let user = source.create_user();
+    source
+        .h
+        .process(&source.deposit_ix(&user, false, 10_000_000))
+        .unwrap();
+    // Pushed down the source pool, the vault position holds mostly token A,
+    // which the destination swaps back toward its range
+    let trader = source.create_user();
+    source
+        .h
+        .process(&source.swap_ix(&trader, true, 400_000_000))
+        .unwrap();
+
+    let mut destination = source.second_vault();
+    source.hand_over(&mut destination);
+    let first = destination.create_user();
+    destination
+        .h
+        .process(&destination.deposit_ix(&first, false, 1_000_000))
+        .unwrap();
+    let migrated = User {
+        wallet: user.wallet,
+        token_a: user.token_a,
+        token_b: user.token_b,
+        shares: destination
+            .h
+            .create_token_account(&destination.share_mint, &user.wallet, 0),
+    };
+    let nav = destination.nav();
+    let sqrt_price = destination.pool().sqrt_price;
+    let shares = destination.h.token_balance(&user.shares);
+    let migrate = destination.migrate_ix(&source, &user, migrated.shares, shares, true);
+    destination.h.process(&migrate).unwrap();
+    assert_eq!(destination.h.token_balance(&user.shares), 0);
+    assert!(destination.pool().sqrt_price < sqrt_price);
+
+    // The destination swap's fee and price impact are the migrating user's
+    let position = destination.user_position(&migrated);
+    let shares_out = destination.h.token_balance(&migrated.shares);
+    assert_eq!(position.shares, shares_out);
+    let moved = math::value_in_b(position.deposited_a, position.deposited_b, sqrt_price).unwrap();
+    assert!(shares_out > 0 && shares_out < nav.shares_for_value(moved).unwrap());
+
+    // So the destination's holders keep at least what the same swap, made by
+    // a trader instead, leaves them
+    let (amount, a_to_b) = math::rebalance_swap(
+        sqrt_price,
+        math::sqrt_price_from_tick_index(-640).unwrap(),
+        math::sqrt_price_from_tick_index(640).unwrap(),
+        position.deposited_a,
+        position.deposited_b,
+    )
+    .unwrap()
+    .unwrap();
+    let mut baseline = Env::new();
+    let baseline_first = baseline.create_user();
+    baseline
+        .h
+        .process(&baseline.deposit_ix(&baseline_first, false, 1_000_000))
+        .unwrap();
+    let trader = baseline.create_user();
+    baseline
+        .h
+        .process(&baseline.swap_ix(&trader, a_to_b, amount))
+        .unwrap();
+    let first_shares = destination.h.token_balance(&first.shares);
+    assert_eq!(baseline.pool().sqrt_price, destination.pool().sqrt_price);
+    // Less an atom of rounding in the shares minted
+    assert!(
+        destination.nav().value_of_shares(first_shares).unwrap() + 1
+            >= baseline.nav().value_of_shares(first_shares).unwrap()
+    );
+}
+
+#[test]
+fn migrations_swap_only_the_migrated_tokens() {
+    let mut source = Env::new();
+    let user = source.create_user();
+    source
+        .h
+        .process(&source.deposit_ix(&user, false, 10_000_000))
+        .unwrap();
+    let mut destination = source.second_vault();
+    source.hand_over(&mut destination);
+    let first = destination.create_user();
+    destination
+        .h
+        .process(&destination.deposit_ix(&first, false, 1_000_000))
+        .unwrap();
+    // Idle token A far past what the migrated tokens need swapped; sizing the
+    // swap over it spent more A than the migration brought
+    let idle_a = destination.h.token_balance(&destination.vault_token_a) + 50_000_000;
+    let (vault_token_a, mint_a, vault) = (
+        destination.vault_token_a,
+        destination.mint_a,
+        destination.vault,
+    );
+    destination
+        .h
+        .set_token_account(vault_token_a, &mint_a, &vault, idle_a);
+    let sqrt_price = destination.pool().sqrt_price;
+    let shares_account =
+        destination
+            .h
+            .create_token_account(&destination.share_mint, &user.wallet, 0);
+    let shares = destination.h.token_balance(&user.shares);
+    let migrate = destination.migrate_ix(&source, &user, shares_account, shares, true);
+    destination.h.process(&migrate).unwrap();
+
+    let migrated = User {
+        shares: shares_account,
+        ..user
+    };
+    let position = destination.user_position(&migrated);
+    assert_eq!(
+        position.shares,
+        destination.h.token_balance(&shares_account)
+    );
+    assert!(position.shares > 0);
+    // The swap moved the pool by what the migrated tokens needed, no further
+    let (amount, a_to_b) = math::rebalance_swap(
+        sqrt_price,
+        math::sqrt_price_from_tick_index(-640).unwrap(),
+        math::sqrt_price_from_tick_index(640).unwrap(),
+        position.deposited_a,
+        position.deposited_b,
+    )
+    .unwrap()
+    .unwrap();
+    let mut baseline = Env::new();
+    let baseline_first = baseline.create_user();
+    baseline
+        .h
+        .process(&baseline.deposit_ix(&baseline_first, false, 1_000_000))
+        .unwrap();
+    let trader = baseline.create_user();
+    baseline
+        .h
+        .process(&baseline.swap_ix(&trader, a_to_b, amount))
+        .unwrap();
+    assert_eq!(baseline.pool().sqrt_price, destination.pool().sqrt_price);
+}
+
+#[test]
+fn migrations_follow_the_destination_deposit_rules() {
+    let mut source = Env::new();
+    let user = source.create_user();
+    source
+        .h
+        .process(&source.deposit_ix(&user, false, 10_000_000))
+        .unwrap();
+    let mut destination = source.second_vault();
+    source.hand_over(&mut destination);
+    let shares_account =
+        destination
+            .h
+            .create_token_account(&destination.share_mint, &user.wallet, 0);
+    let shares = destination.h.token_balance(&user.shares);
+    let migrate = |env: &mut Env| {
+        let instruction = env.migrate_ix(&source, &user, shares_account, shares, true);
+        env.h.process(&instruction)
+    };
+
+    destination.set_allowlist(true, [0; 32]);
+    assert_eq!(
+        migrate(&mut destination),
+        Err(program_error(VaultError::NotAllowlisted))
+    );
+    destination.add_to_allowlist(user.wallet);
+    for (limits, error) in [
+        ((20_000_000, 0, 0), VaultError::DepositTooSmall),
+        ((0, 5_000_000, 0), VaultError::UserDepositCapExceeded),
+        ((0, 0, 5_000_000), VaultError::DepositCapExceeded),
+    ] {
+        destination.set_deposit_limits(limits.0, limits.1, limits.2);
+        assert_eq!(migrate(&mut destination), Err(program_error(error)));
+    }
+    destination.set_deposit_limits(1_000_000, 20_000_000, 20_000_000);
+    let mut bounded = destination.migrate_ix(&source, &user, shares_account, shares, true);
+    bounded.data = vault_manager::instruction::MigrateLiquidity {
+        shares,
+        min_amount_a: 0,
+        min_amount_b: 0,
+        min_swap_out: 0,
+        min_shares_out: u64::MAX,
+    }
+    .data();
+    assert_eq!(
+        destination.h.process(&bounded),
+        Err(program_error(VaultError::SharesBelowMinimum))
+    );
+    migrate(&mut destination).unwrap();
+    assert!(destination.h.token_balance(&shares_account) > 0);
+}
+
+#[test]
+fn user_deposit_cap_counts_shares_in_every_share_account() {
+    let mut env = Env::new();
+    env.set_deposit_limits(0, 10_000_000, 0);
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 6_000_000))
+        .unwrap();
+    // A fresh share account holds none of the shares the user was minted
+    let fresh = User {
+        shares: env.h.create_token_account(&env.share_mint, &user.wallet, 0),
+        ..user
+    };
+    assert_eq!(
+        env.h.process(&env.deposit_ix(&fresh, false, 6_000_000)),
+        Err(program_error(VaultError::UserDepositCapExceeded))
+    );
+    env.h
+        .process(&env.deposit_ix(&fresh, false, 3_000_000))
+        .unwrap();
+}
+
+#[test]
+fn merkle_allowlist_lets_listed_wallets_join_and_deposit() {
+    let mut env = Env::new();
+    let member = env.create_user();
+    let outsider = env.create_user();
+    let leaves = [
+        allowlist::leaf(&member.wallet),
+        allowlist::leaf(&Pubkey::new_unique()),
+        allowlist::leaf(&Pubkey::new_unique()),
+    ];
+    let root = allowlist::node(&allowlist::node(&leaves[0], &leaves[1]), &leaves[2]);
+    env.set_allowlist(true, root);
+    assert_eq!(
+        env.h.process(&env.deposit_ix(&member, false, 1_000_000)),
+        Err(program_error(VaultError::NotAllowlisted))
+    );
+
+    let proof = vec![leaves[1], leaves[2]];
+    assert_eq!(
+        env.h
+            .process(&env.join_allowlist_ix(&outsider, proof.clone())),
+        Err(program_error(VaultError::InvalidAllowlistProof))
+    );
+    env.h
+        .process(&env.join_allowlist_ix(&member, proof))
+        .unwrap();
+    env.h
+        .process(&env.deposit_ix(&member, false, 1_000_000))
+        .unwrap();
+    assert_eq!(
+        env.h.process(&env.deposit_ix(&outsider, false, 1_000_000)),
+        Err(program_error(VaultError::NotAllowlisted))
+    );
+
+    // With the allowlist off, anyone deposits
+    env.set_allowlist(false, root);
+    env.h
+        .process(&env.deposit_ix(&outsider, false, 1_000_000))
+        .unwrap();
+}
+
+#[test]
+fn lockup_refuses_withdrawals_after_a_deposit() {
+    let mut env = Env::new();
+    env.set_lockup(100, 0, 0);
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let shares = env.h.token_balance(&user.shares);
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&user, shares)),
+        Err(program_error(VaultError::SharesLocked))
+    );
+
+    // Shares moved to another wallet are not tracked by its receipt
+    let recipient = env.create_user();
+    env.transfer_shares(&user, &recipient, shares / 2);
+    env.h.advance_clock(100, 40);
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&recipient, shares / 2)),
+        Err(program_error(VaultError::InsufficientShares))
+    );
+    env.h
+        .process(&env.withdraw_ix(&user, shares - shares / 2))
+        .unwrap();
+}
+
+#[test]
+fn early_exit_fee_stays_with_the_remaining_holders() {
+    let mut env = Env::new();
+    env.set_lockup(100, 500, 0);
+    let holder = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&holder, false, 1_000_000))
+        .unwrap();
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let holder_shares = env.h.token_balance(&holder.shares);
+    let before = env.nav().value_of_shares(holder_shares).unwrap();
+
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+    // All shares are burned, 5% of them unpaid
+    assert_eq!(env.h.mint_supply(&env.share_mint), holder_shares);
+    let after = env.nav().value_of_shares(holder_shares).unwrap();
+    assert!(after >= before + 45_000);
+    let received = env.h.token_balance(&user.token_a) + env.h.token_balance(&user.token_b);
+    assert!(received < 2 * USER_BALANCE - 45_000);
+}
+
+#[test]
+fn withdrawal_queue_pays_out_requested_shares_after_the_delay() {
+    let mut env = Env::new();
+    env.set_lockup(0, 0, 50);
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    let shares = env.h.token_balance(&user.shares);
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&user, shares)),
+        Err(program_error(VaultError::WithdrawalNotRequested))
+    );
+
+    let requested = shares / 2;
+    env.h
+        .process(&env.request_withdrawal_ix(&user, requested))
+        .unwrap();
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&user, requested)),
+        Err(program_error(VaultError::WithdrawalNotReady))
+    );
+    env.h.advance_clock(50, 20);
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&user, requested + 1)),
+        Err(program_error(VaultError::WithdrawalNotRequested))
+    );
+    env.h.process(&env.withdraw_ix(&user, requested)).unwrap();
+
+    // The payout used up the request
+    assert_eq!(env.user_position(&user).pending_withdrawal_shares, 0);
+    assert_eq!(
+        env.h.process(&env.withdraw_ix(&user, shares - requested)),
+        Err(program_error(VaultError::WithdrawalNotRequested))
+    );
+}
+
+#[test]
+fn close_vault_sweeps_leftovers_and_leaves_the_registry() {
+    let mut env = Env::new();
+    let authority_tokens = (
+        env.h.create_token_account(&env.mint_a, &env.authority, 0),
+        env.h.create_token_account(&env.mint_b, &env.authority, 0),
+    );
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    assert_eq!(
+        env.h.process(&env.close_vault_ix(authority_tokens)),
+        Err(program_error(VaultError::VaultNotEmpty))
+    );
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+
+    // Tokens sent to the vault after the last withdrawal
+    let gift = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &user.token_a,
+        &env.vault_token_a,
+        &user.wallet,
+        &[],
+        7,
+    )
+    .unwrap();
+    env.h.process(&gift).unwrap();
+    let leftovers = (
+        env.h.token_balance(&env.vault_token_a),
+        env.h.token_balance(&env.vault_token_b),
+    );
+    assert!(leftovers.0 >= 7);
+
+    env.h
+        .process(&env.close_vault_ix(authority_tokens))
+        .unwrap();
+    assert_eq!(
+        (
+            env.h.token_balance(&authority_tokens.0),
+            env.h.token_balance(&authority_tokens.1)
+        ),
+        leftovers
+    );
+    for closed in [
+        env.vault,
+        env.vault_token_a,
+        env.vault_token_b,
+        env.position,
+    ] {
+        assert!(env.h.account(&closed).is_none());
+    }
+    let registry: VaultRegistry = env.h.anchor_account(&env.registry_key());
+    assert!(registry.vaults.is_empty());
+}
+
+#[test]
+fn close_vault_collects_rewards_still_owed_to_the_position() {
+    let mut env = Env::new();
+    let authority_tokens = (
+        env.h.create_token_account(&env.mint_a, &env.authority, 0),
+        env.h.create_token_account(&env.mint_b, &env.authority, 0),
+    );
+    let reward_mint = env.h.create_mint(&env.authority, 6);
+    let rewards = env.h.create_token_account(&reward_mint, &env.vault, 0);
+    env.initialize_reward(reward_mint, 1_000, rewards);
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 1_000_000))
+        .unwrap();
+    env.h.advance_clock(250, 100);
+    let shares = env.h.token_balance(&user.shares);
+    env.h.process(&env.withdraw_ix(&user, shares)).unwrap();
+    // The withdrawal collected the rewards it found
+    let collected = env.h.token_balance(&rewards);
+    assert!(collected > 0);
+
+    // Rewards the emptied position is still owed keep Whirlpool from closing it
+    let mut position: mock_whirlpool::state::Position = env.h.anchor_account(&env.position);
+    assert_eq!(position.liquidity, 0);
+    position.reward_infos[0].amount_owed = 5_000;
+    env.h.set_anchor_account(env.position, &position);
+    env.h
+        .process(&env.close_vault_ix(authority_tokens))
+        .unwrap();
+    assert_eq!(env.h.token_balance(&rewards), collected + 5_000);
+    assert!(env.h.account(&env.position).is_none());
+}
+
+#[test]
+fn timelocked_vaults_queue_strategy_config_and_oracle_changes() {
+    let mut env = Env::new();
+    let now = env.h.clock().unix_timestamp;
+    env.h
+        .process(&env.queue_param_change_ix(ParamChange::Timelock { slots: 100 }, None))
+        .unwrap();
+    env.h
+        .process(&env.execute_param_change_ix(env.authority, false))
+        .unwrap();
+
+    assert_eq!(
+        env.h.process(&env.set_strategy_config_ix(200, 50)),
+        Err(program_error(VaultError::ParamChangeTimelocked))
+    );
+    assert_eq!(
+        env.set_price_oracle(pyth_price(1_000_000, -6, now, PYTH_TRADING), 100, 60),
+        Err(program_error(VaultError::ParamChangeTimelocked))
+    );
+
+    let change = ParamChange::StrategyConfig {
+        range_width: 200,
+        rebalance_threshold: 50,
+    };
+    env.h
+        .process(&env.queue_param_change_ix(change, None))
+        .unwrap();
+    assert_eq!(
+        env.h
+            .process(&env.execute_param_change_ix(env.authority, true)),
+        Err(program_error(VaultError::ParamChangeNotReady))
+    );
+    env.h.advance_clock(100, 40);
+    assert_eq!(
+        env.h
+            .process(&env.execute_param_change_ix(env.authority, false)),
+        Err(program_error(VaultError::StrategyConfigRequired))
+    );
+    // A separate payer funds the strategy config and takes the pending change's rent
+    let payer = env.h.create_wallet();
+    let lamports = |env: &Env, key: &Pubkey| env.h.account(key).unwrap().lamports;
+    let (authority_before, payer_before) = (lamports(&env, &env.authority), lamports(&env, &payer));
+    let pending_rent = lamports(&env, &env.pending_change_key());
+    env.h
+        .process(&env.execute_param_change_ix(payer, true))
+        .unwrap();
+    assert_eq!(lamports(&env, &env.authority), authority_before);
+    assert_eq!(
+        lamports(&env, &payer) + lamports(&env, &env.strategy_config_key()),
+        payer_before + pending_rent
+    );
+    let config: StrategyConfig = env.h.anchor_account(&env.strategy_config_key());
+    assert_eq!(
+        (config.vault, config.range_width, config.rebalance_threshold),
+        (env.vault, 200, 50)
+    );
+
+    let feed = Pubkey::new_unique();
+    env.h
+        .set_account(feed, pyth_price(1_000_000, -6, now, PYTH_TRADING));
+    let change = ParamChange::PriceOracle {
+        price_oracle: feed,
+        max_deviation_bps: 100,
+        max_staleness_secs: 60,
+    };
+    assert_eq!(
+        env.h
+            .process(&env.queue_param_change_ix(change.clone(), None)),
+        Err(program_error(VaultError::OracleRequired))
+    );
+    env.h
+        .process(&env.queue_param_change_ix(change, Some(feed)))
+        .unwrap();
+    env.h.advance_clock(100, 40);
+    env.h
+        .process(&env.execute_param_change_ix(env.authority, false))
+        .unwrap();
+    assert_eq!(env.vault_state().config.price_oracle, feed);
+}
+
+#[test]
+fn nav_drops_pause_the_vault_without_depositing_or_rebalancing() {
+    let mut env = Env::new();
+    let user = env.create_user();
+    env.h
+        .process(&env.deposit_ix(&user, false, 10_000_000))
+        .unwrap();
+    env.set_nav_circuit_breaker(1_000);
+
+    let late = env.create_user();
+    let balance_b = env.h.token_balance(&late.token_b);
+    env.inflate_recorded_nav(2);
+    env.h
+        .process(&env.deposit_ix(&late, false, 1_000_000))
+        .unwrap();
+    assert!(env.vault_state().paused);
+    assert_eq!(env.h.token_balance(&late.token_b), balance_b);
+    assert_eq!(env.h.token_balance(&late.shares), 0);
+    assert!(env.h.account(&env.user_position_key(&late)).is_none());
+    assert_eq!(
+        env.h.process(&env.deposit_ix(&late, false, 1_000_000)),
+        Err(program_error(VaultError::VaultPaused))
+    );
+
+    // Unpaused, the next drop trips on a rebalance, which leaves the position alone
+    let unpause = Instruction {
+        program_id: vault_manager::ID,
+        accounts: vault_manager::accounts::SetPaused {
+            vault: env.vault,
+            authority: env.authority,
+        }
+        .to_account_metas(None),
+        data: vault_manager::instruction::SetPaused { paused: false }.data(),
+    };
+    env.h.process(&unpause).unwrap();
+    env.inflate_recorded_nav(2);
+    let payer = env.h.create_wallet();
+    let position: mock_whirlpool::state::Position = env.h.anchor_account(&env.position);
+    let (rebalance, (new_position, _)) = env.rebalance_ix(payer, (-1280, 1280), 0, 0, 0);
+    env.h.process(&rebalance).unwrap();
+    assert!(env.h.account(&new_position).is_none());
+    assert!(env.vault_state().paused);
+    assert_eq!(env.vault_state().position, env.position);
+    let after: mock_whirlpool::state::Position = env.h.anchor_account(&env.position);
+    assert_eq!(after.liquidity, position.liquidity);
+}
@@ -0,0 +1,539 @@
+//! Whirlpool backend against the mock Whirlpool program.
+//!
+//! Liquidity, trades and fee accrual go through the mock's pool state
+//! transitions, which the mock's instructions run on chain. The vault side is
+//! read back through `WhirlpoolAdapter`, the code behind deposit, withdraw,
+//! rebalance and `get_nav`.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::Harness;
+use mock_whirlpool::pool;
+use mock_whirlpool::state::{Position, TickArray, TickArrays, Whirlpool};
+use vault_manager::dex::whirlpool::WhirlpoolPosition;
+use vault_manager::dex::{Dex, DexAccounts, DexAdapter, WhirlpoolAdapter};
+use vault_manager::nav::PositionValue;
+use vault_manager::{math, Vault, VaultConfig, VaultFees, VaultStrategy};
+
+const TICK_SPACING: u16 = 64;
+/// 0.3%, in hundredths of a basis point.
+const FEE_RATE: u16 = 3_000;
+const NOW: i64 = 1_700_000_000;
+const LP_LIQUIDITY: u128 = 10_000_000_000;
+
+struct Env {
+    h: Harness,
+    whirlpool: Pubkey,
+    token_vault_a: Pubkey,
+    token_vault_b: Pubkey,
+    vault: Pubkey,
+    vault_token_a: Pubkey,
+    vault_token_b: Pubkey,
+    lp_position: Pubkey,
+    /// Position NFT accounts, by position.
+    nft_accounts: Vec<(Pubkey, Pubkey)>,
+    /// `(reward_vault, vault_reward_account)` of each initialized pool reward.
+    reward_accounts: Vec<(Pubkey, Pubkey)>,
+}
+
+impl Env {
+    /// Pool at tick 0 with LP liquidity over ticks [-1280, 1280), and a vault
+    /// whose empty position covers ticks [-640, 640).
+    fn new() -> Self {
+        let mut h = Harness::new();
+        h.add_program(vault_manager::ID, vault_manager::entry);
+        h.add_program(mock_whirlpool::ID, mock_whirlpool::entry);
+
+        let authority = Pubkey::new_unique();
+        let config = Pubkey::new_unique();
+        let mint_a = h.create_mint(&authority, 6);
+        let mint_b = h.create_mint(&authority, 6);
+        let (whirlpool, _) = Pubkey::find_program_address(
+            &[
+                b"whirlpool",
+                config.as_ref(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+                &TICK_SPACING.to_le_bytes(),
+            ],
+            &mock_whirlpool::ID,
+        );
+        let token_vault_a = h.create_token_account(&mint_a, &whirlpool, 0);
+        let token_vault_b = h.create_token_account(&mint_b, &whirlpool, 0);
+        let state = Whirlpool::new(
+            config,
+            mint_a,
+            mint_b,
+            token_vault_a,
+            token_vault_b,
+            TICK_SPACING,
+            FEE_RATE,
+            1 << 64,
+        )
+        .unwrap();
+        h.set_anchor_account(whirlpool, &state);
+
+        let (vault, bump) = Pubkey::find_program_address(
+            &[vault_manager::VAULT_SEED, whirlpool.as_ref()],
+            &vault_manager::ID,
+        );
+        let (share_mint, _) = Pubkey::find_program_address(
+            &[vault_manager::SHARE_MINT_SEED, vault.as_ref()],
+            &vault_manager::ID,
+        );
+        let vault_token_a = h.create_token_account(&mint_a, &vault, 0);
+        let vault_token_b = h.create_token_account(&mint_b, &vault, 0);
+
+        let mut env = Self {
+            h,
+            whirlpool,
+            token_vault_a,
+            token_vault_b,
+            vault,
+            vault_token_a,
+            vault_token_b,
+            lp_position: Pubkey::default(),
+            nft_accounts: Vec::new(),
+            reward_accounts: Vec::new(),
+        };
+        for start in [-5632, 0] {
+            let array = TickArray::new(whirlpool, start, TICK_SPACING).unwrap();
+            env.h.set_anchor_account(env.tick_array(start), &array);
+        }
+
+        env.lp_position = env.open_position(Pubkey::new_unique(), -1280, 1280);
+        env.modify_liquidity(env.lp_position, LP_LIQUIDITY as i128);
+
+        let position = env.open_position(vault, -640, 640);
+        env.h.set_anchor_account(
+            vault,
+            &Vault {
+                authority,
+                dex: Dex::Whirlpool,
+                pool: whirlpool,
+                token_a_mint: mint_a,
+                token_b_mint: mint_b,
+                vault_token_a,
+                vault_token_b,
+                position,
+                share_mint,
+                config: VaultConfig::default(),
+                strategy: VaultStrategy::default(),
+                fees: VaultFees::default(),
+                last_nav_per_share_x64: 0,
+                last_nav_slot: 0,
+                paused: false,
+                token_a_decimals: 6,
+                token_b_decimals: 6,
+                bump,
+                is_active: false,
+            },
+        );
+        env
+    }
+
+    fn tick_array(&self, tick_index: i32) -> Pubkey {
+        let start = TickArray::start_index_of(tick_index, TICK_SPACING);
+        Pubkey::find_program_address(
+            &[
+                b"tick_array",
+                self.whirlpool.as_ref(),
+                start.to_string().as_bytes(),
+            ],
+            &mock_whirlpool::ID,
+        )
+        .0
+    }
+
+    fn pool(&self) -> Whirlpool {
+        self.h.anchor_account(&self.whirlpool)
+    }
+
+    fn position(&self, key: &Pubkey) -> Position {
+        self.h.anchor_account(key)
+    }
+
+    fn ticks(&self) -> TickArrays {
+        let keys = [self.tick_array(-1), self.tick_array(0)];
+        TickArrays::new(
+            TICK_SPACING,
+            keys.iter()
+                .map(|key| (*key, self.h.anchor_account(key)))
+                .collect(),
+        )
+    }
+
+    fn save(&mut self, whirlpool: &Whirlpool, ticks: TickArrays) {
+        self.h.set_anchor_account(self.whirlpool, whirlpool);
+        for (key, array) in ticks.into_arrays() {
+            self.h.set_anchor_account(key, &array);
+        }
+    }
+
+    /// Position of `owner`, with its NFT in a token account of `owner`.
+    fn open_position(&mut self, owner: Pubkey, tick_lower: i32, tick_upper: i32) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let (key, _) =
+            Pubkey::find_program_address(&[b"position", mint.as_ref()], &mock_whirlpool::ID);
+        let position =
+            Position::new(self.whirlpool, &self.pool(), mint, tick_lower, tick_upper).unwrap();
+        self.h.set_anchor_account(key, &position);
+        let nft_account = self.h.create_token_account(&mint, &owner, 1);
+        self.nft_accounts.push((key, nft_account));
+        key
+    }
+
+    fn nft_account(&self, position: &Pubkey) -> Pubkey {
+        self.nft_accounts
+            .iter()
+            .find(|(key, _)| key == position)
+            .unwrap()
+            .1
+    }
+
+    /// Adds or removes liquidity at `NOW`. Returns the token amounts moved.
+    fn modify_liquidity(&mut self, key: Pubkey, liquidity_delta: i128) -> (u64, u64) {
+        let mut whirlpool = self.pool();
+        let mut position = self.position(&key);
+        let mut ticks = self.ticks();
+        let amounts = pool::modify_liquidity(
+            &mut whirlpool,
+            &mut position,
+            &mut ticks,
+            liquidity_delta,
+            NOW,
+        )
+        .unwrap();
+        self.h.set_anchor_account(key, &position);
+        self.save(&whirlpool, ticks);
+        amounts
+    }
+
+    /// Trades `amount` through the pool at `now`. Returns the amounts in and out.
+    fn trade(&mut self, amount: u64, a_to_b: bool, now: i64) -> (u64, u64) {
+        let mut whirlpool = self.pool();
+        let mut ticks = self.ticks();
+        let amounts = pool::swap(&mut whirlpool, &mut ticks, amount, 0, a_to_b, now).unwrap();
+        self.save(&whirlpool, ticks);
+        amounts
+    }
+
+    /// Token amounts removing the whole position would pay out, with the pool left untouched.
+    fn removal_amounts(&self, key: &Pubkey) -> (u64, u64) {
+        let mut position = self.position(key);
+        let liquidity = position.liquidity as i128;
+        pool::modify_liquidity(
+            &mut self.pool(),
+            &mut position,
+            &mut self.ticks(),
+            -liquidity,
+            NOW,
+        )
+        .unwrap()
+    }
+
+    /// Runs `f` on the vault's Whirlpool backend with `position` loaded.
+    fn with_adapter<R>(
+        &self,
+        position: Pubkey,
+        f: impl for<'a> FnOnce(&WhirlpoolAdapter<'a>, &WhirlpoolPosition<'a>) -> Result<R>,
+    ) -> Result<R> {
+        let vault = self.vault_state();
+        let state = self.position(&position);
+        let mut metas = vec![
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new(self.vault_token_a, false),
+            AccountMeta::new(self.vault_token_b, false),
+            AccountMeta::new(self.whirlpool, false),
+            AccountMeta::new(self.token_vault_a, false),
+            AccountMeta::new(self.token_vault_b, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(mock_whirlpool::ID, false),
+            AccountMeta::new(position, false),
+            AccountMeta::new_readonly(self.nft_account(&position), false),
+            AccountMeta::new(self.tick_array(state.tick_lower_index), false),
+            AccountMeta::new(self.tick_array(state.tick_upper_index), false),
+        ];
+        for (reward_vault, vault_reward_account) in &self.reward_accounts {
+            metas.push(AccountMeta::new(*reward_vault, false));
+            metas.push(AccountMeta::new(*vault_reward_account, false));
+        }
+        self.h.with_accounts(&metas, |infos| {
+            let dex = WhirlpoolAdapter::load(DexAccounts {
+                vault: infos[0].clone(),
+                vault_bump: vault.bump,
+                token_a_mint: vault.token_a_mint,
+                token_b_mint: vault.token_b_mint,
+                vault_token_a: infos[1].clone(),
+                vault_token_b: infos[2].clone(),
+                pool: infos[3].clone(),
+                pool_token_vault_a: infos[4].clone(),
+                pool_token_vault_b: infos[5].clone(),
+                token_program: infos[6].clone(),
+                dex_program: infos[7].clone(),
+                bin_strategy: vault.strategy.bin_strategy,
+            })?;
+            let position = dex.load_position(infos[8].clone(), &mut infos[9..].iter())?;
+            f(&dex, &position)
+        })
+    }
+
+    fn position_value(&self, key: Pubkey) -> PositionValue {
+        self.with_adapter(key, |dex, position| dex.position_value(position))
+            .unwrap()
+    }
+
+    fn vault_state(&self) -> Vault {
+        self.h.anchor_account(&self.vault)
+    }
+}
+
+#[test]
+fn layouts_decode_with_the_vault_bindings() {
+    use vault_manager::whirlpool::state as binding;
+
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    env.modify_liquidity(position, 1_000_000);
+
+    let pool = env.pool();
+    let read: binding::Whirlpool = env.h.anchor_account(&env.whirlpool);
+    assert_eq!(
+        (read.tick_spacing, read.fee_rate, read.liquidity),
+        (TICK_SPACING, FEE_RATE, LP_LIQUIDITY + 1_000_000)
+    );
+    assert_eq!(
+        (read.sqrt_price, read.tick_current_index),
+        (pool.sqrt_price, 0)
+    );
+    assert_eq!(
+        (read.token_vault_a, read.token_vault_b),
+        (env.token_vault_a, env.token_vault_b)
+    );
+
+    let read: binding::Position = env.h.anchor_account(&position);
+    assert_eq!(
+        (read.liquidity, read.tick_lower_index, read.tick_upper_index),
+        (1_000_000, -640, 640)
+    );
+
+    // The vault reads ticks out of the zero-copy layout by offset
+    let mut ticks = env.ticks();
+    for (i, tick_index) in [-1280, -640, 640, 1280].into_iter().enumerate() {
+        let tick = ticks.tick(tick_index).unwrap();
+        tick.fee_growth_outside_a = 10 * i as u128 + 1;
+        tick.fee_growth_outside_b = 10 * i as u128 + 2;
+        tick.reward_growths_outside = [3, 4, 5].map(|n| 10 * i as u128 + n);
+    }
+    env.save(&pool, ticks);
+    let mut ticks = env.ticks();
+    for tick_index in [-1280, -640, 640, 1280] {
+        let expected = *ticks.tick(tick_index).unwrap();
+        let array = env.tick_array(tick_index);
+        let tick = env
+            .h
+            .with_accounts(&[AccountMeta::new_readonly(array, false)], |infos| {
+                binding::TickArray::read_tick(&infos[0], &env.whirlpool, tick_index, TICK_SPACING)
+            })
+            .unwrap();
+        assert!(tick.initialized);
+        assert_eq!(tick.liquidity_net, expected.liquidity_net);
+        assert_eq!(tick.liquidity_gross, expected.liquidity_gross);
+        assert_eq!(
+            (tick.fee_growth_outside_a, tick.fee_growth_outside_b),
+            (expected.fee_growth_outside_a, expected.fee_growth_outside_b)
+        );
+        assert_eq!(tick.reward_growths_outside, expected.reward_growths_outside);
+    }
+
+    for tick in [-443636, -12_345, -1, 0, 1, 64, 99_999, 443636] {
+        assert_eq!(
+            mock_whirlpool::math::sqrt_price_from_tick_index(tick).unwrap(),
+            math::sqrt_price_from_tick_index(tick).unwrap()
+        );
+    }
+}
+
+#[test]
+fn liquidity_for_amounts_stays_within_the_maximums() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    env.trade(3_000_000, false, NOW);
+
+    let (max_a, max_b) = (1_000_000, 1_000_000);
+    let liquidity = env
+        .with_adapter(position, |dex, position| {
+            let range = dex.position_range(position)?;
+            math::liquidity_for_amounts(
+                dex.price()?.sqrt_price,
+                range.sqrt_lower,
+                range.sqrt_upper,
+                max_a,
+                max_b,
+            )
+        })
+        .unwrap();
+    let (amount_a, amount_b) = env.modify_liquidity(position, liquidity as i128);
+    assert!(amount_a <= max_a && amount_b <= max_b);
+    // One side is the binding one and is used up to rounding
+    assert!(amount_a >= max_a - 1 || amount_b >= max_b - 1);
+}
+
+#[test]
+fn position_value_matches_what_decrease_pays_out() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    env.modify_liquidity(position, 5_000_000_000);
+    let value = env.position_value(position);
+    assert_eq!(
+        (value.amount_a, value.amount_b),
+        env.removal_amounts(&position)
+    );
+
+    // Up within the vault's range
+    env.trade(50_000_000, false, NOW);
+    let tick = env.pool().tick_current_index;
+    assert!((0..640).contains(&tick), "tick is {tick}");
+    let value = env.position_value(position);
+    assert_eq!(
+        (value.amount_a, value.amount_b),
+        env.removal_amounts(&position)
+    );
+
+    // Past the vault's upper tick, which the swap crosses
+    env.trade(500_000_000, false, NOW);
+    let pool = env.pool();
+    assert!((640..1280).contains(&pool.tick_current_index));
+    assert_eq!(pool.liquidity, LP_LIQUIDITY);
+    let value = env.position_value(position);
+    assert_eq!(value.amount_a, 0);
+    assert_eq!(
+        (value.amount_a, value.amount_b),
+        env.removal_amounts(&position)
+    );
+
+    let price = env.with_adapter(position, |dex, _| dex.price()).unwrap();
+    assert_eq!(
+        (price.sqrt_price, price.tick_current),
+        (pool.sqrt_price, pool.tick_current_index)
+    );
+}
+
+#[test]
+fn fees_and_rewards_are_valued_as_collected() {
+    let mut env = Env::new();
+    let position = env.vault_state().position;
+    env.modify_liquidity(position, 5_000_000_000);
+
+    let reward_mint = Pubkey::new_unique();
+    let reward_vault = env.h.create_token_account(&reward_mint, &env.whirlpool, 0);
+    let vault_rewards = env.h.create_token_account(&reward_mint, &env.vault, 0);
+    env.reward_accounts.push((reward_vault, vault_rewards));
+    let mut whirlpool = env.pool();
+    whirlpool.reward_infos[1].mint = reward_mint;
+    whirlpool.reward_infos[1].vault = reward_vault;
+    whirlpool.reward_infos[1].emissions_per_second_x64 = 1_000 << 64;
+    whirlpool.reward_last_updated_timestamp = NOW as u64;
+    env.h.set_anchor_account(env.whirlpool, &whirlpool);
+
+    let (amount_in, amount_out) = env.trade(20_000_000, true, NOW + 10);
+    assert!(amount_out < amount_in);
+    env.trade(amount_out, false, NOW + 20);
+    // The adapter values rewards up to the pool's last update
+    let mut whirlpool = env.pool();
+    pool::update_rewards(&mut whirlpool, NOW + 100).unwrap();
+    env.h.set_anchor_account(env.whirlpool, &whirlpool);
+
+    let value = env.position_value(position);
+    let mut state = env.position(&position);
+    pool::update_fees_and_rewards(&mut whirlpool, &mut state, &mut env.ticks(), NOW + 100).unwrap();
+    let fees = pool::collect_fees(&mut state);
+    assert!(fees.0 > 0 && fees.1 > 0);
+    assert_eq!((value.fees_owed_a, value.fees_owed_b), fees);
+
+    // A third of the in-range liquidity earns about a third of 100 seconds of emissions
+    let reward = pool::collect_reward(&mut state, 1).unwrap();
+    assert!((33_000..=34_000).contains(&reward), "{reward}");
+    assert_eq!(value.rewards_owed[1], (reward_mint, reward));
+    assert_eq!(value.rewards_owed[0], (Pubkey::default(), 0));
+}
+
+#[test]
+fn price_limit_stops_a_swap_early() {
+    let env = Env::new();
+    let limit = math::sqrt_price_from_tick_index(-100).unwrap();
+    let mut whirlpool = env.pool();
+    let (amount_in, amount_out) = pool::swap(
+        &mut whirlpool,
+        &mut env.ticks(),
+        u64::MAX / 2,
+        limit,
+        true,
+        NOW,
+    )
+    .unwrap();
+    assert_eq!(whirlpool.sqrt_price, limit);
+    assert_eq!(whirlpool.tick_current_index, -100);
+    assert!(amount_in < u64::MAX / 2 && amount_out < amount_in);
+
+    // A limit on the wrong side of the price is rejected
+    let err = pool::swap(
+        &mut whirlpool,
+        &mut env.ticks(),
+        1_000,
+        limit + 1,
+        true,
+        NOW,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        mock_whirlpool::MockWhirlpoolError::InvalidSqrtPriceLimit.into()
+    );
+}
+
+#[test]
+fn set_sqrt_price_moves_the_pool_through_its_ticks() {
+    let mut env = Env::new();
+    let whirlpool = env.whirlpool;
+    let tick_arrays = [env.tick_array(-1), env.tick_array(0)];
+    let set_sqrt_price = |tick: i32| Instruction {
+        program_id: mock_whirlpool::ID,
+        accounts: [
+            mock_whirlpool::accounts::SetSqrtPrice { whirlpool }.to_account_metas(None),
+            tick_arrays
+                .iter()
+                .map(|key| AccountMeta::new(*key, false))
+                .collect(),
+        ]
+        .concat(),
+        data: mock_whirlpool::instruction::SetSqrtPrice {
+            sqrt_price: math::sqrt_price_from_tick_index(tick).unwrap(),
+        }
+        .data(),
+    };
+
+    // Below the LP range: no liquidity left in range
+    let down = set_sqrt_price(-2_000);
+    env.h.process(&down).unwrap();
+    let whirlpool = env.pool();
+    assert_eq!(
+        (whirlpool.tick_current_index, whirlpool.liquidity),
+        (-2_000, 0)
+    );
+    let removal = env.removal_amounts(&env.lp_position);
+    assert_eq!(removal.1, 0);
+
+    // And back, crossing the lower tick the other way
+    let back = set_sqrt_price(10);
+    env.h.process(&back).unwrap();
+    let whirlpool = env.pool();
+    assert_eq!(
+        (whirlpool.tick_current_index, whirlpool.liquidity),
+        (10, LP_LIQUIDITY)
+    );
+}
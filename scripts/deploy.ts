@@ -5,6 +5,13 @@ import { execSync } from "child_process";
 import fs from "fs";
 import path from "path";
 
+// Whirlpool, Raydium CLMM and Meteora DLMM
+const DEX_PROGRAM_IDS = [
+  new PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"),
+  new PublicKey("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"),
+  new PublicKey("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo"),
+];
+
 async function main() {
   console.log("Building program...");
   execSync("anchor build", { stdio: "inherit" });
@@ -34,6 +41,28 @@ async function main() {
     console.log("No existing vault found.");
   }
 
+  // The deployer is the upgrade authority, so it can create the protocol config
+  const [protocolConfig] = PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol_config")],
+    programId
+  );
+  if (!(await provider.connection.getAccountInfo(protocolConfig))) {
+    console.log("Initializing protocol config...");
+    const [programData] = PublicKey.findProgramAddressSync(
+      [programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    await program.methods
+      .initializeProtocolConfig(0, provider.wallet.publicKey, DEX_PROGRAM_IDS)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        payer: provider.wallet.publicKey,
+        program: programId,
+        programData,
+      })
+      .rpc();
+  }
+
   console.log("Deployment complete!");
   console.log(`Program ID: ${programId.toBase58()}`);
   console.log(`IDL: target/idl/vault_manager.json`);
@@ -0,0 +1,205 @@
+// scripts/dump-fixtures.ts
+//
+// Dumps the accounts of one Orca Whirlpool, Raydium CLMM pool or Meteora DLMM
+// pair to JSON fixtures for the Rust tests:
+//
+//   npm run dump:fixtures -- <pool> <name> [rpc url]
+//
+// Writes the pool, the tick or bin arrays around its current price, both mints
+// and both token vaults to programs/vault_manager/tests/fixtures/<name>/, one
+// `<pubkey>.json` per account, along with the Whirlpool or DLMM oracle or the
+// Raydium config and observations. The format is the one `solana account
+// --output json` prints, so the directory also works with
+// `solana-test-validator --account-dir`.
+import { Connection, PublicKey, AccountInfo } from "@solana/web3.js";
+import fs from "fs";
+import path from "path";
+
+const WHIRLPOOL_PROGRAM_ID = new PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+const RAYDIUM_CLMM_PROGRAM_ID = new PublicKey("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+const DLMM_PROGRAM_ID = new PublicKey("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
+const DEFAULT_RPC_URL = "https://api.mainnet-beta.solana.com";
+const FIXTURES_DIR = path.join("programs", "vault_manager", "tests", "fixtures");
+
+// Tick or bin arrays dumped on each side of the one holding the current price
+const ARRAYS_PER_SIDE = 2;
+
+// Byte offsets into each pool account, discriminator included
+const WHIRLPOOL = {
+    tickArraySize: 88,
+    tickSpacing: 41,
+    tickCurrentIndex: 81,
+    tokenMintA: 101,
+    tokenVaultA: 133,
+    tokenMintB: 181,
+    tokenVaultB: 213,
+};
+const RAYDIUM_POOL = {
+    tickArraySize: 60,
+    ammConfig: 9,
+    tokenMint0: 73,
+    tokenMint1: 105,
+    tokenVault0: 137,
+    tokenVault1: 169,
+    observationKey: 201,
+    tickSpacing: 235,
+    tickCurrent: 269,
+};
+const LB_PAIR = {
+    binsPerArray: 70,
+    activeId: 76,
+    tokenXMint: 88,
+    tokenYMint: 120,
+    reserveX: 152,
+    reserveY: 184,
+    oracle: 552,
+};
+
+async function main() {
+    const [address, name, rpcUrl] = process.argv.slice(2);
+    if (!address || !name) {
+        console.error("Usage: ts-node scripts/dump-fixtures.ts <pool> <name> [rpc url]");
+        process.exit(1);
+    }
+    const connection = new Connection(rpcUrl ?? DEFAULT_RPC_URL, "confirmed");
+    const poolKey = new PublicKey(address);
+
+    const pool = await connection.getAccountInfo(poolKey);
+    let keys: PublicKey[];
+    if (pool?.owner.equals(WHIRLPOOL_PROGRAM_ID)) {
+        keys = whirlpoolAccounts(poolKey, pool.data);
+    } else if (pool?.owner.equals(RAYDIUM_CLMM_PROGRAM_ID)) {
+        keys = raydiumAccounts(poolKey, pool.data);
+    } else if (pool?.owner.equals(DLMM_PROGRAM_ID)) {
+        keys = dlmmAccounts(poolKey, pool.data);
+    } else {
+        throw new Error(`${address} is not a Whirlpool, Raydium CLMM pool or DLMM pair`);
+    }
+
+    // Fetched together with the pool's slot so the snapshot is consistent
+    const { context, value } = await connection.getMultipleAccountsInfoAndContext([
+        poolKey,
+        ...keys,
+    ]);
+    const dir = path.join(FIXTURES_DIR, name);
+    fs.rmSync(dir, { recursive: true, force: true });
+    fs.mkdirSync(dir, { recursive: true });
+
+    let written = 0;
+    [poolKey, ...keys].forEach((key, i) => {
+        const account = value[i];
+        if (!account) {
+            // Arrays nobody has initialized and oracles of older pools
+            console.log(`Skipping ${key.toBase58()}: account does not exist`);
+            return;
+        }
+        fs.writeFileSync(
+            path.join(dir, `${key.toBase58()}.json`),
+            JSON.stringify(toFixture(key, account), null, 2) + "\n"
+        );
+        written++;
+    });
+    console.log(`Wrote ${written} accounts at slot ${context.slot} to ${dir}`);
+}
+
+function pubkeyAt(data: Buffer, offset: number): PublicKey {
+    return new PublicKey(data.subarray(offset, offset + 32));
+}
+
+// Start indexes of the arrays of `size` around the one holding `current`
+function arrayStarts(current: number, size: number): number[] {
+    const currentStart = Math.floor(current / size) * size;
+    const starts: number[] = [];
+    for (let i = -ARRAYS_PER_SIDE; i <= ARRAYS_PER_SIDE; i++) {
+        starts.push(currentStart + i * size);
+    }
+    return starts;
+}
+
+function whirlpoolAccounts(whirlpool: PublicKey, data: Buffer): PublicKey[] {
+    const tickSpacing = data.readUInt16LE(WHIRLPOOL.tickSpacing);
+    const tickCurrentIndex = data.readInt32LE(WHIRLPOOL.tickCurrentIndex);
+    const tickArrays = arrayStarts(tickCurrentIndex, tickSpacing * WHIRLPOOL.tickArraySize).map(
+        (start) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("tick_array"), whirlpool.toBuffer(), Buffer.from(start.toString())],
+                WHIRLPOOL_PROGRAM_ID
+            )[0]
+    );
+    const [oracle] = PublicKey.findProgramAddressSync(
+        [Buffer.from("oracle"), whirlpool.toBuffer()],
+        WHIRLPOOL_PROGRAM_ID
+    );
+    return [
+        ...tickArrays,
+        oracle,
+        pubkeyAt(data, WHIRLPOOL.tokenMintA),
+        pubkeyAt(data, WHIRLPOOL.tokenMintB),
+        pubkeyAt(data, WHIRLPOOL.tokenVaultA),
+        pubkeyAt(data, WHIRLPOOL.tokenVaultB),
+    ];
+}
+
+function raydiumAccounts(pool: PublicKey, data: Buffer): PublicKey[] {
+    const tickSpacing = data.readUInt16LE(RAYDIUM_POOL.tickSpacing);
+    const tickCurrent = data.readInt32LE(RAYDIUM_POOL.tickCurrent);
+    const tickArrays = arrayStarts(tickCurrent, tickSpacing * RAYDIUM_POOL.tickArraySize).map(
+        (start) => {
+            const seed = Buffer.alloc(4);
+            seed.writeInt32BE(start);
+            return PublicKey.findProgramAddressSync(
+                [Buffer.from("tick_array"), pool.toBuffer(), seed],
+                RAYDIUM_CLMM_PROGRAM_ID
+            )[0];
+        }
+    );
+    return [
+        ...tickArrays,
+        pubkeyAt(data, RAYDIUM_POOL.ammConfig),
+        pubkeyAt(data, RAYDIUM_POOL.observationKey),
+        pubkeyAt(data, RAYDIUM_POOL.tokenMint0),
+        pubkeyAt(data, RAYDIUM_POOL.tokenMint1),
+        pubkeyAt(data, RAYDIUM_POOL.tokenVault0),
+        pubkeyAt(data, RAYDIUM_POOL.tokenVault1),
+    ];
+}
+
+function dlmmAccounts(lbPair: PublicKey, data: Buffer): PublicKey[] {
+    const activeId = data.readInt32LE(LB_PAIR.activeId);
+    const binArrays = arrayStarts(activeId, LB_PAIR.binsPerArray).map((firstBinId) => {
+        const seed = Buffer.alloc(8);
+        seed.writeBigInt64LE(BigInt(firstBinId / LB_PAIR.binsPerArray));
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from("bin_array"), lbPair.toBuffer(), seed],
+            DLMM_PROGRAM_ID
+        )[0];
+    });
+    return [
+        ...binArrays,
+        pubkeyAt(data, LB_PAIR.oracle),
+        pubkeyAt(data, LB_PAIR.tokenXMint),
+        pubkeyAt(data, LB_PAIR.tokenYMint),
+        pubkeyAt(data, LB_PAIR.reserveX),
+        pubkeyAt(data, LB_PAIR.reserveY),
+    ];
+}
+
+function toFixture(key: PublicKey, account: AccountInfo<Buffer>) {
+    return {
+        pubkey: key.toBase58(),
+        account: {
+            lamports: account.lamports,
+            data: [account.data.toString("base64"), "base64"],
+            owner: account.owner.toBase58(),
+            executable: account.executable,
+            // Rent is no longer collected, and u64::MAX epochs do not survive JSON numbers
+            rentEpoch: 0,
+            space: account.data.length,
+        },
+    };
+}
+
+main().catch((err) => {
+    console.error(err);
+    process.exit(1);
+});
@@ -22,8 +22,13 @@ import path from "path";
 const WHIRLPOOL_PUBKEY = new PublicKey(
     "H1esoY4v3aUcJ6bU8V3bS5yKxYv3V3bS5yKxYv3V3bS5" // Replace with real pool
 );
+// Width of the initial position on each side of the current tick
+const RANGE_TICKS = 1000;
 // =============
 
+const WHIRLPOOL_PROGRAM_ID = new PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+const VAULTS_PER_REGISTRY_PAGE = 100;
+
 async function main() {
     const provider = AnchorProvider.env();
     const connection = provider.connection;
@@ -37,21 +42,42 @@ async function main() {
     const whirlpool = WHIRLPOOL_PUBKEY;
 
     // === Derive PDAs ===
-    const [vaultPda, vaultBump] = PublicKey.findProgramAddressSync(
+    const [vaultPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("vault"), whirlpool.toBuffer()],
         program.programId
     );
 
+    // The new vault goes on the registry page holding the next vault index
+    const [protocolConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from("protocol_config")],
+        program.programId
+    );
+    const config: any = await program.account.protocolConfig.fetch(protocolConfig);
+    const page = Buffer.alloc(4);
+    page.writeUInt32LE(Math.floor(config.vaultCount.toNumber() / VAULTS_PER_REGISTRY_PAGE));
+    const [vaultRegistry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_registry"), page],
+        program.programId
+    );
+
     const positionMint = Keypair.generate();
 
     // === Get Whirlpool state to read mints ===
     const whirlpoolAccount = await connection.getAccountInfo(whirlpool);
     if (!whirlpoolAccount) throw new Error("Whirlpool not found");
 
-    // Parse mints from Whirlpool (first 64 bytes after discriminator)
+    // Parse mints, pool vaults and price from the Whirlpool account layout
     const data = whirlpoolAccount.data;
-    const tokenMintA = new PublicKey(data.subarray(8, 40));
-    const tokenMintB = new PublicKey(data.subarray(40, 72));
+    const tickSpacing = data.readUInt16LE(41);
+    const tickCurrent = data.readInt32LE(81);
+    const tokenMintA = new PublicKey(data.subarray(101, 133));
+    const poolTokenVaultA = new PublicKey(data.subarray(133, 165));
+    const tokenMintB = new PublicKey(data.subarray(181, 213));
+    const poolTokenVaultB = new PublicKey(data.subarray(213, 245));
+
+    // Initial range around the current tick, aligned to the tick spacing
+    const tickLower = Math.floor((tickCurrent - RANGE_TICKS) / tickSpacing) * tickSpacing;
+    const tickUpper = Math.ceil((tickCurrent + RANGE_TICKS) / tickSpacing) * tickSpacing;
 
     console.log("Token A (Mint):", tokenMintA.toBase58());
     console.log("Token B (Mint):", tokenMintB.toBase58());
@@ -63,10 +89,10 @@ async function main() {
     // === Position PDA (Whirlpool owns it) ===
     const [positionPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("position"), positionMint.publicKey.toBuffer()],
-        new PublicKey("whirp111111111111111111111111111111111") // Whirlpool program ID
+        WHIRLPOOL_PROGRAM_ID
     );
 
-    // === Position token account (vault owns the NFT) ===
+    // === Position token account (vault owns the NFT, Whirlpool creates it) ===
     const positionTokenAccount = getAssociatedTokenAddressSync(
         positionMint.publicKey,
         vaultPda,
@@ -100,25 +126,15 @@ async function main() {
         );
     }
 
-    // Create ATA for position NFT
-    if (!(await connection.getAccountInfo(positionTokenAccount))) {
-        tx.add(
-            web3.AssociatedTokenProgram.createAssociatedTokenAccountInstruction(
-                wallet.publicKey,
-                positionTokenAccount,
-                vaultPda,
-                positionMint.publicKey
-            )
-        );
-    }
-
-    // Initialize vault
+    // Initialize vault; the program opens the position with the vault as owner
     tx.add(
         await program.methods
-            .initializeVault(vaultBump)
+            .initializeVault({ whirlpool: {} }, tickLower, tickUpper)
             .accounts({
                 vault: vaultPda,
-                whirlpool,
+                protocolConfig,
+                vaultRegistry,
+                pool: whirlpool,
                 tokenAMint: tokenMintA,
                 tokenBMint: tokenMintB,
                 vaultTokenA,
@@ -126,9 +142,14 @@ async function main() {
                 position: positionPda,
                 positionMint: positionMint.publicKey,
                 positionTokenAccount,
+                poolTokenVaultA,
+                poolTokenVaultB,
                 authority: wallet.publicKey,
+                payer: wallet.publicKey,
                 systemProgram: SystemProgram.programId,
                 tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                dexProgram: WHIRLPOOL_PROGRAM_ID,
                 rent: web3.SYSVAR_RENT_PUBKEY,
             })
             .instruction()
@@ -140,7 +161,9 @@ async function main() {
 
     console.log("Vault initialized!");
     console.log("Vault PDA:", vaultPda.toBase58());
+    console.log("Position:", positionPda.toBase58());
     console.log("Position Mint:", positionMint.publicKey.toBase58());
+    console.log("Range:", tickLower, "to", tickUpper);
 }
 
 main().catch((err) => {
@@ -0,0 +1,382 @@
+//! Native test runtime: an in-memory account store whose instructions run on
+//! programs' entry points compiled for the host.
+//!
+//! Programs are lent their accounts laid out as the BPF loader serializes
+//! them, so reallocating, assigning and closing accounts behave as on chain.
+//! CPIs and return data go through the `solana_program` syscall stubs, which
+//! this crate replaces: the callee runs on a copy of its accounts, its changes are checked
+//! as the runtime does and copied back into the caller's. SPL Token, the
+//! associated token program and the system program instructions Anchor uses
+//! are built in; other programs are registered with `add_program`. `Clock` and
+//! `Rent` come from the harness.
+//!
+//! Anchor's `emit!` logs nothing off chain, so events cannot be observed here.
+#![allow(clippy::result_large_err)]
+
+mod runtime;
+mod system;
+
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::solana_program::entrypoint::{ProgramResult, SUCCESS};
+use anchor_lang::solana_program::instruction::{error::UNSUPPORTED_SYSVAR, Instruction};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::spl_associated_token_account;
+use anchor_spl::token::spl_token;
+use solana_sysvar::program_stubs::{self, SyscallStubs};
+
+pub use runtime::MAX_STACK_HEIGHT;
+use runtime::{Context, InstructionAccount};
+
+pub type ProgramEntry = for<'a> fn(&Pubkey, &'a [AccountInfo<'a>], &[u8]) -> ProgramResult;
+
+/// Lamports `create_wallet` funds wallets with.
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    /// Rent-exempt account holding `data`.
+    pub fn new(data: Vec<u8>, owner: Pubkey) -> Self {
+        Self {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+        }
+    }
+
+    /// Deployed program account, as `Program` accounts require.
+    pub fn executable() -> Self {
+        Self {
+            lamports: 1,
+            data: Vec::new(),
+            owner: anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+            executable: true,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Harness {
+    accounts: HashMap<Pubkey, TestAccount>,
+    programs: HashMap<Pubkey, ProgramEntry>,
+    clock: Clock,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+            solana_cpi::stubs::set_return_data_stubs(
+                program_stubs::sol_set_return_data,
+                program_stubs::sol_get_return_data,
+            );
+        });
+
+        let mut harness = Self {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            clock: Clock {
+                slot: 1_000,
+                unix_timestamp: 1_700_000_000,
+                ..Clock::default()
+            },
+            return_data: None,
+        };
+        harness.add_program(system_program::ID, system::process);
+        harness.add_program(spl_token::ID, spl_token::processor::Processor::process);
+        harness.add_program(
+            spl_associated_token_account::ID,
+            spl_associated_token_account::processor::process_instruction,
+        );
+        let rent = Rent::default();
+        let mut rent_data = Vec::new();
+        rent_data.extend(rent.lamports_per_byte_year.to_le_bytes());
+        rent_data.extend(rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+        harness.set_account(sysvar::rent::ID, TestAccount::new(rent_data, sysvar::ID));
+        harness.set_clock(harness.clock.clone());
+        harness
+    }
+
+    /// Registers `entry` as a deployed program at `program_id`.
+    pub fn add_program(&mut self, program_id: Pubkey, entry: ProgramEntry) {
+        self.programs.insert(program_id, entry);
+        self.set_account(program_id, TestAccount::executable());
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Sets the clock programs read, through `Clock::get` or the sysvar account.
+    pub fn set_clock(&mut self, clock: Clock) {
+        let mut data = Vec::new();
+        data.extend(clock.slot.to_le_bytes());
+        data.extend(clock.epoch_start_timestamp.to_le_bytes());
+        data.extend(clock.epoch.to_le_bytes());
+        data.extend(clock.leader_schedule_epoch.to_le_bytes());
+        data.extend(clock.unix_timestamp.to_le_bytes());
+        self.set_account(sysvar::clock::ID, TestAccount::new(data, sysvar::ID));
+        self.clock = clock;
+    }
+
+    /// Moves the clock `slots` slots and `secs` seconds forward.
+    pub fn advance_clock(&mut self, slots: u64, secs: i64) {
+        let mut clock = self.clock.clone();
+        clock.slot += slots;
+        clock.unix_timestamp += secs;
+        self.set_clock(clock);
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: TestAccount) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&TestAccount> {
+        self.accounts.get(key)
+    }
+
+    /// System account funded with `WALLET_LAMPORTS`.
+    pub fn create_wallet(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.set_account(
+            key,
+            TestAccount {
+                lamports: WALLET_LAMPORTS,
+                ..TestAccount::default()
+            },
+        );
+        key
+    }
+
+    /// Stores `value` with its discriminator as a rent-exempt account of its owner program.
+    pub fn set_anchor_account<T: AccountSerialize + Owner>(&mut self, key: Pubkey, value: &T) {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        self.set_account(key, TestAccount::new(data, T::owner()));
+    }
+
+    /// Deserializes the Anchor account at `key`, panicking if it is missing or invalid.
+    pub fn anchor_account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.account(key).expect("account does not exist");
+        T::try_deserialize(&mut &account.data[..]).expect("invalid account data")
+    }
+
+    pub fn create_mint(&mut self, authority: &Pubkey, decimals: u8) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(*authority),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        self.set_account(key, TestAccount::new(data, spl_token::ID));
+        key
+    }
+
+    /// Token account of `owner` for `mint` holding `amount`. The mint's supply
+    /// is not updated.
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.set_token_account(key, mint, owner, amount);
+        key
+    }
+
+    pub fn set_token_account(&mut self, key: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        self.set_account(key, TestAccount::new(data, spl_token::ID));
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        let account = self.account(key).expect("token account does not exist");
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    pub fn mint_supply(&self, key: &Pubkey) -> u64 {
+        let account = self.account(key).expect("mint does not exist");
+        spl_token::state::Mint::unpack(&account.data)
+            .unwrap()
+            .supply
+    }
+
+    /// Return data set by the last instruction processed.
+    pub fn return_data(&self) -> Option<&(Pubkey, Vec<u8>)> {
+        self.return_data.as_ref()
+    }
+
+    /// Runs `f` on `AccountInfo`s for `metas`, in order. Changes are discarded,
+    /// and `f` cannot make CPIs.
+    pub fn with_accounts<R>(
+        &self,
+        metas: &[AccountMeta],
+        f: impl for<'a> FnOnce(&'a [AccountInfo<'a>]) -> R,
+    ) -> R {
+        let (mut accounts, indices) = self.instruction_accounts(metas);
+        let previous = runtime::replace_context(Some(self.context()));
+        let result = runtime::lend::<_, std::convert::Infallible>(
+            &Pubkey::default(),
+            &mut accounts,
+            &indices,
+            &[],
+            |infos, _| Ok(f(infos)),
+        );
+        runtime::replace_context(previous);
+        let Ok(result) = result;
+        result
+    }
+
+    /// Processes `instruction` as a single-instruction transaction, keeping
+    /// changes to writable accounts if it succeeds. Accounts closed to zero
+    /// lamports are removed.
+    pub fn process(&mut self, instruction: &Instruction) -> std::result::Result<(), ProgramError> {
+        self.process_transaction(std::slice::from_ref(instruction))
+    }
+
+    /// Processes `instructions` in order, keeping their changes only if all succeed.
+    pub fn process_transaction(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> std::result::Result<(), ProgramError> {
+        let snapshot = self.accounts.clone();
+        self.return_data = None;
+        for instruction in instructions {
+            if let Err(err) = self.process_instruction(instruction) {
+                self.accounts = snapshot;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn process_instruction(&mut self, instruction: &Instruction) -> ProgramResult {
+        let (mut accounts, indices) = self.instruction_accounts(&instruction.accounts);
+        let previous = runtime::replace_context(Some(self.context()));
+        let result = runtime::execute(
+            &instruction.program_id,
+            &mut accounts,
+            &indices,
+            &instruction.data,
+        );
+        let context = runtime::replace_context(previous).unwrap();
+        self.return_data = context.return_data;
+        result?;
+
+        for InstructionAccount {
+            key,
+            account,
+            is_writable,
+            ..
+        } in accounts
+        {
+            if !is_writable {
+                continue;
+            }
+            if account.lamports == 0 {
+                self.accounts.remove(&key);
+            } else {
+                self.accounts.insert(key, account);
+            }
+        }
+        Ok(())
+    }
+
+    /// Missing accounts are lent out empty and system owned, as the runtime does.
+    fn instruction_accounts(&self, metas: &[AccountMeta]) -> (Vec<InstructionAccount>, Vec<usize>) {
+        let read = |key: &Pubkey| {
+            Ok::<_, std::convert::Infallible>(self.accounts.get(key).cloned().unwrap_or_default())
+        };
+        let Ok(accounts) = runtime::instruction_accounts(metas, read);
+        accounts
+    }
+
+    fn context(&self) -> Context {
+        Context {
+            programs: self.programs.clone(),
+            clock: self.clock.clone(),
+            frames: Vec::new(),
+            return_data: None,
+        }
+    }
+}
+
+/// Syscalls of programs run by a `Harness`, read from the thread's context.
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        runtime::invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match runtime::try_with_context(|context| context.clock.clone()) {
+            Some(clock) => {
+                // SAFETY: `Clock::get` passes a pointer to a `Clock`
+                unsafe { var_addr.cast::<Clock>().write(clock) };
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: `Rent::get` passes a pointer to a `Rent`
+        unsafe { var_addr.cast::<Rent>().write(Rent::default()) };
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        runtime::try_with_context(|context| context.return_data.clone()).flatten()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        runtime::try_with_context(|context| {
+            let program_id = context.frames.last().map(|frame| frame.program_id);
+            context.return_data = program_id
+                .filter(|_| !data.is_empty())
+                .map(|program_id| (program_id, data.to_vec()));
+        });
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        runtime::try_with_context(|context| context.frames.len() as u64).unwrap_or_default()
+    }
+}
@@ -0,0 +1,338 @@
+//! Instruction execution: the account layout programs are given, the call
+//! stack of the transaction being processed and the checks the runtime makes
+//! on what each program changed.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{
+    self, ProgramResult, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER,
+};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_error::ProgramError;
+
+use crate::{ProgramEntry, TestAccount};
+
+/// Deepest call stack the runtime allows, the top-level instruction included.
+pub const MAX_STACK_HEIGHT: usize = 5;
+
+/// Transaction being processed on this thread.
+pub(crate) struct Context {
+    pub programs: HashMap<Pubkey, ProgramEntry>,
+    pub clock: Clock,
+    pub frames: Vec<Frame>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+/// A running program and its accounts as of its start or latest CPI, which
+/// its own changes are checked against.
+pub(crate) struct Frame {
+    pub program_id: Pubkey,
+    pub accounts: Vec<InstructionAccount>,
+}
+
+/// One of an instruction's unique accounts, with the union of its flags.
+#[derive(Clone)]
+pub(crate) struct InstructionAccount {
+    pub key: Pubkey,
+    pub account: TestAccount,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn replace_context(context: Option<Context>) -> Option<Context> {
+    CONTEXT.with(|current| current.replace(context))
+}
+
+/// Runs `f` on this thread's context, `None` outside of the harness.
+pub(crate) fn try_with_context<R>(f: impl FnOnce(&mut Context) -> R) -> Option<R> {
+    CONTEXT.with(|context| context.borrow_mut().as_mut().map(f))
+}
+
+fn with_context<R>(f: impl FnOnce(&mut Context) -> R) -> R {
+    try_with_context(f).expect("no instruction is being processed on this thread")
+}
+
+/// Dedups `metas` into unique accounts, read with `read`, and the index of
+/// each meta's account among them.
+pub(crate) fn instruction_accounts<E>(
+    metas: &[AccountMeta],
+    mut read: impl FnMut(&Pubkey) -> std::result::Result<TestAccount, E>,
+) -> std::result::Result<(Vec<InstructionAccount>, Vec<usize>), E> {
+    let mut accounts: Vec<InstructionAccount> = Vec::new();
+    let mut indices = Vec::with_capacity(metas.len());
+    for meta in metas {
+        match accounts
+            .iter()
+            .position(|account| account.key == meta.pubkey)
+        {
+            Some(index) => {
+                accounts[index].is_signer |= meta.is_signer;
+                accounts[index].is_writable |= meta.is_writable;
+                indices.push(index);
+            }
+            None => {
+                indices.push(accounts.len());
+                accounts.push(InstructionAccount {
+                    key: meta.pubkey,
+                    account: read(&meta.pubkey)?,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                });
+            }
+        }
+    }
+    Ok((accounts, indices))
+}
+
+/// Serializes the accounts as the BPF loader does, so that `realloc`, `assign`
+/// and closing accounts work on what `f` is lent, then reads the accounts back
+/// if `f` succeeds.
+pub(crate) fn lend<R, E>(
+    program_id: &Pubkey,
+    accounts: &mut [InstructionAccount],
+    indices: &[usize],
+    data: &[u8],
+    f: impl for<'a> FnOnce(&'a [AccountInfo<'a>], &'a [u8]) -> std::result::Result<R, E>,
+) -> std::result::Result<R, E> {
+    let mut buffer = serialize(program_id, accounts, indices, data);
+    // SAFETY: the buffer is 8-byte aligned and laid out as `deserialize` expects
+    let (_, infos, data) = unsafe { entrypoint::deserialize(buffer.as_mut_ptr().cast()) };
+    let result = f(&infos, data)?;
+    for (index, account) in accounts.iter_mut().enumerate() {
+        let position = indices.iter().position(|i| *i == index).unwrap();
+        account.account = read_account(&infos[position]);
+    }
+    Ok(result)
+}
+
+fn serialize(
+    program_id: &Pubkey,
+    accounts: &[InstructionAccount],
+    indices: &[usize],
+    data: &[u8],
+) -> Vec<u64> {
+    let mut bytes = Vec::new();
+    bytes.extend((indices.len() as u64).to_le_bytes());
+    let mut first_position = vec![None; accounts.len()];
+    for (position, &index) in indices.iter().enumerate() {
+        if let Some(first) = first_position[index] {
+            bytes.push(first as u8);
+            bytes.extend([0; 7]);
+            continue;
+        }
+        first_position[index] = Some(position);
+        let InstructionAccount {
+            key,
+            account,
+            is_signer,
+            is_writable,
+        } = &accounts[index];
+        bytes.extend([NON_DUP_MARKER, *is_signer as u8, *is_writable as u8]);
+        bytes.push(account.executable as u8);
+        // Original data length, filled in by `deserialize`
+        bytes.extend([0; 4]);
+        bytes.extend(key.as_ref());
+        bytes.extend(account.owner.as_ref());
+        bytes.extend(account.lamports.to_le_bytes());
+        bytes.extend((account.data.len() as u64).to_le_bytes());
+        bytes.extend(&account.data);
+        bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+        // Rent epoch of rent-exempt accounts
+        bytes.extend(u64::MAX.to_le_bytes());
+    }
+    bytes.extend((data.len() as u64).to_le_bytes());
+    bytes.extend(data);
+    bytes.extend(program_id.as_ref());
+
+    let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+    // SAFETY: the buffer holds at least `bytes.len()` bytes
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.as_mut_ptr().cast(), bytes.len());
+    }
+    buffer
+}
+
+pub(crate) fn read_account(info: &AccountInfo) -> TestAccount {
+    TestAccount {
+        lamports: info.lamports(),
+        data: info.data.borrow().to_vec(),
+        owner: *info.owner,
+        executable: info.executable,
+    }
+}
+
+/// Runs `program_id` on `accounts`, leaving its changes in them if it succeeds.
+///
+/// Changes the runtime would reject - to read-only accounts, to data, lamports
+/// or owners of accounts the program does not own, or to the lamport total -
+/// panic, as they point at a bug in the program rather than at an input it
+/// should refuse.
+pub(crate) fn execute(
+    program_id: &Pubkey,
+    accounts: &mut [InstructionAccount],
+    indices: &[usize],
+    data: &[u8],
+) -> ProgramResult {
+    let entry = with_context(|context| {
+        let entry = *context
+            .programs
+            .get(program_id)
+            .ok_or(ProgramError::IncorrectProgramId)?;
+        if context.frames.len() >= MAX_STACK_HEIGHT {
+            return Err(ProgramError::Custom(u32::MAX));
+        }
+        context.frames.push(Frame {
+            program_id: *program_id,
+            accounts: accounts.to_vec(),
+        });
+        Ok(entry)
+    })?;
+    let lamports_before = total_lamports(accounts);
+
+    let result = lend(program_id, accounts, indices, data, |infos, data| {
+        entry(program_id, infos, data)
+    });
+    let frame = with_context(|context| context.frames.pop().unwrap());
+    result?;
+
+    for (pre, post) in frame.accounts.iter().zip(accounts.iter()) {
+        verify(program_id, pre, &post.account);
+    }
+    assert_eq!(
+        lamports_before,
+        total_lamports(accounts),
+        "{program_id} changed the lamport total of its accounts"
+    );
+    Ok(())
+}
+
+fn total_lamports(accounts: &[InstructionAccount]) -> u128 {
+    accounts
+        .iter()
+        .map(|account| u128::from(account.account.lamports))
+        .sum()
+}
+
+fn verify(program_id: &Pubkey, pre: &InstructionAccount, post: &TestAccount) {
+    if pre.account == *post {
+        return;
+    }
+    let key = pre.key;
+    assert!(
+        pre.is_writable,
+        "{program_id} modified read-only account {key}"
+    );
+    assert_eq!(
+        pre.account.executable, post.executable,
+        "{program_id} changed whether {key} is executable"
+    );
+    if pre.account.owner == *program_id {
+        return;
+    }
+    assert_eq!(
+        pre.account.owner, post.owner,
+        "{program_id} assigned {key}, which it does not own"
+    );
+    assert!(
+        post.lamports >= pre.account.lamports,
+        "{program_id} debited {key}, which it does not own"
+    );
+    assert_eq!(
+        pre.account.data, post.data,
+        "{program_id} modified the data of {key}, which it does not own"
+    );
+}
+
+/// Cross-program invocation from the running program, with `signers_seeds`
+/// signing for its PDAs.
+pub(crate) fn invoke_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    // The callee must be one of the caller's instruction accounts, or the caller itself
+    let (caller, callee_passed) = with_context(|context| {
+        let frame = context
+            .frames
+            .last()
+            .expect("CPI made outside of a program");
+        let callee_passed = frame.program_id == instruction.program_id
+            || frame
+                .accounts
+                .iter()
+                .any(|account| account.key == instruction.program_id);
+        (frame.program_id, callee_passed)
+    });
+    if !callee_passed {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let find = |key: &Pubkey| {
+        account_infos
+            .iter()
+            .find(|info| info.key == key)
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+    for meta in &instruction.accounts {
+        let info = find(&meta.pubkey)?;
+        if meta.is_writable && !info.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if meta.is_signer && !info.is_signer && !signers.contains(&meta.pubkey) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    }
+    let (mut accounts, indices) =
+        instruction_accounts(&instruction.accounts, |key| find(key).map(read_account))?;
+
+    // The caller's changes so far are checked, and the callee's are then its own
+    update_caller_frame(&accounts, true);
+    execute(
+        &instruction.program_id,
+        &mut accounts,
+        &indices,
+        &instruction.data,
+    )?;
+    for account in accounts.iter().filter(|account| account.is_writable) {
+        write_account(find(&account.key)?, &account.account)?;
+    }
+    update_caller_frame(&accounts, false);
+    Ok(())
+}
+
+fn update_caller_frame(accounts: &[InstructionAccount], verify_caller: bool) {
+    with_context(|context| {
+        let frame = context.frames.last_mut().unwrap();
+        for account in accounts {
+            if let Some(pre) = frame.accounts.iter_mut().find(|pre| pre.key == account.key) {
+                if verify_caller {
+                    verify(&frame.program_id, pre, &account.account);
+                }
+                pre.account = account.account.clone();
+            }
+        }
+    })
+}
+
+fn write_account(info: &AccountInfo, account: &TestAccount) -> ProgramResult {
+    **info.try_borrow_mut_lamports()? = account.lamports;
+    if *info.owner != account.owner {
+        info.assign(&account.owner);
+    }
+    if info.data_len() != account.data.len() {
+        info.resize(account.data.len())?;
+    }
+    info.try_borrow_mut_data()?.copy_from_slice(&account.data);
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! The system program instructions Anchor and SPL programs invoke.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::program_error::ProgramError;
+
+const CREATE_ACCOUNT: u32 = 0;
+const ASSIGN: u32 = 1;
+const TRANSFER: u32 = 2;
+const ALLOCATE: u32 = 8;
+
+/// `SystemError::AccountAlreadyInUse`
+const ACCOUNT_ALREADY_IN_USE: u32 = 0;
+/// `SystemError::ResultWithNegativeLamports`
+const RESULT_WITH_NEGATIVE_LAMPORTS: u32 = 1;
+
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let bytes = |at: usize, len: usize| {
+        data.get(at..at + len)
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+    let u64_at = |at: usize| bytes(at, 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+    let pubkey_at = |at: usize| bytes(at, 32).map(|b| Pubkey::try_from(b).unwrap());
+    let account = |index: usize| {
+        accounts
+            .get(index)
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+
+    match u32::from_le_bytes(bytes(0, 4)?.try_into().unwrap()) {
+        CREATE_ACCOUNT => {
+            let (from, to) = (account(0)?, account(1)?);
+            if to.lamports() > 0 {
+                return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+            }
+            allocate(to, u64_at(12)?)?;
+            assign(to, &pubkey_at(20)?)?;
+            transfer(from, to, u64_at(4)?)
+        }
+        ASSIGN => assign(account(0)?, &pubkey_at(4)?),
+        TRANSFER => transfer(account(0)?, account(1)?, u64_at(4)?),
+        ALLOCATE => allocate(account(0)?, u64_at(4)?),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+    }
+    account.resize(space as usize)
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner == owner {
+        return Ok(());
+    }
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *account.owner != system_program::ID {
+        return Err(ProgramError::IllegalOwner);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !from.data_is_empty() || *from.owner != system_program::ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let remaining = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::Custom(RESULT_WITH_NEGATIVE_LAMPORTS))?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
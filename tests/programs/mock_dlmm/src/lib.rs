@@ -0,0 +1,531 @@
+//! Local stand-in for the Meteora DLMM program, for the native test harness.
+//!
+//! It implements the instructions and account layouts vault_manager's DLMM
+//! backend uses, with simplified but deterministic bin math:
+//! - swaps fill bins at their bin price, crossing to the next bin when one runs
+//!   dry, and charge no fee;
+//! - strategies spread each token over its side of the active bin with integer
+//!   weights: equal for spot, falling with distance for curve and rising with
+//!   distance for bid-ask;
+//! - `initialize_lb_pair`, `initialize_bin_array` and `set_active_id` are
+//!   mock-only setup instructions, the last one moving the price without a trade.
+#![allow(clippy::result_large_err)]
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+pub mod math;
+pub mod pool;
+pub mod state;
+
+use state::{BinArray, Bins, LbPair, PositionV2};
+
+declare_id!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
+
+/// Shape of the liquidity distribution across the strategy's bin range.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrategyType {
+    SpotOneSide,
+    CurveOneSide,
+    BidAskOneSide,
+    SpotBalanced,
+    CurveBalanced,
+    BidAskBalanced,
+    SpotImBalanced,
+    CurveImBalanced,
+    BidAskImBalanced,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct StrategyParameters {
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub strategy_type: StrategyType,
+    pub parameteres: [u8; 64],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LiquidityParameterByStrategy {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub active_id: i32,
+    pub max_active_bin_slippage: i32,
+    pub strategy_parameters: StrategyParameters,
+}
+
+#[program]
+pub mod mock_dlmm {
+    use super::*;
+
+    pub fn initialize_lb_pair(
+        ctx: Context<InitializeLbPair>,
+        active_id: i32,
+        bin_step: u16,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        **accounts.lb_pair = LbPair::new(
+            accounts.token_x_mint.key(),
+            accounts.token_y_mint.key(),
+            accounts.reserve_x.key(),
+            accounts.reserve_y.key(),
+            accounts.oracle.key(),
+            bin_step,
+            active_id,
+        );
+        Ok(())
+    }
+
+    pub fn initialize_bin_array(ctx: Context<InitializeBinArray>, index: i64) -> Result<()> {
+        let lb_pair = &ctx.accounts.lb_pair;
+        **ctx.accounts.bin_array = BinArray::new(lb_pair.key(), index, lb_pair.bin_step)?;
+        Ok(())
+    }
+
+    /// Mock-only: moves the active bin without trading, to simulate a price move.
+    pub fn set_active_id(ctx: Context<SetActiveId>, active_id: i32) -> Result<()> {
+        ctx.accounts.lb_pair.active_id = active_id;
+        Ok(())
+    }
+
+    pub fn initialize_position(
+        ctx: Context<InitializePosition>,
+        lower_bin_id: i32,
+        width: i32,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        **accounts.position = PositionV2::new(
+            accounts.lb_pair.key(),
+            accounts.owner.key(),
+            lower_bin_id,
+            width,
+        )?;
+        Ok(())
+    }
+
+    pub fn add_liquidity_by_strategy(
+        ctx: Context<ModifyLiquidity>,
+        liquidity_parameter: LiquidityParameterByStrategy,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let bin_arrays = [
+            accounts.bin_array_lower.as_ref(),
+            accounts.bin_array_upper.as_ref(),
+        ];
+        let mut bins = Bins::load(&accounts.lb_pair.key(), bin_arrays)?;
+        let (amount_x, amount_y) = pool::add_liquidity(
+            &accounts.lb_pair,
+            &mut accounts.position,
+            &mut bins,
+            &liquidity_parameter,
+        )?;
+        bins.save(bin_arrays)?;
+
+        let token_program = accounts.token_x_program.to_account_info();
+        for (from, to, amount) in [
+            (&accounts.user_token_x, &accounts.reserve_x, amount_x),
+            (&accounts.user_token_y, &accounts.reserve_y, amount_y),
+        ] {
+            if amount > 0 {
+                token::transfer(
+                    CpiContext::new(
+                        token_program.clone(),
+                        Transfer {
+                            from: from.to_account_info(),
+                            to: to.to_account_info(),
+                            authority: accounts.sender.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_liquidity_by_range(
+        ctx: Context<ModifyLiquidity>,
+        from_bin_id: i32,
+        to_bin_id: i32,
+        bps_to_remove: u16,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let bin_arrays = [
+            accounts.bin_array_lower.as_ref(),
+            accounts.bin_array_upper.as_ref(),
+        ];
+        let mut bins = Bins::load(&accounts.lb_pair.key(), bin_arrays)?;
+        let (amount_x, amount_y) = pool::remove_liquidity(
+            &mut accounts.position,
+            &mut bins,
+            from_bin_id,
+            to_bin_id,
+            bps_to_remove,
+        )?;
+        bins.save(bin_arrays)?;
+
+        let token_program = accounts.token_x_program.to_account_info();
+        pay_out(
+            &accounts.lb_pair,
+            &token_program,
+            &accounts.reserve_x,
+            &accounts.user_token_x,
+            amount_x,
+        )?;
+        pay_out(
+            &accounts.lb_pair,
+            &token_program,
+            &accounts.reserve_y,
+            &accounts.user_token_y,
+            amount_y,
+        )
+    }
+
+    pub fn claim_fee(ctx: Context<ClaimFee>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let bin_arrays = [
+            accounts.bin_array_lower.as_ref(),
+            accounts.bin_array_upper.as_ref(),
+        ];
+        let mut bins = Bins::load(&accounts.lb_pair.key(), bin_arrays)?;
+        let (fee_x, fee_y) = pool::claim_fee(&mut accounts.position, &mut bins)?;
+        bins.save(bin_arrays)?;
+
+        let token_program = accounts.token_program.to_account_info();
+        pay_out(
+            &accounts.lb_pair,
+            &token_program,
+            &accounts.reserve_x,
+            &accounts.user_token_x,
+            fee_x,
+        )?;
+        pay_out(
+            &accounts.lb_pair,
+            &token_program,
+            &accounts.reserve_y,
+            &accounts.user_token_y,
+            fee_y,
+        )
+    }
+
+    /// Closes a position with no liquidity and nothing left to claim.
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        require!(
+            ctx.accounts.position.is_empty(),
+            MockDlmmError::NonEmptyPosition
+        );
+        Ok(())
+    }
+
+    /// Exact-input swap through the bin arrays in the remaining accounts.
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let lb_pair = &mut accounts.lb_pair;
+        let swap_for_y = accounts.user_token_in.mint == lb_pair.token_x_mint;
+        let (mint_in, mint_out, reserve_in, reserve_out) = if swap_for_y {
+            (
+                lb_pair.token_x_mint,
+                lb_pair.token_y_mint,
+                &accounts.reserve_x,
+                &accounts.reserve_y,
+            )
+        } else {
+            (
+                lb_pair.token_y_mint,
+                lb_pair.token_x_mint,
+                &accounts.reserve_y,
+                &accounts.reserve_x,
+            )
+        };
+        require!(
+            accounts.user_token_in.mint == mint_in && accounts.user_token_out.mint == mint_out,
+            MockDlmmError::MintMismatch
+        );
+
+        let mut bins = Bins::load(&lb_pair.key(), ctx.remaining_accounts)?;
+        let amount_out = pool::swap(lb_pair, &mut bins, amount_in, swap_for_y)?;
+        bins.save(ctx.remaining_accounts)?;
+        require!(
+            amount_out >= min_amount_out,
+            MockDlmmError::ExceededAmountSlippageTolerance
+        );
+
+        let token_program = accounts.token_x_program.to_account_info();
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: accounts.user_token_in.to_account_info(),
+                    to: reserve_in.to_account_info(),
+                    authority: accounts.user.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+        pay_out(
+            lb_pair,
+            &token_program,
+            reserve_out,
+            &accounts.user_token_out,
+            amount_out,
+        )
+    }
+}
+
+/// Transfers `amount` out of a pool reserve, signed by the pair.
+fn pay_out<'info>(
+    lb_pair: &Account<'info, LbPair>,
+    token_program: &AccountInfo<'info>,
+    reserve: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    lb_pair.with_signer(|signer| {
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: reserve.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: lb_pair.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(active_id: i32, bin_step: u16)]
+pub struct InitializeLbPair<'info> {
+    #[account(
+        init, payer = funder,
+        space = 8 + LbPair::INIT_SPACE,
+        seeds = [
+            token_x_mint.key().as_ref(),
+            token_y_mint.key().as_ref(),
+            &bin_step.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    pub token_x_mint: Account<'info, Mint>,
+    pub token_y_mint: Account<'info, Mint>,
+    #[account(token::mint = token_x_mint, token::authority = lb_pair)]
+    pub reserve_x: Account<'info, TokenAccount>,
+    #[account(token::mint = token_y_mint, token::authority = lb_pair)]
+    pub reserve_y: Account<'info, TokenAccount>,
+    /// CHECK: Only recorded on the pair, the mock keeps no price history
+    pub oracle: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: i64)]
+pub struct InitializeBinArray<'info> {
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    #[account(
+        init, payer = funder,
+        space = 8 + BinArray::INIT_SPACE,
+        seeds = [b"bin_array", lb_pair.key().as_ref(), &index.to_le_bytes()],
+        bump
+    )]
+    pub bin_array: Box<Account<'info, BinArray>>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetActiveId<'info> {
+    #[account(mut)]
+    pub lb_pair: Box<Account<'info, LbPair>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(init, payer = payer, space = 8 + PositionV2::INIT_SPACE)]
+    pub position: Box<Account<'info, PositionV2>>,
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: Event CPI authority, the mock emits no events
+    pub event_authority: UncheckedAccount<'info>,
+    /// CHECK: This program, for event CPI
+    pub program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(
+        mut,
+        has_one = lb_pair,
+        constraint = position.owner == sender.key() @ MockDlmmError::Unauthorized,
+    )]
+    pub position: Box<Account<'info, PositionV2>>,
+    #[account(
+        mut,
+        has_one = reserve_x, has_one = reserve_y,
+        has_one = token_x_mint, has_one = token_y_mint,
+    )]
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    /// CHECK: Not used by the mock
+    pub bin_array_bitmap_extension: Option<UncheckedAccount<'info>>,
+    #[account(mut, token::mint = token_x_mint)]
+    pub user_token_x: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = token_y_mint)]
+    pub user_token_y: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub reserve_x: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub reserve_y: Box<Account<'info, TokenAccount>>,
+    pub token_x_mint: Box<Account<'info, Mint>>,
+    pub token_y_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Loaded by the handler, may be the same account as `bin_array_upper`
+    #[account(mut)]
+    pub bin_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub bin_array_upper: UncheckedAccount<'info>,
+    pub sender: Signer<'info>,
+    pub token_x_program: Program<'info, Token>,
+    pub token_y_program: Program<'info, Token>,
+    /// CHECK: Event CPI authority, the mock emits no events
+    pub event_authority: UncheckedAccount<'info>,
+    /// CHECK: This program, for event CPI
+    pub program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFee<'info> {
+    #[account(
+        mut,
+        has_one = reserve_x, has_one = reserve_y,
+        has_one = token_x_mint, has_one = token_y_mint,
+    )]
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    #[account(
+        mut,
+        has_one = lb_pair,
+        constraint = position.owner == sender.key() @ MockDlmmError::Unauthorized,
+    )]
+    pub position: Box<Account<'info, PositionV2>>,
+    /// CHECK: Loaded by the handler, may be the same account as `bin_array_upper`
+    #[account(mut)]
+    pub bin_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub bin_array_upper: UncheckedAccount<'info>,
+    pub sender: Signer<'info>,
+    #[account(mut)]
+    pub reserve_x: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub reserve_y: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = token_x_mint)]
+    pub user_token_x: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = token_y_mint)]
+    pub user_token_y: Box<Account<'info, TokenAccount>>,
+    pub token_x_mint: Box<Account<'info, Mint>>,
+    pub token_y_mint: Box<Account<'info, Mint>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Event CPI authority, the mock emits no events
+    pub event_authority: UncheckedAccount<'info>,
+    /// CHECK: This program, for event CPI
+    pub program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(
+        mut,
+        has_one = lb_pair,
+        constraint = position.owner == sender.key() @ MockDlmmError::Unauthorized,
+        close = rent_receiver,
+    )]
+    pub position: Box<Account<'info, PositionV2>>,
+    #[account(mut)]
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    /// CHECK: Not used by the mock
+    #[account(mut)]
+    pub bin_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Not used by the mock
+    #[account(mut)]
+    pub bin_array_upper: UncheckedAccount<'info>,
+    pub sender: Signer<'info>,
+    /// CHECK: Receives the position's rent
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+    /// CHECK: Event CPI authority, the mock emits no events
+    pub event_authority: UncheckedAccount<'info>,
+    /// CHECK: This program, for event CPI
+    pub program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        mut,
+        has_one = reserve_x, has_one = reserve_y,
+        has_one = token_x_mint, has_one = token_y_mint,
+    )]
+    pub lb_pair: Box<Account<'info, LbPair>>,
+    /// CHECK: Not used by the mock
+    pub bin_array_bitmap_extension: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub reserve_x: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub reserve_y: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_token_in: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_token_out: Box<Account<'info, TokenAccount>>,
+    pub token_x_mint: Box<Account<'info, Mint>>,
+    pub token_y_mint: Box<Account<'info, Mint>>,
+    /// CHECK: The mock keeps no price history
+    #[account(mut)]
+    pub oracle: UncheckedAccount<'info>,
+    /// CHECK: Not used by the mock
+    pub host_fee_in: Option<UncheckedAccount<'info>>,
+    pub user: Signer<'info>,
+    pub token_x_program: Program<'info, Token>,
+    pub token_y_program: Program<'info, Token>,
+    /// CHECK: Event CPI authority, the mock emits no events
+    pub event_authority: UncheckedAccount<'info>,
+    /// CHECK: This program, for event CPI
+    pub program: UncheckedAccount<'info>,
+}
+
+#[error_code]
+pub enum MockDlmmError {
+    #[msg("Bin range is invalid for the position")]
+    InvalidBinRange,
+    #[msg("Bin array is missing or belongs to another pair")]
+    InvalidBinArray,
+    #[msg("Active bin moved more than the allowed slippage")]
+    ExceededBinSlippage,
+    #[msg("Basis points to remove must be between 1 and 10000")]
+    InvalidBps,
+    #[msg("Token accounts do not match the pair")]
+    MintMismatch,
+    #[msg("Signer does not own the position")]
+    Unauthorized,
+    #[msg("Not enough liquidity in the passed bin arrays")]
+    InsufficientLiquidity,
+    #[msg("Swap output is below the minimum")]
+    ExceededAmountSlippageTolerance,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Position still has liquidity, fees or rewards")]
+    NonEmptyPosition,
+}
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::MockDlmmError;
+
+pub use wide::U256;
+
+/// Kept apart from the Anchor prelude, whose `Result` alias the macro cannot use.
+#[allow(clippy::all)]
+mod wide {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+/// Q64.64 price `(1 + bin_step / 10_000) ^ bin_id`, computed like vault_manager does.
+pub fn price_x64_from_bin_id(bin_id: i32, bin_step: u16) -> Result<u128> {
+    let one = U256::one() << 64;
+    let mut base = one + (U256::from(bin_step) << 64) / U256::from(10_000u32);
+    let mut exponent = bin_id.unsigned_abs();
+    let mut price = one;
+    while exponent > 0 {
+        if exponent & 1 != 0 {
+            price = price.checked_mul(base).ok_or(MockDlmmError::MathOverflow)? >> 64;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base.checked_mul(base).ok_or(MockDlmmError::MathOverflow)? >> 64;
+        }
+    }
+    if bin_id < 0 {
+        require!(!price.is_zero(), MockDlmmError::MathOverflow);
+        price = (U256::one() << 128) / price;
+    }
+    to_u128(price)
+}
+
+/// Liquidity of `amount_x` and `amount_y` in a bin: `price * x + y`, in Q64.64 token Y units.
+pub fn liquidity_value(price: u128, amount_x: u64, amount_y: u64) -> Result<u128> {
+    to_u128(U256::from(price) * U256::from(amount_x) + (U256::from(amount_y) << 64))
+}
+
+/// Token Y received for `amount_x` at `price`, rounded down.
+pub fn x_to_y(amount_x: u64, price: u128) -> Result<u64> {
+    to_u64((U256::from(amount_x) * U256::from(price)) >> 64)
+}
+
+/// Token X received for `amount_y` at `price`, rounded down.
+pub fn y_to_x(amount_y: u64, price: u128) -> Result<u64> {
+    require!(price != 0, MockDlmmError::MathOverflow);
+    to_u64((U256::from(amount_y) << 64) / U256::from(price))
+}
+
+/// Token X needed to buy `amount_y` at `price`, rounded up.
+pub fn x_for_y(amount_y: u64, price: u128) -> Result<u64> {
+    require!(price != 0, MockDlmmError::MathOverflow);
+    let numerator = U256::from(amount_y) << 64;
+    let price = U256::from(price);
+    to_u64((numerator + price - 1) / price)
+}
+
+/// Token Y needed to buy `amount_x` at `price`, rounded up.
+pub fn y_for_x(amount_x: u64, price: u128) -> Result<u64> {
+    let product = U256::from(amount_x) * U256::from(price);
+    let rounding = if product.low_u64() == 0 { 0 } else { 1 };
+    to_u64((product >> 64) + rounding)
+}
+
+/// `a * b / denominator` computed in 256 bits, rounded down.
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator != 0, MockDlmmError::MathOverflow);
+    to_u128(U256::from(a) * U256::from(b) / U256::from(denominator))
+}
+
+fn to_u64(value: U256) -> Result<u64> {
+    require!(value <= U256::from(u64::MAX), MockDlmmError::MathOverflow);
+    Ok(value.as_u64())
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    require!(value <= U256::from(u128::MAX), MockDlmmError::MathOverflow);
+    Ok(value.as_u128())
+}
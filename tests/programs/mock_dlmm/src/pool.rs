@@ -0,0 +1,228 @@
+//! Pool state transitions behind the instructions.
+//!
+//! They only touch deserialized state and return the token amounts to move, so
+//! native tests can drive the pool without executing CPIs.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::state::{Bins, LbPair, PositionV2};
+use crate::{LiquidityParameterByStrategy, MockDlmmError, StrategyType};
+
+/// Spreads the deposit over the strategy's bin range and credits the position
+/// with shares. Returns the token X and Y amounts taken.
+pub fn add_liquidity(
+    lb_pair: &LbPair,
+    position: &mut PositionV2,
+    bins: &mut Bins,
+    parameter: &LiquidityParameterByStrategy,
+) -> Result<(u64, u64)> {
+    let strategy = parameter.strategy_parameters;
+    let (min_bin_id, max_bin_id) = (strategy.min_bin_id, strategy.max_bin_id);
+    require!(
+        min_bin_id <= max_bin_id
+            && min_bin_id >= position.lower_bin_id
+            && max_bin_id <= position.upper_bin_id,
+        MockDlmmError::InvalidBinRange
+    );
+    let active_id = lb_pair.active_id;
+    require!(
+        active_id.abs_diff(parameter.active_id) <= parameter.max_active_bin_slippage.unsigned_abs(),
+        MockDlmmError::ExceededBinSlippage
+    );
+
+    // Token X sits at and above the active bin, token Y at and below it
+    let x_amounts = distribute(
+        parameter.amount_x,
+        min_bin_id.max(active_id),
+        max_bin_id,
+        active_id,
+        strategy.strategy_type,
+    );
+    let y_amounts = distribute(
+        parameter.amount_y,
+        min_bin_id,
+        max_bin_id.min(active_id),
+        active_id,
+        strategy.strategy_type,
+    );
+
+    let (mut total_x, mut total_y) = (0u64, 0u64);
+    for bin_id in min_bin_id..=max_bin_id {
+        let amount_x = amount_for(&x_amounts, bin_id);
+        let amount_y = amount_for(&y_amounts, bin_id);
+        if amount_x == 0 && amount_y == 0 {
+            continue;
+        }
+        let bin = bins.bin(bin_id)?;
+        let index = position
+            .bin_index(bin_id)
+            .ok_or(MockDlmmError::InvalidBinRange)?;
+        position.settle(index, bin)?;
+
+        let liquidity = math::liquidity_value(bin.price, amount_x, amount_y)?;
+        let bin_liquidity = math::liquidity_value(bin.price, bin.amount_x, bin.amount_y)?;
+        let share = if bin.liquidity_supply == 0 || bin_liquidity == 0 {
+            liquidity
+        } else {
+            math::mul_div(liquidity, bin.liquidity_supply, bin_liquidity)?
+        };
+        bin.amount_x += amount_x;
+        bin.amount_y += amount_y;
+        bin.liquidity_supply += share;
+        position.liquidity_shares[index] += share;
+        total_x += amount_x;
+        total_y += amount_y;
+    }
+    Ok((total_x, total_y))
+}
+
+/// Burns `bps_to_remove` of the position's share in every bin of the range.
+/// Returns the token X and Y amounts released.
+pub fn remove_liquidity(
+    position: &mut PositionV2,
+    bins: &mut Bins,
+    from_bin_id: i32,
+    to_bin_id: i32,
+    bps_to_remove: u16,
+) -> Result<(u64, u64)> {
+    require!(
+        (1..=10_000).contains(&bps_to_remove),
+        MockDlmmError::InvalidBps
+    );
+    let (mut total_x, mut total_y) = (0u64, 0u64);
+    let from = from_bin_id.max(position.lower_bin_id);
+    let to = to_bin_id.min(position.upper_bin_id);
+    for bin_id in from..=to {
+        let index = (bin_id - position.lower_bin_id) as usize;
+        let removed = math::mul_div(
+            position.liquidity_shares[index],
+            u128::from(bps_to_remove),
+            10_000,
+        )?;
+        if removed == 0 {
+            continue;
+        }
+        let bin = bins.bin(bin_id)?;
+        position.settle(index, bin)?;
+
+        let amount_x =
+            math::mul_div(removed, u128::from(bin.amount_x), bin.liquidity_supply)? as u64;
+        let amount_y =
+            math::mul_div(removed, u128::from(bin.amount_y), bin.liquidity_supply)? as u64;
+        bin.amount_x -= amount_x;
+        bin.amount_y -= amount_y;
+        bin.liquidity_supply -= removed;
+        position.liquidity_shares[index] -= removed;
+        total_x += amount_x;
+        total_y += amount_y;
+    }
+    Ok((total_x, total_y))
+}
+
+/// Settles every bin of the position and takes its pending fees. Returns the
+/// token X and Y fees.
+pub fn claim_fee(position: &mut PositionV2, bins: &mut Bins) -> Result<(u64, u64)> {
+    let (mut fee_x, mut fee_y) = (0u64, 0u64);
+    for bin_id in position.lower_bin_id..=position.upper_bin_id {
+        let index = (bin_id - position.lower_bin_id) as usize;
+        position.settle(index, bins.bin(bin_id)?)?;
+        let fee = &mut position.fee_infos[index];
+        fee_x += std::mem::take(&mut fee.fee_x_pending);
+        fee_y += std::mem::take(&mut fee.fee_y_pending);
+    }
+    position.total_claimed_fee_x_amount += fee_x;
+    position.total_claimed_fee_y_amount += fee_y;
+    Ok((fee_x, fee_y))
+}
+
+/// Exact-input swap from the active bin, filling each bin at its price and
+/// moving to the next one when it runs dry. Returns the amount out.
+pub fn swap(
+    lb_pair: &mut LbPair,
+    bins: &mut Bins,
+    amount_in: u64,
+    swap_for_y: bool,
+) -> Result<u64> {
+    let mut active_id = lb_pair.active_id;
+    let mut remaining_in = amount_in;
+    let mut amount_out = 0u64;
+    while remaining_in > 0 {
+        let bin = bins
+            .get(active_id)
+            .ok_or(MockDlmmError::InsufficientLiquidity)?;
+        if swap_for_y {
+            let out = math::x_to_y(remaining_in, bin.price)?;
+            if out <= bin.amount_y {
+                bin.amount_x += remaining_in;
+                bin.amount_y -= out;
+                amount_out += out;
+                remaining_in = 0;
+            } else {
+                let used = math::x_for_y(bin.amount_y, bin.price)?.min(remaining_in);
+                bin.amount_x += used;
+                amount_out += std::mem::take(&mut bin.amount_y);
+                remaining_in -= used;
+                active_id -= 1;
+            }
+        } else {
+            let out = math::y_to_x(remaining_in, bin.price)?;
+            if out <= bin.amount_x {
+                bin.amount_y += remaining_in;
+                bin.amount_x -= out;
+                amount_out += out;
+                remaining_in = 0;
+            } else {
+                let used = math::y_for_x(bin.amount_x, bin.price)?.min(remaining_in);
+                bin.amount_y += used;
+                amount_out += std::mem::take(&mut bin.amount_x);
+                remaining_in -= used;
+                active_id += 1;
+            }
+        }
+    }
+    lb_pair.active_id = active_id;
+    Ok(amount_out)
+}
+
+/// Splits `amount` over `[from, to]` with the strategy's weights, rounding each bin down.
+fn distribute(
+    amount: u64,
+    from: i32,
+    to: i32,
+    active_id: i32,
+    strategy: StrategyType,
+) -> Vec<(i32, u64)> {
+    if amount == 0 || from > to {
+        return Vec::new();
+    }
+    let max_distance = from.abs_diff(active_id).max(to.abs_diff(active_id));
+    let weight = |bin_id: i32| -> u128 {
+        let distance = bin_id.abs_diff(active_id);
+        u128::from(match strategy {
+            StrategyType::SpotOneSide
+            | StrategyType::SpotBalanced
+            | StrategyType::SpotImBalanced => 1,
+            StrategyType::CurveOneSide
+            | StrategyType::CurveBalanced
+            | StrategyType::CurveImBalanced => max_distance + 1 - distance,
+            StrategyType::BidAskOneSide
+            | StrategyType::BidAskBalanced
+            | StrategyType::BidAskImBalanced => distance + 1,
+        })
+    };
+    let total_weight: u128 = (from..=to).map(weight).sum();
+    (from..=to)
+        .map(|bin_id| {
+            let share = u128::from(amount) * weight(bin_id) / total_weight;
+            (bin_id, share as u64)
+        })
+        .collect()
+}
+
+fn amount_for(amounts: &[(i32, u64)], bin_id: i32) -> u64 {
+    amounts
+        .iter()
+        .find(|(id, _)| *id == bin_id)
+        .map_or(0, |(_, amount)| *amount)
+}
@@ -0,0 +1,325 @@
+//! Account layouts of the DLMM program.
+//!
+//! The real accounts are zero-copy; their fields are laid out without padding,
+//! so the borsh encoding of these structs produces the same bytes. `LbPair`
+//! stops after the oracle, which is as far as vault_manager reads.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::MockDlmmError;
+
+pub const NUM_REWARDS: usize = 2;
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+pub const MAX_BIN_PER_POSITION: usize = 70;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct RewardInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub funder: Pubkey,
+    pub reward_duration: u64,
+    pub reward_duration_end: u64,
+    pub reward_rate: u128,
+    pub last_update_time: u64,
+    pub cumulative_seconds_with_empty_liquidity_reward: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LbPair {
+    pub parameters: [u8; 32],
+    pub v_parameters: [u8; 32],
+    pub bump_seed: [u8; 1],
+    pub bin_step_seed: [u8; 2],
+    pub pair_type: u8,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub status: u8,
+    pub require_base_factor_seed: u8,
+    pub base_factor_seed: [u8; 2],
+    pub activation_type: u8,
+    pub creator_pool_on_off_control: u8,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub protocol_fee: [u8; 16],
+    pub padding_1: [u8; 32],
+    pub reward_infos: [RewardInfo; NUM_REWARDS],
+    pub oracle: Pubkey,
+}
+
+impl LbPair {
+    pub fn new(
+        token_x_mint: Pubkey,
+        token_y_mint: Pubkey,
+        reserve_x: Pubkey,
+        reserve_y: Pubkey,
+        oracle: Pubkey,
+        bin_step: u16,
+        active_id: i32,
+    ) -> Self {
+        let (_, bump) = Pubkey::find_program_address(
+            &[
+                token_x_mint.as_ref(),
+                token_y_mint.as_ref(),
+                &bin_step.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+        Self {
+            parameters: [0; 32],
+            v_parameters: [0; 32],
+            bump_seed: [bump],
+            bin_step_seed: bin_step.to_le_bytes(),
+            pair_type: 0,
+            active_id,
+            bin_step,
+            status: 0,
+            require_base_factor_seed: 0,
+            base_factor_seed: [0; 2],
+            activation_type: 0,
+            creator_pool_on_off_control: 0,
+            token_x_mint,
+            token_y_mint,
+            reserve_x,
+            reserve_y,
+            protocol_fee: [0; 16],
+            padding_1: [0; 32],
+            reward_infos: [RewardInfo::default(); NUM_REWARDS],
+            oracle,
+        }
+    }
+
+    /// Runs `f` with the pair's PDA signer seeds.
+    pub fn with_signer<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        let seeds: &[&[u8]] = &[
+            self.token_x_mint.as_ref(),
+            self.token_y_mint.as_ref(),
+            &self.bin_step_seed,
+            &self.bump_seed,
+        ];
+        f(&[seeds])
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Bin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub price: u128,
+    pub liquidity_supply: u128,
+    pub reward_per_token_stored: [u128; NUM_REWARDS],
+    pub fee_amount_x_per_token_stored: u128,
+    pub fee_amount_y_per_token_stored: u128,
+    pub amount_x_in: u128,
+    pub amount_y_in: u128,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BinArray {
+    pub index: i64,
+    pub version: u8,
+    pub padding: [u8; 7],
+    pub lb_pair: Pubkey,
+    pub bins: [Bin; MAX_BIN_PER_ARRAY as usize],
+}
+
+impl BinArray {
+    /// Empty array `index` of `lb_pair`, with each bin's price set.
+    pub fn new(lb_pair: Pubkey, index: i64, bin_step: u16) -> Result<Self> {
+        let first_bin_id = i32::try_from(index * i64::from(MAX_BIN_PER_ARRAY))
+            .map_err(|_| MockDlmmError::InvalidBinRange)?;
+        let mut bins = [Bin::default(); MAX_BIN_PER_ARRAY as usize];
+        for (i, bin) in bins.iter_mut().enumerate() {
+            bin.price = math::price_x64_from_bin_id(first_bin_id + i as i32, bin_step)?;
+        }
+        Ok(Self {
+            index,
+            version: 0,
+            padding: [0; 7],
+            lb_pair,
+            bins,
+        })
+    }
+
+    pub fn index_of(bin_id: i32) -> i64 {
+        i64::from(bin_id.div_euclid(MAX_BIN_PER_ARRAY))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct UserRewardInfo {
+    pub reward_per_token_completes: [u128; NUM_REWARDS],
+    pub reward_pendings: [u64; NUM_REWARDS],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct FeeInfo {
+    pub fee_x_per_token_complete: u128,
+    pub fee_y_per_token_complete: u128,
+    pub fee_x_pending: u64,
+    pub fee_y_pending: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PositionV2 {
+    pub lb_pair: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity_shares: [u128; MAX_BIN_PER_POSITION],
+    pub reward_infos: [UserRewardInfo; MAX_BIN_PER_POSITION],
+    pub fee_infos: [FeeInfo; MAX_BIN_PER_POSITION],
+    pub lower_bin_id: i32,
+    pub upper_bin_id: i32,
+    pub last_updated_at: i64,
+    pub total_claimed_fee_x_amount: u64,
+    pub total_claimed_fee_y_amount: u64,
+    pub total_claimed_rewards: [u64; NUM_REWARDS],
+    pub operator: Pubkey,
+    pub lock_release_point: u64,
+    pub padding_0: u8,
+    pub fee_owner: Pubkey,
+    pub reserved: [u8; 87],
+}
+
+impl PositionV2 {
+    /// Empty position of `owner` over `width` bins from `lower_bin_id`.
+    pub fn new(lb_pair: Pubkey, owner: Pubkey, lower_bin_id: i32, width: i32) -> Result<Self> {
+        require!(
+            (1..=MAX_BIN_PER_POSITION as i32).contains(&width),
+            MockDlmmError::InvalidBinRange
+        );
+        Ok(Self {
+            lb_pair,
+            owner,
+            liquidity_shares: [0; MAX_BIN_PER_POSITION],
+            reward_infos: [UserRewardInfo::default(); MAX_BIN_PER_POSITION],
+            fee_infos: [FeeInfo::default(); MAX_BIN_PER_POSITION],
+            lower_bin_id,
+            upper_bin_id: lower_bin_id + width - 1,
+            last_updated_at: 0,
+            total_claimed_fee_x_amount: 0,
+            total_claimed_fee_y_amount: 0,
+            total_claimed_rewards: [0; NUM_REWARDS],
+            operator: Pubkey::default(),
+            lock_release_point: 0,
+            padding_0: 0,
+            fee_owner: Pubkey::default(),
+            reserved: [0; 87],
+        })
+    }
+
+    /// Whether the position has no liquidity and no pending fees or rewards.
+    pub fn is_empty(&self) -> bool {
+        self.liquidity_shares.iter().all(|share| *share == 0)
+            && self
+                .fee_infos
+                .iter()
+                .all(|fee| fee.fee_x_pending == 0 && fee.fee_y_pending == 0)
+            && self
+                .reward_infos
+                .iter()
+                .all(|reward| reward.reward_pendings.iter().all(|pending| *pending == 0))
+    }
+
+    /// Index of `bin_id` in the per-bin arrays, if the position covers it.
+    pub fn bin_index(&self, bin_id: i32) -> Option<usize> {
+        (self.lower_bin_id..=self.upper_bin_id)
+            .contains(&bin_id)
+            .then(|| (bin_id - self.lower_bin_id) as usize)
+    }
+
+    /// Credits growth since the last checkpoint of bin `index` as pending
+    /// amounts and moves the checkpoints to the bin's current growth.
+    pub fn settle(&mut self, index: usize, bin: &Bin) -> Result<()> {
+        let scaled_share = self.liquidity_shares[index] >> 64;
+        let accrued = |stored: u128, complete: u128| -> Result<u64> {
+            Ok(math::mul_div(scaled_share, stored.wrapping_sub(complete), 1 << 64)? as u64)
+        };
+
+        let fee = &mut self.fee_infos[index];
+        fee.fee_x_pending += accrued(
+            bin.fee_amount_x_per_token_stored,
+            fee.fee_x_per_token_complete,
+        )?;
+        fee.fee_y_pending += accrued(
+            bin.fee_amount_y_per_token_stored,
+            fee.fee_y_per_token_complete,
+        )?;
+        fee.fee_x_per_token_complete = bin.fee_amount_x_per_token_stored;
+        fee.fee_y_per_token_complete = bin.fee_amount_y_per_token_stored;
+
+        let reward = &mut self.reward_infos[index];
+        for i in 0..NUM_REWARDS {
+            reward.reward_pendings[i] += accrued(
+                bin.reward_per_token_stored[i],
+                reward.reward_per_token_completes[i],
+            )?;
+            reward.reward_per_token_completes[i] = bin.reward_per_token_stored[i];
+        }
+        Ok(())
+    }
+}
+
+/// Bin arrays an instruction works on, by account key. An array passed twice
+/// is only loaded, and written back, once.
+pub struct Bins {
+    arrays: Vec<(Pubkey, BinArray)>,
+}
+
+impl Bins {
+    pub fn new(arrays: Vec<(Pubkey, BinArray)>) -> Self {
+        Self { arrays }
+    }
+
+    pub fn load<'a, 'info: 'a>(
+        lb_pair: &Pubkey,
+        infos: impl IntoIterator<Item = &'a AccountInfo<'info>>,
+    ) -> Result<Self> {
+        let mut arrays: Vec<(Pubkey, BinArray)> = Vec::new();
+        for info in infos {
+            if arrays.iter().any(|(key, _)| key == info.key) {
+                continue;
+            }
+            require_keys_eq!(*info.owner, crate::ID, MockDlmmError::InvalidBinArray);
+            let array = BinArray::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require_keys_eq!(array.lb_pair, *lb_pair, MockDlmmError::InvalidBinArray);
+            arrays.push((info.key(), array));
+        }
+        Ok(Self { arrays })
+    }
+
+    /// Bin `bin_id`, if one of the arrays holds it.
+    pub fn get(&mut self, bin_id: i32) -> Option<&mut Bin> {
+        let index = BinArray::index_of(bin_id);
+        self.arrays
+            .iter_mut()
+            .find(|(_, array)| array.index == index)
+            .map(|(_, array)| &mut array.bins[bin_id.rem_euclid(MAX_BIN_PER_ARRAY) as usize])
+    }
+
+    pub fn bin(&mut self, bin_id: i32) -> Result<&mut Bin> {
+        self.get(bin_id)
+            .ok_or_else(|| error!(MockDlmmError::InvalidBinArray))
+    }
+
+    /// Writes every array back to the account in `infos` it was loaded from.
+    pub fn save<'a, 'info: 'a>(
+        &self,
+        infos: impl IntoIterator<Item = &'a AccountInfo<'info>> + Clone,
+    ) -> Result<()> {
+        for (key, array) in &self.arrays {
+            if let Some(info) = infos.clone().into_iter().find(|info| info.key == key) {
+                array.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_arrays(self) -> Vec<(Pubkey, BinArray)> {
+        self.arrays
+    }
+}
@@ -0,0 +1,236 @@
+//! Local stand-in for a Squads-style multisig program, for the native test harness.
+//!
+//! Members of a multisig propose and approve instructions. Once `threshold`
+//! members have approved a proposal, `execute` invokes its instruction signed by
+//! the multisig's authority PDA, the key that is made authority elsewhere.
+//! `Proposal::check_approved` and `authority_instruction` are the whole of
+//! `execute` besides the CPI, so native tests can run the invoked instruction
+//! themselves with the PDA signing, as `invoke_signed` would.
+#![allow(clippy::result_large_err)]
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+
+declare_id!("F71a449RCrhDbqXHDu73jEmfiiBoezyuB9UVoFQK48mT");
+
+pub const AUTHORITY_SEED: &[u8] = b"authority";
+
+#[program]
+pub mod mock_multisig {
+    use super::*;
+
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            members.len() <= 10 && threshold > 0 && usize::from(threshold) <= members.len(),
+            MockMultisigError::InvalidThreshold
+        );
+        let (_, authority_bump) = authority_address(&ctx.accounts.multisig.key());
+        *ctx.accounts.multisig = Multisig {
+            members,
+            threshold,
+            authority_bump,
+        };
+        Ok(())
+    }
+
+    /// Propose `instruction`, counting the proposer's approval.
+    pub fn propose(ctx: Context<Propose>, instruction: ProposedInstruction) -> Result<()> {
+        let proposer = ctx.accounts.proposer.key();
+        require!(
+            ctx.accounts.multisig.is_member(&proposer),
+            MockMultisigError::NotAMember
+        );
+        require!(
+            instruction.accounts.len() <= 16 && instruction.data.len() <= 256,
+            MockMultisigError::ProposalTooLarge
+        );
+        *ctx.accounts.proposal = Proposal {
+            multisig: ctx.accounts.multisig.key(),
+            instruction,
+            approvals: vec![proposer],
+            executed: false,
+        };
+        Ok(())
+    }
+
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        let member = ctx.accounts.member.key();
+        require!(
+            ctx.accounts.multisig.is_member(&member),
+            MockMultisigError::NotAMember
+        );
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, MockMultisigError::AlreadyExecuted);
+        require!(
+            !proposal.approvals.contains(&member),
+            MockMultisigError::AlreadyApproved
+        );
+        proposal.approvals.push(member);
+        Ok(())
+    }
+
+    /// Invoke an approved proposal's instruction as the multisig authority.
+    ///
+    /// Remaining accounts: the instruction's accounts, then its program.
+    pub fn execute(ctx: Context<Execute>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.check_approved(multisig)?;
+        proposal.executed = true;
+
+        let multisig_key = multisig.key();
+        let instruction = authority_instruction(&multisig_key, &proposal.instruction);
+        let bump = [multisig.authority_bump];
+        let seeds: &[&[u8]] = &[AUTHORITY_SEED, multisig_key.as_ref(), &bump];
+        invoke_signed(&instruction, ctx.remaining_accounts, &[seeds]).map_err(Into::into)
+    }
+}
+
+/// The PDA that signs for `multisig`, and its bump.
+pub fn authority_address(multisig: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_SEED, multisig.as_ref()], &ID)
+}
+
+/// `instruction` as `execute` invokes it, with the multisig's authority PDA as
+/// its only signer.
+pub fn authority_instruction(multisig: &Pubkey, instruction: &ProposedInstruction) -> Instruction {
+    let (authority, _) = authority_address(multisig);
+    Instruction {
+        program_id: instruction.program_id,
+        accounts: instruction
+            .accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.pubkey,
+                is_signer: account.pubkey == authority,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: instruction.data.clone(),
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ProposedAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ProposedInstruction {
+    pub program_id: Pubkey,
+    #[max_len(16)]
+    pub accounts: Vec<ProposedAccount>,
+    #[max_len(256)]
+    pub data: Vec<u8>,
+}
+
+/// Drops the signer flags, which `execute` sets for the authority PDA alone.
+impl From<Instruction> for ProposedInstruction {
+    fn from(instruction: Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .into_iter()
+                .map(|meta| ProposedAccount {
+                    pubkey: meta.pubkey,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    #[max_len(10)]
+    pub members: Vec<Pubkey>,
+    /// Approvals a proposal needs before it can execute.
+    pub threshold: u8,
+    pub authority_bump: u8,
+}
+
+impl Multisig {
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.members.contains(key)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub multisig: Pubkey,
+    pub instruction: ProposedInstruction,
+    #[max_len(10)]
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+}
+
+impl Proposal {
+    /// Whether `execute` may run the proposal.
+    pub fn check_approved(&self, multisig: &Multisig) -> Result<()> {
+        require!(!self.executed, MockMultisigError::AlreadyExecuted);
+        require!(
+            self.approvals.len() >= usize::from(multisig.threshold),
+            MockMultisigError::NotEnoughApprovals
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(init, payer = payer, space = 8 + Multisig::INIT_SPACE)]
+    pub multisig: Account<'info, Multisig>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Propose<'info> {
+    pub multisig: Account<'info, Multisig>,
+    #[account(init, payer = proposer, space = 8 + Proposal::INIT_SPACE)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    pub multisig: Account<'info, Multisig>,
+    #[account(mut, has_one = multisig)]
+    pub proposal: Account<'info, Proposal>,
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    pub multisig: Account<'info, Multisig>,
+    #[account(mut, has_one = multisig)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[error_code]
+pub enum MockMultisigError {
+    #[msg("Signer is not a member of the multisig")]
+    NotAMember,
+    #[msg("Threshold must be between one and the number of members")]
+    InvalidThreshold,
+    #[msg("Proposed instruction has too many accounts or too much data")]
+    ProposalTooLarge,
+    #[msg("Member already approved the proposal")]
+    AlreadyApproved,
+    #[msg("Proposal was already executed")]
+    AlreadyExecuted,
+    #[msg("Proposal does not have enough approvals")]
+    NotEnoughApprovals,
+}
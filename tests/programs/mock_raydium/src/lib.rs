@@ -0,0 +1,782 @@
+//! Local stand-in for the Raydium CLMM program, for the native test harness.
+//!
+//! It implements the instructions and account layouts vault_manager's Raydium
+//! backend uses, with the Whirlpool mock's tick math:
+//! - swaps are exact-input only and trade at the in-range liquidity the pool
+//!   has when they start, crossing the initialized ticks the price passed once
+//!   they are done; fees grow the input token's fee growth and no protocol or
+//!   fund fee is taken;
+//! - a swap that moves the tick records the old tick in the observation state,
+//!   at most once per `OBSERVATION_UPDATE_DURATION`, like the real program;
+//! - pools have no rewards, NFT metadata or protocol positions, and the tick
+//!   array bitmap and initialized tick counts are not maintained;
+//! - `create_amm_config` takes no admin, and `initialize_tick_array` is a
+//!   mock-only setup instruction for the arrays the real program creates when
+//!   opening positions.
+#![allow(clippy::result_large_err)]
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer};
+use mock_whirlpool::math;
+
+pub mod pool;
+pub mod state;
+
+use state::{
+    AmmConfig, ObservationState, PersonalPositionState, PoolState, TickArrayState, TickArrays,
+    AMM_CONFIG_SEED, OBSERVATION_SEED, POOL_SEED, POSITION_SEED, TICK_ARRAY_SEED,
+};
+
+declare_id!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+
+#[program]
+pub mod mock_raydium {
+    use super::*;
+
+    pub fn create_amm_config(
+        ctx: Context<CreateAmmConfig>,
+        index: u16,
+        tick_spacing: u16,
+        trade_fee_rate: u32,
+        protocol_fee_rate: u32,
+        fund_fee_rate: u32,
+    ) -> Result<()> {
+        require!(tick_spacing > 0, MockRaydiumError::InvalidTickSpacing);
+        require!(
+            trade_fee_rate < pool::FEE_RATE_DENOMINATOR,
+            MockRaydiumError::InvalidFeeRate
+        );
+        let accounts = &mut *ctx.accounts;
+        accounts.amm_config.set_inner(AmmConfig {
+            bump: ctx.bumps.amm_config,
+            index,
+            owner: accounts.owner.key(),
+            protocol_fee_rate,
+            trade_fee_rate,
+            tick_spacing,
+            fund_fee_rate,
+            padding_u32: 0,
+            fund_owner: accounts.owner.key(),
+            padding: [0; 3],
+        });
+        Ok(())
+    }
+
+    /// Creates the pool and its observation state. The pool's token vaults are
+    /// existing token accounts owned by the pool, rather than PDAs it creates.
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        sqrt_price_x64: u128,
+        open_time: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        require!(
+            accounts.token_mint_0.key() < accounts.token_mint_1.key(),
+            MockRaydiumError::InvalidTokenMintOrder
+        );
+        let pool_id = accounts.pool_state.key();
+        accounts.observation_state.pool_id = pool_id;
+        **accounts.pool_state = PoolState {
+            bump: [ctx.bumps.pool_state],
+            amm_config: accounts.amm_config.key(),
+            owner: accounts.pool_creator.key(),
+            token_mint_0: accounts.token_mint_0.key(),
+            token_mint_1: accounts.token_mint_1.key(),
+            token_vault_0: accounts.token_vault_0.key(),
+            token_vault_1: accounts.token_vault_1.key(),
+            observation_key: accounts.observation_state.key(),
+            mint_decimals_0: accounts.token_mint_0.decimals,
+            mint_decimals_1: accounts.token_mint_1.decimals,
+            tick_spacing: accounts.amm_config.tick_spacing,
+            liquidity: 0,
+            sqrt_price_x64,
+            tick_current: math::tick_index_from_sqrt_price(sqrt_price_x64)?,
+            padding3: 0,
+            padding4: 0,
+            fee_growth_global_0_x64: 0,
+            fee_growth_global_1_x64: 0,
+            protocol_fees_token_0: 0,
+            protocol_fees_token_1: 0,
+            swap_in_amount_token_0: 0,
+            swap_out_amount_token_1: 0,
+            swap_in_amount_token_1: 0,
+            swap_out_amount_token_0: 0,
+            status: 0,
+            padding: [0; 7],
+            reward_infos: Default::default(),
+            tick_array_bitmap: [0; 16],
+            total_fees_token_0: 0,
+            total_fees_claimed_token_0: 0,
+            total_fees_token_1: 0,
+            total_fees_claimed_token_1: 0,
+            fund_fees_token_0: 0,
+            fund_fees_token_1: 0,
+            open_time,
+            recent_epoch: Clock::get()?.epoch,
+            padding1: [0; 24],
+            padding2: [0; 32],
+        };
+        Ok(())
+    }
+
+    /// Mock-only: creates the tick array starting at `start_tick_index`.
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        let pool_state = &ctx.accounts.pool_state;
+        **ctx.accounts.tick_array =
+            TickArrayState::new(pool_state.key(), start_tick_index, pool_state.tick_spacing)?;
+        Ok(())
+    }
+
+    /// Opens a position, minting its NFT to `position_nft_owner`, and adds
+    /// `liquidity` to it when not zero.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let pool_state = &accounts.pool_state;
+        pool_state.check_tick(tick_lower_index)?;
+        pool_state.check_tick(tick_upper_index)?;
+        require!(
+            tick_lower_index < tick_upper_index,
+            MockRaydiumError::InvalidTickIndex
+        );
+        let spacing = pool_state.tick_spacing;
+        require!(
+            tick_array_lower_start_index
+                == TickArrayState::start_index_of(tick_lower_index, spacing)
+                && tick_array_upper_start_index
+                    == TickArrayState::start_index_of(tick_upper_index, spacing),
+            MockRaydiumError::InvalidTickArray
+        );
+        **accounts.personal_position = PersonalPositionState {
+            bump: [ctx.bumps.personal_position],
+            nft_mint: accounts.position_nft_mint.key(),
+            pool_id: pool_state.key(),
+            tick_lower_index,
+            tick_upper_index,
+            liquidity: 0,
+            fee_growth_inside_0_last_x64: 0,
+            fee_growth_inside_1_last_x64: 0,
+            token_fees_owed_0: 0,
+            token_fees_owed_1: 0,
+            reward_infos: Default::default(),
+            recent_epoch: Clock::get()?.epoch,
+            padding: [0; 7],
+        };
+        pool_state.with_signer(|signer| {
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: accounts.position_nft_mint.to_account_info(),
+                        to: accounts.position_nft_account.to_account_info(),
+                        authority: pool_state.to_account_info(),
+                    },
+                    signer,
+                ),
+                1,
+            )
+        })?;
+        if liquidity == 0 {
+            return Ok(());
+        }
+
+        let (amount_0, amount_1) = modify_liquidity(
+            &mut accounts.pool_state,
+            &mut accounts.personal_position,
+            [
+                accounts.tick_array_lower.as_ref(),
+                accounts.tick_array_upper.as_ref(),
+            ],
+            liquidity_delta(liquidity)?,
+        )?;
+        require!(
+            amount_0 <= amount_0_max && amount_1 <= amount_1_max,
+            MockRaydiumError::PriceSlippageCheck
+        );
+        pay_in(
+            &accounts.token_program,
+            &accounts.payer,
+            [
+                (&accounts.token_account_0, &accounts.token_vault_0, amount_0),
+                (&accounts.token_account_1, &accounts.token_vault_1, amount_1),
+            ],
+        )
+    }
+
+    /// Burns the position NFT, closing its token account, and closes the empty position.
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        require!(
+            accounts.personal_position.is_empty(),
+            MockRaydiumError::ClosePositionErr
+        );
+        let token_program = accounts.token_program.to_account_info();
+        token::burn(
+            CpiContext::new(
+                token_program.clone(),
+                Burn {
+                    mint: accounts.position_nft_mint.to_account_info(),
+                    from: accounts.position_nft_account.to_account_info(),
+                    authority: accounts.nft_owner.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        token::close_account(CpiContext::new(
+            token_program,
+            CloseAccount {
+                account: accounts.position_nft_account.to_account_info(),
+                destination: accounts.nft_owner.to_account_info(),
+                authority: accounts.nft_owner.to_account_info(),
+            },
+        ))
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<IncreaseLiquidity>,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        require!(liquidity > 0, MockRaydiumError::ZeroLiquidity);
+        let (amount_0, amount_1) = modify_liquidity(
+            &mut accounts.pool_state,
+            &mut accounts.personal_position,
+            [
+                accounts.tick_array_lower.as_ref(),
+                accounts.tick_array_upper.as_ref(),
+            ],
+            liquidity_delta(liquidity)?,
+        )?;
+        require!(
+            amount_0 <= amount_0_max && amount_1 <= amount_1_max,
+            MockRaydiumError::PriceSlippageCheck
+        );
+        pay_in(
+            &accounts.token_program,
+            &accounts.nft_owner,
+            [
+                (&accounts.token_account_0, &accounts.token_vault_0, amount_0),
+                (&accounts.token_account_1, &accounts.token_vault_1, amount_1),
+            ],
+        )
+    }
+
+    /// Removes `liquidity` and pays it out with all the fees the position is
+    /// owed; zero liquidity only collects the fees. The minimums bound the
+    /// liquidity amounts, not the fees.
+    pub fn decrease_liquidity(
+        ctx: Context<DecreaseLiquidity>,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let delta = liquidity_delta(liquidity)?
+            .checked_neg()
+            .ok_or(MockRaydiumError::MathOverflow)?;
+        let (amount_0, amount_1) = modify_liquidity(
+            &mut accounts.pool_state,
+            &mut accounts.personal_position,
+            [
+                accounts.tick_array_lower.as_ref(),
+                accounts.tick_array_upper.as_ref(),
+            ],
+            delta,
+        )?;
+        require!(
+            amount_0 >= amount_0_min && amount_1 >= amount_1_min,
+            MockRaydiumError::PriceSlippageCheck
+        );
+        let (fees_0, fees_1) = pool::collect_fees(&mut accounts.personal_position);
+
+        let token_program = accounts.token_program.to_account_info();
+        for (vault, recipient, amount) in [
+            (
+                &accounts.token_vault_0,
+                &accounts.recipient_token_account_0,
+                amount_0 + fees_0,
+            ),
+            (
+                &accounts.token_vault_1,
+                &accounts.recipient_token_account_1,
+                amount_1 + fees_1,
+            ),
+        ] {
+            pay_out(
+                &accounts.pool_state,
+                &token_program,
+                vault,
+                recipient,
+                amount,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Exact-input swap through `tick_array` and the tick arrays in the
+    /// remaining accounts, from `input_vault`'s token to `output_vault`'s.
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+    ) -> Result<()> {
+        require!(is_base_input, MockRaydiumError::ExactOutputUnsupported);
+        let accounts = &mut *ctx.accounts;
+        let pool_state = &accounts.pool_state;
+        let zero_for_one = if accounts.input_vault.key() == pool_state.token_vault_0
+            && accounts.output_vault.key() == pool_state.token_vault_1
+        {
+            true
+        } else if accounts.input_vault.key() == pool_state.token_vault_1
+            && accounts.output_vault.key() == pool_state.token_vault_0
+        {
+            false
+        } else {
+            return err!(MockRaydiumError::InvalidInputPoolVault);
+        };
+        require!(
+            accounts.input_token_account.mint == accounts.input_vault.mint
+                && accounts.output_token_account.mint == accounts.output_vault.mint,
+            MockRaydiumError::MintMismatch
+        );
+
+        let tick_arrays: Vec<&AccountInfo<'info>> = std::iter::once(accounts.tick_array.as_ref())
+            .chain(ctx.remaining_accounts)
+            .collect();
+        let pool_state = &mut accounts.pool_state;
+        let tick_before = pool_state.tick_current;
+        let mut ticks = TickArrays::load(
+            &pool_state.key(),
+            pool_state.tick_spacing,
+            tick_arrays.iter().copied(),
+        )?;
+        let (amount_in, amount_out) = pool::swap(
+            pool_state,
+            &mut ticks,
+            amount,
+            sqrt_price_limit_x64,
+            zero_for_one,
+            accounts.amm_config.trade_fee_rate,
+        )?;
+        ticks.save(tick_arrays.iter().copied())?;
+        require!(
+            amount_out >= other_amount_threshold,
+            MockRaydiumError::TooLittleOutputReceived
+        );
+        if pool_state.tick_current != tick_before {
+            let now = u32::try_from(Clock::get()?.unix_timestamp)
+                .map_err(|_| MockRaydiumError::MathOverflow)?;
+            accounts.observation_state.update(now, tick_before);
+        }
+
+        let token_program = accounts.token_program.to_account_info();
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: accounts.input_token_account.to_account_info(),
+                    to: accounts.input_vault.to_account_info(),
+                    authority: accounts.payer.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+        pay_out(
+            &accounts.pool_state,
+            &token_program,
+            &accounts.output_vault,
+            &accounts.output_token_account,
+            amount_out,
+        )
+    }
+}
+
+fn liquidity_delta(liquidity: u128) -> Result<i128> {
+    i128::try_from(liquidity).map_err(|_| error!(MockRaydiumError::MathOverflow))
+}
+
+/// Runs the liquidity change on the pool and writes the tick arrays back.
+fn modify_liquidity<'a, 'info: 'a>(
+    pool_state: &mut PoolState,
+    position: &mut PersonalPositionState,
+    tick_arrays: [&'a AccountInfo<'info>; 2],
+    liquidity_delta: i128,
+) -> Result<(u64, u64)> {
+    let mut ticks = TickArrays::load(&position.pool_id, pool_state.tick_spacing, tick_arrays)?;
+    let amounts = pool::modify_liquidity(pool_state, position, &mut ticks, liquidity_delta)?;
+    ticks.save(tick_arrays)?;
+    Ok(amounts)
+}
+
+/// Whether `token_account` is `owner`'s account holding the position NFT.
+fn holds_position(
+    token_account: &TokenAccount,
+    position: &PersonalPositionState,
+    owner: &Pubkey,
+) -> bool {
+    token_account.mint == position.nft_mint
+        && token_account.owner == *owner
+        && token_account.amount == 1
+}
+
+/// Transfers each `(from, to, amount)` into the pool, signed by `authority`.
+fn pay_in<'info>(
+    token_program: &Program<'info, Token>,
+    authority: &Signer<'info>,
+    transfers: [(
+        &Account<'info, TokenAccount>,
+        &Account<'info, TokenAccount>,
+        u64,
+    ); 2],
+) -> Result<()> {
+    for (from, to, amount) in transfers {
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: authority.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Transfers `amount` out of a pool token vault, signed by the pool.
+fn pay_out<'info>(
+    pool_state: &Account<'info, PoolState>,
+    token_program: &AccountInfo<'info>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    pool_state.with_signer(|signer| {
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: vault.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: pool_state.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(index: u16)]
+pub struct CreateAmmConfig<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        init, payer = owner,
+        space = 8 + AmmConfig::INIT_SPACE,
+        seeds = [AMM_CONFIG_SEED, &index.to_be_bytes()],
+        bump
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(mut)]
+    pub pool_creator: Signer<'info>,
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+    #[account(
+        init, payer = pool_creator,
+        space = 8 + PoolState::INIT_SPACE,
+        seeds = [
+            POOL_SEED,
+            amm_config.key().as_ref(),
+            token_mint_0.key().as_ref(),
+            token_mint_1.key().as_ref(),
+        ],
+        bump
+    )]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    pub token_mint_0: Box<Account<'info, Mint>>,
+    pub token_mint_1: Box<Account<'info, Mint>>,
+    #[account(token::mint = token_mint_0, token::authority = pool_state)]
+    pub token_vault_0: Box<Account<'info, TokenAccount>>,
+    #[account(token::mint = token_mint_1, token::authority = pool_state)]
+    pub token_vault_1: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init, payer = pool_creator,
+        space = 8 + ObservationState::INIT_SPACE,
+        seeds = [OBSERVATION_SEED, pool_state.key().as_ref()],
+        bump
+    )]
+    pub observation_state: Box<Account<'info, ObservationState>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    pub pool_state: Box<Account<'info, PoolState>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init, payer = payer,
+        space = 8 + TickArrayState::INIT_SPACE,
+        seeds = [
+            TICK_ARRAY_SEED,
+            pool_state.key().as_ref(),
+            &start_tick_index.to_be_bytes(),
+        ],
+        bump
+    )]
+    pub tick_array: Box<Account<'info, TickArrayState>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Receives the position NFT
+    pub position_nft_owner: UncheckedAccount<'info>,
+    #[account(init, payer = payer, mint::decimals = 0, mint::authority = pool_state)]
+    pub position_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init, payer = payer,
+        associated_token::mint = position_nft_mint,
+        associated_token::authority = position_nft_owner,
+    )]
+    pub position_nft_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: The mock creates no NFT metadata
+    #[account(mut)]
+    pub metadata_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: The mock keeps no protocol positions
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler, may be the same account as `tick_array_upper`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+    #[account(
+        init, payer = payer,
+        space = 8 + PersonalPositionState::INIT_SPACE,
+        seeds = [POSITION_SEED, position_nft_mint.key().as_ref()],
+        bump
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    #[account(
+        mut,
+        constraint = token_account_0.mint == pool_state.token_mint_0
+            @ MockRaydiumError::MintMismatch,
+    )]
+    pub token_account_0: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_account_1.mint == pool_state.token_mint_1
+            @ MockRaydiumError::MintMismatch,
+    )]
+    pub token_account_1: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool_state.token_vault_0)]
+    pub token_vault_0: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool_state.token_vault_1)]
+    pub token_vault_1: Box<Account<'info, TokenAccount>>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: The mock creates no NFT metadata
+    pub metadata_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+    #[account(mut, address = personal_position.nft_mint)]
+    pub position_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = holds_position(
+            &position_nft_account, &personal_position, nft_owner.key
+        ) @ MockRaydiumError::NotApproved,
+    )]
+    pub position_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, close = nft_owner)]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct IncreaseLiquidity<'info> {
+    pub nft_owner: Signer<'info>,
+    #[account(
+        constraint = holds_position(
+            &nft_account, &personal_position, nft_owner.key
+        ) @ MockRaydiumError::NotApproved,
+    )]
+    pub nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: The mock keeps no protocol positions
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    /// CHECK: Loaded by the handler, may be the same account as `tick_array_upper`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = token_account_0.mint == pool_state.token_mint_0
+            @ MockRaydiumError::MintMismatch,
+    )]
+    pub token_account_0: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_account_1.mint == pool_state.token_mint_1
+            @ MockRaydiumError::MintMismatch,
+    )]
+    pub token_account_1: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool_state.token_vault_0)]
+    pub token_vault_0: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool_state.token_vault_1)]
+    pub token_vault_1: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DecreaseLiquidity<'info> {
+    pub nft_owner: Signer<'info>,
+    #[account(
+        constraint = holds_position(
+            &nft_account, &personal_position, nft_owner.key
+        ) @ MockRaydiumError::NotApproved,
+    )]
+    pub nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    #[account(mut)]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    /// CHECK: The mock keeps no protocol positions
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
+    #[account(mut, address = pool_state.token_vault_0)]
+    pub token_vault_0: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool_state.token_vault_1)]
+    pub token_vault_1: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Loaded by the handler, may be the same account as `tick_array_upper`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = recipient_token_account_0.mint == pool_state.token_mint_0
+            @ MockRaydiumError::MintMismatch,
+    )]
+    pub recipient_token_account_0: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = recipient_token_account_1.mint == pool_state.token_mint_1
+            @ MockRaydiumError::MintMismatch,
+    )]
+    pub recipient_token_account_1: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub payer: Signer<'info>,
+    #[account(address = pool_state.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+    #[account(mut)]
+    pub pool_state: Box<Account<'info, PoolState>>,
+    #[account(mut)]
+    pub input_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub output_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub input_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub output_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = pool_state.observation_key)]
+    pub observation_state: Box<Account<'info, ObservationState>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Loaded by the handler, arrays not yet created are skipped
+    #[account(mut)]
+    pub tick_array: UncheckedAccount<'info>,
+}
+
+#[error_code]
+pub enum MockRaydiumError {
+    #[msg("Tick spacing must be positive")]
+    InvalidTickSpacing,
+    #[msg("Fee rate must be below 100%")]
+    InvalidFeeRate,
+    #[msg("Token mint 0 must sort before token mint 1")]
+    InvalidTokenMintOrder,
+    #[msg("Tick index is out of range or not a multiple of the tick spacing")]
+    InvalidTickIndex,
+    #[msg("Tick array is missing or belongs to another pool")]
+    InvalidTickArray,
+    #[msg("Liquidity must not be zero")]
+    ZeroLiquidity,
+    #[msg("Liquidity would go below zero")]
+    LiquiditySubValueErr,
+    #[msg("Token amounts are outside the slippage bounds")]
+    PriceSlippageCheck,
+    #[msg("Position still holds liquidity or fees")]
+    ClosePositionErr,
+    #[msg("Token accounts do not match the pool")]
+    MintMismatch,
+    #[msg("Signer does not hold the position")]
+    NotApproved,
+    #[msg("Input and output vaults are not the pool's")]
+    InvalidInputPoolVault,
+    #[msg("Sqrt price limit is on the wrong side of the price or out of range")]
+    SqrtPriceLimitOverflow,
+    #[msg("Swap amount must not be zero")]
+    ZeroAmountSpecified,
+    #[msg("No liquidity at the current price")]
+    LiquidityInsufficient,
+    #[msg("Only exact-input swaps are supported")]
+    ExactOutputUnsupported,
+    #[msg("Swap output is below the minimum")]
+    TooLittleOutputReceived,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
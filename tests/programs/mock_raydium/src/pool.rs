@@ -0,0 +1,317 @@
+//! Pool state transitions behind the instructions.
+//!
+//! They only touch deserialized state and return the token amounts to move, so
+//! native tests can drive the pool without executing CPIs.
+
+use anchor_lang::prelude::*;
+use mock_whirlpool::math;
+
+use crate::state::{PersonalPositionState, PoolState, TickArrays, TickState};
+use crate::MockRaydiumError;
+
+/// Swap fee rates are in hundredths of a basis point.
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// Fee growth per unit of liquidity, either pool-wide or inside a range.
+#[derive(Clone, Copy, Default)]
+struct Growth {
+    fee_0: u128,
+    fee_1: u128,
+}
+
+impl Growth {
+    fn global(pool: &PoolState) -> Self {
+        Self {
+            fee_0: pool.fee_growth_global_0_x64,
+            fee_1: pool.fee_growth_global_1_x64,
+        }
+    }
+
+    fn outside(tick: &TickState) -> Self {
+        Self {
+            fee_0: tick.fee_growth_outside_0_x64,
+            fee_1: tick.fee_growth_outside_1_x64,
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            fee_0: self.fee_0.wrapping_sub(other.fee_0),
+            fee_1: self.fee_1.wrapping_sub(other.fee_1),
+        }
+    }
+
+    fn store_outside(self, tick: &mut TickState) {
+        tick.fee_growth_outside_0_x64 = self.fee_0;
+        tick.fee_growth_outside_1_x64 = self.fee_1;
+    }
+}
+
+/// Adds `liquidity_delta` to the position, or removes it when negative, then
+/// accrues the fees its range earned. Returns the token 0 and 1 amounts owed
+/// to the pool, rounded up, or paid out of it, rounded down. A zero delta
+/// only accrues fees.
+pub fn modify_liquidity(
+    pool: &mut PoolState,
+    position: &mut PersonalPositionState,
+    ticks: &mut TickArrays,
+    liquidity_delta: i128,
+) -> Result<(u64, u64)> {
+    let (lower_index, upper_index) = (position.tick_lower_index, position.tick_upper_index);
+    let mut lower = *ticks.tick(lower_index)?;
+    let mut upper = *ticks.tick(upper_index)?;
+    if liquidity_delta != 0 {
+        update_tick(pool, &mut lower, liquidity_delta, false)?;
+        update_tick(pool, &mut upper, liquidity_delta, true)?;
+    }
+    settle(pool, position, &lower, &upper)?;
+    if liquidity_delta == 0 {
+        return Ok((0, 0));
+    }
+    position.liquidity = add_delta(position.liquidity, liquidity_delta)?;
+
+    // A tick no position references any more is cleared
+    for (index, mut tick) in [(lower_index, lower), (upper_index, upper)] {
+        if !tick.initialized() {
+            tick = TickState {
+                tick: tick.tick,
+                ..TickState::default()
+            };
+        }
+        *ticks.tick(index)? = tick;
+    }
+    if (lower_index..upper_index).contains(&pool.tick_current) {
+        pool.liquidity = add_delta(pool.liquidity, liquidity_delta)?;
+    }
+
+    amounts_for_liquidity(
+        pool,
+        lower_index,
+        upper_index,
+        liquidity_delta.unsigned_abs(),
+        liquidity_delta > 0,
+    )
+}
+
+/// Token 0 and 1 amounts `liquidity` over `[lower_index, upper_index)` holds
+/// at the current price.
+pub fn amounts_for_liquidity(
+    pool: &PoolState,
+    lower_index: i32,
+    upper_index: i32,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    let sqrt_lower = math::sqrt_price_from_tick_index(lower_index)?;
+    let sqrt_upper = math::sqrt_price_from_tick_index(upper_index)?;
+    let current = pool.tick_current;
+    if current < lower_index {
+        Ok((
+            math::amount_a_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+            0,
+        ))
+    } else if current < upper_index {
+        Ok((
+            math::amount_a_delta(pool.sqrt_price_x64, sqrt_upper, liquidity, round_up)?,
+            math::amount_b_delta(sqrt_lower, pool.sqrt_price_x64, liquidity, round_up)?,
+        ))
+    } else {
+        Ok((
+            0,
+            math::amount_b_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+        ))
+    }
+}
+
+/// Takes the position's owed fees. Returns the token 0 and 1 fees.
+pub fn collect_fees(position: &mut PersonalPositionState) -> (u64, u64) {
+    (
+        std::mem::take(&mut position.token_fees_owed_0),
+        std::mem::take(&mut position.token_fees_owed_1),
+    )
+}
+
+/// Exact-input swap at the pool's in-range liquidity, which stays constant for
+/// the whole trade; the initialized ticks the price passed are crossed once it
+/// is done. Stops early at `sqrt_price_limit`, zero meaning no limit. Returns
+/// the amounts in, fee included, and out.
+pub fn swap(
+    pool: &mut PoolState,
+    ticks: &mut TickArrays,
+    amount: u64,
+    sqrt_price_limit: u128,
+    zero_for_one: bool,
+    trade_fee_rate: u32,
+) -> Result<(u64, u64)> {
+    let sqrt_price = pool.sqrt_price_x64;
+    let limit = match (sqrt_price_limit, zero_for_one) {
+        (0, true) => math::MIN_SQRT_PRICE_X64 + 1,
+        (0, false) => math::MAX_SQRT_PRICE_X64 - 1,
+        (limit, _) => limit,
+    };
+    require!(
+        if zero_for_one {
+            limit > math::MIN_SQRT_PRICE_X64 && limit < sqrt_price
+        } else {
+            limit > sqrt_price && limit < math::MAX_SQRT_PRICE_X64
+        },
+        MockRaydiumError::SqrtPriceLimitOverflow
+    );
+    require!(amount > 0, MockRaydiumError::ZeroAmountSpecified);
+    let liquidity = pool.liquidity;
+    require!(liquidity > 0, MockRaydiumError::LiquidityInsufficient);
+
+    let amount_less_fee = amount - fee_amount(amount, trade_fee_rate);
+    // Past u64, the limit is out of reach of any input
+    let to_limit = if zero_for_one {
+        math::amount_a_delta(limit, sqrt_price, liquidity, true)
+    } else {
+        math::amount_b_delta(sqrt_price, limit, liquidity, true)
+    }
+    .unwrap_or(u64::MAX);
+
+    let (next_sqrt_price, amount_in, fee) = if amount_less_fee >= to_limit {
+        let fee = fee_for_net(to_limit, trade_fee_rate)?;
+        (limit, to_limit + fee, fee)
+    } else if zero_for_one {
+        let next = math::next_sqrt_price_from_a_in(sqrt_price, liquidity, amount_less_fee)?;
+        (next, amount, amount - amount_less_fee)
+    } else {
+        let next = math::next_sqrt_price_from_b_in(sqrt_price, liquidity, amount_less_fee)?;
+        (next, amount, amount - amount_less_fee)
+    };
+    let amount_out = if zero_for_one {
+        math::amount_b_delta(next_sqrt_price, sqrt_price, liquidity, false)?
+    } else {
+        math::amount_a_delta(sqrt_price, next_sqrt_price, liquidity, false)?
+    };
+
+    let growth = math::growth(fee, liquidity)?;
+    if zero_for_one {
+        pool.fee_growth_global_0_x64 = pool.fee_growth_global_0_x64.wrapping_add(growth);
+    } else {
+        pool.fee_growth_global_1_x64 = pool.fee_growth_global_1_x64.wrapping_add(growth);
+    }
+    move_price(pool, ticks, next_sqrt_price)?;
+    Ok((amount_in, amount_out))
+}
+
+/// Sets the price, crossing the initialized ticks of `ticks` between the old
+/// and new tick so the in-range liquidity and each tick's outside growth
+/// follow. Ticks in arrays that were not passed are not crossed.
+fn move_price(pool: &mut PoolState, ticks: &mut TickArrays, sqrt_price: u128) -> Result<()> {
+    let tick_current = math::tick_index_from_sqrt_price(sqrt_price)?;
+    let previous = pool.tick_current;
+    let global = Growth::global(pool);
+    let mut liquidity = pool.liquidity;
+
+    let (from, to, downward) = if tick_current < previous {
+        (tick_current, previous, true)
+    } else {
+        (previous, tick_current, false)
+    };
+    for tick in ticks.initialized_between(from, to) {
+        global.sub(Growth::outside(tick)).store_outside(tick);
+        let net = if downward {
+            tick.liquidity_net
+                .checked_neg()
+                .ok_or(MockRaydiumError::MathOverflow)?
+        } else {
+            tick.liquidity_net
+        };
+        liquidity = add_delta(liquidity, net)?;
+    }
+
+    pool.liquidity = liquidity;
+    pool.sqrt_price_x64 = sqrt_price;
+    pool.tick_current = tick_current;
+    Ok(())
+}
+
+/// Adds a position's `liquidity_delta` to one of its bounds. A tick becoming
+/// initialized counts all growth so far as below it when the price is at or
+/// above it.
+fn update_tick(
+    pool: &PoolState,
+    tick: &mut TickState,
+    liquidity_delta: i128,
+    upper: bool,
+) -> Result<()> {
+    if !tick.initialized() {
+        let outside = if tick.tick <= pool.tick_current {
+            Growth::global(pool)
+        } else {
+            Growth::default()
+        };
+        outside.store_outside(tick);
+    }
+    tick.liquidity_gross = add_delta(tick.liquidity_gross, liquidity_delta)?;
+    tick.liquidity_net = if upper {
+        tick.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        tick.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(MockRaydiumError::MathOverflow)?;
+    Ok(())
+}
+
+/// Accrues fee growth inside the position's range at its current liquidity
+/// and moves its checkpoints to the current growth.
+fn settle(
+    pool: &PoolState,
+    position: &mut PersonalPositionState,
+    lower: &TickState,
+    upper: &TickState,
+) -> Result<()> {
+    let global = Growth::global(pool);
+    let current = pool.tick_current;
+    let below = if current >= position.tick_lower_index {
+        Growth::outside(lower)
+    } else {
+        global.sub(Growth::outside(lower))
+    };
+    let above = if current < position.tick_upper_index {
+        Growth::outside(upper)
+    } else {
+        global.sub(Growth::outside(upper))
+    };
+    let inside = global.sub(below).sub(above);
+
+    let liquidity = position.liquidity;
+    let accrue = |owed: u64, growth: u128, checkpoint: u128| -> Result<u64> {
+        owed.checked_add(math::owed(growth.wrapping_sub(checkpoint), liquidity)?)
+            .ok_or_else(|| error!(MockRaydiumError::MathOverflow))
+    };
+    position.token_fees_owed_0 = accrue(
+        position.token_fees_owed_0,
+        inside.fee_0,
+        position.fee_growth_inside_0_last_x64,
+    )?;
+    position.token_fees_owed_1 = accrue(
+        position.token_fees_owed_1,
+        inside.fee_1,
+        position.fee_growth_inside_1_last_x64,
+    )?;
+    position.fee_growth_inside_0_last_x64 = inside.fee_0;
+    position.fee_growth_inside_1_last_x64 = inside.fee_1;
+    Ok(())
+}
+
+/// Fee on `amount` at `fee_rate`, rounded up.
+fn fee_amount(amount: u64, fee_rate: u32) -> u64 {
+    let product = u128::from(amount) * u128::from(fee_rate);
+    product.div_ceil(u128::from(FEE_RATE_DENOMINATOR)) as u64
+}
+
+/// Fee to add on top of a net input of `amount` at `fee_rate`, rounded up.
+fn fee_for_net(amount: u64, fee_rate: u32) -> Result<u64> {
+    let product = u128::from(amount) * u128::from(fee_rate);
+    let fee = product.div_ceil(u128::from(FEE_RATE_DENOMINATOR - fee_rate));
+    u64::try_from(fee).map_err(|_| error!(MockRaydiumError::MathOverflow))
+}
+
+fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    liquidity
+        .checked_add_signed(delta)
+        .ok_or_else(|| error!(MockRaydiumError::LiquiditySubValueErr))
+}
@@ -0,0 +1,333 @@
+//! Account layouts of the Raydium CLMM program.
+//!
+//! `PoolState`, `TickArrayState` and `ObservationState` are zero-copy and
+//! `repr(C, packed)` on chain, so the borsh encoding of these structs produces
+//! the same bytes.
+
+use anchor_lang::prelude::*;
+use mock_whirlpool::math;
+
+use crate::MockRaydiumError;
+
+pub const NUM_REWARDS: usize = 3;
+pub const TICK_ARRAY_SIZE: i32 = 60;
+pub const OBSERVATION_NUM: usize = 100;
+/// Seconds a swap waits after the newest observation before recording another.
+pub const OBSERVATION_UPDATE_DURATION: u32 = 15;
+
+pub const AMM_CONFIG_SEED: &[u8] = b"amm_config";
+pub const POOL_SEED: &[u8] = b"pool";
+pub const POSITION_SEED: &[u8] = b"position";
+pub const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+pub const OBSERVATION_SEED: &[u8] = b"observation";
+
+#[account]
+#[derive(InitSpace)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    pub protocol_fee_rate: u32,
+    /// Swap fee, in hundredths of a basis point.
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+    pub padding_u32: u32,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 3],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct RewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PoolState {
+    pub bump: [u8; 1],
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub padding3: u16,
+    pub padding4: u16,
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    pub status: u8,
+    pub padding: [u8; 7],
+    pub reward_infos: [RewardInfo; NUM_REWARDS],
+    pub tick_array_bitmap: [u64; 16],
+    pub total_fees_token_0: u64,
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    pub open_time: u64,
+    pub recent_epoch: u64,
+    pub padding1: [u64; 24],
+    pub padding2: [u64; 32],
+}
+
+impl PoolState {
+    /// Runs `f` with the pool's PDA signer seeds.
+    pub fn with_signer<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        let seeds: &[&[u8]] = &[
+            POOL_SEED,
+            self.amm_config.as_ref(),
+            self.token_mint_0.as_ref(),
+            self.token_mint_1.as_ref(),
+            &self.bump,
+        ];
+        f(&[seeds])
+    }
+
+    /// Checks `tick_index` can bound a position in this pool.
+    pub fn check_tick(&self, tick_index: i32) -> Result<()> {
+        require!(
+            (math::MIN_TICK_INDEX..=math::MAX_TICK_INDEX).contains(&tick_index)
+                && tick_index % i32::from(self.tick_spacing) == 0,
+            MockRaydiumError::InvalidTickIndex
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct PositionRewardInfo {
+    pub growth_inside_last_x64: u128,
+    pub reward_amount_owed: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PersonalPositionState {
+    pub bump: [u8; 1],
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x64: u128,
+    pub fee_growth_inside_1_last_x64: u128,
+    pub token_fees_owed_0: u64,
+    pub token_fees_owed_1: u64,
+    pub reward_infos: [PositionRewardInfo; NUM_REWARDS],
+    pub recent_epoch: u64,
+    pub padding: [u64; 7],
+}
+
+impl PersonalPositionState {
+    pub fn is_empty(&self) -> bool {
+        self.liquidity == 0 && self.token_fees_owed_0 == 0 && self.token_fees_owed_1 == 0
+    }
+}
+
+/// One tick of a `TickArrayState`. Raydium has no initialized flag: a tick is
+/// initialized while some position's liquidity references it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct TickState {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_0_x64: u128,
+    pub fee_growth_outside_1_x64: u128,
+    pub reward_growths_outside_x64: [u128; NUM_REWARDS],
+    pub padding: [u32; 13],
+}
+
+impl TickState {
+    pub fn initialized(&self) -> bool {
+        self.liquidity_gross != 0
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TickArrayState {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: [TickState; TICK_ARRAY_SIZE as usize],
+    pub initialized_tick_count: u8,
+    pub recent_epoch: u64,
+    pub padding: [u8; 107],
+}
+
+impl TickArrayState {
+    /// Empty array of `pool_id` starting at `start_tick_index`.
+    pub fn new(pool_id: Pubkey, start_tick_index: i32, tick_spacing: u16) -> Result<Self> {
+        require!(
+            start_tick_index == Self::start_index_of(start_tick_index, tick_spacing),
+            MockRaydiumError::InvalidTickArray
+        );
+        let mut ticks = [TickState::default(); TICK_ARRAY_SIZE as usize];
+        for (i, tick) in ticks.iter_mut().enumerate() {
+            tick.tick = start_tick_index + i as i32 * i32::from(tick_spacing);
+        }
+        Ok(Self {
+            pool_id,
+            start_tick_index,
+            ticks,
+            initialized_tick_count: 0,
+            recent_epoch: 0,
+            padding: [0; 107],
+        })
+    }
+
+    /// First tick of the array holding `tick_index`.
+    pub fn start_index_of(tick_index: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = TICK_ARRAY_SIZE * i32::from(tick_spacing);
+        tick_index.div_euclid(ticks_in_array) * ticks_in_array
+    }
+}
+
+/// Tick arrays an instruction works on, by account key. An array passed twice
+/// is only loaded, and written back, once.
+pub struct TickArrays {
+    tick_spacing: u16,
+    arrays: Vec<(Pubkey, TickArrayState)>,
+}
+
+impl TickArrays {
+    pub fn new(tick_spacing: u16, arrays: Vec<(Pubkey, TickArrayState)>) -> Self {
+        Self {
+            tick_spacing,
+            arrays,
+        }
+    }
+
+    /// Loads the arrays of `pool` in `infos`. Accounts that are not yet
+    /// initialized are skipped, as swaps may be passed arrays nobody created.
+    pub fn load<'a, 'info: 'a>(
+        pool: &Pubkey,
+        tick_spacing: u16,
+        infos: impl IntoIterator<Item = &'a AccountInfo<'info>>,
+    ) -> Result<Self> {
+        let mut arrays: Vec<(Pubkey, TickArrayState)> = Vec::new();
+        for info in infos {
+            if info.data_is_empty() || arrays.iter().any(|(key, _)| key == info.key) {
+                continue;
+            }
+            require_keys_eq!(*info.owner, crate::ID, MockRaydiumError::InvalidTickArray);
+            let array = TickArrayState::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require_keys_eq!(array.pool_id, *pool, MockRaydiumError::InvalidTickArray);
+            arrays.push((info.key(), array));
+        }
+        Ok(Self {
+            tick_spacing,
+            arrays,
+        })
+    }
+
+    pub fn tick(&mut self, tick_index: i32) -> Result<&mut TickState> {
+        let start = TickArrayState::start_index_of(tick_index, self.tick_spacing);
+        let offset = (tick_index - start) / i32::from(self.tick_spacing);
+        self.arrays
+            .iter_mut()
+            .find(|(_, array)| array.start_tick_index == start)
+            .map(|(_, array)| &mut array.ticks[offset as usize])
+            .ok_or_else(|| error!(MockRaydiumError::InvalidTickArray))
+    }
+
+    /// Initialized ticks in `(from, to]`, in no particular order.
+    pub fn initialized_between(
+        &mut self,
+        from: i32,
+        to: i32,
+    ) -> impl Iterator<Item = &mut TickState> {
+        self.arrays.iter_mut().flat_map(move |(_, array)| {
+            array
+                .ticks
+                .iter_mut()
+                .filter(move |tick| tick.initialized() && from < tick.tick && tick.tick <= to)
+        })
+    }
+
+    /// Writes every array back to the account in `infos` it was loaded from.
+    pub fn save<'a, 'info: 'a>(
+        &self,
+        infos: impl IntoIterator<Item = &'a AccountInfo<'info>> + Clone,
+    ) -> Result<()> {
+        for (key, array) in &self.arrays {
+            if let Some(info) = infos.clone().into_iter().find(|info| info.key == key) {
+                array.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_arrays(self) -> Vec<(Pubkey, TickArrayState)> {
+        self.arrays
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    pub tick_cumulative: i64,
+    pub padding: [u64; 4],
+}
+
+/// Ring buffer of the pool's cumulative tick, written by swaps.
+#[account]
+#[derive(InitSpace)]
+pub struct ObservationState {
+    pub initialized: bool,
+    pub recent_epoch: u64,
+    pub observation_index: u16,
+    pub pool_id: Pubkey,
+    pub observations: [Observation; OBSERVATION_NUM],
+    pub padding: [u64; 4],
+}
+
+impl ObservationState {
+    /// Records that the pool sat at `tick` since the newest observation, once
+    /// `OBSERVATION_UPDATE_DURATION` has passed since it. The first update only
+    /// starts the series.
+    pub fn update(&mut self, block_timestamp: u32, tick: i32) {
+        let index = usize::from(self.observation_index);
+        if !self.initialized {
+            self.initialized = true;
+            self.observations[index].block_timestamp = block_timestamp;
+            self.observations[index].tick_cumulative = 0;
+            return;
+        }
+        let last = self.observations[index];
+        let elapsed = block_timestamp.saturating_sub(last.block_timestamp);
+        if elapsed < OBSERVATION_UPDATE_DURATION {
+            return;
+        }
+        let next = (index + 1) % OBSERVATION_NUM;
+        self.observations[next].block_timestamp = block_timestamp;
+        self.observations[next].tick_cumulative = last
+            .tick_cumulative
+            .wrapping_add(i64::from(tick) * i64::from(elapsed));
+        self.observation_index = next as u16;
+    }
+}
@@ -0,0 +1,172 @@
+//! Local stand-in for an aggregator-style swap program, for the native test harness.
+//!
+//! A pool swaps between two tokens at a fixed Q64.64 price, token B atoms per
+//! token A atom, minus a fee. The signer's token accounts are passed in the
+//! instruction like an aggregator route, so vault_manager can route zap swaps
+//! through it. `quote` is the whole pricing rule, so native tests can work out a
+//! swap's balance changes without executing CPIs.
+#![allow(clippy::result_large_err)]
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+declare_id!("54M493fZCoXRfsXprEo6D6YdbRHF6eX3A4uKj5b8y711");
+
+#[program]
+pub mod mock_swap {
+    use super::*;
+
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        price_x64: u128,
+        fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            price_x64 > 0 && fee_bps < 10_000,
+            MockSwapError::InvalidPool
+        );
+        let bump = ctx.bumps.pool;
+        let accounts = &mut *ctx.accounts;
+        *accounts.pool = SwapPool {
+            token_a_mint: accounts.token_a_mint.key(),
+            token_b_mint: accounts.token_b_mint.key(),
+            reserve_a: accounts.reserve_a.key(),
+            reserve_b: accounts.reserve_b.key(),
+            price_x64,
+            fee_bps,
+            bump,
+        };
+        Ok(())
+    }
+
+    /// Exact-input swap from `user_token_in` to `user_token_out`; the direction
+    /// follows the input account's mint.
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let pool = &accounts.pool;
+        let a_to_b = accounts.user_token_in.mint == pool.token_a_mint;
+        let (reserve_in, reserve_out, mint_out) = if a_to_b {
+            (&accounts.reserve_a, &accounts.reserve_b, pool.token_b_mint)
+        } else {
+            (&accounts.reserve_b, &accounts.reserve_a, pool.token_a_mint)
+        };
+        require!(
+            accounts.user_token_out.mint == mint_out && accounts.user_token_in.mint != mint_out,
+            MockSwapError::MintMismatch
+        );
+
+        let amount_out = quote(pool, amount_in, a_to_b)?;
+        require!(
+            amount_out >= min_amount_out,
+            MockSwapError::SlippageExceeded
+        );
+        require!(
+            amount_out <= reserve_out.amount,
+            MockSwapError::InsufficientLiquidity
+        );
+
+        let token_program = accounts.token_program.to_account_info();
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: accounts.user_token_in.to_account_info(),
+                    to: reserve_in.to_account_info(),
+                    authority: accounts.user.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+        let pair = [pool.token_a_mint, pool.token_b_mint];
+        let bump = [pool.bump];
+        let seeds: &[&[u8]] = &[b"pool", pair[0].as_ref(), pair[1].as_ref(), &bump];
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program,
+                Transfer {
+                    from: reserve_out.to_account_info(),
+                    to: accounts.user_token_out.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount_out,
+        )
+    }
+}
+
+/// Amount out of swapping `amount_in` at the pool price, rounded down, after the fee.
+pub fn quote(pool: &SwapPool, amount_in: u64, a_to_b: bool) -> Result<u64> {
+    let amount_in = u128::from(amount_in);
+    let gross = if a_to_b {
+        amount_in
+            .checked_mul(pool.price_x64)
+            .map(|value| value >> 64)
+    } else {
+        (amount_in << 64).checked_div(pool.price_x64)
+    }
+    .ok_or(MockSwapError::MathOverflow)?;
+    let fee = gross * u128::from(pool.fee_bps) / 10_000;
+    u64::try_from(gross - fee).map_err(|_| error!(MockSwapError::MathOverflow))
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SwapPool {
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub reserve_a: Pubkey,
+    pub reserve_b: Pubkey,
+    /// Token B atoms per token A atom, Q64.64.
+    pub price_x64: u128,
+    pub fee_bps: u16,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(
+        init, payer = funder, space = 8 + SwapPool::INIT_SPACE,
+        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, SwapPool>,
+    pub token_a_mint: Account<'info, Mint>,
+    pub token_b_mint: Account<'info, Mint>,
+    #[account(token::mint = token_a_mint, token::authority = pool)]
+    pub reserve_a: Account<'info, TokenAccount>,
+    #[account(token::mint = token_b_mint, token::authority = pool)]
+    pub reserve_b: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(has_one = reserve_a, has_one = reserve_b)]
+    pub pool: Account<'info, SwapPool>,
+    #[account(mut)]
+    pub reserve_a: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub reserve_b: Account<'info, TokenAccount>,
+    #[account(mut, token::authority = user)]
+    pub user_token_in: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_out: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[error_code]
+pub enum MockSwapError {
+    #[msg("Pool price must be positive and the fee below 100%")]
+    InvalidPool,
+    #[msg("Token accounts do not match the pool")]
+    MintMismatch,
+    #[msg("Amount out is below the minimum")]
+    SlippageExceeded,
+    #[msg("Pool reserve cannot cover the swap")]
+    InsufficientLiquidity,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,737 @@
+//! Local stand-in for the Orca Whirlpool program, for the native test harness.
+//!
+//! It implements the instructions and account layouts vault_manager's Whirlpool
+//! backend uses, with simplified but deterministic concentrated liquidity math:
+//! - swaps are exact-input only and trade at the in-range liquidity the pool
+//!   has when they start, crossing the initialized ticks the price passed once
+//!   they are done; fees grow the input token's fee growth and no protocol fee
+//!   is taken;
+//! - rewards emit per second to in-range liquidity, like the real program;
+//! - the oracle holds the real adaptive fee layout, but fees stay at the pool's
+//!   static rate and the oracle is never written;
+//! - `initialize_pool`, `initialize_tick_array`, `initialize_oracle`,
+//!   `initialize_reward` and `set_sqrt_price` are setup instructions with
+//!   mock-only arguments, the last one moving the price without a trade.
+#![allow(clippy::result_large_err)]
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer};
+
+pub mod math;
+pub mod pool;
+pub mod state;
+
+use state::{Oracle, Position, TickArray, TickArrays, Whirlpool, NUM_REWARDS};
+
+declare_id!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+/// Encodes like the real program's `OpenPositionBumps` argument, a name Anchor
+/// now gives the bumps of the `OpenPosition` accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PositionBumps {
+    pub position_bump: u8,
+}
+
+#[program]
+pub mod mock_whirlpool {
+    use super::*;
+
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        tick_spacing: u16,
+        fee_rate: u16,
+        initial_sqrt_price: u128,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        **accounts.whirlpool = Whirlpool::new(
+            accounts.whirlpools_config.key(),
+            accounts.token_mint_a.key(),
+            accounts.token_mint_b.key(),
+            accounts.token_vault_a.key(),
+            accounts.token_vault_b.key(),
+            tick_spacing,
+            fee_rate,
+            initial_sqrt_price,
+        )?;
+        Ok(())
+    }
+
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        let whirlpool = &ctx.accounts.whirlpool;
+        **ctx.accounts.tick_array =
+            TickArray::new(whirlpool.key(), start_tick_index, whirlpool.tick_spacing)?;
+        Ok(())
+    }
+
+    /// Mock-only: creates the pool's oracle, which the real program creates with
+    /// adaptive fee pools.
+    pub fn initialize_oracle(ctx: Context<InitializeOracle>) -> Result<()> {
+        ctx.accounts.oracle.whirlpool = ctx.accounts.whirlpool.key();
+        Ok(())
+    }
+
+    /// Mock-only: sets up reward `reward_index` and its emission rate in one go.
+    pub fn initialize_reward(
+        ctx: Context<InitializeReward>,
+        reward_index: u8,
+        emissions_per_second_x64: u128,
+    ) -> Result<()> {
+        require!(
+            usize::from(reward_index) < NUM_REWARDS,
+            MockWhirlpoolError::InvalidRewardIndex
+        );
+        let accounts = &mut *ctx.accounts;
+        // Emissions so far accrue at the old rate
+        pool::update_rewards(&mut accounts.whirlpool, Clock::get()?.unix_timestamp)?;
+        let reward = &mut accounts.whirlpool.reward_infos[usize::from(reward_index)];
+        reward.mint = accounts.reward_mint.key();
+        reward.vault = accounts.reward_vault.key();
+        reward.authority = accounts.reward_authority.key();
+        reward.emissions_per_second_x64 = emissions_per_second_x64;
+        Ok(())
+    }
+
+    /// Mock-only: moves the price without trading, crossing the ticks of the
+    /// tick arrays in the remaining accounts.
+    pub fn set_sqrt_price<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetSqrtPrice<'info>>,
+        sqrt_price: u128,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let whirlpool = &mut accounts.whirlpool;
+        let mut ticks = TickArrays::load(
+            &whirlpool.key(),
+            whirlpool.tick_spacing,
+            ctx.remaining_accounts,
+        )?;
+        pool::move_price(whirlpool, &mut ticks, sqrt_price)?;
+        ticks.save(ctx.remaining_accounts)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        _bumps: PositionBumps,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        **accounts.position = Position::new(
+            accounts.whirlpool.key(),
+            &accounts.whirlpool,
+            accounts.position_mint.key(),
+            tick_lower_index,
+            tick_upper_index,
+        )?;
+        accounts.whirlpool.with_signer(|signer| {
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: accounts.position_mint.to_account_info(),
+                        to: accounts.position_token_account.to_account_info(),
+                        authority: accounts.whirlpool.to_account_info(),
+                    },
+                    signer,
+                ),
+                1,
+            )
+        })
+    }
+
+    /// Burns the position NFT, closing its token account, and closes the empty position.
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        require!(
+            accounts.position.is_empty(),
+            MockWhirlpoolError::ClosePositionNotEmpty
+        );
+        let token_program = accounts.token_program.to_account_info();
+        token::burn(
+            CpiContext::new(
+                token_program.clone(),
+                Burn {
+                    mint: accounts.position_mint.to_account_info(),
+                    from: accounts.position_token_account.to_account_info(),
+                    authority: accounts.position_authority.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        token::close_account(CpiContext::new(
+            token_program,
+            CloseAccount {
+                account: accounts.position_token_account.to_account_info(),
+                destination: accounts.receiver.to_account_info(),
+                authority: accounts.position_authority.to_account_info(),
+            },
+        ))
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity_amount: u128,
+        token_max_a: u64,
+        token_max_b: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let liquidity_delta =
+            i128::try_from(liquidity_amount).map_err(|_| MockWhirlpoolError::MathOverflow)?;
+        let (amount_a, amount_b) = modify_liquidity(accounts, liquidity_delta)?;
+        require!(
+            amount_a <= token_max_a && amount_b <= token_max_b,
+            MockWhirlpoolError::TokenMaxExceeded
+        );
+
+        let token_program = accounts.token_program.to_account_info();
+        for (from, to, amount) in [
+            (
+                &accounts.token_owner_account_a,
+                &accounts.token_vault_a,
+                amount_a,
+            ),
+            (
+                &accounts.token_owner_account_b,
+                &accounts.token_vault_b,
+                amount_b,
+            ),
+        ] {
+            if amount > 0 {
+                token::transfer(
+                    CpiContext::new(
+                        token_program.clone(),
+                        Transfer {
+                            from: from.to_account_info(),
+                            to: to.to_account_info(),
+                            authority: accounts.position_authority.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity_amount: u128,
+        token_min_a: u64,
+        token_min_b: u64,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let liquidity_delta = i128::try_from(liquidity_amount)
+            .ok()
+            .and_then(i128::checked_neg)
+            .ok_or(MockWhirlpoolError::MathOverflow)?;
+        let (amount_a, amount_b) = modify_liquidity(accounts, liquidity_delta)?;
+        require!(
+            amount_a >= token_min_a && amount_b >= token_min_b,
+            MockWhirlpoolError::TokenMinSubceeded
+        );
+
+        let token_program = accounts.token_program.to_account_info();
+        pay_out(
+            &accounts.whirlpool,
+            &token_program,
+            &accounts.token_vault_a,
+            &accounts.token_owner_account_a,
+            amount_a,
+        )?;
+        pay_out(
+            &accounts.whirlpool,
+            &token_program,
+            &accounts.token_vault_b,
+            &accounts.token_owner_account_b,
+            amount_b,
+        )
+    }
+
+    pub fn update_fees_and_rewards(ctx: Context<UpdateFeesAndRewards>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let whirlpool = &mut accounts.whirlpool;
+        let mut ticks = TickArrays::load(
+            &whirlpool.key(),
+            whirlpool.tick_spacing,
+            [
+                accounts.tick_array_lower.as_ref(),
+                accounts.tick_array_upper.as_ref(),
+            ],
+        )?;
+        pool::update_fees_and_rewards(
+            whirlpool,
+            &mut accounts.position,
+            &mut ticks,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let (fee_a, fee_b) = pool::collect_fees(&mut accounts.position);
+
+        let token_program = accounts.token_program.to_account_info();
+        pay_out(
+            &accounts.whirlpool,
+            &token_program,
+            &accounts.token_vault_a,
+            &accounts.token_owner_account_a,
+            fee_a,
+        )?;
+        pay_out(
+            &accounts.whirlpool,
+            &token_program,
+            &accounts.token_vault_b,
+            &accounts.token_owner_account_b,
+            fee_b,
+        )
+    }
+
+    pub fn collect_reward(ctx: Context<CollectReward>, reward_index: u8) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let amount = pool::collect_reward(&mut accounts.position, reward_index)?;
+        pay_out(
+            &accounts.whirlpool,
+            &accounts.token_program.to_account_info(),
+            &accounts.reward_vault,
+            &accounts.reward_owner_account,
+            amount,
+        )
+    }
+
+    /// Exact-input swap through the tick arrays passed.
+    pub fn swap(
+        ctx: Context<Swap>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit: u128,
+        amount_specified_is_input: bool,
+        a_to_b: bool,
+    ) -> Result<()> {
+        require!(
+            amount_specified_is_input,
+            MockWhirlpoolError::ExactOutputUnsupported
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &mut *ctx.accounts;
+
+        let tick_arrays = [
+            accounts.tick_array_0.as_ref(),
+            accounts.tick_array_1.as_ref(),
+            accounts.tick_array_2.as_ref(),
+        ];
+        let whirlpool = &mut accounts.whirlpool;
+        let mut ticks = TickArrays::load(&whirlpool.key(), whirlpool.tick_spacing, tick_arrays)?;
+        let (amount_in, amount_out) =
+            pool::swap(whirlpool, &mut ticks, amount, sqrt_price_limit, a_to_b, now)?;
+        ticks.save(tick_arrays)?;
+        require!(
+            amount_out >= other_amount_threshold,
+            MockWhirlpoolError::AmountOutBelowMinimum
+        );
+
+        let (owner_in, vault_in, owner_out, vault_out) = if a_to_b {
+            (
+                &accounts.token_owner_account_a,
+                &accounts.token_vault_a,
+                &accounts.token_owner_account_b,
+                &accounts.token_vault_b,
+            )
+        } else {
+            (
+                &accounts.token_owner_account_b,
+                &accounts.token_vault_b,
+                &accounts.token_owner_account_a,
+                &accounts.token_vault_a,
+            )
+        };
+        let token_program = accounts.token_program.to_account_info();
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: owner_in.to_account_info(),
+                    to: vault_in.to_account_info(),
+                    authority: accounts.token_authority.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+        pay_out(
+            &accounts.whirlpool,
+            &token_program,
+            vault_out,
+            owner_out,
+            amount_out,
+        )
+    }
+}
+
+/// Runs the liquidity change on the pool and writes the tick arrays back.
+fn modify_liquidity(accounts: &mut ModifyLiquidity, liquidity_delta: i128) -> Result<(u64, u64)> {
+    let tick_arrays = [
+        accounts.tick_array_lower.as_ref(),
+        accounts.tick_array_upper.as_ref(),
+    ];
+    let whirlpool = &mut accounts.whirlpool;
+    let mut ticks = TickArrays::load(&whirlpool.key(), whirlpool.tick_spacing, tick_arrays)?;
+    let amounts = pool::modify_liquidity(
+        whirlpool,
+        &mut accounts.position,
+        &mut ticks,
+        liquidity_delta,
+        Clock::get()?.unix_timestamp,
+    )?;
+    ticks.save(tick_arrays)?;
+    Ok(amounts)
+}
+
+/// Whether `token_account` is `authority`'s account holding the position NFT.
+fn holds_position(token_account: &TokenAccount, position: &Position, authority: &Pubkey) -> bool {
+    token_account.mint == position.position_mint
+        && token_account.owner == *authority
+        && token_account.amount == 1
+}
+
+/// Transfers `amount` out of a pool token vault, signed by the pool.
+fn pay_out<'info>(
+    whirlpool: &Account<'info, Whirlpool>,
+    token_program: &AccountInfo<'info>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    whirlpool.with_signer(|signer| {
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: vault.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: whirlpool.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(tick_spacing: u16)]
+pub struct InitializePool<'info> {
+    /// CHECK: Only recorded on the pool, the mock has no configs or fee tiers
+    pub whirlpools_config: UncheckedAccount<'info>,
+    pub token_mint_a: Account<'info, Mint>,
+    pub token_mint_b: Account<'info, Mint>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(
+        init, payer = funder,
+        space = 8 + Whirlpool::INIT_SPACE,
+        seeds = [
+            b"whirlpool",
+            whirlpools_config.key().as_ref(),
+            token_mint_a.key().as_ref(),
+            token_mint_b.key().as_ref(),
+            &tick_spacing.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    #[account(token::mint = token_mint_a, token::authority = whirlpool)]
+    pub token_vault_a: Account<'info, TokenAccount>,
+    #[account(token::mint = token_mint_b, token::authority = whirlpool)]
+    pub token_vault_b: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(
+        init, payer = funder,
+        space = 8 + TickArray::INIT_SPACE,
+        seeds = [
+            b"tick_array",
+            whirlpool.key().as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        bump
+    )]
+    pub tick_array: Box<Account<'info, TickArray>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeOracle<'info> {
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(
+        init, payer = funder,
+        space = 8 + Oracle::INIT_SPACE,
+        seeds = [b"oracle", whirlpool.key().as_ref()],
+        bump
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReward<'info> {
+    #[account(mut)]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    pub reward_authority: Signer<'info>,
+    pub reward_mint: Account<'info, Mint>,
+    #[account(token::mint = reward_mint, token::authority = whirlpool)]
+    pub reward_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetSqrtPrice<'info> {
+    #[account(mut)]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    /// CHECK: Receives the position NFT
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        init, payer = funder,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(init, payer = funder, mint::decimals = 0, mint::authority = whirlpool)]
+    pub position_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init, payer = funder,
+        associated_token::mint = position_mint,
+        associated_token::authority = owner,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    pub position_authority: Signer<'info>,
+    /// CHECK: Receives the rent of the closed accounts
+    #[account(mut)]
+    pub receiver: UncheckedAccount<'info>,
+    #[account(mut, close = receiver, has_one = position_mint)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(mut)]
+    pub position_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = holds_position(
+            &position_token_account, &position, position_authority.key
+        ) @ MockWhirlpoolError::Unauthorized,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut)]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    pub token_program: Program<'info, Token>,
+    pub position_authority: Signer<'info>,
+    #[account(mut, has_one = whirlpool)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        constraint = holds_position(
+            &position_token_account, &position, position_authority.key
+        ) @ MockWhirlpoolError::Unauthorized,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_owner_account_a.mint == whirlpool.token_mint_a
+            @ MockWhirlpoolError::MintMismatch,
+    )]
+    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_owner_account_b.mint == whirlpool.token_mint_b
+            @ MockWhirlpoolError::MintMismatch,
+    )]
+    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Loaded by the handler, may be the same account as `tick_array_upper`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeesAndRewards<'info> {
+    #[account(mut)]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    #[account(mut, has_one = whirlpool)]
+    pub position: Box<Account<'info, Position>>,
+    /// CHECK: Loaded by the handler, may be the same account as `tick_array_upper`
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    pub tick_array_upper: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    pub position_authority: Signer<'info>,
+    #[account(mut, has_one = whirlpool)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        constraint = holds_position(
+            &position_token_account, &position, position_authority.key
+        ) @ MockWhirlpoolError::Unauthorized,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_owner_account_a.mint == whirlpool.token_mint_a
+            @ MockWhirlpoolError::MintMismatch,
+    )]
+    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_owner_account_b.mint == whirlpool.token_mint_b
+            @ MockWhirlpoolError::MintMismatch,
+    )]
+    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(reward_index: u8)]
+pub struct CollectReward<'info> {
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    pub position_authority: Signer<'info>,
+    #[account(mut, has_one = whirlpool)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        constraint = holds_position(
+            &position_token_account, &position, position_authority.key
+        ) @ MockWhirlpoolError::Unauthorized,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub reward_owner_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = whirlpool
+            .reward_infos
+            .get(usize::from(reward_index))
+            .is_some_and(|reward| reward.vault == reward_vault.key())
+            @ MockWhirlpoolError::InvalidRewardIndex,
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub token_program: Program<'info, Token>,
+    pub token_authority: Signer<'info>,
+    #[account(mut)]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    #[account(
+        mut,
+        constraint = token_owner_account_a.mint == whirlpool.token_mint_a
+            @ MockWhirlpoolError::MintMismatch,
+    )]
+    pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = token_owner_account_b.mint == whirlpool.token_mint_b
+            @ MockWhirlpoolError::MintMismatch,
+    )]
+    pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Loaded by the handler, arrays not yet created are skipped
+    #[account(mut)]
+    pub tick_array_0: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub tick_array_1: UncheckedAccount<'info>,
+    /// CHECK: Loaded by the handler
+    #[account(mut)]
+    pub tick_array_2: UncheckedAccount<'info>,
+    /// CHECK: The pool's oracle address, which need not be initialized
+    #[account(mut, seeds = [b"oracle", whirlpool.key().as_ref()], bump)]
+    pub oracle: UncheckedAccount<'info>,
+}
+
+#[error_code]
+pub enum MockWhirlpoolError {
+    #[msg("Tick spacing must be positive")]
+    InvalidTickSpacing,
+    #[msg("Fee rate must be below 100%")]
+    InvalidFeeRate,
+    #[msg("Tick index is out of range or not a multiple of the tick spacing")]
+    InvalidTickIndex,
+    #[msg("Sqrt price is out of range")]
+    InvalidSqrtPrice,
+    #[msg("Tick array is missing or belongs to another pool")]
+    InvalidTickArray,
+    #[msg("Reward index is out of range or does not match the reward vault")]
+    InvalidRewardIndex,
+    #[msg("Liquidity amount must not be zero")]
+    LiquidityZero,
+    #[msg("Liquidity would go below zero")]
+    LiquidityUnderflow,
+    #[msg("Token amount exceeds the maximum")]
+    TokenMaxExceeded,
+    #[msg("Token amount is below the minimum")]
+    TokenMinSubceeded,
+    #[msg("Position still holds liquidity, fees or rewards")]
+    ClosePositionNotEmpty,
+    #[msg("Token accounts do not match the pool")]
+    MintMismatch,
+    #[msg("Signer does not hold the position")]
+    Unauthorized,
+    #[msg("Sqrt price limit is on the wrong side of the price or out of range")]
+    InvalidSqrtPriceLimit,
+    #[msg("Swap amount must not be zero")]
+    ZeroTradableAmount,
+    #[msg("No liquidity at the current price")]
+    InsufficientLiquidity,
+    #[msg("Only exact-input swaps are supported")]
+    ExactOutputUnsupported,
+    #[msg("Swap output is below the minimum")]
+    AmountOutBelowMinimum,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,198 @@
+use anchor_lang::prelude::*;
+
+use crate::MockWhirlpoolError;
+
+pub use wide::{U256, U512};
+
+/// Kept apart from the Anchor prelude, whose `Result` alias the macro cannot use.
+#[allow(clippy::all)]
+mod wide {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+
+    uint::construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+pub const MIN_TICK_INDEX: i32 = -443636;
+pub const MAX_TICK_INDEX: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
+
+/// Swap fee rates are in hundredths of a basis point.
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Q128 ratios for sqrt(1.0001)^-(2^i), i = 1..19. Bit 0 is handled separately.
+const TICK_RATIOS_X128: [u128; 19] = [
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// Q64.64 sqrt price for a tick index, computed like vault_manager does.
+pub fn sqrt_price_from_tick_index(tick: i32) -> Result<u128> {
+    require!(
+        (MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick),
+        MockWhirlpoolError::InvalidTickIndex
+    );
+    let abs_tick = tick.unsigned_abs();
+
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001u128)
+    } else {
+        U256::one() << 128
+    };
+    for (i, factor) in TICK_RATIOS_X128.iter().enumerate() {
+        if abs_tick & (2 << i) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    let rounding = if ratio.low_u64() == 0 { 0 } else { 1 };
+    Ok(((ratio >> 64) + rounding).as_u128())
+}
+
+/// Highest tick whose sqrt price is at or below `sqrt_price`, by bisection.
+pub fn tick_index_from_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        (MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price),
+        MockWhirlpoolError::InvalidSqrtPrice
+    );
+    let (mut low, mut high) = (MIN_TICK_INDEX, MAX_TICK_INDEX);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_from_tick_index(middle)? <= sqrt_price {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// Token A between two sqrt prices for `liquidity`.
+pub fn amount_a_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (lower, upper) = ordered(sqrt_price_0, sqrt_price_1);
+    if liquidity == 0 || lower == upper {
+        return Ok(0);
+    }
+    let numerator = (U512::from(liquidity) * U512::from(upper - lower)) << 64;
+    let denominator = U512::from(upper) * U512::from(lower);
+    to_u64(div(numerator, denominator, round_up))
+}
+
+/// Token B between two sqrt prices for `liquidity`.
+pub fn amount_b_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (lower, upper) = ordered(sqrt_price_0, sqrt_price_1);
+    let product = U512::from(liquidity) * U512::from(upper - lower);
+    to_u64(div(product, U512::one() << 64, round_up))
+}
+
+/// Sqrt price after `amount` of token A goes in at constant `liquidity`,
+/// rounded up so the price never moves further than the input pays for.
+pub fn next_sqrt_price_from_a_in(sqrt_price: u128, liquidity: u128, amount: u64) -> Result<u128> {
+    let numerator = U512::from(liquidity) << 64;
+    let denominator = numerator + U512::from(amount) * U512::from(sqrt_price);
+    to_u128(div(numerator * U512::from(sqrt_price), denominator, true))
+}
+
+/// Sqrt price after `amount` of token B goes in at constant `liquidity`, rounded down.
+pub fn next_sqrt_price_from_b_in(sqrt_price: u128, liquidity: u128, amount: u64) -> Result<u128> {
+    require!(liquidity != 0, MockWhirlpoolError::MathOverflow);
+    let delta = (U512::from(amount) << 64) / U512::from(liquidity);
+    to_u128(U512::from(sqrt_price) + delta)
+}
+
+/// Fee on `amount` at `fee_rate`, rounded up.
+pub fn fee_amount(amount: u64, fee_rate: u16) -> Result<u64> {
+    let product = U512::from(amount) * U512::from(fee_rate);
+    to_u64(div(product, U512::from(FEE_RATE_DENOMINATOR), true))
+}
+
+/// Fee to add on top of a net input of `amount` at `fee_rate`, rounded up.
+pub fn fee_for_net(amount: u64, fee_rate: u16) -> Result<u64> {
+    let product = U512::from(amount) * U512::from(fee_rate);
+    let denominator = U512::from(FEE_RATE_DENOMINATOR - u64::from(fee_rate));
+    to_u64(div(product, denominator, true))
+}
+
+/// `a * b / denominator` computed in 512 bits, rounded down.
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator != 0, MockWhirlpoolError::MathOverflow);
+    to_u128(U512::from(a) * U512::from(b) / U512::from(denominator))
+}
+
+/// Q64.64 growth per unit of `liquidity` for `amount`, rounded down.
+pub fn growth(amount: u64, liquidity: u128) -> Result<u128> {
+    require!(liquidity != 0, MockWhirlpoolError::MathOverflow);
+    to_u128((U512::from(amount) << 64) / U512::from(liquidity))
+}
+
+/// Amount earned by `liquidity` over a Q64.64 growth delta, rounded down.
+pub fn owed(growth_delta: u128, liquidity: u128) -> Result<u64> {
+    to_u64((U512::from(growth_delta) * U512::from(liquidity)) >> 64)
+}
+
+fn ordered(a: u128, b: u128) -> (u128, u128) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn div(numerator: U512, denominator: U512, round_up: bool) -> U512 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if round_up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+fn to_u64(value: U512) -> Result<u64> {
+    require!(
+        value <= U512::from(u64::MAX),
+        MockWhirlpoolError::MathOverflow
+    );
+    Ok(value.as_u64())
+}
+
+fn to_u128(value: U512) -> Result<u128> {
+    require!(
+        value <= U512::from(u128::MAX),
+        MockWhirlpoolError::MathOverflow
+    );
+    Ok(value.as_u128())
+}
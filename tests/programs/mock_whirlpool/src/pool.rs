@@ -0,0 +1,372 @@
+//! Pool state transitions behind the instructions.
+//!
+//! They only touch deserialized state and return the token amounts to move, so
+//! native tests can drive the pool without executing CPIs.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::state::{Position, Tick, TickArrays, Whirlpool, NUM_REWARDS};
+use crate::MockWhirlpoolError;
+
+/// Fee and reward growth per unit of liquidity, either pool-wide or inside a range.
+#[derive(Clone, Copy, Default)]
+struct Growth {
+    fee_a: u128,
+    fee_b: u128,
+    rewards: [u128; NUM_REWARDS],
+}
+
+impl Growth {
+    fn global(whirlpool: &Whirlpool) -> Self {
+        Self {
+            fee_a: whirlpool.fee_growth_global_a,
+            fee_b: whirlpool.fee_growth_global_b,
+            rewards: whirlpool
+                .reward_infos
+                .map(|reward| reward.growth_global_x64),
+        }
+    }
+
+    fn outside(tick: &Tick) -> Self {
+        Self {
+            fee_a: tick.fee_growth_outside_a,
+            fee_b: tick.fee_growth_outside_b,
+            rewards: tick.reward_growths_outside,
+        }
+    }
+
+    fn zip(self, other: Self, f: impl Fn(u128, u128) -> u128) -> Self {
+        let mut rewards = [0; NUM_REWARDS];
+        for (i, reward) in rewards.iter_mut().enumerate() {
+            *reward = f(self.rewards[i], other.rewards[i]);
+        }
+        Self {
+            fee_a: f(self.fee_a, other.fee_a),
+            fee_b: f(self.fee_b, other.fee_b),
+            rewards,
+        }
+    }
+
+    fn store_outside(self, tick: &mut Tick) {
+        tick.fee_growth_outside_a = self.fee_a;
+        tick.fee_growth_outside_b = self.fee_b;
+        tick.reward_growths_outside = self.rewards;
+    }
+}
+
+/// Accrues reward emissions since the last update to the pool-wide growth.
+pub fn update_rewards(whirlpool: &mut Whirlpool, now: i64) -> Result<()> {
+    let now = u64::try_from(now).map_err(|_| MockWhirlpoolError::MathOverflow)?;
+    let elapsed = now.saturating_sub(whirlpool.reward_last_updated_timestamp);
+    if whirlpool.liquidity > 0 && elapsed > 0 {
+        let liquidity = whirlpool.liquidity;
+        for reward in whirlpool.reward_infos.iter_mut() {
+            if reward.initialized() {
+                let growth =
+                    math::mul_div(reward.emissions_per_second_x64, elapsed.into(), liquidity)?;
+                reward.growth_global_x64 = reward.growth_global_x64.wrapping_add(growth);
+            }
+        }
+    }
+    whirlpool.reward_last_updated_timestamp = whirlpool.reward_last_updated_timestamp.max(now);
+    Ok(())
+}
+
+/// Adds `liquidity_delta` to the position, or removes it when negative.
+/// Returns the token A and B amounts owed to the pool, rounded up, or paid
+/// out of it, rounded down.
+pub fn modify_liquidity(
+    whirlpool: &mut Whirlpool,
+    position: &mut Position,
+    ticks: &mut TickArrays,
+    liquidity_delta: i128,
+    now: i64,
+) -> Result<(u64, u64)> {
+    require!(liquidity_delta != 0, MockWhirlpoolError::LiquidityZero);
+    update_rewards(whirlpool, now)?;
+
+    let (lower_index, upper_index) = (position.tick_lower_index, position.tick_upper_index);
+    let mut lower = *ticks.tick(lower_index)?;
+    let mut upper = *ticks.tick(upper_index)?;
+    update_tick(whirlpool, &mut lower, lower_index, liquidity_delta, false)?;
+    update_tick(whirlpool, &mut upper, upper_index, liquidity_delta, true)?;
+    settle(whirlpool, position, &lower, &upper)?;
+    position.liquidity = add_delta(position.liquidity, liquidity_delta)?;
+
+    // A tick no position references any more goes back to uninitialized
+    for (index, tick) in [(lower_index, lower), (upper_index, upper)] {
+        *ticks.tick(index)? = if tick.liquidity_gross == 0 {
+            Tick::default()
+        } else {
+            tick
+        };
+    }
+    if (lower_index..upper_index).contains(&whirlpool.tick_current_index) {
+        whirlpool.liquidity = add_delta(whirlpool.liquidity, liquidity_delta)?;
+    }
+
+    amounts_for_liquidity(
+        whirlpool,
+        lower_index,
+        upper_index,
+        liquidity_delta.unsigned_abs(),
+        liquidity_delta > 0,
+    )
+}
+
+/// Token A and B amounts `liquidity` over `[lower_index, upper_index)` holds at
+/// the current price.
+pub fn amounts_for_liquidity(
+    whirlpool: &Whirlpool,
+    lower_index: i32,
+    upper_index: i32,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    let sqrt_lower = math::sqrt_price_from_tick_index(lower_index)?;
+    let sqrt_upper = math::sqrt_price_from_tick_index(upper_index)?;
+    let current = whirlpool.tick_current_index;
+    if current < lower_index {
+        Ok((
+            math::amount_a_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+            0,
+        ))
+    } else if current < upper_index {
+        Ok((
+            math::amount_a_delta(whirlpool.sqrt_price, sqrt_upper, liquidity, round_up)?,
+            math::amount_b_delta(sqrt_lower, whirlpool.sqrt_price, liquidity, round_up)?,
+        ))
+    } else {
+        Ok((
+            0,
+            math::amount_b_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+        ))
+    }
+}
+
+/// Credits the position with the fees and rewards its range earned since its
+/// last checkpoint.
+pub fn update_fees_and_rewards(
+    whirlpool: &mut Whirlpool,
+    position: &mut Position,
+    ticks: &mut TickArrays,
+    now: i64,
+) -> Result<()> {
+    require!(position.liquidity != 0, MockWhirlpoolError::LiquidityZero);
+    update_rewards(whirlpool, now)?;
+    let lower = *ticks.tick(position.tick_lower_index)?;
+    let upper = *ticks.tick(position.tick_upper_index)?;
+    settle(whirlpool, position, &lower, &upper)
+}
+
+/// Takes the position's owed fees. Returns the token A and B fees.
+pub fn collect_fees(position: &mut Position) -> (u64, u64) {
+    (
+        std::mem::take(&mut position.fee_owed_a),
+        std::mem::take(&mut position.fee_owed_b),
+    )
+}
+
+/// Takes the position's owed amount of reward `reward_index`.
+pub fn collect_reward(position: &mut Position, reward_index: u8) -> Result<u64> {
+    let reward = position
+        .reward_infos
+        .get_mut(usize::from(reward_index))
+        .ok_or(MockWhirlpoolError::InvalidRewardIndex)?;
+    Ok(std::mem::take(&mut reward.amount_owed))
+}
+
+/// Exact-input swap at the pool's in-range liquidity, which stays constant for
+/// the whole trade; the initialized ticks the price passed are crossed once it
+/// is done. Stops early at `sqrt_price_limit`, zero meaning no limit. Returns
+/// the amounts in, fee included, and out.
+pub fn swap(
+    whirlpool: &mut Whirlpool,
+    ticks: &mut TickArrays,
+    amount: u64,
+    sqrt_price_limit: u128,
+    a_to_b: bool,
+    now: i64,
+) -> Result<(u64, u64)> {
+    let sqrt_price = whirlpool.sqrt_price;
+    let limit = match (sqrt_price_limit, a_to_b) {
+        (0, true) => math::MIN_SQRT_PRICE_X64,
+        (0, false) => math::MAX_SQRT_PRICE_X64,
+        (limit, _) => limit,
+    };
+    require!(
+        if a_to_b {
+            (math::MIN_SQRT_PRICE_X64..sqrt_price).contains(&limit)
+        } else {
+            limit > sqrt_price && limit <= math::MAX_SQRT_PRICE_X64
+        },
+        MockWhirlpoolError::InvalidSqrtPriceLimit
+    );
+    require!(amount > 0, MockWhirlpoolError::ZeroTradableAmount);
+    let liquidity = whirlpool.liquidity;
+    require!(liquidity > 0, MockWhirlpoolError::InsufficientLiquidity);
+    update_rewards(whirlpool, now)?;
+
+    let fee_rate = whirlpool.fee_rate;
+    let amount_less_fee = amount - math::fee_amount(amount, fee_rate)?;
+    // Past u64, the limit is out of reach of any input
+    let to_limit = if a_to_b {
+        math::amount_a_delta(limit, sqrt_price, liquidity, true)
+    } else {
+        math::amount_b_delta(sqrt_price, limit, liquidity, true)
+    }
+    .unwrap_or(u64::MAX);
+
+    let (next_sqrt_price, amount_in, fee) = if amount_less_fee >= to_limit {
+        let fee = math::fee_for_net(to_limit, fee_rate)?;
+        (limit, to_limit + fee, fee)
+    } else if a_to_b {
+        let next = math::next_sqrt_price_from_a_in(sqrt_price, liquidity, amount_less_fee)?;
+        (next, amount, amount - amount_less_fee)
+    } else {
+        let next = math::next_sqrt_price_from_b_in(sqrt_price, liquidity, amount_less_fee)?;
+        (next, amount, amount - amount_less_fee)
+    };
+    let amount_out = if a_to_b {
+        math::amount_b_delta(next_sqrt_price, sqrt_price, liquidity, false)?
+    } else {
+        math::amount_a_delta(sqrt_price, next_sqrt_price, liquidity, false)?
+    };
+
+    let growth = math::growth(fee, liquidity)?;
+    if a_to_b {
+        whirlpool.fee_growth_global_a = whirlpool.fee_growth_global_a.wrapping_add(growth);
+    } else {
+        whirlpool.fee_growth_global_b = whirlpool.fee_growth_global_b.wrapping_add(growth);
+    }
+    move_price(whirlpool, ticks, next_sqrt_price)?;
+    Ok((amount_in, amount_out))
+}
+
+/// Sets the price, crossing the initialized ticks of `ticks` between the old
+/// and new tick so the in-range liquidity and each tick's outside growth
+/// follow. Ticks in arrays that were not passed are not crossed.
+pub fn move_price(
+    whirlpool: &mut Whirlpool,
+    ticks: &mut TickArrays,
+    sqrt_price: u128,
+) -> Result<()> {
+    let tick_current = math::tick_index_from_sqrt_price(sqrt_price)?;
+    let previous = whirlpool.tick_current_index;
+    let global = Growth::global(whirlpool);
+    let mut liquidity = whirlpool.liquidity;
+
+    let (from, to, downward) = if tick_current < previous {
+        (tick_current, previous, true)
+    } else {
+        (previous, tick_current, false)
+    };
+    for (_, tick) in ticks.initialized_between(from, to) {
+        global
+            .zip(Growth::outside(tick), u128::wrapping_sub)
+            .store_outside(tick);
+        let net = if downward {
+            tick.liquidity_net
+                .checked_neg()
+                .ok_or(MockWhirlpoolError::MathOverflow)?
+        } else {
+            tick.liquidity_net
+        };
+        liquidity = add_delta(liquidity, net)?;
+    }
+
+    whirlpool.liquidity = liquidity;
+    whirlpool.sqrt_price = sqrt_price;
+    whirlpool.tick_current_index = tick_current;
+    Ok(())
+}
+
+/// Adds a position's `liquidity_delta` to one of its bounds, initializing it
+/// first. A new tick counts all growth so far as below it when the price is at
+/// or above it.
+fn update_tick(
+    whirlpool: &Whirlpool,
+    tick: &mut Tick,
+    tick_index: i32,
+    liquidity_delta: i128,
+    upper: bool,
+) -> Result<()> {
+    if !tick.initialized {
+        let outside = if tick_index <= whirlpool.tick_current_index {
+            Growth::global(whirlpool)
+        } else {
+            Growth::default()
+        };
+        outside.store_outside(tick);
+        tick.initialized = true;
+    }
+    tick.liquidity_gross = add_delta(tick.liquidity_gross, liquidity_delta)?;
+    tick.liquidity_net = if upper {
+        tick.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        tick.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(MockWhirlpoolError::MathOverflow)?;
+    Ok(())
+}
+
+/// Accrues growth inside the position's range at its current liquidity and
+/// moves its checkpoints to the current growth.
+fn settle(
+    whirlpool: &Whirlpool,
+    position: &mut Position,
+    lower: &Tick,
+    upper: &Tick,
+) -> Result<()> {
+    let global = Growth::global(whirlpool);
+    let current = whirlpool.tick_current_index;
+    let below = if current >= position.tick_lower_index {
+        Growth::outside(lower)
+    } else {
+        global.zip(Growth::outside(lower), u128::wrapping_sub)
+    };
+    let above = if current < position.tick_upper_index {
+        Growth::outside(upper)
+    } else {
+        global.zip(Growth::outside(upper), u128::wrapping_sub)
+    };
+    let inside = global
+        .zip(below, u128::wrapping_sub)
+        .zip(above, u128::wrapping_sub);
+
+    let liquidity = position.liquidity;
+    let accrue = |owed: u64, growth: u128, checkpoint: u128| -> Result<u64> {
+        owed.checked_add(math::owed(growth.wrapping_sub(checkpoint), liquidity)?)
+            .ok_or_else(|| error!(MockWhirlpoolError::MathOverflow))
+    };
+    position.fee_owed_a = accrue(
+        position.fee_owed_a,
+        inside.fee_a,
+        position.fee_growth_checkpoint_a,
+    )?;
+    position.fee_owed_b = accrue(
+        position.fee_owed_b,
+        inside.fee_b,
+        position.fee_growth_checkpoint_b,
+    )?;
+    position.fee_growth_checkpoint_a = inside.fee_a;
+    position.fee_growth_checkpoint_b = inside.fee_b;
+    for (i, reward) in position.reward_infos.iter_mut().enumerate() {
+        if whirlpool.reward_infos[i].initialized() {
+            reward.amount_owed = accrue(
+                reward.amount_owed,
+                inside.rewards[i],
+                reward.growth_inside_checkpoint,
+            )?;
+        }
+        reward.growth_inside_checkpoint = inside.rewards[i];
+    }
+    Ok(())
+}
+
+fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    liquidity
+        .checked_add_signed(delta)
+        .ok_or_else(|| error!(MockWhirlpoolError::LiquidityUnderflow))
+}
@@ -0,0 +1,350 @@
+//! Account layouts of the Whirlpool program.
+//!
+//! `TickArray` is zero-copy on chain; its fields are laid out without padding,
+//! so the borsh encoding of these structs produces the same bytes. So is
+//! `Oracle`, which holds a pool's adaptive fee state.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::MockWhirlpoolError;
+
+pub const NUM_REWARDS: usize = 3;
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct WhirlpoolRewardInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub emissions_per_second_x64: u128,
+    pub growth_global_x64: u128,
+}
+
+impl WhirlpoolRewardInfo {
+    pub fn initialized(&self) -> bool {
+        self.mint != Pubkey::default()
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Whirlpool {
+    pub whirlpools_config: Pubkey,
+    pub whirlpool_bump: [u8; 1],
+    pub tick_spacing: u16,
+    pub fee_tier_index_seed: [u8; 2],
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub protocol_fee_owed_a: u64,
+    pub protocol_fee_owed_b: u64,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub fee_growth_global_a: u128,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub fee_growth_global_b: u128,
+    pub reward_last_updated_timestamp: u64,
+    pub reward_infos: [WhirlpoolRewardInfo; NUM_REWARDS],
+}
+
+impl Whirlpool {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        whirlpools_config: Pubkey,
+        token_mint_a: Pubkey,
+        token_mint_b: Pubkey,
+        token_vault_a: Pubkey,
+        token_vault_b: Pubkey,
+        tick_spacing: u16,
+        fee_rate: u16,
+        sqrt_price: u128,
+    ) -> Result<Self> {
+        require!(tick_spacing > 0, MockWhirlpoolError::InvalidTickSpacing);
+        require!(
+            u64::from(fee_rate) < math::FEE_RATE_DENOMINATOR,
+            MockWhirlpoolError::InvalidFeeRate
+        );
+        let (_, bump) = Pubkey::find_program_address(
+            &[
+                b"whirlpool",
+                whirlpools_config.as_ref(),
+                token_mint_a.as_ref(),
+                token_mint_b.as_ref(),
+                &tick_spacing.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+        Ok(Self {
+            whirlpools_config,
+            whirlpool_bump: [bump],
+            tick_spacing,
+            fee_tier_index_seed: tick_spacing.to_le_bytes(),
+            fee_rate,
+            protocol_fee_rate: 0,
+            liquidity: 0,
+            sqrt_price,
+            tick_current_index: math::tick_index_from_sqrt_price(sqrt_price)?,
+            protocol_fee_owed_a: 0,
+            protocol_fee_owed_b: 0,
+            token_mint_a,
+            token_vault_a,
+            fee_growth_global_a: 0,
+            token_mint_b,
+            token_vault_b,
+            fee_growth_global_b: 0,
+            reward_last_updated_timestamp: 0,
+            reward_infos: [WhirlpoolRewardInfo::default(); NUM_REWARDS],
+        })
+    }
+
+    /// Runs `f` with the pool's PDA signer seeds.
+    pub fn with_signer<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        let seeds: &[&[u8]] = &[
+            b"whirlpool",
+            self.whirlpools_config.as_ref(),
+            self.token_mint_a.as_ref(),
+            self.token_mint_b.as_ref(),
+            &self.fee_tier_index_seed,
+            &self.whirlpool_bump,
+        ];
+        f(&[seeds])
+    }
+
+    /// Checks `tick_index` can bound a position in this pool.
+    pub fn check_tick(&self, tick_index: i32) -> Result<()> {
+        require!(
+            (math::MIN_TICK_INDEX..=math::MAX_TICK_INDEX).contains(&tick_index)
+                && tick_index % i32::from(self.tick_spacing) == 0,
+            MockWhirlpoolError::InvalidTickIndex
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct PositionRewardInfo {
+    pub growth_inside_checkpoint: u128,
+    pub amount_owed: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub whirlpool: Pubkey,
+    pub position_mint: Pubkey,
+    pub liquidity: u128,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub fee_growth_checkpoint_a: u128,
+    pub fee_owed_a: u64,
+    pub fee_growth_checkpoint_b: u128,
+    pub fee_owed_b: u64,
+    pub reward_infos: [PositionRewardInfo; NUM_REWARDS],
+}
+
+impl Position {
+    /// Empty position over `[tick_lower_index, tick_upper_index)` of `whirlpool`.
+    pub fn new(
+        whirlpool: Pubkey,
+        whirlpool_state: &Whirlpool,
+        position_mint: Pubkey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<Self> {
+        whirlpool_state.check_tick(tick_lower_index)?;
+        whirlpool_state.check_tick(tick_upper_index)?;
+        require!(
+            tick_lower_index < tick_upper_index,
+            MockWhirlpoolError::InvalidTickIndex
+        );
+        Ok(Self {
+            whirlpool,
+            position_mint,
+            liquidity: 0,
+            tick_lower_index,
+            tick_upper_index,
+            fee_growth_checkpoint_a: 0,
+            fee_owed_a: 0,
+            fee_growth_checkpoint_b: 0,
+            fee_owed_b: 0,
+            reward_infos: [PositionRewardInfo::default(); NUM_REWARDS],
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.liquidity == 0
+            && self.fee_owed_a == 0
+            && self.fee_owed_b == 0
+            && self
+                .reward_infos
+                .iter()
+                .all(|reward| reward.amount_owed == 0)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Tick {
+    pub initialized: bool,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_a: u128,
+    pub fee_growth_outside_b: u128,
+    pub reward_growths_outside: [u128; NUM_REWARDS],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TickArray {
+    pub start_tick_index: i32,
+    pub ticks: [Tick; TICK_ARRAY_SIZE as usize],
+    pub whirlpool: Pubkey,
+}
+
+impl TickArray {
+    /// Empty array of `whirlpool` starting at `start_tick_index`.
+    pub fn new(whirlpool: Pubkey, start_tick_index: i32, tick_spacing: u16) -> Result<Self> {
+        require!(
+            start_tick_index == Self::start_index_of(start_tick_index, tick_spacing),
+            MockWhirlpoolError::InvalidTickArray
+        );
+        Ok(Self {
+            start_tick_index,
+            ticks: [Tick::default(); TICK_ARRAY_SIZE as usize],
+            whirlpool,
+        })
+    }
+
+    /// First tick of the array holding `tick_index`.
+    pub fn start_index_of(tick_index: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = TICK_ARRAY_SIZE * i32::from(tick_spacing);
+        tick_index.div_euclid(ticks_in_array) * ticks_in_array
+    }
+}
+
+/// Tick arrays an instruction works on, by account key. An array passed twice
+/// is only loaded, and written back, once.
+pub struct TickArrays {
+    tick_spacing: u16,
+    arrays: Vec<(Pubkey, TickArray)>,
+}
+
+impl TickArrays {
+    pub fn new(tick_spacing: u16, arrays: Vec<(Pubkey, TickArray)>) -> Self {
+        Self {
+            tick_spacing,
+            arrays,
+        }
+    }
+
+    /// Loads the arrays of `whirlpool` in `infos`. Accounts that are not yet
+    /// initialized are skipped, as swaps may be passed arrays nobody created.
+    pub fn load<'a, 'info: 'a>(
+        whirlpool: &Pubkey,
+        tick_spacing: u16,
+        infos: impl IntoIterator<Item = &'a AccountInfo<'info>>,
+    ) -> Result<Self> {
+        let mut arrays: Vec<(Pubkey, TickArray)> = Vec::new();
+        for info in infos {
+            if info.data_is_empty() || arrays.iter().any(|(key, _)| key == info.key) {
+                continue;
+            }
+            require_keys_eq!(*info.owner, crate::ID, MockWhirlpoolError::InvalidTickArray);
+            let array = TickArray::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require_keys_eq!(
+                array.whirlpool,
+                *whirlpool,
+                MockWhirlpoolError::InvalidTickArray
+            );
+            arrays.push((info.key(), array));
+        }
+        Ok(Self::new(tick_spacing, arrays))
+    }
+
+    /// Tick `tick_index`, if one of the arrays holds it.
+    pub fn get(&mut self, tick_index: i32) -> Option<&mut Tick> {
+        let start = TickArray::start_index_of(tick_index, self.tick_spacing);
+        let offset = (tick_index - start) / i32::from(self.tick_spacing);
+        self.arrays
+            .iter_mut()
+            .find(|(_, array)| array.start_tick_index == start)
+            .map(|(_, array)| &mut array.ticks[offset as usize])
+    }
+
+    pub fn tick(&mut self, tick_index: i32) -> Result<&mut Tick> {
+        self.get(tick_index)
+            .ok_or_else(|| error!(MockWhirlpoolError::InvalidTickArray))
+    }
+
+    /// Initialized ticks in `(from, to]`, with their index, in no particular order.
+    pub fn initialized_between(
+        &mut self,
+        from: i32,
+        to: i32,
+    ) -> impl Iterator<Item = (i32, &mut Tick)> {
+        let tick_spacing = i32::from(self.tick_spacing);
+        self.arrays.iter_mut().flat_map(move |(_, array)| {
+            let start = array.start_tick_index;
+            array
+                .ticks
+                .iter_mut()
+                .enumerate()
+                .map(move |(i, tick)| (start + i as i32 * tick_spacing, tick))
+                .filter(move |(index, tick)| tick.initialized && from < *index && *index <= to)
+        })
+    }
+
+    /// Writes every array back to the account in `infos` it was loaded from.
+    pub fn save<'a, 'info: 'a>(
+        &self,
+        infos: impl IntoIterator<Item = &'a AccountInfo<'info>> + Clone,
+    ) -> Result<()> {
+        for (key, array) in &self.arrays {
+            if let Some(info) = infos.clone().into_iter().find(|info| info.key == key) {
+                array.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_arrays(self) -> Vec<(Pubkey, TickArray)> {
+        self.arrays
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AdaptiveFeeConstants {
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub adaptive_fee_control_factor: u32,
+    pub max_volatility_accumulator: u32,
+    pub tick_group_size: u16,
+    pub major_swap_threshold_ticks: u16,
+    pub reserved: [u8; 16],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AdaptiveFeeVariables {
+    pub last_reference_update_timestamp: u64,
+    pub last_major_swap_timestamp: u64,
+    pub volatility_reference: u32,
+    pub tick_group_index_reference: i32,
+    pub volatility_accumulator: u32,
+    pub reserved: [u8; 16],
+}
+
+/// Adaptive fee state of a pool. The mock charges the pool's static fee rate
+/// and never updates it.
+#[account]
+#[derive(InitSpace)]
+pub struct Oracle {
+    pub whirlpool: Pubkey,
+    pub trade_enable_timestamp: u64,
+    pub adaptive_fee_constants: AdaptiveFeeConstants,
+    pub adaptive_fee_variables: AdaptiveFeeVariables,
+    pub reserved: [u8; 128],
+}
@@ -14,9 +14,27 @@ import {
   createInitializeMintInstruction,
   getMinimumBalanceForRentExemptMint,
   createInitializeAccountInstruction,
-  getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
+
+const WHIRLPOOL_PROGRAM_ID = new PublicKey(
+  "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"
+);
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+const VAULTS_PER_REGISTRY_PAGE = 100;
+// Pool the vault is created on, built with the mock Whirlpool program that
+// Anchor.toml loads at the Whirlpool program id
+const TICK_SPACING = 64;
+const FEE_RATE = 3_000;
+
+/** Anchor instruction discriminator of `name`. */
+function discriminator(name: string): Buffer {
+  return createHash("sha256").update(`global:${name}`).digest().subarray(0, 8);
+}
 
 describe("vault_manager", () => {
   // Configure the client to use the local cluster.
@@ -44,11 +62,15 @@ describe("vault_manager", () => {
   let vaultTokenA: Keypair | undefined;
   let vaultTokenB: Keypair | undefined;
   let positionMint: Keypair | undefined;
-  let positionTokenAccount: Keypair | undefined;
-  let whirlpool: Keypair | undefined;
-  let position: Keypair | undefined;
+  let positionTokenAccount: PublicKey | undefined;
+  let whirlpool: PublicKey | undefined;
+  let poolTokenVaultA: Keypair | undefined;
+  let poolTokenVaultB: Keypair | undefined;
+  let position: PublicKey | undefined;
   let vaultPda: PublicKey | undefined;
   let vaultBump: number | undefined;
+  let protocolConfigPda: PublicKey | undefined;
+  let vaultRegistry: PublicKey | undefined;
 
   before(async function () {
     this.timeout(30000); // 30 second timeout for setup
@@ -147,25 +169,67 @@ describe("vault_manager", () => {
     );
     await provider.sendAndConfirm(createMintBTx, [tokenBMint]);
 
-    // Create position mint
-    positionMint = Keypair.generate();
-    const positionMintRent = await getMinimumBalanceForRentExemptMint(connection);
-    const createPositionMintTx = new anchor.web3.Transaction().add(
-      anchor.web3.SystemProgram.createAccount({
-        fromPubkey: wallet.publicKey,
-        newAccountPubkey: positionMint.publicKey,
-        space: MINT_SIZE,
-        lamports: positionMintRent,
-        programId: TOKEN_PROGRAM_ID,
-      }),
-      createInitializeMintInstruction(
-        positionMint.publicKey,
-        0,
-        wallet.publicKey,
-        null
-      )
+    // Whirlpool of the two tokens at price 1, its token vaults owned by the pool
+    const tickSpacing = Buffer.alloc(2);
+    tickSpacing.writeUInt16LE(TICK_SPACING);
+    const whirlpoolsConfig = Keypair.generate().publicKey;
+    [whirlpool] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whirlpool"),
+        whirlpoolsConfig.toBuffer(),
+        tokenAMint.publicKey.toBuffer(),
+        tokenBMint.publicKey.toBuffer(),
+        tickSpacing,
+      ],
+      WHIRLPOOL_PROGRAM_ID
+    );
+    poolTokenVaultA = Keypair.generate();
+    poolTokenVaultB = Keypair.generate();
+    const tokenAccountRent = await connection.getMinimumBalanceForRentExemption(165);
+    const createPoolTx = new anchor.web3.Transaction();
+    for (const [account, mint] of [
+      [poolTokenVaultA, tokenAMint],
+      [poolTokenVaultB, tokenBMint],
+    ]) {
+      createPoolTx.add(
+        anchor.web3.SystemProgram.createAccount({
+          fromPubkey: wallet.publicKey,
+          newAccountPubkey: account.publicKey,
+          space: 165,
+          lamports: tokenAccountRent,
+          programId: TOKEN_PROGRAM_ID,
+        }),
+        createInitializeAccountInstruction(account.publicKey, mint.publicKey, whirlpool)
+      );
+    }
+    // initialize_pool(tick_spacing: u16, fee_rate: u16, initial_sqrt_price: u128)
+    const poolArgs = Buffer.alloc(20);
+    poolArgs.writeUInt16LE(TICK_SPACING, 0);
+    poolArgs.writeUInt16LE(FEE_RATE, 2);
+    poolArgs.writeUInt8(1, 4 + 8); // 1 << 64, price 1 in Q64.64
+    createPoolTx.add(
+      new anchor.web3.TransactionInstruction({
+        programId: WHIRLPOOL_PROGRAM_ID,
+        keys: [
+          { pubkey: whirlpoolsConfig, isSigner: false, isWritable: false },
+          { pubkey: tokenAMint.publicKey, isSigner: false, isWritable: false },
+          { pubkey: tokenBMint.publicKey, isSigner: false, isWritable: false },
+          { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+          { pubkey: whirlpool, isSigner: false, isWritable: true },
+          { pubkey: poolTokenVaultA.publicKey, isSigner: false, isWritable: false },
+          { pubkey: poolTokenVaultB.publicKey, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.concat([discriminator("initialize_pool"), poolArgs]),
+      })
+    );
+    await provider.sendAndConfirm(createPoolTx, [poolTokenVaultA, poolTokenVaultB]);
+
+    // The vault PDA must own its token accounts
+    [vaultPda, vaultBump] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), whirlpool.toBuffer()],
+      program.programId
     );
-    await provider.sendAndConfirm(createPositionMintTx, [positionMint]);
 
     // Create vault token accounts
     vaultTokenA = Keypair.generate();
@@ -181,7 +245,7 @@ describe("vault_manager", () => {
       createInitializeAccountInstruction(
         vaultTokenA.publicKey,
         tokenAMint.publicKey,
-        wallet.publicKey
+        vaultPda
       )
     );
     await provider.sendAndConfirm(createVaultTokenATx, [vaultTokenA]);
@@ -199,67 +263,95 @@ describe("vault_manager", () => {
       createInitializeAccountInstruction(
         vaultTokenB.publicKey,
         tokenBMint.publicKey,
-        wallet.publicKey
+        vaultPda
       )
     );
     await provider.sendAndConfirm(createVaultTokenBTx, [vaultTokenB]);
 
-    // Create position token account
-    positionTokenAccount = Keypair.generate();
-    const positionTokenAccountRent = await connection.getMinimumBalanceForRentExemption(165);
-    const createPositionTokenAccountTx = new anchor.web3.Transaction().add(
-      anchor.web3.SystemProgram.createAccount({
-        fromPubkey: wallet.publicKey,
-        newAccountPubkey: positionTokenAccount.publicKey,
-        space: 165,
-        lamports: positionTokenAccountRent,
-        programId: TOKEN_PROGRAM_ID,
-      }),
-      createInitializeAccountInstruction(
-        positionTokenAccount.publicKey,
-        positionMint.publicKey,
-        wallet.publicKey
-      )
+    // The program opens the position; the NFT mint is a fresh keypair that signs
+    positionMint = Keypair.generate();
+    [position] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), positionMint.publicKey.toBuffer()],
+      WHIRLPOOL_PROGRAM_ID
     );
-    await provider.sendAndConfirm(createPositionTokenAccountTx, [positionTokenAccount]);
+    positionTokenAccount = getAssociatedTokenAddressSync(
+      positionMint.publicKey,
+      vaultPda,
+      true
+    );
+  });
+
+  it("Initializes protocol config", async function () {
+    this.timeout(30000); // 30 second timeout
 
-    // Create mock whirlpool and position accounts (just keypairs for testing)
-    whirlpool = Keypair.generate();
-    position = Keypair.generate();
+    [protocolConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("protocol_config")],
+      program.programId
+    );
+    // A validator kept from an earlier run already has it
+    if (!(await connection.getAccountInfo(protocolConfigPda))) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await program.methods
+        .initializeProtocolConfig(0, wallet.publicKey, [WHIRLPOOL_PROGRAM_ID])
+        .accounts({
+          admin: wallet.publicKey,
+          payer: wallet.publicKey,
+          program: program.programId,
+          programData,
+        } as any)
+        .rpc();
+    }
+
+    const config = await program.account.protocolConfig.fetch(protocolConfigPda);
+    expect(config.admin.toString()).to.equal(wallet.publicKey.toString());
+    expect(config.dexPrograms.map((key) => key.toString())).to.include(
+      WHIRLPOOL_PROGRAM_ID.toString()
+    );
   });
 
   it("Initializes vault", async function () {
     this.timeout(30000); // 30 second timeout
 
-    // Ensure previous test completed
-    if (!whirlpool) {
-      throw new Error("Whirlpool not initialized. Run previous test first.");
+    // Ensure previous tests completed
+    if (!whirlpool || !protocolConfigPda) {
+      throw new Error("Whirlpool not initialized. Run previous tests first.");
     }
 
-    // Find PDA for vault
-    [vaultPda, vaultBump] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("vault"),
-        whirlpool.publicKey.toBuffer(),
-      ],
+    // The vault is appended to the registry page holding the next vault index
+    const config = await program.account.protocolConfig.fetch(protocolConfigPda);
+    const vaultIndex = config.vaultCount.toNumber();
+    const page = Buffer.alloc(4);
+    page.writeUInt32LE(Math.floor(vaultIndex / VAULTS_PER_REGISTRY_PAGE));
+    [vaultRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_registry"), page],
       program.programId
     );
 
     // Initialize vault
     try {
       const tx = await program.methods
-        .initializeVault(vaultBump!)
+        .initializeVault({ whirlpool: {} }, -128, 128)
         .accounts({
-          whirlpool: whirlpool!.publicKey,
+          protocolConfig: protocolConfigPda,
+          vaultRegistry,
+          pool: whirlpool!,
           tokenAMint: tokenAMint!.publicKey,
           tokenBMint: tokenBMint!.publicKey,
           vaultTokenA: vaultTokenA!.publicKey,
           vaultTokenB: vaultTokenB!.publicKey,
-          position: position!.publicKey,
+          position: position!,
           positionMint: positionMint!.publicKey,
-          positionTokenAccount: positionTokenAccount!.publicKey,
+          positionTokenAccount: positionTokenAccount!,
+          poolTokenVaultA: poolTokenVaultA!.publicKey,
+          poolTokenVaultB: poolTokenVaultB!.publicKey,
           authority: wallet.publicKey,
-        })
+          payer: wallet.publicKey,
+          dexProgram: WHIRLPOOL_PROGRAM_ID,
+        } as any)
+        .signers([positionMint!])
         .rpc();
 
       console.log("Initialize vault transaction signature:", tx);
@@ -269,8 +361,8 @@ describe("vault_manager", () => {
       expect(vaultAccount.authority.toString()).to.equal(
         wallet.publicKey.toString()
       );
-      expect(vaultAccount.whirlpool.toString()).to.equal(
-        whirlpool.publicKey.toString()
+      expect(vaultAccount.pool.toString()).to.equal(
+        whirlpool.toString()
       );
       expect(vaultAccount.tokenAMint.toString()).to.equal(
         tokenAMint.publicKey.toString()
@@ -280,6 +372,15 @@ describe("vault_manager", () => {
       );
       expect(vaultAccount.isActive).to.be.false;
       expect(vaultAccount.bump).to.equal(vaultBump);
+
+      const registry = await program.account.vaultRegistry.fetch(vaultRegistry!);
+      expect(
+        registry.vaults[vaultIndex % VAULTS_PER_REGISTRY_PAGE].toString()
+      ).to.equal(vaultPda!.toString());
+      const updatedConfig = await program.account.protocolConfig.fetch(
+        protocolConfigPda
+      );
+      expect(updatedConfig.vaultCount.toNumber()).to.equal(vaultIndex + 1);
     } catch (err) {
       console.error("Initialize vault error:", err);
       throw err;
@@ -316,21 +417,21 @@ describe("vault_manager", () => {
     // For testing, we'll use vaultTokenA as the input (simplified)
     // In a real scenario, you'd mint tokens to the user account first
     const depositAmount = new anchor.BN(1000);
-    const tickLower = -100;
-    const tickUpper = 100;
+    const minSwapOut = new anchor.BN(0);
+    const minSharesOut = new anchor.BN(0);
 
     try {
-      // For deposit, the vault PDA is derived from vault.whirlpool
+      // For deposit, the vault PDA is derived from vault.pool
       // We need to pass the vault account explicitly, and Anchor will verify the PDA derivation
       const accounts = {
         vault: vaultPda!,
         user: wallet.publicKey,
         userTokenAccount: userTokenAccount.publicKey,
         vaultTokenInput: vaultTokenA!.publicKey, // Using vault token A as input for simplicity
-        whirlpoolProgram: whirlpool!.publicKey, // Mock whirlpool program
+        dexProgram: WHIRLPOOL_PROGRAM_ID, // Mock whirlpool program
       };
       const tx = await program.methods
-        .depositAndAddLiquidity(depositAmount, tickLower, tickUpper)
+        .depositAndAddLiquidity(depositAmount, minSwapOut, minSharesOut)
         .accounts(accounts as any) // Type assertion needed because Anchor's types don't handle PDAs derived from account fields well
         .rpc();
 